@@ -1,12 +1,15 @@
-use std::path::{Path, PathBuf};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use indexmap::IndexSet;
 
 use crate::{loader, CollateInfo, Error, Result};
 use config::{
-    indexer::QueryList, plugin_cache::PluginCache, script::ScriptAsset,
-    tags::link::LinkTag, Config, LinkOptions, Page, RuntimeOptions,
+    indexer::QueryList, permalink::MissingDate, plugin_cache::PluginCache,
+    script::ScriptAsset, tags::link::LinkTag, Config, LinkOptions, Page,
+    RuntimeOptions,
 };
 
 /// Runtime validation of queries.
@@ -17,6 +20,11 @@ fn verify_query(list: &QueryList) -> Result<()> {
         if q.page.is_some() && each {
             return Err(Error::QueryConflict);
         }
+        if let Some(ref page) = q.page {
+            if !page.is_valid() {
+                return Err(Error::PageSizeTooSmall(page.size));
+            }
+        }
     }
     Ok(())
 }
@@ -36,6 +44,25 @@ pub fn to_href(
     options.absolute(file, href_opts).map_err(Error::from)
 }
 
+/// Validate an explicit output path and convert it to an href.
+///
+/// The path must be a file or directory inside the build target
+/// so absolute paths on disc and parent references are not allowed.
+pub(crate) fn output_href(output: &str, file: &Path) -> Result<String> {
+    let rel = output.trim_start_matches('/');
+    let valid = !rel.is_empty()
+        && Path::new(rel)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if !valid {
+        return Err(Error::InvalidOutputPath(
+            output.to_string(),
+            file.to_path_buf(),
+        ));
+    }
+    Ok(format!("/{}", rel))
+}
+
 /// Builds a single page and mutates the collation with necessary
 /// information from the page data.
 pub struct PageBuilder<'a> {
@@ -48,6 +75,7 @@ pub struct PageBuilder<'a> {
     page: Page,
     rewrite_index: bool,
     destination: PathBuf,
+    pattern: Option<String>,
 }
 
 impl<'a> PageBuilder<'a> {
@@ -73,6 +101,7 @@ impl<'a> PageBuilder<'a> {
             page: Default::default(),
             rewrite_index: false,
             destination: Default::default(),
+            pattern: None,
         }
     }
 
@@ -81,6 +110,18 @@ impl<'a> PageBuilder<'a> {
         Ok(self)
     }
 
+    /// Determine if the page is a draft that should be excluded
+    /// for the current profile.
+    pub fn is_draft(&self) -> bool {
+        self.page.draft() && !self.options.settings.include_drafts()
+    }
+
+    /// Determine if the page is restricted to other profiles or
+    /// an environment condition that is not met.
+    pub fn is_inactive(&self) -> bool {
+        !self.page.is_active(&self.options.settings.name)
+    }
+
     /// Assign a layout name to the page preferring any existing
     /// assigned layout.
     pub fn layout(mut self, layout_name: &str) -> Result<Self> {
@@ -116,6 +157,22 @@ impl<'a> PageBuilder<'a> {
             .rewrite_index(rewrite_index)
             .build(&self.path)?;
 
+        // Explicit output paths and permalink patterns
+        // change the output location
+        let pattern = if let Some(ref output) = self.page.output {
+            Some(output_href(output, &self.path)?)
+        } else {
+            self.permalink_pattern()?
+        };
+        if let Some(ref href) = pattern {
+            let mut file = href.trim_start_matches('/').to_string();
+            if file.is_empty() || file.ends_with('/') {
+                file.push_str(config::INDEX_HTML);
+            }
+            self.destination =
+                PathBuf::from(utils::url::to_path_separator(&file));
+        }
+
         self.page.seal(
             self.config,
             self.options,
@@ -124,9 +181,58 @@ impl<'a> PageBuilder<'a> {
             None,
         )?;
 
+        if let Some(ref href) = pattern {
+            self.page
+                .set_href(self.config, self.options, href.to_string())?;
+        }
+        self.pattern = pattern;
+
         Ok(self)
     }
 
+    /// Resolve the href for the page when it matches the
+    /// permalink pattern configuration.
+    fn permalink_pattern(&self) -> Result<Option<String>> {
+        let permalink = match self.config.permalink() {
+            Some(permalink) => permalink,
+            None => return Ok(None),
+        };
+
+        let href = to_href(&self.path, self.options, false, None)?;
+        if !permalink.matcher().matches(href) {
+            return Ok(None);
+        }
+
+        let date: DateTime<Utc> = if let Some(created) = self.page.created {
+            created
+        } else {
+            match permalink.missing_date() {
+                MissingDate::Modified => {
+                    fs::metadata(&self.path)?.modified()?.into()
+                }
+                MissingDate::Error => {
+                    return Err(Error::NoPermalinkDate(self.path.clone()))
+                }
+            }
+        };
+
+        let slug = self
+            .page
+            .extra
+            .get("slug")
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string())
+            .or(self.page.title.clone())
+            .unwrap_or_else(|| {
+                self.path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            });
+
+        Ok(Some(permalink.resolve(&date, &slug)))
+    }
+
     /// Import scripts from the scripts cache into this page.
     ///
     /// Depends on the page `href` so must come after a call to `seal()`.
@@ -254,7 +360,26 @@ impl<'a> PageBuilder<'a> {
     ///
     /// Depends on `rewrite_index` so must come after a call to `seal()`.
     pub fn link(self) -> Result<Self> {
-        let href = to_href(&self.path, self.options, self.rewrite_index, None)?;
+        let href = if let Some(ref pattern) = self.pattern {
+            let href = if pattern.ends_with('/') {
+                format!("{}{}", pattern, config::INDEX_HTML)
+            } else {
+                pattern.to_string()
+            };
+            if let Some(existing) = self.info.links.reverse.get(&href) {
+                if self.page.output.is_some() {
+                    return Err(Error::DuplicatePermalink(href));
+                }
+                return Err(Error::PermalinkCollision(
+                    pattern.to_string(),
+                    existing.to_path_buf(),
+                    self.key.to_path_buf(),
+                ));
+            }
+            href
+        } else {
+            to_href(&self.path, self.options, self.rewrite_index, None)?
+        };
         self.info
             .link(Arc::clone(self.key), Arc::new(href.clone()))?;
         Ok(self)
@@ -286,7 +411,23 @@ impl<'a> PageBuilder<'a> {
         if let Some(ref feed) = self.config.feed {
             for (name, cfg) in feed.channels.iter() {
                 let href = self.page.href.as_ref().unwrap();
-                if cfg.matcher.filter(href) {
+                let in_collection = if let Some(ref collection) = cfg.collection
+                {
+                    self.config
+                        .db
+                        .as_ref()
+                        .and_then(|db| db.load.as_ref())
+                        .and_then(|load| load.get(collection))
+                        .map_or(false, |provider| {
+                            provider.includes_page(
+                                &self.options.source,
+                                self.key.as_path(),
+                            )
+                        })
+                } else {
+                    true
+                };
+                if in_collection && cfg.matcher.filter(href) {
                     let items = self
                         .info
                         .feeds
@@ -305,3 +446,177 @@ impl<'a> PageBuilder<'a> {
         (self.info, self.key, self.destination, self.page)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use config::{
+        test_util::TempProject, ProfileName, ProfileSettings, RuntimeOptions,
+    };
+
+    use super::PageBuilder;
+    use crate::{CollateInfo, Error, Result};
+
+    #[test]
+    fn permalink_patterns() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[permalink]\npattern = \"/:year/:month/:slug/\"\nincludes = [\"/posts/**\"]\n",
+        )?;
+        let first = project.write(
+            "site/posts/first.md",
+            "+++\ntitle = \"First Post\"\ncreated = 2021-03-07\n+++\n",
+        )?;
+        let second = project.write(
+            "site/posts/second.md",
+            "+++\ntitle = \"Second\"\nslug = \"the-second\"\ncreated = 2022-11-20\n+++\n",
+        )?;
+        let duplicate = project.write(
+            "site/posts/duplicate.md",
+            "+++\ntitle = \"First Post\"\ncreated = 2021-03-21\n+++\n",
+        )?;
+
+        let config = project.config()?;
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        let mut link = |path: &std::path::PathBuf| -> Result<Option<String>> {
+            let key = Arc::new(path.clone());
+            let mut builder = PageBuilder::new(
+                &mut info, &config, &options, None, &key, path,
+            )
+            .compute()?;
+            builder.pattern = builder.permalink_pattern()?;
+            let pattern = builder.pattern.clone();
+            builder.link()?;
+            Ok(pattern)
+        };
+
+        assert_eq!(Some("/2021/03/first-post/".to_string()), link(&first)?);
+        assert_eq!(Some("/2022/11/the-second/".to_string()), link(&second)?);
+        assert!(matches!(
+            link(&duplicate),
+            Err(Error::PermalinkCollision(..))
+        ));
+
+        let other = project.write("site/about.md", "# About\n")?;
+        assert_eq!(None, link(&other)?);
+
+        assert_eq!(
+            Some(&Arc::new(first)),
+            info.links
+                .reverse
+                .get(&"/2021/03/first-post/index.html".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn output_path() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n",
+        )?;
+        let custom = project.write(
+            "site/custom.md",
+            "+++\noutput = \"/sitemap-custom.xml\"\n+++\n",
+        )?;
+        let duplicate = project.write(
+            "site/duplicate.md",
+            "+++\npath = \"sitemap-custom.xml\"\n+++\n",
+        )?;
+        let escape = project.write(
+            "site/escape.md",
+            "+++\noutput = \"../escape.html\"\n+++\n",
+        )?;
+
+        let config = project.config()?;
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        let mut build = |path: &std::path::PathBuf| -> Result<PathBuf> {
+            let key = Arc::new(path.clone());
+            let builder = PageBuilder::new(
+                &mut info, &config, &options, None, &key, path,
+            )
+            .compute()?
+            .seal()?
+            .link()?;
+            let (_, _, destination, page) = builder.build();
+            assert_eq!(Some("/sitemap-custom.xml"), page.href.as_deref());
+            Ok(destination)
+        };
+
+        assert_eq!(PathBuf::from("sitemap-custom.xml"), build(&custom)?);
+        assert!(matches!(
+            build(&duplicate),
+            Err(Error::DuplicatePermalink(..))
+        ));
+        assert!(matches!(build(&escape), Err(Error::InvalidOutputPath(..))));
+        Ok(())
+    }
+
+    #[test]
+    fn collection_feeds() -> Result<()> {
+        let project = TempProject::new()?;
+        let site = r#"lang = "en"
+host = "localhost"
+
+[db.load.blog]
+from = "blog"
+
+[db.load.news]
+from = "news"
+
+[feed.blog]
+target = "blog"
+title = "Blog"
+collection = "blog"
+limit = 10
+
+[feed.news]
+target = "news"
+title = "News"
+collection = "news"
+"#;
+        project.write(config::SITE_TOML, site)?;
+        let post = project.write("site/blog/post.md", "# Post\n")?;
+        let story = project.write("site/news/story.md", "# Story\n")?;
+        let about = project.write("site/about.md", "# About\n")?;
+
+        let config = project.config()?;
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        for path in [&post, &story, &about] {
+            let key = Arc::new(path.clone());
+            PageBuilder::new(&mut info, &config, &options, None, &key, path)
+                .compute()?
+                .seal()?
+                .feeds()?;
+        }
+
+        assert_eq!(Some(&vec![Arc::new(post)]), info.feeds.get("blog"));
+        assert_eq!(Some(&vec![Arc::new(story)]), info.feeds.get("news"));
+
+        // The collection for a feed must exist
+        project.write(
+            config::SITE_TOML,
+            site.replace(r#"collection = "news""#, r#"collection = "events""#),
+        )?;
+        assert!(matches!(
+            project.config(),
+            Err(config::Error::NoFeedCollection(..))
+        ));
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
 use owning_ref::RwLockReadGuardRef;
+use url::Url;
 
 use config::indexer::QueryList;
 use config::{Config, MenuResult, Page, RuntimeOptions};
@@ -15,6 +17,14 @@ use crate::{
 
 const MENU_TEMPLATE_PREFIX: &str = "@menu";
 
+/// Candidate links in source content, the quoted values and the
+/// targets of markdown links and CSS `url()` that look like a file.
+pub(crate) fn references(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split(|c: char| c.is_whitespace() || "\"'`()[]{}<>=,".contains(c))
+        .filter(|s| s.contains('.') && !s.starts_with('#'))
+}
+
 #[derive(Debug, Default)]
 pub struct Collation {
     pub fallback: Arc<RwLock<CollateInfo>>,
@@ -364,6 +374,11 @@ pub struct CollateInfo {
     // Store queries for expansion later
     pub queries: Vec<(QueryList, Arc<PathBuf>)>,
 
+    // Query pages removed from `pages` when they are expanded
+    // into synthetic pages, kept so the synthetic pages can be
+    // generated again when the collections change.
+    pub expanded: HashMap<Arc<PathBuf>, Arc<RwLock<Page>>>,
+
     // Map of books files so that we can assign the theme template
     // and book menu.
     pub(crate) books: HashMap<String, Vec<Arc<PathBuf>>>,
@@ -605,6 +620,36 @@ impl CollateInfo {
         self.pages.remove(p)
     }
 
+    /// Map of synthetic page source paths to the query page
+    /// that generated the synthetic page.
+    pub fn synthetic_templates(&self) -> HashMap<PathBuf, PathBuf> {
+        self.pages
+            .iter()
+            .filter_map(|(key, page_lock)| {
+                let page = page_lock.read().unwrap();
+                if page.is_synthetic() {
+                    Some((key.to_path_buf(), page.get_template().clone()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Remove the synthetic pages generated by a query page.
+    pub fn remove_synthetics(&mut self, template: &PathBuf) {
+        let synthetics: Vec<PathBuf> = self
+            .synthetic_templates()
+            .into_iter()
+            .filter(|(_, t)| t == template)
+            .map(|(key, _)| key)
+            .collect();
+        for key in synthetics.iter() {
+            self.remove_page(key);
+            self.links.remove(key);
+        }
+    }
+
     pub fn add_file(
         &mut self,
         options: &RuntimeOptions,
@@ -664,6 +709,44 @@ impl CollateInfo {
         Ok(())
     }
 
+    /// Copy a resource file so that it can be processed rather
+    /// than linking to the source file.
+    ///
+    /// When the processed output uses another file extension the
+    /// destination and the link for the file are changed to match.
+    pub fn set_processed(
+        &mut self,
+        key: &PathBuf,
+        extension: Option<&str>,
+    ) -> Result<()> {
+        match self.all.get_mut(key) {
+            Some(Resource::File { ref mut target }) => {
+                match target.operation {
+                    ResourceOperation::Copy | ResourceOperation::Link => {}
+                    _ => return Ok(()),
+                }
+                target.operation = ResourceOperation::Copy;
+                if let Some(ext) = extension {
+                    target.destination.set_extension(ext);
+                }
+            }
+            _ => return Ok(()),
+        }
+
+        if let (Some(ext), Some(href)) =
+            (extension, self.links.sources.get(key).cloned())
+        {
+            let name = href.rfind('/').map(|pos| pos + 1).unwrap_or(0);
+            let href = match href[name..].rfind('.') {
+                Some(pos) => format!("{}.{}", &href[..name + pos], ext),
+                None => format!("{}.{}", href, ext),
+            };
+            self.links.remove(key);
+            self.link(Arc::new(key.to_path_buf()), Arc::new(href))?;
+        }
+        Ok(())
+    }
+
     pub fn remove_file(
         &mut self,
         path: &PathBuf,
@@ -701,6 +784,77 @@ impl CollateInfo {
         kind
     }
 
+    /// Find resource files that are not referenced by any page or
+    /// template so authors can locate dead files.
+    ///
+    /// Assets are excluded as they are used by the site layout and
+    /// ignored resources are skipped. The links collected from page
+    /// sources are resolved like the links in the rendered page, to
+    /// the source file when it exists otherwise relative to the page
+    /// URL; links in templates are resolved relative to the site root
+    /// so resources that are referenced dynamically will be reported.
+    pub fn orphans(&self, options: &RuntimeOptions) -> Vec<PathBuf> {
+        let root = Url::parse("http://localhost/").unwrap();
+        let mut referenced: HashSet<PathBuf> = HashSet::new();
+        let mut resolve = |source: Option<&PathBuf>, base: &Url, link: &str| {
+            let path = link.split(|c| c == '?' || c == '#').next().unwrap();
+            let href = source
+                .and_then(|file| options.relative_link(file, path))
+                .or_else(|| {
+                    base.join(path)
+                        .ok()
+                        .filter(|url| url.origin() == root.origin())
+                        .map(|url| url.path().to_string())
+                });
+            if let Some(file) = href.and_then(|href| self.find_link(&href)) {
+                referenced.insert(file);
+            }
+        };
+
+        for (key, page) in self.pages.iter() {
+            let page = page.read().unwrap();
+            let base = page
+                .href
+                .as_ref()
+                .and_then(|href| root.join(href).ok())
+                .unwrap_or_else(|| root.clone());
+            for link in page.references.iter().flatten() {
+                resolve(Some(&**key), &base, link);
+            }
+        }
+
+        for template in self.layouts.values().chain(self.templates.iter()) {
+            if let Ok(content) = fs::read_to_string(&**template) {
+                for link in references(&content) {
+                    resolve(None, &root, link);
+                }
+            }
+        }
+
+        let mut orphans: Vec<PathBuf> = self
+            .all
+            .iter()
+            .filter(|(_, resource)| match resource {
+                Resource::File { target } => {
+                    match (&target.kind, &target.operation) {
+                        (ResourceKind::File, ResourceOperation::Noop) => false,
+                        (ResourceKind::File, _) => true,
+                        _ => false,
+                    }
+                }
+                _ => false,
+            })
+            .filter(|(key, _)| {
+                self.links.get_link_href(key).is_some()
+                    && !referenced.contains(&***key)
+            })
+            .map(|(key, _)| key.to_path_buf())
+            .collect();
+
+        orphans.sort();
+        orphans
+    }
+
     /// Inherit page data from a fallback locale.
     pub fn inherit(
         &mut self,
@@ -762,3 +916,92 @@ impl CollateInfo {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use config::{test_util::TempProject, Page, RuntimeOptions};
+
+    use super::{references, CollateInfo};
+    use crate::{Resource, ResourceOperation, Result};
+
+    #[test]
+    fn orphan_resources() -> Result<()> {
+        let project = TempProject::new()?;
+        let index = project.write("index.md", "![Image](/data.png)\n")?;
+        let guide = project.write(
+            "docs/guide.md",
+            "![Image](../used.png) <img src=\"diagram.png\">\n",
+        )?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.root().to_path_buf();
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        for (page, href) in vec![(index, "/"), (guide, "/docs/guide/")] {
+            let mut data: Page = Default::default();
+            data.href = Some(href.to_string());
+            data.references = Some(
+                references(&std::fs::read_to_string(&page)?)
+                    .map(|s| s.to_string())
+                    .collect(),
+            );
+            info.add_page(
+                &Arc::new(page),
+                "index.html".into(),
+                Arc::new(RwLock::new(data)),
+            );
+        }
+        for href in [
+            "/data.png",
+            "/a.png",
+            "/used.png",
+            "/docs/guide/diagram.png",
+        ] {
+            let file = project.write(href.trim_start_matches('/'), "")?;
+            let dest = href.trim_start_matches('/').into();
+            info.add_file(
+                &options,
+                Arc::new(file),
+                dest,
+                href.to_string(),
+                None,
+            )?;
+        }
+
+        // Links match the whole href and resolve relative to the page
+        assert_eq!(vec![project.root().join("a.png")], info.orphans(&options));
+        Ok(())
+    }
+
+    #[test]
+    fn processed_resource() -> Result<()> {
+        let project = TempProject::new()?;
+        let file = Arc::new(project.write("style.scss", "")?);
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.root().to_path_buf();
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        info.add_file(
+            &options,
+            Arc::clone(&file),
+            "style.scss".into(),
+            "/style.scss".to_string(),
+            None,
+        )?;
+        info.set_processed(&file, Some("css"))?;
+
+        match info.get_resource(&file) {
+            Some(Resource::File { target }) => {
+                assert!(matches!(target.operation, ResourceOperation::Copy));
+                assert_eq!("style.css", target.destination.to_str().unwrap());
+            }
+            _ => panic!("expected a file resource"),
+        }
+        assert_eq!(Some(file.to_path_buf()), info.find_link("/style.css"));
+        assert_eq!(None, info.find_link("/style.scss"));
+        Ok(())
+    }
+}
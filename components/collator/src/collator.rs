@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
-use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, RwLock};
 
@@ -8,7 +7,9 @@ use crossbeam::channel;
 use ignore::{WalkBuilder, WalkState};
 use log::{debug, warn};
 
-use config::{plugin_cache::PluginCache, Config, RuntimeOptions};
+use config::{
+    engine::TemplateEngine, plugin_cache::PluginCache, Config, RuntimeOptions,
+};
 use locale::{LocaleMap, LocaleName};
 
 use crate::{
@@ -92,6 +93,15 @@ pub async fn walk(
     Ok(errors)
 }
 
+/// Determine if a page passes the source filter for the profile.
+fn is_source_match(options: &RuntimeOptions, path: &Path) -> bool {
+    if let Some(ref sources) = options.settings.sources {
+        let relative = path.strip_prefix(&options.source).unwrap_or(path);
+        return sources.accepts(relative);
+    }
+    true
+}
+
 /// Determine the default layout name.
 ///
 /// When a `main.hbs` file exists in the project layouts
@@ -118,9 +128,6 @@ async fn find(
 ) -> Result<Vec<Error>> {
     let languages = req.locales.alternate();
 
-    let engine = req.config.engine();
-    let template_ext = engine.extension();
-
     let layouts_dir = req.options.source.join(config::LAYOUTS);
     let partials_dir = req.options.source.join(config::PARTIALS);
 
@@ -157,7 +164,9 @@ async fn find(
 
                     let key = Arc::new(buf);
 
-                    if path.extension() == Some(OsStr::new(template_ext)) {
+                    // Templates for any engine so that layouts may
+                    // select a different engine to the project default
+                    if TemplateEngine::from_path(path).is_some() {
                         // Partials are a convention that the parser will handle
                         if path.starts_with(&partials_dir) {
                             return WalkState::Continue;
@@ -258,7 +267,21 @@ fn add_page(
     layout_name: &str,
 ) -> Result<()> {
     let builder = PageBuilder::new(info, config, options, plugins, key, path)
-        .compute()?
+        .compute()?;
+
+    // Drafts are excluded before any collation data is
+    // assigned so they do not appear in feeds, menus or queries
+    if builder.is_draft() {
+        warn!("Draft {}", key.display());
+        return Ok(());
+    }
+
+    if builder.is_inactive() {
+        debug!("Skip {} for this profile", key.display());
+        return Ok(());
+    }
+
+    let builder = builder
         .layout(layout_name)?
         .queries()?
         .seal()?
@@ -270,23 +293,28 @@ fn add_page(
         .feeds()?;
 
     let (info, key, destination, page) = builder.build();
-
-    if page.draft() && !options.settings.include_drafts() {
-        warn!("Draft {}", key.display());
-    } else {
-        //println!("Adding page with key {:?}", key);
-        info.add_page(key, destination, Arc::new(RwLock::new(page)));
-    }
+    info.add_page(key, destination, Arc::new(RwLock::new(page)));
 
     Ok(())
 }
 
 fn add_other(
     info: &mut CollateInfo,
-    _config: &Config,
+    config: &Config,
     options: &RuntimeOptions,
     key: &Arc<PathBuf>,
 ) -> Result<()> {
+    // Critical styles are inlined in each page so the
+    // style sheet is not copied to the build
+    if options.settings.should_inline_critical_style() {
+        if let Some(critical) = config.critical_style() {
+            if key.as_ref() == &options.source.join(critical.source()) {
+                debug!("Inline {}", key.display());
+                return Ok(());
+            }
+        }
+    }
+
     let dest = options.destination().exact(true).build(key)?;
 
     let href = to_href(key, options, false, None)?;
@@ -313,10 +341,7 @@ pub fn add(
     key: &Arc<PathBuf>,
     path: &Path,
 ) -> Result<()> {
-    let engine = config.engine();
-    let template_ext = engine.extension();
-
-    if path.extension() == Some(OsStr::new(template_ext)) {
+    if TemplateEngine::from_path(path).is_some() {
         add_template(info, config, options, key)
     } else {
         let is_collection = key.starts_with(options.collections_path());
@@ -325,6 +350,14 @@ pub fn add(
             !is_collection && path.is_file() && options.is_page(&path);
 
         if is_page {
+            // Respect the profile source filter, excluded pages
+            // never reach the collation so they are not available
+            // to menus, feeds or the sitemap
+            if !is_source_match(options, path) {
+                debug!("Filtered {}", path.display());
+                return Ok(());
+            }
+
             let layout_name = layout_name(options);
             add_page(info, config, options, plugins, key, path, layout_name)
         } else {
@@ -332,3 +365,145 @@ pub fn add(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use config::{
+        profile::SourceFilter, test_util::TempProject, ProfileName,
+        ProfileSettings, RuntimeOptions,
+    };
+
+    use super::{add, add_page};
+    use crate::{CollateInfo, Result};
+
+    #[test]
+    fn draft_profile() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n",
+        )?;
+        let draft = project.write(
+            "site/draft.md",
+            "+++\ntitle = \"Draft\"\ndraft = true\n+++\n",
+        )?;
+        let page = project.write("site/page.md", "# Page\n")?;
+        let config = project.config()?;
+
+        for (name, included) in
+            vec![(ProfileName::Release, false), (ProfileName::Debug, true)]
+        {
+            let mut options: RuntimeOptions = Default::default();
+            options.source = project.source();
+            options.settings = ProfileSettings::from(&name);
+
+            let mut info =
+                CollateInfo::new("en".to_string(), Default::default());
+            for path in [&draft, &page] {
+                let key = Arc::new(path.clone());
+                add_page(
+                    &mut info, &config, &options, None, &key, path, "main",
+                )?;
+            }
+
+            assert_eq!(
+                included,
+                info.get_pages().contains_key(&Arc::new(draft.clone()))
+            );
+            assert!(info.get_pages().contains_key(&Arc::new(page.clone())));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn page_profiles() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n",
+        )?;
+        let soon = project.write(
+            "site/coming-soon.md",
+            "+++\ntitle = \"Coming soon\"\nprofiles = [\"staging\"]\n+++\n",
+        )?;
+        let config = project.config()?;
+
+        for (name, included) in vec![
+            (ProfileName::Custom("staging".to_string()), true),
+            (ProfileName::Release, false),
+        ] {
+            let mut options: RuntimeOptions = Default::default();
+            options.source = project.source();
+            options.settings = ProfileSettings::from(&name);
+
+            let mut info =
+                CollateInfo::new("en".to_string(), Default::default());
+            let key = Arc::new(soon.clone());
+            add_page(&mut info, &config, &options, None, &key, &soon, "main")?;
+            assert_eq!(included, info.get_pages().contains_key(&key));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn critical_style_resource() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[critical_style]\n",
+        )?;
+        let critical = project.write("site/critical.css", "body {}")?;
+        let style = project.write("site/style.css", "p {}")?;
+        let config = project.config()?;
+
+        for (inline, copied) in [(Some(true), false), (None, true)] {
+            let mut options: RuntimeOptions = Default::default();
+            options.source = project.source();
+            options.settings = ProfileSettings::from(&ProfileName::Debug);
+            options.settings.critical_style = inline;
+
+            let mut info =
+                CollateInfo::new("en".to_string(), Default::default());
+            for path in [&critical, &style] {
+                let key = Arc::new(path.clone());
+                add(&mut info, &config, &options, None, &key, path)?;
+            }
+
+            assert_eq!(copied, info.resources.contains(&critical));
+            assert!(info.resources.contains(&style));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn source_filter_pages() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n",
+        )?;
+        let page = project.write("site/gallery/index.md", "# Gallery\n")?;
+        let asset = project.write("site/gallery/photo.jpg", "jpg")?;
+        let config = project.config()?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+        let mut sources: SourceFilter =
+            toml::from_str("excludes = [\"gallery/**\"]").unwrap();
+        sources.prepare();
+        options.settings.sources = Some(sources);
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        for path in [&page, &asset] {
+            let key = Arc::new(path.clone());
+            add(&mut info, &config, &options, None, &key, path)?;
+        }
+
+        assert!(!info.get_pages().contains_key(&Arc::new(page)));
+        assert!(info.get_resource(&asset).is_some());
+        Ok(())
+    }
+}
@@ -25,6 +25,9 @@ pub enum Error {
     #[error("No feed template file {0}")]
     NoFeedTemplate(PathBuf),
 
+    #[error("No error page template file {0}")]
+    NoErrorPageTemplate(PathBuf),
+
     #[error("Script {0} not found using src {1}")]
     NoScriptSource(PathBuf, String),
 
@@ -37,6 +40,9 @@ pub enum Error {
     #[error("File {0} for page data with key {1} does not exist")]
     NoPageFile(PathBuf, String),
 
+    #[error("File {0} is not a markdown or template page")]
+    NotPageFile(PathBuf),
+
     #[error("Front matter error in {0} ({1})")]
     FrontMatterParse(PathBuf, toml::de::Error),
 
@@ -45,9 +51,21 @@ pub enum Error {
     )]
     DuplicatePermalink(String),
 
+    #[error("Permalink {0} for {2} is already used by {1}, ensure permalink patterns are unique")]
+    PermalinkCollision(String, PathBuf, PathBuf),
+
+    #[error("Output path '{0}' for {1} must be relative to the build target")]
+    InvalidOutputPath(String, PathBuf),
+
+    #[error("Page {0} matches a permalink pattern but has no created date")]
+    NoPermalinkDate(PathBuf),
+
     #[error("Query may not combine 'each' with 'page'")]
     QueryConflict,
 
+    #[error("Page size {0} is not valid, must be zero or greater than one")]
+    PageSizeTooSmall(usize),
+
     #[error("No page found for menu item reference {0}")]
     NoMenuItem(String),
 
@@ -116,6 +134,9 @@ mod synthetic;
 pub use builder::to_href;
 pub use collation::*;
 pub use collator::*;
+pub use loader::render_markdown_file;
 pub use locale_utils::*;
 pub use resource::*;
-pub use synthetic::{create_file, create_page, feed};
+pub use synthetic::{
+    create_file, create_page, error_pages, feed, translations,
+};
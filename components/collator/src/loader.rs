@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::path::Path;
 
 use inflector::Inflector;
@@ -64,21 +65,82 @@ pub fn compute<P: AsRef<Path>>(
     let file_type = opts.get_type(f.as_ref());
     let mut conf: frontmatter::Config = Default::default();
     match file_type {
-        FileType::Markdown => conf = frontmatter::Config::new_markdown(true),
-        FileType::Template => conf = frontmatter::Config::new_html(true),
+        FileType::Markdown => conf = frontmatter::Config::new_markdown(false),
+        FileType::Template => conf = frontmatter::Config::new_html(false),
         _ => {}
     }
 
-    let (_, has_fm, fm) = frontmatter::load(file, conf)?;
+    // Only the start of the content is needed for the excerpt
+    // unless the links are collected to find orphaned resources
+    let excerpt = config.excerpt();
+    let scan_orphans = opts.settings.should_report_orphans();
+    let (content, has_fm, fm) = if scan_orphans {
+        frontmatter::load(file, conf)?
+    } else {
+        frontmatter::load_excerpt(
+            file,
+            conf,
+            excerpt.marker(),
+            excerpt.words(),
+        )?
+    };
+    if scan_orphans {
+        page.references = Some(
+            crate::collation::references(&fm)
+                .chain(crate::collation::references(&content))
+                .map(|s| s.to_string())
+                .collect(),
+        );
+    }
     if has_fm {
         parse_into(file, fm, &mut page)?;
     }
 
     page.compute(config, opts)?;
+    page.compute_excerpt(config, &content, file_type == FileType::Markdown);
 
     Ok(page)
 }
 
+/// Render a single page file without compiling a project.
+///
+/// Front matter is stripped and merged into a copy of the global
+/// page data; markdown is converted to HTML and the content of
+/// template files is returned as is.
+pub fn render_markdown_file<P: AsRef<Path>>(
+    f: P,
+    config: &Config,
+    opts: &RuntimeOptions,
+) -> Result<(Page, String)> {
+    let file = f.as_ref();
+
+    let file_type = opts.get_type(file);
+    let conf = match file_type {
+        FileType::Markdown => frontmatter::Config::new_markdown(false),
+        FileType::Template => frontmatter::Config::new_html(false),
+        _ => return Err(Error::NotPageFile(file.to_path_buf())),
+    };
+
+    let mut page = config.page.clone().unwrap_or_default();
+    let (content, has_fm, fm) = frontmatter::load(file, conf)?;
+    if has_fm {
+        parse_into(file, fm, &mut page)?;
+    }
+
+    if page.title.is_none() {
+        page.title = file_auto_title(file);
+    }
+
+    let html = match file_type {
+        FileType::Markdown => {
+            config::markdown::render(&mut Cow::from(content), config)
+        }
+        _ => content,
+    };
+
+    Ok((page, html))
+}
+
 fn parse_into<P: AsRef<Path>>(
     file: P,
     source: String,
@@ -110,3 +172,69 @@ pub fn verify(config: &Config, options: &RuntimeOptions) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use config::{test_util::TempProject, ProfileName, ProfileSettings};
+
+    use super::*;
+
+    #[test]
+    fn render_markdown() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n",
+        )?;
+        let file = project.write(
+            "site/post.md",
+            "+++\ntitle = \"Post\"\n+++\n# Heading\n\nBody *text*\n",
+        )?;
+        let config = project.config()?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let (page, html) = render_markdown_file(file, &config, &options)?;
+        assert_eq!(Some("Post".to_string()), page.title);
+        assert!(!html.contains("+++"));
+        assert!(html.contains("<h1>Heading</h1>"));
+        assert!(html.contains("<p>Body <em>text</em></p>"));
+
+        let file = project.write("site/style.css", "body {}")?;
+        assert!(render_markdown_file(file, &config, &options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn compute_excerpt() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n",
+        )?;
+        let page = project.write(
+            "site/about.html",
+            "<!--\ntitle = \"About\"\n-->\n<script>init();</script>\n\
+            <p>About {{title}} <b>us</b></p>\n<!-- more -->\n<p>{{> footer}}",
+        )?;
+        let post = project.write(
+            "site/post.md",
+            "+++\nsummary = \"The *post*\"\n+++\n# Post\n",
+        )?;
+        let config = project.config()?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let data = compute(page, &config, &options)?;
+        assert_eq!(Some("About"), data.title.as_deref());
+        assert_eq!(Some("About us"), data.excerpt.as_deref());
+
+        let data = compute(post, &config, &options)?;
+        assert_eq!(Some("The post"), data.excerpt.as_deref());
+        Ok(())
+    }
+}
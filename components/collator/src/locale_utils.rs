@@ -32,14 +32,14 @@ pub fn get_locale_file_info(
 }
 
 /// Get the target directory depending upon whether multiple locales
-/// are available.
-pub(crate) fn get_locale_target(
+/// are available and the language has a URL prefix.
+pub fn get_locale_target(
     lang: &str,
     base: &PathBuf,
     locales: &LocaleMap,
 ) -> PathBuf {
-    if locales.is_multi_lingual() {
-        base.join(lang)
+    if let Some(prefix) = locales.url_prefix(lang) {
+        base.join(prefix)
     } else {
         base.clone()
     }
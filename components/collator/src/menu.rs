@@ -3,7 +3,8 @@ use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
 use config::{
-    Config, MenuEntry, MenuReference, MenuResult, Page, RuntimeOptions,
+    weight_order, Config, MenuEntry, MenuReference, MenuResult, Page,
+    RuntimeOptions,
 };
 
 use crate::{CollateInfo, Collation, Error, Result};
@@ -120,13 +121,22 @@ pub fn build<'c>(
     }
 
     if should_sort {
-        // Sort by title.
-        page_data.sort_by(|(_, _, a), (_, _, b)| {
+        // Sort by weight, then title and then file path.
+        page_data.sort_by(|(a_path, _, a), (b_path, _, b)| {
             let a = &*a.read().unwrap();
             let b = &*b.read().unwrap();
             let s1 = a.title.as_ref().map(|x| &**x).unwrap_or("");
             let s2 = b.title.as_ref().map(|x| &**x).unwrap_or("");
-            s1.partial_cmp(s2).unwrap()
+            weight_order(a.weight, b.weight)
+                .then_with(|| s1.cmp(s2))
+                .then_with(|| a_path.cmp(b_path))
+        });
+    } else {
+        // Explicit pages keep the declared order unless weighted.
+        page_data.sort_by(|(_, _, a), (_, _, b)| {
+            let a = &*a.read().unwrap();
+            let b = &*b.read().unwrap();
+            weight_order(a.weight, b.weight)
         });
     }
 
@@ -166,8 +176,9 @@ pub fn compile<'c>(
 
     // Compile the menu entries into template strings
     if let Some(ref menu) = config.menu {
-        for (k, v) in menu.entries.iter() {
-            let (result, _page_data) = build(options, collation, v)?;
+        for (order, (k, v)) in menu.ordered().into_iter().enumerate() {
+            let (mut result, _page_data) = build(options, collation, v)?;
+            result.order = order;
             results.insert(k.to_string(), result);
         }
     }
@@ -325,3 +336,62 @@ mod template {
         write(f, "</ul>\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use config::{
+        test_util::TempProject, MenuEntry, MenuReference, Page, RuntimeOptions,
+    };
+
+    use super::build;
+    use crate::{CollateInfo, Result};
+
+    #[test]
+    fn menu_page_weights() -> Result<()> {
+        let project = TempProject::new()?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.root().to_path_buf();
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        for (name, title, weight) in [
+            ("alpha", "Alpha", Some(3)),
+            ("beta", "Beta", Some(1)),
+            ("delta", "Delta", None),
+            ("gamma", "Gamma", Some(1)),
+        ] {
+            let file = project.write(&format!("docs/{}.md", name), "")?;
+            let mut page: Page = Default::default();
+            page.title = Some(title.to_string());
+            page.href = Some(format!("/docs/{}/", name));
+            page.weight = weight;
+            info.add_page(
+                &Arc::new(file),
+                format!("docs/{}/index.html", name).into(),
+                Arc::new(RwLock::new(page)),
+            );
+        }
+
+        let menu = MenuEntry::new(MenuReference::Directory {
+            directory: "/docs".into(),
+            description: None,
+            depth: None,
+            include_index: None,
+        });
+        let (result, _) = build(&options, &info, &menu)?;
+        let pages: Vec<&str> =
+            result.pages.iter().map(|p| p.as_str()).collect();
+        assert_eq!(
+            vec![
+                "/docs/beta/",
+                "/docs/gamma/",
+                "/docs/alpha/",
+                "/docs/delta/"
+            ],
+            pages
+        );
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
@@ -5,15 +6,17 @@ use chrono::prelude::*;
 use jsonfeed::{Feed, Item, VERSION};
 
 use config::{
-    feed::{ChannelConfig, FeedConfig},
+    error_pages::ErrorPagesConfig,
+    feed::{ChannelConfig, FeedConfig, FeedContent},
     plugin_cache::PluginCache,
     tags::link::LinkTag,
     Config, Page, Plugin, RuntimeOptions,
 };
 
 use locale::Locales;
+use serde_json::Value;
 
-use crate::{to_href, CollateInfo, Error, Result};
+use crate::{builder::output_href, to_href, CollateInfo, Error, Result};
 
 // Helper to inject synthetic pages.
 pub fn create_page(
@@ -66,12 +69,8 @@ fn build_feed(
     info: &mut CollateInfo,
     feed_cfg: &FeedConfig,
     channel_cfg: &ChannelConfig,
-) -> Result<Feed> {
-    let url_path = if locales.is_multi_lingual() {
-        Some(info.get_lang())
-    } else {
-        None
-    };
+) -> Result<(Feed, HashMap<String, PathBuf>)> {
+    let url_path = locales.languages().url_prefix(info.get_lang());
 
     let base_url = options.get_canonical_url(config, url_path)?;
 
@@ -138,7 +137,11 @@ fn build_feed(
     });
 
     // Limit the number of items in the feed
-    pages.truncate(*feed_cfg.limit.as_ref().unwrap());
+    let limit = channel_cfg.limit.or(feed_cfg.limit).unwrap();
+    pages.truncate(limit);
+
+    let content = channel_cfg.content.unwrap_or(feed_cfg.content);
+    let mut sources = HashMap::new();
 
     feed.items = pages
         .iter()
@@ -155,7 +158,7 @@ fn build_feed(
             };
 
             item.title = p.title.clone();
-            item.summary = p.description.clone();
+            item.summary = p.description.clone().or(p.excerpt.clone());
 
             let created = if let Some(ref created) = p.created {
                 created.clone()
@@ -174,18 +177,15 @@ fn build_feed(
                 Some(p.file.as_ref().unwrap().modified.to_rfc3339())
             };
 
-            // Page-level authors
+            // Page-level authors with the primary author first
             item.authors = if let Some(ref author_refs) = p.authors() {
-                Some(
-                    config
-                        .authors()
-                        .iter()
-                        .filter(|(k, _)| author_refs.contains(k))
-                        .map(|(_, v)| v)
+                author_refs.resolve(config.authors()).ok().map(|authors| {
+                    authors
+                        .into_iter()
                         .cloned()
                         .map(|a| a.into_json_feed())
-                        .collect::<Vec<_>>(),
-                )
+                        .collect::<Vec<_>>()
+                })
             } else {
                 None
             };
@@ -205,13 +205,16 @@ fn build_feed(
                 item.attachments = entry.attachments.clone();
             }
 
-            // TODO: content
+            // Full content is rendered with the feed pages
+            if let (FeedContent::Full, Some(ref file)) = (content, &p.file) {
+                sources.insert(item.id.clone(), file.source.clone());
+            }
 
-            item
+            Ok(item)
         })
-        .collect();
+        .collect::<Result<Vec<_>>>()?;
 
-    Ok(feed)
+    Ok((feed, sources))
 }
 
 fn find_feed_plugin<'a>(
@@ -261,9 +264,12 @@ pub fn feed(
         // Data is the same for each feed
         let mut feed_page_data: Page = Default::default();
         feed_page_data.standalone = Some(true);
-        feed_page_data.feed = Some(build_feed(
-            name, locales, config, options, info, feed, channel,
-        )?);
+        let (channel_feed, sources) =
+            build_feed(name, locales, config, options, info, feed, channel)?;
+        feed_page_data.feed = Some(channel_feed);
+        if !sources.is_empty() {
+            feed_page_data.feed_sources = Some(sources);
+        }
 
         // Store feed URLs for <link rel="alternate">
         let mut alternates: Vec<(String, &str)> = Vec::new();
@@ -301,11 +307,7 @@ pub fn feed(
 
             let mut item_data = feed_page_data.clone();
 
-            let url_path = if locales.is_multi_lingual() {
-                Some(info.get_lang())
-            } else {
-                None
-            };
+            let url_path = locales.languages().url_prefix(info.get_lang());
 
             // Update the feed url for this file
             let base_url = options.get_canonical_url(config, url_path)?;
@@ -351,3 +353,347 @@ pub fn feed(
     }
     Ok(())
 }
+
+// Link pages to their translations.
+//
+// Every version of a page that has been translated gets a
+// `<link rel="alternate" hreflang="..">` for each version
+// including itself so the links are always reciprocal.
+pub fn translations(
+    locales: &Locales,
+    config: &Config,
+    options: &RuntimeOptions,
+    collations: &[CollateInfo],
+) -> Result<()> {
+    let languages = locales.languages();
+    if !languages.is_multi_lingual() {
+        return Ok(());
+    }
+
+    let base_url = options.get_canonical_url::<&str>(config, None)?;
+    let mut versions: HashMap<&PathBuf, Vec<(&str, String)>> = HashMap::new();
+    for info in collations.iter() {
+        for key in info.pages.keys() {
+            if let Some(href) = info.get_link_href(key) {
+                let mut href = href.trim_start_matches('/');
+                if !options.settings.should_include_index() {
+                    href = href.trim_end_matches(config::INDEX_HTML);
+                }
+                let path = match languages.url_prefix(info.get_lang()) {
+                    Some(prefix) => format!("{}/{}", prefix, href),
+                    None => href.to_string(),
+                };
+                versions
+                    .entry(&**key)
+                    .or_insert_with(Vec::new)
+                    .push((info.get_lang(), base_url.join(&path)?.to_string()));
+            }
+        }
+    }
+
+    for info in collations.iter() {
+        for (key, page_lock) in info.pages.iter() {
+            if let Some(links) = versions.get(&**key) {
+                if links.len() > 1 {
+                    let mut page_write = page_lock.write().unwrap();
+                    for (lang, url) in links.iter() {
+                        page_write.links_mut().insert(
+                            LinkTag::new_translation(
+                                url.to_string(),
+                                lang.to_string(),
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Create error pages from a template.
+pub fn error_pages(
+    error_pages: &ErrorPagesConfig,
+    config: &Config,
+    options: &RuntimeOptions,
+    info: &mut CollateInfo,
+) -> Result<()> {
+    let template = options.source.join(error_pages.template());
+    if !template.exists() || !template.is_file() {
+        return Err(Error::NoErrorPageTemplate(template));
+    }
+
+    for (status, error_page) in error_pages.pages().iter() {
+        let output = error_page.output(status);
+        let source = options.source.join(utils::url::to_path_separator(
+            output_href(&output, &template)?.trim_start_matches('/'),
+        ));
+
+        // Pages in the site take precedence
+        let href = to_href(&source, options, false, None)?;
+        if info.get_link_path(&href).is_some() {
+            continue;
+        }
+
+        let mut page_data: Page = Default::default();
+        page_data.standalone = Some(true);
+        page_data.title = error_page.title().clone();
+        page_data
+            .extra
+            .insert("status".to_string(), Value::from(status.to_string()));
+        if let Some(ref message) = error_page.message() {
+            page_data
+                .extra
+                .insert("message".to_string(), Value::from(message.clone()));
+        }
+
+        create_page(
+            config,
+            options,
+            info,
+            source,
+            template.clone(),
+            Arc::new(RwLock::new(page_data)),
+            false,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{Arc, RwLock};
+
+    use config::{
+        test_util::TempProject, Page, ProfileName, ProfileSettings,
+        RuntimeOptions,
+    };
+    use locale::Locales;
+
+    use super::{build_feed, error_pages, translations};
+    use crate::{
+        builder::PageBuilder, locale_utils::get_locale_target, CollateInfo,
+        Error, Resource, Result,
+    };
+
+    #[test]
+    fn not_found_page() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            r#"lang = "en"
+host = "localhost"
+
+[error_pages.pages.404]
+title = "Not Found"
+message = "Nothing here"
+"#,
+        )?;
+
+        let config = project.config()?;
+        let error_config = config.error_pages().as_ref().unwrap();
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        assert!(matches!(
+            error_pages(error_config, &config, &options, &mut info),
+            Err(Error::NoErrorPageTemplate(..))
+        ));
+
+        let template = project.write(
+            "site/layouts/error.hbs",
+            "<h1>{{title}}</h1><p>{{status}}: {{message}}</p>",
+        )?;
+        error_pages(error_config, &config, &options, &mut info)?;
+
+        let source = project.source().join(config::ERROR_HTML);
+        match info.get_resource(&source) {
+            Some(Resource::Page { target }) => {
+                assert_eq!(
+                    PathBuf::from(config::ERROR_HTML),
+                    target.destination
+                )
+            }
+            _ => panic!("expected a page resource for the error page"),
+        }
+
+        let page = info.resolve(&source).unwrap().read().unwrap();
+        assert_eq!(Some("Not Found".to_string()), page.title);
+        assert_eq!(Some(true), page.standalone);
+        assert_eq!(Some("404"), page.extra.get("status").unwrap().as_str());
+        assert_eq!(
+            Some("Nothing here"),
+            page.extra.get("message").unwrap().as_str()
+        );
+        assert_eq!(template, page.file.as_ref().unwrap().template);
+        assert_eq!(
+            Some(&std::sync::Arc::new("/404.html".to_string())),
+            info.get_link_href(&source)
+        );
+        Ok(())
+    }
+
+    /// Collate the home and about pages where only the
+    /// about page has a French translation.
+    fn translated(
+        project: &TempProject,
+    ) -> std::result::Result<Vec<CollateInfo>, Box<dyn std::error::Error>> {
+        let config = project.config()?;
+        let mut locales: Locales = Default::default();
+        locales.load(&config, project.locales())?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let home = ("index.md", "/", config::INDEX_HTML);
+        let about = ("about.md", "/about/index.html", "about/index.html");
+        let target = PathBuf::from("build");
+        let mut collations = Vec::new();
+        for (lang, pages) in
+            [("en", vec![home, about]), ("fr", vec![about])].iter()
+        {
+            let path = get_locale_target(lang, &target, locales.languages());
+            let mut info = CollateInfo::new(lang.to_string(), path);
+            for (name, href, dest) in pages.iter() {
+                let key = Arc::new(project.source().join(name));
+                info.link(Arc::clone(&key), Arc::new(href.to_string()))?;
+                info.add_page(
+                    &key,
+                    PathBuf::from(dest),
+                    Arc::new(RwLock::new(Page::default())),
+                );
+            }
+            collations.push(info);
+        }
+
+        translations(&locales, &config, &options, &collations)?;
+        Ok(collations)
+    }
+
+    fn hreflang(info: &CollateInfo, key: &PathBuf) -> Vec<String> {
+        let page = info.resolve(key).unwrap().read().unwrap();
+        page.links().iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn translation_links() -> std::result::Result<(), Box<dyn std::error::Error>>
+    {
+        let project = TempProject::multi_lingual(&["en", "fr"])?;
+        let home = project.source().join("index.md");
+        let about = project.source().join("about.md");
+
+        let collations = translated(&project)?;
+        assert_eq!(PathBuf::from("build/en"), collations[0].path);
+        assert_eq!(PathBuf::from("build/fr"), collations[1].path);
+
+        // Pages without a translation are not linked
+        assert!(hreflang(&collations[0], &home).is_empty());
+
+        for info in collations.iter() {
+            let links = hreflang(info, &about);
+            assert_eq!(2, links.len());
+            assert!(links[0].contains(r#"/en/about/" hreflang="en""#));
+            assert!(links[1].contains(r#"/fr/about/" hreflang="fr""#));
+        }
+
+        // Fallback language without a prefix
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[fluent]\nprefix-fallback = false\n",
+        )?;
+        let collations = translated(&project)?;
+        assert_eq!(PathBuf::from("build"), collations[0].path);
+        assert_eq!(PathBuf::from("build/fr"), collations[1].path);
+        for info in collations.iter() {
+            let links = hreflang(info, &about);
+            assert!(links[0].contains(r#"/about/" hreflang="en""#));
+            assert!(!links[0].contains("/en/"));
+            assert!(links[1].contains(r#"/fr/about/" hreflang="fr""#));
+        }
+        Ok(())
+    }
+
+    type FeedItems = (
+        Vec<jsonfeed::Item>,
+        std::collections::HashMap<String, PathBuf>,
+    );
+
+    fn feed_items(
+        site: &str,
+    ) -> std::result::Result<FeedItems, Box<dyn std::error::Error>> {
+        let project = TempProject::new()?;
+        project.write(config::SITE_TOML, site)?;
+
+        let mut posts = Vec::new();
+        for day in 1..=5 {
+            posts.push(project.write(
+                &format!("site/blog/post-{}.md", day),
+                &format!(
+                    "+++\ntitle = \"Post {}\"\ncreated = 2021-03-0{}\n+++\n\nIntro {}\n\n<!-- more -->\n\nRest {}\n",
+                    day, day, day, day
+                ),
+            )?);
+        }
+
+        let config = project.config()?;
+        let locales: Locales = Default::default();
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        for path in posts.iter() {
+            let key = Arc::new(path.clone());
+            let builder = PageBuilder::new(
+                &mut info, &config, &options, None, &key, path,
+            )
+            .compute()?
+            .seal()?
+            .feeds()?;
+            let (info, key, destination, page) = builder.build();
+            info.add_page(key, destination, Arc::new(RwLock::new(page)));
+        }
+
+        let feed = config.feed.as_ref().unwrap();
+        let channel = feed.channels.get("blog").unwrap();
+        let (result, sources) = build_feed(
+            "blog", &locales, &config, &options, &mut info, feed, channel,
+        )?;
+        Ok((result.items, sources))
+    }
+
+    #[test]
+    fn feed_limit_content(
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let site = "lang = \"en\"\nhost = \"localhost\"\n\n[feed]\nlimit = 3\n\n[feed.blog]\ntarget = \"blog\"\ntitle = \"Blog\"\n";
+
+        // Newest items first in summary form by default
+        let (items, sources) = feed_items(site)?;
+        let titles = items
+            .iter()
+            .map(|item| item.title.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Post 5", "Post 4", "Post 3"], titles);
+        assert_eq!(Some("Intro 5"), items[0].summary.as_deref());
+        assert_eq!(None, items[0].content_html);
+        assert!(sources.is_empty());
+
+        // Full content for the channel is rendered with the feed
+        let (items, sources) =
+            feed_items(&format!("{}content = \"full\"\n", site))?;
+        assert_eq!(3, items.len());
+        assert_eq!(3, sources.len());
+        assert!(sources
+            .get(&items[0].id)
+            .unwrap()
+            .ends_with("blog/post-5.md"));
+        assert_eq!(Some("Intro 5"), items[0].summary.as_deref());
+        Ok(())
+    }
+}
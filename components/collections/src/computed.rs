@@ -0,0 +1,74 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use collator::CollateInfo;
+use config::{Config, RuntimeOptions};
+
+use crate::{CollectionDataBase, Result};
+
+/// Request passed to a computed provider.
+pub struct ComputeRequest<'a> {
+    pub name: &'a str,
+    pub config: &'a Config,
+    pub options: &'a RuntimeOptions,
+    pub collation: &'a CollateInfo,
+    /// Collections loaded from files and pages which may be
+    /// used to derive the computed documents.
+    pub collections: &'a BTreeMap<String, CollectionDataBase>,
+}
+
+/// Trait for types that synthesize collection documents at build time.
+///
+/// Each document is returned with its identifier; identifiers must be
+/// unique within the collection.
+pub trait ComputedProvider: Send + Sync {
+    fn documents(
+        &self,
+        req: &ComputeRequest<'_>,
+    ) -> Result<Vec<(String, Value)>>;
+}
+
+impl<F> ComputedProvider for F
+where
+    F: Fn(&ComputeRequest<'_>) -> Result<Vec<(String, Value)>> + Send + Sync,
+{
+    fn documents(
+        &self,
+        req: &ComputeRequest<'_>,
+    ) -> Result<Vec<(String, Value)>> {
+        (self)(req)
+    }
+}
+
+/// Computed providers registered by collection name.
+#[derive(Default, Clone)]
+pub struct ComputedProviders {
+    map: HashMap<String, Arc<dyn ComputedProvider>>,
+}
+
+impl ComputedProviders {
+    pub fn register<S: Into<String>>(
+        &mut self,
+        name: S,
+        provider: Arc<dyn ComputedProvider>,
+    ) {
+        self.map.insert(name.into(), provider);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn ComputedProvider>> {
+        self.map.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl fmt::Debug for ComputedProviders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.map.keys()).finish()
+    }
+}
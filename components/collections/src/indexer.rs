@@ -1,5 +1,5 @@
 use std::collections::BTreeMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -9,12 +9,15 @@ use serde_json::{Map, Value};
 use collator::CollateInfo;
 use config::indexer::{
     DataProvider, IndexKey, IndexQuery, KeyResult, KeyType, QueryResult,
-    QueryValue,
+    QueryValue, SourceProvider,
 };
 use config::{Config, RuntimeOptions};
 use utils::json_path;
 
-use crate::{identifier, provider, Error, Result};
+use crate::{
+    computed::{ComputeRequest, ComputedProvider, ComputedProviders},
+    identifier, provider, reference, Error, Result,
+};
 
 pub type QueryCache = HashMap<IndexQuery, Vec<QueryResult>>;
 pub type IndexValue = (IndexKey, Arc<Value>);
@@ -52,8 +55,21 @@ impl CollectionDataBase {
         &self.config
     }
 
+    /// All documents in the database keyed by identifier.
+    pub fn documents(&self) -> &BTreeMap<String, Arc<Value>> {
+        &self.all
+    }
+
+    fn is_computed(&self) -> bool {
+        matches!(self.config.source_provider(), SourceProvider::Computed)
+    }
+
     /// Build a single database; loading documents from disc
     /// and computing indices.
+    ///
+    /// References to other collections are not resolved, use
+    /// `load()`, `resolve_references()` and `load_indices()` when
+    /// the collection declares references.
     pub async fn build(
         &mut self,
         db_name: &str,
@@ -61,12 +77,8 @@ impl CollectionDataBase {
         options: &RuntimeOptions,
         collation: &CollateInfo,
     ) -> Result<()> {
-        // Ensure the database is pristine
-        self.clear();
-
         // Load the documents for the database
-        self.load_provider(db_name, config, options, collation)
-            .await?;
+        self.load(db_name, config, options, collation).await?;
 
         // Compute the indices for the new database
         self.load_indices(db_name)?;
@@ -74,6 +86,45 @@ impl CollectionDataBase {
         Ok(())
     }
 
+    /// Load the documents for a single database without
+    /// computing indices.
+    pub async fn load(
+        &mut self,
+        db_name: &str,
+        config: &Config,
+        options: &RuntimeOptions,
+        collation: &CollateInfo,
+    ) -> Result<()> {
+        // Computed documents must be supplied by a registered provider
+        if self.is_computed() {
+            return Err(Error::NoComputedProvider(db_name.to_string()));
+        }
+
+        // Ensure the database is pristine
+        self.clear();
+
+        self.load_provider(db_name, config, options, collation)
+            .await
+    }
+
+    /// Replace foreign keys in the documents with the documents
+    /// they reference in the other collections.
+    pub fn resolve_references(
+        &mut self,
+        db_name: &str,
+        collections: &BTreeMap<String, CollectionDataBase>,
+    ) -> Result<()> {
+        if let Some(references) = self.config.references() {
+            self.all = reference::resolve(
+                db_name,
+                &self.all,
+                references,
+                collections,
+            )?;
+        }
+        Ok(())
+    }
+
     async fn load_provider(
         &mut self,
         db_name: &str,
@@ -107,7 +158,35 @@ impl CollectionDataBase {
         Ok(())
     }
 
-    fn load_indices(&mut self, db_name: &str) -> Result<()> {
+    /// Build a single database from computed documents, duplicate
+    /// identifiers are an error.
+    pub fn build_computed(
+        &mut self,
+        db_name: &str,
+        provider: &dyn ComputedProvider,
+        req: &ComputeRequest<'_>,
+    ) -> Result<()> {
+        self.clear();
+
+        info!("Compute {}", db_name);
+
+        for (key, document) in provider.documents(req)? {
+            if self.all.contains_key(&key) {
+                return Err(Error::DuplicateId {
+                    key,
+                    path: self.source.clone(),
+                });
+            }
+            self.all.insert(key, Arc::new(document));
+        }
+
+        self.load_indices(db_name)?;
+
+        Ok(())
+    }
+
+    /// Compute the indices for the loaded documents.
+    pub fn load_indices(&mut self, db_name: &str) -> Result<()> {
         let index = self.config.index.as_ref().unwrap();
 
         for (name, def) in index {
@@ -377,6 +456,7 @@ impl ValueIndex {
 #[derive(Debug, Default)]
 pub struct CollectionsMap {
     map: BTreeMap<String, CollectionDataBase>,
+    computed: ComputedProviders,
 }
 
 impl CollectionsMap {
@@ -393,6 +473,63 @@ impl CollectionsMap {
     pub fn map_mut(&mut self) -> &mut BTreeMap<String, CollectionDataBase> {
         &mut self.map
     }
+
+    /// Names of the collections that reference any of the
+    /// named collections.
+    pub fn referencing(&self, names: &HashSet<String>) -> HashSet<String> {
+        self.map
+            .iter()
+            .filter(|(_, db)| {
+                db.config.references().map_or(false, |references| {
+                    references.values().any(|r| names.contains(&r.collection))
+                })
+            })
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    /// Set the providers for collections that use the
+    /// `computed` source provider.
+    pub fn set_computed(&mut self, computed: ComputedProviders) {
+        self.computed = computed;
+    }
+
+    /// Names of the collections that use the `computed` source provider.
+    pub fn computed_names(&self) -> HashSet<String> {
+        self.map
+            .iter()
+            .filter(|(_, db)| db.is_computed())
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    /// Build a computed collection from the other collections.
+    pub fn compute(
+        &self,
+        db_name: &str,
+        provider: &DataProvider,
+        config: &Config,
+        options: &RuntimeOptions,
+        collation: &CollateInfo,
+    ) -> Result<CollectionDataBase> {
+        let computer =
+            self.computed.get(db_name).cloned().ok_or_else(|| {
+                Error::NoComputedProvider(db_name.to_string())
+            })?;
+
+        let mut db =
+            CollectionDataBase::new(options.source.clone(), provider.clone());
+
+        let req = ComputeRequest {
+            name: db_name,
+            config,
+            options,
+            collation,
+            collections: &self.map,
+        };
+        db.build_computed(db_name, computer.as_ref(), &req)?;
+        Ok(db)
+    }
 }
 
 impl CollectionsMap {
@@ -405,6 +542,14 @@ impl CollectionsMap {
     ) -> Result<()> {
         if let Some(ref db) = config.db {
             if let Some(ref sources) = db.load {
+                let (computed, sources): (Vec<_>, Vec<_>) =
+                    sources.iter().partition(|(_, provider)| {
+                        matches!(
+                            provider.source_provider(),
+                            SourceProvider::Computed
+                        )
+                    });
+
                 for (db_name, provider) in sources {
                     let from = if let Some(ref from) = provider.from() {
                         options.source.join(from)
@@ -417,12 +562,42 @@ impl CollectionsMap {
                         provider.clone(),
                     );
 
-                    // Load the documents for the database and compute indices
-                    db.build(db_name, config, options, collation).await?;
+                    // Load the documents for the database
+                    db.load(db_name, config, options, collation).await?;
 
                     // Store for querying and live reload invalidation
                     self.map.insert(db_name.to_string(), db);
                 }
+
+                // References are resolved once all the documents
+                // are loaded so collections may reference each other
+                let mut resolved = Vec::new();
+                for (db_name, db) in self.map.iter() {
+                    if let Some(references) = db.config.references() {
+                        resolved.push((
+                            db_name.to_string(),
+                            reference::resolve(
+                                db_name, &db.all, references, &self.map,
+                            )?,
+                        ));
+                    }
+                }
+                for (db_name, all) in resolved {
+                    self.map.get_mut(&db_name).unwrap().all = all;
+                }
+
+                for (db_name, db) in self.map.iter_mut() {
+                    db.load_indices(db_name)?;
+                }
+
+                // Computed collections come last so they may be
+                // derived from the other collections
+                for (db_name, provider) in computed {
+                    let db = self.compute(
+                        db_name, provider, config, options, collation,
+                    )?;
+                    self.map.insert(db_name.to_string(), db);
+                }
             }
         }
 
@@ -477,3 +652,133 @@ impl CollectionsMap {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use collator::CollateInfo;
+    use config::{test_util::TempProject, IndexQuery, RuntimeOptions};
+
+    use super::CollectionsMap;
+    use crate::{
+        computed::{ComputeRequest, ComputedProviders},
+        Result,
+    };
+
+    fn synthesize(
+        _req: &ComputeRequest<'_>,
+    ) -> Result<Vec<(String, serde_json::Value)>> {
+        Ok(vec![
+            ("alpha".to_string(), json!({"title": "Alpha"})),
+            ("beta".to_string(), json!({"title": "Beta"})),
+        ])
+    }
+
+    #[tokio::test]
+    async fn computed_collection(
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[db.load.generated]\nprovider = \"computed\"\n\n[db.load.generated.index.all]\nkey = \"title\"\n",
+        )?;
+        let config = project.config()?;
+        let options: RuntimeOptions = Default::default();
+        let mut collation: CollateInfo = Default::default();
+
+        let mut computed: ComputedProviders = Default::default();
+        computed.register("generated", Arc::new(synthesize));
+
+        let mut collections: CollectionsMap = Default::default();
+        collections.set_computed(computed);
+        collections.load(&config, &options, &mut collation).await?;
+
+        let query = IndexQuery {
+            name: "generated".to_string(),
+            index: "all".to_string(),
+            ..Default::default()
+        };
+        let results =
+            collections.query_index(&query, &mut Default::default())?;
+        assert_eq!(2, results.len());
+        assert_eq!(Some("alpha".to_string()), results[0].id);
+        assert_eq!(Some("beta".to_string()), results[1].id);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn collection_references(
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            r#"lang = "en"
+host = "localhost"
+
+[db.load.authors]
+provider = "files"
+type = "json"
+from = "authors"
+
+[db.load.authors.index.all]
+key = "*"
+
+[db.load.posts]
+provider = "files"
+type = "json"
+from = "posts"
+
+[db.load.posts.references.author]
+collection = "authors"
+
+[db.load.posts.index.all]
+key = "*"
+"#,
+        )?;
+        project.write(
+            "site/authors/alice.json",
+            r#"{"name": "Alice", "email": "alice@example.com"}"#,
+        )?;
+        project.write(
+            "site/posts/hello.json",
+            r#"{"title": "Hello", "author": "alice"}"#,
+        )?;
+        let config = project.config()?;
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        let mut collation: CollateInfo = Default::default();
+
+        let mut collections: CollectionsMap = Default::default();
+        collections.load(&config, &options, &mut collation).await?;
+
+        let query = IndexQuery {
+            name: "posts".to_string(),
+            index: "all".to_string(),
+            include_docs: Some(true),
+            ..Default::default()
+        };
+        let results =
+            collections.query_index(&query, &mut Default::default())?;
+        assert_eq!(1, results.len());
+        let doc = results[0].value.as_ref().unwrap();
+        let doc = serde_json::to_value(doc)?;
+        assert_eq!(json!("Alice"), doc["author"]["name"]);
+        assert_eq!(json!("alice@example.com"), doc["author"]["email"]);
+
+        // Dangling references are reported
+        project.write(
+            "site/posts/missing.json",
+            r#"{"title": "Missing", "author": "bob"}"#,
+        )?;
+        let mut collections: CollectionsMap = Default::default();
+        let err = collections
+            .load(&config, &options, &mut collation)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing.author -> authors/bob"));
+        Ok(())
+    }
+}
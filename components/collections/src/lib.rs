@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use thiserror::Error;
@@ -10,6 +11,9 @@ pub enum Error {
     #[error("Duplicate document id {key} ({path})")]
     DuplicateId { key: String, path: PathBuf },
 
+    #[error("Duplicate document ids {}", duplicates(.0))]
+    DuplicateIds(BTreeMap<String, Vec<PathBuf>>),
+
     #[error("Type error building index, keys must be string values")]
     IndexKeyType,
 
@@ -19,7 +23,7 @@ pub enum Error {
     #[error("Data source document must have an id")]
     CollectionDocumentNoId,
 
-    #[error("Page size {0} is not large enough, must be greater than one")]
+    #[error("Page size {0} is not valid, must be zero or greater than one")]
     PageSizeTooSmall(usize),
 
     #[error("No data source with name {0}")]
@@ -45,6 +49,15 @@ pub enum Error {
     #[error("XML document {0} is empty")]
     EmptyXmlDocument(PathBuf),
 
+    #[error("No computed provider registered for collection {0}")]
+    NoComputedProvider(String),
+
+    #[error("Dangling references in collection {name}: {}", .references.join(", "))]
+    DanglingReferences {
+        name: String,
+        references: Vec<String>,
+    },
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -69,11 +82,27 @@ pub enum Error {
     Collator(#[from] collator::Error),
 }
 
+fn duplicates(ids: &BTreeMap<String, Vec<PathBuf>>) -> String {
+    ids.iter()
+        .map(|(key, paths)| {
+            let paths = paths
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{} ({})", key, paths)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 type Result<T> = std::result::Result<T, Error>;
 
+pub mod computed;
 pub mod identifier;
 mod indexer;
 pub mod provider;
+mod reference;
 pub mod synthetic;
 mod xml_value;
 
@@ -63,6 +63,53 @@ fn find_recursive(
     .flatten()
 }
 
+/// Source paths for document ids used to detect duplicates.
+///
+/// In fail fast mode the first duplicate is an error otherwise
+/// all documents are scanned and every duplicate id is reported
+/// with the paths of the documents that share it.
+struct Sources {
+    fail_fast: bool,
+    paths: BTreeMap<String, Vec<PathBuf>>,
+}
+
+impl Sources {
+    fn new(fail_fast: bool) -> Self {
+        Self {
+            fail_fast,
+            paths: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, key: &str, path: &Path) -> Result<()> {
+        let paths = self.paths.entry(key.to_string()).or_insert(Vec::new());
+        if !paths.is_empty() && self.fail_fast {
+            return Err(Error::DuplicateId {
+                key: key.to_string(),
+                path: path.to_path_buf(),
+            });
+        }
+        paths.push(path.to_path_buf());
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        let duplicates: BTreeMap<String, Vec<PathBuf>> = self
+            .paths
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(key, mut paths)| {
+                paths.sort();
+                (key, paths)
+            })
+            .collect();
+        if !duplicates.is_empty() {
+            return Err(Error::DuplicateIds(duplicates));
+        }
+        Ok(())
+    }
+}
+
 pub struct Provider {}
 
 impl Provider {
@@ -99,6 +146,8 @@ impl Provider {
             SourceProvider::Files => Provider::load_files(req).await,
             SourceProvider::Pages => Provider::load_pages(req).await,
             SourceProvider::Document => Provider::load_document(req).await,
+            // Computed documents are supplied by the collections map
+            SourceProvider::Computed => Ok(BTreeMap::new()),
         }
     }
 
@@ -144,6 +193,7 @@ impl Provider {
         req: LoadRequest<'_>,
     ) -> Result<BTreeMap<String, Arc<Value>>> {
         let mut docs: BTreeMap<String, Arc<Value>> = BTreeMap::new();
+        let mut sources = Sources::new(req.definition.fail_fast());
         let limit: usize = 100;
 
         stream::iter(req.collation.pages())
@@ -186,14 +236,11 @@ impl Provider {
                             &document,
                             &count,
                         );
-                        if docs.contains_key(&key) {
-                            return future::err(Error::DuplicateId {
-                                key,
-                                path: path.to_path_buf(),
-                            });
+                        if let Err(e) = sources.add(&key, path) {
+                            return future::err(e);
                         }
 
-                        docs.insert(key, Arc::new(document));
+                        docs.entry(key).or_insert(Arc::new(document));
                     }
                     Err(e) => return future::err(Error::from(e)),
                 }
@@ -201,6 +248,7 @@ impl Provider {
             })
             .await?;
 
+        sources.finish()?;
         Ok(docs)
     }
 
@@ -208,6 +256,7 @@ impl Provider {
         req: LoadRequest<'_>,
     ) -> Result<BTreeMap<String, Arc<Value>>> {
         let mut docs: BTreeMap<String, Arc<Value>> = BTreeMap::new();
+        let mut sources = Sources::new(req.definition.fail_fast());
         let limit: usize = 100;
 
         Provider::find_documents(&req)
@@ -226,14 +275,11 @@ impl Provider {
                             &document,
                             &count,
                         );
-                        if docs.contains_key(&key) {
-                            return future::err(Error::DuplicateId {
-                                key,
-                                path: path.to_path_buf(),
-                            });
+                        if let Err(e) = sources.add(&key, &path) {
+                            return future::err(e);
                         }
 
-                        docs.insert(key, Arc::new(document));
+                        docs.entry(key).or_insert(Arc::new(document));
                     }
                     Err(e) => return future::err(Error::from(e)),
                 }
@@ -245,6 +291,8 @@ impl Provider {
                 future::ok(())
             })
             .await?;
+
+        sources.finish()?;
         Ok(docs)
     }
 
@@ -272,3 +320,63 @@ impl Provider {
             .boxed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use config::indexer::DataProvider;
+
+    use super::*;
+
+    async fn load_json(
+        source: &PathBuf,
+        definition: &DataProvider,
+    ) -> Result<BTreeMap<String, Arc<Value>>> {
+        let config: Config = Default::default();
+        let options: RuntimeOptions = Default::default();
+        let collation: CollateInfo = Default::default();
+        Provider::load(LoadRequest {
+            source,
+            config: &config,
+            options: &options,
+            collation: &collation,
+            definition,
+            strategy: Strategy::FileName,
+            kind: definition.kind(),
+            provider: &SourceProvider::Files,
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn duplicate_ids() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = dir.path().to_path_buf();
+        let first = source.join("a").join("post.json");
+        let second = source.join("b").join("post.json");
+        for (path, title) in [
+            (&first, "One"),
+            (&second, "Two"),
+            (&source.join("c.json"), "Three"),
+        ] {
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, format!(r#"{{"title": "{}"}}"#, title))?;
+        }
+
+        let definition: DataProvider = toml::from_str("type = \"json\"")?;
+        match load_json(&source, &definition).await {
+            Err(Error::DuplicateIds(ids)) => {
+                assert_eq!(1, ids.len());
+                assert_eq!(Some(&vec![first, second]), ids.get("post"));
+            }
+            _ => panic!("expected duplicate ids error"),
+        }
+
+        let definition: DataProvider =
+            toml::from_str("type = \"json\"\nfail-fast = true")?;
+        assert!(matches!(
+            load_json(&source, &definition).await,
+            Err(Error::DuplicateId { .. })
+        ));
+        Ok(())
+    }
+}
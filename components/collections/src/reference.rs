@@ -0,0 +1,100 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use config::indexer::Reference;
+use utils::json_path;
+
+use crate::{CollectionDataBase, Error, Result};
+
+/// Convert a key value to a string for matching.
+fn key_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Map of keys to documents in the target collection.
+fn lookup<'a>(
+    reference: &Reference,
+    target: &'a CollectionDataBase,
+) -> HashMap<String, &'a Arc<Value>> {
+    target
+        .documents()
+        .iter()
+        .filter_map(|(id, doc)| {
+            let key = if let Some(ref key) = reference.key {
+                key_string(&json_path::find_path(key, doc))
+            } else {
+                Some(id.to_string())
+            };
+            key.map(|key| (key, doc))
+        })
+        .collect()
+}
+
+/// Resolve the references for the documents in a collection.
+///
+/// Referenced documents are embedded as they were loaded so
+/// references are not resolved recursively. Every dangling
+/// reference is collected and reported in a single error.
+pub(crate) fn resolve(
+    db_name: &str,
+    documents: &BTreeMap<String, Arc<Value>>,
+    references: &HashMap<String, Reference>,
+    collections: &BTreeMap<String, CollectionDataBase>,
+) -> Result<BTreeMap<String, Arc<Value>>> {
+    let mut targets = HashMap::new();
+    for (field, reference) in references.iter() {
+        let target = collections
+            .get(&reference.collection)
+            .ok_or_else(|| Error::NoCollection(reference.collection.clone()))?;
+        targets.insert(field, lookup(reference, target));
+    }
+
+    let mut dangling = Vec::new();
+    let mut resolved = BTreeMap::new();
+    for (id, doc) in documents.iter() {
+        let mut doc = Value::clone(doc);
+        if let Value::Object(ref mut map) = doc {
+            for (field, reference) in references.iter() {
+                let lookup = targets.get(field).unwrap();
+                let mut embed = |value: &Value| -> Value {
+                    if let Some(key) = key_string(value) {
+                        if let Some(target) = lookup.get(&key) {
+                            return Value::clone(target);
+                        }
+                        dangling.push(format!(
+                            "{}.{} -> {}/{}",
+                            id, field, reference.collection, key
+                        ));
+                    }
+                    value.clone()
+                };
+
+                let value = match map.get(field) {
+                    Some(Value::Array(keys)) => {
+                        Value::Array(keys.iter().map(&mut embed).collect())
+                    }
+                    Some(Value::Null) | None => continue,
+                    Some(key) => embed(key),
+                };
+                map.insert(field.to_string(), value);
+            }
+        }
+        resolved.insert(id.to_string(), Arc::new(doc));
+    }
+
+    if !dangling.is_empty() {
+        dangling.sort();
+        return Err(Error::DanglingReferences {
+            name: db_name.to_string(),
+            references: dangling,
+        });
+    }
+
+    Ok(resolved)
+}
@@ -1,10 +1,12 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock, RwLockWriteGuard};
 
 use serde_json::{json, Value};
 
 use collator::{create_page, CollateInfo};
 use config::{
+    indexer::{PageInfo, QueryList},
     Config, IndexQuery, Page, PageLink, PaginateInfo, RuntimeOptions,
 };
 
@@ -99,48 +101,63 @@ pub fn each(
 
         // Should have raw page data - note that we remove
         // the page as it is being used as an iterator
-        let page = info.remove_page(p).unwrap();
-        let page = page.write().unwrap();
+        let page_lock = info.remove_page(p).unwrap();
+        info.expanded.insert(Arc::clone(p), Arc::clone(&page_lock));
+        let page = page_lock.read().unwrap();
+        expand_each(config, options, info, map, cache, &page, &each)?;
+    }
 
-        let mut rewrite_index = options.settings.should_rewrite_index();
-        // Override with rewrite-index page level setting
-        if let Some(val) = page.rewrite_index {
-            rewrite_index = val;
-        }
+    Ok(())
+}
+
+fn expand_each(
+    config: &Config,
+    options: &RuntimeOptions,
+    info: &mut CollateInfo,
+    map: &CollectionsMap,
+    cache: &mut QueryCache,
+    page: &Page,
+    each: &[IndexQuery],
+) -> Result<()> {
+    let p = &page.file.as_ref().unwrap().source;
+    let mut rewrite_index = options.settings.should_rewrite_index();
+    // Override with rewrite-index page level setting
+    if let Some(val) = page.rewrite_index {
+        rewrite_index = val;
+    }
 
-        for each_query in each.iter() {
-            let idx = map.query_index(each_query, cache)?;
-
-            for doc in &idx {
-                let mut item_data = page.clone();
-
-                if let Some(ref id) = doc.id {
-                    // Assign the document to the page data
-                    item_data.extra.insert(
-                        each_query.get_parameter(),
-                        doc.to_value(each_query)?,
-                    );
-
-                    // Mock a source file to build a destination
-                    // respecting the clean URL setting
-                    let mut mock = p.parent().unwrap().to_path_buf();
-                    mock.push(&id);
-                    if let Some(ext) = p.extension() {
-                        mock.set_extension(ext);
-                    }
-
-                    create_page(
-                        config,
-                        options,
-                        info,
-                        mock,
-                        p.to_path_buf(),
-                        Arc::new(RwLock::new(item_data)),
-                        rewrite_index,
-                    )?;
-                } else {
-                    return Err(Error::CollectionDocumentNoId);
+    for each_query in each.iter() {
+        let idx = map.query_index(each_query, cache)?;
+
+        for doc in &idx {
+            let mut item_data = page.clone();
+
+            if let Some(ref id) = doc.id {
+                // Assign the document to the page data
+                item_data.extra.insert(
+                    each_query.get_parameter(),
+                    doc.to_value(each_query)?,
+                );
+
+                // Mock a source file to build a destination
+                // respecting the clean URL setting
+                let mut mock = p.parent().unwrap().to_path_buf();
+                mock.push(id);
+                if let Some(ext) = p.extension() {
+                    mock.set_extension(ext);
                 }
+
+                create_page(
+                    config,
+                    options,
+                    info,
+                    mock,
+                    p.to_path_buf(),
+                    Arc::new(RwLock::new(item_data)),
+                    rewrite_index,
+                )?;
+            } else {
+                return Err(Error::CollectionDocumentNoId);
             }
         }
     }
@@ -165,146 +182,339 @@ pub fn pages(
 
         // Should have raw page data - note that we remove
         // the page as it is being used as an iterator
-        let page = info.remove_page(p).unwrap();
-        let page = page.read().unwrap();
+        let page_lock = info.remove_page(p).unwrap();
+        info.expanded.insert(Arc::clone(p), Arc::clone(&page_lock));
+        let page = page_lock.read().unwrap();
+        expand_pages(config, options, info, map, cache, &page, &pages_query)?;
+    }
 
-        let mut rewrite_index = options.settings.should_rewrite_index();
-        // Override with rewrite-index page level setting
-        if let Some(val) = page.rewrite_index {
-            rewrite_index = val;
+    Ok(())
+}
+
+fn expand_pages(
+    config: &Config,
+    options: &RuntimeOptions,
+    info: &mut CollateInfo,
+    map: &CollectionsMap,
+    cache: &mut QueryCache,
+    page: &Page,
+    pages_query: &[IndexQuery],
+) -> Result<()> {
+    let mut rewrite_index = options.settings.should_rewrite_index();
+    // Override with rewrite-index page level setting
+    if let Some(val) = page.rewrite_index {
+        rewrite_index = val;
+    }
+
+    for page_query in pages_query.iter() {
+        let idx = map.query_index(page_query, cache)?;
+
+        let length = idx.len();
+        let page_req = page_query.page.as_ref().unwrap();
+
+        if !page_req.is_valid() {
+            return Err(Error::PageSizeTooSmall(page_req.size));
         }
 
-        for page_query in pages_query.iter() {
-            let idx = map.query_index(page_query, cache)?;
+        let file_source = page.file.as_ref().unwrap().source.clone();
+        for (mock, paginate) in paginate_pages(
+            options,
+            &file_source,
+            page_req,
+            length,
+            rewrite_index,
+        )? {
+            let items = &idx[paginate.first..=paginate.last];
+            let mut item_data = page.clone();
+            item_data.paginate = Some(paginate);
+            item_data
+                .extra
+                .insert(page_query.get_parameter(), json!(items));
+
+            create_page(
+                config,
+                options,
+                info,
+                mock,
+                file_source.clone(),
+                Arc::new(RwLock::new(item_data)),
+                rewrite_index,
+            )?;
+        }
+    }
 
-            let length = idx.len();
-            let page_req = page_query.page.as_ref().unwrap();
+    Ok(())
+}
 
-            if page_req.size < 2 {
-                return Err(Error::PageSizeTooSmall(page_req.size));
-            }
+/// Generate the synthetic pages for an expanded query page again
+/// so they reflect the current collections.
+pub fn refresh(
+    config: &Config,
+    options: &RuntimeOptions,
+    info: &mut CollateInfo,
+    map: &CollectionsMap,
+    cache: &mut QueryCache,
+    template: &PathBuf,
+) -> Result<()> {
+    let page_lock = match info.expanded.get(template) {
+        Some(page_lock) => Arc::clone(page_lock),
+        None => return Ok(()),
+    };
+    let queries: Vec<QueryList> = info
+        .queries
+        .iter()
+        .filter(|(_, p)| p.as_ref() == template)
+        .map(|(q, _)| q.clone())
+        .collect();
 
-            let mut total = idx.len() / page_req.size;
-            if idx.len() % page_req.size != 0 {
-                total += 1;
-            }
+    info.remove_synthetics(template);
 
-            let mut chunks = Vec::new();
-            let mut links = Vec::new();
-
-            for (current, items) in idx.chunks(page_req.size).enumerate() {
-                let first = current * page_req.size;
-                let last = if first + page_req.size >= length {
-                    length - 1
-                } else {
-                    first + page_req.size - 1
-                };
-
-                let size = last - first + 1;
-
-                let page_name = format!("{}", current + 1);
-
-                let item_data = page.clone();
-
-                let file_ctx = item_data.file.as_ref().unwrap();
-                let file_source = file_ctx.source.clone();
-
-                let parent = file_source.parent().unwrap().to_path_buf();
-                let mut stem = if let Some(stem) = file_source.file_stem() {
-                    if stem == config::INDEX_STEM {
-                        PathBuf::from("")
-                    } else {
-                        PathBuf::from(stem)
-                    }
-                } else {
-                    PathBuf::from("")
-                };
-
-                stem = if rewrite_index {
-                    stem.join(&page_name)
-                } else {
-                    stem.set_file_name(format!(
-                        "{}{}",
-                        stem.to_string_lossy().into_owned(),
-                        page_name
-                    ));
-                    stem
-                };
-
-                if let Some(ext) = file_source.extension() {
-                    stem.set_extension(ext);
-                }
+    let page = page_lock.read().unwrap();
+    for q in queries.iter() {
+        let each = q.to_each_vec();
+        if !each.is_empty() {
+            expand_each(config, options, info, map, cache, &page, &each)?;
+        }
+        let pages_query = q.to_page_vec();
+        if !pages_query.is_empty() {
+            expand_pages(
+                config,
+                options,
+                info,
+                map,
+                cache,
+                &page,
+                &pages_query,
+            )?;
+        }
+    }
 
-                let mock = parent.join(stem);
-
-                let paginate = PaginateInfo {
-                    total,
-                    current,
-                    length,
-                    first,
-                    last,
-                    size,
-                    name: (current + 1).to_string(),
-                    links: Vec::new(),
-                    prev: None,
-                    next: None,
-                    //span: Some(get_page_span(current, total, 2)),
-                };
-
-                let link = PageLink {
-                    index: current,
-                    name: page_name,
-                    href: options.absolute(&mock, Default::default())?,
-                };
-
-                links.push(link);
-
-                chunks.push((
-                    current,
-                    mock,
-                    file_source,
-                    item_data,
-                    paginate,
-                    items,
-                ));
-            }
+    Ok(())
+}
 
-            for (
-                current,
-                mock,
-                file_source,
-                mut item_data,
-                mut paginate,
-                items,
-            ) in chunks
-            {
-                paginate.links = links.clone();
-
-                if current > 0 {
-                    paginate.prev = Some(paginate.links[current - 1].clone());
-                }
+/// Source paths and pagination data for each page of a query
+/// result containing `length` items.
+fn paginate_pages(
+    options: &RuntimeOptions,
+    file_source: &Path,
+    page_req: &PageInfo,
+    length: usize,
+    rewrite_index: bool,
+) -> Result<Vec<(PathBuf, PaginateInfo)>> {
+    let bounds = page_req.bounds(length);
+    let total = bounds.len();
+
+    let parent = file_source.parent().unwrap().to_path_buf();
+    let stem = match file_source.file_stem() {
+        Some(stem) if stem != config::INDEX_STEM => PathBuf::from(stem),
+        _ => PathBuf::from(""),
+    };
+
+    let mut hrefs = HashSet::new();
+    let mut pages = Vec::new();
+    let mut links = Vec::new();
+    for (current, (first, last)) in bounds.into_iter().enumerate() {
+        let page_name = format!("{}", current + 1);
+
+        // Without a path pattern the page number is appended
+        // to the file name unless index files are rewritten
+        let mut file = if rewrite_index || page_req.path.is_some() {
+            stem.join(page_req.page_path(current + 1))
+        } else {
+            let mut file = stem.clone();
+            file.set_file_name(format!(
+                "{}{}",
+                stem.to_string_lossy(),
+                page_name
+            ));
+            file
+        };
+
+        if let Some(ext) = file_source.extension() {
+            file.set_extension(ext);
+        }
 
-                if current < (links.len() - 1) {
-                    paginate.next = Some(paginate.links[current + 1].clone());
-                }
+        let mock = parent.join(file);
+        let href = options.absolute(&mock, Default::default())?;
+        if !hrefs.insert(href.clone()) {
+            return Err(Error::from(collator::Error::DuplicatePermalink(href)));
+        }
 
-                item_data.paginate = Some(paginate);
-                item_data
-                    .extra
-                    .insert(page_query.get_parameter(), json!(items));
+        links.push(PageLink {
+            index: current,
+            name: page_name.clone(),
+            href,
+        });
+
+        let paginate = PaginateInfo {
+            total,
+            current,
+            length,
+            first,
+            last,
+            size: last - first + 1,
+            name: page_name,
+            links: Vec::new(),
+            prev: None,
+            next: None,
+            first_page: None,
+            last_page: None,
+        };
+        pages.push((mock, paginate));
+    }
 
-                create_page(
-                    config,
-                    options,
-                    info,
-                    mock,
-                    file_source,
-                    Arc::new(RwLock::new(item_data)),
-                    rewrite_index,
-                )?;
-            }
+    for (_, paginate) in pages.iter_mut() {
+        let current = paginate.current;
+        paginate.links = links.clone();
+        if current > 0 {
+            paginate.prev = Some(links[current - 1].clone());
+        }
+        if current < (links.len() - 1) {
+            paginate.next = Some(links[current + 1].clone());
         }
+        paginate.first_page = links.first().cloned();
+        paginate.last_page = links.last().cloned();
     }
 
-    Ok(())
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{Arc, RwLock};
+
+    use collator::CollateInfo;
+    use config::{
+        indexer::{PageInfo, QueryList},
+        test_util::TempProject,
+        IndexQuery, Page, RuntimeOptions,
+    };
+
+    use super::{each, paginate_pages, refresh};
+    use crate::{CollectionsMap, Error, Result};
+
+    fn options() -> RuntimeOptions {
+        let mut options: RuntimeOptions = Default::default();
+        options.source = PathBuf::from("/site");
+        options.settings.rewrite_index = Some(true);
+        options
+    }
+
+    #[test]
+    fn paginate_path() -> Result<()> {
+        let file = PathBuf::from("/site/blog/index.md");
+        let page_req = PageInfo {
+            size: 2,
+            path: Some("page/{n}".to_string()),
+        };
+
+        let pages = paginate_pages(&options(), &file, &page_req, 6, true)?;
+        assert_eq!(3, pages.len());
+
+        let hrefs: Vec<&str> =
+            pages[0].1.links.iter().map(|l| l.href.as_str()).collect();
+        assert_eq!(
+            vec!["/blog/page/1/", "/blog/page/2/", "/blog/page/3/"],
+            hrefs
+        );
+
+        let (mock, paginate) = &pages[1];
+        assert_eq!(&PathBuf::from("/site/blog/page/2.md"), mock);
+        assert_eq!(1, paginate.current);
+        assert_eq!(3, paginate.total);
+        assert_eq!((2, 3), (paginate.first, paginate.last));
+        assert_eq!("/blog/page/1/", paginate.prev.as_ref().unwrap().href);
+        assert_eq!("/blog/page/3/", paginate.next.as_ref().unwrap().href);
+        assert_eq!("/blog/page/1/", paginate.first_page.as_ref().unwrap().href);
+        assert_eq!("/blog/page/3/", paginate.last_page.as_ref().unwrap().href);
+
+        let (_, paginate) = &pages[0];
+        assert!(paginate.prev.is_none());
+        assert_eq!("/blog/page/2/", paginate.next.as_ref().unwrap().href);
+        Ok(())
+    }
+
+    #[test]
+    fn paginate_duplicate_path() {
+        let file = PathBuf::from("/site/blog/index.md");
+        let page_req = PageInfo {
+            size: 2,
+            path: Some("page".to_string()),
+        };
+        assert!(matches!(
+            paginate_pages(&options(), &file, &page_req, 6, true),
+            Err(Error::Collator(collator::Error::DuplicatePermalink(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn refresh_each_pages(
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            r#"lang = "en"
+host = "localhost"
+
+[db.load.tags]
+provider = "files"
+type = "json"
+from = "data"
+
+[db.load.tags.index.all]
+key = "*"
+"#,
+        )?;
+        project.write("site/data/alpha.json", r#"{"title": "Alpha"}"#)?;
+        let config = project.config()?;
+        let mut options = options();
+        options.source = project.source();
+        let mut info: CollateInfo = Default::default();
+
+        let mut collections: CollectionsMap = Default::default();
+        collections.load(&config, &options, &mut info).await?;
+
+        let key = Arc::new(options.source.join("tags").join("index.md"));
+        let dest = options.destination().build(&key)?;
+        let mut page: Page = Default::default();
+        page.seal(&config, &options, &key, &dest, None)?;
+        info.add_page(&key, dest, Arc::new(RwLock::new(page)));
+
+        let query = IndexQuery {
+            name: "tags".to_string(),
+            index: "all".to_string(),
+            each: Some(true),
+            ..Default::default()
+        };
+        info.queries.push((QueryList::One(query), Arc::clone(&key)));
+
+        each(
+            &config,
+            &options,
+            &mut info,
+            &collections,
+            &mut Default::default(),
+        )?;
+        assert_eq!(1, info.synthetic_templates().len());
+        assert!(info.resolve(&key).is_none());
+
+        project.write("site/data/beta.json", r#"{"title": "Beta"}"#)?;
+        let mut collections: CollectionsMap = Default::default();
+        collections.load(&config, &options, &mut info).await?;
+
+        refresh(
+            &config,
+            &options,
+            &mut info,
+            &collections,
+            &mut Default::default(),
+            &key,
+        )?;
+        let templates = info.synthetic_templates();
+        assert_eq!(2, templates.len());
+        assert!(templates.values().all(|template| template == &*key));
+        assert!(info.find_link("/tags/beta/").is_some());
+        Ok(())
+    }
 }
@@ -1,11 +1,17 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
+use bracket::helper::Helper;
+
 use collator::{self, Collation};
 use config::{plugin_cache::PluginCache, Config, RuntimeOptions};
 use locale::Locales;
 
-use crate::ParseData;
+use crate::{
+    output::Output, process::ProcessorMap, timing::Timings, ParseData,
+};
 
 #[derive(Debug, Default)]
 pub struct CompilerOutput {
@@ -15,6 +21,48 @@ pub struct CompilerOutput {
     pub files: Vec<Arc<PathBuf>>,
 }
 
+/// Template helpers supplied by library embedders.
+///
+/// Helpers are registered with the handlebars parser after all
+/// other helpers so a custom helper with the same name as a
+/// standard helper replaces it.
+///
+/// Helpers are invoked whilst the renderer holds a read lock on
+/// `BuildContext::collation`; a helper may acquire additional read
+/// locks but must never attempt to acquire a write lock otherwise
+/// the build will deadlock.
+#[derive(Clone, Default)]
+pub struct HelperMap {
+    helpers: HashMap<&'static str, Arc<dyn Helper + Send + Sync>>,
+}
+
+impl HelperMap {
+    /// Add a named helper.
+    pub fn insert<H>(&mut self, name: &'static str, helper: H)
+    where
+        H: Helper + Send + Sync + 'static,
+    {
+        self.helpers.insert(name, Arc::new(helper));
+    }
+
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&&'static str, &Arc<dyn Helper + Send + Sync>)>
+    {
+        self.helpers.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.helpers.is_empty()
+    }
+}
+
+impl fmt::Debug for HelperMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.helpers.keys()).finish()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct BuildContext {
     pub config: Arc<Config>,
@@ -22,4 +70,8 @@ pub struct BuildContext {
     pub plugins: Option<Arc<PluginCache>>,
     pub locales: Arc<Locales>,
     pub collation: Arc<RwLock<Collation>>,
+    pub output: Output,
+    pub helpers: HelperMap,
+    pub processors: ProcessorMap,
+    pub timings: Timings,
 }
@@ -0,0 +1,17 @@
+use std::sync::Arc;
+
+use bracket::helper::prelude::*;
+
+/// Delegate to a helper shared between parsers.
+pub struct Shared(pub Arc<dyn Helper + Send + Sync>);
+
+impl Helper for Shared {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        self.0.call(rc, ctx, template)
+    }
+}
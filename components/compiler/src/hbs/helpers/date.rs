@@ -1,8 +1,17 @@
 use bracket::helper::prelude::*;
 use chrono::{DateTime, Local, Utc};
 use serde_json::from_value;
+use unic_langid::LanguageIdentifier;
 
-pub struct DateFormat;
+use config::date::DateConfig;
+use locale::format::date_format;
+
+use super::number::language;
+
+pub struct DateFormat {
+    /// Fallback language for named formats.
+    pub fallback: LanguageIdentifier,
+}
 
 impl Helper for DateFormat {
     fn call<'render, 'call>(
@@ -18,11 +27,19 @@ impl Helper for DateFormat {
         //
         // "%a %b %e %Y"
 
-        // TODO: support format shortcuts for common formats
-        // TODO: support locale aware date/time formats
+        // Named formats such as "date-short" are resolved from
+        // the date configuration for the page language.
 
         let dt = ctx.try_get(0, &[Type::String])?;
-        let fmt = ctx.try_get(1, &[Type::String])?.as_str().unwrap();
+        let name = ctx.try_get(1, &[Type::String])?.as_str().unwrap();
+
+        let lang = language(rc, ctx, &self.fallback)?;
+        let date_config: DateConfig = rc
+            .evaluate("@root/date")?
+            .cloned()
+            .and_then(|v| from_value(v).ok())
+            .unwrap_or_default();
+        let fmt = date_format(name, &date_config, &lang, &self.fallback);
 
         let local = ctx.param("local");
         let date: DateTime<Utc> = from_value(dt.clone())?;
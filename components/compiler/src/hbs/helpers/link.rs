@@ -91,10 +91,19 @@ fn url<'render, 'call>(
                 "Type error for `link`, file is outside source!",
             ));
         }
+    } else if let Some(prefix) = context
+        .locales
+        .languages()
+        .url_prefix(&collation.get_lang())
+    {
+        format!("/{}/{}", prefix, input)
     } else {
         format!("/{}", input)
     };
 
+    // Respect the trailing slash policy for directory links
+    value = opts.settings.canonical_href(&value);
+
     if let Some(fragment) = fragment {
         value.push('#');
         value.push_str(fragment);
@@ -194,3 +203,28 @@ impl Helper for Link {
         Ok(None)
     }
 }
+
+/// Generate an absolute URL using a named base URL from the site settings.
+pub struct PrefixUrl {
+    pub context: Arc<BuildContext>,
+}
+
+impl Helper for PrefixUrl {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.assert_statement(template)?;
+        ctx.arity(2..2)?;
+        let name = ctx.try_get(0, &[Type::String])?.as_str().unwrap();
+        let path = ctx.try_get(1, &[Type::String])?.as_str().unwrap();
+        let value =
+            self.context.config.prefix_url(name, path).map_err(|e| {
+                HelperError::new(format!("Error for `url`, {}", e))
+            })?;
+        rc.write(&value)?;
+        Ok(None)
+    }
+}
@@ -0,0 +1,60 @@
+use bracket::helper::prelude::*;
+use serde_json::{Map, Value};
+
+use utils::entity;
+
+/// Render meta tags for the page including open graph properties.
+pub struct MetaTags;
+
+impl MetaTags {
+    fn write_map<'render>(
+        rc: &mut Render<'render>,
+        map: &Map<String, Value>,
+        attr: &str,
+        prefix: &str,
+    ) -> HelperResult<()> {
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        for key in keys {
+            if let Some(content) = map.get(key).and_then(|v| v.as_str()) {
+                rc.write(&format!(
+                    "<meta {}=\"{}{}\" content=\"{}\">\n",
+                    attr,
+                    prefix,
+                    entity::escape(key),
+                    entity::escape(content)
+                ))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Helper for MetaTags {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        _template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.arity(0..0)?;
+
+        if let Some(meta) = rc
+            .evaluate("@root/meta")?
+            .and_then(|v| v.as_object())
+            .cloned()
+        {
+            MetaTags::write_map(rc, &meta, "name", "")?;
+        }
+
+        if let Some(og) = rc
+            .evaluate("@root/open-graph")?
+            .and_then(|v| v.as_object())
+            .cloned()
+        {
+            MetaTags::write_map(rc, &og, "property", "og:")?;
+        }
+
+        Ok(None)
+    }
+}
@@ -1,5 +1,6 @@
 pub mod bytes;
 pub mod crumbtrail;
+pub mod custom;
 pub mod date;
 pub mod document;
 pub mod file_meta;
@@ -10,6 +11,8 @@ pub mod links;
 pub mod markdown;
 pub mod matcher;
 pub mod menu;
+pub mod meta;
+pub mod number;
 pub mod page;
 pub mod parent;
 pub mod powered;
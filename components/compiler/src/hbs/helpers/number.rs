@@ -0,0 +1,84 @@
+use bracket::helper::prelude::*;
+use serde_json::Value;
+use unic_langid::LanguageIdentifier;
+
+use locale::format::{format_currency, format_number, format_percent};
+
+/// Kinds of locale aware number formatting.
+#[derive(Clone, Copy)]
+pub enum NumberKind {
+    Number,
+    Percent,
+    Currency,
+}
+
+pub struct NumberFormat {
+    pub kind: NumberKind,
+    /// Fallback language for locales without formatting rules.
+    pub fallback: LanguageIdentifier,
+}
+
+/// Resolve the language from the `lang` hash parameter
+/// or the language for the current page.
+pub(crate) fn language<'render, 'call>(
+    rc: &mut Render<'render>,
+    ctx: &Context<'call>,
+    fallback: &LanguageIdentifier,
+) -> HelperResult<LanguageIdentifier> {
+    let lang = if let Some(lang) = ctx.param("lang") {
+        lang.as_str().map(|s| s.to_string()).ok_or_else(|| {
+            HelperError::new(format!(
+                "Type error for `{}` helper, hash parameter `lang` must be a string",
+                ctx.name()
+            ))
+        })?
+    } else {
+        rc.evaluate("@root/lang")?
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| fallback.to_string())
+    };
+    Ok(lang.parse().unwrap_or_else(|_| fallback.clone()))
+}
+
+impl Helper for NumberFormat {
+    fn call<'render, 'call>(
+        &self,
+        rc: &mut Render<'render>,
+        ctx: &Context<'call>,
+        template: Option<&'render Node<'render>>,
+    ) -> HelperValue {
+        ctx.assert_statement(template)?;
+        ctx.arity(1..1)?;
+
+        let value = ctx.try_get(0, &[Type::Number])?.as_f64().unwrap();
+
+        let decimals = if let Some(decimals) = ctx.param("decimals") {
+            decimals.as_u64().ok_or_else(|| {
+                HelperError::new(format!(
+                    "Type error for `{}` helper, hash parameter `decimals` must be a positive integer",
+                    ctx.name()
+                ))
+            })? as usize
+        } else {
+            0
+        };
+
+        let lang = language(rc, ctx, &self.fallback)?;
+        let result = match self.kind {
+            NumberKind::Number => {
+                format_number(value, decimals, &lang, &self.fallback)
+            }
+            NumberKind::Percent => {
+                format_percent(value, decimals, &lang, &self.fallback)
+            }
+            NumberKind::Currency => {
+                let symbol =
+                    ctx.param("symbol").and_then(|v| v.as_str()).unwrap_or("$");
+                format_currency(value, symbol, &lang, &self.fallback)
+            }
+        };
+
+        Ok(Some(Value::String(result)))
+    }
+}
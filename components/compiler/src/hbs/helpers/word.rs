@@ -1,7 +1,10 @@
 use bracket::helper::prelude::*;
 use serde_json::json;
 
-pub struct Count;
+pub struct Count {
+    /// Default average used for the reading time.
+    pub words_per_minute: usize,
+}
 
 impl Helper for Count {
     fn call<'render, 'call>(
@@ -25,7 +28,7 @@ impl Helper for Count {
 
         let avg = ctx
             .param("avg")
-            .or(Some(&json!(250)))
+            .or(Some(&json!(self.words_per_minute)))
             .and_then(|v| v.as_u64())
             .ok_or(HelperError::new(
                 "Type error for `words` helper, hash parameter `avg` must be a positive integer",
@@ -5,6 +5,7 @@ use log::debug;
 
 use bracket::Registry;
 use bracket_fluent::FluentHelper;
+use unic_langid::LanguageIdentifier;
 
 use locale::{Locales, LOCALES};
 
@@ -29,7 +30,8 @@ pub fn parser<'a>(
         .partials()?
         .templates()?
         .menus()?
-        .layouts()?;
+        .layouts()?
+        .custom();
 
     Ok(Box::new(builder.build()?))
 }
@@ -118,6 +120,8 @@ impl<'reg> ParserBuilder<'reg> {
             }),
         );
 
+        helpers.insert("meta-tags", Box::new(helpers::meta::MetaTags));
+
         helpers.insert(
             "menu",
             Box::new(helpers::menu::Menu {
@@ -149,6 +153,12 @@ impl<'reg> ParserBuilder<'reg> {
                 context: Arc::clone(&self.context),
             }),
         );
+        helpers.insert(
+            "url",
+            Box::new(helpers::link::PrefixUrl {
+                context: Arc::clone(&self.context),
+            }),
+        );
         helpers.insert(
             "markdown",
             Box::new(helpers::markdown::Markdown {
@@ -205,7 +215,35 @@ impl<'reg> ParserBuilder<'reg> {
         );
         helpers.insert("random", Box::new(helpers::random::Random));
         helpers.insert("slug", Box::new(helpers::slug::Slug));
-        helpers.insert("date", Box::new(helpers::date::DateFormat));
+        let fallback: LanguageIdentifier =
+            self.context.config.lang().parse()?;
+        helpers.insert(
+            "date",
+            Box::new(helpers::date::DateFormat {
+                fallback: fallback.clone(),
+            }),
+        );
+        helpers.insert(
+            "number",
+            Box::new(helpers::number::NumberFormat {
+                kind: helpers::number::NumberKind::Number,
+                fallback: fallback.clone(),
+            }),
+        );
+        helpers.insert(
+            "percent",
+            Box::new(helpers::number::NumberFormat {
+                kind: helpers::number::NumberKind::Percent,
+                fallback: fallback.clone(),
+            }),
+        );
+        helpers.insert(
+            "currency",
+            Box::new(helpers::number::NumberFormat {
+                kind: helpers::number::NumberKind::Currency,
+                fallback,
+            }),
+        );
         helpers.insert("bytes", Box::new(helpers::bytes::Bytes));
 
         helpers.insert(
@@ -232,7 +270,12 @@ impl<'reg> ParserBuilder<'reg> {
                 }
 
                 if html.use_words() {
-                    helpers.insert("words", Box::new(helpers::word::Count));
+                    helpers.insert(
+                        "words",
+                        Box::new(helpers::word::Count {
+                            words_per_minute: html.words_per_minute(),
+                        }),
+                    );
                 }
             }
         }
@@ -240,11 +283,27 @@ impl<'reg> ParserBuilder<'reg> {
         Ok(self)
     }
 
+    /// Register helpers supplied by library embedders.
+    pub fn custom(mut self) -> Self {
+        let helpers = self.registry.helpers_mut();
+        for (name, helper) in self.context.helpers.iter() {
+            helpers.insert(
+                name,
+                Box::new(helpers::custom::Shared(Arc::clone(helper))),
+            );
+        }
+        self
+    }
+
     /// Register templates in the source tree.
     pub fn templates(mut self) -> Result<Self> {
         let collation = self.context.collation.read().unwrap();
         for path in collation.templates().as_ref() {
-            self.registry.load(path.as_ref())?;
+            if TemplateEngine::from_path(path.as_ref()).as_ref()
+                == Some(&self.engine)
+            {
+                self.registry.load(path.as_ref())?;
+            }
         }
         drop(collation);
         Ok(self)
@@ -289,8 +348,12 @@ impl<'reg> ParserBuilder<'reg> {
     pub fn layouts(mut self) -> Result<Self> {
         let layouts = self.context.collation.read().unwrap().layouts().clone();
         for (name, path) in layouts.iter() {
-            debug!("Layout: {}", name);
-            self.registry.add(name.to_string(), path.as_ref())?;
+            if TemplateEngine::from_path(path.as_ref()).as_ref()
+                == Some(&self.engine)
+            {
+                debug!("Layout: {}", name);
+                self.registry.add(name.to_string(), path.as_ref())?;
+            }
         }
 
         Ok(self)
@@ -351,3 +414,53 @@ impl Parser for BracketParser<'_> {
         self.registry.load(file).map_err(Error::from)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bracket::helper::prelude::*;
+    use serde_json::json;
+
+    use config::engine::TemplateEngine;
+
+    use super::ParserBuilder;
+    use crate::{context::BuildContext, HelperMap, Result};
+
+    struct Shout;
+
+    impl Helper for Shout {
+        fn call<'render, 'call>(
+            &self,
+            rc: &mut Render<'render>,
+            ctx: &Context<'call>,
+            template: Option<&'render Node<'render>>,
+        ) -> HelperValue {
+            ctx.assert_statement(template)?;
+            ctx.arity(1..1)?;
+            let value = ctx.try_get(0, &[Type::String])?.as_str().unwrap();
+            rc.write(&value.to_uppercase())?;
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn custom_helper() -> Result<()> {
+        let mut helpers: HelperMap = Default::default();
+        helpers.insert("shout", Shout);
+        let context = Arc::new(BuildContext {
+            helpers,
+            ..Default::default()
+        });
+
+        let builder =
+            ParserBuilder::new(TemplateEngine::Handlebars, context).custom();
+        let result = builder.registry.once(
+            "page.hbs",
+            "{{shout name}}!",
+            &json!({"name": "hello"}),
+        )?;
+        assert_eq!("HELLO!", result);
+        Ok(())
+    }
+}
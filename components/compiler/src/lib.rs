@@ -28,9 +28,17 @@ pub enum Error {
     #[error("Resources not a directory {0}")]
     ResourceNotDirectory(PathBuf),
 
+    #[error("Failed to process resource {0}: {1}")]
+    ResourceProcess(PathBuf, String),
+
     #[error("Layout not found {0}")]
     LayoutNotFound(String),
 
+    #[error(
+        "Layout {0} uses the {2} template engine but the page {3} uses {1}"
+    )]
+    LayoutEngineMismatch(String, String, String, PathBuf),
+
     #[error("Multiple build errors")]
     Multi { errs: Vec<Error> },
 
@@ -45,12 +53,18 @@ pub enum Error {
     #[error(transparent)]
     TomlDeser(#[from] toml::de::Error),
 
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
     #[error(transparent)]
     Url(#[from] url::ParseError),
 
     #[error(transparent)]
     Bracket(#[from] bracket::Error),
 
+    #[error(transparent)]
+    Tera(#[from] ::tera::Error),
+
     /*
     #[error(transparent)]
     TemplateFile(#[from] handlebars::TemplateFileError),
@@ -71,6 +85,12 @@ pub enum Error {
     Collator(#[from] collator::Error),
     #[error(transparent)]
     Transform(#[from] transform::Error),
+    #[error(transparent)]
+    Minify(#[from] minify::Error),
+    #[error(transparent)]
+    Utils(#[from] utils::Error),
+    #[error(transparent)]
+    LanguageIdentifier(#[from] unic_langid::LanguageIdentifierError),
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -78,10 +98,18 @@ type Result<T> = std::result::Result<T, Error>;
 mod compile;
 mod context;
 mod hbs;
+pub mod output;
 mod page;
 pub mod parser;
+pub mod process;
 pub mod run;
+pub mod short_code;
+mod tera;
+pub mod timing;
 
 pub use compile::compile;
-pub use context::{BuildContext, CompilerOutput};
+pub use context::{BuildContext, CompilerOutput, HelperMap};
+pub use output::{DiskSink, MemorySink, Output, OutputSink};
+pub use process::{ProcessorMap, ResourceProcessor};
 pub use run::ParseData;
+pub use timing::Timings;
@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use config::memfs::DynamicMemoryFileSystem;
+
+use crate::Result;
+
+/// Destination for the files generated by the compiler.
+pub trait OutputSink: fmt::Debug + Send + Sync {
+    /// Write the content for a destination file.
+    fn write(&self, dest: &Path, content: &[u8]) -> Result<()>;
+
+    /// Copy a source file to a destination.
+    fn copy(&self, file: &Path, dest: &Path) -> Result<()> {
+        let content = utils::fs::read_bytes(file)?;
+        self.write(dest, &content)
+    }
+
+    /// Link a source file to a destination.
+    fn link(&self, file: &Path, dest: &Path) -> Result<()> {
+        self.copy(file, dest)
+    }
+
+    /// In-memory file system for sinks that do not write to disk.
+    fn memory(&self) -> Option<&DynamicMemoryFileSystem> {
+        None
+    }
+}
+
+/// Write generated files to the file system.
+#[derive(Debug, Default)]
+pub struct DiskSink;
+
+impl OutputSink for DiskSink {
+    fn write(&self, dest: &Path, content: &[u8]) -> Result<()> {
+        utils::fs::write_all(dest, content)?;
+        Ok(())
+    }
+
+    fn copy(&self, file: &Path, dest: &Path) -> Result<()> {
+        utils::fs::copy(file, dest)?;
+        Ok(())
+    }
+
+    fn link(&self, file: &Path, dest: &Path) -> Result<()> {
+        // NOTE: prevent errors trying to symlink when the target
+        // NOTE: already exists, otherwise when live reload is enabled
+        // NOTE: the compiler errors will cause the websocket build
+        // NOTE: complete message to never fire and the browser client
+        // NOTE: will hang whilst building :(
+        if dest.exists() {
+            return Ok(());
+        }
+
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let abs = file.canonicalize()?;
+        utils::symlink::soft_fallback(
+            abs.as_path(),
+            dest,
+            utils::symlink::Fallback::Copy,
+        )?;
+        Ok(())
+    }
+}
+
+/// Write generated files to an in-memory file system.
+///
+/// Destination paths are stored relative to the build target
+/// using a forward slash separator so the file system may be
+/// mounted by the web server.
+#[derive(Debug, Clone)]
+pub struct MemorySink {
+    target: PathBuf,
+    memfs: DynamicMemoryFileSystem,
+}
+
+impl MemorySink {
+    pub fn new(target: PathBuf, memfs: DynamicMemoryFileSystem) -> Self {
+        Self { target, memfs }
+    }
+
+    pub fn memfs(&self) -> &DynamicMemoryFileSystem {
+        &self.memfs
+    }
+
+    fn key(&self, dest: &Path) -> String {
+        let rel = dest.strip_prefix(&self.target).unwrap_or(dest);
+        utils::url::to_href_separator(rel)
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn write(&self, dest: &Path, content: &[u8]) -> Result<()> {
+        self.memfs.insert(self.key(dest), content.to_vec());
+        Ok(())
+    }
+
+    fn memory(&self) -> Option<&DynamicMemoryFileSystem> {
+        Some(&self.memfs)
+    }
+}
+
+/// Shared output sink for a build, defaults to writing to disk.
+///
+/// The destination of every file written through the sink is
+/// recorded so the outputs of a build can be determined.
+#[derive(Debug, Clone)]
+pub struct Output {
+    sink: Arc<dyn OutputSink>,
+    files: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl Output {
+    pub fn new(sink: Arc<dyn OutputSink>) -> Self {
+        Self {
+            sink,
+            files: Default::default(),
+        }
+    }
+
+    /// Write the content for a destination file.
+    pub fn write(&self, dest: &Path, content: &[u8]) -> Result<()> {
+        self.touch(dest);
+        self.sink.write(dest, content)
+    }
+
+    /// Copy a source file to a destination.
+    pub fn copy(&self, file: &Path, dest: &Path) -> Result<()> {
+        self.touch(dest);
+        self.sink.copy(file, dest)
+    }
+
+    /// Link a source file to a destination.
+    pub fn link(&self, file: &Path, dest: &Path) -> Result<()> {
+        self.touch(dest);
+        self.sink.link(file, dest)
+    }
+
+    /// Record a destination as an output without writing it,
+    /// used when an output of a previous build is up to date.
+    pub fn touch(&self, dest: &Path) {
+        self.files.lock().unwrap().insert(dest.to_path_buf());
+    }
+
+    /// Destinations recorded since the sink was created.
+    pub fn files(&self) -> HashSet<PathBuf> {
+        self.files.lock().unwrap().clone()
+    }
+
+    /// In-memory file system when files are not written to disk.
+    pub fn memory(&self) -> Option<&DynamicMemoryFileSystem> {
+        self.sink.memory()
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::new(Arc::new(DiskSink))
+    }
+}
@@ -54,6 +54,9 @@ pub struct CollatedPage<'config, 'locale> {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     members: &'config Option<HashMap<String, String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    urls: &'config Option<HashMap<String, String>>,
 }
 
 #[skip_serializing_none]
@@ -63,11 +66,12 @@ pub struct CollatedAuthors<'config> {
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     all: &'config HashMap<String, Author>,
     attributed: Option<Vec<&'config Author>>,
+    primary: Option<&'config Author>,
 }
 
 impl CollatedAuthors<'_> {
     pub fn is_empty(&self) -> bool {
-        self.all.is_empty()
+        self.all.is_empty() && self.attributed.is_none()
     }
 }
 
@@ -87,30 +91,25 @@ impl<'config, 'locale> CollatedPage<'config, 'locale> {
         };
 
         let attributed = if let Some(author_refs) = page.authors() {
-            let authors = config
-                .authors()
-                .iter()
-                .filter(|(k, _)| author_refs.contains(k))
-                .map(|(_, v)| v)
-                .collect::<Vec<_>>();
-
-            if authors.len() != author_refs.len() {
-                let missing = author_refs
-                    .iter()
-                    .filter(|r| !config.authors().contains_key(r.as_str()))
-                    .cloned()
-                    .collect::<Vec<String>>();
-                return Err(Error::NoAuthor(missing.join(", "), file.clone()));
-            }
-
+            let authors =
+                author_refs.resolve(config.authors()).map_err(|missing| {
+                    Error::NoAuthor(missing.join(", "), file.clone())
+                })?;
             Some(authors)
         } else {
             None
         };
 
+        // The first attributed author is the primary author
+        let primary = attributed
+            .as_ref()
+            .and_then(|authors| authors.first())
+            .cloned();
+
         let authors = CollatedAuthors {
             all: config.authors(),
             attributed,
+            primary,
         };
 
         let commit = if options.settings.include_commit() {
@@ -135,6 +134,7 @@ impl<'config, 'locale> CollatedPage<'config, 'locale> {
             version: config.version(),
             commit,
             members: config.member_urls(),
+            urls: config.urls(),
         })
     }
 
@@ -142,3 +142,38 @@ impl<'config, 'locale> CollatedPage<'config, 'locale> {
         self.page
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use config::{
+        test_util::TempProject, Page, ProfileName, ProfileSettings,
+        RuntimeOptions,
+    };
+    use locale::Locales;
+
+    use super::CollatedPage;
+    use crate::Result;
+
+    #[test]
+    fn url_prefixes() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[urls]\ncdn = \"https://cdn.example.com\"\n",
+        )?;
+        let config = project.config()?;
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+        let locales: Locales = Default::default();
+        let file = project.source().join("index.md");
+        let mut page: Page = Default::default();
+        page.href = Some("/".to_string());
+
+        let data =
+            CollatedPage::new(&file, &config, &options, &locales, &page, "en")?;
+        let value = serde_json::to_value(data).unwrap();
+        assert_eq!("https://cdn.example.com", value["urls"]["cdn"]);
+        Ok(())
+    }
+}
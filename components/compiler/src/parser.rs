@@ -1,10 +1,14 @@
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
 use config::engine::TemplateEngine;
+use config::Page;
 use locale::Locales;
 
-use crate::{context::BuildContext, hbs, page::CollatedPage, Result};
+use crate::{
+    context::BuildContext, hbs, page::CollatedPage, tera, Error, Result,
+};
 
 /// The trait all template engines must implement.
 pub trait Parser {
@@ -27,13 +31,287 @@ pub trait Parser {
     fn load(&mut self, file: &PathBuf) -> Result<()>;
 }
 
-/// Generate a parser for the given template engine.
-pub fn build<'a>(
+/// Generate a parser for a single template engine.
+fn engine_parser<'a>(
     engine: TemplateEngine,
     context: Arc<BuildContext>,
     locales: Arc<Locales>,
-) -> Result<Box<impl Parser + Send + Sync + 'a>> {
+) -> Result<Box<dyn Parser + Send + Sync + 'a>> {
     match engine {
-        TemplateEngine::Handlebars => hbs::parser(engine, context, locales),
+        TemplateEngine::Handlebars => {
+            let parser: Box<dyn Parser + Send + Sync + 'a> =
+                hbs::parser(engine, context, locales)?;
+            Ok(parser)
+        }
+        TemplateEngine::Tera => tera::parser(engine, context),
+    }
+}
+
+/// Generate a parser for the given template engine.
+///
+/// Pages are rendered using the given engine unless the page
+/// declares an `engine` in the front matter or uses a layout
+/// whose file extension belongs to another template engine.
+///
+/// Only the parser for the given engine is built, parsers for
+/// other engines are built when first used.
+pub fn build<'a>(
+    default: TemplateEngine,
+    context: Arc<BuildContext>,
+    locales: Arc<Locales>,
+) -> Result<Box<dyn Parser + Send + Sync + 'a>> {
+    Ok(Box::new(EngineParser::new(default, context, locales)?))
+}
+
+/// Delegate to the parser for the template engine selected by a page.
+struct EngineParser<'a> {
+    default: TemplateEngine,
+    context: Arc<BuildContext>,
+    locales: Arc<Locales>,
+    parsers:
+        RwLock<HashMap<TemplateEngine, Box<dyn Parser + Send + Sync + 'a>>>,
+    /// Template engine for each named template.
+    templates: HashMap<String, TemplateEngine>,
+}
+
+impl<'a> EngineParser<'a> {
+    fn new(
+        default: TemplateEngine,
+        context: Arc<BuildContext>,
+        locales: Arc<Locales>,
+    ) -> Result<Self> {
+        let templates = context
+            .collation
+            .read()
+            .unwrap()
+            .layouts()
+            .iter()
+            .filter_map(|(name, path)| {
+                TemplateEngine::from_path(path.as_ref())
+                    .map(|engine| (name.to_string(), engine))
+            })
+            .collect();
+
+        let parser = Self {
+            default: default.clone(),
+            context,
+            locales,
+            parsers: RwLock::new(HashMap::new()),
+            templates,
+        };
+        parser.ensure(&default)?;
+        Ok(parser)
+    }
+
+    /// Build the parser for an engine when it does not exist.
+    fn ensure(&self, engine: &TemplateEngine) -> Result<()> {
+        if self.parsers.read().unwrap().contains_key(engine) {
+            return Ok(());
+        }
+        let parser = engine_parser(
+            engine.clone(),
+            Arc::clone(&self.context),
+            Arc::clone(&self.locales),
+        )?;
+        self.parsers
+            .write()
+            .unwrap()
+            .entry(engine.clone())
+            .or_insert(parser);
+        Ok(())
+    }
+
+    /// Determine the template engine for a page.
+    ///
+    /// An engine declared by the page must match the engine
+    /// for the page layout.
+    fn resolve(&self, file: &Path, page: &Page) -> Result<TemplateEngine> {
+        let declared: Option<TemplateEngine> = match page.engine {
+            Some(ref name) => Some(name.parse()?),
+            None => None,
+        };
+
+        let layout = if !page.is_standalone() {
+            page.layout.as_ref().and_then(|name| {
+                self.templates.get(name).map(|engine| (name, engine))
+            })
+        } else {
+            None
+        };
+
+        match (declared, layout) {
+            (Some(declared), Some((name, engine))) => {
+                if &declared != engine {
+                    return Err(Error::LayoutEngineMismatch(
+                        name.to_string(),
+                        declared.to_string(),
+                        engine.to_string(),
+                        file.to_path_buf(),
+                    ));
+                }
+                Ok(declared)
+            }
+            (Some(declared), None) => Ok(declared),
+            (None, Some((_, engine))) => Ok(engine.clone()),
+            (None, None) => Ok(self.default.clone()),
+        }
+    }
+}
+
+impl Parser for EngineParser<'_> {
+    fn parse(&self, file: &PathBuf, data: CollatedPage) -> Result<String> {
+        let engine = self.resolve(file, data.page())?;
+        self.ensure(&engine)?;
+        self.parsers
+            .read()
+            .unwrap()
+            .get(&engine)
+            .unwrap()
+            .parse(file, data)
+    }
+
+    fn add(&mut self, name: String, file: &PathBuf) -> Result<()> {
+        let engine =
+            TemplateEngine::from_path(file).unwrap_or(self.default.clone());
+        self.templates.insert(name.clone(), engine.clone());
+        self.ensure(&engine)?;
+        self.parsers
+            .get_mut()
+            .unwrap()
+            .get_mut(&engine)
+            .unwrap()
+            .add(name, file)
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.templates.remove(name);
+        for parser in self.parsers.get_mut().unwrap().values_mut() {
+            parser.remove(name);
+        }
+    }
+
+    fn load(&mut self, file: &PathBuf) -> Result<()> {
+        let engine =
+            TemplateEngine::from_path(file).unwrap_or(self.default.clone());
+        self.ensure(&engine)?;
+        self.parsers
+            .get_mut()
+            .unwrap()
+            .get_mut(&engine)
+            .unwrap()
+            .load(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use config::{
+        engine::TemplateEngine, test_util::TempProject, Page, RuntimeOptions,
+    };
+
+    use super::{build, EngineParser, Parser};
+    use crate::{context::BuildContext, page::CollatedPage, Error, Result};
+
+    fn page(engine: &str, layout: Option<&str>) -> Page {
+        let mut page: Page = Default::default();
+        page.title = Some("Hello".to_string());
+        page.href = Some("/index.html".to_string());
+        page.engine = Some(engine.to_string());
+        page.layout = layout.map(|s| s.to_string());
+        page
+    }
+
+    #[test]
+    fn declared_engines() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write("site.toml", "lang = \"en\"\nhost = \"localhost\"\n")?;
+        let hbs = project.write("site/hbs.html", "<p>{{title}}</p>")?;
+        let tera =
+            project.write("site/tera.html", "<p>{{ title | upper }}</p>")?;
+        let layout = project.write("site/layouts/main.tera", "{{ title }}")?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        let context = Arc::new(BuildContext {
+            config: Arc::new(project.config()?),
+            options: Arc::new(options),
+            ..Default::default()
+        });
+
+        let mut parser = build(
+            TemplateEngine::Handlebars,
+            Arc::clone(&context),
+            Arc::clone(&context.locales),
+        )?;
+        parser.add("main".to_string(), &layout)?;
+
+        let render = |file: &PathBuf, page: &Page| -> Result<String> {
+            let data = CollatedPage::new(
+                file,
+                &context.config,
+                &context.options,
+                &context.locales,
+                page,
+                "en",
+            )?;
+            parser.parse(file, data)
+        };
+
+        let result = render(&hbs, &page("handlebars", None))?;
+        assert_eq!("<p>Hello</p>", result.trim());
+
+        let result = render(&tera, &page("tera", None))?;
+        assert_eq!("<p>HELLO</p>", result.trim());
+
+        let result = render(&hbs, &page("handlebars", Some("main")));
+        assert!(matches!(result, Err(Error::LayoutEngineMismatch(..))));
+
+        let result = render(&hbs, &page("liquid", None));
+        assert!(matches!(
+            result,
+            Err(Error::Config(config::Error::UnsupportedTemplateEngine(_)))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn build_engines_on_demand() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write("site.toml", "lang = \"en\"\nhost = \"localhost\"\n")?;
+        let tera = project.write("site/tera.html", "<p>{{ title }}</p>")?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        let context = Arc::new(BuildContext {
+            config: Arc::new(project.config()?),
+            options: Arc::new(options),
+            ..Default::default()
+        });
+
+        let parser = EngineParser::new(
+            TemplateEngine::Handlebars,
+            Arc::clone(&context),
+            Arc::clone(&context.locales),
+        )?;
+        let engines = |parser: &EngineParser| -> usize {
+            parser.parsers.read().unwrap().len()
+        };
+        assert_eq!(1, engines(&parser));
+
+        let page = page("tera", None);
+        let data = CollatedPage::new(
+            &tera,
+            &context.config,
+            &context.options,
+            &context.locales,
+            &page,
+            "en",
+        )?;
+        assert_eq!("<p>Hello</p>", parser.parse(&tera, data)?.trim());
+        assert_eq!(2, engines(&parser));
+        Ok(())
     }
 }
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+
+use config::profile::ProcessCommand;
+
+use crate::{Error, Result};
+
+/// Transform the content of a resource before it is written
+/// to the build target.
+pub trait ResourceProcessor: Send + Sync {
+    /// Extension for the output file, when `None` the extension
+    /// of the source file is used.
+    fn extension(&self) -> Option<&str> {
+        None
+    }
+
+    /// Process the content of a resource file.
+    fn process(&self, file: &Path, content: Vec<u8>) -> Result<Vec<u8>>;
+}
+
+/// Resource processors keyed by file extension.
+///
+/// Resources without a processor are copied unchanged.
+#[derive(Clone, Default)]
+pub struct ProcessorMap {
+    processors: HashMap<String, Arc<dyn ResourceProcessor>>,
+}
+
+impl ProcessorMap {
+    /// Add a processor for an extension, the extension should
+    /// not include a leading period.
+    pub fn insert<P>(&mut self, extension: &str, processor: P)
+    where
+        P: ResourceProcessor + 'static,
+    {
+        self.processors
+            .insert(extension.to_string(), Arc::new(processor));
+    }
+
+    /// Find the processor for a file.
+    pub fn find(&self, file: &Path) -> Option<&Arc<dyn ResourceProcessor>> {
+        let ext = file.extension()?.to_str()?;
+        self.processors.get(ext)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+}
+
+impl fmt::Debug for ProcessorMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.processors.keys()).finish()
+    }
+}
+
+/// Process resources using an external program.
+#[derive(Debug)]
+pub struct CommandProcessor {
+    command: ProcessCommand,
+    cwd: PathBuf,
+}
+
+impl CommandProcessor {
+    pub fn new(command: ProcessCommand, cwd: PathBuf) -> Self {
+        Self { command, cwd }
+    }
+}
+
+impl ResourceProcessor for CommandProcessor {
+    fn extension(&self) -> Option<&str> {
+        self.command.extension.as_deref()
+    }
+
+    fn process(&self, file: &Path, content: Vec<u8>) -> Result<Vec<u8>> {
+        let failed = |message: String| {
+            Error::ResourceProcess(file.to_path_buf(), message)
+        };
+
+        let mut child = Command::new(&self.command.command)
+            .args(&self.command.args)
+            .current_dir(&self.cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| failed(format!("{} ({})", self.command.command, e)))?;
+
+        // Write on another thread so a large output can not block
+        // the program before it has read all the input
+        let mut stdin = child.stdin.take().unwrap();
+        let writer = thread::spawn(move || stdin.write_all(&content));
+
+        let output = child.wait_with_output()?;
+        writer.join().unwrap()?;
+
+        if !output.status.success() {
+            return Err(failed(format!(
+                "{} exited with {}: {}",
+                self.command.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use collator::{ResourceKind, ResourceOperation, ResourceTarget};
+    use config::{
+        memfs::{DynamicMemoryFileSystem, MemoryFileSystem},
+        test_util::TempProject,
+    };
+
+    use super::{ProcessorMap, ResourceProcessor};
+    use crate::{
+        context::BuildContext, output::MemorySink, run, Output, Result,
+    };
+
+    struct Shout;
+
+    impl ResourceProcessor for Shout {
+        fn extension(&self) -> Option<&str> {
+            Some("txt")
+        }
+
+        fn process(&self, _file: &Path, content: Vec<u8>) -> Result<Vec<u8>> {
+            Ok(content.to_ascii_uppercase())
+        }
+    }
+
+    #[tokio::test]
+    async fn process_resource() -> Result<()> {
+        let project = TempProject::new()?;
+        let shout = project.write("site/hello.shout", "hello")?;
+        let plain = project.write("site/plain.txt", "plain")?;
+
+        let mut processors: ProcessorMap = Default::default();
+        processors.insert("shout", Shout);
+
+        let memfs = DynamicMemoryFileSystem::default();
+        let sink = MemorySink::new(PathBuf::new(), memfs.clone());
+        let context = BuildContext {
+            processors,
+            output: Output::new(Arc::new(sink)),
+            ..Default::default()
+        };
+
+        // Processed files are copied even when they would be linked
+        for (file, name, operation) in &[
+            (&shout, "hello.shout", ResourceOperation::Link),
+            (&plain, "plain.txt", ResourceOperation::Copy),
+        ] {
+            let target = ResourceTarget {
+                destination: PathBuf::from(name),
+                operation: operation.clone(),
+                kind: ResourceKind::File,
+            };
+            run::resource(&context, file, &target).await?;
+        }
+
+        let content = |name: &str| memfs.get(name).map(|c| c.into_owned());
+        assert_eq!(Some(b"HELLO".to_vec()), content("hello.txt"));
+        assert!(!memfs.contains("hello.shout"));
+        assert_eq!(Some(b"plain".to_vec()), content("plain.txt"));
+        Ok(())
+    }
+}
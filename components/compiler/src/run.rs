@@ -1,9 +1,12 @@
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 
-use log::info;
+use log::{info, warn};
+use url::Url;
 
-use collator::{Resource, ResourceOperation, ResourceTarget};
+use collator::{Collation, Resource, ResourceOperation, ResourceTarget};
 use config::{
+    markdown,
     profile::{ProfileName, Profiles},
     Config, Page,
 };
@@ -12,7 +15,8 @@ use config::transform::HtmlTransformFlags;
 use transform::text::TextExtraction;
 
 use crate::{
-    context::BuildContext, page::CollatedPage, parser::Parser, Error, Result,
+    context::BuildContext, page::CollatedPage, parser::Parser, timing, Error,
+    Result,
 };
 
 #[derive(Debug)]
@@ -86,10 +90,17 @@ pub async fn resource(
     match target.operation {
         ResourceOperation::Noop => Ok(()),
         ResourceOperation::Copy => {
-            copy(file, &target.get_output(collation.get_path().as_ref())).await
+            let dest = target.get_output(collation.get_path().as_ref());
+            copy(context, file, &dest).await
         }
         ResourceOperation::Link => {
-            link(file, &target.get_output(collation.get_path().as_ref())).await
+            let dest = target.get_output(collation.get_path().as_ref());
+            // Processed files must be copied
+            if context.processors.find(file).is_some() {
+                copy(context, file, &dest).await
+            } else {
+                link(context, file, &dest).await
+            }
         }
         _ => Err(Error::InvalidResourceOperation(file.to_path_buf())),
     }
@@ -105,7 +116,7 @@ fn is_html_extension<P: AsRef<Path>>(dest: P) -> bool {
 fn should_minify_html<P: AsRef<Path>>(
     dest: P,
     tag: &ProfileName,
-    release: bool,
+    minify: bool,
     config: &Config,
 ) -> bool {
     let html_extension = is_html_extension(dest.as_ref());
@@ -116,33 +127,86 @@ fn should_minify_html<P: AsRef<Path>>(
             }
         }
     }
-    release && html_extension
+    minify && html_extension
+}
+
+/// Sidecar file for the template data of a page, for example
+/// `index.html` dumps the data to `index.html.json`.
+pub fn context_file<P: AsRef<Path>>(dest: P) -> PathBuf {
+    let mut name = dest.as_ref().as_os_str().to_os_string();
+    name.push(".json");
+    PathBuf::from(name)
 }
 
-async fn copy<'a>(file: &PathBuf, dest: &PathBuf) -> Result<()> {
+async fn copy<'a>(
+    context: &BuildContext,
+    file: &Path,
+    dest: &Path,
+) -> Result<()> {
+    if let Some(processor) = context.processors.find(file) {
+        let dest = match processor.extension() {
+            Some(ext) => dest.with_extension(ext),
+            None => dest.to_path_buf(),
+        };
+        info!("{} -> {}", file.display(), dest.display());
+        let content = processor.process(file, utils::fs::read_bytes(file)?)?;
+        let _timer = context.timings.scope(timing::WRITE);
+        return context.output.write(&dest, &content);
+    }
+
     info!("{} -> {}", file.display(), dest.display());
-    utils::fs::copy(file, dest)?;
-    Ok(())
+    let _timer = context.timings.scope(timing::WRITE);
+    context.output.copy(file, dest)
 }
 
-async fn link<'a>(file: &PathBuf, dest: &PathBuf) -> Result<()> {
+async fn link<'a>(
+    context: &BuildContext,
+    file: &Path,
+    dest: &Path,
+) -> Result<()> {
     info!("{} -> {}", file.display(), dest.display());
+    let _timer = context.timings.scope(timing::WRITE);
+    context.output.link(file, dest)
+}
 
-    // NOTE: prevent errors trying to symlink when the target
-    // NOTE: already exists, otherwise when live reload is enabled
-    // NOTE: the compiler errors will cause the websocket build
-    // NOTE: complete message to never fire and the browser client
-    // NOTE: will hang whilst building :(
-    if dest.exists() {
-        return Ok(());
-    }
+/// Render the content of a page for a feed item without a layout,
+/// links are resolved and made absolute using the item URL.
+fn feed_content(
+    ctx: &BuildContext,
+    parser: &(impl Parser + Send + Sync + ?Sized),
+    collation: &Collation,
+    file: &PathBuf,
+    url: &str,
+) -> Result<Option<String>> {
+    let mut page = match collation.resolve(file) {
+        Some(page) => page.read().unwrap().clone(),
+        None => return Ok(None),
+    };
+    page.standalone = Some(true);
 
-    if let Some(parent) = dest.parent() {
-        std::fs::create_dir_all(parent)?;
+    let lang = collation.get_lang();
+    let page_data = CollatedPage::new(
+        file,
+        &ctx.config,
+        &ctx.options,
+        &ctx.locales,
+        &page,
+        lang.as_ref(),
+    )?;
+
+    let mut content = parser.parse(file, page_data)?;
+    if ctx.options.is_markdown_file(file) {
+        content = markdown::render(&mut Cow::from(content), &ctx.config);
     }
-    let abs = file.canonicalize()?;
-    utils::symlink::soft(&abs, dest)?;
-    Ok(())
+
+    let base = Url::parse(url)?;
+    content = transform::html::rewrite_links(&content, |href| {
+        ctx.options.relative_link(file, href)
+    })?;
+    content = transform::html::rewrite_urls(&content, |value| {
+        base.join(value).ok().map(|url| url.to_string())
+    })?;
+    Ok(Some(content))
 }
 
 pub async fn parse(
@@ -157,12 +221,32 @@ pub async fn parse(
     let minify_html = should_minify_html(
         &dest,
         &ctx.options.settings.name,
-        ctx.options.settings.is_release(),
+        ctx.options.settings.should_minify(),
         &ctx.config,
     );
 
     let collation = &*ctx.collation.read().unwrap();
     let lang = collation.get_lang();
+
+    // Feed items with the full content render the item pages
+    let feed_data;
+    let data = if let Some(ref sources) = data.feed_sources {
+        let mut page = data.clone();
+        if let Some(ref mut feed) = page.feed {
+            for item in feed.items.iter_mut() {
+                if let Some(source) = sources.get(&item.id) {
+                    item.content_html = feed_content(
+                        ctx, &**parser, collation, source, &item.id,
+                    )?;
+                }
+            }
+        }
+        feed_data = page;
+        &feed_data
+    } else {
+        data
+    };
+
     let mut page_data = CollatedPage::new(
         file,
         &ctx.config,
@@ -174,12 +258,29 @@ pub async fn parse(
 
     page_data.menus = collation.menu_page_href();
 
-    let mut s = if minify_html {
-        minify::html(parser.parse(file, page_data)?)
-    } else {
+    if ctx.options.settings.should_dump_context() {
+        let _timer = ctx.timings.scope(timing::WRITE);
+        let context = serde_json::to_vec_pretty(&page_data)?;
+        ctx.output.write(&context_file(dest), &context)?;
+    }
+
+    let mut s = {
+        let _timer = ctx.timings.scope(timing::RENDER);
         parser.parse(file, page_data)?
     };
 
+    if minify_html {
+        let _timer = ctx.timings.scope(timing::MINIFY);
+        let html = ctx.config.minify.as_ref().and_then(|m| m.html.as_ref());
+        let options = minify::HtmlOptions {
+            collapse_whitespace: html
+                .map(|h| h.collapse_whitespace())
+                .unwrap_or(false),
+            strip_comments: html.map(|h| h.strip_comments()).unwrap_or(false),
+        };
+        s = minify::build(s, &options)?;
+    }
+
     let mut res = ParseData::new(data.file.as_ref().unwrap().source.clone());
 
     if is_html_extension(&dest) {
@@ -218,16 +319,135 @@ pub async fn parse(
                 None
             };
 
+            if let Some(ref search) = ctx.config.search {
+                cache.ignore = search.ignore_selectors();
+            }
+
             if html_flags.is_active() || cache.is_active() {
+                let _timer = ctx.timings.scope(timing::TRANSFORM);
+                // Must resolve links before any base path is applied
+                if html_flags.use_relative_links() {
+                    s = transform::html::rewrite_links(&s, |href| {
+                        let url = ctx.options.relative_link(file, href);
+                        if url.is_none() {
+                            let message = format!(
+                                "Unresolved link {} in {}",
+                                href,
+                                file.display()
+                            );
+                            warn!("{}", message);
+                            ctx.options.settings.diagnostics.warn(
+                                config::diagnostics::UNRESOLVED_LINK,
+                                message,
+                                file,
+                            );
+                        }
+                        url
+                    })?;
+                }
                 s = transform::html::apply(&s, &html_flags, &mut cache)?;
+                for id in cache.duplicates.drain(..) {
+                    let message = format!(
+                        "Duplicate heading id {} in {}",
+                        id,
+                        file.display()
+                    );
+                    warn!("{}", message);
+                    ctx.options.settings.diagnostics.warn(
+                        config::diagnostics::DUPLICATE_ID,
+                        message,
+                        file,
+                    );
+                }
                 // Assign the extracted text so we can use it later
                 // to build the search index
                 res.extract = cache.text.clone();
             }
         }
+
+        if ctx.options.settings.should_inline_critical_style() {
+            if let Some(critical) = ctx.config.critical_style() {
+                let _timer = ctx.timings.scope(timing::TRANSFORM);
+                s = transform::html::inline_critical_style(
+                    &s,
+                    critical.content(),
+                    critical.defer(),
+                )?;
+            }
+        }
+    }
+
+    {
+        let _timer = ctx.timings.scope(timing::WRITE);
+        ctx.output.write(dest, s.as_bytes())?;
     }
 
-    utils::fs::write_string(&dest, &s)?;
+    ctx.options.settings.events.page_rendered(file, dest);
 
     Ok(Some(res))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{Arc, RwLock};
+
+    use collator::{CollateInfo, Collation};
+    use config::{
+        engine::TemplateEngine, test_util::TempProject, Page, RuntimeOptions,
+    };
+
+    use super::feed_content;
+    use crate::{context::BuildContext, tera::parser, Result};
+
+    #[test]
+    fn render_feed_content() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write("site.toml", "lang = \"en\"\nhost = \"localhost\"\n")?;
+        let post = project.write(
+            "site/blog/post.md",
+            "+++\ntitle = \"Post\"\n+++\n# {{ title }}\n\n[Other](other.md) ![Image](image.png)\n",
+        )?;
+        project.write("site/blog/other.md", "# Other\n")?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings.types = Some(Default::default());
+
+        let mut page: Page = Default::default();
+        page.title = Some("Post".to_string());
+        page.href = Some("/blog/post/".to_string());
+        let mut info = CollateInfo::new("en".to_string(), PathBuf::new());
+        info.add_page(
+            &Arc::new(post.clone()),
+            PathBuf::from("blog/post/index.html"),
+            Arc::new(RwLock::new(page)),
+        );
+        let info = Arc::new(RwLock::new(info));
+        let collation = Collation {
+            fallback: Arc::clone(&info),
+            locale: info,
+        };
+
+        let context = Arc::new(BuildContext {
+            config: Arc::new(project.config()?),
+            options: Arc::new(options),
+            ..Default::default()
+        });
+        let parser = parser(TemplateEngine::Tera, Arc::clone(&context))?;
+
+        let content = feed_content(
+            &context,
+            &*parser,
+            &collation,
+            &post,
+            "https://example.com/blog/post/",
+        )?
+        .unwrap();
+        assert!(content.contains("<h1>Post</h1>"));
+        assert!(content.contains(r#"href="https://example.com/blog/other/""#));
+        assert!(content
+            .contains(r#"src="https://example.com/blog/post/image.png""#));
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// File used to mark the version that created a cache.
+const VERSION_FILE: &str = ".version";
+
+/// Get the short code cache directory for a version, creating it
+/// when necessary.
+pub fn cache_dir(version: &str) -> Result<PathBuf> {
+    let dir = dirs::short_code_cache_dir()?;
+    ensure(&dir, version)?;
+    Ok(dir)
+}
+
+/// Remove all the entries in the short code cache.
+pub fn clear() -> Result<()> {
+    let dir = dirs::short_code_cache_dir()?;
+    if dir.exists() {
+        if !dir.is_dir() {
+            return Err(Error::NoShortCodeCache(dir));
+        }
+        fs::remove_dir_all(&dir)?;
+    }
+    Ok(())
+}
+
+/// Ensure a short code cache directory exists and is valid.
+///
+/// A missing directory is created and a cache written by another
+/// version is considered stale and emptied. It is an error if
+/// the path exists but is not a directory.
+pub(crate) fn ensure(dir: &Path, version: &str) -> Result<()> {
+    if dir.exists() && !dir.is_dir() {
+        return Err(Error::NoShortCodeCache(dir.to_path_buf()));
+    }
+
+    let marker = dir.join(VERSION_FILE);
+    let stale = dir.exists()
+        && fs::read_to_string(&marker)
+            .map(|v| v.trim() != version)
+            .unwrap_or(true);
+    if stale {
+        fs::remove_dir_all(dir)?;
+    }
+
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+        fs::write(&marker, version)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ensure;
+    use crate::{Error, Result};
+    use config::test_util::TempProject;
+
+    #[test]
+    fn create_missing_cache() -> Result<()> {
+        let project = TempProject::new()?;
+        let dir = project.root().join("short-codes");
+        ensure(&dir, "0.1.0")?;
+        assert!(dir.is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn cache_is_file() -> Result<()> {
+        let project = TempProject::new()?;
+        let file = project.write("short-codes", "")?;
+        match ensure(&file, "0.1.0") {
+            Err(Error::NoShortCodeCache(path)) => assert_eq!(file, path),
+            _ => panic!("expected short code cache error"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::SystemTime;
+
+use log::debug;
+
+use ::tera::{Context, Tera};
+
+use config::{engine::TemplateEngine, markdown as md};
+
+use crate::{
+    context::BuildContext, page::CollatedPage, parser::Parser, Error, Result,
+};
+
+/// Generate a parser using the tera template engine.
+///
+/// Helpers available to handlebars templates are not registered.
+pub fn parser<'a>(
+    engine: TemplateEngine,
+    context: Arc<BuildContext>,
+) -> Result<Box<dyn Parser + Send + Sync + 'a>> {
+    let builder = ParserBuilder::new(engine, context)
+        .partials()?
+        .templates()?
+        .layouts()?;
+    Ok(Box::new(builder.build()))
+}
+
+struct ParserBuilder {
+    engine: TemplateEngine,
+    context: Arc<BuildContext>,
+    tera: Tera,
+}
+
+impl ParserBuilder {
+    pub fn new(engine: TemplateEngine, context: Arc<BuildContext>) -> Self {
+        Self {
+            engine,
+            context,
+            tera: Tera::default(),
+        }
+    }
+
+    /// Register files in the partials directory by file stem.
+    pub fn partials(mut self) -> Result<Self> {
+        let templates = self.context.options.partials_path();
+        if templates.exists() && templates.is_dir() {
+            for entry in fs::read_dir(&templates)? {
+                let path = entry?.path();
+                let ext = self.engine.extension();
+                if path.is_file()
+                    && path.extension().map(|e| e == ext).unwrap_or(false)
+                {
+                    let name = path.file_stem().unwrap().to_string_lossy();
+                    self.tera.add_template_file(&path, Some(&name))?;
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Register templates in the source tree.
+    pub fn templates(mut self) -> Result<Self> {
+        let collation = self.context.collation.read().unwrap();
+        for path in collation.templates().as_ref() {
+            if TemplateEngine::from_path(path.as_ref()).as_ref()
+                == Some(&self.engine)
+            {
+                let name = path.to_string_lossy().into_owned();
+                self.tera.add_template_file(path.as_ref(), Some(&name))?;
+            }
+        }
+        drop(collation);
+        Ok(self)
+    }
+
+    pub fn layouts(mut self) -> Result<Self> {
+        let layouts = self.context.collation.read().unwrap().layouts().clone();
+        for (name, path) in layouts.iter() {
+            if TemplateEngine::from_path(path.as_ref()).as_ref()
+                == Some(&self.engine)
+            {
+                debug!("Layout: {}", name);
+                self.tera.add_template_file(path.as_ref(), Some(name))?;
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn build(self) -> TeraParser {
+        TeraParser {
+            context: self.context,
+            templates: RwLock::new(Templates {
+                tera: self.tera,
+                pages: HashMap::new(),
+            }),
+        }
+    }
+}
+
+/// Registered templates and the modification time of each page
+/// when it was registered.
+struct Templates {
+    tera: Tera,
+    pages: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Templates {
+    /// Determine if the template for a page was registered
+    /// from the current version of the file.
+    fn is_current(
+        &self,
+        file: &PathBuf,
+        modified: &Option<SystemTime>,
+    ) -> bool {
+        self.pages.get(file) == Some(modified)
+    }
+
+    /// Register the template for a page without the front matter.
+    fn register(
+        &mut self,
+        file: &PathBuf,
+        modified: Option<SystemTime>,
+    ) -> Result<()> {
+        let (content, _has_fm, _fm) =
+            frontmatter::load(file, frontmatter::get_config(file))?;
+        let name = file.to_string_lossy();
+        self.tera.add_raw_template(&name, &content)?;
+        self.pages.insert(file.to_path_buf(), modified);
+        Ok(())
+    }
+}
+
+// Render templates using tera.
+pub struct TeraParser {
+    context: Arc<BuildContext>,
+    templates: RwLock<Templates>,
+}
+
+impl TeraParser {
+    /// Get the registered templates ensuring the template
+    /// for a page reflects the file content.
+    fn templates(
+        &self,
+        file: &PathBuf,
+    ) -> Result<RwLockReadGuard<'_, Templates>> {
+        let modified = fs::metadata(file).and_then(|m| m.modified()).ok();
+        {
+            let templates = self.templates.read().unwrap();
+            if templates.is_current(file, &modified) {
+                return Ok(templates);
+            }
+        }
+        self.templates.write().unwrap().register(file, modified)?;
+        Ok(self.templates.read().unwrap())
+    }
+}
+
+impl Parser for TeraParser {
+    fn parse(&self, file: &PathBuf, data: CollatedPage) -> Result<String> {
+        let name = file.to_string_lossy();
+        let standalone = data.page().is_standalone();
+        let layout = data.page().layout.clone();
+        let mut context = Context::from_serialize(&data)?;
+
+        let templates = self.templates(file)?;
+        let content = templates.tera.render(&name, &context)?;
+
+        // Try to render a named layout
+        if !standalone {
+            if let Some(ref layout) = layout {
+                if templates.tera.get_template(layout).is_err() {
+                    return Err(Error::LayoutNotFound(layout.to_string()));
+                }
+
+                let content = if self.context.options.is_markdown_file(file) {
+                    md::render(&mut Cow::from(content), &self.context.config)
+                } else {
+                    content
+                };
+                context.insert("content", &content);
+                return Ok(templates.tera.render(layout, &context)?);
+            }
+        }
+
+        // Otherwise just render the page
+        Ok(content)
+    }
+
+    fn add(&mut self, name: String, file: &PathBuf) -> Result<()> {
+        self.templates
+            .get_mut()
+            .unwrap()
+            .tera
+            .add_template_file(file, Some(&name))
+            .map_err(Error::from)
+    }
+
+    fn remove(&mut self, name: &str) {
+        let templates = self.templates.get_mut().unwrap();
+        templates.tera.templates.remove(name);
+        templates.pages.remove(&PathBuf::from(name));
+    }
+
+    fn load(&mut self, file: &PathBuf) -> Result<()> {
+        let name = file.to_string_lossy().into_owned();
+        self.templates
+            .get_mut()
+            .unwrap()
+            .tera
+            .add_template_file(file, Some(&name))
+            .map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use config::{
+        engine::TemplateEngine, test_util::TempProject, Page, RuntimeOptions,
+    };
+
+    use super::parser;
+    use crate::{context::BuildContext, page::CollatedPage, Result};
+
+    #[test]
+    fn render_tera_layout() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write("site.toml", "lang = \"en\"\nhost = \"localhost\"\n")?;
+        let page = project.write(
+            "site/index.md",
+            "+++\nlayout = \"main\"\n+++\n# {{ title | upper }}",
+        )?;
+        let layout = project.write(
+            "site/layouts/main.tera",
+            "<title>{{ title }}</title>{{ content | safe }}",
+        )?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings.types = Some(Default::default());
+        let context = Arc::new(BuildContext {
+            config: Arc::new(project.config()?),
+            options: Arc::new(options),
+            ..Default::default()
+        });
+
+        let mut parser = parser(TemplateEngine::Tera, Arc::clone(&context))?;
+        parser.add("main".to_string(), &layout)?;
+
+        let render = |file: &PathBuf| -> Result<String> {
+            let mut data: Page = Default::default();
+            data.title = Some("Hello".to_string());
+            data.href = Some("/index.html".to_string());
+            data.layout = Some("main".to_string());
+            let data = CollatedPage::new(
+                file,
+                &context.config,
+                &context.options,
+                &context.locales,
+                &data,
+                "en",
+            )?;
+            parser.parse(file, data)
+        };
+
+        let result = render(&page)?;
+        assert!(result.starts_with("<title>Hello</title><h1>HELLO</h1>"));
+
+        // Changes to the page are rendered
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        fs::write(&page, "+++\nlayout = \"main\"\n+++\n*{{ title }}*")?;
+        let result = render(&page)?;
+        assert!(result.starts_with("<title>Hello</title><p><em>Hello</em></p>"));
+        Ok(())
+    }
+}
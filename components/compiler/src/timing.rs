@@ -0,0 +1,270 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{info, trace, Level};
+use once_cell::sync::OnceCell;
+use serde_json::{Map, Value};
+
+use utils::terminal;
+
+pub const COLLATION: &str = "collation";
+pub const DATA_SOURCES: &str = "data sources";
+pub const RENDER: &str = "rendering";
+pub const TRANSFORM: &str = "transforms";
+pub const MINIFY: &str = "minify";
+pub const SEARCH_INDEX: &str = "search index";
+pub const WRITE: &str = "writing";
+
+/// Durations for the phases of a build.
+///
+/// Clones share the same storage so that a phase may be recorded
+/// from any clone; when timings are not enabled nothing is recorded.
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    enabled: bool,
+    phases: Arc<Mutex<Vec<(String, Duration)>>>,
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            phases: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Add a duration to a phase.
+    ///
+    /// Phases that are recorded multiple times (for example rendering
+    /// each page) accumulate the total duration.
+    pub fn record(&self, phase: &str, duration: Duration) {
+        if !self.enabled {
+            return;
+        }
+        let mut phases = self.phases.lock().unwrap();
+        if let Some((_, total)) =
+            phases.iter_mut().find(|(name, _)| name == phase)
+        {
+            *total += duration;
+        } else {
+            phases.push((phase.to_string(), duration));
+        }
+    }
+
+    /// Start timing a phase; the duration is recorded when
+    /// the returned scope is dropped.
+    pub fn scope(&self, phase: &'static str) -> TimingScope<'_> {
+        TimingScope {
+            timings: self,
+            phase,
+            start: Instant::now(),
+            _span: span(phase).level(Level::Debug),
+        }
+    }
+
+    /// Recorded phases in the order they were first recorded.
+    pub fn phases(&self) -> Vec<(String, Duration)> {
+        self.phases.lock().unwrap().clone()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.lock().unwrap().iter().map(|(_, d)| *d).sum()
+    }
+
+    /// Discard the recorded phases.
+    pub fn reset(&self) {
+        self.phases.lock().unwrap().clear();
+    }
+
+    /// Print a breakdown of the recorded phases.
+    pub fn print(&self) {
+        let phases = self.phases();
+        let width = phases.iter().map(|(name, _)| name.len()).max();
+        let width = width.unwrap_or(0);
+        for (name, duration) in phases.iter() {
+            info!("{:width$} {:?}", name, duration, width = width);
+        }
+        info!("{:width$} {:?}", "total", self.total(), width = width);
+    }
+}
+
+/// Guard that records the elapsed time for a phase when dropped.
+pub struct TimingScope<'a> {
+    timings: &'a Timings,
+    phase: &'static str,
+    start: Instant,
+    _span: Span,
+}
+
+impl Drop for TimingScope<'_> {
+    fn drop(&mut self) {
+        self.timings.record(self.phase, self.start.elapsed());
+    }
+}
+
+type SpanWriter = Box<dyn Write + Send>;
+
+fn span_writer() -> &'static Mutex<Option<SpanWriter>> {
+    static WRITER: OnceCell<Mutex<Option<SpanWriter>>> = OnceCell::new();
+    WRITER.get_or_init(|| Mutex::new(None))
+}
+
+/// Write span records for all threads to a writer instead of
+/// standard error, pass `None` to restore standard error.
+///
+/// Whilst a writer is assigned every span is written as a JSON
+/// record regardless of the output mode and log level; returns
+/// the previous writer.
+pub fn set_span_writer(writer: Option<SpanWriter>) -> Option<SpanWriter> {
+    std::mem::replace(&mut *span_writer().lock().unwrap(), writer)
+}
+
+/// Start a span for a region of work.
+pub fn span<S: Into<Cow<'static, str>>>(name: S) -> Span {
+    Span {
+        name: name.into(),
+        level: Level::Info,
+        fields: Map::new(),
+        start: Instant::now(),
+    }
+}
+
+/// Timed region of work that is logged when dropped.
+///
+/// In the JSON output mode a record with the duration and fields
+/// is written when the level is enabled for the logger, otherwise
+/// the duration is logged at the trace level.
+pub struct Span {
+    name: Cow<'static, str>,
+    level: Level,
+    fields: Map<String, Value>,
+    start: Instant,
+}
+
+impl Span {
+    /// Set the level for the span, the default is `info`.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Add a field to the span record.
+    pub fn field<V: Into<Value>>(mut self, key: &str, value: V) -> Self {
+        self.fields.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+impl Span {
+    fn record(&mut self, elapsed: Duration) -> Map<String, Value> {
+        let mut fields = std::mem::take(&mut self.fields);
+        fields.insert("target".to_string(), Value::from("span"));
+        fields.insert("span".to_string(), Value::from(&*self.name));
+        fields.insert(
+            "duration_ms".to_string(),
+            Value::from(elapsed.as_secs_f64() * 1000.0),
+        );
+        fields
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let level = self.level.to_string().to_lowercase();
+
+        if let Some(writer) = span_writer().lock().unwrap().as_mut() {
+            let fields = self.record(elapsed);
+            let _ = terminal::write_record(writer, &level, &self.name, &fields);
+            return;
+        }
+
+        if self.level > log::max_level() {
+            return;
+        }
+
+        if !terminal::is_json() {
+            trace!(target: "span", "{} {:?}", self.name, elapsed);
+            return;
+        }
+
+        let fields = self.record(elapsed);
+        let stderr = io::stderr();
+        let mut writer = stderr.lock();
+        let _ =
+            terminal::write_record(&mut writer, &level, &self.name, &fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_phases() {
+        let timings = Timings::new(true);
+        {
+            let _scope = timings.scope(RENDER);
+        }
+        timings.record(RENDER, Duration::from_millis(5));
+        let shared = timings.clone();
+        std::thread::spawn(move || {
+            shared.record(WRITE, Duration::from_millis(1));
+        })
+        .join()
+        .unwrap();
+
+        let phases = timings.phases();
+        assert_eq!(2, phases.len());
+        assert_eq!(RENDER, phases[0].0);
+        assert!(phases[0].1 >= Duration::from_millis(5));
+        assert_eq!(WRITE, phases[1].0);
+        assert!(timings.total() >= Duration::from_millis(6));
+
+        let all = [
+            COLLATION,
+            DATA_SOURCES,
+            RENDER,
+            TRANSFORM,
+            MINIFY,
+            SEARCH_INDEX,
+            WRITE,
+        ];
+        timings.reset();
+        for phase in all.iter() {
+            let _scope = timings.scope(phase);
+        }
+        let phases = timings.phases();
+        assert_eq!(all.len(), phases.len());
+        for (phase, (name, _)) in all.iter().zip(phases.iter()) {
+            assert_eq!(*phase, name);
+        }
+
+        // Repeated phases accumulate the total duration
+        timings.reset();
+        for _ in 0..3 {
+            for phase in all.iter() {
+                timings.record(phase, Duration::from_millis(2));
+            }
+        }
+        let phases = timings.phases();
+        assert_eq!(all.len(), phases.len());
+        for (_, duration) in phases.iter() {
+            assert_eq!(Duration::from_millis(6), *duration);
+        }
+        assert_eq!(
+            Duration::from_millis(6 * all.len() as u64),
+            timings.total()
+        );
+
+        let disabled = Timings::new(false);
+        disabled.record(RENDER, Duration::from_millis(5));
+        assert!(disabled.phases().is_empty());
+    }
+}
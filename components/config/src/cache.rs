@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Name of the file for cache directives written to the build target.
+pub const CACHE_FILE: &str = "cache.json";
+
+/// Directives that do not accept a value.
+const FLAGS: [&str; 9] = [
+    "public",
+    "private",
+    "no-cache",
+    "no-store",
+    "no-transform",
+    "must-revalidate",
+    "proxy-revalidate",
+    "must-understand",
+    "immutable",
+];
+
+/// Directives that require a number of seconds.
+const SECONDS: [&str; 4] = [
+    "max-age",
+    "s-maxage",
+    "stale-while-revalidate",
+    "stale-if-error",
+];
+
+/// Validate the syntax of a `Cache-Control` value.
+pub fn validate(value: &str) -> Result<()> {
+    let invalid = |reason: String| {
+        Err(Error::InvalidCacheDirective(value.to_string(), reason))
+    };
+
+    if value.trim().is_empty() {
+        return invalid("the value is empty".to_string());
+    }
+
+    for directive in value.split(',').map(|d| d.trim()) {
+        let (name, seconds) = match directive.split_once('=') {
+            Some((name, seconds)) => (name.trim(), Some(seconds.trim())),
+            None => (directive, None),
+        };
+        let name = name.to_lowercase();
+        if FLAGS.contains(&name.as_str()) {
+            if seconds.is_some() {
+                return invalid(format!("{} does not accept a value", name));
+            }
+        } else if SECONDS.contains(&name.as_str()) {
+            match seconds {
+                Some(seconds) if seconds.parse::<u64>().is_ok() => {}
+                _ => {
+                    return invalid(format!(
+                        "{} requires a number of seconds",
+                        name
+                    ))
+                }
+            }
+        } else {
+            return invalid(format!("unknown directive '{}'", directive));
+        }
+    }
+    Ok(())
+}
+
+/// Apply a `Cache-Control` value to output files matching a pattern.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheRule {
+    /// Glob for paths relative to the build target.
+    pattern: Glob,
+    /// Value for the `Cache-Control` header.
+    directive: String,
+}
+
+impl CacheRule {
+    pub fn pattern(&self) -> &Glob {
+        &self.pattern
+    }
+
+    pub fn directive(&self) -> &str {
+        &self.directive
+    }
+}
+
+/// Settings for the cache directives of output files.
+///
+/// Rules are matched in order and the first matching rule is
+/// used; the `cache` setting of a page takes precedence.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CacheConfig {
+    rules: Vec<CacheRule>,
+
+    #[serde(skip)]
+    matcher: Option<GlobSet>,
+}
+
+impl CacheConfig {
+    pub fn rules(&self) -> &Vec<CacheRule> {
+        &self.rules
+    }
+
+    /// Validate the directives and compile the rule patterns.
+    ///
+    /// Callers should ensure this is done early, eg, when
+    /// the configuration data has been loaded.
+    pub fn prepare(&mut self) -> Result<()> {
+        let mut builder = GlobSetBuilder::new();
+        for rule in self.rules.iter() {
+            validate(&rule.directive)?;
+            builder.add(rule.pattern.clone());
+        }
+        self.matcher = Some(builder.build()?);
+        Ok(())
+    }
+
+    /// Find the directive for a path relative to the build target.
+    pub fn find(&self, path: &str) -> Option<&str> {
+        let path = path.trim_start_matches('/');
+        let matcher = self.matcher.as_ref()?;
+        matcher
+            .matches(path)
+            .first()
+            .map(|index| self.rules[*index].directive.as_str())
+    }
+}
+
+/// Cache directives keyed by path relative to the build target.
+///
+/// Written to the build target so the server and the publisher
+/// apply the same directives to each file.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct CacheManifest {
+    #[serde(flatten)]
+    map: BTreeMap<String, String>,
+}
+
+impl CacheManifest {
+    pub fn map(&self) -> &BTreeMap<String, String> {
+        &self.map
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn insert(&mut self, path: String, directive: String) {
+        self.map.insert(path, directive);
+    }
+
+    /// Get the directive for a path relative to the build target.
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.map
+            .get(path.trim_start_matches('/'))
+            .map(|d| d.as_str())
+    }
+
+    /// Load the manifest from a build target, the manifest is
+    /// empty when the target does not have a manifest file.
+    pub fn load<P: AsRef<Path>>(target: P) -> Result<Self> {
+        let file = target.as_ref().join(CACHE_FILE);
+        if !file.exists() {
+            return Ok(Default::default());
+        }
+        let content = utils::fs::read_string(&file)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write the manifest to a build target.
+    pub fn write<P: AsRef<Path>>(&self, target: P) -> Result<PathBuf> {
+        let file = target.as_ref().join(CACHE_FILE);
+        utils::fs::write_string(&file, serde_json::to_string_pretty(self)?)?;
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempProject;
+
+    #[test]
+    fn cache_directives() -> Result<()> {
+        for value in &[
+            "no-store",
+            "public, max-age=31536000, immutable",
+            "Private, No-Cache",
+            "max-age=0, must-revalidate",
+        ] {
+            assert!(validate(value).is_ok(), "{}", value);
+        }
+        for value in &[
+            "",
+            "no-store,",
+            "max-age",
+            "max-age=-1",
+            "no-store=1",
+            "forever",
+        ] {
+            assert!(matches!(
+                validate(value),
+                Err(Error::InvalidCacheDirective(..))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cache_rules() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            crate::SITE_TOML,
+            r#"lang = "en"
+host = "localhost"
+
+[[cache.rules]]
+pattern = "assets/**"
+directive = "public, max-age=31536000, immutable"
+
+[[cache.rules]]
+pattern = "**/*.html"
+directive = "no-cache"
+"#,
+        )?;
+        let config = project.config()?;
+        let cache = config.cache().as_ref().unwrap();
+        assert_eq!(
+            Some("public, max-age=31536000, immutable"),
+            cache.find("/assets/main.css")
+        );
+        assert_eq!(Some("no-cache"), cache.find("docs/index.html"));
+        assert_eq!(None, cache.find("robots.txt"));
+
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[[cache.rules]]\npattern = \"*\"\ndirective = \"max-age=soon\"\n",
+        )?;
+        assert!(matches!(
+            project.config(),
+            Err(Error::InvalidCacheDirective(..))
+        ));
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::path::{Path, PathBuf};
 
@@ -12,18 +12,28 @@ use log::debug;
 use unic_langid::LanguageIdentifier;
 
 use crate::{
+    cache::CacheConfig,
     date::DateConfig,
     dependency::{DependencyDefinitionMap, DependencyMap},
     engine::TemplateEngine,
+    error_pages::ErrorPagesConfig,
+    excerpt::ExcerptConfig,
     feed::FeedConfig,
+    fingerprint::FingerprintConfig,
     fluent::FluentConfig,
+    generator::{self, BuildInfoConfig},
+    hints::HintsConfig,
     hook::{HookConfig, HookMap},
+    images::ImagesConfig,
     indexer::DataBase,
+    limits::LimitsConfig,
     link::LinkConfig,
+    link_check::LinkCheckConfig,
     live_reload::LiveReload,
     menu::MenuConfig,
     minify::MinifyConfig,
     page::{Author, Page},
+    permalink::PermalinkConfig,
     plugin::Plugin,
     profile::{NodeConfig, ProfileName, ProfileSettings, Profiles},
     redirect::RedirectConfig,
@@ -32,7 +42,8 @@ use crate::{
     script::ScriptAsset,
     search::SearchConfig,
     sitemap::SiteMapConfig,
-    style::StyleAsset,
+    social::SocialConfig,
+    style::{CriticalStyleConfig, StyleAsset},
     sync::SyncConfig,
     syntax::SyntaxConfig,
     tags::{link::LinkTag, script::ScriptTag},
@@ -120,6 +131,13 @@ pub const OG_IMAGE: &str = "image";
 pub const OG_TITLE: &str = "title";
 pub const OG_DESCRIPTION: &str = "description";
 
+// For twitter card meta data.
+pub const TWITTER_CARD: &str = "twitter:card";
+pub const TWITTER_SITE: &str = "twitter:site";
+pub const TWITTER_TITLE: &str = "twitter:title";
+pub const TWITTER_DESCRIPTION: &str = "twitter:description";
+pub const TWITTER_IMAGE: &str = "twitter:image";
+
 pub const LAYOUT_HBS: &str = "main.hbs";
 pub const MAIN: &str = "main";
 //pub const MAIN_CSS: &str = "main.css";
@@ -135,6 +153,10 @@ const DEFAULT_ICON_DATA: &str =
 
 pub const DEFAULT_PWA_MANIFEST: &str = "app.webmanifest";
 
+/// Manifest of build outputs written to the build target,
+/// a dot file so it does not clash with site files.
+pub const OUTPUT_MANIFEST: &str = ".uwe-manifest.json";
+
 pub const PUBLIC_HTML: &str = "public_html";
 
 // Environment variables
@@ -142,6 +164,9 @@ pub const ENV_DISABLE_SSL: &str = "UWE_DISABLE_SSL";
 pub const ENV_LOOPBACK_HOST: &str = "UWE_LOOPBACK_HOST";
 pub const ENV_WEBDAV: &str = "UWE_WEBDAV";
 pub const ENV_WEBSOCKET_URL: &str = "UWE_WEBSOCKET_URL";
+pub const ENV_OFFLINE: &str = "UWE_OFFLINE";
+pub const ENV_REGISTRY_MIRROR: &str = "UWE_REGISTRY_MIRROR";
+pub const ENV_STRICT_CONFIG: &str = "UWE_STRICT_CONFIG";
 
 // Webdav
 pub const WEBDAV_MOUNT_PATH: &str = "/-/webdav";
@@ -182,6 +207,63 @@ pub fn parse_language<S: AsRef<str>>(
     Ok(id)
 }
 
+/// Determine if the value of a boolean environment variable is set.
+///
+/// Accepts `1`, `true`, `yes` and `on` (case insensitive); any other
+/// value, including the empty string, is false.
+pub fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// Determine if a boolean environment variable is enabled.
+pub fn env_flag(name: &str) -> bool {
+    std::env::var(name).map(|v| parse_flag(&v)).unwrap_or(false)
+}
+
+/// Find keys in site configuration that do not map to a setting
+/// so that typos which would otherwise be silently ignored can
+/// be reported.
+///
+/// Nested keys are returned as a dotted path including the table
+/// name, eg: `build.rewite-index`.
+pub fn unknown_keys(content: &str) -> Result<Vec<String>, Error> {
+    let mut keys = Vec::new();
+    let mut deserializer = toml::Deserializer::new(content);
+    let _: Config = serde_ignored::deserialize(&mut deserializer, |path| {
+        // Optional tables are marked with a `?` segment
+        let key = path
+            .to_string()
+            .split('.')
+            .filter(|s| *s != "?")
+            .collect::<Vec<_>>()
+            .join(".");
+        keys.push(key)
+    })?;
+    Ok(keys)
+}
+
+/// Parse the base URL for a named URL prefix.
+pub(crate) fn parse_url_prefix(name: &str, base: &str) -> Result<Url, Error> {
+    let url = Url::parse(base).map_err(|e| {
+        Error::InvalidUrlPrefix(
+            name.to_string(),
+            base.to_string(),
+            e.to_string(),
+        )
+    })?;
+    if url.cannot_be_a_base() {
+        return Err(Error::InvalidUrlPrefix(
+            name.to_string(),
+            base.to_string(),
+            "not a base URL".to_string(),
+        ));
+    }
+    Ok(url)
+}
+
 pub fn parse_host<S: AsRef<str>>(host: S) -> Result<Url, Error> {
     let mut host = host.as_ref().clone().to_string();
     // It's ok if people want to declare a scheme but we don't
@@ -209,6 +291,10 @@ pub struct Config {
     lang: String,
     host: String,
 
+    /// Shared config files merged into this config, the canonical
+    /// paths are assigned when the config is loaded.
+    include: Option<Vec<PathBuf>>,
+
     /// Project version
     #[serde_as(as = "DisplayFromStr")]
     version: Version,
@@ -238,8 +324,8 @@ pub struct Config {
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     authors: HashMap<String, Author>,
 
-    #[serde(skip_serializing_if = "HashSet::is_empty")]
-    hook: HashSet<HookConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hook: Vec<HookConfig>,
     #[serde(skip)]
     hook_map: Option<HookMap>,
 
@@ -252,6 +338,45 @@ pub struct Config {
     // Optional robots config
     robots: RobotsConfig,
 
+    // Redirect and feature depth limits
+    limits: LimitsConfig,
+
+    // Computed page excerpt settings
+    excerpt: ExcerptConfig,
+
+    // Optional build metadata config
+    build_info: Option<BuildInfoConfig>,
+
+    // Optional asset fingerprint config
+    fingerprint: Option<FingerprintConfig>,
+
+    // Optional responsive images config
+    images: Option<ImagesConfig>,
+
+    // Optional permalink pattern config
+    permalink: Option<PermalinkConfig>,
+
+    // Named base URLs, eg: `cdn` or `api`
+    urls: Option<HashMap<String, String>>,
+
+    // Optional social sharing config
+    social: Option<SocialConfig>,
+
+    // Optional resource hints for the document head
+    hints: Option<HintsConfig>,
+
+    // Optional cache directives for output files
+    cache: Option<CacheConfig>,
+
+    // Optional critical styles inlined in each page
+    critical_style: Option<CriticalStyleConfig>,
+
+    // Optional generated error pages config
+    error_pages: Option<ErrorPagesConfig>,
+
+    // Optional link checking for the generated output
+    link_check: Option<LinkCheckConfig>,
+
     dependencies: Option<DependencyDefinitionMap>,
     dependencies_map: Option<DependencyMap>,
 
@@ -299,6 +424,7 @@ impl Default for Config {
         Config {
             lang: String::from(LANG),
             host: String::from(HOST),
+            include: None,
             version: Version::from((1, 0, 0)),
             website: format!("{}{}:{}", SCHEME_HTTP, HOST, PORT)
                 .parse()
@@ -327,6 +453,19 @@ impl Default for Config {
             menu: None,
             sitemap: Default::default(),
             robots: Default::default(),
+            limits: Default::default(),
+            excerpt: Default::default(),
+            build_info: None,
+            fingerprint: None,
+            images: None,
+            permalink: None,
+            urls: None,
+            social: None,
+            hints: None,
+            cache: None,
+            critical_style: None,
+            error_pages: None,
+            link_check: None,
             dependencies: None,
             dependencies_map: None,
             syntax: None,
@@ -376,6 +515,68 @@ impl Config {
         &self.robots
     }
 
+    pub fn build_info(&self) -> &Option<BuildInfoConfig> {
+        &self.build_info
+    }
+
+    pub fn fingerprint(&self) -> &Option<FingerprintConfig> {
+        &self.fingerprint
+    }
+
+    pub fn images(&self) -> &Option<ImagesConfig> {
+        &self.images
+    }
+
+    pub fn urls(&self) -> &Option<HashMap<String, String>> {
+        &self.urls
+    }
+
+    /// Join a path to the base URL for a named prefix.
+    pub fn prefix_url(&self, name: &str, path: &str) -> Result<String, Error> {
+        let base = self
+            .urls
+            .as_ref()
+            .and_then(|urls| urls.get(name))
+            .ok_or_else(|| Error::UnknownUrlPrefix(name.to_string()))?;
+        let mut base = parse_url_prefix(name, base)?;
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(base.join(path.trim_start_matches('/'))?.to_string())
+    }
+
+    pub fn permalink(&self) -> &Option<PermalinkConfig> {
+        &self.permalink
+    }
+
+    pub fn social(&self) -> &Option<SocialConfig> {
+        &self.social
+    }
+
+    pub fn hints(&self) -> &Option<HintsConfig> {
+        &self.hints
+    }
+
+    pub fn cache(&self) -> &Option<CacheConfig> {
+        &self.cache
+    }
+
+    pub fn critical_style(&self) -> &Option<CriticalStyleConfig> {
+        &self.critical_style
+    }
+
+    pub fn critical_style_mut(&mut self) -> &mut Option<CriticalStyleConfig> {
+        &mut self.critical_style
+    }
+
+    pub fn error_pages(&self) -> &Option<ErrorPagesConfig> {
+        &self.error_pages
+    }
+
+    pub fn link_check(&self) -> &Option<LinkCheckConfig> {
+        &self.link_check
+    }
+
     pub fn redirects(&self) -> &RedirectConfig {
         &self.redirects
     }
@@ -384,6 +585,14 @@ impl Config {
         &self.node
     }
 
+    pub fn limits(&self) -> &LimitsConfig {
+        &self.limits
+    }
+
+    pub fn excerpt(&self) -> &ExcerptConfig {
+        &self.excerpt
+    }
+
     pub fn sitemap(&self) -> &SiteMapConfig {
         &self.sitemap
     }
@@ -392,6 +601,10 @@ impl Config {
         &self.dependencies_map
     }
 
+    pub fn set_dependencies(&mut self, dependencies: DependencyMap) {
+        self.dependencies_map = Some(dependencies);
+    }
+
     pub fn member_name(&self) -> &Option<String> {
         &self.member_name
     }
@@ -488,6 +701,12 @@ impl Config {
         &self.version
     }
 
+    /// Canonical paths of the shared config files merged
+    /// into this config.
+    pub fn includes(&self) -> &[PathBuf] {
+        self.include.as_deref().unwrap_or(&[])
+    }
+
     pub fn charset(&self) -> &str {
         self.charset.as_ref().unwrap()
     }
@@ -532,6 +751,17 @@ impl Config {
             let content = utils::fs::read_string(file)?;
             let mut cfg: Config = toml::from_str(&content)?;
 
+            // Merge shared config files
+            if cfg.include.is_some() {
+                let (value, includes) = crate::include::read(file)?;
+                cfg = value.try_into()?;
+                cfg.include = Some(includes);
+            }
+
+            if env_flag(ENV_STRICT_CONFIG) {
+                crate::validate::config_keys(file, &content, cfg.includes())?;
+            }
+
             let project = resolve_project(&file);
             if project.is_none() {
                 return Err(Error::ProjectResolve(file.to_path_buf()));
@@ -549,6 +779,40 @@ impl Config {
             // Ensure the host is a valid Url
             parse_host(&cfg.host)?;
 
+            if let Some(ref urls) = cfg.urls {
+                for (name, base) in urls {
+                    parse_url_prefix(name, base)?;
+                }
+            }
+
+            if let Some(ref hints) = cfg.hints {
+                hints.links(&cfg)?;
+            }
+
+            let profiles = cfg
+                .build
+                .iter()
+                .chain(cfg.profile.iter().flat_map(|p| p.values()));
+            for settings in profiles {
+                if let Some(ref proxy) = settings.proxy {
+                    for proxy in proxy.iter() {
+                        proxy.validate()?;
+                    }
+                }
+            }
+
+            if let Some(ref mut cache) = cfg.cache {
+                cache.prepare()?;
+            }
+
+            if let Some(aws) =
+                cfg.publish.as_ref().and_then(|p| p.aws.as_ref())
+            {
+                for (name, env) in aws.environments.iter() {
+                    env.validate(name)?;
+                }
+            }
+
             if let Some(deps) = cfg.dependencies.take() {
                 let mut dependency_map: DependencyMap = deps.try_into()?;
 
@@ -567,10 +831,13 @@ impl Config {
             }
 
             cfg.fluent.prepare(lang_id);
+            cfg.limits.validate()?;
+            cfg.excerpt.validate()?;
+            crate::validate::config(&cfg)?;
 
             if !cfg.hook.is_empty() {
-                let exec_hooks: HashSet<HookConfig> =
-                    cfg.hook.drain().collect();
+                let exec_hooks: Vec<HookConfig> =
+                    cfg.hook.drain(..).collect();
                 cfg.hook_map = Some(HookMap::from(exec_hooks));
             }
 
@@ -582,9 +849,14 @@ impl Config {
             }
             if let Some(search) = cfg.search.as_mut() {
                 search.prepare();
+                search.validate(cfg.db.as_ref())?;
             }
             if let Some(feed) = cfg.feed.as_mut() {
                 feed.prepare();
+                feed.validate(cfg.db.as_ref())?;
+            }
+            if let Some(permalink) = cfg.permalink.as_mut() {
+                permalink.prepare();
             }
             for (k, v) in cfg.authors.iter_mut() {
                 v.alias.get_or_insert(k.to_string());
@@ -592,6 +864,18 @@ impl Config {
             if let Some(menu) = cfg.menu.as_mut() {
                 menu.prepare();
             }
+            if let Some(ref build_info) = cfg.build_info {
+                if let (true, Some(app)) =
+                    (build_info.meta(), generator::try_get())
+                {
+                    if let Some(page) = cfg.page.as_mut() {
+                        page.meta.get_or_insert(HashMap::new()).insert(
+                            generator::META_NAME.to_string(),
+                            generator::meta_content(app),
+                        );
+                    }
+                }
+            }
 
             return Ok(cfg);
         }
@@ -668,10 +952,108 @@ pub struct AwsPublishEnvironment {
     pub prefix: Option<String>,
     pub bucket: Option<String>,
     keep_remote: Option<bool>,
+    /// Maximum number of remote objects that may be deleted.
+    pub max_delete: Option<usize>,
+    /// Maximum proportion of remote objects (0-1) that may be deleted.
+    pub max_delete_ratio: Option<f64>,
+    /// Minimum number of deletions before the ratio is enforced.
+    pub min_delete: Option<usize>,
 }
 
 impl AwsPublishEnvironment {
     pub fn keep_remote(&self) -> bool {
         self.keep_remote.is_some() && self.keep_remote.unwrap()
     }
+
+    /// Validate the delete ratio for the named environment.
+    pub fn validate(&self, name: &str) -> Result<(), Error> {
+        if let Some(ratio) = self.max_delete_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(Error::InvalidDeleteRatio(name.to_string(), ratio));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_config_keys() -> Result<(), Error> {
+        let content = r#"
+lang = "en"
+host = "example.com"
+titel = "Example"
+
+[build]
+rewite-index = true
+"#;
+        let keys = unknown_keys(content)?;
+        assert_eq!(vec!["titel", "build.rewite-index"], keys);
+
+        let keys =
+            unknown_keys("lang = \"en\"\n[build]\nrewrite-index = true")?;
+        assert!(keys.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn flag_values() {
+        for value in &["1", "true", "YES", " on "] {
+            assert!(parse_flag(value), "{}", value);
+        }
+        for value in &["", "0", "false", "off", "no", "enabled"] {
+            assert!(!parse_flag(value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn delete_ratio() -> Result<(), Error> {
+        let project = crate::test_util::TempProject::new()?;
+        let site = |ratio: &str| {
+            format!(
+                "lang = \"en\"\nhost = \"localhost\"\n\n[publish.aws]\ncredentials = \"default\"\nregion = \"ap-southeast-1\"\n\n[publish.aws.environments.production]\nmax-delete-ratio = {}\n",
+                ratio
+            )
+        };
+        for ratio in &["0.0", "0.25", "1.0"] {
+            project.write(crate::SITE_TOML, &site(ratio))?;
+            project.config()?;
+        }
+        for ratio in &["-0.5", "1.5", "nan"] {
+            project.write(crate::SITE_TOML, &site(ratio))?;
+            assert!(matches!(
+                project.config(),
+                Err(Error::InvalidDeleteRatio(..))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn url_prefixes() -> Result<(), Error> {
+        let project = crate::test_util::TempProject::new()?;
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[urls]\ncdn = \"https://cdn.example.com/assets\"\n",
+        )?;
+        let config = project.config()?;
+        assert_eq!(
+            "https://cdn.example.com/assets/img/logo.png",
+            config.prefix_url("cdn", "/img/logo.png")?
+        );
+        assert!(matches!(
+            config.prefix_url("api", "users"),
+            Err(Error::UnknownUrlPrefix(..))
+        ));
+
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[urls]\ncdn = \"not a url\"\n",
+        )?;
+        assert!(matches!(project.config(), Err(Error::InvalidUrlPrefix(..))));
+        Ok(())
+    }
 }
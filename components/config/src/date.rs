@@ -5,9 +5,22 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DateConfig {
     pub formats: HashMap<String, String>,
+    /// Named formats for a language that override the default formats.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub locales: HashMap<String, HashMap<String, String>>,
 }
 
 impl DateConfig {
+    /// Get a named format for a language falling back
+    /// to the default formats.
+    pub fn format(&self, name: &str, lang: &str) -> Option<&str> {
+        self.locales
+            .get(lang)
+            .and_then(|formats| formats.get(name))
+            .or_else(|| self.formats.get(name))
+            .map(|s| s.as_str())
+    }
+
     pub(crate) fn prepare(&mut self) {
         let mut datetime_formats = HashMap::new();
         datetime_formats.insert("date-short".to_string(), "%F".to_string());
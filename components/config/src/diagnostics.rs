@@ -0,0 +1,210 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::Events;
+
+/// Write the report to standard output instead of a file.
+pub const STDOUT: &str = "-";
+
+pub const BUILD: &str = "build";
+pub const CONFIG: &str = "config";
+pub const DEAD_LINK: &str = "dead-link";
+pub const DUPLICATE_ID: &str = "duplicate-id";
+pub const FRONT_MATTER: &str = "front-matter";
+pub const MENU_LINK: &str = "menu-link";
+pub const ORPHAN_RESOURCE: &str = "orphan-resource";
+pub const PAGE_FILE: &str = "page-file";
+pub const PERMALINK: &str = "permalink";
+pub const REDIRECT: &str = "redirect";
+pub const SEARCH_INDEX: &str = "search-index";
+pub const TEMPLATE: &str = "template";
+pub const TRANSLATION: &str = "translation";
+pub const UNRESOLVED_LINK: &str = "unresolved-link";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single build warning or error.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Stable identifier for the kind of problem.
+    pub code: String,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn new(code: &str, severity: Severity, message: String) -> Self {
+        Self {
+            code: code.to_string(),
+            severity,
+            message,
+            file: None,
+            line: None,
+        }
+    }
+
+    pub fn error(code: &str, message: String) -> Self {
+        Self::new(code, Severity::Error, message)
+    }
+
+    pub fn warning(code: &str, message: String) -> Self {
+        Self::new(code, Severity::Warning, message)
+    }
+
+    pub fn with_file<P: AsRef<Path>>(mut self, file: P) -> Self {
+        self.file = Some(file.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    diagnostics: &'a Vec<Diagnostic>,
+}
+
+/// Collects diagnostics during a build.
+///
+/// Clones share the same storage so diagnostics may be recorded
+/// from any clone; when not enabled nothing is recorded but
+/// warnings are still published to the build events.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    enabled: bool,
+    items: Arc<Mutex<Vec<Diagnostic>>>,
+    events: Events,
+}
+
+impl Diagnostics {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            items: Arc::new(Mutex::new(Vec::new())),
+            events: Default::default(),
+        }
+    }
+
+    /// Publish warnings to the build events.
+    pub fn set_events(&mut self, events: Events) {
+        self.events = events;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn push(&self, diagnostic: Diagnostic) {
+        if diagnostic.severity == Severity::Warning {
+            self.events.warning(&diagnostic);
+        }
+        if self.enabled {
+            self.items.lock().unwrap().push(diagnostic);
+        }
+    }
+
+    /// Record a warning for a file.
+    pub fn warn<P: AsRef<Path>>(&self, code: &str, message: String, file: P) {
+        self.push(Diagnostic::warning(code, message).with_file(file));
+    }
+
+    /// Recorded diagnostics in the order they were recorded.
+    pub fn items(&self) -> Vec<Diagnostic> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.items
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    /// Write the diagnostics as a JSON report.
+    pub fn to_writer<W: Write>(&self, writer: W) -> io::Result<()> {
+        let items = self.items.lock().unwrap();
+        serde_json::to_writer_pretty(
+            writer,
+            &Report {
+                diagnostics: &items,
+            },
+        )
+        .map_err(io::Error::from)
+    }
+
+    /// Write the JSON report to a file or to standard
+    /// output when the path is `-`.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if path == Path::new(STDOUT) {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            self.to_writer(&mut handle)?;
+            writeln!(handle)
+        } else {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            self.to_writer(fs::File::create(path)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_report() -> io::Result<()> {
+        let diagnostics = Diagnostics::new(true);
+        diagnostics.warn(
+            ORPHAN_RESOURCE,
+            "Orphan image.png".to_string(),
+            "site/image.png",
+        );
+        let shared = diagnostics.clone();
+        std::thread::spawn(move || {
+            shared.push(
+                Diagnostic::error(FRONT_MATTER, "Invalid".to_string())
+                    .with_file("site/index.md")
+                    .with_line(2),
+            );
+        })
+        .join()
+        .unwrap();
+        assert!(diagnostics.has_errors());
+
+        let mut buf: Vec<u8> = Vec::new();
+        diagnostics.to_writer(&mut buf)?;
+        let value: serde_json::Value = serde_json::from_slice(&buf)?;
+        let items = value["diagnostics"].as_array().unwrap();
+        assert_eq!(2, items.len());
+        assert_eq!("orphan-resource", items[0]["code"]);
+        assert_eq!("warning", items[0]["severity"]);
+        assert!(items[0].get("line").is_none());
+        assert_eq!("error", items[1]["severity"]);
+        assert_eq!(2, items[1]["line"]);
+
+        let disabled = Diagnostics::new(false);
+        disabled.warn(ORPHAN_RESOURCE, String::new(), "site/image.png");
+        assert!(disabled.items().is_empty());
+        Ok(())
+    }
+}
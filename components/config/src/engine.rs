@@ -1,14 +1,18 @@
 use serde::{Deserialize, Serialize, Serializer};
 use std::fmt;
+use std::path::Path;
 use std::str::FromStr;
 
 use crate::Error;
 
 const HANDLEBARS: &str = "handlebars";
 const HANDLEBARS_EXT: &str = "hbs";
+const TERA: &str = "tera";
+const TERA_EXT: &str = "tera";
 
 /// All available template engines.
-pub const ENGINES: [TemplateEngine; 1] = [TemplateEngine::Handlebars];
+pub const ENGINES: [TemplateEngine; 2] =
+    [TemplateEngine::Handlebars, TemplateEngine::Tera];
 
 /// The supported template engines.
 ///
@@ -21,14 +25,30 @@ pub const ENGINES: [TemplateEngine; 1] = [TemplateEngine::Handlebars];
 pub enum TemplateEngine {
     #[serde(rename = "handlebars")]
     Handlebars,
+    #[serde(rename = "tera")]
+    Tera,
 }
 
 impl TemplateEngine {
     pub fn extension(&self) -> &str {
         match *self {
             Self::Handlebars => HANDLEBARS_EXT,
+            Self::Tera => TERA_EXT,
         }
     }
+
+    /// Find the template engine for a file extension.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        ENGINES.iter().find(|e| e.extension() == ext).cloned()
+    }
+
+    /// Find the template engine for a file path using the file extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_extension)
+    }
 }
 
 impl Default for TemplateEngine {
@@ -41,6 +61,7 @@ impl fmt::Display for TemplateEngine {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Self::Handlebars => write!(f, "{}", HANDLEBARS),
+            Self::Tera => write!(f, "{}", TERA),
         }
     }
 }
@@ -50,6 +71,8 @@ impl FromStr for TemplateEngine {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s == HANDLEBARS {
             return Ok(TemplateEngine::Handlebars);
+        } else if s == TERA {
+            return Ok(TemplateEngine::Tera);
         }
         Err(Error::UnsupportedTemplateEngine(s.to_string()))
     }
@@ -62,6 +85,7 @@ impl Serialize for TemplateEngine {
     {
         match *self {
             Self::Handlebars => serializer.serialize_str(HANDLEBARS),
+            Self::Tera => serializer.serialize_str(TERA),
         }
     }
 }
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::LAYOUTS;
+
+/// Status code for the not found error page.
+pub const NOT_FOUND: &str = "404";
+
+/// Settings for error pages generated from a template
+/// so that every site has a consistent `404.html`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ErrorPagesConfig {
+    /// Template file relative to the site source; it is
+    /// rendered as a standalone page for each error page.
+    template: PathBuf,
+
+    /// Error pages keyed by HTTP status code, eg: `404`.
+    pages: BTreeMap<String, ErrorPage>,
+}
+
+impl Default for ErrorPagesConfig {
+    fn default() -> Self {
+        let mut pages = BTreeMap::new();
+        pages.insert(NOT_FOUND.to_string(), Default::default());
+        Self {
+            template: PathBuf::from(LAYOUTS).join("error.hbs"),
+            pages,
+        }
+    }
+}
+
+impl ErrorPagesConfig {
+    pub fn template(&self) -> &PathBuf {
+        &self.template
+    }
+
+    pub fn pages(&self) -> &BTreeMap<String, ErrorPage> {
+        &self.pages
+    }
+
+    /// Output path for the not found page when configured.
+    pub fn not_found(&self) -> Option<String> {
+        self.pages.get(NOT_FOUND).map(|p| p.output(NOT_FOUND))
+    }
+}
+
+/// Data for a single error page.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ErrorPage {
+    /// Page title.
+    title: Option<String>,
+    /// Message for the template.
+    message: Option<String>,
+    /// Output path relative to the build target,
+    /// default is the status code with an `.html` extension.
+    output: Option<String>,
+}
+
+impl ErrorPage {
+    pub fn title(&self) -> &Option<String> {
+        &self.title
+    }
+
+    pub fn message(&self) -> &Option<String> {
+        &self.message
+    }
+
+    /// Output path for the page with any leading slash removed.
+    pub fn output(&self, status: &str) -> String {
+        if let Some(ref output) = self.output {
+            output.trim_start_matches('/').to_string()
+        } else {
+            format!("{}.html", status)
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::Diagnostic;
+
+/// Progress event published during a build.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum BuildEvent {
+    PhaseStarted {
+        phase: String,
+    },
+    PhaseFinished {
+        phase: String,
+        millis: u64,
+    },
+    PageRendered {
+        file: PathBuf,
+        dest: PathBuf,
+    },
+    Warning {
+        diagnostic: Diagnostic,
+    },
+    Complete {
+        pages: usize,
+        warnings: usize,
+        millis: u64,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Subscribers {
+    active: AtomicBool,
+    senders: Mutex<Vec<Sender<BuildEvent>>>,
+    started: Mutex<Option<Instant>>,
+    pages: AtomicUsize,
+    warnings: AtomicUsize,
+}
+
+/// Channel for build progress events.
+///
+/// Clones share the same subscribers so events may be published
+/// from any clone; when there are no subscribers publishing an
+/// event only checks a flag and the event is never created.
+#[derive(Debug, Clone, Default)]
+pub struct Events {
+    inner: Arc<Subscribers>,
+}
+
+impl Events {
+    /// Receive all the events published after this call.
+    pub fn subscribe(&self) -> Receiver<BuildEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.inner.senders.lock().unwrap().push(tx);
+        self.inner.active.store(true, Ordering::SeqCst);
+        rx
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.inner.active.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, event: BuildEvent) {
+        let mut senders = self.inner.senders.lock().unwrap();
+        senders.retain(|tx| tx.send(event.clone()).is_ok());
+        if senders.is_empty() {
+            self.inner.active.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Publish an event when there are subscribers.
+    pub fn emit<F: FnOnce() -> BuildEvent>(&self, event: F) {
+        if self.is_active() {
+            self.send(event());
+        }
+    }
+
+    /// Publish the start of a phase, the finish is published
+    /// when the returned scope is dropped.
+    pub fn phase(&self, phase: &'static str) -> PhaseScope<'_> {
+        if self.is_active() {
+            let mut started = self.inner.started.lock().unwrap();
+            started.get_or_insert_with(Instant::now);
+        }
+        self.emit(|| BuildEvent::PhaseStarted {
+            phase: phase.to_string(),
+        });
+        PhaseScope {
+            events: self,
+            phase,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn page_rendered<P: AsRef<Path>>(&self, file: P, dest: P) {
+        if self.is_active() {
+            self.inner.pages.fetch_add(1, Ordering::SeqCst);
+            self.send(BuildEvent::PageRendered {
+                file: file.as_ref().to_path_buf(),
+                dest: dest.as_ref().to_path_buf(),
+            });
+        }
+    }
+
+    pub fn warning(&self, diagnostic: &Diagnostic) {
+        if self.is_active() {
+            self.inner.warnings.fetch_add(1, Ordering::SeqCst);
+            self.send(BuildEvent::Warning {
+                diagnostic: diagnostic.clone(),
+            });
+        }
+    }
+
+    /// Publish the summary for a build and reset the counters
+    /// for the next build.
+    pub fn complete(&self) {
+        if self.is_active() {
+            let started = self.inner.started.lock().unwrap().take();
+            let millis =
+                started.map(|s| s.elapsed().as_millis() as u64).unwrap_or(0);
+            self.send(BuildEvent::Complete {
+                pages: self.inner.pages.swap(0, Ordering::SeqCst),
+                warnings: self.inner.warnings.swap(0, Ordering::SeqCst),
+                millis,
+            });
+        }
+    }
+}
+
+/// Guard that publishes the end of a phase when dropped.
+pub struct PhaseScope<'a> {
+    events: &'a Events,
+    phase: &'static str,
+    start: Instant,
+}
+
+impl Drop for PhaseScope<'_> {
+    fn drop(&mut self) {
+        let millis = self.start.elapsed().as_millis() as u64;
+        let phase = self.phase;
+        self.events.emit(|| BuildEvent::PhaseFinished {
+            phase: phase.to_string(),
+            millis,
+        });
+    }
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+const MARKER: &str = "<!-- more -->";
+const WORDS: usize = 50;
+
+/// Settings for the excerpt computed for each page.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ExcerptConfig {
+    /// Number of words when the excerpt is taken from
+    /// the start of the page content.
+    words: usize,
+    /// Marker in the page content that ends the excerpt.
+    marker: String,
+}
+
+impl Default for ExcerptConfig {
+    fn default() -> Self {
+        Self {
+            words: WORDS,
+            marker: MARKER.to_string(),
+        }
+    }
+}
+
+impl ExcerptConfig {
+    pub fn words(&self) -> usize {
+        self.words
+    }
+
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.words == 0 {
+            return Err(Error::InvalidLimit("excerpt words".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Get the excerpt for some HTML content.
+    ///
+    /// When the marker is found the content before the marker
+    /// is used otherwise the content is truncated to the number
+    /// of words; markup is removed in both cases.
+    pub fn excerpt(&self, html: &str) -> Option<String> {
+        let text = if let Some(pos) = html.find(&self.marker) {
+            strip_html(&html[..pos])
+        } else {
+            let text = strip_html(html);
+            let words: Vec<&str> = text.split(' ').collect();
+            if words.len() > self.words {
+                format!("{}…", words[..self.words].join(" "))
+            } else {
+                text
+            }
+        };
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+}
+
+/// Delimiters of template expressions and comments, longer
+/// openings are first so they match before their prefixes.
+const TEMPLATE_DELIMITERS: [(&str, &str); 5] = [
+    ("{{!--", "--}}"),
+    ("{{{", "}}}"),
+    ("{{", "}}"),
+    ("{%", "%}"),
+    ("{#", "#}"),
+];
+
+/// Elements that are removed with their content.
+const RAW_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// Remove template expressions and comments from page content.
+///
+/// Excerpts are computed before pages are rendered so expressions
+/// would otherwise appear in the excerpt text.
+pub fn strip_template(content: &str) -> String {
+    let mut text = String::new();
+    let mut rest = content;
+    while let Some(start) = rest.find('{') {
+        let (open, close) = match TEMPLATE_DELIMITERS
+            .iter()
+            .find(|(open, _)| rest[start..].starts_with(open))
+        {
+            Some(delimiters) => *delimiters,
+            None => {
+                text.push_str(&rest[..start + 1]);
+                rest = &rest[start + 1..];
+                continue;
+            }
+        };
+        text.push_str(&rest[..start]);
+        let after = start + open.len();
+        rest = match rest[after..].find(close) {
+            Some(end) => &rest[after + end + close.len()..],
+            None => "",
+        };
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Remove tags and comments from HTML and collapse whitespace.
+///
+/// The content of script and style elements is removed.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        let tag = &rest[start + 1..];
+        let raw = RAW_ELEMENTS.iter().find(|name| {
+            tag.get(..name.len())
+                .map(|t| t.eq_ignore_ascii_case(name))
+                .unwrap_or(false)
+        });
+        let end = if rest[start..].starts_with("<!--") {
+            rest[start..].find("-->").map(|i| start + i + 3)
+        } else if let Some(name) = raw {
+            let close = format!("</{}", name);
+            rest[start..]
+                .to_ascii_lowercase()
+                .find(&close)
+                .map(|i| start + i)
+                .and_then(|i| rest[i..].find('>').map(|j| i + j + 1))
+        } else {
+            rest[start..].find('>').map(|i| start + i + 1)
+        };
+        rest = match end {
+            Some(end) => &rest[end..],
+            None => "",
+        };
+    }
+    text.push_str(rest);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Page};
+
+    #[test]
+    fn excerpt_text() {
+        let config: ExcerptConfig = Default::default();
+        assert_eq!(
+            Some("Intro with bold text.".to_string()),
+            config.excerpt(
+                "<p>Intro with <b>bold</b>\ntext.</p>\n<!-- more -->\n<p>More</p>"
+            )
+        );
+
+        let config = ExcerptConfig {
+            words: 3,
+            marker: "<!--cut-->".to_string(),
+        };
+        assert_eq!(
+            Some("One two three…".to_string()),
+            config.excerpt("<h1>One</h1><p>two three <!-- note -->four</p>")
+        );
+        assert_eq!(
+            Some("One two".to_string()),
+            config.excerpt("<p>One two</p>")
+        );
+        assert_eq!(None, config.excerpt("<p> </p>"));
+        assert_eq!(
+            Some("Text".to_string()),
+            config.excerpt(
+                "<script>let a = 1 < 2;</script><STYLE>p {}</STYLE>Text"
+            )
+        );
+    }
+
+    #[test]
+    fn template_text() {
+        assert_eq!(
+            "Hello , welcome  { x } and .",
+            strip_template(
+                "Hello {{name}}, welcome {{!-- {{note}} --}} { x } and {{{raw}}}{% if a %}{# c #}."
+            )
+        );
+    }
+
+    #[test]
+    fn page_excerpt() {
+        let config: Config = Default::default();
+
+        let mut page: Page = Default::default();
+        page.summary = Some("Explicit *summary*".to_string());
+        page.compute_excerpt(&config, "# Title\n\nContent", true);
+        assert_eq!(Some("Explicit summary"), page.excerpt.as_deref());
+
+        let mut page: Page = Default::default();
+        page.compute_excerpt(
+            &config,
+            "Some *emphasis* in the first paragraph.\n\n<!-- more -->\n\nRest",
+            true,
+        );
+        assert_eq!(
+            Some("Some emphasis in the first paragraph."),
+            page.excerpt.as_deref()
+        );
+
+        let mut page: Page = Default::default();
+        page.compute_excerpt(
+            &config,
+            "# {{title}}\n\nThe **page** {{> partial}}content",
+            true,
+        );
+        assert_eq!(Some("The page content"), page.excerpt.as_deref());
+
+        let words = vec!["word"; WORDS + 10];
+        page.compute_excerpt(&config, &words.join(" "), true);
+        let expected = format!("{}…", words[..WORDS].join(" "));
+        assert_eq!(Some(expected), page.excerpt);
+    }
+}
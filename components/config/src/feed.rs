@@ -3,7 +3,11 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-use crate::utils::matcher::GlobPatternMatcher;
+use crate::{
+    indexer::{DataBase, SourceProvider},
+    utils::matcher::GlobPatternMatcher,
+    Error, Result,
+};
 
 const JSON: &str = "json";
 const XML: &str = "xml";
@@ -63,6 +67,22 @@ impl FeedType {
     }
 }
 
+/// Content included for each feed item.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedContent {
+    /// The rendered page content and the summary.
+    Full,
+    /// The page description or excerpt.
+    Summary,
+}
+
+impl Default for FeedContent {
+    fn default() -> Self {
+        Self::Summary
+    }
+}
+
 // The partial names in the feed template plugin.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
@@ -100,6 +120,9 @@ pub struct FeedConfig {
     // The resulting list will be truncated to this value.
     pub limit: Option<usize>,
 
+    // Whether feed items include the full content or a summary.
+    pub content: FeedContent,
+
     // The name of the plugin defining the feed template partials.
     pub plugin: Option<String>,
 
@@ -117,6 +140,7 @@ impl Default for FeedConfig {
     fn default() -> Self {
         Self {
             limit: Some(100),
+            content: Default::default(),
             plugin: Some(PLUGIN_NAME.to_string()),
             names: Default::default(),
             channels: HashMap::new(),
@@ -135,6 +159,33 @@ impl FeedConfig {
             v.alternate.compile();
         }
     }
+
+    // Ensure the collections used by channels are pages collections.
+    pub(crate) fn validate(&self, db: Option<&DataBase>) -> Result<()> {
+        for (k, v) in self.channels.iter() {
+            if let Some(ref collection) = v.collection {
+                let provider = db
+                    .and_then(|db| db.load.as_ref())
+                    .and_then(|load| load.get(collection))
+                    .ok_or_else(|| {
+                        Error::NoFeedCollection(
+                            k.to_string(),
+                            collection.to_string(),
+                        )
+                    })?;
+                match provider.source_provider() {
+                    SourceProvider::Pages => {}
+                    _ => {
+                        return Err(Error::FeedCollectionNotPages(
+                            k.to_string(),
+                            collection.to_string(),
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -150,6 +201,18 @@ pub struct ChannelConfig {
     // A description for the feed channel.
     pub description: Option<String>,
 
+    // Name of a pages collection, when set only pages
+    // in the collection are included in the feed.
+    pub collection: Option<String>,
+
+    // The limit for the number of items in this feed,
+    // overrides the limit for all feeds.
+    pub limit: Option<usize>,
+
+    // Content for the items in this feed, overrides
+    // the content for all feeds.
+    pub content: Option<FeedContent>,
+
     // Path for a favicon, it will be made absolute.
     pub favicon: Option<String>,
 
@@ -172,6 +235,9 @@ impl Default for ChannelConfig {
             target: Some("".to_string()),
             title: None,
             description: None,
+            collection: None,
+            limit: None,
+            content: None,
             favicon: None,
             icon: None,
             types: vec![FeedType::Json, FeedType::Rss, FeedType::Atom],
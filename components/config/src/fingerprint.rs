@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::profile::{ProfileFilter, Profiles};
+
+/// Settings for renaming assets to include a content hash.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FingerprintConfig {
+    /// File extensions for assets that are renamed, when not set
+    /// stylesheets, scripts, images and fonts are renamed.
+    extensions: Option<Vec<String>>,
+    /// Paths relative to the build target that keep their name.
+    exclude: Vec<String>,
+    /// Number of characters of the hash used in file names.
+    length: usize,
+
+    profiles: ProfileFilter,
+}
+
+impl Default for FingerprintConfig {
+    fn default() -> Self {
+        Self {
+            extensions: None,
+            exclude: vec!["robots.txt".to_string()],
+            length: 8,
+            profiles: ProfileFilter::Flag(false),
+        }
+    }
+}
+
+impl Profiles for FingerprintConfig {
+    fn profiles(&self) -> &ProfileFilter {
+        &self.profiles
+    }
+}
+
+impl FingerprintConfig {
+    pub fn extensions(&self) -> Option<&Vec<String>> {
+        self.extensions.as_ref()
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Determine if a path relative to the build target is excluded.
+    pub fn is_excluded(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        self.exclude
+            .iter()
+            .any(|e| e.trim_start_matches('/') == path)
+    }
+}
@@ -8,11 +8,15 @@ pub const CORE_FTL: &str = "core.ftl";
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(default)]
+#[serde(default, rename_all = "kebab-case")]
 pub struct FluentConfig {
     #[serde_as(as = "Option<DisplayFromStr>")]
     fallback: Option<LanguageIdentifier>,
     shared: String,
+    /// Write the fallback language to a directory named
+    /// after the language like the other languages, when
+    /// disabled it is written to the root of the build target.
+    prefix_fallback: bool,
 }
 
 impl FluentConfig {
@@ -29,6 +33,10 @@ impl FluentConfig {
     pub fn shared(&self) -> &str {
         &self.shared
     }
+
+    pub fn prefix_fallback(&self) -> bool {
+        self.prefix_fallback
+    }
 }
 
 impl Default for FluentConfig {
@@ -36,6 +44,7 @@ impl Default for FluentConfig {
         Self {
             fallback: None,
             shared: CORE_FTL.to_string(),
+            prefix_fallback: true,
         }
     }
 }
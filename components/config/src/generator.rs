@@ -1,5 +1,14 @@
+use chrono::{SecondsFormat, Utc};
 use once_cell::sync::OnceCell;
 use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::profile::{ProfileFilter, Profiles};
+
+pub const FILE: &str = "build-info.json";
+pub const META_NAME: &str = "generator";
+
+static INSTANCE: OnceCell<AppData> = OnceCell::new();
 
 #[derive(Debug)]
 pub struct AppData {
@@ -11,13 +20,17 @@ pub struct AppData {
 }
 
 pub fn get(generator: Option<AppData>) -> &'static AppData {
-    static INSTANCE: OnceCell<AppData> = OnceCell::new();
     INSTANCE.get_or_init(|| {
         let generator = generator.unwrap();
         generator
     })
 }
 
+/// Get the application data when it has been assigned.
+pub fn try_get() -> Option<&'static AppData> {
+    INSTANCE.get()
+}
+
 pub fn name() -> &'static str {
     &get(None).name
 }
@@ -37,3 +50,129 @@ pub fn semver() -> &'static Version {
 pub fn bin_name() -> &'static str {
     &get(None).bin_name
 }
+
+/// Content for a generator meta tag, eg: `uwe 0.1.0`.
+pub fn meta_content(app: &AppData) -> String {
+    format!("{} {}", app.name, app.version)
+}
+
+/// Render a `<meta name="generator">` tag for the application.
+pub fn meta_tag(app: &AppData) -> String {
+    format!(
+        "<meta name=\"{}\" content=\"{}\">",
+        META_NAME,
+        utils::entity::escape(&meta_content(app))
+    )
+}
+
+/// Settings for embedding build metadata into the output.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BuildInfoConfig {
+    /// Add a generator meta entry to the default page data.
+    meta: Option<bool>,
+    /// Write a build information file to the target.
+    file: Option<bool>,
+    /// Include the build timestamp, disable for reproducible builds.
+    timestamp: Option<bool>,
+
+    profiles: ProfileFilter,
+}
+
+impl Default for BuildInfoConfig {
+    fn default() -> Self {
+        Self {
+            meta: Some(true),
+            file: Some(true),
+            timestamp: Some(true),
+            profiles: Default::default(),
+        }
+    }
+}
+
+impl Profiles for BuildInfoConfig {
+    fn profiles(&self) -> &ProfileFilter {
+        &self.profiles
+    }
+}
+
+impl BuildInfoConfig {
+    pub fn meta(&self) -> bool {
+        self.meta.is_some() && self.meta.unwrap()
+    }
+
+    pub fn file(&self) -> bool {
+        self.file.is_some() && self.file.unwrap()
+    }
+
+    pub fn timestamp(&self) -> bool {
+        self.timestamp.is_some() && self.timestamp.unwrap()
+    }
+}
+
+/// Machine-readable build information written to the target.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub generator: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+}
+
+impl BuildInfo {
+    pub fn new(
+        app: &AppData,
+        config: &BuildInfoConfig,
+        commit: Option<String>,
+    ) -> Self {
+        let timestamp = if config.timestamp() {
+            Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true))
+        } else {
+            None
+        };
+        Self {
+            generator: app.name.clone(),
+            version: app.version.clone(),
+            commit,
+            timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_data() -> AppData {
+        AppData {
+            name: "uwe".to_string(),
+            bin_name: "uwe".to_string(),
+            version: "0.1.0".to_string(),
+            semver: Version::new(0, 1, 0),
+            user_agent: "uwe/0.1.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn generator_meta_tag() {
+        let app = app_data();
+        assert_eq!(
+            "<meta name=\"generator\" content=\"uwe 0.1.0\">",
+            meta_tag(&app)
+        );
+    }
+
+    #[test]
+    fn build_info_reproducible() {
+        let app = app_data();
+        let config = BuildInfoConfig {
+            timestamp: Some(false),
+            ..Default::default()
+        };
+        let info = BuildInfo::new(&app, &config, None);
+        assert_eq!("0.1.0", info.version);
+        assert!(info.timestamp.is_none());
+    }
+}
@@ -0,0 +1,242 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    tags::{
+        attr::{normalize_rel, As, CrossOrigin, RelValue},
+        link::LinkTag,
+    },
+    Config, Error, Result,
+};
+
+/// Resource hint declared with explicit attributes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HintLink {
+    rel: String,
+    href: String,
+    #[serde(rename = "as")]
+    as_attr: Option<As>,
+    crossorigin: Option<CrossOrigin>,
+    #[serde(rename = "type")]
+    link_type: Option<String>,
+}
+
+/// Settings for the resource hints added to the document head.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HintsConfig {
+    /// Origins to connect to early; a name in the `[urls]`
+    /// settings uses the origin of the URL prefix.
+    preconnect: Vec<String>,
+    /// Origins to resolve early, names are the same as `preconnect`.
+    dns_prefetch: Vec<String>,
+    /// Font files to preload.
+    fonts: Vec<String>,
+    /// Critical style sheets to preload.
+    styles: Vec<String>,
+    /// Additional hints.
+    links: Vec<HintLink>,
+}
+
+impl HintsConfig {
+    /// Get the link tags for all the hints.
+    pub fn links(&self, config: &Config) -> Result<Vec<LinkTag>> {
+        let mut links = Vec::new();
+        for origin in self.preconnect.iter() {
+            links.push(hint(
+                origin_url(config, origin)?,
+                vec![RelValue::PreConnect],
+                None,
+                None,
+                None,
+            )?);
+        }
+        for origin in self.dns_prefetch.iter() {
+            links.push(hint(
+                origin_url(config, origin)?,
+                vec![RelValue::DnsPrefetch],
+                None,
+                None,
+                None,
+            )?);
+        }
+        for font in self.fonts.iter() {
+            links.push(hint(
+                font.to_string(),
+                vec![RelValue::PreLoad],
+                Some(As::Font),
+                Some(CrossOrigin::Anonymous),
+                font_type(font),
+            )?);
+        }
+        for style in self.styles.iter() {
+            links.push(hint(
+                style.to_string(),
+                vec![RelValue::PreLoad],
+                Some(As::Style),
+                None,
+                None,
+            )?);
+        }
+        for link in self.links.iter() {
+            let rel = normalize_rel(&link.rel)?
+                .split(' ')
+                .map(|s| s.parse::<RelValue>())
+                .collect::<Result<Vec<_>>>()?;
+            links.push(hint(
+                link.href.clone(),
+                rel,
+                link.as_attr.clone(),
+                link.crossorigin.clone(),
+                link.link_type.clone(),
+            )?);
+        }
+        Ok(links)
+    }
+}
+
+/// Create a link tag for a hint after checking the `rel` and
+/// `as` combination.
+fn hint(
+    href: String,
+    rel: Vec<RelValue>,
+    as_attr: Option<As>,
+    crossorigin: Option<CrossOrigin>,
+    link_type: Option<String>,
+) -> Result<LinkTag> {
+    let invalid = |reason: &str| {
+        Err(Error::InvalidResourceHint(href.clone(), reason.to_string()))
+    };
+
+    let is_preload = rel.contains(&RelValue::PreLoad);
+    let is_connect = rel.contains(&RelValue::PreConnect)
+        || rel.contains(&RelValue::DnsPrefetch);
+    let is_hint = is_preload
+        || is_connect
+        || rel.contains(&RelValue::ModulePreload)
+        || rel.contains(&RelValue::PreFetch);
+
+    if !is_hint {
+        return invalid("the rel value is not a resource hint");
+    }
+    if is_preload && as_attr.is_none() {
+        return invalid("preload requires an as value");
+    }
+    if is_connect && as_attr.is_some() {
+        return invalid("preconnect and dns-prefetch may not have an as value");
+    }
+    if as_attr == Some(As::Font) && crossorigin.is_none() {
+        return invalid("fonts must be loaded with crossorigin");
+    }
+
+    Ok(LinkTag::new_hint(
+        href,
+        rel,
+        as_attr,
+        crossorigin,
+        link_type,
+    ))
+}
+
+/// Resolve the origin for a hint from a URL prefix name or a URL.
+fn origin_url(config: &Config, value: &str) -> Result<String> {
+    let url = match config.urls().as_ref().and_then(|urls| urls.get(value)) {
+        Some(base) => crate::config::parse_url_prefix(value, base)?,
+        None => Url::parse(value).map_err(|_| {
+            Error::InvalidResourceHint(
+                value.to_string(),
+                "not a URL prefix name or URL".to_string(),
+            )
+        })?,
+    };
+    if !url.origin().is_tuple() {
+        return Err(Error::InvalidResourceHint(
+            value.to_string(),
+            "the URL does not have an origin".to_string(),
+        ));
+    }
+    Ok(url.origin().ascii_serialization())
+}
+
+/// Media type for a font file from the file extension.
+fn font_type(href: &str) -> Option<String> {
+    let ext = Path::new(href.split(|c| c == '?' || c == '#').next()?)
+        .extension()?
+        .to_str()?
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "woff" | "woff2" | "ttf" | "otf" => Some(format!("font/{}", ext)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempProject;
+
+    const SITE: &str = "lang = \"en\"\nhost = \"localhost\"\n\n[urls]\ncdn = \"https://cdn.example.com/assets\"\n";
+
+    fn hints(settings: &str) -> Result<Vec<String>> {
+        let project = TempProject::new()?;
+        project.write(crate::SITE_TOML, format!("{}\n{}", SITE, settings))?;
+        let config = project.config()?;
+        let links = config.hints().as_ref().unwrap().links(&config)?;
+        Ok(links.iter().map(|l| l.to_string()).collect())
+    }
+
+    #[test]
+    fn font_preload() -> Result<()> {
+        let links = hints(
+            r#"[hints]
+preconnect = ["cdn"]
+dns-prefetch = ["https://api.example.com/v1"]
+fonts = ["/fonts/inter.woff2"]
+styles = ["/critical.css"]
+"#,
+        )?;
+        assert_eq!(
+            vec![
+                r#"<link rel="preconnect" href="https://cdn.example.com">"#,
+                r#"<link rel="dns-prefetch" href="https://api.example.com">"#,
+                r#"<link rel="preload" href="/fonts/inter.woff2" as="font" crossorigin="anonymous" type="font/woff2">"#,
+                r#"<link rel="preload" href="/critical.css" as="style">"#,
+            ],
+            links
+        );
+
+        let links = hints(
+            r#"[[hints.links]]
+rel = "Preload"
+href = "/hero.webp"
+as = "image"
+"#,
+        )?;
+        assert_eq!(
+            vec![r#"<link rel="preload" href="/hero.webp" as="image">"#],
+            links
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_hints() -> Result<()> {
+        for settings in &[
+            "[[hints.links]]\nrel = \"preload\"\nhref = \"/app.js\"\n",
+            "[[hints.links]]\nrel = \"stylesheet\"\nhref = \"/a.css\"\n",
+            "[[hints.links]]\nrel = \"preconnect\"\nhref = \"/\"\nas = \"font\"\n",
+            "[[hints.links]]\nrel = \"preload\"\nhref = \"/a.woff\"\nas = \"font\"\n",
+            "[hints]\npreconnect = [\"api\"]\n",
+        ] {
+            let project = TempProject::new()?;
+            project.write(crate::SITE_TOML, format!("{}\n{}", SITE, settings))?;
+            assert!(matches!(
+                project.config(),
+                Err(Error::InvalidResourceHint(..))
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
-use std::collections::{hash_set, HashSet};
 use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
+use std::slice;
 
 use globset::{Glob, GlobMatcher};
 use serde::{Deserialize, Serialize};
@@ -8,23 +8,30 @@ use serde_with::skip_serializing_none;
 
 use crate::{profile::ProfileName, Error, Result};
 
+/// Hooks in declaration order, duplicate hooks are ignored.
 #[derive(Debug, Clone)]
 pub struct HookMap {
-    exec: HashSet<HookConfig>,
+    exec: Vec<HookConfig>,
 }
 
-impl From<HashSet<HookConfig>> for HookMap {
-    fn from(exec: HashSet<HookConfig>) -> Self {
+impl From<Vec<HookConfig>> for HookMap {
+    fn from(hooks: Vec<HookConfig>) -> Self {
+        let mut exec: Vec<HookConfig> = Vec::new();
+        for v in hooks {
+            if !exec.contains(&v) {
+                exec.push(v);
+            }
+        }
         Self { exec }
     }
 }
 
 impl HookMap {
-    pub fn exec(&self) -> &HashSet<HookConfig> {
+    pub fn exec(&self) -> &Vec<HookConfig> {
         &self.exec
     }
 
-    pub fn iter(&self) -> hash_set::Iter<'_, HookConfig> {
+    pub fn iter(&self) -> slice::Iter<'_, HookConfig> {
         self.exec.iter()
     }
 
@@ -33,8 +40,8 @@ impl HookMap {
     }
 
     pub fn prepare(&mut self, source: &PathBuf, base: &PathBuf) -> Result<()> {
-        let mut out: HashSet<HookConfig> = HashSet::new();
-        for mut v in self.exec.drain() {
+        let mut out: Vec<HookConfig> = Vec::new();
+        for mut v in self.exec.drain(..) {
             if v.command.is_empty() {
                 return Err(Error::HookPathEmpty(base.to_path_buf()));
             }
@@ -49,7 +56,9 @@ impl HookMap {
                 }
             }
 
-            out.insert(v);
+            if !out.contains(&v) {
+                out.push(v);
+            }
         }
 
         self.exec = out;
@@ -57,12 +66,30 @@ impl HookMap {
     }
 
     pub fn append(&mut self, other: &mut HookMap) {
-        for v in other.exec.drain() {
-            self.exec.insert(v);
+        for v in other.exec.drain(..) {
+            if !self.exec.contains(&v) {
+                self.exec.push(v);
+            }
         }
     }
 }
 
+/// Lifecycle stage when a hook is executed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookStage {
+    /// Before the project is collated.
+    PreBuild,
+    /// After collation and before pages are rendered.
+    PostCollate,
+    /// After pages are rendered.
+    PostRender,
+    /// After all build output has been written.
+    PostBuild,
+    /// Before the build output is published.
+    PrePublish,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
@@ -74,9 +101,12 @@ pub struct HookConfig {
     // Command arguments.
     pub args: Option<Vec<String>>,
 
-    // Marks the hook to run after a build
+    // Marks the hook to run after a build, prefer `stage`
     pub after: Option<bool>,
 
+    // Lifecycle stage for the hook
+    pub stage: Option<HookStage>,
+
     // Only run for these profiles
     pub profiles: Option<Vec<ProfileName>>,
 
@@ -116,6 +146,7 @@ impl Default for HookConfig {
             stdout: Some(true),
             stderr: Some(true),
             after: Some(false),
+            stage: None,
             profiles: None,
             files: None,
             watch: None,
@@ -131,6 +162,19 @@ impl HookConfig {
         &self.base
     }
 
+    /// Stage for the hook, when no stage is declared hooks marked
+    /// to run after a build run after rendering otherwise they
+    /// run before rendering.
+    pub fn stage(&self) -> HookStage {
+        match self.stage {
+            Some(stage) => stage,
+            None if self.after.is_some() && self.after.unwrap() => {
+                HookStage::PostRender
+            }
+            None => HookStage::PostCollate,
+        }
+    }
+
     pub fn has_matchers(&self) -> bool {
         !self.files_match.is_empty()
     }
@@ -171,3 +215,77 @@ impl Hash for HookConfig {
         self.args.hash(state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::HookStage;
+    use crate::{test_util::TempProject, Result};
+
+    const SITE: &str = "lang = \"en\"\nhost = \"localhost\"\n";
+
+    #[test]
+    fn hook_stage() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            crate::SITE_TOML,
+            format!(
+                "{}\n{}",
+                SITE,
+                r#"[[hook]]
+command = "npm"
+args = ["run", "assets"]
+stage = "pre-build"
+
+[[hook]]
+command = "npm"
+args = ["run", "check"]
+after = true
+"#
+            ),
+        )?;
+        let config = project.config()?;
+        let hooks = config.hooks().as_ref().unwrap();
+        let stages: Vec<HookStage> = hooks.iter().map(|h| h.stage()).collect();
+        assert_eq!(vec![HookStage::PreBuild, HookStage::PostRender], stages);
+
+        project.write(
+            crate::SITE_TOML,
+            format!(
+                "{}\n[[hook]]\ncommand = \"npm\"\nstage = \"pre-render\"\n",
+                SITE
+            ),
+        )?;
+        assert!(project.config().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn hook_order() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            crate::SITE_TOML,
+            format!(
+                "{}\n{}",
+                SITE,
+                r#"[[hook]]
+command = "styles"
+
+[[hook]]
+command = "scripts"
+
+[[hook]]
+command = "images"
+
+[[hook]]
+command = "styles"
+"#
+            ),
+        )?;
+        let config = project.config()?;
+        let hooks = config.hooks().as_ref().unwrap();
+        let commands: Vec<&str> =
+            hooks.iter().map(|h| h.command.as_str()).collect();
+        assert_eq!(vec!["styles", "scripts", "images"], commands);
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use crate::profile::{ProfileFilter, Profiles};
+
+/// Settings for generating responsive image sizes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ImagesConfig {
+    /// Widths in pixels for the generated images.
+    widths: Vec<u32>,
+    /// Quality used when encoding JPEG images (1-100).
+    quality: u8,
+    /// File extensions for images that are processed.
+    extensions: Vec<String>,
+
+    profiles: ProfileFilter,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            widths: vec![480, 960, 1440],
+            quality: 80,
+            extensions: ["png", "jpg", "jpeg"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            profiles: ProfileFilter::Flag(false),
+        }
+    }
+}
+
+impl Profiles for ImagesConfig {
+    fn profiles(&self) -> &ProfileFilter {
+        &self.profiles
+    }
+}
+
+impl ImagesConfig {
+    /// Get the configured widths sorted in ascending order
+    /// without duplicates.
+    pub fn widths(&self) -> Vec<u32> {
+        let mut widths: Vec<u32> =
+            self.widths.iter().cloned().filter(|w| *w > 0).collect();
+        widths.sort();
+        widths.dedup();
+        widths
+    }
+
+    pub fn quality(&self) -> u8 {
+        self.quality.max(1).min(100)
+    }
+
+    pub fn extensions(&self) -> &Vec<String> {
+        &self.extensions
+    }
+}
@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+
+use toml::{value::Table, Value};
+
+use crate::{Error, Result};
+
+/// Key for the list of included config files.
+const INCLUDE: &str = "include";
+
+/// Merge a value into a base value.
+///
+/// Tables are merged recursively, any other value replaces
+/// the value in the base.
+fn merge(base: &mut Value, value: Value) {
+    match (base, value) {
+        (Value::Table(base), Value::Table(table)) => {
+            for (key, value) in table {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+/// Read a config file and merge the files it includes.
+///
+/// Included files are relative to the file that includes them
+/// and are merged in order so later includes override earlier
+/// ones and the including file overrides all of them.
+///
+/// Returns the merged value and the canonical paths of all the
+/// included files.
+pub(crate) fn read(file: &Path) -> Result<(Value, Vec<PathBuf>)> {
+    let mut stack = Vec::new();
+    let mut includes = Vec::new();
+    let value = resolve(file, &mut stack, &mut includes)?;
+    Ok((value, includes))
+}
+
+fn resolve(
+    file: &Path,
+    stack: &mut Vec<PathBuf>,
+    includes: &mut Vec<PathBuf>,
+) -> Result<Value> {
+    let file = file
+        .canonicalize()
+        .map_err(|_| Error::NoInclude(file.to_path_buf()))?;
+    if stack.contains(&file) {
+        return Err(Error::CyclicInclude(file));
+    }
+    if !stack.is_empty() && !includes.contains(&file) {
+        includes.push(file.clone());
+    }
+
+    let content = utils::fs::read_string(&file)?;
+    let mut value: Value = toml::from_str(&content)?;
+    let paths: Vec<PathBuf> =
+        match value.as_table_mut().and_then(|t| t.remove(INCLUDE)) {
+            Some(paths) => paths.try_into()?,
+            None => return Ok(value),
+        };
+
+    let base = file.parent().unwrap().to_path_buf();
+    stack.push(file);
+    let mut merged = Value::Table(Table::new());
+    for path in paths {
+        merge(&mut merged, resolve(&base.join(path), stack, includes)?);
+    }
+    stack.pop();
+
+    merge(&mut merged, value);
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_util::TempProject, Error, Result};
+
+    #[test]
+    fn include_merge() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            "shared/base.toml",
+            r#"host = "example.com"
+
+[urls]
+cdn = "https://cdn.example.com"
+api = "https://old.example.com"
+"#,
+        )?;
+        project.write(
+            "shared/override.toml",
+            "[urls]\napi = \"https://api.example.com\"\n",
+        )?;
+        project.write(
+            crate::SITE_TOML,
+            r#"lang = "en"
+include = ["shared/base.toml", "shared/override.toml"]
+
+[urls]
+docs = "https://docs.example.com"
+"#,
+        )?;
+
+        let config = project.config()?;
+        assert_eq!("example.com", config.host());
+        assert_eq!(2, config.includes().len());
+
+        let urls = config.urls().as_ref().unwrap();
+        assert_eq!("https://cdn.example.com", urls["cdn"]);
+        assert_eq!("https://api.example.com", urls["api"]);
+        assert_eq!("https://docs.example.com", urls["docs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn include_cycle() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write("shared/a.toml", "include = [\"b.toml\"]\n")?;
+        project.write("shared/b.toml", "include = [\"a.toml\"]\n")?;
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\ninclude = [\"shared/a.toml\"]\n",
+        )?;
+        assert!(matches!(
+            project.config(),
+            Err(Error::CyclicInclude(file)) if file.ends_with("shared/a.toml")
+        ));
+
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\ninclude = [\"missing.toml\"]\n",
+        )?;
+        assert!(matches!(project.config(), Err(Error::NoInclude(_))));
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde_json::{to_value, Value};
 use serde_with::skip_serializing_none;
@@ -40,6 +40,10 @@ pub enum SourceProvider {
     Pages,
     #[serde(rename = "document")]
     Document,
+    /// Documents are computed at build time by a provider
+    /// registered with the same name as the collection.
+    #[serde(rename = "computed")]
+    Computed,
 }
 
 impl Default for SourceProvider {
@@ -89,6 +93,15 @@ pub struct DataProvider {
 
     #[serde(alias = "on")]
     pub index: Option<HashMap<String, IndexRequest>>,
+
+    // Stop loading on the first duplicate document id rather
+    // than reporting all duplicates.
+    #[serde(rename = "fail-fast")]
+    fail_fast: Option<bool>,
+
+    // References to documents in other collections keyed by
+    // the document field that holds the foreign key.
+    references: Option<HashMap<String, Reference>>,
 }
 
 impl Default for DataProvider {
@@ -99,6 +112,8 @@ impl Default for DataProvider {
             from: None,
             index: Some(HashMap::new()),
             matcher: Default::default(),
+            fail_fast: None,
+            references: None,
         }
     }
 }
@@ -120,12 +135,54 @@ impl DataProvider {
         &self.matcher
     }
 
+    /// Determine if a page file belongs to this collection
+    /// when the `from` path is resolved against `source`.
+    pub fn includes_page<P: AsRef<Path>>(
+        &self,
+        source: &Path,
+        file: P,
+    ) -> bool {
+        let base = if let Some(ref from) = self.from {
+            source.join(from)
+        } else {
+            source.to_path_buf()
+        };
+        match file.as_ref().strip_prefix(&base) {
+            Ok(relative) => !self.matcher.is_excluded(relative),
+            Err(_) => false,
+        }
+    }
+
+    pub fn fail_fast(&self) -> bool {
+        self.fail_fast.is_some() && self.fail_fast.unwrap()
+    }
+
+    pub fn references(&self) -> Option<&HashMap<String, Reference>> {
+        self.references.as_ref()
+    }
+
     fn prepare(&mut self) -> Result<()> {
         self.matcher.compile();
         Ok(())
     }
 }
 
+/// Reference from a document field to a document in another
+/// collection.
+///
+/// The field value is the foreign key, when the value is an array
+/// each key is resolved. Resolved references replace the key with
+/// the target document.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Reference {
+    /// Name of the target collection.
+    pub collection: String,
+    /// Field in the target documents that is matched against
+    /// the foreign key; when not set the document id is used.
+    pub key: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct IndexRequest {
     // The document key to use for the index, may be dot-delimited
@@ -152,9 +209,56 @@ impl Default for KeyType {
     }
 }
 
+/// Placeholder for the page number in a pagination path.
+pub const PAGE_NUMBER: &str = "{n}";
+
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 pub struct PageInfo {
     pub size: usize,
+    // Path for each page relative to the paginated page,
+    // the page number replaces `{n}`, eg: `page/{n}`
+    pub path: Option<String>,
+}
+
+impl PageInfo {
+    /// Determine if the page size is valid, a size of zero
+    /// disables pagination but a size of one is not allowed.
+    pub fn is_valid(&self) -> bool {
+        self.size != 1
+    }
+
+    /// Number of items for each page of a collection, when the
+    /// page size is zero all the items are placed on a single page.
+    pub fn chunk_size(&self, length: usize) -> usize {
+        if self.size == 0 {
+            std::cmp::max(length, 1)
+        } else {
+            self.size
+        }
+    }
+
+    /// Path for a page relative to the paginated page.
+    pub fn page_path(&self, number: usize) -> PathBuf {
+        let number = number.to_string();
+        match self.path {
+            Some(ref pattern) => PathBuf::from(
+                pattern
+                    .trim_start_matches('/')
+                    .replace(PAGE_NUMBER, &number),
+            ),
+            None => PathBuf::from(number),
+        }
+    }
+
+    /// Index of the first and last item for each page of a collection.
+    pub fn bounds(&self, length: usize) -> Vec<(usize, usize)> {
+        let size = self.chunk_size(length);
+        (0..length)
+            .step_by(size)
+            .map(|first| (first, std::cmp::min(first + size, length) - 1))
+            .collect()
+    }
 }
 
 #[skip_serializing_none]
@@ -353,3 +457,38 @@ impl QueryResult {
         Ok(to_value(self)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PageInfo;
+
+    #[test]
+    fn page_size_zero() {
+        let info = PageInfo {
+            size: 0,
+            path: None,
+        };
+        assert!(info.is_valid());
+        assert_eq!(vec![(0, 4)], info.bounds(5));
+        assert!(info.bounds(0).is_empty());
+    }
+
+    #[test]
+    fn page_size_one() {
+        let info = PageInfo {
+            size: 1,
+            path: None,
+        };
+        assert!(!info.is_valid());
+    }
+
+    #[test]
+    fn page_size_boundary() {
+        let info = PageInfo {
+            size: 2,
+            path: None,
+        };
+        assert_eq!(vec![(0, 1), (2, 3)], info.bounds(4));
+        assert_eq!(vec![(0, 1), (2, 3), (4, 4)], info.bounds(5));
+    }
+}
@@ -29,6 +29,26 @@ pub enum Error {
     #[error("Collections `from` path {0} may not be absolute")]
     FromAbsolute(PathBuf),
 
+    #[error("Feed {0} uses the collection {1} which does not exist")]
+    NoFeedCollection(String, String),
+
+    #[error("Feed {0} uses the collection {1} which is not a pages collection")]
+    FeedCollectionNotPages(String, String),
+
+    #[error("Search index name {0} may only contain letters, numbers, hyphens and underscores")]
+    InvalidSearchName(String),
+
+    #[error("Search indexes {0} and {1} are both written to {2}, set a different `index` path")]
+    DuplicateSearchIndex(String, String, String),
+
+    #[error("Search index {0} uses the collection {1} which does not exist")]
+    NoSearchCollection(String, String),
+
+    #[error(
+        "Search index {0} uses the collection {1} which is not a pages collection"
+    )]
+    SearchCollectionNotPages(String, String),
+
     #[error("Failed to parse git URL: {0} ({1})")]
     GitUrlParseFail(String, String),
 
@@ -49,6 +69,9 @@ pub enum Error {
     #[error("Filters given for {0} dependency but no plugin files matched")]
     ApplyFiltersNoMatch(String),
 
+    #[error("Profile {0} sets features for {1} which is not a dependency")]
+    ProfileFeatureNoDependency(String, String),
+
     //#[error("Page {0} is outside the source directory {1}")]
     //PageOutsideSource(PathBuf, PathBuf),
     #[error("Failed to read link catalog {0}")]
@@ -66,6 +89,18 @@ pub enum Error {
     #[error("Feature stack depth has exceeded the maximum {0}")]
     FeatureStackTooLarge(usize),
 
+    #[error("Limit for {0} must be greater than zero")]
+    InvalidLimit(String),
+
+    #[error("Source and target are the same directory {1} (profile {0})")]
+    SourceIsTarget(String, PathBuf),
+
+    #[error("Target {1} is inside the source {2} which causes recursive builds (profile {0})")]
+    TargetInsideSource(String, PathBuf, PathBuf),
+
+    #[error("Source {1} is outside the project (profile {0})")]
+    SourceOutsideProject(String, PathBuf),
+
     #[error("Redirect file {0} already exists")]
     RedirectFileExists(PathBuf),
 
@@ -75,6 +110,30 @@ pub enum Error {
     #[error("The value {0} for 'rel' is not supported")]
     InvalidRelValue(String),
 
+    #[error("URL prefix {0} has an invalid base URL {1} ({2})")]
+    InvalidUrlPrefix(String, String, String),
+
+    #[error("Publish environment {0} has an invalid max-delete-ratio {1}, must be between 0 and 1")]
+    InvalidDeleteRatio(String, f64),
+
+    #[error("No URL prefix {0}, add it to the [urls] settings")]
+    UnknownUrlPrefix(String),
+
+    #[error("Resource hint {0} is invalid, {1}")]
+    InvalidResourceHint(String, String),
+
+    #[error("Cache directive '{0}' is invalid, {1}")]
+    InvalidCacheDirective(String, String),
+
+    #[error("Proxy prefix '{0}' must start with a slash")]
+    InvalidProxyPrefix(String),
+
+    #[error("Included config file {0} does not exist")]
+    NoInclude(PathBuf),
+
+    #[error("Config file {0} is included by itself")]
+    CyclicInclude(PathBuf),
+
     #[error("Plugin ref spec {0} is not valid (namespace required)")]
     InvalidPluginSpecName(String),
 
@@ -105,6 +164,9 @@ pub enum Error {
     #[error(transparent)]
     Lang(#[from] unic_langid::LanguageIdentifierError),
 
+    #[error(transparent)]
+    Glob(#[from] globset::Error),
+
     #[error(transparent)]
     ReqParse(#[from] semver::ReqParseError),
 }
@@ -134,22 +196,34 @@ pub fn to_url_string(
     url
 }
 
+pub mod cache;
 mod config;
 pub mod date;
+pub mod diagnostics;
 pub mod engine;
+pub mod error_pages;
+pub mod events;
+pub mod excerpt;
 pub mod feed;
+pub mod fingerprint;
 mod fluent;
 pub mod generator;
+pub mod hints;
 pub mod hook;
+pub mod images;
+mod include;
 pub mod indexer;
 pub mod license;
+pub mod limits;
 mod link;
+pub mod link_check;
 mod live_reload;
 pub mod memfs;
 mod menu;
 mod minify;
 mod options;
 pub mod page;
+pub mod permalink;
 pub mod plugin;
 pub mod plugin_cache;
 pub mod profile;
@@ -160,25 +234,32 @@ pub mod script;
 pub mod search;
 pub mod server;
 pub mod sitemap;
+pub mod social;
 pub mod style;
 pub mod sync;
 pub mod syntax;
 pub mod tags;
 pub mod test;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 pub mod transform;
+pub mod validate;
 
 pub(crate) mod utils;
 
 pub use self::utils::{href, markdown};
 pub use config::*;
 pub use fluent::{FluentConfig, CORE_FTL};
-pub use hook::HookConfig;
+pub use hook::{HookConfig, HookStage};
 pub use indexer::{IndexQuery, KeyType, QueryResult, SourceProvider};
-pub use menu::{MenuEntry, MenuReference, MenuResult};
-pub use options::{DestinationBuilder, FileType, LinkOptions, RuntimeOptions};
-pub use page::{Author, Page, PageLink, PaginateInfo};
+pub use link::TrailingSlash;
+pub use menu::{weight_order, MenuEntry, MenuReference, MenuResult};
+pub use options::{
+    DestinationBuilder, FileType, LinkOptions, RuntimeOptions, SourcePaths,
+};
+pub use page::{Author, AuthorRef, Authors, Page, PageLink, PaginateInfo};
 pub use plugin::*;
-pub use profile::{ProfileName, ProfileSettings, RenderTypes};
+pub use profile::{ExtensionConfig, ProfileName, ProfileSettings, RenderTypes};
 pub use redirect::*;
 pub use search::SearchConfig;
 
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    plugin::dependency::FEATURE_STACK_SIZE, redirect::MAX_REDIRECTS, Error,
+    Result,
+};
+
+/// Limits that guard against runaway resolution of redirect
+/// chains and nested plugin features.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LimitsConfig {
+    /// Maximum number of chained redirects.
+    redirects: usize,
+    /// Maximum depth of nested plugin features.
+    features: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            redirects: MAX_REDIRECTS,
+            features: FEATURE_STACK_SIZE,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn redirects(&self) -> usize {
+        self.redirects
+    }
+
+    pub fn features(&self) -> usize {
+        self.features
+    }
+
+    /// Ensure the configured limits are positive.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.redirects == 0 {
+            return Err(Error::InvalidLimit("redirects".to_string()));
+        }
+        if self.features == 0 {
+            return Err(Error::InvalidLimit("features".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_util::TempProject, Error, Result};
+
+    #[test]
+    fn positive_limits() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[limits]\nredirects = 8\n",
+        )?;
+        let config = project.config()?;
+        assert_eq!(8, config.limits().redirects());
+        assert_eq!(16, config.limits().features());
+
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[limits]\nfeatures = 0\n",
+        )?;
+        assert!(matches!(project.config(), Err(Error::InvalidLimit(..))));
+        Ok(())
+    }
+}
@@ -5,6 +5,64 @@ use serde_with::skip_serializing_none;
 
 use crate::{Error, Result};
 
+/// Canonical form for the URLs of directory pages.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailingSlash {
+    /// Directory URLs end with a slash, eg: `/docs/`.
+    Always,
+    /// Directory URLs do not end with a slash, eg: `/docs`.
+    Never,
+}
+
+impl TrailingSlash {
+    /// Convert an href to the canonical form.
+    ///
+    /// Links to files with a known file extension, the root
+    /// path and relative links that end with a dot segment are
+    /// not changed; a query string or fragment identifier is
+    /// preserved.
+    pub fn apply(&self, href: &str) -> String {
+        let split = href.find(|c| c == '?' || c == '#').unwrap_or(href.len());
+        let (path, suffix) = href.split_at(split);
+        let trimmed = path.trim_end_matches('/');
+        let name = trimmed.rsplit('/').next().unwrap_or("");
+        if trimmed.is_empty() || name == "." || name == ".." || is_file(name) {
+            return href.to_string();
+        }
+        match *self {
+            Self::Always => format!("{}/{}", trimmed, suffix),
+            Self::Never => format!("{}{}", trimmed, suffix),
+        }
+    }
+
+    /// The non-canonical form of an href or `None` when
+    /// the href is not changed by the policy.
+    pub fn alternate(&self, href: &str) -> Option<String> {
+        let other = match *self {
+            Self::Always => Self::Never,
+            Self::Never => Self::Always,
+        };
+        let alternate = other.apply(href);
+        if alternate == href {
+            None
+        } else {
+            Some(alternate)
+        }
+    }
+}
+
+/// Determine if the last segment of a path names a file
+/// by checking for a known file extension.
+fn is_file(name: &str) -> bool {
+    match name.rsplit_once('.') {
+        Some((_, ext)) if !ext.is_empty() => {
+            mime_guess::from_ext(ext).first().is_some()
+        }
+        _ => false,
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
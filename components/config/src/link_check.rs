@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use crate::profile::{ProfileFilter, Profiles};
+
+/// Settings for checking links in the generated output.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LinkCheckConfig {
+    /// Also check links to other websites.
+    external: bool,
+    /// Timeout in seconds for each external request.
+    timeout: u64,
+    /// External URLs that start with any of these
+    /// prefixes are not checked.
+    allow: Vec<String>,
+
+    profiles: ProfileFilter,
+}
+
+impl Default for LinkCheckConfig {
+    fn default() -> Self {
+        Self {
+            external: false,
+            timeout: 10,
+            allow: Vec::new(),
+            profiles: Default::default(),
+        }
+    }
+}
+
+impl Profiles for LinkCheckConfig {
+    fn profiles(&self) -> &ProfileFilter {
+        &self.profiles
+    }
+}
+
+impl LinkCheckConfig {
+    pub fn external(&self) -> bool {
+        self.external
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+
+    /// Determine if an external URL is allowed without checking.
+    pub fn is_allowed(&self, url: &str) -> bool {
+        self.allow.iter().any(|prefix| url.starts_with(prefix))
+    }
+}
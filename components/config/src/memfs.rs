@@ -1,5 +1,32 @@
 use dyn_clone::DynClone;
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Metadata for a file in a memory file system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMeta {
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Time the file was last modified when known.
+    pub modified: Option<SystemTime>,
+}
+
+impl FileMeta {
+    /// Entity tag for conditional requests derived from the
+    /// size and modification time, files without a modification
+    /// time do not have an entity tag.
+    pub fn etag(&self) -> Option<String> {
+        let modified = self.modified?.duration_since(UNIX_EPOCH).ok()?;
+        Some(format!(
+            "{:x}.{:x}-{:x}",
+            modified.as_secs(),
+            modified.subsec_nanos(),
+            self.size
+        ))
+    }
+}
 
 /// Type for implementations that expose in-memory file systems.
 ///
@@ -9,8 +36,161 @@ use std::borrow::Cow;
 pub trait MemoryFileSystem: std::fmt::Debug {
     fn get(&self, file_path: &str) -> Option<Cow<'static, [u8]>>;
     fn iter(&self) -> Box<dyn Iterator<Item = Cow<'static, str>>>;
+
+    /// Get a file and the metadata for the file in a single lookup.
+    ///
+    /// The default implementation only knows the size of the
+    /// content; implementations that track modification times
+    /// should override it.
+    fn open(&self, file_path: &str) -> Option<(Cow<'static, [u8]>, FileMeta)> {
+        self.get(file_path).map(|content| {
+            let meta = FileMeta {
+                size: content.len() as u64,
+                modified: None,
+            };
+            (content, meta)
+        })
+    }
 }
 
 pub trait EmbeddedFileSystem: MemoryFileSystem + Send + DynClone {}
 
 dyn_clone::clone_trait_object!(EmbeddedFileSystem);
+
+/// In-memory file system that may be populated at runtime.
+///
+/// Clones share the same underlying storage so that content written
+/// after the file system has been mounted by the web server is
+/// immediately available to all workers.
+type Files = HashMap<String, (Vec<u8>, SystemTime)>;
+
+#[derive(Debug, Clone, Default)]
+pub struct DynamicMemoryFileSystem {
+    files: Arc<RwLock<Files>>,
+}
+
+impl DynamicMemoryFileSystem {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn key(file_path: &str) -> String {
+        file_path.trim_start_matches('/').to_string()
+    }
+
+    /// Add a file, any existing content for the path is replaced.
+    pub fn insert<S: AsRef<str>>(&self, file_path: S, content: Vec<u8>) {
+        let mut files = self.files.write().unwrap();
+        files.insert(
+            Self::key(file_path.as_ref()),
+            (content, SystemTime::now()),
+        );
+    }
+
+    /// Remove a file returning the content if it existed.
+    pub fn remove<S: AsRef<str>>(&self, file_path: S) -> Option<Vec<u8>> {
+        let mut files = self.files.write().unwrap();
+        files
+            .remove(&Self::key(file_path.as_ref()))
+            .map(|(content, _)| content)
+    }
+
+    /// Determine if a file exists.
+    pub fn contains<S: AsRef<str>>(&self, file_path: S) -> bool {
+        let files = self.files.read().unwrap();
+        files.contains_key(&Self::key(file_path.as_ref()))
+    }
+
+    /// Remove all files.
+    pub fn clear(&self) {
+        self.files.write().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.read().unwrap().is_empty()
+    }
+}
+
+impl MemoryFileSystem for DynamicMemoryFileSystem {
+    fn get(&self, file_path: &str) -> Option<Cow<'static, [u8]>> {
+        let files = self.files.read().unwrap();
+        files
+            .get(&Self::key(file_path))
+            .map(|(content, _)| Cow::Owned(content.clone()))
+    }
+
+    fn open(&self, file_path: &str) -> Option<(Cow<'static, [u8]>, FileMeta)> {
+        let files = self.files.read().unwrap();
+        files.get(&Self::key(file_path)).map(|(content, modified)| {
+            let meta = FileMeta {
+                size: content.len() as u64,
+                modified: Some(*modified),
+            };
+            (Cow::Owned(content.clone()), meta)
+        })
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = Cow<'static, str>>> {
+        let files = self.files.read().unwrap();
+        let mut keys = files
+            .keys()
+            .map(|k| Cow::Owned(k.to_string()))
+            .collect::<Vec<_>>();
+        keys.sort();
+        Box::new(keys.into_iter())
+    }
+}
+
+impl EmbeddedFileSystem for DynamicMemoryFileSystem {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dynamic_get_and_iter() {
+        let memfs = DynamicMemoryFileSystem::new();
+        memfs.insert("/index.html", b"<p>Hello</p>".to_vec());
+        memfs.insert("assets/style.css", b"p{}".to_vec());
+
+        let mounted: Box<dyn EmbeddedFileSystem> = Box::new(memfs.clone());
+        assert_eq!(
+            Some(Cow::Borrowed(&b"<p>Hello</p>"[..])),
+            mounted.get("index.html")
+        );
+        assert!(mounted.get("missing.html").is_none());
+
+        let keys = mounted.iter().collect::<Vec<_>>();
+        assert_eq!(vec!["assets/style.css", "index.html"], keys);
+
+        // Content added after mounting is visible to the clone
+        memfs.insert("about.html", Vec::new());
+        assert!(mounted.get("about.html").is_some());
+    }
+
+    #[test]
+    fn open_etag() {
+        let memfs = DynamicMemoryFileSystem::new();
+        memfs.insert("assets/style.css", b"p{}".to_vec());
+
+        let mounted: Box<dyn EmbeddedFileSystem> = Box::new(memfs.clone());
+        let (content, meta) = mounted.open("assets/style.css").unwrap();
+        assert_eq!(&b"p{}"[..], &content[..]);
+        assert_eq!(3, meta.size);
+        assert!(meta.etag().is_some());
+        assert_eq!(
+            Some(meta.clone()),
+            mounted.open("/assets/style.css").map(|(_, meta)| meta)
+        );
+        assert!(mounted.open("missing.css").is_none());
+
+        memfs.insert("assets/style.css", b"p{margin:0}".to_vec());
+        let (_, changed) = mounted.open("assets/style.css").unwrap();
+        assert_eq!(11, changed.size);
+        assert_ne!(meta.etag(), changed.etag());
+    }
+}
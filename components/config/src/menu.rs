@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
@@ -23,6 +24,16 @@ pub struct MenuConfig {
 }
 
 impl MenuConfig {
+    /// Menu entries sorted by weight and then by name.
+    pub fn ordered(&self) -> Vec<(&String, &MenuEntry)> {
+        let mut entries: Vec<(&String, &MenuEntry)> =
+            self.entries.iter().collect();
+        entries.sort_by(|(a_name, a), (b_name, b)| {
+            weight_order(a.weight, b.weight).then_with(|| a_name.cmp(b_name))
+        });
+        entries
+    }
+
     /// Iterate the entries and create variants.
     pub fn prepare(&mut self) {
         if let Some(ref variants) = self.variants {
@@ -46,6 +57,17 @@ impl MenuConfig {
     }
 }
 
+/// Compare optional weights, entries without a weight
+/// are ordered after those with a weight.
+pub fn weight_order(a: Option<i64>, b: Option<i64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct MenuResult {
     /// Compiled HTML string, may contain template statements.
@@ -54,6 +76,9 @@ pub struct MenuResult {
     /// List of pages that were referenced by this menu so that
     /// callers can easily iterate the page data for a menu.
     pub pages: Vec<Arc<String>>,
+
+    /// Position of this menu in the resolved menu order.
+    pub order: usize,
 }
 
 #[skip_serializing_none]
@@ -64,6 +89,9 @@ pub struct MenuEntry {
     #[serde(flatten, skip_serializing)]
     pub definition: MenuReference,
 
+    /// Position of this menu, lower weights are ordered first.
+    pub weight: Option<i64>,
+
     /// Stores the hash map key as the name so that after
     /// the menu is compiled it can be re-assigned to the
     /// correct page menu entry.
@@ -79,6 +107,7 @@ impl MenuEntry {
     pub fn new(definition: MenuReference) -> Self {
         Self {
             definition,
+            weight: None,
             name: Default::default(),
             result: Default::default(),
         }
@@ -127,3 +156,29 @@ impl Default for MenuReference {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(weight: Option<i64>) -> MenuEntry {
+        MenuEntry {
+            weight,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn menu_order() {
+        let mut menu: MenuConfig = Default::default();
+        menu.entries.insert("docs".to_string(), entry(Some(3)));
+        menu.entries.insert("about".to_string(), entry(None));
+        menu.entries.insert("blog".to_string(), entry(Some(1)));
+        menu.entries.insert("guides".to_string(), entry(Some(3)));
+        menu.entries.insert("home".to_string(), entry(Some(-1)));
+
+        let names: Vec<&str> =
+            menu.ordered().iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(vec!["home", "blog", "docs", "guides", "about"], names);
+    }
+}
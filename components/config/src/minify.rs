@@ -10,6 +10,23 @@ pub struct MinifyConfig {
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct MinifyFormat {
     profiles: ProfileFilter,
+    /// Collapse runs of whitespace in text to a single space.
+    #[serde(default, rename = "collapse-whitespace")]
+    collapse_whitespace: Option<bool>,
+    /// Remove comments except for conditional comments
+    /// and those inside `build:keep` markers.
+    #[serde(default, rename = "strip-comments")]
+    strip_comments: Option<bool>,
+}
+
+impl MinifyFormat {
+    pub fn collapse_whitespace(&self) -> bool {
+        self.collapse_whitespace.is_some() && self.collapse_whitespace.unwrap()
+    }
+
+    pub fn strip_comments(&self) -> bool {
+        self.strip_comments.is_some() && self.strip_comments.unwrap()
+    }
 }
 
 impl Profiles for MinifyFormat {
@@ -1,19 +1,57 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use url::Url;
 
 use crate::{
-    profile::{ProfileName, ProfileSettings},
+    link::TrailingSlash,
+    profile::{ExtensionConfig, ProfileName, ProfileSettings},
     Config, RenderTypes, Result, HTML, INDEX_STEM,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
     Markdown,
     Template,
+    Stylesheet,
+    Script,
+    Image,
+    Font,
+    Data,
     Unknown,
 }
 
+impl FileType {
+    /// Determine if the file type is rendered as a page.
+    pub fn is_page(&self) -> bool {
+        matches!(*self, FileType::Markdown | FileType::Template)
+    }
+}
+
+/// All the paths inside the source directory that have special
+/// meaning to the build, resolved against the project source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourcePaths {
+    pub assets: PathBuf,
+    pub includes: PathBuf,
+    pub partials: PathBuf,
+    pub layouts: PathBuf,
+    pub collections: PathBuf,
+    pub locales: PathBuf,
+}
+
+impl SourcePaths {
+    /// Determine if a path is inside one of the special directories.
+    pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        path.starts_with(&self.assets)
+            || path.starts_with(&self.includes)
+            || path.starts_with(&self.partials)
+            || path.starts_with(&self.layouts)
+            || path.starts_with(&self.collections)
+            || path.starts_with(&self.locales)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct RuntimeOptions {
     // Project root
@@ -141,15 +179,16 @@ impl RuntimeOptions {
                     return FileType::Template;
                 }
             }
+            return match self.settings.extensions {
+                Some(ref extensions) => extensions.get_type(&ext),
+                None => ExtensionConfig::default().get_type(&ext),
+            };
         }
         FileType::Unknown
     }
 
     pub fn is_page<P: AsRef<Path>>(&self, p: P) -> bool {
-        match self.get_type(p) {
-            FileType::Markdown | FileType::Template => true,
-            _ => false,
-        }
+        self.get_type(p).is_page()
     }
 
     pub fn get_canonical_url<S>(
@@ -192,6 +231,18 @@ impl RuntimeOptions {
         self.source.join(self.settings.locales.as_ref().unwrap())
     }
 
+    /// Get all the source relative paths in a single call.
+    pub fn source_paths(&self) -> SourcePaths {
+        SourcePaths {
+            assets: self.assets_path(),
+            includes: self.includes_path(),
+            partials: self.partials_path(),
+            layouts: self.layouts_path(),
+            collections: self.collections_path(),
+            locales: self.locales_path(),
+        }
+    }
+
     pub fn get_render_types(&self) -> &RenderTypes {
         self.settings.types.as_ref().unwrap()
     }
@@ -257,6 +308,10 @@ impl RuntimeOptions {
         //println!("value {}", &value);
         //println!("value {:?}", rel.extension());
 
+        if let Some(policy) = self.settings.trailing_slash {
+            return Ok(policy.apply(&value));
+        }
+
         // Automatically append a trailing slash for links that
         // look like directories when we have clean links enabled
         if rewrite_index && !value.ends_with("/") {
@@ -279,7 +334,7 @@ impl RuntimeOptions {
     pub fn absolute<F: AsRef<Path>>(
         &self,
         file: F,
-        options: LinkOptions,
+        mut options: LinkOptions,
     ) -> Result<String> {
         let src = if let Some(ref source) = options.strip {
             source
@@ -335,9 +390,48 @@ impl RuntimeOptions {
             }
         }
 
+        if self.settings.trailing_slash == Some(TrailingSlash::Never) {
+            options.trailing = false;
+        }
+
         to_href(rel, options)
     }
 
+    /// Resolve a document-relative link from a page to the URL
+    /// for the output file.
+    ///
+    /// Links to pages use the same rules as `absolute()` and other
+    /// links become root-relative paths; returns `None` when the
+    /// link does not reference a file in the source directory.
+    pub fn relative_link<F: AsRef<Path>>(
+        &self,
+        file: F,
+        href: &str,
+    ) -> Option<String> {
+        let mut target = file.as_ref().parent()?.to_path_buf();
+        for component in Path::new(href).components() {
+            match component {
+                Component::Normal(part) => target.push(part),
+                Component::ParentDir => {
+                    target.pop();
+                }
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+
+        if !target.starts_with(&self.source) || !target.is_file() {
+            return None;
+        }
+
+        if self.settings.types.is_some() && self.is_page(&target) {
+            self.absolute(&target, Default::default()).ok()
+        } else {
+            let rel = target.strip_prefix(&self.source).ok()?;
+            Some(format!("/{}", utils::url::to_href_separator(rel)))
+        }
+    }
+
     pub fn destination(&self) -> DestinationBuilder {
         DestinationBuilder::new(self)
     }
@@ -514,7 +608,10 @@ impl<'a> DestinationBuilder<'a> {
 #[cfg(test)]
 mod tests {
     use crate::link::*;
-    use crate::{Config, RuntimeOptions};
+    use crate::{
+        ExtensionConfig, FileType, ProfileName, ProfileSettings,
+        RedirectConfig, Result, RuntimeOptions, MAX_REDIRECTS,
+    };
     use std::path::PathBuf;
 
     #[test]
@@ -533,6 +630,7 @@ mod tests {
     fn absolute_page_extension_rewrite() -> Result<()> {
         let mut opts: RuntimeOptions = Default::default();
         opts.source = PathBuf::from("site");
+        opts.settings.rewrite_index = Some(false);
         let page = PathBuf::from("site/post/article.md");
         let result = opts.absolute(&page, Default::default())?;
         assert_eq!("/post/article.html", result);
@@ -543,6 +641,7 @@ mod tests {
     fn absolute_page() -> Result<()> {
         let mut opts: RuntimeOptions = Default::default();
         opts.source = PathBuf::from("site");
+        opts.settings.rewrite_index = Some(false);
         let page = PathBuf::from("site/post/article.html");
         let result = opts.absolute(&page, Default::default())?;
         assert_eq!("/post/article.html", result);
@@ -560,4 +659,106 @@ mod tests {
         assert_eq!("/post/article/", result);
         Ok(())
     }
+
+    #[test]
+    fn trailing_slash_never() -> Result<()> {
+        let mut opts = RuntimeOptions {
+            source: PathBuf::from("site"),
+            ..Default::default()
+        };
+        opts.settings.rewrite_index = Some(true);
+        opts.settings.trailing_slash = Some(TrailingSlash::Never);
+
+        let page = PathBuf::from("site/docs/index.md");
+        assert_eq!("/docs", opts.absolute(page, Default::default())?);
+        let page = PathBuf::from("site/post/article.html");
+        assert_eq!("/post/article", opts.absolute(page, Default::default())?);
+        let page = PathBuf::from("site/index.md");
+        assert_eq!("/", opts.absolute(page, Default::default())?);
+        assert_eq!("/docs#usage", opts.settings.canonical_href("/docs/#usage"));
+        assert_eq!("/v1.2", opts.settings.canonical_href("/v1.2/"));
+        assert_eq!("/main.css", opts.settings.canonical_href("/main.css"));
+
+        let mut redirects: RedirectConfig = Default::default();
+        let hrefs = vec!["/index.html", "/docs/index.html", "/about.html"];
+        redirects.trailing_slash(TrailingSlash::Never, hrefs.into_iter());
+        assert_eq!(1, redirects.map().len());
+        assert_eq!(
+            Some("/docs"),
+            redirects.map().get("/docs/").map(|s| &s[..])
+        );
+        redirects.validate(MAX_REDIRECTS)?;
+        Ok(())
+    }
+
+    #[test]
+    fn relative_link() -> Result<()> {
+        let project = crate::test_util::TempProject::new()?;
+        let page = project.write("site/post/article.md", "")?;
+        project.write("site/post/other.md", "")?;
+        project.write("site/docs/index.md", "")?;
+        project.write("site/img/logo.png", "")?;
+
+        let mut opts = RuntimeOptions {
+            source: project.source(),
+            settings: ProfileSettings::from(&ProfileName::Debug),
+            ..Default::default()
+        };
+        opts.settings.rewrite_index = Some(true);
+
+        assert_eq!(
+            Some("/post/other/".to_string()),
+            opts.relative_link(&page, "./other.md")
+        );
+        assert_eq!(
+            Some("/docs/".to_string()),
+            opts.relative_link(&page, "../docs/index.md")
+        );
+        assert_eq!(
+            Some("/img/logo.png".to_string()),
+            opts.relative_link(&page, "../img/logo.png")
+        );
+        assert_eq!(None, opts.relative_link(&page, "missing.md"));
+        assert_eq!(None, opts.relative_link(&page, "../../outside.md"));
+        Ok(())
+    }
+
+    #[test]
+    fn source_paths() -> Result<()> {
+        let opts = RuntimeOptions {
+            source: PathBuf::from("site"),
+            ..Default::default()
+        };
+        let paths = opts.source_paths();
+        assert_eq!(opts.partials_path(), paths.partials);
+        assert_eq!(opts.locales_path(), paths.locales);
+        assert!(paths.contains(PathBuf::from("site/assets/style.css")));
+        assert!(!paths.contains(PathBuf::from("site/index.md")));
+        Ok(())
+    }
+
+    #[test]
+    fn file_types() {
+        let mut opts = RuntimeOptions {
+            settings: ProfileSettings::from(&ProfileName::Debug),
+            ..Default::default()
+        };
+
+        assert_eq!(FileType::Markdown, opts.get_type("site/index.md"));
+        assert_eq!(FileType::Stylesheet, opts.get_type("assets/style.css"));
+        assert_eq!(FileType::Script, opts.get_type("assets/main.js"));
+        assert_eq!(FileType::Image, opts.get_type("img/logo.PNG"));
+        assert_eq!(FileType::Data, opts.get_type("data/posts.json"));
+        assert_eq!(FileType::Unknown, opts.get_type("assets/archive.zip"));
+        assert_eq!(FileType::Unknown, opts.get_type("LICENSE"));
+        assert!(!opts.is_page("assets/style.css"));
+
+        // Extension sets are taken from the profile settings
+        let mut extensions: ExtensionConfig = Default::default();
+        extensions.stylesheet.push("pcss".to_string());
+        extensions.data.retain(|e| e != "json");
+        opts.settings.extensions = Some(extensions);
+        assert_eq!(FileType::Stylesheet, opts.get_type("assets/main.pcss"));
+        assert_eq!(FileType::Unknown, opts.get_type("data/posts.json"));
+    }
 }
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use url::Url;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_with::{serde_as, skip_serializing_none, DisplayFromStr};
 
 #[serde_as]
@@ -9,11 +11,14 @@ use serde_with::{serde_as, skip_serializing_none, DisplayFromStr};
 #[serde(default)]
 pub struct Author {
     pub name: String,
+    pub email: Option<String>,
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub url: Option<Url>,
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub avatar: Option<Url>,
     pub alias: Option<String>,
+    /// Role of the author such as `editor` or `translator`.
+    pub role: Option<String>,
 }
 
 impl Author {
@@ -35,3 +40,141 @@ impl Author {
         };
     }
 }
+
+/// Reference to a site author by alias or an author declared inline.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum AuthorRef {
+    Alias(String),
+    Author(Box<Author>),
+}
+
+/// Authors of a page in the declared order.
+///
+/// A single alias or author table is accepted in place of a list.
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct Authors(Vec<AuthorRef>);
+
+impl<'de> Deserialize<'de> for Authors {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum OneOrMany {
+            One(AuthorRef),
+            Many(Vec<AuthorRef>),
+        }
+
+        Ok(match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(author) => Self(vec![author]),
+            OneOrMany::Many(authors) => Self(authors),
+        })
+    }
+}
+
+impl Authors {
+    pub fn iter(&self) -> std::slice::Iter<'_, AuthorRef> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Resolve aliases to the site authors, when any alias does
+    /// not exist the missing aliases are returned as the error.
+    pub fn resolve<'a>(
+        &'a self,
+        all: &'a HashMap<String, Author>,
+    ) -> std::result::Result<Vec<&'a Author>, Vec<String>> {
+        let mut authors = Vec::new();
+        let mut missing = Vec::new();
+        for author in self.0.iter() {
+            match author {
+                AuthorRef::Alias(alias) => match all.get(alias) {
+                    Some(author) => authors.push(author),
+                    None => missing.push(alias.to_string()),
+                },
+                AuthorRef::Author(author) => authors.push(author),
+            }
+        }
+        if missing.is_empty() {
+            Ok(authors)
+        } else {
+            Err(missing)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Result;
+
+    #[derive(Deserialize)]
+    struct PageAuthors {
+        authors: Authors,
+    }
+
+    fn parse(value: &str) -> Result<Authors> {
+        let page: PageAuthors = toml::from_str(value)?;
+        Ok(page.authors)
+    }
+
+    #[test]
+    fn author_forms() -> Result<()> {
+        let mut all = HashMap::new();
+        all.insert(
+            "alice".to_string(),
+            Author {
+                name: "Alice".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let authors = parse(r#"authors = "alice""#)?;
+        let resolved = authors.resolve(&all).unwrap();
+        assert_eq!(1, resolved.len());
+        assert_eq!("Alice", resolved[0].name);
+
+        let authors =
+            parse(r#"authors = { name = "Bob", email = "bob@example.com" }"#)?;
+        let resolved = authors.resolve(&all).unwrap();
+        assert_eq!(1, resolved.len());
+        assert_eq!(Some("bob@example.com"), resolved[0].email.as_deref());
+
+        let authors = parse(
+            r#"
+[[authors]]
+name = "Carol"
+url = "https://example.com/carol"
+role = "editor"
+
+[[authors]]
+name = "Dave"
+role = "translator"
+"#,
+        )?;
+        let resolved = authors.resolve(&all).unwrap();
+        assert_eq!(2, resolved.len());
+        assert_eq!("Carol", resolved[0].name);
+        assert_eq!(Some("editor"), resolved[0].role.as_deref());
+        assert_eq!(
+            "https://example.com/carol",
+            resolved[0].url.as_ref().unwrap().as_str()
+        );
+        assert_eq!(Some("translator"), resolved[1].role.as_deref());
+
+        let authors = parse(r#"authors = ["alice", "eve"]"#)?;
+        assert_eq!(2, authors.len());
+        assert_eq!(vec!["eve".to_string()], authors.resolve(&all).unwrap_err());
+        Ok(())
+    }
+}
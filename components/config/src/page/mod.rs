@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::mem;
 use std::path::PathBuf;
@@ -16,7 +17,7 @@ use serde_with::skip_serializing_none;
 use crate::{
     href::UrlPath, indexer::QueryList, script::ScriptAsset, style::StyleAsset,
     tags::link::LinkTag, utils::toml_datetime::from_toml_datetime, Config,
-    Result, RuntimeOptions,
+    ProfileName, Result, RuntimeOptions,
 };
 
 use self::{feed::FeedEntry, file_context::FileContext};
@@ -26,7 +27,7 @@ pub(crate) mod feed;
 pub(crate) mod file_context;
 pub(crate) mod paginate;
 
-pub use author::Author;
+pub use author::{Author, AuthorRef, Authors};
 pub use paginate::{PageLink, PaginateInfo};
 
 #[skip_serializing_none]
@@ -53,6 +54,16 @@ pub struct Page {
     /// Do not render a layout for this page.
     pub standalone: Option<bool>,
 
+    /// Only generate this page for these profiles.
+    profiles: Option<Vec<String>>,
+
+    /// Name of an environment variable that must be truthy for
+    /// this page to be generated, prefix with `!` to negate.
+    when: Option<String>,
+
+    /// Value for the `Cache-Control` header of the output file.
+    cache: Option<String>,
+
     /// Flag to indicate this page is intended for print media.
     print: Option<bool>,
 
@@ -62,16 +73,24 @@ pub struct Page {
     /// Instruction for helpers to make links absolute.
     absolute: Option<bool>,
 
-    authors: Option<Vec<String>>,
+    /// Aliases of site authors or authors declared inline.
+    authors: Option<Authors>,
 
     /// Ignore from directory listings.
     listing: Option<bool>,
 
+    /// Position of this page in menus, lower weights are listed first.
+    pub weight: Option<i64>,
+
     /// Ignore from collections indexing.
     //ignore_collections_index: Option<bool>,
     pub query: Option<QueryList>,
 
     pub layout: Option<String>,
+
+    /// Template engine used to render this page.
+    pub engine: Option<String>,
+
     pub taxonomies: Option<HashMap<String, Vec<String>>>,
 
     #[serde(skip_serializing_if = "IndexSet::is_empty")]
@@ -86,6 +105,10 @@ pub struct Page {
     #[serde(skip_serializing)]
     pub permalink: Option<UrlPath>,
 
+    /// Explicit output path relative to the build target.
+    #[serde(alias = "path", skip_serializing)]
+    pub output: Option<String>,
+
     // Custom values for feed entry
     pub entry: Option<FeedEntry>,
 
@@ -112,6 +135,17 @@ pub struct Page {
     pub paginate: Option<PaginateInfo>,
     #[serde(skip_deserializing)]
     pub feed: Option<Feed>,
+    /// Source files keyed by feed item identifier for the items
+    /// that include the full content, rendered with the feed.
+    #[serde(skip)]
+    pub feed_sources: Option<HashMap<String, PathBuf>>,
+    /// Plain text excerpt from the summary or page content.
+    #[serde(skip_deserializing)]
+    pub excerpt: Option<String>,
+    /// Links to other files in the source, only collected
+    /// when scanning for orphaned resources.
+    #[serde(skip)]
+    pub references: Option<Vec<String>>,
 
     #[serde(flatten)]
     pub extra: Map<String, Value>,
@@ -121,6 +155,25 @@ pub struct Page {
     synthetic: bool,
 }
 
+/// Evaluate a page condition, the condition is the name of a
+/// variable that is truthy when set to a value other than an
+/// empty string, `0`, `false`, `no` or `off`.
+fn condition<F>(when: &str, lookup: F) -> bool
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let when = when.trim();
+    let (negate, name) = match when.strip_prefix('!') {
+        Some(name) => (true, name.trim()),
+        None => (false, when),
+    };
+    let truthy = lookup(name).map_or(false, |value| {
+        let value = value.trim().to_lowercase();
+        !matches!(value.as_str(), "" | "0" | "false" | "no" | "off")
+    });
+    truthy != negate
+}
+
 impl Default for Page {
     fn default() -> Self {
         Self {
@@ -137,7 +190,11 @@ impl Default for Page {
             render: Some(true),
             draft: None,
             standalone: None,
+            profiles: None,
+            when: None,
+            cache: None,
             listing: None,
+            weight: None,
             //ignore_collections_index: None,
             noindex: None,
             print: None,
@@ -145,11 +202,13 @@ impl Default for Page {
             absolute: None,
             query: None,
             layout: None,
+            engine: None,
             taxonomies: None,
             links: IndexSet::new(),
             scripts: IndexSet::new(),
             styles: IndexSet::new(),
             permalink: None,
+            output: None,
             entry: None,
 
             created: None,
@@ -162,6 +221,9 @@ impl Default for Page {
             //canonical: None,
             paginate: None,
             feed: None,
+            feed_sources: None,
+            excerpt: None,
+            references: None,
 
             synthetic: false,
         }
@@ -198,7 +260,7 @@ impl Page {
         &self.file.as_ref().unwrap().target
     }
 
-    pub fn authors(&self) -> &Option<Vec<String>> {
+    pub fn authors(&self) -> &Option<Authors> {
         &self.authors
     }
 
@@ -257,6 +319,28 @@ impl Page {
         self.draft.is_some() && self.draft.unwrap()
     }
 
+    pub fn noindex(&self) -> bool {
+        self.noindex.is_some() && self.noindex.unwrap()
+    }
+
+    /// Determine if the page should be generated for a profile
+    /// and the current environment.
+    pub fn is_active(&self, profile: &ProfileName) -> bool {
+        let name = profile.to_string();
+        let in_profile = self
+            .profiles
+            .as_ref()
+            .map_or(true, |profiles| profiles.iter().any(|p| p == &name));
+        in_profile
+            && self.when.as_ref().map_or(true, |when| {
+                condition(when, |name| std::env::var(name).ok())
+            })
+    }
+
+    pub fn cache(&self) -> Option<&str> {
+        self.cache.as_deref()
+    }
+
     /*
     pub fn ignore_collections_index(&self) -> bool {
         self.ignore_collections_index.is_some() && self.ignore_collections_index.unwrap()
@@ -327,12 +411,46 @@ impl Page {
             og.entry(crate::OG_DESCRIPTION.to_string())
                 .or_insert(description.clone());
         }
-        if let Some(ref image) = self.image {
-            let img = website.join(image.as_str().trim_start_matches("/"))?;
+
+        // Fallback to the site default image
+        let social = config.social().as_ref();
+        let image = self
+            .image
+            .as_ref()
+            .or_else(|| social.and_then(|s| s.image().as_ref()));
+        let image = if let Some(image) = image {
+            Some(website.join(image.as_str().trim_start_matches('/'))?)
+        } else {
+            None
+        };
+
+        if let Some(ref img) = image {
             og.entry(crate::OG_IMAGE.to_string())
                 .or_insert(img.to_string());
         }
 
+        if let Some(social) = social {
+            let meta = self.meta.get_or_insert(Default::default());
+            meta.entry(crate::TWITTER_CARD.to_string())
+                .or_insert(social.card(image.is_some()).as_str().to_string());
+            if let Some(ref site) = social.twitter() {
+                meta.entry(crate::TWITTER_SITE.to_string())
+                    .or_insert(site.to_string());
+            }
+            if let Some(ref title) = self.title {
+                meta.entry(crate::TWITTER_TITLE.to_string())
+                    .or_insert(title.clone());
+            }
+            if let Some(ref description) = self.description {
+                meta.entry(crate::TWITTER_DESCRIPTION.to_string())
+                    .or_insert(description.clone());
+            }
+            if let Some(ref img) = image {
+                meta.entry(crate::TWITTER_IMAGE.to_string())
+                    .or_insert(img.to_string());
+            }
+        }
+
         self.file = Some(file_context);
         self.href = Some(href);
 
@@ -350,6 +468,29 @@ impl Page {
         Ok(())
     }
 
+    /// Assign the href for a sealed page, for example, when the href
+    /// is computed from a permalink pattern; the canonical URL is
+    /// updated to match.
+    pub fn set_href(
+        &mut self,
+        config: &Config,
+        options: &RuntimeOptions,
+        href: String,
+    ) -> Result<()> {
+        let website = options.settings.get_host_url(config, None)?;
+        let canonical = website.join(&options.settings.canonical_href(&href))?;
+
+        let og = self.open_graph.get_or_insert(Default::default());
+        og.insert(crate::OG_URL.to_string(), canonical.to_string());
+
+        self.links.retain(|link| !link.is_canonical());
+        self.links
+            .insert(LinkTag::new_canonical(canonical.to_string()));
+
+        self.href = Some(href);
+        Ok(())
+    }
+
     /// Compute is called after the loaded data inheritance has been handled
     /// and can be used to finalize default values for a page.
     pub fn compute(
@@ -371,6 +512,43 @@ impl Page {
         Ok(())
     }
 
+    /// Compute the excerpt for this page.
+    ///
+    /// An explicit summary is used without markup otherwise the content
+    /// before the excerpt marker or the leading words of the content
+    /// are used; template expressions are removed and markdown content
+    /// is rendered before markup is removed.
+    pub fn compute_excerpt(
+        &mut self,
+        config: &Config,
+        content: &str,
+        markdown: bool,
+    ) {
+        let excerpt = config.excerpt();
+        if let Some(ref summary) = self.summary {
+            let html = crate::markdown::render(
+                &mut Cow::from(summary.as_str()),
+                config,
+            );
+            let text = crate::excerpt::strip_html(&html);
+            self.excerpt = if text.is_empty() { None } else { Some(text) };
+            return;
+        }
+
+        let content = crate::excerpt::strip_template(content);
+        let content = if markdown {
+            // Only render the content before the marker
+            let source = match content.find(excerpt.marker()) {
+                Some(pos) => &content[..pos + excerpt.marker().len()],
+                None => &content[..],
+            };
+            crate::markdown::render(&mut Cow::from(source), config)
+        } else {
+            content
+        };
+        self.excerpt = excerpt.excerpt(&content);
+    }
+
     pub fn append(&mut self, other: &mut Self) {
         // NOTE: handle lists like this so precedence is correct
 
@@ -441,10 +619,26 @@ impl Page {
             self.standalone = Some(mem::take(standalone));
         }
 
+        if let Some(profiles) = other.profiles.as_mut() {
+            self.profiles = Some(mem::take(profiles));
+        }
+
+        if let Some(when) = other.when.as_mut() {
+            self.when = Some(mem::take(when));
+        }
+
+        if let Some(cache) = other.cache.as_mut() {
+            self.cache = Some(mem::take(cache));
+        }
+
         if let Some(listing) = other.listing.as_mut() {
             self.listing = Some(mem::take(listing));
         }
 
+        if let Some(weight) = other.weight.as_mut() {
+            self.weight = Some(mem::take(weight));
+        }
+
         if let Some(noindex) = other.noindex.as_mut() {
             self.noindex = Some(mem::take(noindex));
         }
@@ -473,6 +667,10 @@ impl Page {
             self.layout = Some(mem::take(layout));
         }
 
+        if let Some(engine) = other.engine.as_mut() {
+            self.engine = Some(mem::take(engine));
+        }
+
         if let Some(taxonomies) = other.taxonomies.as_mut() {
             self.taxonomies = Some(mem::take(taxonomies));
         }
@@ -481,6 +679,10 @@ impl Page {
             self.permalink = Some(mem::take(permalink));
         }
 
+        if let Some(output) = other.output.as_mut() {
+            self.output = Some(mem::take(output));
+        }
+
         if let Some(entry) = other.entry.as_mut() {
             self.entry = Some(mem::take(entry));
         }
@@ -495,3 +697,22 @@ impl Page {
         self.extra.append(&mut other.extra);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::condition;
+
+    #[test]
+    fn page_condition() {
+        let lookup = |name: &str| match name {
+            "STAGING" => Some("1".to_string()),
+            "PREVIEW" => Some("off".to_string()),
+            _ => None,
+        };
+        assert!(condition("STAGING", lookup));
+        assert!(!condition("!STAGING", lookup));
+        assert!(!condition("PREVIEW", lookup));
+        assert!(!condition("MISSING", lookup));
+        assert!(condition("! MISSING", lookup));
+    }
+}
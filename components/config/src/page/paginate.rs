@@ -34,4 +34,7 @@ pub struct PaginateInfo {
     // Links for next and previous pages when available
     pub prev: Option<PageLink>,
     pub next: Option<PageLink>,
+    // Links for the first and last pages
+    pub first_page: Option<PageLink>,
+    pub last_page: Option<PageLink>,
 }
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::matcher::GlobPatternMatcher;
+
+/// Behavior when a page matched by a permalink pattern
+/// does not declare a `created` date.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MissingDate {
+    /// Use the modification time of the page file.
+    Modified,
+    /// Treat the missing date as an error.
+    Error,
+}
+
+impl Default for MissingDate {
+    fn default() -> Self {
+        Self::Modified
+    }
+}
+
+/// Settings for computing page URLs from a pattern such
+/// as `/:year/:month/:slug/`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PermalinkConfig {
+    /// Pattern for the page URL.
+    pattern: String,
+    /// Behavior for pages without a created date.
+    missing_date: MissingDate,
+
+    /// Pages the pattern applies to; matched against the page href.
+    #[serde(flatten)]
+    matcher: GlobPatternMatcher,
+}
+
+impl Default for PermalinkConfig {
+    fn default() -> Self {
+        Self {
+            pattern: "/:year/:month/:day/:slug/".to_string(),
+            missing_date: Default::default(),
+            matcher: Default::default(),
+        }
+    }
+}
+
+impl PermalinkConfig {
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn missing_date(&self) -> &MissingDate {
+        &self.missing_date
+    }
+
+    pub fn matcher(&self) -> &GlobPatternMatcher {
+        &self.matcher
+    }
+
+    // Prepare the configuration by compiling the glob matchers.
+    pub(crate) fn prepare(&mut self) {
+        self.matcher.compile();
+    }
+
+    /// Resolve the pattern to a URL path for a page date and
+    /// title; the title is converted to a slug.
+    pub fn resolve(&self, date: &DateTime<Utc>, title: &str) -> String {
+        let href = self
+            .pattern
+            .replace(":year", &format!("{:04}", date.year()))
+            .replace(":month", &format!("{:02}", date.month()))
+            .replace(":day", &format!("{:02}", date.day()))
+            .replace(":slug", &slug::slugify(title));
+        format!("/{}", href.trim_start_matches('/'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn resolve_pattern() {
+        let mut permalink = PermalinkConfig {
+            pattern: "/:year/:month/:slug/".to_string(),
+            ..Default::default()
+        };
+
+        let date = Utc.ymd(2021, 3, 7).and_hms(9, 30, 0);
+        assert_eq!(
+            "/2021/03/hello-world/",
+            permalink.resolve(&date, "Hello World")
+        );
+
+        permalink.pattern = ":year-:month-:day/:slug.html".to_string();
+        assert_eq!(
+            "/2021-03-07/hello-world.html",
+            permalink.resolve(&date, "Hello World")
+        );
+    }
+}
@@ -9,12 +9,13 @@ use semver::VersionReq;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 
-use crate::{utils::href::UrlPath, Error, Result};
+use crate::{profile::ProfileSettings, utils::href::UrlPath, Error, Result};
 
 use super::features::{FeatureFlags, FeatureMap};
 use super::plugin_spec::{ExactPluginSpec, PluginSpec};
 
-const FEATURE_STACK_SIZE: usize = 16;
+/// Default maximum depth of nested features.
+pub const FEATURE_STACK_SIZE: usize = 16;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DependencyDefinitionMap {
@@ -108,6 +109,27 @@ impl DependencyMap {
         });
     }
 
+    /// Dependency map with the feature flags for a build profile
+    /// assigned to the named dependencies.
+    pub fn for_profile(
+        &self,
+        settings: &ProfileSettings,
+    ) -> Result<DependencyMap> {
+        let mut out = self.clone();
+        if let Some(ref features) = settings.features {
+            for (name, flags) in features.iter() {
+                let dep = out.items.get_mut(name).ok_or_else(|| {
+                    Error::ProfileFeatureNoDependency(
+                        settings.name.to_string(),
+                        name.to_string(),
+                    )
+                })?;
+                dep.features = Some(flags.clone());
+            }
+        }
+        Ok(out)
+    }
+
     /// Recursive feature resolver.
     fn resolver(
         &self,
@@ -116,10 +138,11 @@ impl DependencyMap {
         features: &Vec<String>,
         out: &mut DependencyMap,
         stack: &mut Vec<String>,
+        max_depth: usize,
     ) -> Result<()> {
         features.iter().try_for_each(|n| {
-            if stack.len() > FEATURE_STACK_SIZE {
-                return Err(Error::FeatureStackTooLarge(FEATURE_STACK_SIZE));
+            if stack.len() > max_depth {
+                return Err(Error::FeatureStackTooLarge(max_depth));
             } else if stack.contains(n) {
                 return Err(Error::CyclicFeature(n.to_string()));
             }
@@ -128,7 +151,7 @@ impl DependencyMap {
                 out.items.insert(n.clone(), dep.clone());
             } else if let Some(item) = map.get(n) {
                 stack.push(n.clone());
-                self.resolver(src, map, item, out, stack)?;
+                self.resolver(src, map, item, out, stack, max_depth)?;
                 stack.pop();
             } else {
                 return Err(Error::NoFeature(src.to_string(), n.to_string()));
@@ -145,18 +168,27 @@ impl DependencyMap {
         src: &Dependency,
         map: &FeatureMap,
         features: &Vec<String>,
+        max_depth: usize,
     ) -> Result<DependencyMap> {
         let mut out: DependencyMap = Default::default();
-        self.resolver(src, map, features, &mut out, &mut Default::default())?;
+        self.resolver(
+            src,
+            map,
+            features,
+            &mut out,
+            &mut Default::default(),
+            max_depth,
+        )?;
         Ok(out)
     }
 
     /// Filter this dependency map using the feature flags from a
-    /// source dependency.
+    /// source dependency; nested features may not exceed `max_depth`.
     pub fn filter(
         &self,
         src: &Dependency,
         map: &FeatureMap,
+        max_depth: usize,
     ) -> Result<DependencyMap> {
         let flags = &src.features;
 
@@ -182,7 +214,7 @@ impl DependencyMap {
         // Assign default features if required and available
         if default_features {
             if let Some(default) = defaults {
-                let deps = self.resolve(src, map, default)?;
+                let deps = self.resolve(src, map, default, max_depth)?;
                 out.append(deps);
             }
         }
@@ -190,7 +222,7 @@ impl DependencyMap {
         // Resolve requested features
         if let Some(ref specs) = flags {
             if let Some(ref include_flags) = specs.flags {
-                let deps = self.resolve(src, map, include_flags)?;
+                let deps = self.resolve(src, map, include_flags, max_depth)?;
                 out.append(deps);
             }
         }
@@ -215,6 +247,15 @@ pub enum DependencyTarget {
     Local { scope: String },
 }
 
+/// Capabilities a project may grant to a plugin dependency,
+/// all capabilities are denied by default.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Allow the post-install script of the plugin to run.
+    PostInstall,
+}
+
 #[serde_as]
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case")]
@@ -237,6 +278,9 @@ pub struct Dependency {
     /// Patterns that determine how styles, scripts and layouts
     /// are applied to pages.
     pub apply: Option<Apply>,
+
+    /// Capabilities granted to the plugin.
+    pub enable: Option<Vec<Capability>>,
 }
 
 impl From<DependencyTarget> for Dependency {
@@ -247,6 +291,7 @@ impl From<DependencyTarget> for Dependency {
             optional: None,
             features: None,
             apply: None,
+            enable: None,
         }
     }
 }
@@ -259,6 +304,7 @@ impl Dependency {
             optional: None,
             features: None,
             apply: None,
+            enable: None,
         }
     }
 
@@ -269,6 +315,7 @@ impl Dependency {
             optional: None,
             features: None,
             apply: None,
+            enable: None,
         }
     }
 
@@ -279,6 +326,7 @@ impl Dependency {
             optional: Some(true),
             features: None,
             apply: None,
+            enable: None,
         }
     }
 
@@ -286,6 +334,15 @@ impl Dependency {
         &self.target
     }
 
+    /// Determine if this dependency refers to a plugin folder
+    /// on the local file system.
+    pub fn is_path(&self) -> bool {
+        if let Some(DependencyTarget::File { .. }) = self.target {
+            return true;
+        }
+        false
+    }
+
     pub fn range(&self) -> &VersionReq {
         &self.version
     }
@@ -297,6 +354,13 @@ impl Dependency {
     pub fn apply(&self) -> &Option<Apply> {
         &self.apply
     }
+
+    /// Determine if a capability has been granted to the plugin.
+    pub fn is_enabled(&self, capability: Capability) -> bool {
+        self.enable
+            .as_ref()
+            .map_or(false, |enable| enable.contains(&capability))
+    }
 }
 
 impl fmt::Display for Dependency {
@@ -325,6 +389,7 @@ impl From<PluginSpec> for Dependency {
         Self {
             version: spec.range,
             apply: None,
+            enable: None,
             features: None,
             optional: None,
             target: None,
@@ -343,6 +408,7 @@ impl From<ExactPluginSpec> for Dependency {
         Self {
             version,
             apply: None,
+            enable: None,
             features: None,
             optional: None,
             target: None,
@@ -532,3 +598,122 @@ impl Apply {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use semver::VersionReq;
+
+    use super::{Dependency, DependencyMap};
+    use crate::{
+        plugin::features::{FeatureFlags, FeatureMap},
+        profile::{ProfileName, ProfileSettings},
+        Error, Result,
+    };
+
+    #[test]
+    fn feature_depth() -> Result<()> {
+        let mut optional = Dependency::new(VersionReq::any());
+        optional.optional = Some(true);
+        let mut dependencies: DependencyMap = Default::default();
+        dependencies.entry("dep".to_string()).or_insert(optional);
+
+        // Three nested features before reaching the dependency
+        let mut features: FeatureMap = Default::default();
+        for (name, item) in [("a", "b"), ("b", "c"), ("c", "dep")] {
+            features
+                .entry(name.to_string())
+                .or_insert(vec![item.to_string()]);
+        }
+
+        let mut src = Dependency::new(VersionReq::any());
+        src.features = Some(FeatureFlags {
+            default_features: None,
+            flags: Some(vec!["a".to_string()]),
+        });
+
+        let out = dependencies.filter(&src, &features, 3)?;
+        assert!(out.contains_key("dep"));
+        assert!(matches!(
+            dependencies.filter(&src, &features, 2),
+            Err(Error::FeatureStackTooLarge(2))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn profile_features() -> Result<()> {
+        // Plugin with an optional widget enabled by a feature
+        let mut widget = Dependency::new(VersionReq::any());
+        widget.optional = Some(true);
+        let mut plugin_deps: DependencyMap = Default::default();
+        plugin_deps.entry("widget".to_string()).or_insert(widget);
+        let mut plugin_features: FeatureMap = Default::default();
+        plugin_features
+            .entry("widget".to_string())
+            .or_insert(vec!["widget".to_string()]);
+
+        let mut project: DependencyMap = Default::default();
+        project
+            .entry("site".to_string())
+            .or_insert(Dependency::new(VersionReq::any()));
+
+        let debug = ProfileSettings::from(&ProfileName::Debug);
+        let mut fast =
+            ProfileSettings::from(&ProfileName::Custom("fast".to_string()));
+        let mut features = HashMap::new();
+        features.insert(
+            "site".to_string(),
+            FeatureFlags {
+                default_features: None,
+                flags: Some(vec!["widget".to_string()]),
+            },
+        );
+        fast.features = Some(features);
+
+        let deps = project.for_profile(&debug)?;
+        let out = plugin_deps.filter(
+            deps.get("site").unwrap(),
+            &plugin_features,
+            16,
+        )?;
+        assert!(!out.contains_key("widget"));
+
+        let deps = project.for_profile(&fast)?;
+        let out = plugin_deps.filter(
+            deps.get("site").unwrap(),
+            &plugin_features,
+            16,
+        )?;
+        assert!(out.contains_key("widget"));
+
+        // Unknown feature for the plugin
+        fast.features.as_mut().unwrap().insert(
+            "site".to_string(),
+            FeatureFlags {
+                default_features: None,
+                flags: Some(vec!["missing".to_string()]),
+            },
+        );
+        let deps = project.for_profile(&fast)?;
+        assert!(matches!(
+            plugin_deps.filter(deps.get("site").unwrap(), &plugin_features, 16),
+            Err(Error::NoFeature(_, _))
+        ));
+
+        // Features for a dependency that is not used by the project
+        fast.features.as_mut().unwrap().insert(
+            "other".to_string(),
+            FeatureFlags {
+                default_features: None,
+                flags: None,
+            },
+        );
+        assert!(matches!(
+            project.for_profile(&fast),
+            Err(Error::ProfileFeatureNoDependency(_, _))
+        ));
+        Ok(())
+    }
+}
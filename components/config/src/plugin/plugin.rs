@@ -181,6 +181,11 @@ pub struct Plugin {
     /// Directives for blueprint plugins
     blueprint: Option<BlueprintDirectives>,
 
+    /// Script to run in the installation directory once the
+    /// plugin is installed, requires the `post-install` capability.
+    #[serde(rename = "post-install")]
+    post_install: Option<PostInstall>,
+
     /// Base path this plugin was loaded from,
     /// used to resolve assets during collation.
     #[serde(skip)]
@@ -224,6 +229,7 @@ impl Default for Plugin {
             base: PathBuf::new(),
             compute: Default::default(),
             blueprint: None,
+            post_install: None,
             checksum: None,
             source: None,
             prefix: None,
@@ -399,6 +405,10 @@ impl Plugin {
         &mut self.blueprint
     }
 
+    pub fn post_install(&self) -> &Option<PostInstall> {
+        &self.post_install
+    }
+
     /// Generate a qualified name relative to the plugin name.
     pub fn qualified(&self, val: &str) -> String {
         format!("{}::{}", &self.name, val)
@@ -483,6 +493,36 @@ impl BlueprintDirectives {
     }
 }
 
+/// Default number of seconds a post-install script may run.
+const POST_INSTALL_TIMEOUT: u64 = 60;
+
+/// Command run after a plugin has been installed.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct PostInstall {
+    /// Program to execute.
+    command: String,
+    /// Arguments passed to the program.
+    #[serde(default)]
+    args: Vec<String>,
+    /// Maximum number of seconds the command may run.
+    timeout: Option<u64>,
+}
+
+impl PostInstall {
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn args(&self) -> &Vec<String> {
+        &self.args
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout.unwrap_or(POST_INSTALL_TIMEOUT)
+    }
+}
+
 // So we can skip serializing default values for the compute
 // directive boolean flags.
 fn is_true(b: &bool) -> bool {
@@ -3,9 +3,10 @@ use std::convert::From;
 use std::convert::Infallible;
 use std::fmt;
 use std::mem;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize, Serializer};
 use serde_with::skip_serializing_none;
 
@@ -13,7 +14,13 @@ use url::Url;
 
 use crate::{
     config::{self, Config},
-    server::SslConfig,
+    diagnostics::Diagnostics,
+    events::Events,
+    link::TrailingSlash,
+    options::FileType,
+    plugin::features::FeatureFlags,
+    redirect::RedirectFormat,
+    server::{ProxyConfig, SslConfig},
     utils::matcher::GlobPatternMatcher,
 };
 
@@ -166,6 +173,7 @@ pub struct ProfileSettings {
     pub include_drafts: Option<bool>,
 
     pub types: Option<RenderTypes>,
+    pub extensions: Option<ExtensionConfig>,
     pub strict: Option<bool>,
     pub parallel: Option<bool>,
     pub offline: Option<bool>,
@@ -183,6 +191,17 @@ pub struct ProfileSettings {
     pub launch: Option<String>,
     pub release: Option<bool>,
 
+    /// Minify HTML output, when not set release builds are minified.
+    pub minify: Option<bool>,
+
+    /// Inline the critical styles in each page and defer loading
+    /// the full style sheets.
+    pub critical_style: Option<bool>,
+
+    /// Write the template data for each page to a sidecar JSON
+    /// file, ignored for release builds.
+    pub dump_context: Option<bool>,
+
     // Name for the default layout, when not specified
     // and `layouts/main.hbs` exists it will be used
     // otherwise the default layout name `std::core::main`
@@ -191,6 +210,9 @@ pub struct ProfileSettings {
 
     pub rewrite_index: Option<bool>,
     pub include_index: Option<bool>,
+
+    /// Canonical form for the URLs of directory pages.
+    pub trailing_slash: Option<TrailingSlash>,
     /// Should we pass the commit hash to page templates.
     pub include_commit: Option<bool>,
 
@@ -200,6 +222,9 @@ pub struct ProfileSettings {
 
     pub write_redirect_files: Option<bool>,
 
+    /// Formats for the redirect files written to the build target.
+    pub redirect_formats: Option<Vec<RedirectFormat>>,
+
     // Base URL to strip when building links etc
     pub base: Option<String>,
 
@@ -214,10 +239,50 @@ pub struct ProfileSettings {
     pub scheme: Option<String>,
     pub tls: Option<SslConfig>,
 
+    /// Path prefixes the development server forwards to a
+    /// backend server.
+    pub proxy: Option<Vec<ProxyConfig>>,
+
     pub resources: Option<Resources>,
 
+    /// Report resources that are not referenced by any page.
+    pub report_orphans: Option<bool>,
+
+    /// Write a manifest of output files with content checksums.
+    pub output_manifest: Option<bool>,
+
+    /// Remove only the outputs of the previous build rather than
+    /// the entire build target; requires the output manifest.
+    pub clean_stale: Option<bool>,
+
+    /// Paths relative to the build target that are not build
+    /// outputs and are never removed when cleaning stale outputs.
+    pub keep: Option<Vec<Glob>>,
+
+    /// Record durations for the phases of a build.
+    pub timings: Option<bool>,
+
+    /// Write the build output to memory rather than the build
+    /// target, used by the development server.
+    pub memory: Option<bool>,
+
+    /// Filter the pages processed for this profile.
+    pub sources: Option<SourceFilter>,
+
+    /// Plugin feature flags for this profile keyed by dependency
+    /// name; replaces the features declared by the dependency.
+    pub features: Option<HashMap<String, FeatureFlags>>,
+
     /// List of workspace members to filter.
     pub member: Vec<String>,
+
+    /// Collector for build warnings and errors.
+    #[serde(skip)]
+    pub diagnostics: Diagnostics,
+
+    /// Channel for build progress events.
+    #[serde(skip)]
+    pub events: Events,
 }
 
 impl From<&ProfileName> for ProfileSettings {
@@ -228,8 +293,13 @@ impl From<&ProfileName> for ProfileSettings {
         };
 
         match name {
+            ProfileName::Debug => {
+                settings.include_drafts = Some(true);
+                settings.minify = Some(false);
+            }
             ProfileName::Release => {
                 settings.release = Some(true);
+                settings.minify = Some(true);
             }
             ProfileName::Dist => {
                 settings.include_index = Some(true);
@@ -250,6 +320,7 @@ impl Default for ProfileSettings {
             exec: None,
             include_drafts: None,
             types: Some(Default::default()),
+            extensions: Some(Default::default()),
             strict: None,
             parallel: None,
             offline: None,
@@ -269,22 +340,38 @@ impl Default for ProfileSettings {
             port: Some(config::PORT),
             scheme: Some(config::SCHEME_HTTPS.to_string()),
             tls: None,
+            proxy: None,
 
             live: None,
             launch: None,
             release: None,
+            minify: None,
+            dump_context: None,
+            critical_style: None,
             layout: None,
             include_index: None,
+            trailing_slash: None,
             incremental: None,
             pristine: None,
             force: None,
             write_redirect_files: None,
+            redirect_formats: None,
             base: None,
             paths: None,
             base_href: None,
 
             resources: None,
+            report_orphans: None,
+            output_manifest: None,
+            clean_stale: None,
+            keep: None,
+            timings: None,
+            memory: None,
+            sources: None,
+            features: None,
             member: Vec::new(),
+            diagnostics: Default::default(),
+            events: Default::default(),
 
             include_commit: None,
         }
@@ -314,6 +401,18 @@ impl ProfileSettings {
             && self.write_redirect_files.unwrap()
     }
 
+    /// Redirect file formats for this profile, when no formats
+    /// are given `write-redirect-files` selects the HTML pages.
+    pub fn redirect_formats(&self) -> Vec<RedirectFormat> {
+        if let Some(ref formats) = self.redirect_formats {
+            formats.clone()
+        } else if self.write_redirect_files() {
+            vec![RedirectFormat::Html]
+        } else {
+            Vec::new()
+        }
+    }
+
     /// Determine if drafts should be included.
     pub fn include_drafts(&self) -> bool {
         self.include_drafts.is_some() && self.include_drafts.unwrap()
@@ -341,6 +440,9 @@ impl ProfileSettings {
         if other.types.is_some() {
             self.types = mem::take(&mut other.types)
         }
+        if other.extensions.is_some() {
+            self.extensions = mem::take(&mut other.extensions)
+        }
         if other.strict.is_some() {
             self.strict = mem::take(&mut other.strict)
         }
@@ -389,6 +491,9 @@ impl ProfileSettings {
         if other.tls.is_some() {
             self.tls = mem::take(&mut other.tls)
         }
+        if other.proxy.is_some() {
+            self.proxy = mem::take(&mut other.proxy)
+        }
 
         if other.live.is_some() {
             self.live = mem::take(&mut other.live)
@@ -396,12 +501,21 @@ impl ProfileSettings {
         if other.release.is_some() {
             self.release = mem::take(&mut other.release)
         }
+        if other.minify.is_some() {
+            self.minify = mem::take(&mut other.minify)
+        }
+        if other.dump_context.is_some() {
+            self.dump_context = mem::take(&mut other.dump_context)
+        }
         if other.layout.is_some() {
             self.layout = mem::take(&mut other.layout)
         }
         if other.include_index.is_some() {
             self.include_index = mem::take(&mut other.include_index)
         }
+        if other.trailing_slash.is_some() {
+            self.trailing_slash = mem::take(&mut other.trailing_slash)
+        }
         if other.incremental.is_some() {
             self.incremental = mem::take(&mut other.incremental)
         }
@@ -415,6 +529,9 @@ impl ProfileSettings {
             self.write_redirect_files =
                 mem::take(&mut other.write_redirect_files)
         }
+        if other.redirect_formats.is_some() {
+            self.redirect_formats = mem::take(&mut other.redirect_formats)
+        }
         if other.base.is_some() {
             self.base = mem::take(&mut other.base)
         }
@@ -428,9 +545,44 @@ impl ProfileSettings {
         if other.resources.is_some() {
             self.resources = mem::take(&mut other.resources)
         }
+        if other.report_orphans.is_some() {
+            self.report_orphans = mem::take(&mut other.report_orphans)
+        }
+        if other.output_manifest.is_some() {
+            self.output_manifest = mem::take(&mut other.output_manifest)
+        }
+        if other.clean_stale.is_some() {
+            self.clean_stale = mem::take(&mut other.clean_stale)
+        }
+        if other.critical_style.is_some() {
+            self.critical_style = mem::take(&mut other.critical_style)
+        }
+        if other.keep.is_some() {
+            self.keep = mem::take(&mut other.keep)
+        }
+        if other.timings.is_some() {
+            self.timings = mem::take(&mut other.timings)
+        }
+        if other.memory.is_some() {
+            self.memory = mem::take(&mut other.memory)
+        }
+        if other.sources.is_some() {
+            self.sources = mem::take(&mut other.sources)
+        }
+        if other.features.is_some() {
+            self.features = mem::take(&mut other.features)
+        }
 
         self.member = mem::take(&mut other.member);
 
+        if other.diagnostics.is_enabled() {
+            self.diagnostics = other.diagnostics.clone();
+        }
+
+        if other.events.is_active() {
+            self.events = other.events.clone();
+        }
+
         if other.include_commit.is_some() {
             self.include_commit = mem::take(&mut other.include_commit);
         }
@@ -528,12 +680,38 @@ impl ProfileSettings {
         self.release.is_some() && self.release.unwrap()
     }
 
+    pub fn should_minify(&self) -> bool {
+        if let Some(minify) = self.minify {
+            minify
+        } else {
+            self.is_release()
+        }
+    }
+
+    pub fn should_dump_context(&self) -> bool {
+        self.dump_context.is_some()
+            && self.dump_context.unwrap()
+            && !self.is_release()
+    }
+
+    pub fn should_inline_critical_style(&self) -> bool {
+        self.critical_style.is_some() && self.critical_style.unwrap()
+    }
+
     pub fn is_force(&self) -> bool {
         self.force.is_some() && self.force.unwrap()
     }
 
+    /// Incremental builds compare sources with the output files
+    /// on disk so they are disabled when writing to memory.
     pub fn is_incremental(&self) -> bool {
-        self.incremental.is_some() && self.incremental.unwrap()
+        self.incremental.is_some()
+            && self.incremental.unwrap()
+            && !self.is_memory()
+    }
+
+    pub fn is_memory(&self) -> bool {
+        self.memory.is_some() && self.memory.unwrap()
     }
 
     pub fn is_pristine(&self) -> bool {
@@ -547,6 +725,43 @@ impl ProfileSettings {
     pub fn should_rewrite_index(&self) -> bool {
         self.rewrite_index.is_some() && self.rewrite_index.unwrap()
     }
+
+    /// Convert an href to the canonical form when a trailing
+    /// slash policy is configured.
+    pub fn canonical_href(&self, href: &str) -> String {
+        match self.trailing_slash {
+            Some(policy) => policy.apply(href),
+            None => href.to_string(),
+        }
+    }
+
+    pub fn should_report_orphans(&self) -> bool {
+        self.report_orphans.is_some() && self.report_orphans.unwrap()
+    }
+
+    pub fn should_write_output_manifest(&self) -> bool {
+        ((self.output_manifest.is_some() && self.output_manifest.unwrap())
+            || self.is_clean_stale())
+            && !self.is_memory()
+    }
+
+    pub fn is_clean_stale(&self) -> bool {
+        self.clean_stale.is_some() && self.clean_stale.unwrap()
+    }
+
+    /// Compile the keep list to match paths relative to the build
+    /// target that are not build outputs.
+    pub fn keep_matcher(&self) -> crate::Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for glob in self.keep.iter().flatten() {
+            builder.add(glob.clone());
+        }
+        Ok(builder.build()?)
+    }
+
+    pub fn should_record_timings(&self) -> bool {
+        self.timings.is_some() && self.timings.unwrap()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -596,6 +811,55 @@ impl RenderTypes {
     }
 }
 
+/// File extensions for each kind of asset file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ExtensionConfig {
+    pub stylesheet: Vec<String>,
+    pub script: Vec<String>,
+    pub image: Vec<String>,
+    pub font: Vec<String>,
+    pub data: Vec<String>,
+}
+
+impl Default for ExtensionConfig {
+    fn default() -> Self {
+        let list = |items: &[&str]| -> Vec<String> {
+            items.iter().map(|s| s.to_string()).collect()
+        };
+        Self {
+            stylesheet: list(&["css"]),
+            script: list(&["js", "mjs"]),
+            image: list(&[
+                "png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "ico",
+            ]),
+            font: list(&["woff", "woff2", "ttf", "otf", "eot"]),
+            data: list(&["json", "toml", "yaml", "yml", "csv", "xml"]),
+        }
+    }
+}
+
+impl ExtensionConfig {
+    /// Classify a file that is not rendered by the file extension.
+    pub fn get_type(&self, ext: &str) -> FileType {
+        let ext = ext.to_lowercase();
+        let has = |list: &Vec<String>| list.iter().any(|e| *e == ext);
+        if has(&self.stylesheet) {
+            FileType::Stylesheet
+        } else if has(&self.script) {
+            FileType::Script
+        } else if has(&self.image) {
+            FileType::Image
+        } else if has(&self.font) {
+            FileType::Font
+        } else if has(&self.data) {
+            FileType::Data
+        } else {
+            FileType::Unknown
+        }
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
@@ -625,6 +889,10 @@ pub struct Resources {
     pub ignore: ResourceGroup,
     pub symlink: ResourceGroup,
     pub copy: ResourceGroup,
+
+    /// Commands that process copied resources keyed by the
+    /// file extension, eg: `scss`.
+    pub process: HashMap<String, ProcessCommand>,
 }
 
 impl Resources {
@@ -635,9 +903,64 @@ impl Resources {
     }
 }
 
+/// Command that processes the content of a resource.
+///
+/// The file content is written to standard input and standard
+/// output is written to the build target.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProcessCommand {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extension for the output file, eg: `css`.
+    pub extension: Option<String>,
+}
+
+/// Include and exclude patterns for page source files.
+///
+/// Patterns are matched against paths relative to the
+/// source directory, excludes take precedence. Assets,
+/// templates, locales and data files are not filtered.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SourceFilter {
+    #[serde(flatten)]
+    pub matcher: GlobPatternMatcher,
+}
+
+impl SourceFilter {
+    pub fn prepare(&mut self) {
+        self.matcher.compile();
+    }
+
+    /// Determine if a source relative path should be processed.
+    pub fn accepts<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.matcher.filter(path)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(default)]
 pub struct ResourceGroup {
     #[serde(flatten)]
     pub matcher: GlobPatternMatcher,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SourceFilter;
+    use globset::Glob;
+
+    #[test]
+    fn source_filter_exclude() {
+        let mut filter: SourceFilter = Default::default();
+        filter
+            .matcher
+            .excludes
+            .push(Glob::new("gallery/**").unwrap());
+        filter.prepare();
+        assert!(filter.accepts("index.md"));
+        assert!(!filter.accepts("gallery/index.md"));
+        assert!(!filter.accepts("gallery/2020/photo.jpg"));
+    }
+}
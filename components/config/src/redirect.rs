@@ -1,20 +1,123 @@
 use std::collections::HashMap;
 use std::convert::TryInto;
-use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 
-use log::info;
+use log::debug;
 
 use http::Uri;
 
-use crate::{Error, Result, RuntimeOptions};
+use crate::{link::TrailingSlash, Error, Result, RuntimeOptions};
 
-const MAX_REDIRECTS: usize = 4;
+/// Default maximum number of chained redirects.
+pub const MAX_REDIRECTS: usize = 4;
 pub const REDIRECTS_FILE: &str = "redirects.json";
 
+/// Redirects file for Netlify.
+pub const NETLIFY_FILE: &str = "_redirects";
+/// Configuration snippet for nginx.
+pub const NGINX_FILE: &str = "redirects.nginx.conf";
+/// Configuration file for Apache.
+pub const APACHE_FILE: &str = ".htaccess";
+
+/// Formats for the redirect files written to the build target.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum RedirectFormat {
+    /// An HTML page with a meta refresh for each redirect.
+    Html,
+    /// A `_redirects` file for Netlify.
+    Netlify,
+    /// A configuration snippet for nginx.
+    Nginx,
+    /// An `.htaccess` file for Apache.
+    Apache,
+}
+
+impl RedirectFormat {
+    /// Name of the file for formats that write a single file.
+    pub fn file_name(&self) -> Option<&'static str> {
+        match *self {
+            Self::Html => None,
+            Self::Netlify => Some(NETLIFY_FILE),
+            Self::Nginx => Some(NGINX_FILE),
+            Self::Apache => Some(APACHE_FILE),
+        }
+    }
+
+    /// Rule for a single redirect in a file format.
+    ///
+    /// When the redirect removes the trailing slash from a directory
+    /// URL the directory is also served from the location so the web
+    /// server does not redirect back to the URL with a slash.
+    fn rule(&self, source: &str, location: &str) -> String {
+        let directory = if removes_trailing_slash(source, location) {
+            Some(format!("{}/{}", location, crate::INDEX_HTML))
+        } else {
+            None
+        };
+        match (*self, directory) {
+            (Self::Html, _) => String::new(),
+            (Self::Netlify, None) => format!("{} {} 301\n", source, location),
+            (Self::Netlify, Some(index)) => format!(
+                "{} {} 301!\n{} {} 200\n",
+                source, location, location, index
+            ),
+            (Self::Nginx, None) => format!(
+                "location = {} {{ return 301 {}; }}\n",
+                source, location
+            ),
+            (Self::Nginx, Some(index)) => format!(
+                "location = {} {{ return 301 {}; }}\nlocation = {} {{ try_files {} =404; }}\n",
+                source, location, location, index
+            ),
+            // Match the whole path, a prefix match would also
+            // redirect the files in the directory
+            (Self::Apache, None) if is_trailing_slash(source, location) => {
+                format!("RedirectMatch 301 ^{}$ {}\n", source, location)
+            }
+            (Self::Apache, None) => {
+                format!("Redirect 301 {} {}\n", source, location)
+            }
+            (Self::Apache, Some(index)) => format!(
+                "RedirectMatch 301 ^{}$ {}\nRewriteRule ^{}$ {} [L]\n",
+                source,
+                location,
+                location.trim_start_matches('/'),
+                index.trim_start_matches('/')
+            ),
+        }
+    }
+
+    /// Directives before the rules of a file format.
+    fn preamble(&self, redirects: &RedirectConfig) -> &'static str {
+        let directories = redirects
+            .map()
+            .iter()
+            .any(|(k, v)| removes_trailing_slash(k, v));
+        match *self {
+            // Directories without a slash are rewritten to the index page
+            Self::Apache if directories => {
+                "DirectorySlash Off\nRewriteEngine On\n"
+            }
+            _ => "",
+        }
+    }
+}
+
+/// Determine if a redirect only changes the trailing slash.
+fn is_trailing_slash(source: &str, location: &str) -> bool {
+    source != location
+        && source.trim_end_matches('/') == location.trim_end_matches('/')
+}
+
+/// Determine if a redirect only removes the trailing slash.
+fn removes_trailing_slash(source: &str, location: &str) -> bool {
+    source.ends_with('/') && is_trailing_slash(source, location)
+}
+
 //pub type Redirects = HashMap<String, Uri>;
 
 #[serde_as]
@@ -76,24 +179,47 @@ impl RedirectConfig {
         self.manifest.map_mut()
     }
 
-    pub fn validate(&self) -> Result<()> {
+    /// Add redirects from the non-canonical form of directory
+    /// URLs to the canonical form for a trailing slash policy.
+    ///
+    /// The hrefs are page links that include the index file name,
+    /// existing redirects take precedence.
+    pub fn trailing_slash<'a, I>(&mut self, policy: TrailingSlash, hrefs: I)
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        for href in hrefs {
+            if let Some(dir) = href.strip_suffix(crate::INDEX_HTML) {
+                if dir.trim_matches('/').is_empty() {
+                    continue;
+                }
+                let canonical = policy.apply(dir);
+                if let Some(source) = policy.alternate(&canonical) {
+                    self.map_mut().entry(source).or_insert(canonical);
+                }
+            }
+        }
+    }
+
+    /// Validate the redirects do not contain cycles or
+    /// chains longer than `max_depth`.
+    pub fn validate(&self, max_depth: usize) -> Result<()> {
         for (k, v) in self.map() {
             let mut stack: Vec<String> = Vec::new();
-            self.validate_redirect(k, v, &mut stack)?;
+            self.validate_redirect(k, v, &mut stack, max_depth)?;
         }
         Ok(())
     }
 
-    // FIXME: improve this redirect validation logic to handle
-    // FIXME: trailing slashes on sources and targets better
     fn validate_redirect<S: AsRef<str>, T: AsRef<str>>(
         &self,
         k: S,
         v: T,
         stack: &mut Vec<String>,
+        max_depth: usize,
     ) -> Result<()> {
-        if stack.len() >= MAX_REDIRECTS {
-            return Err(Error::TooManyRedirects(MAX_REDIRECTS));
+        if stack.len() >= max_depth {
+            return Err(Error::TooManyRedirects(max_depth));
         }
 
         let mut key = k.as_ref().to_string().clone();
@@ -106,73 +232,93 @@ impl RedirectConfig {
             });
         }
 
-        stack.push(key);
+        stack.push(key.clone());
 
         // Check raw value first
         if let Some(value) = self.manifest.map.get(v.as_ref()) {
-            return self.validate_redirect(v.as_ref(), value, stack);
+            return self.validate_redirect(v.as_ref(), value, stack, max_depth);
         }
 
-        // Try with a trailing slash removed
+        // Try with a trailing slash removed unless the redirect
+        // only adds a trailing slash to the source
         let mut val_key = v.as_ref().to_string();
         val_key = val_key.trim_end_matches("/").to_string();
+        if val_key == key {
+            return Ok(());
+        }
         if let Some(value) = self.manifest.map.get(&val_key) {
-            return self.validate_redirect(&val_key, value, stack);
+            return self.validate_redirect(&val_key, value, stack, max_depth);
         }
 
         Ok(())
     }
 
-    pub fn write(&self, options: &RuntimeOptions) -> Result<()> {
-        let build_target = options.build_target();
-        if options.settings.write_redirect_files() {
-            self.create_files(build_target)?;
+    /// Files for the redirects in the configured formats and the
+    /// JSON redirects used by the web server.
+    ///
+    /// Paths are relative to the build target.
+    pub fn files(
+        &self,
+        options: &RuntimeOptions,
+    ) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+        let mut files = Vec::new();
+        for format in options.settings.redirect_formats() {
+            files.append(&mut self.format_files(format));
+        }
+        files.push((
+            PathBuf::from(REDIRECTS_FILE),
+            serde_json::to_vec(&self.manifest)?,
+        ));
+        Ok(files)
+    }
+
+    /// Files for the redirects in a format relative to the build target.
+    pub fn format_files(
+        &self,
+        format: RedirectFormat,
+    ) -> Vec<(PathBuf, Vec<u8>)> {
+        match format.file_name() {
+            Some(name) => vec![(PathBuf::from(name), self.host_file(format))],
+            None => self.html_files(),
         }
-        self.create_json(build_target)?;
-        Ok(())
     }
 
-    fn create_json<P: AsRef<Path>>(&self, target: P) -> Result<()> {
-        info!(
-            "Write {} redirect(s) to {}",
-            self.map().len(),
-            target.as_ref().display()
-        );
-        let target = target.as_ref().join(REDIRECTS_FILE);
-        fs::write(target, serde_json::to_vec(&self.manifest)?)?;
-        Ok(())
+    fn host_file(&self, format: RedirectFormat) -> Vec<u8> {
+        let mut keys: Vec<&String> = self.map().keys().collect();
+        keys.sort();
+        let mut content = format.preamble(self).to_string();
+        let rules: String = keys
+            .into_iter()
+            .map(|k| format.rule(k, self.map().get(k).unwrap()))
+            .collect();
+        content.push_str(&rules);
+        content.into_bytes()
     }
 
-    fn create_files<P: AsRef<Path>>(&self, target: P) -> Result<()> {
-        let target = target.as_ref();
+    fn html_files(&self) -> Vec<(PathBuf, Vec<u8>)> {
+        let mut files = Vec::new();
         for (k, v) in self.map() {
+            // A file for a directory URL would replace the index
+            // page it redirects to, the page declares the canonical
+            // URL instead
+            if is_trailing_slash(k, v) {
+                debug!("Canonical {} -> {} in the page", &k, &v);
+                continue;
+            }
+
             // Strip the trailing slash so it is not treated
             // as an absolute path on UNIX
             let key = k.trim_start_matches("/");
-            let mut buf = target.join(utils::url::to_path_separator(key));
+            let mut buf = PathBuf::from(utils::url::to_path_separator(key));
             if k.ends_with("/") {
                 buf.push(crate::INDEX_HTML);
             }
-
-            if buf.exists() {
-                return Err(Error::RedirectFileExists(buf));
-            }
-
-            let short = buf.strip_prefix(target)?;
-            info!("{} -> {} as {}", &k, &v, short.display());
-            if let Some(ref parent) = buf.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            self.write_file(&v, &buf)?;
+            files.push((buf, self.html_file(v).into_bytes()));
         }
-        Ok(())
+        files
     }
 
-    fn write_file<P: AsRef<Path>>(
-        &self,
-        location: &str,
-        target: P,
-    ) -> std::io::Result<()> {
+    fn html_file(&self, location: &str) -> String {
         let mut content = String::from("<!doctype html>");
         let body = format!(
             "<body onload=\"document.location.replace('{}');\"></body>",
@@ -192,6 +338,119 @@ impl RedirectConfig {
         content.push_str("</head>");
         content.push_str(&body);
         content.push_str("</html>");
-        utils::fs::write_string(target, content)
+        content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        RedirectConfig, RedirectFormat, Redirects, APACHE_FILE, NETLIFY_FILE,
+    };
+    use crate::{link::TrailingSlash, Error, Result};
+
+    #[test]
+    fn redirect_depth() -> Result<()> {
+        let mut redirects: RedirectConfig = Default::default();
+        for (source, target) in [("/a", "/b"), ("/b", "/c"), ("/c", "/d")] {
+            redirects
+                .map_mut()
+                .insert(source.to_string(), target.to_string());
+        }
+
+        redirects.validate(3)?;
+        assert!(matches!(
+            redirects.validate(2),
+            Err(Error::TooManyRedirects(2))
+        ));
+        Ok(())
+    }
+
+    fn content(files: &[(PathBuf, Vec<u8>)], name: &str) -> Option<String> {
+        files
+            .iter()
+            .find(|(path, _)| path == Path::new(name))
+            .map(|(_, content)| String::from_utf8(content.clone()).unwrap())
+    }
+
+    #[test]
+    fn redirect_trailing_slash() -> Result<()> {
+        let mut redirects: RedirectConfig = Default::default();
+        redirects.trailing_slash(
+            TrailingSlash::Always,
+            vec!["/docs/index.html", "/v1.2/index.html"].into_iter(),
+        );
+        redirects
+            .map_mut()
+            .insert("/old".to_string(), "/docs".to_string());
+        redirects.validate(2)?;
+        let uris: Redirects = redirects.clone().try_into()?;
+        assert_eq!("/v1.2/", uris.items().get("/v1.2").unwrap().to_string());
+
+        let mut redirects: RedirectConfig = Default::default();
+        redirects.trailing_slash(
+            TrailingSlash::Never,
+            vec!["/docs/index.html"].into_iter(),
+        );
+        redirects.validate(2)?;
+        let uris: Redirects = redirects.clone().try_into()?;
+        assert_eq!("/docs", uris.items().get("/docs/").unwrap().to_string());
+
+        assert_eq!(
+            Some("/docs/ /docs 301!\n/docs /docs/index.html 200\n".to_string()),
+            content(
+                &redirects.format_files(RedirectFormat::Netlify),
+                NETLIFY_FILE
+            )
+        );
+        assert_eq!(
+            Some(
+                "DirectorySlash Off\nRewriteEngine On\n\
+                RedirectMatch 301 ^/docs/$ /docs\n\
+                RewriteRule ^docs$ docs/index.html [L]\n"
+                    .to_string()
+            ),
+            content(
+                &redirects.format_files(RedirectFormat::Apache),
+                APACHE_FILE
+            )
+        );
+        assert!(redirects.format_files(RedirectFormat::Html).is_empty());
+
+        // Redirects back to the trailing slash are a cycle
+        redirects
+            .map_mut()
+            .insert("/docs".to_string(), "/docs/".to_string());
+        assert!(matches!(
+            redirects.validate(4),
+            Err(Error::CyclicRedirect { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn redirect_formats() -> Result<()> {
+        let mut redirects: RedirectConfig = Default::default();
+        for (source, target) in [("/old/", "/new/"), ("/docs", "/guide/")] {
+            redirects
+                .map_mut()
+                .insert(source.to_string(), target.to_string());
+        }
+
+        assert_eq!(
+            Some("/docs /guide/ 301\n/old/ /new/ 301\n".to_string()),
+            content(
+                &redirects.format_files(RedirectFormat::Netlify),
+                NETLIFY_FILE
+            )
+        );
+        let html = redirects.format_files(RedirectFormat::Html);
+        let stub = content(&html, "old/index.html").unwrap();
+        assert!(stub.contains(r#"content="0; /new/""#));
+        assert!(content(&html, "docs").is_some());
+        Ok(())
     }
 }
@@ -1,15 +1,22 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::utils::matcher::GlobPatternMatcher;
+use crate::{
+    indexer::{DataBase, SourceProvider},
+    utils::matcher::GlobPatternMatcher,
+    Config, Error, Result,
+};
 
 const ID: &str = "site-index";
 const INDEX: &str = "/search.idx";
 const JS: &str = "/assets/plugins/std::search/scripts/search.js";
 const WASM: &str = "/assets/plugins/std::search/assets/search.wasm";
 
+/// Selector for regions that are always excluded from search indices.
+pub const IGNORE: &str = "[data-search-ignore]";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct SearchConfig {
@@ -18,6 +25,9 @@ pub struct SearchConfig {
     // The URL relative to the site root for the wasm file
     pub wasm: Option<String>,
 
+    // CSS selectors for regions excluded from search indices
+    pub ignore: Option<Vec<String>>,
+
     // Search index configurations
     #[serde(flatten)]
     pub items: HashMap<String, SearchItemConfig>,
@@ -28,12 +38,23 @@ impl Default for SearchConfig {
         Self {
             js: Some(JS.to_string()),
             wasm: Some(WASM.to_string()),
+            ignore: None,
             items: HashMap::new(),
         }
     }
 }
 
 impl SearchConfig {
+    /// Selectors for regions whose text is not indexed, elements
+    /// with a `data-search-ignore` attribute are always excluded.
+    pub fn ignore_selectors(&self) -> Vec<String> {
+        let mut selectors = vec![IGNORE.to_string()];
+        if let Some(ref ignore) = self.ignore {
+            selectors.extend(ignore.iter().cloned());
+        }
+        selectors
+    }
+
     // Prepare the configuration by assigning id fields
     // and compiling the glob matchers
     pub(crate) fn prepare(&mut self) {
@@ -42,6 +63,57 @@ impl SearchConfig {
             v.matcher.compile();
         }
     }
+
+    // Ensure index names are valid identifiers, each index is
+    // written to a different file and collections are pages
+    // collections.
+    pub(crate) fn validate(&self, db: Option<&DataBase>) -> Result<()> {
+        let mut names: Vec<&String> = self.items.keys().collect();
+        names.sort();
+
+        let mut outputs: HashMap<&str, &str> = HashMap::new();
+        for name in names {
+            let item = self.items.get(name).unwrap();
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(Error::InvalidSearchName(name.to_string()));
+            }
+
+            let index = item.index.as_deref().unwrap_or(INDEX);
+            if let Some(other) = outputs.insert(index, name) {
+                return Err(Error::DuplicateSearchIndex(
+                    other.to_string(),
+                    name.to_string(),
+                    index.to_string(),
+                ));
+            }
+
+            if let Some(ref collection) = item.collection {
+                let provider = db
+                    .and_then(|db| db.load.as_ref())
+                    .and_then(|load| load.get(collection))
+                    .ok_or_else(|| {
+                        Error::NoSearchCollection(
+                            name.to_string(),
+                            collection.to_string(),
+                        )
+                    })?;
+                match provider.source_provider() {
+                    SourceProvider::Pages => {}
+                    _ => {
+                        return Err(Error::SearchCollectionNotPages(
+                            name.to_string(),
+                            collection.to_string(),
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -53,6 +125,10 @@ pub struct SearchItemConfig {
     // Maximum number of results displayed for a query
     pub results: Option<u8>,
 
+    // Name of a pages collection, when set only pages
+    // in the collection are indexed.
+    pub collection: Option<String>,
+
     #[serde(flatten)]
     pub matcher: GlobPatternMatcher,
 
@@ -77,6 +153,7 @@ impl Default for SearchItemConfig {
             results: Some(10),
             excerpt_buffer: Some(8),
             excerpts_per_result: Some(5),
+            collection: None,
             matcher: Default::default(),
         }
     }
@@ -87,4 +164,99 @@ impl SearchItemConfig {
         let val = self.index.as_ref().unwrap().trim_start_matches("/");
         return base.join(utils::url::to_path_separator(val));
     }
+
+    /// Determine if a page belongs to the scope of this index,
+    /// the file is the page source and the href is the page URL.
+    pub fn includes<P: AsRef<Path>>(
+        &self,
+        config: &Config,
+        source: &Path,
+        file: P,
+        href: &str,
+    ) -> bool {
+        let in_collection = if let Some(ref collection) = self.collection {
+            config
+                .db
+                .as_ref()
+                .and_then(|db| db.load.as_ref())
+                .and_then(|load| load.get(collection))
+                .map_or(false, |provider| provider.includes_page(source, file))
+        } else {
+            true
+        };
+        in_collection && self.matcher.filter(href)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_util::TempProject, Error, Result};
+
+    const SITE: &str = "lang = \"en\"\nhost = \"localhost\"\n\n[db.load.blog]\nfrom = \"blog\"\n";
+
+    #[test]
+    fn scoped_indexes() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            crate::SITE_TOML,
+            format!(
+                "{}\n{}",
+                SITE,
+                r#"[search.docs]
+index = "/docs.idx"
+includes = ["/docs/**"]
+
+[search.blog]
+index = "/blog.idx"
+collection = "blog"
+"#
+            ),
+        )?;
+        let config = project.config()?;
+        let source = project.source();
+        let search = config.search.as_ref().unwrap();
+        let pages = vec![
+            (source.join("index.md"), "/"),
+            (source.join("docs").join("intro.md"), "/docs/intro/"),
+            (source.join("blog").join("hello.md"), "/blog/hello/"),
+        ];
+
+        let scoped = |name: &str| -> Vec<&str> {
+            let item = search.items.get(name).unwrap();
+            pages
+                .iter()
+                .filter(|(file, href)| {
+                    item.includes(&config, &source, file, href)
+                })
+                .map(|(_, href)| *href)
+                .collect()
+        };
+        assert_eq!(vec!["/docs/intro/"], scoped("docs"));
+        assert_eq!(vec!["/blog/hello/"], scoped("blog"));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_indexes() -> Result<()> {
+        let project = TempProject::new()?;
+        let config = |settings: &str| {
+            project
+                .write(crate::SITE_TOML, format!("{}\n{}", SITE, settings))?;
+            project.config()
+        };
+
+        assert!(matches!(
+            config("[search.docs]\n\n[search.blog]\n"),
+            Err(Error::DuplicateSearchIndex(a, b, _)) if a == "blog" && b == "docs"
+        ));
+        assert!(matches!(
+            config("[search.\"my docs\"]\n"),
+            Err(Error::InvalidSearchName(_))
+        ));
+        assert!(matches!(
+            config("[search.news]\ncollection = \"news\"\n"),
+            Err(Error::NoSearchCollection(..))
+        ));
+        Ok(())
+    }
 }
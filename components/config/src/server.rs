@@ -1,11 +1,47 @@
 use std::collections::HashMap;
 use std::fs;
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
-
-use crate::{memfs::EmbeddedFileSystem, redirect::Redirects, Error, Result};
+use serde_with::{serde_as, DisplayFromStr};
+use url::Url;
+
+use crate::{
+    cache::CacheManifest,
+    link::TrailingSlash,
+    memfs::{DynamicMemoryFileSystem, EmbeddedFileSystem, MemoryFileSystem},
+    redirect::Redirects,
+    Error, Result,
+};
+
+/// Content types for file extensions that the
+/// server defaults do not know about.
+const MIME_TYPES: [(&str, &str); 2] = [
+    ("wasm", "application/wasm"),
+    ("webmanifest", "application/manifest+json"),
+];
+
+/// Find the content type for a path by file extension, the
+/// overrides take precedence over the built in content types.
+pub fn mime_type<'a>(
+    overrides: &'a HashMap<String, String>,
+    path: &str,
+) -> Option<&'a str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    overrides
+        .iter()
+        .find(|(k, _)| {
+            k.trim_start_matches('.').eq_ignore_ascii_case(&extension)
+        })
+        .map(|(_, v)| v.as_str())
+        .or_else(|| {
+            MIME_TYPES
+                .iter()
+                .find(|(k, _)| *k == extension)
+                .map(|(_, v)| *v)
+        })
+}
 
 pub fn to_websocket_url(
     tls: bool,
@@ -70,7 +106,8 @@ impl ConnectionInfo {
         } else {
             crate::SCHEME_HTTP
         };
-        let url = crate::to_url_string(scheme, &host, addr.port());
+        let url =
+            crate::to_url_string(scheme, browse_host(&host), addr.port());
         Self {
             addr,
             host,
@@ -89,6 +126,15 @@ impl ConnectionInfo {
     }
 }
 
+/// Get a host name suitable for a browser, an unspecified bind
+/// address such as `0.0.0.0` is replaced with `localhost`.
+pub fn browse_host(host: &str) -> &str {
+    match host.parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => crate::config::HOST,
+        _ => host,
+    }
+}
+
 /// Determines the type of port to use.
 pub enum PortType {
     Infer,
@@ -130,6 +176,49 @@ pub struct LaunchConfig {
     pub open: bool,
 }
 
+/// Forward requests for a path prefix to a backend server.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Path prefix for the requests to forward, eg: `/api`.
+    prefix: String,
+    /// Base URL of the backend server; the request path
+    /// and query are appended to this URL.
+    #[serde_as(as = "DisplayFromStr")]
+    target: Url,
+}
+
+impl ProxyConfig {
+    pub fn new(prefix: String, target: Url) -> Self {
+        Self { prefix, target }
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn target(&self) -> &Url {
+        &self.target
+    }
+
+    /// Ensure the prefix is an absolute path.
+    pub fn validate(&self) -> Result<()> {
+        if !self.prefix.starts_with('/') {
+            return Err(Error::InvalidProxyPrefix(self.prefix.clone()));
+        }
+        Ok(())
+    }
+
+    /// URL of the backend resource for a request path and query.
+    pub fn upstream(&self, path_and_query: &str) -> String {
+        format!(
+            "{}{}",
+            self.target.as_str().trim_end_matches('/'),
+            path_and_query
+        )
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct ServerConfig {
@@ -148,6 +237,11 @@ pub struct ServerConfig {
     #[serde(default)]
     authorities: Option<Vec<String>>,
 
+    /// Content types keyed by file extension that override
+    /// the content types inferred by the server.
+    #[serde(default)]
+    mime_types: HashMap<String, String>,
+
     /// When running a server over SSL redirect HTTP to HTTPS.
     #[serde(skip)]
     redirect_insecure: bool,
@@ -161,6 +255,11 @@ pub struct ServerConfig {
 
     #[serde(skip)]
     disable_signals: bool,
+
+    /// Path prefixes forwarded to backend servers, only
+    /// available for the development server.
+    #[serde(skip)]
+    proxy: Vec<ProxyConfig>,
 }
 
 impl Default for ServerConfig {
@@ -174,8 +273,10 @@ impl Default for ServerConfig {
             temporary_redirect: false,
             allow_ssl_from_env: true,
             authorities: None,
+            mime_types: HashMap::new(),
             hosts: vec![],
             disable_signals: false,
+            proxy: Vec::new(),
         }
     }
 }
@@ -289,6 +390,24 @@ impl ServerConfig {
         &self.authorities
     }
 
+    pub fn mime_types(&self) -> &HashMap<String, String> {
+        &self.mime_types
+    }
+
+    pub fn set_mime_types(&mut self, mime_types: HashMap<String, String>) {
+        self.mime_types = mime_types;
+    }
+
+    /// Content type for a request path when the file extension
+    /// has an override or a built in content type.
+    pub fn mime_type(&self, path: &str) -> Option<&str> {
+        mime_type(&self.mime_types, path)
+    }
+
+    pub fn listen(&self) -> &str {
+        &self.listen
+    }
+
     pub fn set_listen(&mut self, listen: String) {
         self.listen = listen;
     }
@@ -321,6 +440,14 @@ impl ServerConfig {
         self.disable_signals = flag;
     }
 
+    pub fn proxy(&self) -> &Vec<ProxyConfig> {
+        &self.proxy
+    }
+
+    pub fn set_proxy(&mut self, proxy: Vec<ProxyConfig>) {
+        self.proxy = proxy;
+    }
+
     pub fn redirect_insecure(&self) -> bool {
         self.redirect_insecure
     }
@@ -380,6 +507,11 @@ impl ServerConfig {
             .next()
             .ok_or_else(|| Error::NoSocketAddress(address))?)
     }
+
+    /// Verify the listen address resolves before launching.
+    pub fn validate(&self) -> Result<()> {
+        self.get_sock_addr(PortType::Infer).map(|_| ())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -396,10 +528,16 @@ pub struct HostConfig {
     #[serde(skip)]
     embedded: Option<Box<dyn EmbeddedFileSystem>>,
 
+    /// Build output written to memory, overrides the directory
+    /// for the static files.
+    #[serde(skip)]
+    memory: Option<DynamicMemoryFileSystem>,
+
     /// Require an index page inside the directory.
     require_index: bool,
 
-    /// Send headers that instruct browsers to disable caching.
+    /// Send headers that instruct browsers to revalidate cached files
+    /// on every request.
     disable_cache: bool,
 
     /// Deny embedding as an iframe.
@@ -407,7 +545,10 @@ pub struct HostConfig {
 
     /// Log server requests.
     log: bool,
-        
+
+    /// Page served for missing files relative to the directory.
+    error_page: PathBuf,
+
     /*
     /// Configuration for webdav.
     #[serde(skip)]
@@ -417,6 +558,14 @@ pub struct HostConfig {
     #[serde(skip)]
     redirects: Option<Redirects>,
 
+    /// Canonical form for the URLs of directory pages.
+    #[serde(skip)]
+    trailing_slash: Option<TrailingSlash>,
+
+    /// Cache directives for files in the directory.
+    #[serde(skip)]
+    cache: Option<CacheManifest>,
+
     /// Websocket endpoint when watching for file system changes.
     #[serde(skip)]
     endpoint: Option<String>,
@@ -428,13 +577,17 @@ impl Default for HostConfig {
             name: crate::config::HOST.to_string(),
             directory: PathBuf::from(""),
             embedded: None,
+            memory: None,
             //webdav: None,
             redirects: None,
+            trailing_slash: None,
+            cache: None,
             endpoint: None,
             disable_cache: false,
             require_index: true,
             deny_iframe: true,
             log: false,
+            error_page: PathBuf::from(crate::config::ERROR_HTML),
         }
     }
 }
@@ -477,6 +630,14 @@ impl HostConfig {
         self.embedded = fs;
     }
 
+    pub fn memory(&self) -> &Option<DynamicMemoryFileSystem> {
+        &self.memory
+    }
+
+    pub fn set_memory(&mut self, memory: Option<DynamicMemoryFileSystem>) {
+        self.memory = memory;
+    }
+
     pub fn require_index(&self) -> bool {
         self.require_index
     }
@@ -517,6 +678,14 @@ impl HostConfig {
         self.log = log;
     }
 
+    pub fn error_page(&self) -> &PathBuf {
+        &self.error_page
+    }
+
+    pub fn set_error_page(&mut self, error_page: PathBuf) {
+        self.error_page = error_page;
+    }
+
     pub fn redirects(&self) -> &Option<Redirects> {
         &self.redirects
     }
@@ -524,6 +693,25 @@ impl HostConfig {
     pub fn set_redirects(&mut self, redirects: Option<Redirects>) {
         self.redirects = redirects;
     }
+
+    pub fn trailing_slash(&self) -> Option<TrailingSlash> {
+        self.trailing_slash
+    }
+
+    pub fn set_trailing_slash(
+        &mut self,
+        trailing_slash: Option<TrailingSlash>,
+    ) {
+        self.trailing_slash = trailing_slash;
+    }
+
+    pub fn cache(&self) -> &Option<CacheManifest> {
+        &self.cache
+    }
+
+    pub fn set_cache(&mut self, cache: Option<CacheManifest>) {
+        self.cache = cache;
+    }
     
     /*
     pub fn webdav(&self) -> &Option<WebDavConfig> {
@@ -544,6 +732,18 @@ impl HostConfig {
         }
         Ok(())
     }
+
+    /// Load the cache directives file for the directory into this host.
+    pub fn load_cache(&mut self) -> Result<()> {
+        self.cache = match self.memory {
+            Some(ref memfs) => match memfs.get(crate::cache::CACHE_FILE) {
+                Some(content) => Some(serde_json::from_slice(&content)?),
+                None => Some(Default::default()),
+            },
+            None => Some(CacheManifest::load(&self.directory)?),
+        };
+        Ok(())
+    }
 }
 
 /*
@@ -579,3 +779,51 @@ impl WebDavConfig {
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn explicit_listen_address() -> Result<()> {
+        let config = ServerConfig::new("127.0.0.1".to_string(), 8080, None);
+        config.validate()?;
+        let addr = config.get_sock_addr(PortType::Infer)?;
+        assert_eq!(IpAddr::V4(Ipv4Addr::LOCALHOST), addr.ip());
+        assert_eq!(8080, addr.port());
+        Ok(())
+    }
+
+    #[test]
+    fn unspecified_listen_url() {
+        let addr: SocketAddr = "0.0.0.0:8080".parse().unwrap();
+        let info = ConnectionInfo::new(
+            addr,
+            "0.0.0.0".to_string(),
+            false,
+            HashMap::new(),
+        );
+        assert_eq!("http://localhost:8080", info.url());
+    }
+
+    #[test]
+    fn proxy_prefix() -> Result<()> {
+        let project = crate::test_util::TempProject::new()?;
+        let site = |prefix: &str| {
+            format!(
+                "lang = \"en\"\nhost = \"localhost\"\n\n[[profile.dev.proxy]]\nprefix = \"{}\"\ntarget = \"http://localhost:3000\"\n",
+                prefix
+            )
+        };
+        project.write(crate::SITE_TOML, site("/api"))?;
+        assert!(project.config().is_ok());
+
+        project.write(crate::SITE_TOML, site("api"))?;
+        assert!(matches!(
+            project.config(),
+            Err(Error::InvalidProxyPrefix(_))
+        ));
+        Ok(())
+    }
+}
@@ -6,7 +6,12 @@ use url::Url;
 pub const FILE: &str = "index.xml";
 pub const NAME: &str = "sitemap";
 
-use crate::profile::{ProfileFilter, ProfileName, Profiles};
+const XMLNS_XHTML: &str = "http://www.w3.org/1999/xhtml";
+
+use crate::{
+    profile::{ProfileFilter, ProfileName, Profiles},
+    Page,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default, rename_all = "kebab-case")]
@@ -66,18 +71,18 @@ impl SiteMapIndex {
     where
         W: io::Write,
     {
-        write!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
-        write!(
+        writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
             w,
-            "<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n"
+            "<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">"
         )?;
         for map in self.maps.iter() {
-            write!(w, "\t<sitemap>\n")?;
+            writeln!(w, "\t<sitemap>")?;
             let loc = utils::entity::escape(
-                &map.to_location(&self.to_location()).to_string(),
+                map.to_location(&self.to_location()).as_str(),
             );
-            write!(w, "\t\t<loc>{}</loc>\n", loc)?;
-            write!(w, "\t</sitemap>\n")?;
+            writeln!(w, "\t\t<loc>{}</loc>", loc)?;
+            writeln!(w, "\t</sitemap>")?;
         }
         write!(w, "</sitemapindex>")?;
         Ok(())
@@ -95,18 +100,30 @@ impl SiteMapFile {
     where
         W: io::Write,
     {
-        write!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
         write!(
             w,
-            "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n"
+            "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\""
         )?;
+        if self.entries.iter().any(|e| !e.alternates.is_empty()) {
+            write!(w, " xmlns:xhtml=\"{}\"", XMLNS_XHTML)?;
+        }
+        writeln!(w, ">")?;
         for entry in self.entries.iter() {
-            write!(w, "\t<url>\n")?;
-            let loc = utils::entity::escape(&entry.location.to_string());
+            writeln!(w, "\t<url>")?;
+            let loc = utils::entity::escape(entry.location.as_str());
             let lastmod = utils::entity::escape(&entry.lastmod);
-            write!(w, "\t\t<loc>{}</loc>\n", loc)?;
-            write!(w, "\t\t<lastmod>{}</lastmod>\n", lastmod)?;
-            write!(w, "\t</url>\n")?;
+            writeln!(w, "\t\t<loc>{}</loc>", loc)?;
+            writeln!(w, "\t\t<lastmod>{}</lastmod>", lastmod)?;
+            for alternate in entry.alternates.iter() {
+                writeln!(
+                    w,
+                    "\t\t<xhtml:link rel=\"alternate\" hreflang=\"{}\" href=\"{}\"/>",
+                    utils::entity::escape(&alternate.lang),
+                    utils::entity::escape(alternate.location.as_str()),
+                )?;
+            }
+            writeln!(w, "\t</url>")?;
         }
         write!(w, "</urlset>")?;
         Ok(())
@@ -121,4 +138,36 @@ impl SiteMapFile {
 pub struct SiteMapEntry {
     pub location: Url,
     pub lastmod: String,
+    pub alternates: Vec<SiteMapAlternate>,
+}
+
+impl SiteMapEntry {
+    /// Create an entry for a page, translations of the page
+    /// are listed as alternates.
+    pub fn new(location: Url, page: &Page) -> Self {
+        let alternates = page
+            .links()
+            .iter()
+            .filter_map(|link| {
+                let lang = link.translation()?;
+                let location = Url::parse(link.source()).ok()?;
+                Some(SiteMapAlternate {
+                    lang: lang.to_string(),
+                    location,
+                })
+            })
+            .collect();
+        Self {
+            location,
+            lastmod: page.lastmod(),
+            alternates,
+        }
+    }
+}
+
+/// Alternate version of a sitemap entry for a language.
+#[derive(Debug)]
+pub struct SiteMapAlternate {
+    pub lang: String,
+    pub location: Url,
 }
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils::href::UrlPath;
+
+/// Type of card used when a page is shared on Twitter.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TwitterCard {
+    Summary,
+    SummaryLargeImage,
+}
+
+impl Default for TwitterCard {
+    fn default() -> Self {
+        Self::SummaryLargeImage
+    }
+}
+
+impl TwitterCard {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Summary => "summary",
+            Self::SummaryLargeImage => "summary_large_image",
+        }
+    }
+}
+
+/// Site wide settings for social sharing meta data.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SocialConfig {
+    /// Image for pages that do not declare an image.
+    image: Option<UrlPath>,
+    /// Twitter account for the site, eg: `@example`.
+    twitter: Option<String>,
+    /// Card type for pages that have an image.
+    card: TwitterCard,
+}
+
+impl SocialConfig {
+    pub fn image(&self) -> &Option<UrlPath> {
+        &self.image
+    }
+
+    pub fn twitter(&self) -> &Option<String> {
+        &self.twitter
+    }
+
+    /// Get the card type; pages without an image always
+    /// use the summary card.
+    pub fn card(&self, has_image: bool) -> TwitterCard {
+        if has_image {
+            self.card
+        } else {
+            TwitterCard::Summary
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use crate::{
+        page::Page, test_util::TempProject, utils::href::UrlPath, ProfileName,
+        ProfileSettings, Result, RuntimeOptions,
+    };
+
+    #[test]
+    fn canonical_and_open_graph() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            crate::SITE_TOML,
+            "lang = \"en\"\nhost = \"example.com\"\n\n[social]\nimage = \"/default.png\"\ntwitter = \"@example\"\n",
+        )?;
+        let file = project.write("site/post.md", "# Post\n")?;
+        let config = project.config()?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Release);
+
+        let mut page: Page = Default::default();
+        page.title = Some("Post".to_string());
+        page.description = Some("About the post".to_string());
+        page.image = Some(UrlPath::from_str("/images/post.png").unwrap());
+        let destination = options.destination().build(&file)?;
+        page.seal(&config, &options, &file, &destination, None)?;
+
+        let canonical = page
+            .links()
+            .iter()
+            .find(|link| link.is_canonical())
+            .map(|link| link.source().to_string());
+        assert_eq!(Some("https://example.com/post/".to_string()), canonical);
+
+        let og = page.open_graph.as_ref().unwrap();
+        assert_eq!("https://example.com/post/", og.get("url").unwrap());
+        assert_eq!("Post", og.get("title").unwrap());
+        assert_eq!("About the post", og.get("description").unwrap());
+        assert_eq!(
+            "https://example.com/images/post.png",
+            og.get("image").unwrap()
+        );
+
+        let meta = page.meta.as_ref().unwrap();
+        assert_eq!("summary_large_image", meta.get("twitter:card").unwrap());
+        assert_eq!("@example", meta.get("twitter:site").unwrap());
+        assert_eq!("Post", meta.get("twitter:title").unwrap());
+        assert_eq!(
+            "https://example.com/images/post.png",
+            meta.get("twitter:image").unwrap()
+        );
+
+        // Default image is used when the page does not declare one
+        let mut page: Page = Default::default();
+        page.meta = Some(HashMap::new());
+        page.meta
+            .as_mut()
+            .unwrap()
+            .insert("twitter:card".to_string(), "summary".to_string());
+        page.seal(&config, &options, &file, &destination, None)?;
+        let og = page.open_graph.as_ref().unwrap();
+        assert_eq!("https://example.com/default.png", og.get("image").unwrap());
+        let meta = page.meta.as_ref().unwrap();
+        assert_eq!("summary", meta.get("twitter:card").unwrap());
+
+        Ok(())
+    }
+}
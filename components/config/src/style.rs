@@ -1,4 +1,5 @@
 use std::fmt;
+use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -112,6 +113,66 @@ impl fmt::Display for StyleAsset {
     }
 }
 
+/// How the full style sheets are loaded when the critical
+/// styles are inlined.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeferStyle {
+    /// Load with `media="print"` and swap the media on load.
+    Media,
+    /// Load with `rel="preload"` and swap the rel on load.
+    Preload,
+}
+
+impl Default for DeferStyle {
+    fn default() -> Self {
+        Self::Media
+    }
+}
+
+/// Settings for inlining the critical styles in each page.
+///
+/// Only used when the `critical-style` profile setting is enabled.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CriticalStyleConfig {
+    /// Style sheet relative to the site source.
+    source: PathBuf,
+    defer: DeferStyle,
+    #[serde(skip)]
+    content: String,
+}
+
+impl Default for CriticalStyleConfig {
+    fn default() -> Self {
+        Self {
+            source: PathBuf::from("critical.css"),
+            defer: Default::default(),
+            content: String::new(),
+        }
+    }
+}
+
+impl CriticalStyleConfig {
+    pub fn source(&self) -> &PathBuf {
+        &self.source
+    }
+
+    pub fn defer(&self) -> DeferStyle {
+        self.defer
+    }
+
+    /// Content of the style sheet, assigned when the runtime
+    /// options are prepared.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn set_content(&mut self, content: String) {
+        self.content = content;
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone, Hash)]
 pub struct StyleTag {
@@ -1,13 +1,44 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 const REMOTE: &str = "origin";
 const BRANCH: &str = "main";
 
+/// Determines how conflicts are handled when pulling changes.
+#[derive(
+    Debug, Default, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Stop so that conflicts may be resolved manually.
+    #[default]
+    Abort,
+    /// Choose the local version of conflicted files.
+    PreferLocal,
+    /// Choose the remote version of conflicted files.
+    PreferRemote,
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ConflictPolicy::Abort => write!(f, "abort"),
+            ConflictPolicy::PreferLocal => write!(f, "prefer-local"),
+            ConflictPolicy::PreferRemote => write!(f, "prefer-remote"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 #[serde(default)]
 pub struct SyncConfig {
     remote: String,
     branch: String,
+    conflict: ConflictPolicy,
+    /// Additional remotes that receive the branch after
+    /// the remote has been updated.
+    mirrors: Vec<String>,
 }
 
 impl SyncConfig {
@@ -18,6 +49,14 @@ impl SyncConfig {
     pub fn branch(&self) -> &str {
         &self.branch
     }
+
+    pub fn conflict(&self) -> ConflictPolicy {
+        self.conflict
+    }
+
+    pub fn mirrors(&self) -> &Vec<String> {
+        &self.mirrors
+    }
 }
 
 impl Default for SyncConfig {
@@ -25,6 +64,8 @@ impl Default for SyncConfig {
         Self {
             remote: REMOTE.to_string(),
             branch: BRANCH.to_string(),
+            conflict: Default::default(),
+            mirrors: Vec::new(),
         }
     }
 }
@@ -3,12 +3,20 @@ use std::collections::HashMap;
 
 use crate::profile::{ProfileFilter, Profiles};
 
+/// Default maximum number of highlighted code blocks to cache.
+const CACHE_SIZE: usize = 4096;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(default)]
+#[serde(default, rename_all = "kebab-case")]
 pub struct SyntaxConfig {
     theme: Option<String>,
     profiles: ProfileFilter,
     map: HashMap<String, String>,
+    /// Maximum number of highlighted code blocks to cache,
+    /// zero disables the cache.
+    cache_size: usize,
+    /// Persist the cache between builds.
+    persist_cache: bool,
 }
 
 impl Default for SyntaxConfig {
@@ -17,6 +25,8 @@ impl Default for SyntaxConfig {
             theme: None,
             map: HashMap::new(),
             profiles: Default::default(),
+            cache_size: CACHE_SIZE,
+            persist_cache: false,
         }
     }
 }
@@ -33,6 +43,14 @@ impl SyntaxConfig {
     pub fn map(&self) -> &HashMap<String, String> {
         &self.map
     }
+
+    pub fn cache_size(&self) -> usize {
+        self.cache_size
+    }
+
+    pub fn persist_cache(&self) -> bool {
+        self.persist_cache
+    }
 }
 
 impl Profiles for SyntaxConfig {
@@ -10,6 +10,7 @@ const ALTERNATE: &str = "alternate";
 const AUTHOR: &str = "author";
 const BOOKMARK: &str = "bookmark";
 const CANONICAL: &str = "canonical";
+const DNS_PREFETCH: &str = "dns-prefetch";
 const EXTERNAL: &str = "external";
 const HELP: &str = "help";
 const ICON: &str = "icon";
@@ -21,6 +22,7 @@ const NO_FOLLOW: &str = "nofollow";
 const NO_OPENER: &str = "noopener";
 const NO_REFERRER: &str = "noreferrer";
 const PING_BACK: &str = "pingback";
+const PRE_CONNECT: &str = "preconnect";
 const PRE_FETCH: &str = "prefetch";
 const PRE_LOAD: &str = "preload";
 const PREV: &str = "prev";
@@ -52,7 +54,8 @@ pub enum RelValue {
     Author,
     Bookmark,
     Canonical,
-    // TODO: dns-prefetch - maybe later?
+    #[serde(rename = "dns-prefetch")]
+    DnsPrefetch,
     External,
     Help,
     Icon,
@@ -64,6 +67,7 @@ pub enum RelValue {
     NoOpener,
     NoReferrer,
     PingBack,
+    PreConnect,
     PreFetch,
     PreLoad,
     Prev,
@@ -84,6 +88,8 @@ impl FromStr for RelValue {
             Self::Bookmark
         } else if s == CANONICAL {
             Self::Canonical
+        } else if s == DNS_PREFETCH {
+            Self::DnsPrefetch
         } else if s == EXTERNAL {
             Self::External
         } else if s == HELP {
@@ -106,6 +112,8 @@ impl FromStr for RelValue {
             Self::NoReferrer
         } else if s == PING_BACK {
             Self::PingBack
+        } else if s == PRE_CONNECT {
+            Self::PreConnect
         } else if s == PRE_FETCH {
             Self::PreFetch
         } else if s == PRE_LOAD {
@@ -134,6 +142,7 @@ impl RelValue {
             Self::Author => AUTHOR,
             Self::Bookmark => BOOKMARK,
             Self::Canonical => CANONICAL,
+            Self::DnsPrefetch => DNS_PREFETCH,
             Self::External => EXTERNAL,
             Self::Help => HELP,
             Self::Icon => ICON,
@@ -145,6 +154,7 @@ impl RelValue {
             Self::NoOpener => NO_OPENER,
             Self::NoReferrer => NO_REFERRER,
             Self::PingBack => PING_BACK,
+            Self::PreConnect => PRE_CONNECT,
             Self::PreFetch => PRE_FETCH,
             Self::PreLoad => PRE_LOAD,
             Self::Prev => PREV,
@@ -162,6 +172,32 @@ impl fmt::Display for RelValue {
     }
 }
 
+/// Attribute value for a list of `rel` values.
+///
+/// Duplicate values are removed and the values are sorted so
+/// the attribute is consistent regardless of declaration order.
+pub fn rel_attr(values: &[RelValue]) -> String {
+    let mut tokens = values.iter().map(|r| r.as_str()).collect::<Vec<_>>();
+    tokens.sort_unstable();
+    tokens.dedup();
+    tokens.join(" ")
+}
+
+/// Validate and normalize a `rel` attribute value.
+///
+/// Tokens are separated by whitespace and compared without case,
+/// any token that is not a known link type is an error.
+pub fn normalize_rel(value: &str) -> Result<String, Error> {
+    let values = value
+        .split_ascii_whitespace()
+        .map(|s| s.to_ascii_lowercase().parse::<RelValue>())
+        .collect::<Result<Vec<_>, Error>>()?;
+    if values.is_empty() {
+        return Err(Error::InvalidRelValue(value.to_string()));
+    }
+    Ok(rel_attr(&values))
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum CrossOrigin {
@@ -281,3 +317,29 @@ pub mod referrer_policy {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rel_values() {
+        assert_eq!(
+            "noopener noreferrer",
+            normalize_rel("noreferrer  NoOpener").unwrap()
+        );
+        assert_eq!(
+            "preload stylesheet",
+            normalize_rel("stylesheet preload stylesheet").unwrap()
+        );
+        assert!(matches!(
+            normalize_rel("stylesheet sidebar"),
+            Err(Error::InvalidRelValue(value)) if value == "sidebar"
+        ));
+        assert!(matches!(normalize_rel(" "), Err(Error::InvalidRelValue(_))));
+        assert_eq!(
+            "dns-prefetch preconnect",
+            normalize_rel("preconnect DNS-Prefetch").unwrap()
+        );
+    }
+}
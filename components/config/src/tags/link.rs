@@ -7,7 +7,7 @@ use serde_with::{
 
 use utils::entity;
 
-use super::attr::{As, CrossOrigin, RelValue};
+use super::attr::{rel_attr, As, CrossOrigin, RelValue};
 
 #[serde_as]
 #[skip_serializing_none]
@@ -74,6 +74,14 @@ impl LinkTag {
         }
     }
 
+    pub fn is_canonical(&self) -> bool {
+        if let Some(ref rel) = self.rel {
+            rel.contains(&RelValue::Canonical)
+        } else {
+            false
+        }
+    }
+
     pub fn new_bookmark(href: String) -> Self {
         Self {
             href,
@@ -99,10 +107,48 @@ impl LinkTag {
         }
     }
 
+    pub fn new_translation(href: String, lang: String) -> Self {
+        Self {
+            href,
+            href_lang: Some(lang),
+            rel: Some(vec![RelValue::Alternate]),
+            ..Default::default()
+        }
+    }
+
+    /// Resource hint, the caller is responsible for validating
+    /// the combination of `rel` and `as` values.
+    pub fn new_hint(
+        href: String,
+        rel: Vec<RelValue>,
+        as_attr: Option<As>,
+        crossorigin: Option<CrossOrigin>,
+        link_type: Option<String>,
+    ) -> Self {
+        Self {
+            href,
+            rel: Some(rel),
+            as_attr,
+            crossorigin,
+            link_type,
+            ..Default::default()
+        }
+    }
+
     pub fn source(&self) -> &str {
         &self.href
     }
 
+    /// Language for an alternate link to a translation of a page.
+    pub fn translation(&self) -> Option<&str> {
+        match (&self.rel, &self.href_lang) {
+            (Some(rel), Some(lang)) if rel.contains(&RelValue::Alternate) => {
+                Some(lang)
+            }
+            _ => None,
+        }
+    }
+
     pub fn set_source(&mut self, val: String) {
         self.href = val;
     }
@@ -113,9 +159,7 @@ impl fmt::Display for LinkTag {
         write!(f, "<link")?;
 
         if let Some(ref rel) = self.rel {
-            let values = rel.iter().map(|r| r.as_str()).collect::<Vec<_>>();
-            let attr = values.join(" ");
-            write!(f, " rel=\"{}\"", entity::escape(&attr))?;
+            write!(f, " rel=\"{}\"", entity::escape(&rel_attr(rel)))?;
         }
 
         write!(f, " href=\"{}\"", entity::escape(&self.href))?;
@@ -0,0 +1,117 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    generator::{self, AppData},
+    Config, Result, CORE_FTL, LOCALES, MAIN_FTL, SITE, SITE_TOML,
+};
+
+static TEMP_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Assign the application data when it has not been assigned so
+/// that builds which need the generator version can run in tests.
+fn generator() {
+    let version = env!("CARGO_PKG_VERSION").to_string();
+    generator::get(Some(AppData {
+        name: "uwe".to_string(),
+        bin_name: "uwe".to_string(),
+        user_agent: format!("uwe/{}", version),
+        semver: version.parse().unwrap(),
+        version,
+    }));
+}
+
+/// Temporary project on disk for tests in downstream crates.
+///
+/// The project directory is removed when the guard is dropped.
+#[derive(Debug)]
+pub struct TempProject {
+    root: PathBuf,
+}
+
+impl TempProject {
+    /// Create an empty project directory.
+    pub fn new() -> io::Result<Self> {
+        generator();
+        let id = TEMP_ID.fetch_add(1, Ordering::SeqCst);
+        let root = std::env::temp_dir().join(format!(
+            "uwe-test-{}-{}",
+            std::process::id(),
+            id
+        ));
+        if root.exists() {
+            fs::remove_dir_all(&root)?;
+        }
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Create a minimal project with a site settings file, shared
+    /// and per-locale fluent files and two pages.
+    ///
+    /// The first locale is used as the project language.
+    pub fn multi_lingual(locales: &[&str]) -> io::Result<Self> {
+        let project = TempProject::new()?;
+        let lang = locales.first().cloned().unwrap_or("en");
+        project.write(
+            SITE_TOML,
+            format!("lang = \"{}\"\nhost = \"localhost\"\n", lang),
+        )?;
+        project.write(
+            Path::new(SITE).join(LOCALES).join(CORE_FTL),
+            "site-name = Test\n",
+        )?;
+        for locale in locales {
+            project.write(
+                Path::new(SITE).join(LOCALES).join(locale).join(MAIN_FTL),
+                format!("greeting = Hello ({})\n", locale),
+            )?;
+        }
+        project.write(Path::new(SITE).join("index.md"), "# Home\n")?;
+        project.write(Path::new(SITE).join("about.md"), "# About\n")?;
+        Ok(project)
+    }
+
+    /// Write a file relative to the project root creating
+    /// parent directories as needed.
+    pub fn write<P: AsRef<Path>, S: AsRef<[u8]>>(
+        &self,
+        path: P,
+        content: S,
+    ) -> io::Result<PathBuf> {
+        let file = self.root.join(path);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&file, content)?;
+        Ok(file)
+    }
+
+    /// The project root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The source directory for the project.
+    pub fn source(&self) -> PathBuf {
+        self.root.join(SITE)
+    }
+
+    /// The locales directory for the project.
+    pub fn locales(&self) -> PathBuf {
+        self.source().join(LOCALES)
+    }
+
+    /// Load the settings for the project.
+    pub fn config(&self) -> Result<Config> {
+        Config::load(&self.root, false)
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
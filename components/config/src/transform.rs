@@ -14,6 +14,23 @@ impl Default for TransformConfig {
     }
 }
 
+/// Determines how duplicate heading identifiers are handled.
+#[derive(
+    Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq,
+)]
+pub enum DuplicateIdPolicy {
+    /// Append a numeric suffix so that identifiers are unique.
+    #[default]
+    #[serde(rename = "suffix")]
+    Suffix,
+    /// Keep the identifiers and report a warning.
+    #[serde(rename = "warn")]
+    Warn,
+    /// Treat duplicate identifiers as an error.
+    #[serde(rename = "error")]
+    Error,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct HtmlTransformFlags {
@@ -21,6 +38,19 @@ pub struct HtmlTransformFlags {
     pub auto_id: Option<bool>,
     pub toc: Option<bool>,
     pub words: Option<bool>,
+    /// Average reading speed used to estimate reading time.
+    pub words_per_minute: Option<usize>,
+    /// Prefix root-relative URLs with this path when
+    /// hosting a site in a subdirectory.
+    pub base_path: Option<String>,
+    /// How to handle duplicate heading identifiers in a page.
+    pub duplicate_ids: Option<DuplicateIdPolicy>,
+    /// Rewrite document-relative links to source files so
+    /// that they reference the output URLs.
+    pub relative_links: Option<bool>,
+    /// Query parameter names removed from external links,
+    /// a `*` matches any characters, eg: `utm_*`.
+    pub strip_params: Option<Vec<String>>,
 }
 
 impl Default for HtmlTransformFlags {
@@ -30,6 +60,11 @@ impl Default for HtmlTransformFlags {
             auto_id: Some(false),
             toc: Some(false),
             words: Some(false),
+            words_per_minute: None,
+            base_path: None,
+            duplicate_ids: None,
+            relative_links: Some(false),
+            strip_params: None,
         }
     }
 }
@@ -51,10 +86,36 @@ impl HtmlTransformFlags {
         self.words.is_some() && self.words.unwrap()
     }
 
+    pub fn words_per_minute(&self) -> usize {
+        self.words_per_minute.unwrap_or(250)
+    }
+
+    pub fn duplicate_ids(&self) -> DuplicateIdPolicy {
+        self.duplicate_ids.unwrap_or_default()
+    }
+
+    pub fn use_relative_links(&self) -> bool {
+        self.relative_links.is_some() && self.relative_links.unwrap()
+    }
+
+    pub fn strip_params(&self) -> &[String] {
+        self.strip_params.as_deref().unwrap_or(&[])
+    }
+
+    pub fn base_path(&self) -> Option<&str> {
+        self.base_path
+            .as_ref()
+            .map(|s| s.trim_end_matches('/'))
+            .filter(|s| !s.is_empty())
+    }
+
     pub fn is_active(&self) -> bool {
         self.use_strip_comments()
             || self.use_auto_id()
             || self.use_toc()
             || self.use_words()
+            || self.use_relative_links()
+            || !self.strip_params().is_empty()
+            || self.base_path().is_some()
     }
 }
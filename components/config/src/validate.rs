@@ -0,0 +1,179 @@
+//! Cross-check the build source and target for the build
+//! settings and each profile.
+use std::path::{Component, Path, PathBuf};
+
+use log::warn;
+
+use crate::{Config, Error, ProfileSettings, Result, BUILD};
+
+/// Normalize a path relative to the project without touching
+/// the file system.
+///
+/// Returns `None` when the path escapes the project.
+fn normalize(project: &Path, path: &Path) -> Option<PathBuf> {
+    let path = if path.is_absolute() {
+        path.strip_prefix(project).ok()?
+    } else {
+        path
+    };
+
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(part) => result.push(part),
+            _ => return None,
+        }
+    }
+    Some(result)
+}
+
+/// Validate the source and target for a profile.
+///
+/// The source must be inside the project, the source and target
+/// must be different and the target may not be inside the source
+/// otherwise each build would include the previous build output.
+pub fn paths(
+    project: &Path,
+    name: &str,
+    settings: &ProfileSettings,
+) -> Result<()> {
+    let source = normalize(project, &settings.source).ok_or_else(|| {
+        Error::SourceOutsideProject(name.to_string(), settings.source.clone())
+    })?;
+
+    if let Some(target) = normalize(project, &settings.target) {
+        if target == source {
+            return Err(Error::SourceIsTarget(
+                name.to_string(),
+                settings.source.clone(),
+            ));
+        }
+        if target.starts_with(&source) {
+            return Err(Error::TargetInsideSource(
+                name.to_string(),
+                settings.target.clone(),
+                settings.source.clone(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validate the paths for the build settings and all profiles.
+///
+/// Profiles are merged with the build settings in the same way
+/// as when a build starts so the effective paths are validated.
+pub(crate) fn config(config: &Config) -> Result<()> {
+    let project = config.project();
+    let build = config.build.clone().unwrap_or_default();
+    paths(project, BUILD, &build)?;
+    if let Some(ref profiles) = config.profile {
+        for (name, settings) in profiles.iter() {
+            let mut merged = build.clone();
+            merged.append(&mut settings.clone());
+            paths(project, name, &merged)?;
+        }
+    }
+    Ok(())
+}
+
+/// Warn about keys in a configuration file that do not map to
+/// a setting.
+///
+/// Returns the unknown keys so callers may decide whether
+/// they should be treated as an error.
+pub fn keys(file: &Path, content: &str) -> Result<Vec<String>> {
+    let keys = crate::unknown_keys(content)?;
+    for key in keys.iter() {
+        warn!("Unknown key {} in {}", key, file.display());
+    }
+    Ok(keys)
+}
+
+/// Warn about unknown keys in a configuration file and the
+/// files it includes.
+pub(crate) fn config_keys(
+    file: &Path,
+    content: &str,
+    includes: &[PathBuf],
+) -> Result<Vec<String>> {
+    let mut unknown = keys(file, content)?;
+    for include in includes {
+        let content = utils::fs::read_string(include)?;
+        unknown.append(&mut keys(include, &content)?);
+    }
+    Ok(unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempProject;
+
+    fn build_error(site: &str) -> Result<Error> {
+        let project = TempProject::new()?;
+        project.write(crate::SITE_TOML, site)?;
+        match project.config() {
+            Err(e) => Ok(e),
+            Ok(_) => panic!("expected the build paths to be invalid"),
+        }
+    }
+
+    #[test]
+    fn source_target_paths() -> Result<()> {
+        let site = "lang = \"en\"\nhost = \"localhost\"\n";
+
+        let project = TempProject::new()?;
+        project.write(crate::SITE_TOML, site)?;
+        project.config()?;
+
+        let error = build_error(&format!(
+            "{}\n[build]\nsource = \"site\"\ntarget = \"./site\"\n",
+            site
+        ))?;
+        assert!(matches!(error, Error::SourceIsTarget(..)));
+
+        let error = build_error(&format!(
+            "{}\n[profile.release]\ntarget = \"site/build\"\n",
+            site
+        ))?;
+        match error {
+            Error::TargetInsideSource(name, target, source) => {
+                assert_eq!("release", name);
+                assert_eq!(PathBuf::from("site/build"), target);
+                assert_eq!(PathBuf::from("site"), source);
+            }
+            _ => panic!("expected the target to be inside the source"),
+        }
+
+        let error = build_error(&format!(
+            "{}\n[profile.docs]\nsource = \"../docs\"\n",
+            site
+        ))?;
+        assert!(matches!(error, Error::SourceOutsideProject(..)));
+        Ok(())
+    }
+
+    #[test]
+    fn include_keys() -> Result<()> {
+        let project = TempProject::new()?;
+        let shared = project.write(
+            "shared/base.toml",
+            "host = \"example.com\"\nhots = \"example.com\"\n",
+        )?;
+        let site = "lang = \"en\"\ninclude = [\"shared/base.toml\"]\n";
+        let file = project.write(crate::SITE_TOML, site)?;
+
+        let config = project.config()?;
+        let unknown = config_keys(&file, site, config.includes())?;
+        assert_eq!(vec!["hots".to_string()], unknown);
+        assert_eq!(vec![shared.canonicalize()?], config.includes());
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::ffi::OsString;
 use std::path::PathBuf;
 use std::{fs, io};
 
@@ -12,25 +13,63 @@ const PROJECTS_FILE: &str = "projects.toml";
 const RELEASES_REPO: &str = "https://github.com/uwe-app/releases";
 const REGISTRY_REPO: &str = "https://github.com/uwe-app/registry";
 
+/// Environment variable to override the releases repository URL.
+const RELEASES_REPO_ENV: &str = "UWE_RELEASES_REPO";
+
+/// Environment variable to override the root directory.
+const ROOT_DIR_ENV: &str = "UWE_HOME";
+
 /// Name of the releases reppsitory.
 const RELEASES: &str = "releases";
 
 /// Name of the plugin registry repository.
 const REGISTRY: &str = "registry";
 /// Name for the location of cached plugin downloads.
-const DOWNLOADS: &str = "downloads";
+pub const DOWNLOADS: &str = "downloads";
 /// Name for the location of registry packages (JSON files).
-const PACKAGES: &str = "packages";
+pub const PACKAGES: &str = "packages";
 /// Name for the location of cached plugin repositories.
 const REPOSITORIES: &str = "repositories";
 /// Name for the location of where plugins installed from archives are placed.
 const ARCHIVES: &str = "archives";
 /// Name for the socket used to communicate with child processes.
 const SOCKET: &str = "uwe.sock";
+/// Name for the location of cached short code renders.
+const SHORT_CODES: &str = "short-codes";
+/// Name for the location of cached image variants.
+const IMAGES: &str = "images";
+/// Name for the location of last published checksums.
+const PUBLISHED: &str = "published";
+/// Name for the location of cached syntax highlighting.
+const SYNTAX: &str = "syntax";
 
 /// Get the root directory (~/.uwe) but do not
 /// create it if it does not exist.
+///
+/// The default directory may be overridden using the
+/// `UWE_HOME` environment variable which must be an
+/// absolute path.
 pub fn root_dir() -> io::Result<PathBuf> {
+    root_from(std::env::var_os(ROOT_DIR_ENV))
+}
+
+/// Resolve the root directory from the value of the override.
+fn root_from(value: Option<OsString>) -> io::Result<PathBuf> {
+    if let Some(root) = value.filter(|root| !root.is_empty()).map(PathBuf::from)
+    {
+        if !root.is_absolute() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} must be an absolute path ({})",
+                    ROOT_DIR_ENV,
+                    root.display()
+                ),
+            ));
+        }
+        return Ok(root);
+    }
+
     home::home_dir().map(|p| p.join(ROOT_DIR)).ok_or_else(|| {
         io::Error::new(
             io::ErrorKind::NotFound,
@@ -60,6 +99,30 @@ pub fn tmp_dir() -> io::Result<PathBuf> {
     Ok(tmp)
 }
 
+/// Get the short code cache directory but do not
+/// create it if it does not exist.
+pub fn short_code_cache_dir() -> io::Result<PathBuf> {
+    Ok(root_dir()?.join(SHORT_CODES))
+}
+
+/// Get the image cache directory but do not
+/// create it if it does not exist.
+pub fn image_cache_dir() -> io::Result<PathBuf> {
+    Ok(root_dir()?.join(IMAGES))
+}
+
+/// Get the syntax highlighting cache directory but do not
+/// create it if it does not exist.
+pub fn syntax_cache_dir() -> io::Result<PathBuf> {
+    Ok(root_dir()?.join(SYNTAX))
+}
+
+/// Get the directory for last published checksums but do
+/// not create it if it does not exist.
+pub fn published_dir() -> io::Result<PathBuf> {
+    Ok(root_dir()?.join(PUBLISHED))
+}
+
 pub fn bin_dir() -> io::Result<PathBuf> {
     let mut bin = root_dir()?;
     bin.push(BIN);
@@ -69,8 +132,18 @@ pub fn bin_dir() -> io::Result<PathBuf> {
     Ok(bin)
 }
 
+/// Get the URL for the releases repository.
+///
+/// The default repository may be overridden using the
+/// `UWE_RELEASES_REPO` environment variable.
 pub fn releases_url() -> String {
-    RELEASES_REPO.to_string()
+    releases_url_from(std::env::var(RELEASES_REPO_ENV).ok())
+}
+
+fn releases_url_from(value: Option<String>) -> String {
+    value
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| RELEASES_REPO.to_string())
 }
 
 pub fn registry_url() -> String {
@@ -102,3 +175,31 @@ pub fn repositories_dir() -> io::Result<PathBuf> {
 }
 
 pub use home::home_dir;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_dir_override() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        assert_eq!(
+            root.path(),
+            root_from(Some(root.path().as_os_str().to_os_string()))?
+        );
+        assert!(root_from(Some(OsString::from("relative/uwe"))).is_err());
+
+        let default = root_from(None)?;
+        assert!(default.ends_with(ROOT_DIR));
+        assert_eq!(default, root_from(Some(OsString::new()))?);
+        Ok(())
+    }
+
+    #[test]
+    fn releases_url_override() {
+        let mirror = "https://git.example.com/releases";
+        assert_eq!(mirror, releases_url_from(Some(mirror.to_string())));
+        assert_eq!(RELEASES_REPO, releases_url_from(Some(" ".to_string())));
+        assert_eq!(RELEASES_REPO, releases_url_from(None));
+    }
+}
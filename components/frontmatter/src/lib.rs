@@ -29,6 +29,10 @@ pub enum Error {
 
 type ContentResult = (String, bool, String);
 
+/// Front matter, whether front matter was found and the
+/// byte offset where the body content starts.
+type FrontMatterResult = (String, bool, u64);
+
 #[derive(Debug, Default)]
 pub struct Config {
     pub start: String,
@@ -55,16 +59,62 @@ impl Config {
     }
 }
 
+/// Prefix used to escape a delimiter line within front matter.
+const ESCAPE: &str = "\\";
+
+/// Unescape a front matter line that escapes the end delimiter.
+fn unescape<'a>(line: &'a str, end: &str) -> &'a str {
+    let trimmed = line.trim();
+    if trimmed.starts_with(ESCAPE) && trimmed[ESCAPE.len()..] == *end {
+        &trimmed[ESCAPE.len()..]
+    } else {
+        line
+    }
+}
+
 // Pads the content with lines so that template
 // error messages with line numbers are correct.
+//
+// The start delimiter is only recognised when it is the first
+// non-empty line of the file; once front matter has been parsed
+// (or the document has started) delimiter lines in the body, for
+// example in a code block, are treated as regular content.
+//
+// The end delimiter is matched against every line of the front
+// matter; to include a literal delimiter line in the front matter
+// prefix it with a backslash (eg: `\+++`).
 pub fn load<P: AsRef<Path>>(
     p: P,
     conf: Config,
+) -> Result<ContentResult, Error> {
+    load_until(p, conf, None)
+}
+
+/// Load the front matter and the start of the content.
+///
+/// Reading stops after the line that contains the marker or at the
+/// end of the paragraph once the content has more than the number of
+/// words; use this when only a summary of the content is needed.
+pub fn load_excerpt<P: AsRef<Path>>(
+    p: P,
+    conf: Config,
+    marker: &str,
+    words: usize,
+) -> Result<ContentResult, Error> {
+    load_until(p, conf, Some((marker, words)))
+}
+
+fn load_until<P: AsRef<Path>>(
+    p: P,
+    conf: Config,
+    until: Option<(&str, usize)>,
 ) -> Result<ContentResult, Error> {
     let mut fm = String::new();
     let mut content = String::new();
     let mut in_front_matter = false;
     let mut has_front_matter = false;
+    let mut at_start = true;
+    let mut words = 0;
 
     let f = File::open(p.as_ref())?;
     let reader = BufReader::new(f);
@@ -85,21 +135,39 @@ pub fn load<P: AsRef<Path>>(
 
                 if in_front_matter {
                     content.push_str(newline);
-                    fm.push_str(&line);
+                    fm.push_str(unescape(&line, &conf.end));
                     fm.push_str(newline);
                     continue;
                 }
 
-                if !has_front_matter
-                    && line.trim() == conf.start
-                    && content.is_empty()
-                {
+                // Allow leading blank lines before the front matter
+                if at_start && line.trim().is_empty() {
+                    content.push_str(newline);
+                    continue;
+                }
+
+                if !has_front_matter && at_start && line.trim() == conf.start {
+                    at_start = false;
                     content.push_str(newline);
                     in_front_matter = true;
                     has_front_matter = true;
                     continue;
                 }
 
+                at_start = false;
+
+                if let Some((marker, limit)) = until {
+                    if words > limit && line.trim().is_empty() {
+                        return Ok((content, has_front_matter, fm));
+                    }
+                    words += line.split_whitespace().count();
+                    if line.contains(marker) {
+                        content.push_str(&line);
+                        content.push_str(newline);
+                        return Ok((content, has_front_matter, fm));
+                    }
+                }
+
                 // Always respect bail, it tells us to never read the
                 // actual file content as we only want to extract the
                 // front matter data
@@ -118,5 +186,126 @@ pub fn load<P: AsRef<Path>>(
         return Err(Error::NotTerminated(p.as_ref().to_path_buf()));
     }
 
-    return Ok((content, has_front_matter, fm));
+    Ok((content, has_front_matter, fm))
+}
+
+/// Read only the front matter for a file.
+///
+/// Reading stops at the closing delimiter and the returned offset is
+/// the byte position where the body starts so that callers may stream
+/// the body separately. When a file has no front matter the offset
+/// is zero.
+pub fn load_frontmatter_only<P: AsRef<Path>>(
+    p: P,
+) -> Result<FrontMatterResult, Error> {
+    let conf = get_config(&p.as_ref().to_path_buf());
+    let mut fm = String::new();
+    let mut offset: u64 = 0;
+    let mut in_front_matter = false;
+
+    let f = File::open(p.as_ref())?;
+    let mut reader = BufReader::new(f);
+    let mut buf = String::new();
+
+    loop {
+        buf.clear();
+        let read = reader.read_line(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        offset += read as u64;
+
+        let line = buf.trim_end_matches(&['\n', '\r'][..]);
+
+        if in_front_matter {
+            if line.trim() == conf.end {
+                return Ok((fm, true, offset));
+            }
+            fm.push_str(unescape(line, &conf.end));
+            fm.push('\n');
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.trim() == conf.start {
+            in_front_matter = true;
+            continue;
+        }
+
+        // Document does not start with front matter
+        return Ok((fm, false, 0));
+    }
+
+    if in_front_matter {
+        return Err(Error::NotTerminated(p.as_ref().to_path_buf()));
+    }
+
+    Ok((fm, false, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) -> Result<PathBuf, Error> {
+        let file = dir.join(name);
+        std::fs::write(&file, content)?;
+        Ok(file)
+    }
+
+    #[test]
+    fn delimiters_in_body() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let file = write(
+            dir.path(),
+            "page.md",
+            "+++\ntitle = \"Page\"\nquote = \"\"\"\n\\+++\n\"\"\"\n+++\n# Page\n\n```\n+++\n```\n",
+        )?;
+        let (content, has_front_matter, fm) =
+            load(file, Config::new_markdown(false))?;
+        assert!(has_front_matter);
+        assert_eq!("title = \"Page\"\nquote = \"\"\"\n+++\n\"\"\"\n", fm);
+        assert!(content.contains("```\n+++\n```"));
+
+        // Delimiter after the document has started is content
+        let file = write(dir.path(), "body.md", "# Page\n+++\nbody\n+++\n")?;
+        let (content, has_front_matter, fm) =
+            load(file, Config::new_markdown(false))?;
+        assert!(!has_front_matter);
+        assert!(fm.is_empty());
+        assert_eq!("# Page\n+++\nbody\n+++\n", content);
+        Ok(())
+    }
+
+    #[test]
+    fn front_matter_offset() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let file = write(
+            dir.path(),
+            "page.md",
+            "\n+++\ntitle = \"Page\"\n+++\n# Page\n",
+        )?;
+        let (fm, has_front_matter, offset) = load_frontmatter_only(&file)?;
+        assert!(has_front_matter);
+        assert_eq!("title = \"Page\"\n", fm);
+
+        let mut reader = BufReader::new(File::open(&file)?);
+        reader.seek(std::io::SeekFrom::Start(offset))?;
+        let mut body = String::new();
+        reader.read_to_string(&mut body)?;
+        assert_eq!("# Page\n", body);
+
+        let file = write(dir.path(), "plain.md", "# Page\n")?;
+        assert_eq!((String::new(), false, 0), load_frontmatter_only(file)?);
+
+        let file = write(dir.path(), "open.md", "+++\ntitle = \"Page\"\n")?;
+        assert!(matches!(
+            load_frontmatter_only(file),
+            Err(Error::NotTerminated(_))
+        ));
+        Ok(())
+    }
 }
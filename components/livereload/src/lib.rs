@@ -1,7 +1,4 @@
-use std::io::Result;
-use std::path::PathBuf;
-
-use config::Config;
+use std::path::{Path, PathBuf};
 
 pub mod messages;
 
@@ -42,19 +39,6 @@ fn get_script(endpoint: &str) -> String {
     script
 }
 
-fn write_javascript(target: &PathBuf, url: &str) -> Result<()> {
-    let mut dest = target.clone();
-    dest.push(JS_FILE);
-    let script = get_script(url);
-    utils::fs::write_string(dest, script)
-}
-
-fn write_stylesheet(target: &PathBuf) -> Result<()> {
-    let mut dest = target.clone();
-    dest.push(CSS_FILE);
-    utils::fs::write_string(dest, CSS)
-}
-
 /// Get the URL path to the stylesheet.
 pub fn stylesheet() -> String {
     format!("/{}", CSS_FILE)
@@ -65,12 +49,10 @@ pub fn javascript() -> String {
     format!("/{}", JS_FILE)
 }
 
-/// Write out the Javascript and CSS files.
-pub fn write(
-    _config: &Config,
-    target: &PathBuf,
-    endpoint: &str,
-) -> std::io::Result<()> {
-    write_javascript(target, endpoint)?;
-    write_stylesheet(target)
+/// Javascript and CSS files with the destination in the target.
+pub fn files(target: &Path, endpoint: &str) -> Vec<(PathBuf, String)> {
+    vec![
+        (target.join(JS_FILE), get_script(endpoint)),
+        (target.join(CSS_FILE), CSS.to_string()),
+    ]
 }
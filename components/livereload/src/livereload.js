@@ -17,6 +17,16 @@ socket.onmessage = (event) => {
 	}else if (e.type === 'reload') {
 		socket.close();
     if (e.href) { location.href = e.href; } else { location.reload(); }
+	}else if (e.type === 'stylesheet') {
+		el.style.display = 'none';
+    const links = document.querySelectorAll('link[rel="stylesheet"]');
+    links.forEach((link) => {
+      const url = new URL(link.href, document.location.href);
+      if (e.paths.includes(url.pathname)) {
+        url.searchParams.set('livereload', Date.now());
+        link.href = url.toString();
+      }
+    });
 	}else if (e.type === 'notify') {
 		el.style.display = 'block';
         msg.innerText = e.message;
@@ -6,6 +6,8 @@ enum EventType {
     Start,
     #[serde(rename = "reload")]
     Reload,
+    #[serde(rename = "stylesheet")]
+    Stylesheet,
     #[serde(rename = "notify")]
     Notify,
 }
@@ -22,6 +24,8 @@ struct ReloadEvent {
     pub event_type: EventType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub href: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub paths: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,10 +42,28 @@ pub fn start() -> impl Serialize + std::fmt::Debug {
     }
 }
 
-pub fn reload(href: Option<String>) -> impl Serialize + std::fmt::Debug {
+const CSS: &str = ".css";
+
+/// Create a reload event for the changed output paths.
+///
+/// When every changed path is a stylesheet the event is tagged
+/// so that clients may swap the stylesheets without reloading
+/// the page, otherwise a full reload is requested.
+pub fn reload(
+    href: Option<String>,
+    paths: Vec<String>,
+) -> impl Serialize + std::fmt::Debug {
+    let stylesheets =
+        !paths.is_empty() && paths.iter().all(|p| p.ends_with(CSS));
+    let event_type = if stylesheets {
+        EventType::Stylesheet
+    } else {
+        EventType::Reload
+    };
     ReloadEvent {
-        event_type: EventType::Reload,
+        event_type,
         href,
+        paths,
     }
 }
 
@@ -55,3 +77,36 @@ pub fn notify(
         error,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::reload;
+
+    #[test]
+    fn reload_paths() -> serde_json::Result<()> {
+        let event = reload(None, vec!["/style.css".to_string()]);
+        assert_eq!(
+            json!({"type": "stylesheet", "paths": ["/style.css"]}),
+            serde_json::to_value(&event)?
+        );
+
+        let event = reload(
+            Some("/index.html".to_string()),
+            vec!["/style.css".to_string(), "/index.html".to_string()],
+        );
+        assert_eq!(
+            json!({
+                "type": "reload",
+                "href": "/index.html",
+                "paths": ["/style.css", "/index.html"]
+            }),
+            serde_json::to_value(&event)?
+        );
+
+        let event = reload(None, vec![]);
+        assert_eq!(json!({"type": "reload"}), serde_json::to_value(&event)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+//! Locale aware formatting for numbers and dates.
+use chrono::{DateTime, Utc};
+use unic_langid::LanguageIdentifier;
+
+use config::date::DateConfig;
+
+/// Separators used when formatting numbers for a locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Separator between the integer and fractional parts.
+    pub decimal: char,
+    /// Separator between groups of thousands.
+    pub group: char,
+    /// Whether a space precedes percent and currency suffixes.
+    pub space: bool,
+    /// Whether the currency symbol is written after the number.
+    pub symbol_after: bool,
+}
+
+const NBSP: char = '\u{a0}';
+
+const DOT_DECIMAL: NumberFormat = NumberFormat {
+    decimal: '.',
+    group: ',',
+    space: false,
+    symbol_after: false,
+};
+
+const COMMA_DECIMAL: NumberFormat = NumberFormat {
+    decimal: ',',
+    group: '.',
+    space: true,
+    symbol_after: true,
+};
+
+const SPACE_GROUP: NumberFormat = NumberFormat {
+    decimal: ',',
+    group: NBSP,
+    space: true,
+    symbol_after: true,
+};
+
+/// Get the number formatting rules for a language.
+pub fn number_format(lang: &LanguageIdentifier) -> Option<NumberFormat> {
+    match lang.language.as_str() {
+        "en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" => Some(DOT_DECIMAL),
+        "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => {
+            Some(COMMA_DECIMAL)
+        }
+        "fr" | "ru" | "pl" | "cs" | "sv" | "fi" | "nb" | "uk" | "hu" => {
+            Some(SPACE_GROUP)
+        }
+        _ => None,
+    }
+}
+
+/// Resolve the number formatting rules for a language using the
+/// rules for the fallback language when the language is not known.
+pub fn resolve(
+    lang: &LanguageIdentifier,
+    fallback: &LanguageIdentifier,
+) -> NumberFormat {
+    number_format(lang)
+        .or_else(|| number_format(fallback))
+        .unwrap_or(DOT_DECIMAL)
+}
+
+/// Format a number with a fixed number of decimal places.
+pub fn format_number(
+    value: f64,
+    decimals: usize,
+    lang: &LanguageIdentifier,
+    fallback: &LanguageIdentifier,
+) -> String {
+    let rules = resolve(lang, fallback);
+    let fixed = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = match fixed.find('.') {
+        Some(pos) => (&fixed[..pos], Some(&fixed[pos + 1..])),
+        None => (&fixed[..], None),
+    };
+
+    let mut result = String::new();
+    if value < 0.0 && fixed.chars().any(|c| c != '0' && c != '.') {
+        result.push('-');
+    }
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            result.push(rules.group);
+        }
+        result.push(c);
+    }
+    if let Some(fraction) = fraction {
+        result.push(rules.decimal);
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// Format a ratio as a percentage, a value of `0.5` is `50%`.
+pub fn format_percent(
+    value: f64,
+    decimals: usize,
+    lang: &LanguageIdentifier,
+    fallback: &LanguageIdentifier,
+) -> String {
+    let rules = resolve(lang, fallback);
+    let number = format_number(value * 100.0, decimals, lang, fallback);
+    if rules.space {
+        format!("{}{}%", number, NBSP)
+    } else {
+        format!("{}%", number)
+    }
+}
+
+/// Format an amount with a currency symbol using two decimal places.
+pub fn format_currency(
+    value: f64,
+    symbol: &str,
+    lang: &LanguageIdentifier,
+    fallback: &LanguageIdentifier,
+) -> String {
+    let rules = resolve(lang, fallback);
+    let number = format_number(value, 2, lang, fallback);
+    if rules.symbol_after {
+        format!("{}{}{}", number, NBSP, symbol)
+    } else {
+        format!("{}{}", symbol, number)
+    }
+}
+
+/// Resolve a named date format for a language, a format that does
+/// not match a named format is returned as the format specifier.
+pub fn date_format<'a>(
+    format: &'a str,
+    config: &'a DateConfig,
+    lang: &LanguageIdentifier,
+    fallback: &LanguageIdentifier,
+) -> &'a str {
+    config
+        .locales
+        .get(&lang.to_string())
+        .and_then(|formats| formats.get(format))
+        .map(|s| s.as_str())
+        .or_else(|| config.format(format, &fallback.to_string()))
+        .unwrap_or(format)
+}
+
+/// Format a date using a named format from the date configuration.
+pub fn format_date(
+    date: &DateTime<Utc>,
+    format: &str,
+    config: &DateConfig,
+    lang: &LanguageIdentifier,
+    fallback: &LanguageIdentifier,
+) -> String {
+    date.format(date_format(format, config, lang, fallback))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lang(id: &str) -> LanguageIdentifier {
+        id.parse().unwrap()
+    }
+
+    #[test]
+    fn format_number_separators() {
+        let en = lang("en");
+        assert_eq!("1,234,567.89", format_number(1234567.891, 2, &en, &en));
+        assert_eq!(
+            "1.234.567,89",
+            format_number(1234567.891, 2, &lang("de"), &en)
+        );
+        assert_eq!("-1,000", format_number(-1000.0, 0, &en, &en));
+        assert_eq!("999", format_number(999.0, 0, &en, &en));
+    }
+
+    #[test]
+    fn format_number_fallback() {
+        // No rules for Welsh so the fallback rules apply
+        assert_eq!(
+            "1.000,5",
+            format_number(1000.5, 1, &lang("cy"), &lang("de"))
+        );
+    }
+
+    #[test]
+    fn format_percent_currency() {
+        let en = lang("en");
+        let de = lang("de");
+        assert_eq!("12.5%", format_percent(0.125, 1, &en, &en));
+        assert_eq!("12,5\u{a0}%", format_percent(0.125, 1, &de, &en));
+        assert_eq!("$1,234.50", format_currency(1234.5, "$", &en, &en));
+        assert_eq!("1.234,50\u{a0}€", format_currency(1234.5, "€", &de, &en));
+    }
+
+    #[test]
+    fn format_date_locale() {
+        let mut config: DateConfig = Default::default();
+        config
+            .formats
+            .insert("date-short".to_string(), "%F".to_string());
+        let mut de = std::collections::HashMap::new();
+        de.insert("date-short".to_string(), "%d.%m.%Y".to_string());
+        config.locales.insert("de".to_string(), de);
+
+        let date: DateTime<Utc> = "2020-11-02T10:00:00Z".parse().unwrap();
+        let en = lang("en");
+        assert_eq!(
+            "2020-11-02",
+            format_date(&date, "date-short", &config, &en, &en)
+        );
+        assert_eq!(
+            "02.11.2020",
+            format_date(&date, "date-short", &config, &lang("de"), &en)
+        );
+        assert_eq!("2020", format_date(&date, "%Y", &config, &en, &en));
+    }
+}
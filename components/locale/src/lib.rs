@@ -1,5 +1,6 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 
@@ -22,6 +23,8 @@ pub enum Error {
 
 type Result<T> = std::result::Result<T, Error>;
 
+pub mod format;
+
 static_loader! {
     pub static LOCALES = {locales: "./locales", fallback_language: "en"};
 }
@@ -44,6 +47,11 @@ pub struct LocaleMap {
     #[serde(skip)]
     multi: bool,
 
+    /// Whether the fallback language has a URL prefix
+    /// when there are multiple languages.
+    #[serde(skip)]
+    prefix_fallback: bool,
+
     /// Enabled is active when we are able to load
     /// locale files at runtime.
     #[serde(skip)]
@@ -64,11 +72,30 @@ impl LocaleMap {
     pub fn alternate(&self) -> &Vec<String> {
         &self.alternate
     }
+
+    /// Get the fallback locale identifier.
+    pub fn fallback(&self) -> &str {
+        &self.fallback
+    }
+
+    /// Get the URL path prefix for a language.
+    ///
+    /// Languages are only prefixed when there are multiple languages
+    /// and the fallback language may be configured without a prefix.
+    pub fn url_prefix<'a>(&self, lang: &'a str) -> Option<&'a str> {
+        if !self.multi || (lang == self.fallback && !self.prefix_fallback) {
+            None
+        } else {
+            Some(lang)
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct Locales {
     languages: LocaleMap,
+    /// Additional locale directories, typically from plugins.
+    resources: Vec<PathBuf>,
 }
 
 impl Locales {
@@ -76,12 +103,14 @@ impl Locales {
         &self,
         arc: &Option<Box<ArcLoader>>,
         fallback: &str,
+        prefix_fallback: bool,
     ) -> Result<LocaleMap> {
         let mut res = LocaleMap {
             fallback: fallback.to_string(),
             map: HashMap::new(),
             enabled: arc.is_some(),
             multi: false,
+            prefix_fallback,
             alternate: vec![],
         };
 
@@ -119,6 +148,15 @@ impl Locales {
     {
         let locales_dir = path.as_ref();
         if locales_dir.exists() && locales_dir.is_dir() {
+            if !self.resources.is_empty() {
+                let merged =
+                    merged_arc(locales_dir, &self.resources, config.fluent());
+                return match merged {
+                    Ok(result) => (Some(Box::new(result)), None),
+                    Err(e) => (None, Some(e)),
+                };
+            }
+
             match arc(locales_dir, config.fluent()) {
                 Ok(result) => {
                     return (Some(Box::new(result)), None);
@@ -151,6 +189,16 @@ impl Locales {
         self.wrap(None)
     }
 
+    /// Add a directory of locale resources to merge with the
+    /// project locales.
+    ///
+    /// Messages defined by the project take precedence over
+    /// messages with the same identifier in additional resources
+    /// and resources added first take precedence over those added later.
+    pub fn add_resources<P: AsRef<Path>>(&mut self, dir: P) {
+        self.resources.push(dir.as_ref().to_path_buf());
+    }
+
     pub fn load<P>(&mut self, config: &Config, path: P) -> Result<&LocaleMap>
     where
         P: AsRef<Path>,
@@ -167,7 +215,11 @@ impl Locales {
 
         let arc_ref = self.wrap(arc);
 
-        self.languages = self.get_locale_map(arc_ref, config.lang())?;
+        self.languages = self.get_locale_map(
+            arc_ref,
+            config.lang(),
+            config.fluent().prefix_fallback(),
+        )?;
         Ok(&self.languages)
     }
 }
@@ -183,3 +235,159 @@ fn arc<'a, P: AsRef<Path>>(
         .shared_resources(Some(&[core]))
         .build()
 }
+
+fn merged_arc(
+    locales_dir: &Path,
+    resources: &[PathBuf],
+    fluent: &FluentConfig,
+) -> std::result::Result<ArcLoader, Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    merge(locales_dir, resources, fluent, dir.path())?;
+    arc(dir.path(), fluent)
+}
+
+/// Merge the project locales and additional resource directories
+/// into the target directory.
+///
+/// Only locales that exist in the project are merged and entries
+/// whose identifier has already been defined are omitted.
+fn merge(
+    locales_dir: &Path,
+    resources: &[PathBuf],
+    fluent: &FluentConfig,
+    target: &Path,
+) -> std::io::Result<()> {
+    let shared = locales_dir.join(fluent.shared());
+    let mut shared_ids = HashSet::new();
+    if shared.is_file() {
+        let content = fs::read_to_string(&shared)?;
+        shared_ids.extend(message_ids(&content));
+        fs::write(target.join(fluent.shared()), content)?;
+    }
+
+    for entry in fs::read_dir(locales_dir)? {
+        let locale = entry?.path();
+        if !locale.is_dir() {
+            continue;
+        }
+
+        let name = locale.file_name().unwrap();
+        let dest = target.join(name);
+        fs::create_dir_all(&dest)?;
+
+        let mut seen = shared_ids.clone();
+        for file in ftl_files(&locale)? {
+            let content = fs::read_to_string(&file)?;
+            seen.extend(message_ids(&content));
+            fs::write(dest.join(file.file_name().unwrap()), content)?;
+        }
+
+        for (index, dir) in resources.iter().enumerate() {
+            let source = dir.join(name);
+            if !source.is_dir() {
+                continue;
+            }
+            for file in ftl_files(&source)? {
+                let content = fs::read_to_string(&file)?;
+                let content = exclude_messages(&content, &mut seen);
+                let file_name = format!(
+                    "{}-{}",
+                    index,
+                    file.file_name().unwrap().to_string_lossy()
+                );
+                fs::write(dest.join(file_name), content)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn ftl_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file()
+            && path.extension().map(|e| e == "ftl").unwrap_or(false)
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Identifier for a line that starts a message or term.
+fn entry_id(line: &str) -> Option<&str> {
+    let first = line.chars().next()?;
+    if first.is_whitespace() || first == '#' {
+        return None;
+    }
+    line.find('=').map(|pos| line[..pos].trim())
+}
+
+fn message_ids(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(entry_id)
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Remove entries already in the seen set and add the
+/// identifiers of the remaining entries.
+fn exclude_messages(content: &str, seen: &mut HashSet<String>) -> String {
+    let mut result = String::new();
+    let mut skip = false;
+    for line in content.lines() {
+        if let Some(id) = entry_id(line) {
+            skip = seen.contains(id);
+            seen.insert(id.to_string());
+        } else if line.starts_with('#') {
+            skip = false;
+        }
+        if !skip {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merged_arc, Locales};
+    use config::test_util::TempProject;
+    use fluent_templates::Loader;
+    use unic_langid::LanguageIdentifier;
+
+    #[test]
+    fn load_multi_lingual() -> Result<(), Box<dyn std::error::Error>> {
+        let project = TempProject::multi_lingual(&["en", "fr"])?;
+        let config = project.config()?;
+        let mut locales: Locales = Default::default();
+        let languages = locales.load(&config, project.locales())?;
+        assert!(languages.is_multi_lingual());
+        assert_eq!(&vec!["fr".to_string()], languages.alternate());
+        Ok(())
+    }
+
+    #[test]
+    fn load_plugin_resources() -> Result<(), Box<dyn std::error::Error>> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project.write(
+            "plugin/locales/en/plugin.ftl",
+            "greeting = Plugin greeting\nplugin-label = Search\n",
+        )?;
+        let config = project.config()?;
+        let resources = vec![project.root().join("plugin").join("locales")];
+
+        // NOTE: the loader for `Locales` is stored in a global
+        // NOTE: so we build the merged loader directly
+        let loader =
+            merged_arc(&project.locales(), &resources, config.fluent())?;
+        let lang: LanguageIdentifier = "en".parse()?;
+        assert_eq!("Search", loader.lookup(&lang, "plugin-label"));
+        assert_eq!("Hello (en)", loader.lookup(&lang, "greeting"));
+        Ok(())
+    }
+}
@@ -1,9 +1,229 @@
+use std::fmt;
+
+use thiserror::Error;
+
+mod pretty;
+
+pub use pretty::{pretty, PrettyOptions};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Unknown build marker {0} on line {1}")]
+    UnknownMarker(String, usize),
+
+    #[error("Closing build marker {0} on line {1} has no opening marker")]
+    UnexpectedClose(String, usize),
+
+    #[error("Build marker {0} on line {1} is closed by {2} on line {3}")]
+    MismatchedMarker(String, usize, String, usize),
+
+    #[error("Build marker {0} on line {1} is never closed")]
+    UnclosedMarker(String, usize),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
 enum State {
     None,
     Inside,
     Between,
 }
 
+/// Elements whose text content must be preserved verbatim.
+const RAW_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Comment markers that control sections of markup at build time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    /// Remove the section, eg: `<!-- build:remove -->`.
+    Remove,
+    /// Preserve the section verbatim, eg: `<!-- build:keep -->`.
+    Keep,
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Remove => write!(f, "build:remove"),
+            Self::Keep => write!(f, "build:keep"),
+        }
+    }
+}
+
+/// Section of markup delimited by build markers.
+#[derive(Debug, PartialEq, Eq)]
+enum Section<'a> {
+    Minify(String),
+    Keep(&'a str),
+}
+
+/// Options for HTML minification.
+#[derive(Debug, Default, Clone)]
+pub struct HtmlOptions {
+    /// Collapse runs of whitespace inside text to a single space.
+    pub collapse_whitespace: bool,
+    /// Remove comments except for conditional comments.
+    pub strip_comments: bool,
+}
+
+// Minify an HTML string for a build.
+//
+// Sections between `<!-- build:remove -->` and `<!-- /build:remove -->`
+// are removed and sections between `<!-- build:keep -->` and
+// `<!-- /build:keep -->` are preserved exactly; the marker comments
+// are always removed.
+pub fn build<S: AsRef<str>>(
+    content: S,
+    options: &HtmlOptions,
+) -> Result<String> {
+    let mut buf = String::new();
+    for section in sections(content.as_ref())? {
+        match section {
+            Section::Minify(s) => {
+                if options.strip_comments {
+                    buf.push_str(&html_with(strip_comments(&s), options));
+                } else {
+                    buf.push_str(&html_with(&s, options));
+                }
+            }
+            Section::Keep(s) => buf.push_str(s),
+        }
+    }
+    Ok(buf)
+}
+
+/// Split content into sections using the build markers.
+fn sections(content: &str) -> Result<Vec<Section<'_>>> {
+    let mut out = Vec::new();
+    let mut stack: Vec<(Marker, usize)> = Vec::new();
+    let mut start = 0;
+    let mut pos = 0;
+
+    while let Some(offset) = content[pos..].find("<!--") {
+        let open = pos + offset;
+        let close = match content[open..].find("-->") {
+            Some(end) => open + end + 3,
+            None => break,
+        };
+        pos = close;
+
+        let inner = content[open + 4..close - 3].trim();
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        let name = match inner.strip_prefix("build:") {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let line = content[..open].matches('\n').count() + 1;
+        let marker = match name.trim() {
+            "remove" => Marker::Remove,
+            "keep" => Marker::Keep,
+            _ => return Err(Error::UnknownMarker(inner.to_string(), line)),
+        };
+
+        push_section(&mut out, &stack, &content[start..open]);
+        start = close;
+
+        if closing {
+            match stack.pop() {
+                None => {
+                    return Err(Error::UnexpectedClose(inner.to_string(), line))
+                }
+                Some((opened, opened_line)) if opened != marker => {
+                    return Err(Error::MismatchedMarker(
+                        opened.to_string(),
+                        opened_line,
+                        inner.to_string(),
+                        line,
+                    ))
+                }
+                _ => {}
+            }
+        } else {
+            stack.push((marker, line));
+        }
+    }
+
+    if let Some((marker, line)) = stack.pop() {
+        return Err(Error::UnclosedMarker(marker.to_string(), line));
+    }
+
+    push_section(&mut out, &stack, &content[start..]);
+    Ok(out)
+}
+
+fn push_section<'a>(
+    out: &mut Vec<Section<'a>>,
+    stack: &[(Marker, usize)],
+    text: &'a str,
+) {
+    if text.is_empty() || stack.iter().any(|(m, _)| *m == Marker::Remove) {
+        return;
+    }
+    if stack.iter().any(|(m, _)| *m == Marker::Keep) {
+        out.push(Section::Keep(text));
+    } else if let Some(Section::Minify(last)) = out.last_mut() {
+        // Join the markup either side of a removed section
+        last.push_str(text);
+    } else {
+        out.push(Section::Minify(text.to_string()));
+    }
+}
+
+/// Remove comments outside of script and style elements;
+/// conditional comments are preserved.
+fn strip_comments(content: &str) -> String {
+    let lower = content.to_ascii_lowercase();
+    let mut buf = String::new();
+    let mut pos = 0;
+    while pos < content.len() {
+        let comment = content[pos..].find("<!--").map(|i| pos + i);
+        let raw = ["<script", "<style"]
+            .iter()
+            .filter_map(|tag| lower[pos..].find(tag).map(|i| (pos + i, *tag)))
+            .min();
+
+        match (comment, raw) {
+            (Some(open), Some((raw_start, tag))) if raw_start < open => {
+                // Copy the raw element verbatim
+                let end_tag = format!("</{}", &tag[1..]);
+                let end = lower[raw_start..]
+                    .find(&end_tag)
+                    .map(|i| raw_start + i)
+                    .unwrap_or(content.len());
+                buf.push_str(&content[pos..end]);
+                pos = end;
+                if pos < content.len() {
+                    buf.push('<');
+                    pos += 1;
+                }
+            }
+            (Some(open), _) => {
+                buf.push_str(&content[pos..open]);
+                let close = content[open..]
+                    .find("-->")
+                    .map(|i| open + i + 3)
+                    .unwrap_or(content.len());
+                let comment = &content[open..close];
+                if comment.starts_with("<!--[if")
+                    || comment.contains("<![endif]")
+                {
+                    buf.push_str(comment);
+                }
+                pos = close;
+            }
+            (None, _) => {
+                buf.push_str(&content[pos..]);
+                pos = content.len();
+            }
+        }
+    }
+    buf
+}
+
 // Minify an HTML string.
 //
 // Designed so it doesn't break your markup (not overly agressive)
@@ -12,22 +232,33 @@ enum State {
 // Finds consecutive sections of whitespace between nodes and ignores
 // them.
 pub fn html<S: AsRef<str>>(content: S) -> String {
+    html_with(content, &Default::default())
+}
+
+// Minify an HTML string with options.
+pub fn html_with<S: AsRef<str>>(content: S, options: &HtmlOptions) -> String {
     let s = content.as_ref();
     let mut buf = "".to_string();
     let mut tmp = "".to_string();
     let mut state = State::None;
     let mut empty = true;
+    let mut tag_start = 0;
+    let mut raw: Option<&str> = None;
 
     for c in s.chars() {
         if c == '<' {
             if !empty {
-                buf.push_str(&tmp);
+                push_text(&mut buf, &tmp, options, raw.is_some());
             }
 
             tmp = "".to_string();
             state = State::Inside;
+            tag_start = buf.len();
         } else if c == '>' {
             if let State::Inside = state {
+                if options.collapse_whitespace {
+                    raw = raw_element(&buf[tag_start..], raw);
+                }
                 state = State::Between;
                 empty = true;
                 buf.push(c);
@@ -47,11 +278,64 @@ pub fn html<S: AsRef<str>>(content: S) -> String {
     }
 
     if !empty {
-        buf.push_str(&tmp);
+        push_text(&mut buf, &tmp, options, raw.is_some());
     }
     buf
 }
 
+fn push_text(buf: &mut String, text: &str, options: &HtmlOptions, raw: bool) {
+    if !options.collapse_whitespace || raw {
+        buf.push_str(text);
+        return;
+    }
+
+    // Only HTML whitespace collapses, non-breaking spaces are content
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !space {
+                buf.push(' ');
+            }
+            space = true;
+        } else {
+            buf.push(c);
+            space = false;
+        }
+    }
+}
+
+// Determine the raw element we are inside after a tag.
+fn raw_element<'a>(tag: &str, current: Option<&'a str>) -> Option<&'a str> {
+    let tag = tag.trim_start_matches('<');
+    let (closing, tag) = match tag.strip_prefix('/') {
+        Some(tag) => (true, tag),
+        None => (false, tag),
+    };
+
+    let name: String = tag
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    match current {
+        Some(element) => {
+            if closing && name == element {
+                None
+            } else {
+                current
+            }
+        }
+        None => {
+            if closing {
+                None
+            } else {
+                RAW_ELEMENTS.iter().find(|e| **e == name).copied()
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::*;
@@ -115,4 +399,79 @@ if (1 < 10 && 12 > 1) {
         let res = html(val);
         assert_eq!(res, val.to_string());
     }
+
+    #[test]
+    fn collapse_whitespace() {
+        let val = "<p>word    word</p>";
+        assert_eq!(html(val), val.to_string());
+
+        let options = HtmlOptions {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(html_with(val, &options), "<p>word word</p>".to_string());
+
+        let val = "<p>a\u{a0}\u{a0}b \n\t c</p>";
+        assert_eq!(
+            html_with(val, &options),
+            "<p>a\u{a0}\u{a0}b c</p>".to_string()
+        );
+    }
+
+    #[test]
+    fn collapse_whitespace_preformatted() {
+        let val =
+            "<pre>  keep   this  </pre><p>a   b</p><textarea>x    y</textarea>";
+        let expect =
+            "<pre>  keep   this  </pre><p>a b</p><textarea>x    y</textarea>";
+        let options = HtmlOptions {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(html_with(val, &options), expect.to_string());
+    }
+
+    #[test]
+    fn build_remove_block() -> Result<()> {
+        let val = "<body>\n<!-- build:remove -->\n<script src=\"dev.js\"></script>\n<!-- /build:remove -->\n<p>Content</p></body>";
+        let res = build(val, &Default::default())?;
+        assert_eq!("<body><p>Content</p></body>", res);
+        Ok(())
+    }
+
+    #[test]
+    fn build_keep_block() -> Result<()> {
+        let val = "<div>  <!-- note -->  <!-- build:keep --><!-- credits -->  <b>x</b><!-- /build:keep --></div>";
+        let options = HtmlOptions {
+            strip_comments: true,
+            ..Default::default()
+        };
+        let res = build(val, &options)?;
+        assert_eq!("<div><!-- credits -->  <b>x</b></div>", res);
+        Ok(())
+    }
+
+    #[test]
+    fn build_unmatched_marker() {
+        let val = "<p>a</p>\n<!-- build:remove -->\n<p>b</p>";
+        match build(val, &Default::default()) {
+            Err(Error::UnclosedMarker(marker, line)) => {
+                assert_eq!("build:remove", marker);
+                assert_eq!(2, line);
+            }
+            _ => panic!("expected unclosed marker error"),
+        }
+
+        let val = "<!-- build:keep --><p>a</p><!-- /build:remove -->";
+        assert!(matches!(
+            build(val, &Default::default()),
+            Err(Error::MismatchedMarker(..))
+        ));
+
+        let val = "<p>a</p><!-- /build:keep -->";
+        assert!(matches!(
+            build(val, &Default::default()),
+            Err(Error::UnexpectedClose(..))
+        ));
+    }
 }
@@ -0,0 +1,239 @@
+use crate::RAW_ELEMENTS;
+
+/// Elements that never have content or a closing tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "param", "source", "track", "wbr",
+];
+
+/// Options for pretty printing HTML.
+#[derive(Debug, Clone)]
+pub struct PrettyOptions {
+    /// Number of spaces for each level of indentation.
+    pub indent: usize,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self { indent: 2 }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Kind {
+    Open,
+    Close,
+    /// Void elements, comments, declarations and raw elements
+    /// which do not change the indentation level.
+    Single,
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+    Tag(Kind, &'a str),
+    Text(&'a str),
+}
+
+/// Find the end of a tag starting at `start` ignoring any
+/// `>` characters inside quoted attribute values.
+fn tag_end(s: &str, start: usize) -> usize {
+    let mut quote: Option<char> = None;
+    for (i, c) in s[start..].char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return start + i + 1,
+            None => {}
+        }
+    }
+    s.len()
+}
+
+/// Lowercase element name for a tag without the angle bracket.
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn tokens(s: &str) -> Vec<Token<'_>> {
+    let lower = s.to_ascii_lowercase();
+    let mut out = Vec::new();
+    let mut text = 0;
+    let mut pos = 0;
+
+    while let Some(offset) = s[pos..].find('<') {
+        let open = pos + offset;
+        let next = s[open + 1..].chars().next();
+        let is_tag = match next {
+            Some(c) => c.is_ascii_alphabetic() || c == '/' || c == '!',
+            None => false,
+        };
+        if !is_tag {
+            pos = open + 1;
+            continue;
+        }
+
+        if text < open {
+            out.push(Token::Text(&s[text..open]));
+        }
+
+        let (kind, end) = if s[open..].starts_with("<!--") {
+            let end = s[open..]
+                .find("-->")
+                .map(|i| open + i + 3)
+                .unwrap_or(s.len());
+            (Kind::Single, end)
+        } else {
+            let end = tag_end(s, open);
+            let tag = &s[open + 1..end];
+            let name = tag_name(tag);
+            if tag.starts_with('/') {
+                (Kind::Close, end)
+            } else if tag.starts_with('!') {
+                (Kind::Single, end)
+            } else if RAW_ELEMENTS.contains(&name.as_str()) {
+                // Raw elements are kept verbatim with the closing tag
+                let close = format!("</{}", name);
+                let end = lower[end..]
+                    .find(&close)
+                    .map(|i| tag_end(s, end + i))
+                    .unwrap_or(s.len());
+                (Kind::Single, end)
+            } else if tag.trim_end_matches('>').ends_with('/')
+                || VOID_ELEMENTS.contains(&name.as_str())
+            {
+                (Kind::Single, end)
+            } else {
+                (Kind::Open, end)
+            }
+        };
+
+        out.push(Token::Tag(kind, &s[open..end]));
+        text = end;
+        pos = end;
+    }
+
+    if text < s.len() {
+        out.push(Token::Text(&s[text..]));
+    }
+    out
+}
+
+// Pretty print an HTML string.
+//
+// Tags that are only separated by whitespace are placed on separate
+// lines and indented by their nesting depth; elements that contain
+// text are kept on a single line. Text and the content of raw elements
+// such as `<pre>` and `<script>` are not changed so the markup is
+// equivalent once minified.
+pub fn pretty<S: AsRef<str>>(content: S, options: &PrettyOptions) -> String {
+    let mut buf = String::new();
+    // Open elements, `Some(true)` when the content is inline text
+    // and `None` until the first child is seen.
+    let mut open: Vec<Option<bool>> = Vec::new();
+    let mut after_tag = false;
+
+    for token in tokens(content.as_ref()) {
+        match token {
+            Token::Text(text) => {
+                // Whitespace between tags is not significant
+                if after_tag && text.trim().is_empty() {
+                    continue;
+                }
+                if let Some(parent @ None) = open.last_mut() {
+                    *parent = Some(true);
+                }
+                buf.push_str(text);
+                after_tag = false;
+            }
+            Token::Tag(kind, tag) => {
+                let closed = if kind == Kind::Close {
+                    open.pop().flatten()
+                } else {
+                    if let Some(parent @ None) = open.last_mut() {
+                        *parent = Some(false);
+                    }
+                    Some(false)
+                };
+                let inline =
+                    closed != Some(false) || open.contains(&Some(true));
+                if after_tag && !inline {
+                    buf.push('\n');
+                    buf.push_str(&" ".repeat(open.len() * options.indent));
+                }
+                buf.push_str(tag);
+                if kind == Kind::Open {
+                    open.push(None);
+                }
+                after_tag = true;
+            }
+        }
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+
+    #[test]
+    fn pretty_nested() {
+        let val = "<!doctype html><html><head><meta charset=\"utf-8\"><title>Title</title></head><body><div><p>Hello <b>world</b></p><hr/></div></body></html>";
+        let expect = "<!doctype html>
+<html>
+  <head>
+    <meta charset=\"utf-8\">
+    <title>Title</title>
+  </head>
+  <body>
+    <div>
+      <p>Hello <b>world</b></p>
+      <hr/>
+    </div>
+  </body>
+</html>";
+        assert_eq!(expect, pretty(val, &Default::default()));
+    }
+
+    #[test]
+    fn pretty_indent_width() {
+        let val = "<ul><li><a href=\"/a>b\">A</a></li></ul>";
+        let expect =
+            "<ul>\n    <li>\n        <a href=\"/a>b\">A</a>\n    </li>\n</ul>";
+        let options = PrettyOptions { indent: 4 };
+        assert_eq!(expect, pretty(val, &options));
+    }
+
+    #[test]
+    fn pretty_preserve_raw() {
+        let val = "<div><pre>  <b>keep</b>\n  this</pre><script>if (a < b) { x = '</div>'; }</script></div>";
+        let expect = "<div>\n  <pre>  <b>keep</b>\n  this</pre>\n  <script>if (a < b) { x = '</div>'; }</script>\n</div>";
+        assert_eq!(expect, pretty(val, &Default::default()));
+    }
+
+    #[test]
+    fn pretty_minify_round_trip() {
+        let fixtures = [
+            "This is some plain text",
+            "<!doctype html>",
+            "<!doctype html> This is some text",
+            "<p>   <b>bold</b>    <i>italic</i></p>",
+            "<p>   <b>bold</b> with some inline text <i>italic</i>   \n</p>",
+            "<script>\n    const el = document.querySelector('main > header > .title');\n</script>",
+            "<script>\nif (1 < 10 && 12 > 1) {\n    if (foo < bar && bar > baz) {\n\n    }\n}\n</script>",
+            "<p>word    word</p>",
+            "<pre>  keep   this  </pre><p>a   b</p><textarea>x    y</textarea>",
+            "<body><!-- note --><section><h1>Title</h1><p>Content</p></section></body>",
+            "<div></div><p>a <em>b <strong>c</strong></em> d</p>",
+        ];
+        for val in fixtures.iter() {
+            let printed = pretty(val, &Default::default());
+            assert_eq!(html(val), html(&printed), "round trip for {}", val);
+        }
+    }
+}
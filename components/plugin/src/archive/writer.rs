@@ -1,6 +1,5 @@
-use std::fs::{remove_file, File};
+use std::fs::{self, remove_file, File};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
 
 use sha3::{Digest, Sha3_256};
 use tar::{Builder, EntryType, Header};
@@ -64,7 +63,9 @@ impl PackageWriter {
         let file = File::create(&self.target)?;
         let mut tarball = Builder::new(file);
 
-        let files = walk::find(src, |_| true);
+        // Sort the entries so the archive is reproducible
+        let mut files = walk::find(src, |_| true);
+        files.sort();
         for file in files.into_iter() {
             debug!("Archive file {:?}", &file);
 
@@ -89,7 +90,9 @@ impl PackageWriter {
                     )?;
                     append_file(&mut tarball, &plugin_path, plugin.as_bytes())?;
                 } else {
-                    tarball.append_file(rel, &mut File::open(&file)?)?;
+                    let mode =
+                        if is_executable(&file)? { 0o755 } else { 0o644 };
+                    append_entry(&mut tarball, rel, &fs::read(&file)?, mode)?;
                 }
             }
         }
@@ -144,18 +147,38 @@ fn append_file<P: AsRef<Path>>(
     tarball: &mut Builder<File>,
     path: P,
     contents: &[u8],
+) -> Result<()> {
+    append_entry(tarball, path, contents, 0o644)
+}
+
+/// Append a file entry with a zero timestamp and no owner so that
+/// the same plugin source always produces an identical archive.
+fn append_entry<P: AsRef<Path>>(
+    tarball: &mut Builder<File>,
+    path: P,
+    contents: &[u8],
+    mode: u32,
 ) -> Result<()> {
     let mut header = Header::new_gnu();
     header.set_entry_type(EntryType::file());
-    header.set_mode(0o644);
-    header.set_mtime(
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-    );
+    header.set_mode(mode);
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
     header.set_size(contents.len() as u64);
     header.set_cksum();
     tarball.append_data(&mut header, path.as_ref(), contents)?;
     Ok(())
 }
+
+/// Determine if a file has any executable permission bits set.
+#[cfg(unix)]
+fn is_executable(file: &Path) -> Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::metadata(file)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_file: &Path) -> Result<bool> {
+    Ok(false)
+}
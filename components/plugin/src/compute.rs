@@ -306,12 +306,14 @@ fn load_styles(
     computed: &mut Plugin,
 ) {
     let ext = OsStr::new("css");
-    let files = walk::find(dir, |e| {
+    let mut files = walk::find(dir, |e| {
         if let Some(extension) = e.extension() {
             return extension == ext;
         }
         false
     });
+    // Order matters for scripts and styles so it must be stable
+    files.sort();
     if !files.is_empty() {
         let mut items = files
             .iter()
@@ -349,12 +351,14 @@ fn load_scripts(
     computed: &mut Plugin,
 ) {
     let ext = OsStr::new("js");
-    let files = walk::find(dir, |e| {
+    let mut files = walk::find(dir, |e| {
         if let Some(extension) = e.extension() {
             return extension == ext;
         }
         false
     });
+    // Order matters for scripts and styles so it must be stable
+    files.sort();
     if !files.is_empty() {
         let mut items = files
             .iter()
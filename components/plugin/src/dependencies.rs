@@ -23,22 +23,117 @@ use crate::{
 
 const DEPENDENCY_STACK_SIZE: usize = 32;
 
+// Name of the requester for direct dependencies of a project.
+const PROJECT_REQUESTER: &str = "project";
+
 // Top-level of a project dependencies
 pub type DependencyTree = BTreeMap<String, PluginDependencyState>;
 
 /// Resolve the dependencies for a project.
+///
+/// Nested feature flags may not exceed the `feature_depth` limit.
 pub async fn resolve<P: AsRef<Path>>(
     project: P,
     dependencies: &DependencyMap,
     lock: &LockFile,
+    feature_depth: usize,
 ) -> Result<DependencyTree> {
     let mut out = BTreeMap::new();
-    let mut solver =
-        Solver::new(project.as_ref().to_path_buf(), dependencies, lock)?;
+    let mut solver = Solver::new(
+        project.as_ref().to_path_buf(),
+        dependencies,
+        lock,
+        feature_depth,
+    )?;
     solver.solve(&mut out).await?;
+    check_conflicts(&out)?;
     Ok(out)
 }
 
+/// Plugin names in a dependency tree, depth first with each
+/// name listed once, so resolved plugins have a stable order.
+pub fn order(tree: &DependencyTree) -> Vec<&str> {
+    fn walk<'a>(tree: &'a DependencyTree, out: &mut Vec<&'a str>) {
+        for (name, state) in tree.iter() {
+            if !out.contains(&name.as_str()) {
+                out.push(name);
+            }
+            walk(&state.transitive, out);
+        }
+    }
+    let mut out = Vec::new();
+    walk(tree, &mut out);
+    out
+}
+
+/// Requirements on each plugin in a dependency tree as tuples
+/// of the requester, version range and resolved version.
+type Requirements<'a> =
+    BTreeMap<&'a str, Vec<(&'a str, &'a VersionReq, &'a Option<Version>)>>;
+
+fn collect_requirements<'a>(
+    tree: &'a DependencyTree,
+    requester: &'a str,
+    out: &mut Requirements<'a>,
+) {
+    for (name, state) in tree.iter() {
+        // Local plugins are scoped to the parent so they
+        // can never conflict with another requester
+        if !state.is_local_scope() {
+            out.entry(name.as_str()).or_insert_with(Vec::new).push((
+                requester,
+                state.dependency.range(),
+                &state.version,
+            ));
+        }
+        collect_requirements(&state.transitive, name, out);
+    }
+}
+
+/// Detect plugins that are required more than once with version
+/// ranges that cannot be satisfied by any of the resolved versions.
+fn check_conflicts(tree: &DependencyTree) -> Result<()> {
+    let mut requirements: Requirements = BTreeMap::new();
+    collect_requirements(tree, PROJECT_REQUESTER, &mut requirements);
+
+    for (name, requests) in requirements {
+        if requests.len() < 2 {
+            continue;
+        }
+
+        let candidates: Vec<&Version> = requests
+            .iter()
+            .filter_map(|(_, _, version)| version.as_ref())
+            .collect();
+
+        // Nothing was resolved so missing plugins
+        // are reported elsewhere
+        if candidates.is_empty() {
+            continue;
+        }
+
+        let satisfied = candidates.iter().any(|version| {
+            requests.iter().all(|(_, range, _)| range.matches(version))
+        });
+
+        if !satisfied {
+            let requesters = requests
+                .iter()
+                .map(|(requester, range, _)| {
+                    format!("{} requires {}", requester, range)
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(Error::DependencyConflict(
+                name.to_string(),
+                requesters,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub enum MaybePlugin {
     /// Plugin could not be found
@@ -143,7 +238,14 @@ impl PluginDependencyState {
     }
 
     /// Is the dependency completely satisfied?
+    ///
+    /// Path dependencies are never satisfied so that they are
+    /// always installed again from the local folder.
     pub fn satisfied(&self) -> Result<bool> {
+        if self.dependency.is_path() {
+            return Ok(false);
+        }
+
         let has_lock_file_entry = self.entry.is_some();
         let has_plugin = if let MaybePlugin::Plugin(_) = self.plugin {
             true
@@ -189,6 +291,7 @@ struct Solver<'a> {
     dependencies: &'a DependencyMap,
     registry: Registry<'a>,
     lock: &'a LockFile,
+    feature_depth: usize,
 }
 
 impl<'a> Solver<'a> {
@@ -196,6 +299,7 @@ impl<'a> Solver<'a> {
         project: PathBuf,
         dependencies: &'a DependencyMap,
         lock: &'a LockFile,
+        feature_depth: usize,
     ) -> Result<Self> {
         let registry = registry::new_registry()?;
         Ok(Self {
@@ -203,6 +307,7 @@ impl<'a> Solver<'a> {
             dependencies,
             registry,
             lock,
+            feature_depth,
         })
     }
 
@@ -216,6 +321,7 @@ impl<'a> Solver<'a> {
             &mut Default::default(),
             out,
             None,
+            self.feature_depth,
         )
         .await?;
         Ok(())
@@ -231,6 +337,7 @@ async fn solver(
     stack: &mut Vec<String>,
     tree: &mut DependencyTree,
     parent: Option<MaybePlugin>,
+    feature_depth: usize,
 ) -> Result<()> {
     if stack.len() > DEPENDENCY_STACK_SIZE {
         return Err(Error::DependencyStackTooLarge(DEPENDENCY_STACK_SIZE));
@@ -314,7 +421,8 @@ async fn solver(
 
             // Filter nested dependencies to resolve depending upon the
             // requested and declared features.
-            let dependencies = dependencies.filter(&dep, feature_map)?;
+            let dependencies =
+                dependencies.filter(dep, feature_map, feature_depth)?;
 
             stack.push(name.clone());
 
@@ -328,6 +436,7 @@ async fn solver(
                 stack,
                 &mut transitive,
                 Some(solved),
+                feature_depth,
             )
             .await?;
 
@@ -437,3 +546,68 @@ async fn resolve_version<P: AsRef<Path>>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str, range: &str, version: &str) -> PluginDependencyState {
+        PluginDependencyState::new(
+            name.to_string(),
+            Dependency::new(VersionReq::parse(range).unwrap()),
+            MaybePlugin::NotFound,
+            Some(Version::parse(version).unwrap()),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn tree_order() {
+        let mut search = state("search", "^1", "1.0.0");
+        search
+            .transitive
+            .insert("shared".to_string(), state("shared", "^1", "1.0.0"));
+        let mut blog = state("blog", "^1", "1.0.0");
+        blog.transitive
+            .insert("shared".to_string(), state("shared", "^1", "1.0.0"));
+
+        let mut tree: DependencyTree = BTreeMap::new();
+        tree.insert("search".to_string(), search);
+        tree.insert("blog".to_string(), blog);
+        tree.insert("theme".to_string(), state("theme", "^1", "1.0.0"));
+
+        assert_eq!(vec!["blog", "shared", "search", "theme"], order(&tree));
+    }
+
+    #[test]
+    fn diamond_conflict() {
+        let mut left = state("left", "^1", "1.0.0");
+        left.transitive
+            .insert("shared".to_string(), state("shared", "^1.2", "1.4.0"));
+        let mut right = state("right", "^1", "1.0.0");
+        right
+            .transitive
+            .insert("shared".to_string(), state("shared", "^2", "2.1.0"));
+
+        let mut tree: DependencyTree = BTreeMap::new();
+        tree.insert("left".to_string(), left);
+        tree.insert("right".to_string(), right);
+
+        match check_conflicts(&tree) {
+            Err(Error::DependencyConflict(name, requesters)) => {
+                assert_eq!("shared", name);
+                assert!(requesters.starts_with("left requires ^1.2"));
+                assert!(requesters.contains("right requires ^2"));
+            }
+            _ => panic!("expected a dependency conflict"),
+        }
+
+        // Compatible ranges resolve to a shared version
+        tree.get_mut("right")
+            .unwrap()
+            .transitive
+            .insert("shared".to_string(), state("shared", "^1.3", "1.4.0"));
+        assert!(check_conflicts(&tree).is_ok());
+    }
+}
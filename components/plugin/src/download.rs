@@ -8,7 +8,7 @@ use pbr::{ProgressBar, Units};
 use semver::Version;
 use tokio::io::AsyncWriteExt;
 
-use crate::{Error, Result};
+use crate::{registry, Error, Result};
 
 pub(crate) const REGISTRY: &str =
     "http://s3-ap-southeast-1.amazonaws.com/registry.uwe.app";
@@ -21,7 +21,7 @@ pub struct FetchInfo {
 }
 
 fn local_archive(name: &str, version: &Version) -> Result<PathBuf> {
-    let downloads_cache_dir = dirs::downloads_dir()?;
+    let downloads_cache_dir = registry::registry_dir()?.join(dirs::DOWNLOADS);
     let downloads_cache_name =
         format!("{}{}{}.tar.xz", name, config::PLUGIN_NS, version);
     Ok(downloads_cache_dir.join(&downloads_cache_name))
@@ -49,6 +49,12 @@ pub async fn get(name: &str, version: &Version) -> Result<FetchInfo> {
             cached: true,
         });
     }
+    if registry::is_offline() {
+        return Err(Error::RegistryArchiveNotCached(
+            name.to_string(),
+            version.to_string(),
+        ));
+    }
     fetch(name, version).await
 }
 
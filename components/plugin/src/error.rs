@@ -11,6 +11,15 @@ pub enum LintError {
     #[error("Plugin description may not be empty")]
     LintPluginDescriptionEmpty,
 
+    #[error("Plugin version is missing or zero (0.0.0)")]
+    LintPluginVersionZero,
+
+    #[error("Plugin version {0} is a pre-release")]
+    LintPluginPrerelease(String),
+
+    #[error("Plugin does not declare a license")]
+    LintPluginNoLicense,
+
     #[error("Plugin names must contain at least one namespace (::)")]
     LintPluginNameSpace,
 
@@ -41,6 +50,12 @@ pub enum LintError {
     #[error("Plugin blueprint directives may only be used with plugins of type 'blueprint'")]
     LintBlueprintNotAllowed,
 
+    #[error("Plugin declares templates for the engine {0} but has no partials or layouts")]
+    LintEngineNoTemplates(String),
+
+    #[error("Plugin template {0} does not use the file extension for the engine {1}")]
+    LintTemplateExtension(String, String),
+
     #[error("{0}")]
     Syntax(String),
 
@@ -83,6 +98,9 @@ pub enum Error {
     #[error("Cyclic dependency {0}")]
     CyclicDependency(String),
 
+    #[error("Conflicting versions of {0} are required: {1}")]
+    DependencyConflict(String, String),
+
     #[error("Cyclic plugin {0}")]
     CyclicPlugin(PathBuf),
 
@@ -133,6 +151,17 @@ pub enum Error {
     #[error("Package {0} exists but no version found matching {1}")]
     RegistryPackageVersionNotFound(String, String),
 
+    #[error("Package {0} matching {1} is not in the local registry and network access is disabled (offline)")]
+    RegistryPackageNotCached(String, String),
+
+    #[error("Archive for {0}@{1} is not in the downloads cache and network access is disabled (offline)")]
+    RegistryArchiveNotCached(String, String),
+
+    #[error(
+        "Registry cannot be updated when network access is disabled (offline)"
+    )]
+    RegistryOffline,
+
     #[error("Attempt to install local scope {0} without plugins")]
     PluginWithNoParentScope(String),
 
@@ -169,6 +198,15 @@ pub enum Error {
     #[error("Failed to get plugin for a dependency that should be satisfied")]
     PluginNotSatisfied,
 
+    #[error("Plugin {0} has a post-install script but access is denied (needs `enable = [\"post-install\"]`)")]
+    NoPostInstallGrant(String),
+
+    #[error("Post-install script for plugin {0} timed out after {1} seconds")]
+    PostInstallTimeout(String, u64),
+
+    #[error("Post-install script for plugin {0} failed ({1})\n{2}")]
+    PostInstallFailed(String, String, String),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -213,6 +251,9 @@ pub enum Error {
     #[error(transparent)]
     Lint(#[from] self::LintError),
 
+    #[error("Plugin lint failed\n{0}")]
+    LintReport(crate::LintReport),
+
     #[error(transparent)]
     Config(#[from] config::Error),
 
@@ -29,8 +29,13 @@ pub async fn install(config: &Config) -> Result<ResolvedPlugins> {
         let lock_path = LockFile::get_lock_file(config.project());
         let lock = LockFile::load(&lock_path)?;
 
-        let tree = dependencies::resolve(config.project(), dependencies, &lock)
-            .await?;
+        let tree = dependencies::resolve(
+            config.project(),
+            dependencies,
+            &lock,
+            config.limits().features(),
+        )
+        .await?;
 
         // Partition into plugins that have already been resolved
         // and candidates for installation
@@ -112,6 +117,13 @@ pub async fn install(config: &Config) -> Result<ResolvedPlugins> {
             let result = LockFile::union(lock, lock_installed);
             result.write(&lock_path)?;
         }
+
+        // Installed plugins are resolved last so restore the
+        // dependency tree order
+        let order = dependencies::order(&tree);
+        resolved.sort_by_key(|(_, plugin)| {
+            order.iter().position(|name| *name == plugin.name())
+        });
     }
 
     scope_inheritance(&mut resolved)?;
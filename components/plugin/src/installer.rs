@@ -1,5 +1,6 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 use futures::TryFutureExt;
 use log::debug;
@@ -14,13 +15,22 @@ use config::{
     PLUGIN,
 };
 
-//use utils::walk;
+use utils::walk;
 
 use crate::{
-    archive::reader::PackageReader, compute, download, reader::read, Error,
-    Registry, Result,
+    archive::reader::PackageReader, compute, download, post_install,
+    reader::read, Error, Registry, Result,
 };
 
+/// Marker file written to the installation directory of plugins
+/// installed from a path dependency; the content is the source path
+/// and the stamp of the source files.
+const PATH_MARKER: &str = ".path";
+
+/// Version control directories that are not copied from
+/// a plugin folder.
+const VCS_DIRS: [&str; 3] = [".git", ".hg", ".svn"];
+
 /// Read the plugin info from an archive
 pub async fn peek<F: AsRef<Path>>(archive: F) -> Result<Plugin> {
     // Extract the archive
@@ -43,29 +53,77 @@ pub async fn install_dependency<P: AsRef<Path>>(
     force: bool,
     locals: Option<PluginMap>,
 ) -> Result<Plugin> {
-    let plugin = if let Some(ref target) = dep.target {
-        match target {
-            DependencyTarget::File { ref path } => {
-                install_path(project, path, None).await
-            }
-            DependencyTarget::Archive { ref archive } => {
-                install_archive(project, archive, force).await
-            }
-            DependencyTarget::Repo {
-                ref git,
-                ref prefix,
-            } => install_repo(project, git, prefix, force).await,
-            DependencyTarget::Local { ref scope } => {
-                install_local(project, scope, locals).await
-            }
-        }
+    if let Some(ref target) = dep.target {
+        install_target(
+            project,
+            target,
+            dep,
+            force,
+            locals,
+            &config::plugins_dir()?,
+        )
+        .await
     } else {
         install_registry(project, registry, name, dep).await
-    }?;
+    }
+}
 
+/// Install a dependency target.
+///
+/// The post-install script only runs when the plugin files are
+/// new: a copied folder, an extracted archive or a cloned repository.
+async fn install_target<P: AsRef<Path>>(
+    project: P,
+    target: &DependencyTarget,
+    dep: &Dependency,
+    force: bool,
+    locals: Option<PluginMap>,
+    plugins: &Path,
+) -> Result<Plugin> {
+    let (plugin, fresh, installed) = match target {
+        DependencyTarget::File { ref path } => {
+            let (plugin, copied) = copy_folder(project, path, plugins).await?;
+            let marker = plugin.base().join(PATH_MARKER);
+            (plugin, copied, marker)
+        }
+        DependencyTarget::Archive { ref archive } => {
+            let plugin = install_archive(project, archive, force).await?;
+            let base = plugin.base().to_path_buf();
+            (plugin, true, base)
+        }
+        DependencyTarget::Repo {
+            ref git,
+            ref prefix,
+        } => {
+            let (plugin, cloned) =
+                clone_repo(project, git, prefix, force).await?;
+            (plugin, cloned, repository_dir(git)?)
+        }
+        DependencyTarget::Local { ref scope } => {
+            return install_local(project, scope, locals).await
+        }
+    };
+    if fresh {
+        if let Err(e) = post_install::run(&plugin, dep) {
+            // Discard the installation so the script runs again
+            // when the dependency is next installed
+            remove(&installed)?;
+            return Err(e);
+        }
+    }
     Ok(plugin)
 }
 
+/// Remove a file or directory when it exists.
+fn remove(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
 /// Resolve to a canonical path.
 fn canonical<P: AsRef<Path>, F: AsRef<Path>>(
     project: P,
@@ -121,73 +179,87 @@ pub async fn install_path<P: AsRef<Path>, F: AsRef<Path>>(
     compute::transform(&plugin).await
 }
 
-/*
-/// Install a plugin from a file system path and compute the
-/// plugin data then copy the files over to the installation
-/// directory.
-pub async fn install_folder<P: AsRef<Path>, F: AsRef<Path>>(
-    project: P,
-    path: F,
-    _force: bool,
-) -> Result<Plugin> {
-    let plugin = install_path(project, path.as_ref(), None).await?;
-    //let plugin = copy_plugin_folder(path.as_ref(), plugin, force).await?;
-    Ok(plugin)
-}
-*/
-
-/*
-/// Copy a source plugin folder into the standard plugin installation
-/// directory location.
+/// Install a plugin from a path dependency by copying the plugin
+/// folder into a plugins directory and computing the plugin data
+/// from the copy.
 ///
-/// If the force flag is set and the installation location exists it
-/// is removed before copying files.
+/// The registry is never consulted and the installation is marked
+/// so it is not treated as a cached registry package. Version control
+/// directories are not copied and the copy is skipped when the stamp
+/// of the source files matches the stamp recorded for the installation.
 ///
-/// If the force flag is not set and the the installation location exists
-/// the existing plugin is returned.
-///
-/// The `plugin.toml` file in the source location is moved to `plugin.orig.toml`
-/// and the computed `plugin` information is written to `plugin.toml` instead.
-async fn copy_plugin_folder<S: AsRef<Path>>(
-    source: S,
-    plugin: Plugin,
-    force: bool,
-) -> Result<Plugin> {
-    let destination = installation_dir(plugin.name(), plugin.version())?;
-    let source = source.as_ref();
-    let target = &destination;
-
-    if target.exists() && !force {
-        return Ok(plugin);
-    } else if target.exists() && target.is_dir() && force {
-        debug!("Remove plugin {}", target.display());
-        fs::remove_dir_all(target)?;
-    }
-
-    let source = source.canonicalize()?;
-    let target = target.canonicalize().unwrap_or(target.to_path_buf());
-    if source != target {
-        walk::copy(&source, &target, |f| {
-            debug!("Install {:?}", f.display());
-            true
-        })?;
+/// Returns the plugin and whether the folder was copied.
+async fn copy_folder<P: AsRef<Path>, F: AsRef<Path>>(
+    project: P,
+    path: F,
+    plugins: &Path,
+) -> Result<(Plugin, bool)> {
+    // Validates the source folder has a plugin manifest
+    let (source, plugin) = install_file(project.as_ref(), path).await?;
+    let target =
+        plugins.join(installation_name(plugin.name(), plugin.version()));
+
+    let mut files = walk::find(&source, |file| {
+        !file
+            .strip_prefix(&source)
+            .map(|path| {
+                path.components()
+                    .any(|c| VCS_DIRS.iter().any(|d| c.as_os_str() == *d))
+            })
+            .unwrap_or(false)
+    });
+    files.sort();
+
+    let marker = format!(
+        "{}\n{}",
+        source.to_string_lossy(),
+        folder_stamp(&source, &files)?
+    );
+    let marker_file = target.join(PATH_MARKER);
+    let copy =
+        !marker_file.exists() || fs::read_to_string(&marker_file)? != marker;
+
+    if copy {
+        debug!("Install plugin folder {}", target.display());
+
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        fs::create_dir_all(&target)?;
+
+        for file in files {
+            let dest = target.join(file.strip_prefix(&source)?);
+            if file.is_dir() {
+                fs::create_dir_all(&dest)?;
+            } else {
+                fs::copy(&file, &dest)?;
+            }
+        }
+        fs::write(&marker_file, marker.as_bytes())?;
     }
 
-    // The source plugin definition must be replaced
-    // with our computed plugin data!
-    //
-    // Keep the original file as `plugin.orig.toml` like
-    // we do with archives.
-    let source_plugin = target.join(config::PLUGIN);
-    let original_plugin = target.join("plugin.orig.toml");
-    fs::rename(&source_plugin, &original_plugin)?;
-
-    let content = toml::to_string(&plugin)?;
-    fs::write(&source_plugin, content.as_bytes())?;
+    let (target, mut plugin) = install_file(project, &target).await?;
+    attributes(&mut plugin, &target, PluginSource::File(source), None)?;
+    Ok((compute::transform(&plugin).await?, copy))
+}
 
-    Ok(plugin)
+/// Compute a stamp for the files in a plugin folder from the
+/// path, size and modification time of each file.
+fn folder_stamp(source: &Path, files: &[PathBuf]) -> Result<String> {
+    let mut hasher = Sha3_256::new();
+    for file in files.iter().filter(|f| f.is_file()) {
+        let meta = file.metadata()?;
+        let modified = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        hasher.update(file.strip_prefix(source)?.to_string_lossy().as_bytes());
+        hasher.update(meta.len().to_le_bytes());
+        hasher.update(modified.to_le_bytes());
+    }
+    Ok(hex::encode(hasher.finalize().as_slice()))
 }
-*/
 
 /// Install from a local archive file.
 ///
@@ -240,15 +312,30 @@ pub async fn install_repo<P: AsRef<Path>>(
     prefix: &Option<UrlPath>,
     force: bool,
 ) -> Result<Plugin> {
-    //let scm_url_str = scm_url.to_string();
-    let repos_dir = dirs::repositories_dir()?;
+    let (plugin, _) = clone_repo(project, scm_url, prefix, force).await?;
+    Ok(plugin)
+}
+
+/// Get the cache directory for a plugin repository.
+fn repository_dir(scm_url: &str) -> Result<PathBuf> {
     let mut hasher = Sha3_256::new();
     hasher.update(scm_url.as_bytes());
     let scm_id = hex::encode(hasher.finalize().as_slice().to_owned());
+    Ok(dirs::repositories_dir()?.join(scm_id))
+}
 
-    let mut repo_path = repos_dir.join(scm_id);
+/// Install a plugin from a repository and determine if the
+/// repository was cloned.
+async fn clone_repo<P: AsRef<Path>>(
+    project: P,
+    scm_url: &str,
+    prefix: &Option<UrlPath>,
+    force: bool,
+) -> Result<(Plugin, bool)> {
+    let mut repo_path = repository_dir(scm_url)?;
     debug!("Install repository {}", repo_path.display());
-    scm::clone_or_fetch(scm_url, &repo_path)?;
+    let (_, cloned) =
+        scm::clone_or_fetch(scm_url, &repo_path, Some(scm::progress::bar()))?;
 
     let source = Some(PluginSource::Repo(scm_url.to_string()));
 
@@ -270,7 +357,7 @@ pub async fn install_repo<P: AsRef<Path>>(
         ));
     }
 
-    Ok(plugin)
+    Ok((plugin, cloned))
 }
 
 pub(crate) async fn install_local<P: AsRef<Path>, S: AsRef<str>>(
@@ -300,6 +387,11 @@ pub async fn dependency_installed<P: AsRef<Path>>(
     version_installed(project, registry, name, &version, Some(&package)).await
 }
 
+/// Determine if an installation was copied from a path dependency.
+pub fn is_path_install(name: &str, version: &Version) -> Result<bool> {
+    Ok(installation_dir(name, version)?.join(PATH_MARKER).exists())
+}
+
 pub fn is_installed(name: &str, version: &Version) -> Result<bool> {
     let extract_target = installation_dir(name, &version)?;
     let extract_target_plugin = extract_target.join(PLUGIN);
@@ -319,8 +411,9 @@ pub async fn version_installed<P: AsRef<Path>>(
     let extract_target_plugin = extract_target.join(PLUGIN);
 
     // Got an existing plugin file in the target cache directory
-    // so we should try to use that
-    if extract_target_plugin.exists() {
+    // so we should try to use that unless it was copied from a
+    // path dependency
+    if extract_target_plugin.exists() && !is_path_install(name, version)? {
         let (target, mut plugin) =
             install_file(project, &extract_target).await?;
         let source = PluginSource::Registry(download::REGISTRY.parse()?);
@@ -341,9 +434,12 @@ pub async fn version_installed<P: AsRef<Path>>(
 }
 
 pub fn installation_dir(name: &str, version: &Version) -> Result<PathBuf> {
-    let extract_dir =
-        format!("{}{}{}", name, config::PLUGIN_NS, version.to_string());
-    Ok(config::plugins_dir()?.join(extract_dir))
+    Ok(config::plugins_dir()?.join(installation_name(name, version)))
+}
+
+/// Name of the installation directory for a plugin version.
+fn installation_name(name: &str, version: &Version) -> String {
+    format!("{}{}{}", name, config::PLUGIN_NS, version)
 }
 
 /// Assign some private attributes to the plugin.
@@ -390,6 +486,11 @@ pub async fn install_registry<P: AsRef<Path>>(
         return Ok(plugin);
     }
 
+    // Discard any copy installed from a path dependency
+    if is_path_install(name, &version)? {
+        fs::remove_dir_all(&extract_target)?;
+    }
+
     // We will extract the temporary archive file here so the
     // directory must exist
     if !extract_target.exists() {
@@ -412,5 +513,158 @@ pub async fn install_registry<P: AsRef<Path>>(
     let (_target, _digest, mut plugin) = reader.into_inner();
     let source = PluginSource::Registry(download::REGISTRY.parse()?);
     attributes(&mut plugin, &extract_target, source, Some(&package.digest))?;
+    if let Err(e) = post_install::run(&plugin, dep) {
+        remove(&extract_target)?;
+        return Err(e);
+    }
     Ok(plugin)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{dependency::Capability, engine::TemplateEngine};
+
+    #[tokio::test]
+    async fn install_local_folder() -> Result<()> {
+        let plugins = tempfile::tempdir()?;
+        let project = tempfile::tempdir()?;
+        let source = project.path().join("theme");
+        fs::create_dir_all(source.join(config::LAYOUTS))?;
+        fs::create_dir_all(source.join(config::PARTIALS))?;
+        fs::create_dir_all(source.join(".git"))?;
+        fs::write(
+            source.join(PLUGIN),
+            "name = \"local::theme\"\nversion = \"1.0.0\"\n",
+        )?;
+        fs::write(
+            source.join(config::LAYOUTS).join("main.hbs"),
+            "{{{template}}}",
+        )?;
+        fs::write(
+            source.join(config::PARTIALS).join("nav.hbs"),
+            "<nav></nav>",
+        )?;
+        fs::write(source.join(".git").join("HEAD"), "ref: refs/heads/main")?;
+
+        let (plugin, copied) =
+            copy_folder(project.path(), "theme", plugins.path()).await?;
+        let version = plugin.version().clone();
+        let target = plugins
+            .path()
+            .join(installation_name("local::theme", &version));
+
+        assert!(copied);
+        assert_eq!(&target.canonicalize()?, plugin.base());
+        assert!(target.join(PATH_MARKER).exists());
+        assert!(!target.join(".git").exists());
+        assert_eq!(
+            &PluginSource::File(source.canonicalize()?),
+            plugin.source().as_ref().unwrap()
+        );
+
+        let templates = plugin.templates().get(&TemplateEngine::Handlebars);
+        let templates = templates.unwrap();
+        assert!(templates.layouts.as_ref().unwrap().contains_key("main"));
+        assert!(templates.partials.as_ref().unwrap().contains_key("nav"));
+
+        // Unchanged source folder is not copied again
+        let (_, copied) =
+            copy_folder(project.path(), "theme", plugins.path()).await?;
+        assert!(!copied);
+
+        // Changes to the source folder are copied
+        fs::write(
+            source.join(config::PARTIALS).join("nav.hbs"),
+            "<nav class=\"main\"></nav>",
+        )?;
+        let (_, copied) =
+            copy_folder(project.path(), "theme", plugins.path()).await?;
+        assert!(copied);
+        assert_eq!(
+            "<nav class=\"main\"></nav>",
+            fs::read_to_string(target.join(config::PARTIALS).join("nav.hbs"))?
+        );
+
+        // Missing manifest is an error
+        fs::create_dir_all(project.path().join("empty"))?;
+        assert!(copy_folder(project.path(), "empty", plugins.path())
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn install_post_install() -> Result<()> {
+        let plugins = tempfile::tempdir()?;
+        let project = tempfile::tempdir()?;
+        let source = project.path().join("generated");
+        fs::create_dir_all(&source)?;
+        fs::write(
+            source.join(PLUGIN),
+            r#"name = "local::generated"
+version = "1.0.0"
+
+[post-install]
+command = "sh"
+args = ["-c", "echo derived >> derived.txt"]
+"#,
+        )?;
+
+        let target = DependencyTarget::File {
+            path: PathBuf::from("generated"),
+        };
+        let mut dep = Dependency::new_target(target.clone());
+        let install = |dep: Dependency| {
+            let target = target.clone();
+            let project = project.path().to_path_buf();
+            let plugins = plugins.path().to_path_buf();
+            async move {
+                install_target(project, &target, &dep, true, None, &plugins)
+                    .await
+            }
+        };
+
+        // Denied without an explicit grant
+        match install(dep.clone()).await {
+            Err(Error::NoPostInstallGrant(_)) => {}
+            _ => panic!("expected post-install to be denied"),
+        }
+
+        dep.enable = Some(vec![Capability::PostInstall]);
+        let plugin = install(dep.clone()).await?;
+        assert_eq!(
+            "derived\n",
+            fs::read_to_string(plugin.base().join("derived.txt"))?
+        );
+        assert!(!source.join("derived.txt").exists());
+
+        // Only runs for a new copy of the plugin
+        let plugin = install(dep.clone()).await?;
+        assert_eq!(
+            "derived\n",
+            fs::read_to_string(plugin.base().join("derived.txt"))?
+        );
+
+        // Failures include the captured output
+        fs::write(
+            source.join(PLUGIN),
+            r#"name = "local::generated"
+version = "1.0.0"
+
+[post-install]
+command = "sh"
+args = ["-c", "echo broken >&2; exit 1"]
+"#,
+        )?;
+        match install(dep).await {
+            Err(Error::PostInstallFailed(_, _, output)) => {
+                assert_eq!("broken\n", output)
+            }
+            _ => panic!("expected post-install to fail"),
+        }
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ mod installer;
 mod linter;
 mod list;
 mod packager;
+mod post_install;
 mod publisher;
 mod reader;
 mod registry;
@@ -24,11 +25,11 @@ pub use installer::{
     dependency_installed, install_dependency, install_path, install_repo,
     installation_dir, peek, version_installed,
 };
-pub use linter::lint;
+pub use linter::{lint, LintReport, Severity};
 pub use list::list_dependencies;
 pub use packager::pack;
 pub use registry::{
-    check_for_updates, new_registry, update_registry, RegistryAccess,
-    RegistryFileAccess,
+    check_for_updates, is_offline, new_registry, update_registry,
+    RegistryAccess, RegistryFileAccess,
 };
 pub use system_plugins::install_docs;
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::fs;
 use std::path::Path;
 
@@ -7,6 +8,7 @@ use spdx::license_id;
 use bracket::{parser::ParserOptions, template::Template};
 
 use config::{
+    engine::TemplateEngine,
     features::FeatureMap,
     href::UrlPath,
     license::{License, LicenseGroup},
@@ -17,50 +19,152 @@ use utils::walk;
 
 use crate::{compute, error::LintError, reader::read};
 
-pub async fn lint<P: AsRef<Path>>(path: P) -> crate::Result<Plugin> {
+/// Severity of a problem found when linting a plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Problem that should be fixed but does not prevent publishing.
+    Warning,
+    /// Problem that must be fixed before publishing.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// All the problems found when linting a plugin.
+#[derive(Debug, Default)]
+pub struct LintReport {
+    problems: Vec<(Severity, LintError)>,
+}
+
+impl LintReport {
+    pub fn problems(&self) -> &Vec<(Severity, LintError)> {
+        &self.problems
+    }
+
+    /// Iterator of the problems that are errors.
+    pub fn errors(&self) -> impl Iterator<Item = &LintError> {
+        self.problems.iter().filter_map(|(severity, e)| {
+            if let Severity::Error = severity {
+                Some(e)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Iterator of the problems that are warnings.
+    pub fn warnings(&self) -> impl Iterator<Item = &LintError> {
+        self.problems.iter().filter_map(|(severity, e)| {
+            if let Severity::Warning = severity {
+                Some(e)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    fn error(&mut self, e: LintError) {
+        self.problems.push((Severity::Error, e));
+    }
+
+    fn warn(&mut self, e: LintError) {
+        self.problems.push((Severity::Warning, e));
+    }
+
+    /// Record the error from a check.
+    fn check(&mut self, result: Result<(), LintError>) {
+        if let Err(e) = result {
+            self.error(e);
+        }
+    }
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (severity, e) in self.problems.iter() {
+            writeln!(f, "{}: {}", severity, e)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lint the plugin at a path returning the computed plugin and
+/// any warnings; when the lint finds errors all the problems are
+/// returned in the error.
+pub async fn lint<P: AsRef<Path>>(
+    path: P,
+) -> crate::Result<(Plugin, LintReport)> {
     let plugin = read(path).await?;
     let plugin = compute::transform(&plugin).await?;
-    lint_plugin(&plugin)?;
-    Ok(plugin)
+    let report = lint_plugin(&plugin)?;
+    Ok((plugin, report))
 }
 
-pub(crate) fn lint_plugin(plugin: &Plugin) -> crate::Result<()> {
-    let result = match plugin.kind() {
-        PluginType::Library => lint_library(plugin),
-        PluginType::Blueprint => lint_blueprint(plugin),
-    };
-    Ok(result.map_err(crate::Error::from)?)
+pub(crate) fn lint_plugin(plugin: &Plugin) -> crate::Result<LintReport> {
+    let report = report(plugin);
+    if report.has_errors() {
+        return Err(crate::Error::LintReport(report));
+    }
+    Ok(report)
+}
+
+/// Collect all the problems for a plugin.
+pub fn report(plugin: &Plugin) -> LintReport {
+    let mut report: LintReport = Default::default();
+    match plugin.kind() {
+        PluginType::Library => lint_library(plugin, &mut report),
+        PluginType::Blueprint => lint_blueprint(plugin, &mut report),
+    }
+    report
 }
 
 /// Lint common to all plugin types.
-fn lint_common(plugin: &Plugin) -> Result<(), LintError> {
-    let ns_re = Regex::new("^[a-zA-Z0-9-]+$")?;
+fn lint_common(plugin: &Plugin, report: &mut LintReport) {
+    let ns_re = Regex::new("^[a-zA-Z0-9-]+$").unwrap();
 
     if plugin.name().trim().is_empty() {
-        return Err(LintError::LintPluginNameEmpty);
+        report.error(LintError::LintPluginNameEmpty);
+    } else if !plugin.name().contains(PLUGIN_NS) {
+        report.error(LintError::LintPluginNameSpace);
+    } else {
+        for ns in plugin.name().split(PLUGIN_NS) {
+            if !ns_re.is_match(ns) {
+                report.error(LintError::LintPluginNameInvalidNameSpace(
+                    ns.to_string(),
+                ));
+            }
+        }
     }
 
     if plugin.description().trim().is_empty() {
-        return Err(LintError::LintPluginDescriptionEmpty);
+        report.error(LintError::LintPluginDescriptionEmpty);
     }
 
-    if !plugin.name().contains(PLUGIN_NS) {
-        return Err(LintError::LintPluginNameSpace);
+    // Version defaults to zero when missing from the manifest
+    let version = plugin.version();
+    if version.major == 0 && version.minor == 0 && version.patch == 0 {
+        report.error(LintError::LintPluginVersionZero);
+    } else if version.is_prerelease() {
+        report.warn(LintError::LintPluginPrerelease(version.to_string()));
     }
 
-    for ns in plugin.name().split(PLUGIN_NS) {
-        if !ns_re.is_match(ns) {
-            return Err(LintError::LintPluginNameInvalidNameSpace(
-                ns.to_string(),
-            ));
-        }
+    if plugin.license().is_none() {
+        report.warn(LintError::LintPluginNoLicense);
     }
 
-    lint_licenses(plugin)?;
-
-    lint_symlinks(plugin)?;
-
-    Ok(())
+    report.check(lint_licenses(plugin));
+    report.check(lint_symlinks(plugin));
 }
 
 /// Walk all files and check for symbolic links.
@@ -89,66 +193,82 @@ fn lint_symlinks(plugin: &Plugin) -> Result<(), LintError> {
 }
 
 /// Lint for the blueprint plugin type.
-fn lint_blueprint(plugin: &Plugin) -> Result<(), LintError> {
-    lint_common(plugin)?;
+fn lint_blueprint(plugin: &Plugin, report: &mut LintReport) {
+    lint_common(plugin, report);
     if !plugin.features().is_empty() {
-        return Err(LintError::LintFeaturesSiteType);
+        report.error(LintError::LintFeaturesSiteType);
+    }
+    if let Err(e) = Config::load_config(plugin.base()) {
+        report.error(LintError::from(e));
     }
-    let _ = Config::load_config(plugin.base())?;
-    Ok(())
 }
 
 /// Lint for the library plugin type.
-fn lint_library(plugin: &Plugin) -> Result<(), LintError> {
-    lint_common(plugin)?;
+fn lint_library(plugin: &Plugin, report: &mut LintReport) {
+    lint_common(plugin, report);
 
     if plugin.blueprint().is_some() {
-        return Err(LintError::LintBlueprintNotAllowed);
+        report.error(LintError::LintBlueprintNotAllowed);
     }
 
     if !plugin.features().is_empty() {
-        lint_features(plugin, plugin.features())?;
+        report.check(lint_features(plugin, plugin.features()));
     }
 
     plugin
         .assets()
         .iter()
-        .try_for_each(|u| lint_path(plugin, u))?;
+        .for_each(|u| report.check(lint_path(plugin, u)));
 
-    plugin.styles().iter().try_for_each(|s| {
+    plugin.styles().iter().for_each(|s| {
         if let Some(src) = s.source() {
             let u = UrlPath::from(src);
-            lint_path(plugin, &u)?;
+            report.check(lint_path(plugin, &u));
         }
-        Ok::<(), LintError>(())
-    })?;
+    });
 
-    plugin.scripts().iter().try_for_each(|s| {
+    plugin.scripts().iter().for_each(|s| {
         if let Some(src) = s.source() {
             let u = UrlPath::from(src);
-            lint_path(plugin, &u)?;
+            report.check(lint_path(plugin, &u));
         }
-        Ok::<(), LintError>(())
-    })?;
-
-    //if let Some(ref templates) = plugin.templates {
-    for (_engine, templates) in plugin.templates().iter() {
-        if let Some(ref partials) = templates.partials {
-            for (_, asset) in partials {
-                lint_path(plugin, &asset.file)?;
-                lint_template(plugin, &asset.file)?;
-            }
+    });
+
+    for (engine, templates) in plugin.templates().iter() {
+        let partials = templates.partials.iter().flatten();
+        let layouts = templates.layouts.iter().flatten();
+        let mut assets = partials.chain(layouts).peekable();
+
+        if assets.peek().is_none() {
+            report.warn(LintError::LintEngineNoTemplates(engine.to_string()));
         }
-        if let Some(ref layouts) = templates.layouts {
-            for (_, asset) in layouts {
-                lint_path(plugin, &asset.file)?;
-                lint_template(plugin, &asset.file)?;
+
+        for (_, asset) in assets {
+            lint_engine_extension(engine, &asset.file, report);
+            match lint_path(plugin, &asset.file) {
+                Ok(_) => report.check(lint_template(plugin, &asset.file)),
+                Err(e) => report.error(e),
             }
         }
     }
-    //}
+}
 
-    Ok(())
+/// Templates for an engine should use the extension for the engine.
+fn lint_engine_extension(
+    engine: &TemplateEngine,
+    path: &UrlPath,
+    report: &mut LintReport,
+) {
+    let extension = Path::new(path.as_str())
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if extension != engine.extension() {
+        report.warn(LintError::LintTemplateExtension(
+            path.to_string(),
+            engine.to_string(),
+        ));
+    }
 }
 
 fn lint_licenses(plugin: &Plugin) -> Result<(), LintError> {
@@ -229,3 +349,42 @@ fn lint_template(plugin: &Plugin, path: &UrlPath) -> Result<(), LintError> {
     let _ = Template::compile(content, options)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[tokio::test]
+    async fn missing_layout() -> crate::Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir_all(dir.path().join(config::PARTIALS))?;
+        fs::write(dir.path().join(config::PARTIALS).join("nav.hbs"), "<nav>")?;
+        fs::write(
+            dir.path().join(config::PLUGIN),
+            r#"name = "std::theme"
+version = "1.0.0"
+description = "Theme"
+license = "MIT"
+
+[handlebars.layouts.main]
+file = "layouts/main.hbs"
+"#,
+        )?;
+
+        match lint(dir.path()).await {
+            Err(Error::LintReport(report)) => {
+                let errors = report.errors().collect::<Vec<_>>();
+                assert_eq!(1, errors.len());
+                match errors[0] {
+                    LintError::LintNoPluginFile(_, path) => {
+                        assert_eq!("layouts/main.hbs", path)
+                    }
+                    e => panic!("unexpected lint error {}", e),
+                }
+            }
+            _ => panic!("expected lint to fail"),
+        }
+        Ok(())
+    }
+}
@@ -20,8 +20,13 @@ pub async fn list_dependencies(config: &Config) -> Result<()> {
         let path = LockFile::get_lock_file(config.project());
         let lock = LockFile::load(&path)?;
 
-        let tree = dependencies::resolve(config.project(), dependencies, &lock)
-            .await?;
+        let tree = dependencies::resolve(
+            config.project(),
+            dependencies,
+            &lock,
+            config.limits().features(),
+        )
+        .await?;
         print_dependencies(&tree, 0)?;
     } else {
         info!("No plugin dependencies defined");
@@ -32,3 +32,41 @@ pub(crate) async fn pack_plugin(
     let (pkg, digest) = writer.into_inner();
     Ok((pkg, digest, plugin))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[tokio::test]
+    async fn reproducible_archive() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = dir.path().join("plugin");
+        fs::create_dir_all(source.join(config::PARTIALS))?;
+        fs::write(
+            source.join(config::PLUGIN),
+            "name = \"std::theme\"\nversion = \"1.0.0\"\ndescription = \"Theme\"\n",
+        )?;
+        fs::write(source.join(config::PARTIALS).join("nav.hbs"), "<nav>")?;
+        fs::write(source.join("style.css"), "body {}")?;
+
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        fs::create_dir_all(&first)?;
+        fs::create_dir_all(&second)?;
+
+        let (first_pkg, first_digest, _) =
+            pack(&source, &first.join(config::PACKAGE)).await?;
+
+        // Change the modification time of a file between builds
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        fs::write(source.join("style.css"), "body {}")?;
+
+        let (second_pkg, second_digest, _) =
+            pack(&source, &second.join(config::PACKAGE)).await?;
+
+        assert_eq!(first_digest, second_digest);
+        assert_eq!(fs::read(first_pkg)?, fs::read(second_pkg)?);
+        Ok(())
+    }
+}
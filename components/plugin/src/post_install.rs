@@ -0,0 +1,56 @@
+use std::io;
+use std::time::Duration;
+
+use log::{debug, info};
+
+use config::{
+    dependency::{Capability, Dependency},
+    plugin::Plugin,
+};
+
+use utils::command::capture_with_timeout;
+
+use crate::{Error, Result};
+
+/// Run the post-install script for a newly installed plugin in
+/// the installation directory.
+///
+/// The dependency must grant the `post-install` capability; when
+/// the script fails the captured output is returned in the error.
+pub(crate) fn run(plugin: &Plugin, dep: &Dependency) -> Result<()> {
+    let script = match plugin.post_install() {
+        Some(script) => script,
+        None => return Ok(()),
+    };
+
+    if !dep.is_enabled(Capability::PostInstall) {
+        return Err(Error::NoPostInstallGrant(plugin.to_string()));
+    }
+
+    info!("Post-install {} ({})", plugin, script.command());
+
+    let args = script.args().iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    let output = capture_with_timeout(
+        script.command(),
+        &args,
+        Some(plugin.base().to_path_buf()),
+        Duration::from_secs(script.timeout()),
+    )
+    .map_err(|e| match e.kind() {
+        io::ErrorKind::TimedOut => {
+            Error::PostInstallTimeout(plugin.to_string(), script.timeout())
+        }
+        _ => Error::from(e),
+    })?;
+
+    if !output.status.success() {
+        return Err(Error::PostInstallFailed(
+            plugin.to_string(),
+            output.status.to_string(),
+            format!("{}{}", output.stdout, output.stderr),
+        ));
+    }
+
+    debug!("{}", output.stdout);
+    Ok(())
+}
@@ -12,7 +12,7 @@ use crate::{
 
 /// Publish a plugin.
 pub async fn publish(source: &PathBuf) -> Result<(PathBuf, Vec<u8>, Plugin)> {
-    let plugin = lint(source).await?;
+    let (plugin, _) = lint(source).await?;
     //lint_plugin(&plugin)?;
 
     let registry_path = option_env!("UPM_PUBLISH")
@@ -74,7 +74,7 @@ pub async fn publish(source: &PathBuf) -> Result<(PathBuf, Vec<u8>, Plugin)> {
     scm::commit_file(&repo, &rel, &msg)?;
 
     info!("Push {}", repo_path.display());
-    scm::push_remote_name(&repo, scm::ORIGIN, None, None)?;
+    scm::push_remote_name(&repo, scm::ORIGIN, None, None, false)?;
 
     info!("Published {} ✓", &id);
 
@@ -45,7 +45,7 @@ pub async fn normalize<P: AsRef<Path>>(
         plugin
     };
 
-    let plugin = &toml::to_string(&plugin)?;
+    let plugin = &toml::to_string(&canonical(toml::Value::try_from(&plugin)?))?;
 
     let mut out = String::new();
     out.push_str(NORMALIZED_HEADER);
@@ -53,6 +53,38 @@ pub async fn normalize<P: AsRef<Path>>(
     Ok((original, out))
 }
 
+/// Sort tables and the set of assets so that the serialized plugin
+/// is stable as iteration order for hash maps is not deterministic.
+fn canonical(value: toml::Value) -> toml::Value {
+    match value {
+        toml::Value::Table(table) => {
+            let mut entries = table.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            toml::Value::Table(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value = match (key.as_str(), canonical(value)) {
+                            ("assets", toml::Value::Array(mut items)) => {
+                                items.sort_by(|a, b| {
+                                    a.as_str().cmp(&b.as_str())
+                                });
+                                toml::Value::Array(items)
+                            }
+                            (_, value) => value,
+                        };
+                        (key, value)
+                    })
+                    .collect(),
+            )
+        }
+        toml::Value::Array(items) => {
+            toml::Value::Array(items.into_iter().map(canonical).collect())
+        }
+        value => value,
+    }
+}
+
 pub async fn read_path<P: AsRef<Path>>(file: P) -> Result<Plugin> {
     let file = file.as_ref();
     let parent = file
@@ -12,7 +12,44 @@ use config::{
 
 use crate::{Error, Registry, Result};
 
+/// Determine if network access is disabled so that the registry
+/// is resolved using only the local packages.
+pub fn is_offline() -> bool {
+    config::env_flag(config::ENV_OFFLINE)
+}
+
+/// Get the configured registry mirror directory.
+fn mirror_dir() -> Option<PathBuf> {
+    std::env::var(config::ENV_REGISTRY_MIRROR)
+        .ok()
+        .map(PathBuf::from)
+}
+
+/// Get the registry directory; when a mirror is configured it is
+/// used instead of the default registry location.
+pub fn registry_dir() -> Result<PathBuf> {
+    registry_location(mirror_dir())
+}
+
+fn registry_location(mirror: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(mirror) = mirror {
+        if !mirror.is_dir() {
+            return Err(Error::RegistryNotDirectory(mirror));
+        }
+        return Ok(mirror);
+    }
+    Ok(dirs::registry_dir()?)
+}
+
 pub async fn check_for_updates() -> Result<bool> {
+    is_current(is_offline(), mirror_dir().is_some()).await
+}
+
+async fn is_current(offline: bool, mirrored: bool) -> Result<bool> {
+    // Mirrors are maintained externally and may not be a repository
+    if offline || mirrored {
+        return Ok(true);
+    }
     let registry_repo = dirs::registry_dir()?;
     let repo = scm::open(&registry_repo)?;
     let (is_current, _) = scm::is_current_with_remote(&repo, None, None)?;
@@ -20,6 +57,13 @@ pub async fn check_for_updates() -> Result<bool> {
 }
 
 pub async fn update_registry() -> Result<()> {
+    fetch(is_offline()).await
+}
+
+async fn fetch(offline: bool) -> Result<()> {
+    if offline {
+        return Err(Error::RegistryOffline);
+    }
     scm::system_repo::fetch_registry().await?;
     Ok(())
 }
@@ -82,6 +126,7 @@ pub trait RegistryAccess {
 pub struct RegistryFileAccess {
     reader: PathBuf,
     writer: PathBuf,
+    offline: bool,
 }
 
 impl RegistryFileAccess {
@@ -94,7 +139,18 @@ impl RegistryFileAccess {
             return Err(Error::RegistryNotDirectory(writer));
         }
 
-        Ok(Self { reader, writer })
+        Ok(Self {
+            reader,
+            writer,
+            offline: false,
+        })
+    }
+
+    /// Report packages that cannot be resolved as missing from
+    /// the local registry rather than the remote registry.
+    pub fn set_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
     }
 }
 
@@ -135,16 +191,30 @@ impl RegistryAccess for RegistryFileAccess {
         name: &str,
         version: &VersionReq,
     ) -> Result<(Version, RegistryItem)> {
-        let entry = self
-            .entry(name)
-            .await?
-            .ok_or_else(|| Error::RegistryPackageNotFound(name.to_string()))?;
-
-        let (version, package) = entry.find(version).ok_or_else(|| {
-            Error::RegistryPackageVersionNotFound(
+        let not_cached = || {
+            Error::RegistryPackageNotCached(
                 name.to_string(),
                 version.to_string(),
             )
+        };
+
+        let entry = self.entry(name).await?.ok_or_else(|| {
+            if self.offline {
+                not_cached()
+            } else {
+                Error::RegistryPackageNotFound(name.to_string())
+            }
+        })?;
+
+        let (version, package) = entry.find(version).ok_or_else(|| {
+            if self.offline {
+                not_cached()
+            } else {
+                Error::RegistryPackageVersionNotFound(
+                    name.to_string(),
+                    version.to_string(),
+                )
+            }
         })?;
 
         Ok((version.clone(), package.clone()))
@@ -237,6 +307,53 @@ impl RegistryAccess for RegistryFileAccess {
 }
 
 pub fn new_registry<'r>() -> Result<Registry<'r>> {
-    let reg = dirs::packages_dir()?;
-    Ok(Box::new(RegistryFileAccess::new(reg.clone(), reg.clone())?))
+    registry_at(registry_dir()?, is_offline())
+}
+
+fn registry_at<'r>(dir: PathBuf, offline: bool) -> Result<Registry<'r>> {
+    let reg = dir.join(dirs::PACKAGES);
+    Ok(Box::new(
+        RegistryFileAccess::new(reg.clone(), reg)?.set_offline(offline),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resolve_offline_mirror() -> Result<()> {
+        let mirror = tempfile::tempdir()?;
+        let packages = mirror.path().join(dirs::PACKAGES);
+        fs::create_dir_all(&packages)?;
+        fs::write(
+            packages.join("std::theme.json"),
+            r#"{"1.2.0":{"name":"std::theme","version":"1.2.0","digest":"00"}}"#,
+        )?;
+
+        let dir = registry_location(Some(mirror.path().to_path_buf()))?;
+        assert_eq!(mirror.path(), dir);
+        assert!(registry_location(Some(packages.join("missing"))).is_err());
+
+        assert!(is_current(true, true).await?);
+        assert!(fetch(true).await.is_err());
+
+        let registry = registry_at(dir, true)?;
+        let (version, item) = registry
+            .resolve("std::theme", &VersionReq::parse("^1").unwrap())
+            .await?;
+        assert_eq!(Version::new(1, 2, 0), version);
+        assert_eq!("std::theme", item.name());
+
+        match registry
+            .resolve("std::missing", &VersionReq::parse("^1").unwrap())
+            .await
+        {
+            Err(Error::RegistryPackageNotCached(name, _)) => {
+                assert_eq!("std::missing", name)
+            }
+            _ => panic!("expected package not cached error"),
+        }
+        Ok(())
+    }
 }
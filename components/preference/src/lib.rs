@@ -1,5 +1,5 @@
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
@@ -27,6 +27,7 @@ pub enum Error {
 pub struct Preferences {
     pub lang: Option<String>,
     pub ssh: Option<SshPreferences>,
+    pub release: Option<ReleasePreferences>,
 }
 
 impl Default for Preferences {
@@ -34,6 +35,34 @@ impl Default for Preferences {
         Self {
             lang: Some(String::from(LANG)),
             ssh: None,
+            release: None,
+        }
+    }
+}
+
+impl Preferences {
+    /// Resolve the preferred language against the locales
+    /// available to a project.
+    ///
+    /// When the preferred language is not one of the locales the
+    /// fallback language is returned with a warning message. If
+    /// no locales are available the preferred language is used.
+    pub fn resolve_lang(
+        &self,
+        locales: &[String],
+        fallback: &str,
+    ) -> (String, Option<String>) {
+        let lang = self.lang.as_deref().unwrap_or(LANG);
+        if locales.is_empty() || locales.iter().any(|l| l == lang) {
+            (lang.to_string(), None)
+        } else {
+            let warning = format!(
+                "Preferred language {} is not available in {}, using {}",
+                lang,
+                locales.join(","),
+                fallback
+            );
+            (fallback.to_string(), Some(warning))
         }
     }
 }
@@ -51,6 +80,24 @@ impl Default for SshPreferences {
     }
 }
 
+#[skip_serializing_none]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ReleasePreferences {
+    /// Verify the signatures of downloaded release artifacts,
+    /// signatures are verified unless this is set to false.
+    pub verify_signatures: Option<bool>,
+    /// Base URL used to download release artifacts, allows
+    /// releases to be installed from a mirror.
+    pub base_url: Option<String>,
+}
+
+impl ReleasePreferences {
+    pub fn verify_signatures(&self) -> bool {
+        self.verify_signatures.unwrap_or(true)
+    }
+}
+
 pub fn get_prefs_file() -> io::Result<PathBuf> {
     let mut buf = dirs::root_dir()?;
     buf.push(PREFERENCES);
@@ -72,6 +119,48 @@ pub fn load() -> Result<Preferences, Error> {
     Ok(prefs)
 }
 
+/// Get the path for the preferences file in a project.
+pub fn get_project_prefs_file<P: AsRef<Path>>(project: P) -> PathBuf {
+    project.as_ref().join(PREFERENCES)
+}
+
+/// Load the global preferences with the preferences for a
+/// project layered over them; fields declared in the project
+/// file take precedence.
+pub fn load_project<P: AsRef<Path>>(project: P) -> Result<Preferences, Error> {
+    load_layers(&[get_prefs_file()?, get_project_prefs_file(project)])
+}
+
+/// Load preference files merging the tables so that fields in
+/// later files override fields in earlier files.
+///
+/// Files that do not exist are ignored.
+fn load_layers(files: &[PathBuf]) -> Result<Preferences, Error> {
+    let mut merged = toml::Value::Table(Default::default());
+    for file in files {
+        if file.exists() {
+            let content = utils::fs::read_string(file)?;
+            merge(&mut merged, toml::from_str(&content)?);
+        }
+    }
+    Ok(merged.try_into()?)
+}
+
+fn merge(base: &mut toml::Value, layer: toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                if let Some(existing) = base.get_mut(&key) {
+                    merge(existing, value);
+                } else {
+                    base.insert(key, value);
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
 pub fn init_if_none() -> Result<(), Error> {
     let buf = get_prefs_file()?;
     if !buf.exists() {
@@ -81,3 +170,50 @@ pub fn init_if_none() -> Result<(), Error> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_lang_fallback() {
+        let prefs = Preferences {
+            lang: Some("fr".to_string()),
+            ..Default::default()
+        };
+        let locales = vec!["en".to_string(), "de".to_string()];
+        let (lang, warning) = prefs.resolve_lang(&locales, "en");
+        assert_eq!("en", lang);
+        assert!(warning.is_some());
+    }
+
+    #[test]
+    fn project_overrides_global() -> Result<(), Error> {
+        let root = tempfile::tempdir()?;
+        let global = root.path().join(PREFERENCES);
+        let project = root.path().join("project");
+        std::fs::create_dir_all(&project)?;
+
+        utils::fs::write_string(
+            &global,
+            "lang = \"fr\"\n\n[release]\nbase-url = \"https://example.com\"\n",
+        )?;
+        utils::fs::write_string(
+            get_project_prefs_file(&project),
+            "lang = \"de\"\n\n[release]\nverify-signatures = false\n",
+        )?;
+
+        let prefs =
+            load_layers(&[global.clone(), get_project_prefs_file(&project)])?;
+        assert_eq!(Some("de".to_string()), prefs.lang);
+        let release = prefs.release.unwrap();
+        assert_eq!(Some("https://example.com".to_string()), release.base_url);
+        assert!(!release.verify_signatures());
+        assert!(ReleasePreferences::default().verify_signatures());
+
+        // Missing project file uses the global preferences
+        let prefs = load_layers(&[global, root.path().join("missing.toml")])?;
+        assert_eq!(Some("fr".to_string()), prefs.lang);
+        Ok(())
+    }
+}
@@ -1,14 +1,15 @@
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use log::info;
+use log::{info, warn};
 use toml::map::Map;
 use toml::value::{Table, Value};
 
 use utils::walk;
 
-use crate::{Error, ProjectManifestEntry, Result};
+use crate::{gitignore, Error, ProjectManifestEntry, Result};
 use config::{
     href::UrlPath,
     plugin::{
@@ -25,6 +26,39 @@ const DEFAULT_MESSAGE: &str = "Initial files.";
 // Files to remove for projects created from blueprint plugins
 const REMOVE: [&str; 3] = [".ignore", "plugin.orig.toml", "plugin.toml"];
 
+/// Name of the git directory.
+const GIT_DIR: &str = ".git";
+
+/// Determines the initial version control state for a new project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcsInit {
+    /// Create a fresh repository discarding any blueprint history.
+    Detach,
+    /// Preserve the history of a git blueprint and commit the
+    /// new project files on top.
+    Keep,
+    /// Do not create a repository.
+    None,
+}
+
+impl Default for VcsInit {
+    fn default() -> Self {
+        Self::Detach
+    }
+}
+
+impl FromStr for VcsInit {
+    type Err = Error;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "detach" => Ok(Self::Detach),
+            "keep" => Ok(Self::Keep),
+            "none" => Ok(Self::None),
+            _ => Err(Error::UnknownVcsInit(s.to_string())),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ProjectSource {
     Plugin(String),
@@ -46,6 +80,8 @@ pub struct ProjectOptions {
     pub bare: bool,
     pub remote_name: String,
     pub remote_url: Option<String>,
+    pub gitignore: Option<String>,
+    pub vcs: VcsInit,
 }
 
 struct InitSettings {
@@ -56,6 +92,24 @@ struct InitSettings {
 
 // Read, modify and write the site configuration
 // with options for language, host and locales.
+/// Get the names of the locale directories in a blueprint.
+fn blueprint_locales(target: &Path) -> Result<Vec<String>> {
+    let mut locales = Vec::new();
+    let dir = target.join(config::SITE).join(config::LOCALES);
+    if dir.is_dir() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if let Some(name) = path.file_name() {
+                    locales.push(name.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+    locales.sort();
+    Ok(locales)
+}
+
 fn write_settings<P: AsRef<Path>>(
     output: P,
     settings: InitSettings,
@@ -63,7 +117,9 @@ fn write_settings<P: AsRef<Path>>(
     dependency: Dependency,
     plugin: Plugin,
 ) -> Result<()> {
-    let prefs = preference::load()?;
+    // Shared defaults committed with the blueprint override the
+    // global preferences
+    let prefs = preference::load_project(output.as_ref())?;
     let lang = settings.language;
     let host = settings.host;
     let locale_ids = settings.locale_ids;
@@ -79,7 +135,7 @@ fn write_settings<P: AsRef<Path>>(
         if !locale_ids.is_empty() {
             Some(locale_ids[0].clone())
         } else {
-            prefs.lang.clone()
+            None
         }
     };
 
@@ -89,6 +145,23 @@ fn write_settings<P: AsRef<Path>>(
 
     let mut site_config: Table =
         toml::from_str(&utils::fs::read_string(&config_file)?)?;
+
+    // Validate the preferred language against the blueprint locales
+    let language = if language.is_none() {
+        let fallback = site_config
+            .get(config::LANG_KEY)
+            .and_then(|v| v.as_str())
+            .unwrap_or(config::LANG)
+            .to_string();
+        let locales = blueprint_locales(&target)?;
+        let (lang, warning) = prefs.resolve_lang(&locales, &fallback);
+        if let Some(warning) = warning {
+            warn!("{}", warning);
+        }
+        Some(lang)
+    } else {
+        language
+    };
     if let Some(ref lang) = language {
         site_config.insert(
             config::LANG_KEY.to_string(),
@@ -225,6 +298,68 @@ fn create_target_parents<T: AsRef<Path>>(target: T) -> Result<()> {
     Ok(())
 }
 
+/// Initialize the target with the history of the blueprint repository.
+///
+/// When the blueprint is not the root of a git repository a fresh
+/// repository is created instead.
+/// Initialize version control for a new project and add the
+/// `remote` (name and URL) when given.
+fn init_vcs(
+    vcs: VcsInit,
+    source: &Path,
+    target: &Path,
+    message: &str,
+    gitignore_template: &str,
+    remote: Option<(&str, &str)>,
+) -> Result<()> {
+    if vcs == VcsInit::None {
+        return Ok(());
+    }
+
+    gitignore::write(target, gitignore_template)?;
+    match vcs {
+        VcsInit::Keep => keep_history(source, target, message)?,
+        _ => {
+            scm::init(target, message)?;
+        }
+    }
+    if let Some((name, url)) = remote {
+        scm::set_remote(target, name, url)?;
+    }
+    Ok(())
+}
+
+fn keep_history(source: &Path, target: &Path, message: &str) -> Result<()> {
+    let is_repo_root = source.join(GIT_DIR).is_dir();
+    if !is_repo_root {
+        warn!("Blueprint {} has no history to keep", source.display());
+        scm::init(target, message)?;
+        return Ok(());
+    }
+
+    let repo = clone_history(source, target)?;
+    scm::commit_all(&repo, message)?;
+    Ok(())
+}
+
+/// Move the git directory of a clone of the blueprint into the target.
+fn clone_history(source: &Path, target: &Path) -> Result<scm::Repository> {
+    // Clone inside the target so the git directory can be
+    // renamed without crossing file systems
+    let source_url = source.to_string_lossy().into_owned();
+    let tmp = tempfile::Builder::new()
+        .prefix(".history")
+        .tempdir_in(target)?;
+    scm::clone(source_url, tmp.path(), Some(scm::progress::bar()))?;
+    fs::rename(tmp.path().join(GIT_DIR), target.join(GIT_DIR))?;
+    tmp.close()?;
+
+    // Do not point at the local cache of the blueprint
+    let repo = scm::open(target)?;
+    let _ = repo.remote_delete(scm::ORIGIN);
+    Ok(repo)
+}
+
 pub async fn create(
     mut options: ProjectOptions,
 ) -> Result<ProjectManifestEntry> {
@@ -260,6 +395,12 @@ pub async fn create(
         config::parse_host(host)?;
     }
 
+    let gitignore_template = options
+        .gitignore
+        .clone()
+        .unwrap_or(gitignore::DEFAULT_TEMPLATE.to_string());
+    gitignore::check(&gitignore_template)?;
+
     let mut locale_ids = Vec::new();
     if let Some(ref locales) = options.locales {
         let locale_list = locales
@@ -392,6 +533,14 @@ pub async fn create(
     }
 
     walk::copy(&source_dir, &target, |f| {
+        // Never copy repository history, it is handled
+        // by the version control initialization.
+        if let Ok(relative) = f.strip_prefix(&source_dir) {
+            if relative.components().any(|c| c.as_os_str() == GIT_DIR) {
+                return false;
+            }
+        }
+
         // Built in files we always want to ignore.
         if let Some(file_name) = f.file_name() {
             let name = file_name.to_string_lossy();
@@ -422,12 +571,25 @@ pub async fn create(
 
     write_settings(&target, settings, name, dependency, plugin)?;
 
-    if !options.bare {
-        scm::init(&target, message)?;
-        if let Some(ref url) = options.remote_url {
-            scm::set_remote(&target, &options.remote_name, url)?;
-        }
-    }
+    let vcs = if options.bare {
+        VcsInit::None
+    } else {
+        options.vcs
+    };
+
+    let remote_name = &options.remote_name;
+    let remote = options
+        .remote_url
+        .as_ref()
+        .map(|url| (&remote_name[..], &url[..]));
+    init_vcs(
+        vcs,
+        &source_dir,
+        &target,
+        message,
+        &gitignore_template,
+        remote,
+    )?;
 
     info!("Created {} ✓", target.to_string_lossy());
 
@@ -443,3 +605,116 @@ pub async fn create(
 
     Ok(entry)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{clone_history, init_vcs, VcsInit, GIT_DIR};
+    use crate::gitignore::{DEFAULT_TEMPLATE, GITIGNORE};
+    use scm::Repository;
+    use std::error::Error;
+    use std::fs;
+    use std::path::Path;
+
+    fn set_identity(repo: &Repository) -> Result<(), Box<dyn Error>> {
+        let mut config = repo.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+        Ok(())
+    }
+
+    fn history(repo: &Repository) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        let mut messages = Vec::new();
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            messages.push(commit.message().unwrap_or("").to_string());
+        }
+        Ok(messages)
+    }
+
+    fn blueprint() -> Result<tempfile::TempDir, Box<dyn Error>> {
+        let source = tempfile::tempdir()?;
+        set_identity(&Repository::init(source.path())?)?;
+        fs::write(source.path().join("site.toml"), "lang = \"en\"")?;
+        scm::init(source.path(), "Blueprint files.")?;
+        Ok(source)
+    }
+
+    #[test]
+    fn detach_blueprint_history() -> Result<(), Box<dyn Error>> {
+        let source = blueprint()?;
+        let target = tempfile::tempdir()?;
+        fs::write(target.path().join("site.toml"), "lang = \"fr\"")?;
+
+        // Identity for the commit, the repository is reinitialized
+        set_identity(&Repository::init(target.path())?)?;
+        init_vcs(
+            VcsInit::Detach,
+            source.path(),
+            target.path(),
+            "Initial files.",
+            DEFAULT_TEMPLATE,
+            None,
+        )?;
+
+        let repo = scm::open(target.path())?;
+        assert_eq!(vec!["Initial files."], history(&repo)?);
+        let tree = repo.head()?.peel_to_tree()?;
+        assert!(tree.get_name("site.toml").is_some());
+        assert!(tree.get_name(GITIGNORE).is_some());
+        assert!(scm::is_clean(&repo));
+        Ok(())
+    }
+
+    #[test]
+    fn no_repository() -> Result<(), Box<dyn Error>> {
+        let source = blueprint()?;
+        let target = tempfile::tempdir()?;
+        fs::write(target.path().join("site.toml"), "lang = \"fr\"")?;
+
+        init_vcs(
+            VcsInit::None,
+            source.path(),
+            target.path(),
+            "Initial files.",
+            DEFAULT_TEMPLATE,
+            Some((scm::ORIGIN, "https://example.com/site.git")),
+        )?;
+
+        assert!(!target.path().join(GIT_DIR).exists());
+        assert!(!target.path().join(GITIGNORE).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn keep_blueprint_history() -> Result<(), Box<dyn Error>> {
+        let source = tempfile::tempdir()?;
+        set_identity(&Repository::init(source.path())?)?;
+        fs::write(source.path().join("site.toml"), "lang = \"en\"")?;
+        fs::write(source.path().join("draft.md"), "Draft")?;
+        scm::init(source.path(), "Blueprint files.")?;
+
+        // The draft was excluded by the blueprint
+        let target = tempfile::tempdir()?;
+        fs::write(target.path().join("site.toml"), "lang = \"fr\"")?;
+        let repo = clone_history(source.path(), target.path())?;
+        set_identity(&repo)?;
+        scm::commit_all(&repo, "Initial files.")?;
+
+        assert_eq!(vec!["Initial files.", "Blueprint files."], history(&repo)?);
+
+        let tree = repo.head()?.peel_to_tree()?;
+        assert!(tree.get_name("site.toml").is_some());
+        assert!(tree.get_name("draft.md").is_none());
+        assert!(repo.index()?.get_path(Path::new("draft.md"), 0).is_none());
+        assert!(scm::is_clean(&repo));
+
+        // Nothing is left behind from the clone
+        let names = fs::read_dir(target.path())?
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(2, names.len());
+        Ok(())
+    }
+}
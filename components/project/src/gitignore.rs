@@ -0,0 +1,161 @@
+use std::path::{Component, Path, PathBuf};
+
+use config::Config;
+use log::debug;
+
+use crate::{Error, Result};
+
+pub const GITIGNORE: &str = ".gitignore";
+
+/// Name of the default template.
+pub const DEFAULT_TEMPLATE: &str = "default";
+
+const OS: &str = "# Operating system files
+.DS_Store
+._*
+Thumbs.db
+desktop.ini
+";
+
+const EDITOR: &str = "# Editor files
+*.swp
+*~
+.idea/
+.vscode/
+";
+
+/// Get the content for a named template.
+///
+/// The `build` directory is relative to the project, when it is
+/// not given the build output is not ignored.
+fn template(name: &str, build: Option<&str>) -> Result<String> {
+    let build = build.map(|dir| format!("# Build output\n/{}/\n", dir));
+    let modules = Some("# Dependencies\nnode_modules/\n");
+    let sections = match name {
+        "minimal" => vec![build.as_deref()],
+        DEFAULT_TEMPLATE => vec![build.as_deref(), modules, Some(OS)],
+        "full" => vec![build.as_deref(), modules, Some(OS), Some(EDITOR)],
+        _ => return Err(Error::UnknownGitIgnoreTemplate(name.to_string())),
+    };
+    Ok(sections
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Get the build directory relative to the project.
+///
+/// A build directory outside the project is `None` as
+/// there is nothing in the project to ignore.
+fn build_dir(project: &Path, build: &Path) -> Option<String> {
+    let build = if build.is_absolute() {
+        build.strip_prefix(project).ok()?
+    } else {
+        build
+    };
+    let mut parts = Vec::new();
+    for part in build.components() {
+        match part {
+            Component::Normal(name) => parts.push(name.to_string_lossy()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+/// Check a template name is valid.
+pub(crate) fn check(name: &str) -> Result<()> {
+    template(name, Some(config::BUILD)).map(|_| ())
+}
+
+/// Write a .gitignore file into the target project.
+///
+/// The build directory to ignore is the build target in the
+/// site settings of the project.
+///
+/// An existing file (for example from a blueprint) is never overwritten.
+pub(crate) fn write<P: AsRef<Path>>(target: P, name: &str) -> Result<()> {
+    let file = target.as_ref().join(GITIGNORE);
+    if file.exists() {
+        debug!("Keep existing {}", file.display());
+        return Ok(());
+    }
+    let config = Config::load(target.as_ref(), false)?;
+    let build = config
+        .build
+        .as_ref()
+        .map(|settings| settings.target.clone())
+        .unwrap_or_else(|| PathBuf::from(config::BUILD));
+    let build = build_dir(target.as_ref(), &build);
+    utils::fs::write_string(&file, template(name, build.as_deref())?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gitignore_templates() -> Result<()> {
+        let build = Some(config::BUILD);
+        let pattern = format!("/{}/", config::BUILD);
+        assert!(template("minimal", build)?.contains(&pattern));
+        assert!(!template("minimal", build)?.contains("node_modules/"));
+        assert!(template(DEFAULT_TEMPLATE, build)?.contains("node_modules/"));
+        assert!(!template(DEFAULT_TEMPLATE, build)?.contains(".vscode/"));
+        assert!(template("full", build)?.contains(".vscode/"));
+        assert!(!template(DEFAULT_TEMPLATE, None)?.contains(&pattern));
+        assert!(matches!(
+            check("unknown"),
+            Err(Error::UnknownGitIgnoreTemplate(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn build_dirs() {
+        let project = Path::new("/sites/blog");
+        let build = |dir: &str| build_dir(project, Path::new(dir));
+        assert_eq!(Some("build".to_string()), build("build"));
+        assert_eq!(Some("out/site".to_string()), build("./out/site"));
+        assert_eq!(Some("public".to_string()), build("/sites/blog/public"));
+        assert_eq!(None, build("/var/www"));
+        assert_eq!(None, build("../public"));
+    }
+
+    #[test]
+    fn configured_build_target() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        utils::fs::write_string(
+            dir.path().join(config::SITE_TOML),
+            "lang = \"en\"\n\n[build]\ntarget = \"public\"\n",
+        )?;
+        write(dir.path(), "minimal")?;
+        let content = utils::fs::read_string(dir.path().join(GITIGNORE))?;
+        assert_eq!("# Build output\n/public/\n", content);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_existing_gitignore() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        utils::fs::write_string(
+            dir.path().join(config::SITE_TOML),
+            "lang = \"en\"\n",
+        )?;
+        write(dir.path(), "minimal")?;
+        let content = utils::fs::read_string(dir.path().join(GITIGNORE))?;
+        assert_eq!(template("minimal", Some(config::BUILD))?, content);
+
+        utils::fs::write_string(dir.path().join(GITIGNORE), "custom\n")?;
+        write(dir.path(), "full")?;
+        let content = utils::fs::read_string(dir.path().join(GITIGNORE))?;
+        assert_eq!("custom\n", content);
+        Ok(())
+    }
+}
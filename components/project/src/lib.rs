@@ -27,6 +27,12 @@ pub enum Error {
     #[error("Language {0} does not exist in the locales {1}")]
     LanguageMissingFromLocales(String, String),
 
+    #[error("Unknown gitignore template {0}, expected one of minimal, default or full")]
+    UnknownGitIgnoreTemplate(String),
+
+    #[error("Unknown version control option {0}, expected one of detach, keep or none")]
+    UnknownVcsInit(String),
+
     #[error("Target {0} exists, please move it away")]
     TargetExists(PathBuf),
 
@@ -64,13 +70,16 @@ type Result<T> = std::result::Result<T, Error>;
 
 mod bridge;
 mod create;
+mod gitignore;
 mod manage;
+mod published;
 
 pub use bridge::ConnectionBridge;
-pub use create::{create, ProjectOptions};
+pub use create::{create, ProjectOptions, VcsInit};
 pub use manage::{
     add, find, import, list, load, remove, ProjectList, ProjectManifestEntry,
 };
+pub use published::{content_checksum, PublishState};
 
 /// Compute the SHA3-256 checksum of a project path.
 pub(crate) fn digest<P: AsRef<Path>>(target: P) -> Result<Vec<u8>> {
@@ -81,11 +90,13 @@ pub(crate) fn digest<P: AsRef<Path>>(target: P) -> Result<Vec<u8>> {
 
 /// Compute the SHA3-256 checksum of a project path as a hex string.
 pub fn checksum<P: AsRef<Path>>(target: P) -> Result<String> {
-    let checksum = digest(target)?;
-    let s = checksum
+    Ok(to_hex(&digest(target)?))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes
         .iter()
         .map(|b| format!("{:x}", b))
         .collect::<Vec<_>>()
-        .join("");
-    Ok(s)
+        .join("")
 }
@@ -0,0 +1,122 @@
+use sha3::{Digest, Sha3_256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{checksum, to_hex, Result};
+
+/// Compute the SHA3-256 checksum of the files in a build target.
+///
+/// Relative paths and file contents are hashed in a stable order
+/// so the checksum only changes when the published content changes.
+pub fn content_checksum<P: AsRef<Path>>(target: P) -> Result<String> {
+    let target = target.as_ref();
+    let mut files = Vec::new();
+    collect(target, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha3_256::new();
+    for file in files {
+        let rel = file.strip_prefix(target).unwrap();
+        hasher.write(rel.to_string_lossy().as_bytes())?;
+        hasher.write(&[0])?;
+        hasher.write(&fs::read(&file)?)?;
+    }
+    Ok(to_hex(hasher.finalize().as_slice()))
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Last published content checksum for a project environment.
+#[derive(Debug)]
+pub struct PublishState {
+    file: PathBuf,
+}
+
+impl PublishState {
+    /// State for a project and publish environment stored
+    /// in the published directory.
+    pub fn new<P: AsRef<Path>>(project: P, env: &str) -> Result<Self> {
+        PublishState::in_dir(dirs::published_dir()?, project, env)
+    }
+
+    fn in_dir<D: AsRef<Path>, P: AsRef<Path>>(
+        dir: D,
+        project: P,
+        env: &str,
+    ) -> Result<Self> {
+        let name = format!("{}-{}", checksum(project)?, env);
+        Ok(Self {
+            file: dir.as_ref().join(name),
+        })
+    }
+
+    /// Checksum of the last published content, if any.
+    pub fn last(&self) -> Result<Option<String>> {
+        if self.file.exists() {
+            let value = fs::read_to_string(&self.file)?;
+            return Ok(Some(value.trim().to_string()));
+        }
+        Ok(None)
+    }
+
+    /// Determine if content with the given checksum differs
+    /// from the last published content.
+    pub fn is_changed(&self, checksum: &str) -> Result<bool> {
+        Ok(self.last()?.as_deref() != Some(checksum))
+    }
+
+    /// Record the checksum of successfully published content.
+    pub fn save(&self, checksum: &str) -> Result<()> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.file, checksum)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{content_checksum, PublishState};
+    use crate::Result;
+    use std::fs;
+
+    #[test]
+    fn publish_unchanged() -> Result<()> {
+        let state_dir = tempfile::tempdir()?;
+        let target = tempfile::tempdir()?;
+        fs::create_dir(target.path().join("docs"))?;
+        fs::write(target.path().join("index.html"), "<p>Home</p>")?;
+        fs::write(target.path().join("docs").join("index.html"), "<p>Doc</p>")?;
+
+        let state = PublishState::in_dir(state_dir.path(), "/site", "prod")?;
+        let first = content_checksum(target.path())?;
+        assert!(state.is_changed(&first)?);
+        state.save(&first)?;
+
+        // Nothing changed so the second publish is skipped
+        let second = content_checksum(target.path())?;
+        assert!(!state.is_changed(&second)?);
+
+        fs::write(target.path().join("index.html"), "<p>Updated</p>")?;
+        let third = content_checksum(target.path())?;
+        assert!(state.is_changed(&third)?);
+
+        // Environments are tracked separately
+        let staging =
+            PublishState::in_dir(state_dir.path(), "/site", "staging")?;
+        assert!(staging.is_changed(&first)?);
+        Ok(())
+    }
+}
@@ -1,3 +1,3 @@
 pub(crate) mod provider;
 mod redirects;
-mod report;
+pub(crate) mod report;
@@ -1,16 +1,22 @@
 use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::PathBuf;
-use std::{fs, io};
 
 use rusoto_core::Region;
 use rusoto_s3::{DeleteObjectRequest, PutObjectRequest, S3Client, S3};
 
-use log::{debug, error, info};
+use log::{error, info};
 
 use crate::{s3_util::*, Error, Result};
-use config::redirect::RedirectManifest;
+use config::{
+    cache::{CacheManifest, CACHE_FILE},
+    redirect::RedirectManifest,
+};
 
-use super::{redirects, report::FileBuilder};
+use super::{
+    redirects,
+    report::{Diff, FileBuilder, HashAlgorithm},
+};
 
 #[derive(Debug)]
 pub struct PublishRequest {
@@ -22,6 +28,49 @@ pub struct PublishRequest {
     pub build_target: PathBuf,
     pub sync_redirects: bool,
     pub redirects_manifest: Option<RedirectManifest>,
+    pub delete_limit: DeleteLimit,
+    pub allow_mass_delete: bool,
+}
+
+/// Limits the number of remote objects that may be deleted
+/// to protect against wiping a site when the local build
+/// directory is empty or misconfigured.
+#[derive(Debug, Clone)]
+pub struct DeleteLimit {
+    /// Maximum number of deletions.
+    pub max: Option<usize>,
+    /// Maximum proportion of remote objects that may be deleted.
+    pub ratio: f64,
+    /// Minimum number of deletions before the ratio is enforced
+    /// so that small sites may delete most of their files.
+    pub min: usize,
+}
+
+impl Default for DeleteLimit {
+    fn default() -> Self {
+        Self {
+            max: None,
+            ratio: DELETE_RATIO,
+            min: DELETE_MIN,
+        }
+    }
+}
+
+impl DeleteLimit {
+    /// Determine if deleting `deleted` of `remote` objects exceeds the limit.
+    pub fn exceeded(&self, deleted: usize, remote: usize) -> bool {
+        if deleted == 0 {
+            return false;
+        }
+        if let Some(max) = self.max {
+            if deleted > max {
+                return true;
+            }
+        }
+        deleted >= self.min
+            && remote > 0
+            && (deleted as f64 / remote as f64) > self.ratio
+    }
 }
 
 impl PublishRequest {
@@ -30,14 +79,36 @@ impl PublishRequest {
     }
 }
 
+/// Default maximum proportion of remote objects that may be deleted.
+const DELETE_RATIO: f64 = 0.5;
+
+/// Default minimum number of deletions before the ratio is enforced.
+const DELETE_MIN: usize = 10;
+
 pub async fn publish(mut request: PublishRequest) -> Result<()> {
     let (file_builder, diff) = prepare_diff(&mut request).await?;
+    check_deletions(&request, &diff)?;
     sync_content(request, file_builder, diff).await
 }
 
+/// Abort before any changes are made when the number
+/// of deletions exceeds the limit for the request.
+fn check_deletions(request: &PublishRequest, diff: &Diff) -> Result<()> {
+    if request.keep_remote || request.allow_mass_delete {
+        return Ok(());
+    }
+
+    let deleted = diff.deleted.len();
+    let remote = diff.remote_len();
+    if request.delete_limit.exceeded(deleted, remote) {
+        return Err(Error::MassDelete(deleted, remote));
+    }
+    Ok(())
+}
+
 async fn prepare_diff(
     request: &mut PublishRequest,
-) -> Result<(FileBuilder, DiffReport)> {
+) -> Result<(FileBuilder, Diff)> {
     let delimiter = utils::terminal::delimiter();
 
     println!("{}", &delimiter);
@@ -48,8 +119,10 @@ async fn prepare_diff(
     info!("Building local file list");
 
     // Create the list of local build files
+    // S3 reports the MD5 digest of simple uploads as the e-tag
     let mut file_builder =
-        FileBuilder::new(request.build_target.clone(), request.prefix.clone());
+        FileBuilder::new(request.build_target.clone(), request.prefix.clone())
+            .with_hash(HashAlgorithm::Md5);
     file_builder.walk()?;
 
     info!("Local objects {}", file_builder.keys.len());
@@ -70,7 +143,13 @@ async fn prepare_diff(
 
     info!("Remote objects {}", remote.len());
 
-    let diff = diff(&file_builder, &remote, &etags)?;
+    let mut diff = file_builder.diff(&remote, &etags)?;
+
+    // Upload files when only the cache directive has changed
+    let cache = CacheManifest::load(&request.build_target)?;
+    let remote_cache =
+        load_bucket_cache(&client, &request.bucket, &request.prefix).await?;
+    changed_directives(&mut diff, &file_builder, &cache, &remote_cache);
 
     let (redirects_manifest, redirects_manifest_file) =
         redirects::diff_redirects(
@@ -94,33 +173,102 @@ async fn prepare_diff(
     Ok((file_builder, diff))
 }
 
+/// Load the cache manifest of the last publish from the remote bucket.
+async fn load_bucket_cache(
+    client: &S3Client,
+    bucket: &str,
+    prefix: &Option<String>,
+) -> Result<CacheManifest> {
+    let key = if let Some(prefix) = prefix {
+        format!("{}/{}", prefix, CACHE_FILE)
+    } else {
+        CACHE_FILE.to_string()
+    };
+    if let Some(buf) = get_object_bytes(client, bucket, &key).await? {
+        return Ok(serde_json::from_slice(&buf)?);
+    }
+    Ok(Default::default())
+}
+
+/// Get the cache directive for the file of a key.
+fn cache_directive<'a>(
+    builder: &FileBuilder,
+    key: &str,
+    cache: &'a CacheManifest,
+) -> Option<&'a str> {
+    builder
+        .from_key(key)
+        .strip_prefix(&builder.base)
+        .ok()
+        .and_then(|rel| cache.get(&utils::url::to_href_separator(rel)))
+}
+
+/// Move unchanged files whose cache directive differs from the
+/// directive of the last publish into the changed set so the
+/// object metadata is updated.
+fn changed_directives(
+    diff: &mut Diff,
+    builder: &FileBuilder,
+    local: &CacheManifest,
+    remote: &CacheManifest,
+) {
+    let changed: Vec<String> = diff
+        .unchanged
+        .iter()
+        .filter(|key| {
+            cache_directive(builder, key, local)
+                != cache_directive(builder, key, remote)
+        })
+        .cloned()
+        .collect();
+    for key in changed {
+        diff.unchanged.remove(&key);
+        diff.changed.insert(key);
+    }
+}
+
+/// Request to upload the file for a key including the
+/// cache directive declared for the file.
+fn put_request(
+    bucket: &str,
+    builder: &FileBuilder,
+    key: &str,
+    cache: &CacheManifest,
+) -> PutObjectRequest {
+    PutObjectRequest {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        cache_control: cache_directive(builder, key, cache)
+            .map(|directive| directive.to_string()),
+        ..Default::default()
+    }
+}
+
 async fn sync_content(
     mut request: PublishRequest,
     builder: FileBuilder,
-    diff: DiffReport,
+    diff: Diff,
 ) -> Result<()> {
     let delimiter = utils::terminal::delimiter();
 
     println!("{}", &delimiter);
     println!(" DELTA");
     println!("{}", &delimiter);
-    info!("New {}", diff.upload.len());
+    info!("New {}", diff.added.len());
     info!("Update {}", diff.changed.len());
+    info!("Unchanged {}", diff.unchanged.len());
     info!("Delete {}", diff.deleted.len());
 
     let mut errors: Vec<Error> = Vec::new();
     let mut uploaded: u64 = 0;
     let mut deleted: u64 = 0;
     let client = request.new_client()?;
+    let cache = CacheManifest::load(&request.build_target)?;
 
-    let push: HashSet<_> = diff.upload.union(&diff.changed).collect();
+    let push: Vec<_> = diff.added.union(&diff.changed).collect();
     for k in push {
         let local_path = builder.from_key(&k);
-        let req = PutObjectRequest {
-            bucket: request.bucket.clone(),
-            key: k.clone(),
-            ..Default::default()
-        };
+        let req = put_request(&request.bucket, &builder, k, &cache);
 
         /*
         info!("Upload {}", local_path.display());
@@ -206,57 +354,71 @@ async fn sync_content(
     Ok(())
 }
 
-#[derive(Debug)]
-struct DiffReport {
-    // Files that have the same checksums (etag)
-    pub same: HashSet<String>,
-    // New files that should be uploaded
-    pub upload: HashSet<String>,
-    // Files that exist in remote but have changed or new files
-    // that should be uploaded
-    pub changed: HashSet<String>,
-    // Files that exist on remote but no longer exist locally
-    pub deleted: HashSet<String>,
-}
-
-fn diff(
-    builder: &FileBuilder,
-    remote: &HashSet<String>,
-    etags: &HashMap<String, String>,
-) -> io::Result<DiffReport> {
-    let local = &builder.keys;
-
-    let mut same = HashSet::new();
-    let mut upload = HashSet::new();
-    let mut changed = HashSet::new();
-    let mut deleted = HashSet::new();
-
-    for k in local.intersection(&remote) {
-        if let Some(etag) = etags.get(k) {
-            let local_path = builder.from_key(&k);
-            let local_etag = read_file_etag(&local_path)?;
-            if etag == &local_etag {
-                debug!("Checksum match {} {}", etag, local_path.display());
-                same.insert(k.clone());
-                continue;
-            }
-        }
-
-        changed.insert(k.clone());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delete_limit() {
+        let limit: DeleteLimit = Default::default();
+        assert!(!limit.exceeded(0, 100));
+        assert!(!limit.exceeded(50, 100));
+        assert!(limit.exceeded(51, 100));
+        // Small sites are below the minimum for the ratio
+        assert!(!limit.exceeded(3, 4));
+
+        let limit = DeleteLimit {
+            max: Some(5),
+            ratio: 1.0,
+            min: 0,
+        };
+        assert!(!limit.exceeded(5, 5));
+        assert!(limit.exceeded(6, 100));
+        assert!(!limit.exceeded(1, 1));
     }
 
-    for k in local.difference(&remote) {
-        upload.insert(k.clone());
+    #[test]
+    fn cache_metadata() -> Result<()> {
+        let base = tempfile::tempdir()?;
+        fs::write(
+            base.path().join(config::cache::CACHE_FILE),
+            r#"{"news/index.html": "no-store"}"#,
+        )?;
+        let cache = CacheManifest::load(base.path())?;
+        let builder =
+            FileBuilder::new(base.path().to_path_buf(), Some("site".into()));
+
+        let req =
+            put_request("bucket", &builder, "site/news/index.html", &cache);
+        assert_eq!("site/news/index.html", req.key);
+        assert_eq!(Some("no-store".to_string()), req.cache_control);
+
+        let req = put_request("bucket", &builder, "site/index.html", &cache);
+        assert_eq!(None, req.cache_control);
+        Ok(())
     }
 
-    for k in remote.difference(&local) {
-        deleted.insert(k.clone());
+    #[test]
+    fn cache_directive_changed() -> Result<()> {
+        let base = tempfile::tempdir()?;
+        fs::write(base.path().join("index.html"), "<p>Home</p>")?;
+        fs::write(base.path().join("main.css"), "p {}")?;
+        let builder = FileBuilder::new(base.path().to_path_buf(), None);
+
+        let mut local: CacheManifest = Default::default();
+        local.insert("index.html".to_string(), "no-store".to_string());
+        local.insert("main.css".to_string(), "max-age=60".to_string());
+        let mut remote: CacheManifest = Default::default();
+        remote.insert("main.css".to_string(), "max-age=60".to_string());
+
+        let mut diff: Diff = Default::default();
+        diff.unchanged.insert("index.html".to_string());
+        diff.unchanged.insert("main.css".to_string());
+        changed_directives(&mut diff, &builder, &local, &remote);
+
+        assert!(diff.changed.contains("index.html"));
+        assert!(diff.unchanged.contains("main.css"));
+        assert_eq!(1, diff.unchanged.len());
+        Ok(())
     }
-
-    Ok(DiffReport {
-        same,
-        upload,
-        changed,
-        deleted,
-    })
 }
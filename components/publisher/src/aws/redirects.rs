@@ -3,11 +3,9 @@ use std::path::{Path, PathBuf};
 
 use config::redirect::{RedirectManifest, REDIRECTS_FILE};
 
-use futures_util::TryStreamExt;
-use rusoto_s3::{GetObjectRequest, S3Client, S3};
-use tokio_util::codec;
+use rusoto_s3::S3Client;
 
-use crate::Result;
+use crate::{s3_util::get_object_bytes, Result};
 
 pub(crate) async fn diff_redirects<P: AsRef<Path>>(
     client: &S3Client,
@@ -47,30 +45,9 @@ async fn load_bucket_redirects(
     };
 
     // Load remote `redirects.json` file.
-    let req = GetObjectRequest {
-        bucket: bucket.to_string(),
-        key,
-        ..Default::default()
-    };
-
-    if let Ok(mut res) = client.get_object(req).await {
-        if let Some(body) = res.body.take() {
-            let content = codec::FramedRead::new(
-                body.into_async_read(),
-                codec::BytesCodec::new(),
-            );
-
-            let mut buf: Vec<u8> = Vec::new();
-            content
-                .try_for_each(|bytes| {
-                    buf.extend(&bytes);
-                    futures::future::ok(())
-                })
-                .await?;
-
-            let manifest: RedirectManifest = serde_json::from_slice(&buf)?;
-            return Ok(manifest);
-        }
+    if let Some(buf) = get_object_bytes(client, bucket, &key).await? {
+        let manifest: RedirectManifest = serde_json::from_slice(&buf)?;
+        return Ok(manifest);
     }
 
     Ok(Default::default())
@@ -1,15 +1,65 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 
 use ignore::WalkBuilder;
+use sha2::{Digest, Sha256};
 
-use crate::{Error, Result};
+use crate::{s3_util::read_file_etag, Error, Result};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct ResultFile {
     pub key: Option<String>,
     pub e_tag: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+/// Algorithm used to compute content hashes so that local files
+/// may be compared with the hashes reported by a remote provider.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum HashAlgorithm {
+    /// Quoted MD5 digest matching the S3 e-tag for simple uploads.
+    Md5,
+    /// Hex encoded SHA-256 digest.
+    Sha256,
+}
+
+/// Compute the content hash for a file.
+pub fn content_hash<P: AsRef<Path>>(
+    path: P,
+    algorithm: HashAlgorithm,
+) -> io::Result<String> {
+    match algorithm {
+        HashAlgorithm::Md5 => read_file_etag(path),
+        HashAlgorithm::Sha256 => {
+            let mut file = File::open(path)?;
+            let mut hasher = Sha256::new();
+            io::copy(&mut file, &mut hasher)?;
+            Ok(format!("{:x}", hasher.finalize()))
+        }
+    }
+}
+
+/// Comparison of local files with remote objects.
+#[derive(Debug, Default)]
+pub struct Diff {
+    // Local files that do not exist on the remote
+    pub added: BTreeSet<String>,
+    // Files that exist on the remote with a different etag
+    pub changed: BTreeSet<String>,
+    // Files that exist on the remote with the same etag
+    pub unchanged: BTreeSet<String>,
+    // Objects on the remote that no longer exist locally
+    pub deleted: BTreeSet<String>,
+}
+
+impl Diff {
+    /// Number of objects that exist on the remote.
+    pub fn remote_len(&self) -> usize {
+        self.changed.len() + self.unchanged.len() + self.deleted.len()
+    }
 }
 
 #[derive(Debug)]
@@ -20,6 +70,10 @@ pub struct FileBuilder {
     pub prefix: Option<String>,
     // List of file keys
     pub keys: HashSet<String>,
+    // Algorithm for content hashes computed when walking
+    pub hash: Option<HashAlgorithm>,
+    // Content hashes by file key
+    pub hashes: HashMap<String, String>,
 }
 
 impl FileBuilder {
@@ -28,9 +82,17 @@ impl FileBuilder {
             base,
             prefix,
             keys: HashSet::new(),
+            hash: None,
+            hashes: HashMap::new(),
         }
     }
 
+    /// Compute content hashes using an algorithm when walking files.
+    pub fn with_hash(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash = Some(algorithm);
+        self
+    }
+
     fn add<P: AsRef<Path>>(&mut self, raw: P) -> Result<()> {
         let mut key = raw.as_ref().strip_prefix(&self.base)?.to_path_buf();
         key = if let Some(ref prefix) = self.prefix {
@@ -43,7 +105,12 @@ impl FileBuilder {
 
         let key_str = key.to_string_lossy().into_owned();
         // Assuming we will compare with s3 using a slash as the folder delimiter
-        self.keys.insert(utils::url::to_href_separator(key_str));
+        let key = utils::url::to_href_separator(key_str);
+        if let Some(algorithm) = self.hash {
+            self.hashes
+                .insert(key.clone(), content_hash(raw.as_ref(), algorithm)?);
+        }
+        self.keys.insert(key);
         Ok(())
     }
 
@@ -61,11 +128,67 @@ impl FileBuilder {
         pth
     }
 
+    /// Compare the local files with the remote keys and a map of
+    /// remote keys to etags.
+    ///
+    /// Remote keys must use a slash as the folder delimiter like
+    /// the keys for local files; folder objects are ignored. Remote
+    /// objects without an etag are always changed.
+    pub fn diff(
+        &self,
+        remote: &HashSet<String>,
+        etags: &HashMap<String, String>,
+    ) -> Result<Diff> {
+        let mut diff: Diff = Default::default();
+        for key in self.keys.iter() {
+            if !remote.contains(key) {
+                diff.added.insert(key.clone());
+                continue;
+            }
+            match etags.get(key) {
+                Some(etag) => {
+                    let local_etag = match self.hash {
+                        Some(HashAlgorithm::Md5) => {
+                            self.hashes.get(key).cloned()
+                        }
+                        _ => None,
+                    };
+                    let local_etag = match local_etag {
+                        Some(local_etag) => local_etag,
+                        None => read_file_etag(self.from_key(key))?,
+                    };
+                    if etag == &local_etag {
+                        diff.unchanged.insert(key.clone());
+                    } else {
+                        diff.changed.insert(key.clone());
+                    }
+                }
+                None => {
+                    diff.changed.insert(key.clone());
+                }
+            }
+        }
+
+        for key in remote.iter() {
+            if !key.ends_with('/') && !self.keys.contains(key) {
+                diff.deleted.insert(key.clone());
+            }
+        }
+        Ok(diff)
+    }
+
     pub fn walk(&mut self) -> Result<()> {
         for result in WalkBuilder::new(&self.base).follow_links(true).build() {
             match result {
                 Ok(entry) => {
                     let path = entry.path();
+                    // The output manifest describes the build and
+                    // is not a site file
+                    if path.strip_prefix(&self.base).ok()
+                        == Some(Path::new(config::OUTPUT_MANIFEST))
+                    {
+                        continue;
+                    }
                     if path.is_file() {
                         self.add(&path)?;
                     }
@@ -76,3 +199,105 @@ impl FileBuilder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn identical_content_hash() -> Result<()> {
+        let base = tempfile::tempdir()?;
+        fs::create_dir(base.path().join("docs"))?;
+        fs::write(base.path().join("index.html"), "<p>Same</p>")?;
+        fs::write(base.path().join("docs").join("other.html"), "<p>Same</p>")?;
+        fs::write(base.path().join("changed.html"), "<p>Changed</p>")?;
+        fs::write(base.path().join(config::OUTPUT_MANIFEST), "[]")?;
+
+        let mut builder = FileBuilder::new(base.path().to_path_buf(), None)
+            .with_hash(HashAlgorithm::Sha256);
+        builder.walk()?;
+        assert_eq!(3, builder.keys.len());
+        assert!(!builder.keys.contains(config::OUTPUT_MANIFEST));
+
+        let first = builder.hashes.get("index.html").unwrap();
+        assert_eq!(64, first.len());
+        assert_eq!(first, builder.hashes.get("docs/other.html").unwrap());
+        assert_ne!(first, builder.hashes.get("changed.html").unwrap());
+
+        let md5 =
+            content_hash(base.path().join("index.html"), HashAlgorithm::Md5)?;
+        assert_eq!(
+            md5,
+            content_hash(
+                base.path().join("docs").join("other.html"),
+                HashAlgorithm::Md5
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diff_remote() -> Result<()> {
+        let base = tempfile::tempdir()?;
+        fs::create_dir(base.path().join("docs"))?;
+        fs::write(base.path().join("index.html"), "<p>Home</p>")?;
+        fs::write(base.path().join("docs").join("about.html"), "<p>About</p>")?;
+        fs::write(base.path().join("new.html"), "<p>New</p>")?;
+        fs::write(base.path().join("plain.html"), "<p>Plain</p>")?;
+
+        let mut builder =
+            FileBuilder::new(base.path().to_path_buf(), Some("site".into()));
+        builder.walk()?;
+
+        let etag = read_file_etag(base.path().join("index.html"))?;
+        let etags: HashMap<String, String> = vec![
+            ("site/index.html", etag.as_str()),
+            ("site/docs/about.html", "\"stale\""),
+            ("site/old.html", "\"old\""),
+            ("site/docs/", "\"folder\""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        // Objects without an etag are still listed
+        let remote: HashSet<String> = vec![
+            "site/index.html",
+            "site/docs/about.html",
+            "site/old.html",
+            "site/plain.html",
+            "site/untagged.html",
+        ]
+        .into_iter()
+        .map(|k| k.to_string())
+        .collect();
+
+        let diff = builder.diff(&remote, &etags)?;
+        let keys = |set: &BTreeSet<String>| -> Vec<String> {
+            set.iter().cloned().collect()
+        };
+        assert_eq!(vec!["site/new.html"], keys(&diff.added));
+        assert_eq!(
+            vec!["site/docs/about.html", "site/plain.html"],
+            keys(&diff.changed)
+        );
+        assert_eq!(vec!["site/index.html"], keys(&diff.unchanged));
+        assert_eq!(
+            vec!["site/old.html", "site/untagged.html"],
+            keys(&diff.deleted)
+        );
+        assert_eq!(5, diff.remote_len());
+
+        // Hashes computed when walking give the same result
+        let mut builder =
+            FileBuilder::new(base.path().to_path_buf(), Some("site".into()))
+                .with_hash(HashAlgorithm::Md5);
+        builder.walk()?;
+        assert_eq!(
+            keys(&diff.unchanged),
+            keys(&builder.diff(&remote, &etags)?.unchanged)
+        );
+        Ok(())
+    }
+}
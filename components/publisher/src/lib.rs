@@ -2,6 +2,9 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    #[error("Publish would delete {0} of {1} remote objects which exceeds the delete limit, check the build or allow mass deletion")]
+    MassDelete(usize, usize),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
@@ -32,6 +35,9 @@ pub enum Error {
 
     #[error(transparent)]
     Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Config(#[from] config::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -45,7 +51,8 @@ mod aws;
 mod s3_util;
 
 pub use aws::provider::{
-    publish as aws_publish, PublishRequest as AwsPublishRequest,
+    publish as aws_publish, DeleteLimit, PublishRequest as AwsPublishRequest,
 };
+pub use aws::report::{content_hash, FileBuilder, HashAlgorithm};
 
 pub use s3_util::*;
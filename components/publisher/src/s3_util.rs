@@ -106,6 +106,37 @@ pub async fn list_bucket_all(
     Ok(())
 }
 
+/// Read the content of an object, `None` is returned when
+/// the object could not be fetched.
+pub async fn get_object_bytes(
+    client: &S3Client,
+    bucket: &str,
+    key: &str,
+) -> Result<Option<Vec<u8>>> {
+    let req = GetObjectRequest {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        ..Default::default()
+    };
+
+    if let Ok(mut res) = client.get_object(req).await {
+        if let Some(body) = res.body.take() {
+            let content =
+                FramedRead::new(body.into_async_read(), BytesCodec::new());
+
+            let mut buf: Vec<u8> = Vec::new();
+            content
+                .try_for_each(|bytes| {
+                    buf.extend(&bytes);
+                    futures::future::ok(())
+                })
+                .await?;
+            return Ok(Some(buf));
+        }
+    }
+    Ok(None)
+}
+
 pub async fn put_object_with_progress<P: AsRef<Path>>(
     client: &S3Client,
     mut req: PutObjectRequest,
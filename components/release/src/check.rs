@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use log::warn;
+use semver::Version;
+
+use crate::{
+    releases::{self, MANIFEST_JSON},
+    version, Result,
+};
+
+/// Determine if a newer release is available without downloading
+/// or installing it.
+///
+/// The releases repository is fetched first unless network access
+/// is disabled using the `UWE_OFFLINE` environment variable; when
+/// the fetch fails the cached releases manifest is used instead.
+///
+/// Returns the latest version when it is newer than the installed
+/// version; `None` is returned when there is no installation or
+/// no cached releases manifest.
+pub async fn check() -> Result<Option<Version>> {
+    if !plugin::is_offline() {
+        if let Err(e) = scm::system_repo::fetch_releases().await {
+            warn!("Unable to fetch releases ({})", e);
+        }
+    }
+    let manifest = dirs::releases_dir()?.join(MANIFEST_JSON);
+    newer(&manifest, &version::file()?)
+}
+
+fn newer(manifest: &Path, version_file: &Path) -> Result<Option<Version>> {
+    if !manifest.exists() || !version_file.exists() {
+        return Ok(None);
+    }
+
+    let releases = releases::load(manifest)?;
+    if releases.is_empty() {
+        return Ok(None);
+    }
+
+    let installed = version::read(version_file)?;
+    let (latest, _) = releases.latest();
+    if latest > &installed {
+        Ok(Some(latest.clone()))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn newer_version() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let manifest = dir.path().join(MANIFEST_JSON);
+        let version_file = dir.path().join("version");
+        fs::write(
+            &manifest,
+            r#"{"latest": {"version": "1.2.0"}, "versions": [{"version": "1.0.0"}]}"#,
+        )?;
+
+        assert_eq!(None, newer(&manifest, &version_file)?);
+
+        fs::write(&version_file, "1.0.0")?;
+        assert_eq!(
+            Some(Version::new(1, 2, 0)),
+            newer(&manifest, &version_file)?
+        );
+
+        fs::write(&version_file, "1.2.0")?;
+        assert_eq!(None, newer(&manifest, &version_file)?);
+        Ok(())
+    }
+}
@@ -16,16 +16,50 @@ use http::StatusCode;
 
 use crate::{
     releases::{self, ReleaseInfo},
-    Error, Result,
+    signature, Error, Result,
 };
 
 const RELEASE_URL: &str = "https://releases.uwe.app";
 //const RELEASE_URL: &str = "http://releases.uwe.app.s3-website-ap-southeast-1.amazonaws.com";
 
+/// Environment variable to override the release base URL.
+const RELEASE_URL_ENV: &str = "UWE_RELEASE_URL";
+
+/// Get the base URL for release artifacts.
+///
+/// The `UWE_RELEASE_URL` environment variable takes precedence
+/// over the `release.base-url` preference, when neither is set
+/// the default release URL is used.
+pub(crate) fn base_url() -> Result<String> {
+    let custom = match std::env::var(RELEASE_URL_ENV).ok() {
+        Some(url) => Some(url),
+        None => preference::load()?
+            .release
+            .as_ref()
+            .and_then(|r| r.base_url.clone()),
+    };
+    base_url_from(custom)
+}
+
+fn base_url_from(custom: Option<String>) -> Result<String> {
+    if let Some(custom) = custom {
+        let custom = custom.trim().trim_end_matches('/');
+        let parsed: Url = custom
+            .parse()
+            .map_err(|_| Error::InvalidReleaseUrl(custom.to_string()))?;
+        if parsed.cannot_be_a_base() || parsed.host_str().is_none() {
+            return Err(Error::InvalidReleaseUrl(custom.to_string()));
+        }
+        return Ok(custom.to_string());
+    }
+
+    Ok(RELEASE_URL.to_string())
+}
+
 pub(crate) fn url(version: &Version, name: &str) -> Result<Url> {
     let full_url = format!(
         "{}/{}/{}/{}",
-        RELEASE_URL,
+        base_url()?,
         version.to_string(),
         releases::current_platform(),
         name
@@ -51,6 +85,8 @@ pub(crate) async fn all(
 
     let mut output: HashMap<String, PathBuf> = HashMap::new();
 
+    let verify_signatures = signature::enabled()?;
+
     //for name in releases::INSTALL_EXE_NAMES.iter() {
     for name in names.iter() {
         let expected = platform_info.get(*name).unwrap();
@@ -76,6 +112,17 @@ pub(crate) async fn all(
             ));
         }
 
+        if verify_signatures {
+            let signature_url =
+                self::url(version, &signature::file_name(name))?;
+            debug!("Signature {}", signature_url.to_string());
+            let signature = text(&signature_url).await?;
+            let mut temp_source = temp_target.reopen()?;
+            let mut data = Vec::new();
+            io::copy(&mut temp_source, &mut data)?;
+            signature::verify(name, &data, &signature)?;
+        }
+
         // Remove any existing target
         if download_file.exists() {
             fs::remove_file(&download_file)?;
@@ -132,3 +179,40 @@ async fn download(
 
     Ok(hasher.finalize().as_slice().to_owned())
 }
+
+/// Download a small text file into memory.
+async fn text(url: &Url) -> Result<String> {
+    let response = reqwest::get(url.clone()).await?;
+    if response.status() != StatusCode::OK {
+        return Err(Error::DownloadFail(
+            response.status().to_string(),
+            url.to_string(),
+        ));
+    }
+    Ok(response.text().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_base_url() -> Result<()> {
+        assert_eq!(RELEASE_URL, base_url_from(None)?);
+        assert_eq!(
+            "https://mirror.example.com/uwe",
+            base_url_from(Some(
+                " https://mirror.example.com/uwe/ ".to_string()
+            ))?
+        );
+        assert!(matches!(
+            base_url_from(Some("mirror.example.com".to_string())),
+            Err(Error::InvalidReleaseUrl(_))
+        ));
+        assert!(matches!(
+            base_url_from(Some("file:///releases".to_string())),
+            Err(Error::InvalidReleaseUrl(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 use log::{debug, info, warn};
 use semver::{Version, VersionReq};
@@ -147,26 +148,115 @@ pub(crate) fn permissions(binaries: &HashMap<String, PathBuf>) -> Result<()> {
     Ok(())
 }
 
-pub(crate) fn rename(binaries: &HashMap<String, PathBuf>) -> Result<()> {
-    let releases_dir = dirs::releases_dir()?;
-    let bin_dir = dirs::bin_dir()?;
+/// Verify and move downloaded binaries into the installation
+/// binary directory.
+pub(crate) fn rename(
+    binaries: &HashMap<String, PathBuf>,
+    version: &Version,
+) -> Result<()> {
+    swap(binaries, &dirs::bin_dir()?, version)
+}
+
+/// Verify a downloaded binary before it replaces an installed binary.
+///
+/// The binary must be an executable file and unless it is a shim
+/// the output of `--version` must include the expected version.
+fn verify_binary(
+    name: &str,
+    path: &Path,
+    version: &Version,
+    probe: bool,
+) -> Result<()> {
+    if !path.is_file() {
+        return Err(Error::InvalidBinary(
+            path.to_path_buf(),
+            "not a file".to_string(),
+        ));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if path.metadata()?.permissions().mode() & 0o111 == 0 {
+            return Err(Error::InvalidBinary(
+                path.to_path_buf(),
+                "not executable".to_string(),
+            ));
+        }
+    }
+
+    if probe {
+        let output =
+            Command::new(path).arg("--version").output().map_err(|e| {
+                Error::InvalidBinary(path.to_path_buf(), e.to_string())
+            })?;
+        let reported =
+            String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let expected = version.to_string();
+        if !reported.split_whitespace().any(|word| word == expected) {
+            return Err(Error::BinaryVersionMismatch(
+                name.to_string(),
+                reported,
+                expected,
+            ));
+        }
+    }
+    Ok(())
+}
 
+/// Replace the binaries in a directory.
+///
+/// All binaries are verified before any installed binary is
+/// changed; existing binaries are kept as backups until every
+/// binary has been moved into place so that a failure part way
+/// through is rolled back.
+fn swap(
+    binaries: &HashMap<String, PathBuf>,
+    bin_dir: &Path,
+    version: &Version,
+) -> Result<()> {
     let shims = releases::shim_map();
+
+    let mut targets = Vec::new();
     for (name, src) in binaries {
-        let bin_name = if let Some(shim_dest) = shims.get(name) {
-            shim_dest.to_string()
+        let (bin_name, is_shim) = if let Some(shim_dest) = shims.get(name) {
+            (shim_dest.to_string(), true)
         } else {
-            name.to_string()
+            (name.to_string(), false)
         };
+        verify_binary(name, src, version, !is_shim)?;
+        targets.push((src, bin_dir.join(&bin_name)));
+    }
 
-        let dest = bin_dir.join(&bin_name);
+    let mut backups: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let result: Result<()> = targets.iter().try_for_each(|(src, dest)| {
         if dest.exists() {
-            fs::remove_file(&dest)?;
+            let mut backup = dest.clone().into_os_string();
+            backup.push(".backup");
+            let backup = PathBuf::from(backup);
+            fs::rename(dest, &backup)?;
+            backups.push((backup, dest.clone()));
+        }
+        debug!("Move {} -> {}", src.display(), dest.display());
+        fs::rename(src, dest)?;
+        moved.push((src.to_path_buf(), dest.clone()));
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        warn!("Update failed, restoring previous binaries");
+        for (src, dest) in moved {
+            let _ = fs::rename(dest, src);
+        }
+        for (backup, dest) in backups {
+            let _ = fs::rename(backup, dest);
         }
+        return Err(e);
+    }
 
-        let short_src = src.strip_prefix(&releases_dir)?;
-        debug!("Move {} -> {}", short_src.display(), dest.display());
-        std::fs::rename(src, dest)?;
+    for (backup, _) in backups {
+        fs::remove_file(backup)?;
     }
     Ok(())
 }
@@ -245,7 +335,7 @@ pub async fn update(name: &str, range: Option<VersionReq>) -> Result<()> {
                 (s, path)
             })
             .collect::<HashMap<_, _>>();
-        rename(&binaries)?;
+        rename(&binaries, &version)?;
 
         welcome()?;
     }
@@ -292,9 +382,53 @@ pub async fn update_self(current: &str) -> Result<()> {
     let binaries =
         download::all(version, info, &releases::VERSION_EXE_NAMES).await?;
     permissions(&binaries)?;
-    rename(&binaries)?;
+    rename(&binaries, version)?;
 
     info!("Updated to {}@{} ✓", name, version.to_string());
 
     Ok(())
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    fn script(path: &Path, version: &str) -> Result<()> {
+        fs::write(path, format!("#!/bin/sh\necho \"uvm {}\"\n", version))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    }
+
+    #[test]
+    fn version_mismatch_aborts_swap() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let bin_dir = dir.path().join("bin");
+        let download_dir = dir.path().join("download");
+        fs::create_dir_all(&bin_dir)?;
+        fs::create_dir_all(&download_dir)?;
+
+        let installed = bin_dir.join("uvm");
+        script(&installed, "1.0.0")?;
+
+        let downloaded = download_dir.join("uvm");
+        script(&downloaded, "1.0.0")?;
+
+        let mut binaries = HashMap::new();
+        binaries.insert("uvm".to_string(), downloaded.clone());
+
+        let version = Version::new(1, 1, 0);
+        let result = swap(&binaries, &bin_dir, &version);
+        assert!(matches!(result, Err(Error::BinaryVersionMismatch(..))));
+        assert!(downloaded.exists());
+        assert!(fs::read_to_string(&installed)?.contains("1.0.0"));
+
+        script(&downloaded, "1.1.0")?;
+        swap(&binaries, &bin_dir, &version)?;
+        assert!(!downloaded.exists());
+        assert!(fs::read_to_string(&installed)?.contains("1.1.0"));
+        assert!(!bin_dir.join("uvm.backup").exists());
+        Ok(())
+    }
+}
@@ -31,12 +31,24 @@ pub enum Error {
     #[error("Digests do not match for {0} ({1} != {2})")]
     DigestMismatch(String, String, String),
 
+    #[error("Release URL {0} is not valid")]
+    InvalidReleaseUrl(String),
+
+    #[error("Signature verification failed for {0} ({1})")]
+    SignatureInvalid(String, String),
+
     #[error("No releases found, check an `update` semver range matches released versions")]
     NoReleasesFound,
 
     #[error("Range filters cannot be used on the first installation")]
     RangeFilterNotAllowedOnFirstRun,
 
+    #[error("Binary {0} is not valid ({1})")]
+    InvalidBinary(PathBuf, String),
+
+    #[error("Binary {0} reports version {1} but expected {2}")]
+    BinaryVersionMismatch(String, String, String),
+
     #[error("Unable to parse version in {0} ({1})")]
     VersionFileRead(PathBuf, String),
 
@@ -79,12 +91,16 @@ pub enum Error {
     #[error(transparent)]
     Plugin(#[from] plugin::Error),
 
+    #[error(transparent)]
+    Preference(#[from] preference::Error),
+
     #[error(transparent)]
     Utils(#[from] utils::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+mod check;
 mod checksum;
 mod download;
 mod env;
@@ -93,14 +109,16 @@ mod list;
 mod publish;
 mod releases;
 mod remove;
+mod signature;
 mod uninstall;
 mod verify;
 mod version;
 
+pub use check::check;
 pub use install::{install, select, update, update_self};
-pub use list::list;
+pub use list::{installed, list, InstalledVersion};
 pub use publish::publish;
 pub use releases::mount;
-pub use remove::{prune, remove};
+pub use remove::{prune, prune_keep, remove};
 pub use uninstall::uninstall;
 pub use version::{default_version, find_local_version};
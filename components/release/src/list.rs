@@ -1,9 +1,68 @@
+use std::fs;
+use std::path::Path;
+
+use human_bytes::human_bytes;
 use log::{info, warn};
+use semver::Version;
 
 use crate::{releases, version, Error, Result};
 
+/// Version installed in the releases directory.
+#[derive(Debug, Clone)]
+pub struct InstalledVersion {
+    /// The installed version.
+    pub version: Version,
+    /// Size on disk in bytes.
+    pub size: u64,
+    /// Whether this is the current version.
+    pub current: bool,
+}
+
+/// Compute the size of all the files in a directory.
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += meta.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Find the versions installed in a releases directory sorted
+/// newest first.
+pub fn installed<P: AsRef<Path>>(
+    dir: P,
+    current: &Version,
+) -> Result<Vec<InstalledVersion>> {
+    let mut versions = Vec::new();
+    for entry in fs::read_dir(dir.as_ref())? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let version = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<Version>().ok());
+        if let Some(version) = version {
+            versions.push(InstalledVersion {
+                current: &version == current,
+                size: dir_size(&path)?,
+                version,
+            });
+        }
+    }
+    versions.sort_by(|a, b| b.version.cmp(&a.version));
+    Ok(versions)
+}
+
 /// List versions.
-pub async fn list() -> Result<()> {
+pub async fn list() -> Result<Vec<InstalledVersion>> {
     scm::system_repo::fetch_releases().await?;
 
     // Load the releases manifest
@@ -26,6 +85,7 @@ pub async fn list() -> Result<()> {
     };
 
     let total = releases.versions.iter().count();
+    let installed = installed(dirs::releases_dir()?, &current)?;
 
     info!("-------------------------------");
     info!("| ◯ (installed) | ✓ (current) |");
@@ -33,19 +93,25 @@ pub async fn list() -> Result<()> {
     info!("");
 
     for (version, _) in releases.versions.iter() {
-        let version_dir = releases::dir(version.semver())?;
-        let is_installed = version_dir.exists() && version_dir.is_dir();
+        let install = installed.iter().find(|i| &i.version == version.semver());
+        let is_installed = install.is_some();
         let mark = if is_installed { "◯" } else { "-" };
+        let size = if let Some(install) = install {
+            format!(" ({})", human_bytes(install.size as f64))
+        } else {
+            String::new()
+        };
         if &current == version.semver() {
             let message = if let Some(ref file) = local_version_file {
                 format!(
-                    "{} {} ✓ (set by {})",
+                    "{} {}{} ✓ (set by {})",
                     mark,
                     version.to_string(),
+                    size,
                     file.display()
                 )
             } else {
-                format!("{} {} ✓", mark, version.to_string())
+                format!("{} {}{} ✓", mark, version, size)
             };
 
             if is_installed {
@@ -54,7 +120,7 @@ pub async fn list() -> Result<()> {
                 warn!("{}", message);
             }
         } else {
-            info!("{} {}", mark, version.to_string());
+            info!("{} {}{}", mark, version.to_string(), size);
         }
     }
 
@@ -69,5 +135,36 @@ pub async fn list() -> Result<()> {
     info!("");
     info!("{} version(s){}", total, mark);
 
-    Ok(())
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn installed_versions() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let old = dir.path().join("0.9.0");
+        let new = dir.path().join("1.2.0");
+        fs::create_dir_all(old.join("bin"))?;
+        fs::create_dir_all(&new)?;
+        fs::write(old.join("bin").join("uwe"), vec![0u8; 100])?;
+        fs::write(old.join("plugins.json"), vec![0u8; 20])?;
+        fs::write(new.join("uwe"), vec![0u8; 50])?;
+        fs::write(dir.path().join("manifest.json"), "{}")?;
+
+        let current = Version::new(0, 9, 0);
+        let versions = installed(dir.path(), &current)?;
+        assert_eq!(2, versions.len());
+
+        assert_eq!(Version::new(1, 2, 0), versions[0].version);
+        assert_eq!(50, versions[0].size);
+        assert!(!versions[0].current);
+
+        assert_eq!(current, versions[1].version);
+        assert_eq!(120, versions[1].size);
+        assert!(versions[1].current);
+        Ok(())
+    }
 }
@@ -140,7 +140,7 @@ pub async fn publish(
         "Update release manifest.",
     )?;
     info!("Push {}", releases_repo.display());
-    scm::push_remote_name(&repo, scm::ORIGIN, None, None)?;
+    scm::push_remote_name(&repo, scm::ORIGIN, None, None, false)?;
 
     let website_repo = PathBuf::from("../sites/website");
     update_website(&website_repo)?;
@@ -421,7 +421,7 @@ fn update_releases_website(releases_file: &PathBuf) -> Result<()> {
     );
     scm::commit_file(&repo, manifest_file, "Update release manifest.")?;
     info!("Push {}", releases_website_repo.display());
-    scm::push_remote_name(&repo, scm::ORIGIN, None, None)?;
+    scm::push_remote_name(&repo, scm::ORIGIN, None, None, false)?;
 
     // Compile and publish the website
     // FIXME: do not remove the lock file!
@@ -1,9 +1,10 @@
 use std::fs;
+use std::path::Path;
 
 use log::info;
 use semver::Version;
 
-use crate::{releases, version, Error, Result};
+use crate::{list::installed, releases, version, Error, Result};
 
 /// Remove an installed version.
 pub async fn remove(version: String) -> Result<()> {
@@ -39,6 +40,41 @@ pub async fn prune() -> Result<()> {
     Ok(())
 }
 
+/// Remove installed versions keeping the `keep` most recent
+/// versions and the current version.
+///
+/// Returns the versions that were removed.
+pub async fn prune_keep(keep: usize) -> Result<Vec<Version>> {
+    let version_file = version::file()?;
+    if !version_file.exists() {
+        return Err(Error::NotInstalled);
+    }
+    let current = version::default_version()?;
+    let removed = prune_dir(dirs::releases_dir()?, &current, keep)?;
+    for version in removed.iter() {
+        info!("Deleted {}", version);
+    }
+    Ok(removed)
+}
+
+/// Remove versions from a releases directory except for the
+/// `keep` most recent versions and the current version.
+fn prune_dir<P: AsRef<Path>>(
+    dir: P,
+    current: &Version,
+    keep: usize,
+) -> Result<Vec<Version>> {
+    let mut removed = Vec::new();
+    for install in installed(dir.as_ref(), current)?.into_iter().skip(keep) {
+        if install.current {
+            continue;
+        }
+        fs::remove_dir_all(dir.as_ref().join(install.version.to_string()))?;
+        removed.push(install.version);
+    }
+    Ok(removed)
+}
+
 /// Delete a specific version.
 async fn delete(version: &Version) -> Result<()> {
     let version_file = version::file()?;
@@ -62,3 +98,35 @@ async fn delete(version: &Version) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_keep_recent() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for version in &["0.1.0", "0.2.0", "0.3.0", "1.0.0", "1.1.0"] {
+            fs::create_dir_all(dir.path().join(version))?;
+        }
+
+        let current = Version::new(1, 0, 0);
+        let removed = prune_dir(dir.path(), &current, 2)?;
+        assert_eq!(
+            vec![
+                Version::new(0, 3, 0),
+                Version::new(0, 2, 0),
+                Version::new(0, 1, 0)
+            ],
+            removed
+        );
+        assert!(dir.path().join("1.1.0").exists());
+        assert!(dir.path().join("1.0.0").exists());
+        assert!(!dir.path().join("0.1.0").exists());
+
+        // Current version is kept in addition to recent versions
+        let removed = prune_dir(dir.path(), &Version::new(1, 0, 0), 1)?;
+        assert!(removed.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use minisign_verify::{PublicKey, Signature};
+
+use crate::{Error, Result};
+
+/// Extension for detached signature files.
+pub(crate) const SIGNATURE_EXT: &str = "minisig";
+
+/// Minisign public key file for the key that signs release artifacts.
+const PUBLIC_KEY: &str = include_str!("../release.pub");
+
+/// Public key that replaces the bundled key.
+///
+/// Supplied at build time in the `UWE_RELEASE_PUBLIC_KEY` environment
+/// variable as the base64 line of a minisign public key file, for
+/// builds that publish releases signed with a different key.
+const PUBLIC_KEY_OVERRIDE: Option<&str> = option_env!("UWE_RELEASE_PUBLIC_KEY");
+
+/// Determine if release artifact signatures should be verified.
+///
+/// Signatures are verified unless the preferences disable it.
+pub(crate) fn enabled() -> Result<bool> {
+    let prefs = preference::load()?;
+    Ok(prefs
+        .release
+        .as_ref()
+        .map_or(true, |r| r.verify_signatures()))
+}
+
+/// Get the name of the signature file for an artifact.
+pub(crate) fn file_name(name: &str) -> String {
+    format!("{}.{}", name, SIGNATURE_EXT)
+}
+
+/// Get the public key used to verify release artifacts.
+fn public_key() -> std::result::Result<PublicKey, minisign_verify::Error> {
+    match PUBLIC_KEY_OVERRIDE {
+        Some(key) => PublicKey::from_base64(key),
+        None => PublicKey::decode(PUBLIC_KEY),
+    }
+}
+
+fn invalid(name: &str, e: minisign_verify::Error) -> Error {
+    Error::SignatureInvalid(name.to_string(), e.to_string())
+}
+
+/// Verify the detached signature for an artifact against the
+/// release public key.
+pub(crate) fn verify(name: &str, data: &[u8], signature: &str) -> Result<()> {
+    let public_key = public_key().map_err(|e| invalid(name, e))?;
+    verify_key(&public_key, name, data, signature)
+}
+
+/// Verify the detached signature for an artifact against a public key.
+fn verify_key(
+    public_key: &PublicKey,
+    name: &str,
+    data: &[u8],
+    signature: &str,
+) -> Result<()> {
+    let signature =
+        Signature::decode(signature).map_err(|e| invalid(name, e))?;
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|e| invalid(name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Key pair generated for these tests only
+    const TEST_KEY: &str =
+        "RWQLH4POiX8Ny6+x4D/EgbsAn5AgIN3e2BhzA6/RFijjsO+fXXViiuyU";
+    const OTHER_KEY: &str =
+        "RWSSQCYidrxHlq99Upy+JkQLFHVlSLH5gJIXlbM2vjVfp5dhKaM7mmKe";
+
+    const DATA: &[u8] = b"uwe release artifact\n";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQLH4POiX8Ny4U5Frp1C7BwJcMJ+WKrrEmQ5lAmUINeUYe5G97v+b9pFk/siJtKsb9HQHQUoELg3e8jQoWtQHjvtZNKggdL8wY=
+trusted comment: timestamp:1700000000\tfile:uwe
+flhaymuZr4tXaj5RXjVXZvQsSvWBFWRbVAVf2xUemdlXLVpkTvd08NQqq1cTtWYmnbbSdglZpk9irhML3qVmDQ==
+";
+
+    fn is_invalid(result: Result<()>) -> bool {
+        matches!(result, Err(Error::SignatureInvalid(..)))
+    }
+
+    #[test]
+    fn bundled_public_key() {
+        assert!(PublicKey::decode(PUBLIC_KEY).is_ok());
+    }
+
+    #[test]
+    fn verify_signature() -> Result<()> {
+        let test_key = PublicKey::from_base64(TEST_KEY).unwrap();
+        let other_key = PublicKey::from_base64(OTHER_KEY).unwrap();
+        verify_key(&test_key, "uwe", DATA, SIGNATURE)?;
+
+        // Tampered artifact
+        assert!(is_invalid(verify_key(
+            &test_key,
+            "uwe",
+            b"uwe release artifact!\n",
+            SIGNATURE
+        )));
+
+        // Tampered trusted comment
+        let tampered = SIGNATURE.replace("file:uwe", "file:upm");
+        assert!(is_invalid(verify_key(&test_key, "uwe", DATA, &tampered)));
+
+        // Signed by another key
+        assert!(is_invalid(verify_key(&other_key, "uwe", DATA, SIGNATURE)));
+        Ok(())
+    }
+}
@@ -1,34 +1,20 @@
-use std::io::stderr;
 use std::path::Path;
 
 use git2::{build::RepoBuilder, FetchOptions, Repository};
 
-use pbr::ProgressBar;
-
-use crate::{callbacks, Error, Result};
+use crate::{
+    callbacks,
+    progress::{self, ProgressFn},
+    Error, Result,
+};
 
 pub(crate) fn clone<S: AsRef<str>, P: AsRef<Path>>(
     src: S,
     target: P,
+    progress: Option<ProgressFn<'_>>,
 ) -> Result<Repository> {
     let mut callbacks = callbacks::ssh_agent();
-
-    let mut pb = ProgressBar::on(stderr(), 0);
-    pb.show_speed = false;
-    callbacks.transfer_progress(move |stats| {
-        if stats.received_objects() == stats.total_objects() {
-            pb.message(" Resolve deltas ");
-            pb.total = stats.total_deltas() as u64;
-            pb.set(stats.indexed_deltas() as u64);
-        } else if stats.total_objects() > 0 {
-            pb.message(" Fetch ");
-            pb.total = stats.total_objects() as u64;
-            pb.set(stats.received_objects() as u64);
-        }
-        true
-    });
-
-    //progress::add_progress_callbacks(&mut callbacks);
+    progress::add_progress_callbacks(&mut callbacks, progress);
 
     let mut fo = FetchOptions::new();
     fo.remote_callbacks(callbacks);
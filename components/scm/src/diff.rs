@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+
+use git2::{Delta, DiffOptions, Repository, Tree};
+
+use crate::{Error, Result};
+
+/// Status of a file between two revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    Typechange,
+    Unmodified,
+}
+
+impl From<Delta> for FileStatus {
+    fn from(delta: Delta) -> Self {
+        match delta {
+            Delta::Added | Delta::Untracked => Self::Added,
+            Delta::Deleted => Self::Deleted,
+            Delta::Renamed => Self::Renamed,
+            Delta::Copied => Self::Copied,
+            Delta::Typechange => Self::Typechange,
+            Delta::Unmodified | Delta::Ignored => Self::Unmodified,
+            _ => Self::Modified,
+        }
+    }
+}
+
+/// Lines for a hunk in a file delta.
+#[derive(Debug, Clone, Default)]
+pub struct Hunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<String>,
+}
+
+/// Change to a single file between two revisions.
+#[derive(Debug, Clone)]
+pub struct FileDelta {
+    pub status: FileStatus,
+    pub old_path: Option<PathBuf>,
+    pub new_path: Option<PathBuf>,
+    pub hunks: Vec<Hunk>,
+}
+
+fn find_tree<'a>(repo: &'a Repository, spec: &str) -> Result<Tree<'a>> {
+    let object = repo
+        .revparse_single(spec)
+        .map_err(|_| Error::NoRevision(spec.to_string()))?;
+    Ok(object.peel_to_tree()?)
+}
+
+/// Compute the difference between two revisions.
+///
+/// Revisions may be any spec accepted by `revparse` such as a
+/// reference name or commit id; when `hunks` is set the changed
+/// lines for each file are also collected.
+pub(crate) fn diff(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+    hunks: bool,
+) -> Result<Vec<FileDelta>> {
+    let old_tree = find_tree(repo, from)?;
+    let new_tree = find_tree(repo, to)?;
+
+    let mut opts = DiffOptions::new();
+    let mut diff = repo.diff_tree_to_tree(
+        Some(&old_tree),
+        Some(&new_tree),
+        Some(&mut opts),
+    )?;
+    diff.find_similar(None)?;
+
+    let mut deltas: Vec<FileDelta> = diff
+        .deltas()
+        .map(|delta| FileDelta {
+            status: delta.status().into(),
+            old_path: delta.old_file().path().map(|p| p.to_path_buf()),
+            new_path: delta.new_file().path().map(|p| p.to_path_buf()),
+            hunks: Vec::new(),
+        })
+        .collect();
+
+    if hunks {
+        for (idx, delta) in deltas.iter_mut().enumerate() {
+            let patch = match git2::Patch::from_diff(&diff, idx)? {
+                Some(patch) => patch,
+                // Binary files do not have a patch
+                None => continue,
+            };
+
+            for hunk_idx in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_idx)?;
+                let mut item = Hunk {
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines: Vec::new(),
+                };
+
+                for line_idx in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                    let content = String::from_utf8_lossy(line.content());
+                    item.lines.push(format!(
+                        "{}{}",
+                        line.origin(),
+                        content.trim_end_matches('\n')
+                    ));
+                }
+
+                delta.hunks.push(item);
+            }
+        }
+    }
+
+    Ok(deltas)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use git2::{Oid, Repository};
+
+    use super::{diff, FileStatus};
+    use crate::{Error, Result};
+
+    fn commit(
+        repo: &Repository,
+        files: &[(&str, Option<&str>)],
+    ) -> Result<Oid> {
+        let root = repo.workdir().unwrap();
+        let mut index = repo.index()?;
+        for (name, content) in files {
+            if let Some(content) = content {
+                fs::write(root.join(name), content)?;
+                index.add_path(Path::new(name))?;
+            } else {
+                fs::remove_file(root.join(name))?;
+                index.remove_path(Path::new(name))?;
+            }
+        }
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let sig = git2::Signature::now("Test", "test@example.com")?;
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents = parent.iter().collect::<Vec<_>>();
+        Ok(repo.commit(Some("HEAD"), &sig, &sig, "Update", &tree, &parents)?)
+    }
+
+    #[test]
+    fn revision_diff() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let repo = Repository::init(dir.path())?;
+        let first = commit(
+            &repo,
+            &[("file.txt", Some("one\n")), ("old.txt", Some("old\n"))],
+        )?;
+        let second = commit(
+            &repo,
+            &[
+                ("file.txt", Some("two\n")),
+                ("old.txt", None),
+                ("new.txt", Some("new\n")),
+            ],
+        )?;
+
+        let mut deltas =
+            diff(&repo, &first.to_string(), &second.to_string(), true)?;
+        deltas.sort_by(|a, b| a.new_path.cmp(&b.new_path));
+        let statuses: Vec<(FileStatus, PathBuf)> = deltas
+            .iter()
+            .map(|d| (d.status, d.new_path.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            vec![
+                (FileStatus::Modified, PathBuf::from("file.txt")),
+                (FileStatus::Added, PathBuf::from("new.txt")),
+                (FileStatus::Deleted, PathBuf::from("old.txt")),
+            ],
+            statuses
+        );
+        assert_eq!(1, deltas[0].hunks.len());
+        assert_eq!(vec!["-one", "+two"], deltas[0].hunks[0].lines);
+
+        let deltas = diff(&repo, "HEAD~1", "HEAD", false)?;
+        assert_eq!(3, deltas.len());
+        assert!(deltas.iter().all(|d| d.hunks.is_empty()));
+
+        assert!(matches!(
+            diff(&repo, "missing", "HEAD", false),
+            Err(Error::NoRevision(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use git2::{
-    BranchType, Commit, IndexAddOption, Oid, PushOptions, Remote,
-    RemoteCallbacks, Repository, RepositoryInitOptions, RepositoryState,
+    BranchType, Commit, Index, IndexAddOption, Oid, PushOptions, Remote,
+    RemoteCallbacks, RepositoryInitOptions, RepositoryState,
     StatusOptions,
 };
 
@@ -13,7 +15,10 @@ use thiserror::Error;
 pub const HEAD: &str = "HEAD";
 pub const ORIGIN: &str = "origin";
 pub const MAIN: &str = "main";
-pub const REFSPEC: &str = "+refs/heads/main:refs/heads/main";
+pub const REFSPEC: &str = "refs/heads/main:refs/heads/main";
+
+/// Timeout when checking a network remote is reachable.
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -26,9 +31,21 @@ pub enum Error {
     #[error("Remote {0} does not exist in the repository {1}")]
     NoRemote(String, PathBuf),
 
+    #[error("Remote {0} is unreachable ({1})")]
+    RemoteUnreachable(String, String),
+
+    #[error("Push to {0} is not a fast-forward, pull the remote changes or force the push")]
+    NonFastForward(String),
+
+    #[error("Push failed for remotes {0}")]
+    PushFailed(String),
+
     #[error("Branch {0} does not exist in the repository {1}")]
     NoBranch(String, PathBuf),
 
+    #[error("Revision {0} could not be found")]
+    NoRevision(String),
+
     #[error(transparent)]
     Git(#[from] git2::Error),
 
@@ -41,12 +58,19 @@ pub enum Error {
 
 type Result<T> = std::result::Result<T, Error>;
 
+pub use git2::Repository;
+
 mod callbacks;
 mod clone;
-//mod progress;
+mod diff;
+pub mod progress;
 mod pull;
 pub mod system_repo;
 
+pub use diff::{FileDelta, FileStatus, Hunk};
+pub use progress::{ProgressFn, TransferProgress};
+pub use pull::ConflictPolicy;
+
 fn find_remote_head(
     repo: &Repository,
     remote: Option<&str>,
@@ -55,14 +79,103 @@ fn find_remote_head(
     let ref_spec = ref_spec.as_ref().map(|s| &s[..]).unwrap_or(HEAD);
     let remote_name = remote.as_ref().map(|s| &s[..]).unwrap_or(ORIGIN);
     let mut remote_spec = repo.find_remote(remote_name)?;
-    let _ = remote_spec.connect(git2::Direction::Fetch)?;
-    let heads = remote_spec.list()?.iter().collect::<Vec<_>>();
-    for remote_head in heads {
-        if remote_head.name() == ref_spec {
-            return Ok(Some(remote_head.oid()));
+    list_remote_head(&mut remote_spec, ref_spec)
+}
+
+fn list_remote_head(
+    remote: &mut Remote<'_>,
+    name: &str,
+) -> Result<Option<Oid>> {
+    remote.connect(git2::Direction::Fetch)?;
+    let head = remote
+        .list()?
+        .iter()
+        .find(|remote_head| remote_head.name() == name)
+        .map(|remote_head| remote_head.oid());
+    remote.disconnect()?;
+    Ok(head)
+}
+
+/// Get the host and port for a network remote URL.
+///
+/// Local paths and `file://` URLs return `None`.
+fn remote_address(url: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = match url.find("://") {
+        Some(pos) => (&url[..pos], &url[pos + 3..]),
+        // SCP style syntax, eg: `git@github.com:org/repo.git`
+        None => match url.find(':') {
+            Some(pos) if !url[..pos].contains('/') && pos > 1 => {
+                ("ssh", &url[..pos])
+            }
+            _ => return None,
+        },
+    };
+
+    let port = match scheme {
+        "http" => 80,
+        "https" => 443,
+        "ssh" => 22,
+        "git" => 9418,
+        _ => return None,
+    };
+
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    match host.rfind(':') {
+        Some(pos) if !host.ends_with(']') => {
+            let port = host[pos + 1..].parse().unwrap_or(port);
+            Some((host[..pos].to_string(), port))
         }
+        _ => Some((host.to_string(), port)),
     }
-    Ok(None)
+}
+
+/// Verify a remote is reachable by connecting in the fetch
+/// direction and listing the remote references.
+///
+/// For network remotes a connection to the host is attempted
+/// first with a short timeout so that an unreachable host fails
+/// quickly.
+pub fn check_remote(repo: &Repository, remote: &str) -> Result<()> {
+    let dir = repo.workdir().unwrap_or(repo.path()).to_path_buf();
+    let mut remote_spec = repo
+        .find_remote(remote)
+        .map_err(|_| Error::NoRemote(remote.to_string(), dir))?;
+
+    let unreachable =
+        |e: String| Error::RemoteUnreachable(remote.to_string(), e);
+
+    if let Some((host, port)) = remote_spec.url().and_then(remote_address) {
+        let addrs = (host.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|e| unreachable(e.to_string()))?;
+        let mut connected = false;
+        let mut reason = format!("could not resolve {}", host);
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, REMOTE_TIMEOUT) {
+                Ok(_) => {
+                    connected = true;
+                    break;
+                }
+                Err(e) => reason = e.to_string(),
+            }
+        }
+        if !connected {
+            return Err(unreachable(reason));
+        }
+    }
+
+    remote_spec
+        .connect_auth(
+            git2::Direction::Fetch,
+            Some(callbacks::ssh_agent()),
+            None,
+        )
+        .map_err(|e| unreachable(e.message().to_string()))?;
+    let refs = remote_spec.list()?.len();
+    debug!("Remote {} has {} reference(s)", remote, refs);
+    remote_spec.disconnect()?;
+    Ok(())
 }
 
 /// Determine if a repository is up to date with a remote.
@@ -84,10 +197,16 @@ pub fn is_current_with_remote(
     Ok((false, None))
 }
 
+/// Pull from a remote and merge into a local branch.
+///
+/// When a `progress` callback is given it is invoked with the
+/// transfer statistics whilst fetching.
 pub fn pull<P: AsRef<Path>>(
     path: P,
     remote: Option<&str>,
     branch: Option<&str>,
+    policy: ConflictPolicy,
+    progress: Option<ProgressFn<'_>>,
 ) -> Result<()> {
     let remote_name = remote.as_ref().map(|s| &s[..]).unwrap_or(ORIGIN);
     let branch_name = branch.as_ref().map(|s| &s[..]).unwrap_or(MAIN);
@@ -99,14 +218,50 @@ pub fn pull<P: AsRef<Path>>(
         path.as_ref().display()
     );
 
-    pull::pull(path, remote_name, branch_name).map_err(Error::from)
+    pull::pull(path, remote_name, branch_name, policy, progress)
+        .map_err(Error::from)
 }
 
+/// Fetch from a remote without merging.
+///
+/// Remote tracking references are updated but the working tree and
+/// `HEAD` are left unchanged so callers can inspect the remote state
+/// (for example with `is_current_with_remote()`) before deciding
+/// whether to `pull()`.
+///
+/// When `refspecs` is empty the configured fetch refspecs for the
+/// remote are used.
+///
+/// When a `progress` callback is given it is invoked with the
+/// transfer statistics whilst fetching.
+pub fn fetch<P: AsRef<Path>>(
+    path: P,
+    remote: Option<&str>,
+    refspecs: &[&str],
+    progress: Option<ProgressFn<'_>>,
+) -> Result<()> {
+    let remote_name = remote.as_ref().map(|s| &s[..]).unwrap_or(ORIGIN);
+
+    info!("Fetch {} in {}", remote_name, path.as_ref().display());
+
+    let repo = open(path.as_ref())?;
+    let mut remote_spec = repo.find_remote(remote_name).map_err(|_| {
+        Error::NoRemote(remote_name.to_string(), path.as_ref().to_path_buf())
+    })?;
+    pull::fetch_remote(&mut remote_spec, refspecs, progress)
+        .map_err(Error::from)
+}
+
+/// Clone a repository.
+///
+/// When a `progress` callback is given it is invoked with the
+/// transfer statistics whilst receiving objects.
 pub fn clone<S: AsRef<str>, P: AsRef<Path>>(
     src: S,
     target: P,
+    progress: Option<ProgressFn<'_>>,
 ) -> Result<Repository> {
-    Ok(clone::clone(src, target).map_err(Error::from)?)
+    clone::clone(src, target, progress).map_err(Error::from)
 }
 
 pub fn copy<S: AsRef<str>, P: AsRef<Path>>(
@@ -115,13 +270,16 @@ pub fn copy<S: AsRef<str>, P: AsRef<Path>>(
     message: &str,
 ) -> Result<Repository> {
     let target = target.as_ref();
-    let repo = clone(src, target).map_err(Error::from)?;
+    let repo = clone(src, target, None).map_err(Error::from)?;
     pristine(target, &repo, message)?;
     Ok(repo)
 }
 
 /// Detach a repository from upstream by removing the entire commit
 /// history and creating a fresh repository.
+///
+/// Files matched by the ignore rules of the working tree are not
+/// added to the new repository.
 pub fn pristine<P: AsRef<Path>>(
     target: P,
     repo: &Repository,
@@ -147,7 +305,7 @@ pub fn init<P: AsRef<Path>>(target: P, message: &str) -> Result<Oid> {
 
     // Add all the files
     let mut index = new_repo.index()?;
-    index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
+    add_all_unignored(&new_repo, &mut index)?;
 
     // NOTE: must call `write` and `write_tree`
     index.write()?;
@@ -164,6 +322,32 @@ pub fn init<P: AsRef<Path>>(target: P, message: &str) -> Result<Oid> {
     Ok(new_repo.commit(Some(HEAD), &sig, &sig, message, &tree, parents)?)
 }
 
+/// Add all files in the working directory to the index skipping
+/// any paths that match the ignore rules for the repository.
+///
+/// Ignore rules are read from any existing `.gitignore` files in
+/// the working tree so that a fresh repository created by `pristine`
+/// or `init` does not commit build artifacts or dependencies such
+/// as `node_modules`.
+fn add_all_unignored(repo: &Repository, index: &mut Index) -> Result<()> {
+    let mut filter = |path: &Path, _spec: &[u8]| -> i32 {
+        match repo.is_path_ignored(path) {
+            Ok(true) => {
+                debug!("Ignore {}", path.display());
+                1
+            }
+            Ok(false) => 0,
+            // Abort the operation if we cannot determine
+            // the ignore status of a path
+            Err(_) => -1,
+        }
+    };
+
+    let callback: &mut git2::IndexMatchedPath = &mut filter;
+    index.add_all(["*"].iter(), IndexAddOption::DEFAULT, Some(callback))?;
+    Ok(())
+}
+
 pub fn find_last_commit<'a>(
     repo: &'a Repository,
 ) -> Result<Option<Commit<'a>>> {
@@ -176,21 +360,95 @@ pub fn find_last_commit<'a>(
     Ok(None)
 }
 
+/// Get the push refspec for a local branch to the branch
+/// with the same name on a remote.
+pub fn branch_refspec(branch: &str) -> String {
+    format!("refs/heads/{}:refs/heads/{}", branch, branch)
+}
+
 pub fn push_remote_name(
     repo: &Repository,
     remote: &str,
     cbs: Option<RemoteCallbacks<'_>>,
     refspecs: Option<Vec<String>>,
+    force: bool,
 ) -> Result<()> {
     let mut remote_spec = repo.find_remote(remote)?;
-    push(repo, &mut remote_spec, cbs, refspecs)
+    push(repo, &mut remote_spec, cbs, refspecs, force)
+}
+
+/// Outcome of pushing to multiple remotes.
+#[derive(Debug, Default)]
+pub struct PushReport {
+    /// Remotes that received the push.
+    pub pushed: Vec<String>,
+    /// Remotes that could not be pushed to and the reason.
+    pub failed: Vec<(String, Error)>,
+}
+
+/// Push to each of the named remotes.
+///
+/// A failure for one remote does not prevent the push to the
+/// remaining remotes; the outcome for each remote is collected
+/// in the report.
+pub fn push_remotes(
+    repo: &Repository,
+    remotes: &[String],
+    refspecs: Option<Vec<String>>,
+    force: bool,
+) -> PushReport {
+    let mut report: PushReport = Default::default();
+    for remote in remotes {
+        match push_remote_name(repo, remote, None, refspecs.clone(), force) {
+            Ok(_) => {
+                info!("Pushed to {}", remote);
+                report.pushed.push(remote.to_string());
+            }
+            Err(e) => {
+                warn!("Push to {} failed ({})", remote, e);
+                report.failed.push((remote.to_string(), e));
+            }
+        }
+    }
+    report
+}
+
+/// Ensure the remote reference for a refspec can be fast-forwarded
+/// to the local reference.
+fn check_fast_forward(
+    repo: &Repository,
+    remote: &mut Remote<'_>,
+    refspec: &str,
+) -> Result<()> {
+    let mut parts = refspec.splitn(2, ':');
+    let src = parts.next().unwrap_or(HEAD);
+    let dst = parts.next().unwrap_or(src);
+    let local = repo.revparse_single(src)?.peel_to_commit()?.id();
+    if let Some(remote_id) = list_remote_head(remote, dst)? {
+        if remote_id != local
+            && !repo.graph_descendant_of(local, remote_id).unwrap_or(false)
+        {
+            return Err(Error::NonFastForward(dst.to_string()));
+        }
+    }
+    Ok(())
 }
 
+/// Push to a remote.
+///
+/// When `refspecs` is not given the configured push refspecs for
+/// the remote are used falling back to `REFSPEC`.
+///
+/// Unless `force` is set the remote references are compared with
+/// the local references first and `Error::NonFastForward` is returned
+/// if the push would discard commits on the remote; any force prefix
+/// (`+`) in the refspecs is ignored.
 pub fn push(
-    _repo: &Repository,
+    repo: &Repository,
     remote: &mut Remote<'_>,
     cbs: Option<RemoteCallbacks<'_>>,
     refspecs: Option<Vec<String>>,
+    force: bool,
 ) -> Result<()> {
     let mut cbs = cbs.unwrap_or(callbacks::ssh_agent());
 
@@ -211,6 +469,24 @@ pub fn push(
         //vec![]
     };
 
+    let refspecs = refspecs
+        .iter()
+        .map(|s| {
+            let s = s.trim_start_matches('+');
+            if force {
+                format!("+{}", s)
+            } else {
+                s.to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if !force {
+        for refspec in refspecs.iter() {
+            check_fast_forward(repo, remote, refspec)?;
+        }
+    }
+
     //cbs.push_transfer_progress(|obj_sent, obj_total, bytes| {});
 
     cbs.push_update_reference(|name, status| {
@@ -230,6 +506,18 @@ pub fn push(
     Ok(())
 }
 
+/// Add all files that are not ignored and commit them on top
+/// of the current tip, files missing from the working tree are
+/// removed from the index.
+pub fn commit_all(repo: &Repository, message: &str) -> Result<Oid> {
+    let mut index = repo.index()?;
+    add_all_unignored(repo, &mut index)?;
+    index.update_all(["*"].iter(), None)?;
+    index.write()?;
+    let oid = index.write_tree()?;
+    commit(repo, Some(HEAD), oid, message)
+}
+
 /// Add and commit a file; the path must be relative to the repository.
 pub fn commit_file(
     repo: &Repository,
@@ -345,6 +633,21 @@ fn fetch<P: AsRef<Path>>(repo: &Repository, base: P) -> Result<()> {
 }
 */
 
+/// Get the changes between two revisions.
+///
+/// The `from` and `to` revisions may be references or commit ids,
+/// if either revision cannot be found `Error::NoRevision` is returned.
+///
+/// When `hunks` is set the changed lines for each file are included.
+pub fn diff(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+    hunks: bool,
+) -> Result<Vec<FileDelta>> {
+    diff::diff(repo, from, to, hunks)
+}
+
 pub fn print_clone<P: AsRef<Path>>(from: &str, to: P) {
     info!("Clone {}", from);
     info!("   -> {}", to.as_ref().display());
@@ -372,17 +675,23 @@ pub fn is_clean(repo: &Repository) -> bool {
     repo.state() == RepositoryState::Clean
 }
 
+/// Clone a repository when the target does not exist otherwise
+/// pull from the default remote and branch.
+///
+/// The `progress` callback is passed to `clone()` or `pull()`.
 pub fn clone_or_fetch<P: AsRef<Path>>(
     from: &str,
     to: P,
+    progress: Option<ProgressFn<'_>>,
 ) -> Result<(Repository, bool)> {
     let to = to.as_ref();
     if !to.exists() {
         print_clone(from, to);
-        Ok((clone(from, to)?, true))
+        Ok((clone(from, to, progress)?, true))
     } else {
         let repo = open(to)?;
-        pull(to, None, None)?;
+        // Cached repositories always follow the remote
+        pull(to, None, None, ConflictPolicy::PreferRemote, progress)?;
         Ok((repo, false))
     }
 }
@@ -414,25 +723,64 @@ where
     Ok(())
 }
 
+/// Get the paths of conflicted entries in a repository.
+pub fn conflicts(repo: &Repository) -> Result<Vec<PathBuf>> {
+    let mut status_options = StatusOptions::new();
+    status_options.include_untracked(false);
+    let statuses = repo.statuses(Some(&mut status_options))?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| entry.status().is_conflicted())
+        .filter_map(|entry| entry.path().map(PathBuf::from))
+        .collect())
+}
+
 /// Sync a project with a remote repository.
+///
+/// Sync is not transactional across the pull; if the pull results
+/// in merge conflicts the commit for local changes has already been
+/// made and the working tree is left mid-merge so that the conflicts
+/// may be resolved manually. In this case `Error::Conflict` is
+/// returned with the path of the first conflicted file and nothing
+/// is pushed.
+///
+/// Unless the conflict policy is `Abort` conflicts are resolved
+/// automatically by choosing the local or remote version of each file.
+///
+/// The remote is checked before any changes are committed and
+/// `Error::RemoteUnreachable` is returned when it cannot be reached.
+///
+/// Once the remote has been updated the branch is pushed to each of
+/// the `mirrors`, a failed push to a mirror is recorded in the report
+/// and does not stop the push to the other mirrors.
+///
+/// The `progress` callback is passed to `pull()`.
 pub fn sync<P: AsRef<Path>>(
     dir: P,
     remote: String,
     branch: String,
+    mirrors: Vec<String>,
     add_untracked: bool,
     message: Option<String>,
-) -> Result<()> {
+    policy: ConflictPolicy,
+    progress: Option<ProgressFn<'_>>,
+) -> Result<PushReport> {
     let repo = open(dir.as_ref())?;
 
     let mut remote_spec = repo.find_remote(&remote).map_err(|_| {
         Error::NoRemote(remote.to_string(), dir.as_ref().to_path_buf())
     })?;
 
+    // Fail early before making any local commit
+    check_remote(&repo, &remote)?;
+
+    let mut refspecs = None;
     if remote_spec.push_refspecs()?.is_empty() {
         if let Some(url) = remote_spec.url() {
             remote_spec =
                 repo.remote_anonymous(remote_spec.pushurl().unwrap_or(url))?;
         }
+        refspecs = Some(vec![branch_refspec(&branch)]);
     }
 
     let _ = repo.find_branch(&branch, BranchType::Local).map_err(|_| {
@@ -512,8 +860,16 @@ pub fn sync<P: AsRef<Path>>(
     }
 
     // 3) Pull the remote repository
-    // TODO: Handle merge conflicts on the pull???
-    pull(dir.as_ref(), Some(&remote), Some(&branch))?;
+    pull(dir.as_ref(), Some(&remote), Some(&branch), policy, progress)?;
+
+    // Leave the merge in progress for the user to resolve
+    let conflicted = conflicts(&repo)?;
+    if !conflicted.is_empty() {
+        for path in conflicted.iter() {
+            warn!("Conflict {}", path.display());
+        }
+        return Err(Error::Conflict(dir.as_ref().join(&conflicted[0])));
+    }
 
     //refs/heads/*:refs/remotes/origin/
 
@@ -522,7 +878,7 @@ pub fn sync<P: AsRef<Path>>(
     }
 
     // 4) Push to the remote repository
-    push(&repo, &mut remote_spec, None, None)?;
+    push(&repo, &mut remote_spec, None, refspecs, false)?;
 
     // 5) Update a remote tracking branch if it exists
     let refspec = format!("refs/remotes/{}/{}", remote, branch);
@@ -532,7 +888,365 @@ pub fn sync<P: AsRef<Path>>(
         reference.set_target(commit_oid, "Update remote tracking branch")?;
     }
 
+    // 6) Push to the mirrors
+    let mut report = push_remotes(
+        &repo,
+        &mirrors,
+        Some(vec![branch_refspec(&branch)]),
+        false,
+    );
+    report.pushed.insert(0, remote);
+
     info!("Sync complete ✓");
 
-    Ok(())
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Once;
+
+    use git2::{
+        Oid, Repository, RepositoryInitOptions, RepositoryState, ResetType,
+    };
+
+    use super::{
+        branch_refspec, check_remote, clone, fetch, init, open, pristine,
+        push_remote_name, push_remotes, remote_address, sync, Error, Result,
+        TransferProgress,
+    };
+    use crate::{conflicts, ConflictPolicy, MAIN};
+
+    #[test]
+    fn remote_addresses() {
+        assert_eq!(
+            Some(("github.com".to_string(), 443)),
+            remote_address("https://github.com/uwe-app/app")
+        );
+        assert_eq!(
+            Some(("example.com".to_string(), 2222)),
+            remote_address("ssh://git@example.com:2222/repo.git")
+        );
+        assert_eq!(
+            Some(("github.com".to_string(), 22)),
+            remote_address("git@github.com:uwe-app/app.git")
+        );
+        assert_eq!(None, remote_address("/tmp/repo.git"));
+        assert_eq!(None, remote_address("file:///tmp/repo.git"));
+    }
+
+    // A fresh repository takes the commit identity from the global
+    // configuration so point libgit2 at a global configuration file
+    // that has an identity
+    fn global_identity() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let dir = std::env::temp_dir().join("scm-test-identity");
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join(".gitconfig"),
+                "[user]\n\tname = Test\n\temail = test@example.com\n",
+            )
+            .unwrap();
+            let path = CString::new(dir.to_string_lossy().as_bytes()).unwrap();
+            libgit2_sys::init();
+            unsafe {
+                libgit2_sys::git_libgit2_opts(
+                    libgit2_sys::GIT_OPT_SET_SEARCH_PATH as i32,
+                    libgit2_sys::GIT_CONFIG_LEVEL_GLOBAL,
+                    path.as_ptr(),
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn pristine_skips_ignored() -> Result<()> {
+        global_identity();
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("index.md"), "# Home")?;
+        init(dir.path(), "Upstream")?;
+        let repo = open(dir.path())?;
+
+        fs::write(dir.path().join(".gitignore"), "node_modules/\n")?;
+        fs::create_dir(dir.path().join("node_modules"))?;
+        fs::write(dir.path().join("node_modules").join("lib.js"), "")?;
+        pristine(dir.path(), &repo, "Initial files.")?;
+
+        let repo = open(dir.path())?;
+        let head = repo.head()?.peel_to_commit()?;
+        assert_eq!(Some("Initial files."), head.message());
+        assert_eq!(0, head.parent_count());
+        let names: Vec<String> = head
+            .tree()?
+            .iter()
+            .map(|entry| entry.name().unwrap_or("").to_string())
+            .collect();
+        assert_eq!(vec![".gitignore", "index.md"], names);
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_remote() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let repo = Repository::init(dir.path())?;
+        let bogus = dir.path().join("missing.git");
+        repo.remote("origin", &bogus.to_string_lossy())?;
+
+        assert!(matches!(
+            check_remote(&repo, "upstream"),
+            Err(Error::NoRemote(..))
+        ));
+        assert!(matches!(
+            check_remote(&repo, "origin"),
+            Err(Error::RemoteUnreachable(..))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn sync_unreachable_remote() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN);
+        let repo = Repository::init_opts(dir.path(), &opts)?;
+        let mut config = repo.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+
+        fs::write(dir.path().join("index.md"), "# Home")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("index.md"))?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let sig = repo.signature()?;
+        let initial =
+            repo.commit(Some("HEAD"), &sig, &sig, "Initial", &tree, &[])?;
+
+        let bogus = dir.path().join("missing.git");
+        repo.remote("origin", &bogus.to_string_lossy())?;
+        fs::write(dir.path().join("about.md"), "# About")?;
+
+        let result = sync(
+            dir.path(),
+            "origin".to_string(),
+            MAIN.to_string(),
+            vec![],
+            true,
+            Some("Update".to_string()),
+            ConflictPolicy::Abort,
+            None,
+        );
+        assert!(matches!(result, Err(Error::RemoteUnreachable(..))));
+        // No local commit was made
+        assert_eq!(initial, repo.head()?.peel_to_commit()?.id());
+        Ok(())
+    }
+
+    fn write_commit(repo: &Repository, message: &str) -> Result<Oid> {
+        let root = repo.workdir().unwrap();
+        fs::write(root.join("file.txt"), message)?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("file.txt"))?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let sig = repo.signature()?;
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents = parent.iter().collect::<Vec<_>>();
+        Ok(repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)?)
+    }
+
+    fn remote_head(origin: &Repository) -> Result<Oid> {
+        Ok(origin.refname_to_id("refs/heads/main")?)
+    }
+
+    /// Create an origin repository with a commit and a local clone.
+    fn origin_and_local(base: &Path) -> Result<(Repository, Repository)> {
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN);
+        let origin = Repository::init_opts(base.join("origin"), &opts)?;
+        let mut config = origin.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+        write_commit(&origin, "base")?;
+
+        let local = Repository::clone(
+            &base.join("origin").to_string_lossy(),
+            base.join("local"),
+        )?;
+        let mut config = local.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+        Ok((origin, local))
+    }
+
+    #[test]
+    fn fetch_without_merge() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (origin, local) = origin_and_local(dir.path())?;
+        let head = local.head()?.peel_to_commit()?.id();
+        let remote = write_commit(&origin, "remote")?;
+
+        fetch(dir.path().join("local"), None, &[], None)?;
+        assert_eq!(head, local.head()?.peel_to_commit()?.id());
+        assert_eq!(remote, local.refname_to_id("refs/remotes/origin/main")?);
+        assert_eq!(
+            "base",
+            fs::read_to_string(dir.path().join("local").join("file.txt"))?
+        );
+
+        assert!(matches!(
+            fetch(dir.path().join("local"), Some("upstream"), &[], None),
+            Err(Error::NoRemote(..))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn sync_conflict() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (origin, local) = origin_and_local(dir.path())?;
+        let remote = write_commit(&origin, "remote")?;
+        write_commit(&local, "local")?;
+
+        let result = sync(
+            dir.path().join("local"),
+            "origin".to_string(),
+            MAIN.to_string(),
+            vec![],
+            false,
+            None,
+            ConflictPolicy::Abort,
+            None,
+        );
+        match result {
+            Err(Error::Conflict(path)) => {
+                assert_eq!(dir.path().join("local").join("file.txt"), path)
+            }
+            _ => panic!("expected a merge conflict"),
+        }
+        // Merge is left in progress and nothing was pushed
+        assert_eq!(RepositoryState::Merge, local.state());
+        assert_eq!(1, conflicts(&local)?.len());
+        assert_eq!(remote, remote_head(&origin)?);
+        Ok(())
+    }
+
+    #[test]
+    fn push_fast_forward_guard() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN).bare(true);
+        let origin = Repository::init_opts(dir.path().join("origin"), &opts)?;
+
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN);
+        let local = Repository::init_opts(dir.path().join("local"), &opts)?;
+        let mut config = local.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+        local.remote("origin", &dir.path().join("origin").to_string_lossy())?;
+        let refspecs = Some(vec![branch_refspec(MAIN)]);
+
+        // Normal push
+        let first = write_commit(&local, "First")?;
+        push_remote_name(&local, "origin", None, refspecs.clone(), false)?;
+        assert_eq!(first, remote_head(&origin)?);
+        let second = write_commit(&local, "Second")?;
+        push_remote_name(&local, "origin", None, refspecs.clone(), false)?;
+        assert_eq!(second, remote_head(&origin)?);
+
+        // Rewrite history so the remote is ahead
+        let commit = local.find_commit(first)?;
+        local.reset(commit.as_object(), ResetType::Hard, None)?;
+        let diverged = write_commit(&local, "Diverged")?;
+
+        // A forced refspec does not bypass the guard
+        let forced = Some(vec![format!("+{}", branch_refspec(MAIN))]);
+        let result = push_remote_name(&local, "origin", None, forced, false);
+        assert!(matches!(result, Err(Error::NonFastForward(_))));
+        assert_eq!(second, remote_head(&origin)?);
+
+        push_remote_name(&local, "origin", None, refspecs, true)?;
+        assert_eq!(diverged, remote_head(&origin)?);
+        Ok(())
+    }
+
+    #[test]
+    fn push_multiple_remotes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN).bare(true);
+        let primary = Repository::init_opts(dir.path().join("primary"), &opts)?;
+        let mirror = Repository::init_opts(dir.path().join("mirror"), &opts)?;
+
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN);
+        let local = Repository::init_opts(dir.path().join("local"), &opts)?;
+        let mut config = local.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+        for name in &["primary", "mirror", "missing"] {
+            let url = format!("file://{}", dir.path().join(name).display());
+            local.remote(name, &url)?;
+        }
+
+        let head = write_commit(&local, "First")?;
+        let remotes = vec![
+            "primary".to_string(),
+            "missing".to_string(),
+            "mirror".to_string(),
+        ];
+        let report = push_remotes(
+            &local,
+            &remotes,
+            Some(vec![branch_refspec(MAIN)]),
+            false,
+        );
+
+        assert_eq!(
+            vec!["primary".to_string(), "mirror".to_string()],
+            report.pushed
+        );
+        assert_eq!(1, report.failed.len());
+        assert_eq!("missing", report.failed[0].0);
+        assert_eq!(head, remote_head(&primary)?);
+        assert_eq!(head, remote_head(&mirror)?);
+        Ok(())
+    }
+
+    #[test]
+    fn clone_progress() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN);
+        let origin = Repository::init_opts(dir.path().join("origin"), &opts)?;
+        let mut config = origin.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+        for message in &["First", "Second", "Third"] {
+            write_commit(&origin, message)?;
+        }
+
+        let url = format!("file://{}", dir.path().join("origin").display());
+        let mut seen: Vec<TransferProgress> = Vec::new();
+        clone(
+            &url,
+            dir.path().join("local"),
+            Some(Box::new(|stats: TransferProgress| seen.push(stats))),
+        )?;
+
+        assert!(!seen.is_empty());
+        for pair in seen.windows(2) {
+            assert!(pair[1].received_objects >= pair[0].received_objects);
+            assert!(pair[1].received_bytes >= pair[0].received_bytes);
+        }
+        let last = seen.last().unwrap();
+        assert!(last.total_objects > 0);
+        assert_eq!(last.total_objects, last.received_objects);
+        Ok(())
+    }
 }
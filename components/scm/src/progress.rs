@@ -1,50 +1,75 @@
+use std::io::stderr;
+
 use git2::{Progress, RemoteCallbacks};
-use std::io::{self, Write};
-
-use log::info;
-
-/*
-pub fn print_stats(stats: Progress) {
-    if stats.local_objects() > 0 {
-        info!(
-            "Received {}/{} objects in {} bytes (used {} local objects)",
-            stats.indexed_objects(),
-            stats.total_objects(),
-            stats.received_bytes(),
-            stats.local_objects()
-        );
-    } else if stats.received_bytes() > 0 {
-        info!(
-            "Received {}/{} objects in {} bytes",
-            stats.indexed_objects(),
-            stats.total_objects(),
-            stats.received_bytes()
-        );
+
+use pbr::ProgressBar;
+
+/// Statistics reported whilst receiving objects from a remote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub indexed_objects: usize,
+    pub indexed_deltas: usize,
+    pub total_deltas: usize,
+    pub received_bytes: usize,
+}
+
+impl TransferProgress {
+    /// Determine if all objects have been received and
+    /// deltas are being resolved.
+    pub fn is_resolving(&self) -> bool {
+        self.total_objects > 0 && self.received_objects == self.total_objects
     }
 }
-*/
-
-/*
-pub fn add_progress_callbacks(cb: &mut RemoteCallbacks) {
-    // Print out our transfer progress.
-    cb.transfer_progress(|stats| {
-        if stats.received_objects() == stats.total_objects() {
-            info!(
-                "Resolving deltas {}/{}\r",
-                stats.indexed_deltas(),
-                stats.total_deltas()
-            );
-        } else if stats.total_objects() > 0 {
-            info!(
-                "Received {}/{} objects ({}) in {} bytes\r",
-                stats.received_objects(),
-                stats.total_objects(),
-                stats.indexed_objects(),
-                stats.received_bytes()
-            );
+
+impl From<Progress<'_>> for TransferProgress {
+    fn from(stats: Progress<'_>) -> Self {
+        Self {
+            received_objects: stats.received_objects(),
+            total_objects: stats.total_objects(),
+            indexed_objects: stats.indexed_objects(),
+            indexed_deltas: stats.indexed_deltas(),
+            total_deltas: stats.total_deltas(),
+            received_bytes: stats.received_bytes(),
         }
-        io::stdout().flush().unwrap();
+    }
+}
+
+/// Callback invoked with the transfer statistics for a clone or fetch.
+pub type ProgressFn<'a> = Box<dyn FnMut(TransferProgress) + 'a>;
+
+/// Progress callback that ignores the transfer statistics.
+pub fn noop<'a>() -> ProgressFn<'a> {
+    Box::new(|_| {})
+}
+
+/// Progress callback that renders a progress bar to stderr.
+pub fn bar<'a>() -> ProgressFn<'a> {
+    let mut pb = ProgressBar::on(stderr(), 0);
+    pb.show_speed = false;
+    Box::new(move |stats| {
+        if stats.is_resolving() {
+            pb.message(" Resolve deltas ");
+            pb.total = stats.total_deltas as u64;
+            pb.set(stats.indexed_deltas as u64);
+        } else if stats.total_objects > 0 {
+            pb.message(" Fetch ");
+            pb.total = stats.total_objects as u64;
+            pb.set(stats.received_objects as u64);
+        }
+    })
+}
+
+/// Report transfer progress to a callback, when no callback
+/// is given progress is not reported.
+pub fn add_progress_callbacks<'a>(
+    cb: &mut RemoteCallbacks<'a>,
+    progress: Option<ProgressFn<'a>>,
+) {
+    let mut progress = progress.unwrap_or_else(noop);
+    cb.transfer_progress(move |stats| {
+        progress(stats.into());
         true
     });
 }
-*/
@@ -12,38 +12,47 @@
  * <http://creativecommons.org/publicdomain/zero/1.0/>.
  */
 
-use std::io::stderr;
+use std::fmt;
 use std::path::Path;
 
-use git2::Repository;
+use git2::{FileFavor, MergeOptions, Repository};
 use log::{debug, info};
 
-use pbr::ProgressBar;
+use crate::{
+    callbacks,
+    progress::{self, ProgressFn},
+};
 
-use crate::callbacks;
+/// Determines how conflicts are handled when pulling changes.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Stop so that conflicts may be resolved manually.
+    Abort,
+    /// Choose the local version of conflicted files.
+    PreferLocal,
+    /// Choose the remote version of conflicted files.
+    PreferRemote,
+}
 
-fn do_fetch<'a>(
-    repo: &'a git2::Repository,
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ConflictPolicy::Abort => write!(f, "abort"),
+            ConflictPolicy::PreferLocal => write!(f, "prefer-local"),
+            ConflictPolicy::PreferRemote => write!(f, "prefer-remote"),
+        }
+    }
+}
+
+/// Fetch from a remote updating the remote tracking references
+/// but leave the working tree untouched.
+pub(crate) fn fetch_remote(
+    remote: &mut git2::Remote,
     refs: &[&str],
-    remote: &'a mut git2::Remote,
-    remote_name: &'a str,
-) -> Result<git2::AnnotatedCommit<'a>, git2::Error> {
+    progress: Option<ProgressFn<'_>>,
+) -> Result<(), git2::Error> {
     let mut cb = callbacks::ssh_agent();
-    let mut pb = ProgressBar::on(stderr(), 0);
-    pb.show_speed = false;
-
-    cb.transfer_progress(move |stats| {
-        if stats.received_objects() == stats.total_objects() {
-            pb.message(" Resolve deltas ");
-            pb.total = stats.total_deltas() as u64;
-            pb.set(stats.indexed_deltas() as u64);
-        } else if stats.total_objects() > 0 {
-            pb.message(" Fetch ");
-            pb.total = stats.total_objects() as u64;
-            pb.set(stats.received_objects() as u64);
-        }
-        true
-    });
+    progress::add_progress_callbacks(&mut cb, progress);
 
     let mut fo = git2::FetchOptions::new();
     fo.remote_callbacks(cb);
@@ -52,7 +61,7 @@ fn do_fetch<'a>(
     // Perform a download and also update tips
     fo.download_tags(git2::AutotagOption::All);
 
-    debug!("Fetching {}", remote.name().unwrap());
+    debug!("Fetching {}", remote.name().unwrap_or(""));
     remote.fetch(refs, Some(&mut fo), None)?;
 
     //let stats = remote.stats();
@@ -64,6 +73,18 @@ fn do_fetch<'a>(
     // how many objects we saved from having to cross the network.
     //let stats = remote.stats();
 
+    Ok(())
+}
+
+fn do_fetch<'a>(
+    repo: &'a git2::Repository,
+    refs: &[&str],
+    remote: &'a mut git2::Remote,
+    remote_name: &'a str,
+    progress: Option<ProgressFn<'_>>,
+) -> Result<git2::AnnotatedCommit<'a>, git2::Error> {
+    fetch_remote(remote, refs, progress)?;
+
     let fetch_ref = format!("refs/remotes/{}/{}", remote_name, refs[0]);
     let fetch_head = repo.find_reference(&fetch_ref)?;
     Ok(repo.reference_to_annotated_commit(&fetch_head)?)
@@ -94,10 +115,28 @@ fn fast_forward(
     Ok(())
 }
 
+/// Paths for the conflicted entries in a merge index.
+fn conflicted_paths(idx: &git2::Index) -> Result<Vec<String>, git2::Error> {
+    let mut paths = Vec::new();
+    for conflict in idx.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref());
+        if let Some(entry) = entry {
+            paths.push(String::from_utf8_lossy(&entry.path).to_string());
+        }
+    }
+    Ok(paths)
+}
+
 fn normal_merge(
     repo: &Repository,
     local: &git2::AnnotatedCommit,
     remote: &git2::AnnotatedCommit,
+    policy: ConflictPolicy,
 ) -> Result<(), git2::Error> {
     let local_tree = repo.find_commit(local.id())?.tree()?;
     let remote_tree = repo.find_commit(remote.id())?.tree()?;
@@ -107,12 +146,47 @@ fn normal_merge(
     let mut idx =
         repo.merge_trees(&ancestor, &local_tree, &remote_tree, None)?;
 
+    // Resolve conflicts by favouring one side of the merge
+    if idx.has_conflicts() {
+        let favor = match policy {
+            ConflictPolicy::Abort => None,
+            ConflictPolicy::PreferLocal => Some(FileFavor::Ours),
+            ConflictPolicy::PreferRemote => Some(FileFavor::Theirs),
+        };
+
+        if let Some(favor) = favor {
+            let resolved = conflicted_paths(&idx)?;
+            let mut opts = MergeOptions::new();
+            opts.file_favor(favor);
+            idx = repo.merge_trees(
+                &ancestor,
+                &local_tree,
+                &remote_tree,
+                Some(&opts),
+            )?;
+            if !idx.has_conflicts() {
+                for path in resolved {
+                    info!("Resolved conflict {} ({})", path, policy);
+                }
+            }
+        }
+    }
+
     if idx.has_conflicts() {
         info!("Merge conficts detected...");
-        repo.checkout_index(Some(&mut idx), None)?;
+        // Merge into the repository index and working tree so that
+        // the conflicts are recorded and may be resolved manually.
+        repo.merge(&[remote], None, None)?;
         return Ok(());
     }
     let result_tree = repo.find_tree(idx.write_tree_to(repo)?)?;
+    // Update the working tree before the commit moves HEAD otherwise
+    // the checkout compares against the merged tree and skips files
+    // changed by the merge.
+    repo.checkout_tree(
+        result_tree.as_object(),
+        Some(git2::build::CheckoutBuilder::default().safe()),
+    )?;
     // now create the merge commit
     let msg = format!("Merge: {} into {}", remote.id(), local.id());
     let sig = repo.signature()?;
@@ -127,8 +201,6 @@ fn normal_merge(
         &result_tree,
         &[&local_commit, &remote_commit],
     )?;
-    // Set working tree to match head.
-    repo.checkout_head(None)?;
     Ok(())
 }
 
@@ -136,6 +208,7 @@ fn do_merge<'a>(
     repo: &'a Repository,
     remote_branch: &str,
     fetch_commit: git2::AnnotatedCommit<'a>,
+    policy: ConflictPolicy,
 ) -> Result<(), git2::Error> {
     // 1. do a merge analysis
     let analysis = repo.merge_analysis(&[&fetch_commit])?;
@@ -175,7 +248,7 @@ fn do_merge<'a>(
     } else if analysis.0.is_normal() {
         // do a normal merge
         let head_commit = repo.reference_to_annotated_commit(&repo.head()?)?;
-        normal_merge(&repo, &head_commit, &fetch_commit)?;
+        normal_merge(repo, &head_commit, &fetch_commit, policy)?;
     } else {
         debug!("No merge needed");
     }
@@ -186,6 +259,8 @@ pub(crate) fn pull<P: AsRef<Path>>(
     path: P,
     remote_name: &str,
     branch_name: &str,
+    policy: ConflictPolicy,
+    progress: Option<ProgressFn<'_>>,
 ) -> Result<(), git2::Error> {
     /*
     let remote_name = remote.as_ref().map(|s| &s[..]).unwrap_or("origin");
@@ -202,6 +277,82 @@ pub(crate) fn pull<P: AsRef<Path>>(
     let repo = Repository::open(path)?;
     let mut remote = repo.find_remote(remote_name)?;
     let fetch_commit =
-        do_fetch(&repo, &[branch_name], &mut remote, &remote_name)?;
-    do_merge(&repo, &branch_name, fetch_commit)
+        do_fetch(&repo, &[branch_name], &mut remote, remote_name, progress)?;
+    do_merge(&repo, branch_name, fetch_commit, policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use git2::{Repository, RepositoryInitOptions};
+
+    use super::pull;
+    use crate::{commit_file, conflicts, ConflictPolicy, Result, MAIN};
+
+    fn configure(repo: &Repository) -> Result<()> {
+        let mut config = repo.config()?;
+        config.set_str("user.name", "Test")?;
+        config.set_str("user.email", "test@example.com")?;
+        Ok(())
+    }
+
+    fn write_commit(repo: &Repository, content: &str) -> Result<()> {
+        let root = repo.workdir().unwrap();
+        fs::write(root.join("file.txt"), content)?;
+        commit_file(repo, Path::new("file.txt"), content.trim())?;
+        Ok(())
+    }
+
+    /// Create a local clone with a commit that conflicts
+    /// with a commit in the origin repository.
+    fn conflicting(base: &Path) -> Result<Repository> {
+        let mut opts = RepositoryInitOptions::new();
+        opts.initial_head(MAIN);
+        let origin = Repository::init_opts(base.join("origin"), &opts)?;
+        configure(&origin)?;
+        fs::write(base.join("origin").join("file.txt"), "base\n")?;
+        let mut index = origin.index()?;
+        index.add_path(Path::new("file.txt"))?;
+        index.write()?;
+        let tree = origin.find_tree(index.write_tree()?)?;
+        let sig = origin.signature()?;
+        origin.commit(Some("HEAD"), &sig, &sig, "Base", &tree, &[])?;
+
+        let local = Repository::clone(
+            &base.join("origin").to_string_lossy(),
+            base.join("local"),
+        )?;
+        configure(&local)?;
+
+        write_commit(&origin, "remote\n")?;
+        write_commit(&local, "local\n")?;
+        Ok(local)
+    }
+
+    fn sync_with(policy: ConflictPolicy) -> Result<(String, usize)> {
+        let base = tempfile::tempdir()?;
+        let local = conflicting(base.path())?;
+        pull(base.path().join("local"), "origin", MAIN, policy, None)?;
+        let content =
+            fs::read_to_string(base.path().join("local").join("file.txt"))?;
+        Ok((content, conflicts(&local)?.len()))
+    }
+
+    #[test]
+    fn conflict_policy() -> Result<()> {
+        let (content, conflicted) = sync_with(ConflictPolicy::PreferLocal)?;
+        assert_eq!("local\n", content);
+        assert_eq!(0, conflicted);
+
+        let (content, conflicted) = sync_with(ConflictPolicy::PreferRemote)?;
+        assert_eq!("remote\n", content);
+        assert_eq!(0, conflicted);
+
+        let (content, conflicted) = sync_with(ConflictPolicy::Abort)?;
+        assert!(content.contains("<<<<<<<"));
+        assert_eq!(1, conflicted);
+        Ok(())
+    }
 }
@@ -15,7 +15,8 @@ pub async fn fetch_registry() -> Result<()> {
 }
 
 async fn fetch(url: String, dir: PathBuf) -> Result<()> {
-    let (_, cloned) = crate::clone_or_fetch(&url, &dir)?;
+    let (_, cloned) =
+        crate::clone_or_fetch(&url, dir, Some(crate::progress::bar()))?;
 
     // Clear the fetch progress
     utils::terminal::clear_current_line()?;
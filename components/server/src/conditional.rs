@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use once_cell::sync::OnceCell;
+use sha3::{Digest, Sha3_256};
+
+use actix_web::{
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, EntityTag, Header, HeaderValue, HttpDate},
+        Method, StatusCode,
+    },
+    HttpMessage, HttpResponse,
+};
+
+use crate::precompressed::resolve;
+
+type Hashes = Mutex<HashMap<PathBuf, (SystemTime, u64, String)>>;
+
+/// Content hashes for files keyed by path and invalidated
+/// when the modification time or size changes.
+fn hashes() -> &'static Hashes {
+    static INSTANCE: OnceCell<Hashes> = OnceCell::new();
+    INSTANCE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Validators used to answer conditional requests.
+///
+/// Entity tags are weak as the same tag is sent for
+/// pre-compressed variants of a file.
+#[derive(Debug, Clone)]
+pub(crate) struct Validators {
+    etag: EntityTag,
+    last_modified: Option<HttpDate>,
+}
+
+impl Validators {
+    pub fn new(hash: String, modified: Option<SystemTime>) -> Self {
+        Self {
+            etag: EntityTag::new_weak(hash),
+            last_modified: modified.map(HttpDate::from),
+        }
+    }
+
+    /// Compute the validators for a file using a hash of the
+    /// file content and the modification time.
+    pub fn from_file(file: &Path) -> Option<Self> {
+        let metadata = fs::metadata(file).ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        let modified = metadata.modified().ok()?;
+        let size = metadata.len();
+
+        let mut hashes = hashes().lock().unwrap();
+        if let Some((cached_modified, cached_size, hash)) = hashes.get(file) {
+            if *cached_modified == modified && *cached_size == size {
+                return Some(Self::new(hash.clone(), Some(modified)));
+            }
+        }
+
+        let content = fs::read(file).ok()?;
+        let hash = hex::encode(Sha3_256::digest(&content));
+        hashes.insert(file.to_path_buf(), (modified, size, hash.clone()));
+        Some(Self::new(hash, Some(modified)))
+    }
+
+    /// Determine if the client already has the current representation.
+    ///
+    /// When `If-None-Match` is present `If-Modified-Since` is ignored.
+    pub fn is_fresh<M: HttpMessage>(&self, req: &M) -> bool {
+        if req.headers().contains_key(header::IF_NONE_MATCH) {
+            return match header::IfNoneMatch::parse(req) {
+                Ok(header::IfNoneMatch::Any) => true,
+                Ok(header::IfNoneMatch::Items(ref items)) => {
+                    items.iter().any(|tag| tag.weak_eq(&self.etag))
+                }
+                Err(_) => false,
+            };
+        }
+
+        if let (Some(modified), Ok(header::IfModifiedSince(since))) =
+            (self.last_modified, header::IfModifiedSince::parse(req))
+        {
+            return SystemTime::from(modified) <= SystemTime::from(since);
+        }
+        false
+    }
+
+    /// Add the validator headers to a response.
+    pub fn apply(&self, headers: &mut header::HeaderMap) {
+        if let Ok(value) = HeaderValue::from_str(&self.etag.to_string()) {
+            headers.insert(header::ETAG, value);
+        }
+        if let Some(modified) = self.last_modified {
+            if let Ok(value) = HeaderValue::from_str(&modified.to_string()) {
+                headers.insert(header::LAST_MODIFIED, value);
+            }
+        }
+    }
+
+    /// Response for a client that has the current representation.
+    pub fn not_modified(&self) -> HttpResponse {
+        let mut res = HttpResponse::NotModified().finish();
+        self.apply(res.headers_mut());
+        res
+    }
+}
+
+/// Respond with `304 Not Modified` when a request for a file in the
+/// root directory is fresh; otherwise the request is returned with
+/// the validators for the file so that they may be added to the
+/// response from the next service.
+pub(crate) fn check(
+    root: &Path,
+    req: ServiceRequest,
+) -> std::result::Result<ServiceResponse, (ServiceRequest, Option<Validators>)>
+{
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return Err((req, None));
+    }
+
+    let validators = match resolve(root, req.path())
+        .and_then(|file| Validators::from_file(&file))
+    {
+        Some(validators) => validators,
+        None => return Err((req, None)),
+    };
+
+    if validators.is_fresh(&req) {
+        let (http_req, _payload) = req.into_parts();
+        return Ok(ServiceResponse::new(http_req, validators.not_modified()));
+    }
+
+    Err((req, Some(validators)))
+}
+
+/// Add validators to a successful response.
+pub(crate) fn apply(res: &mut ServiceResponse, validators: Option<Validators>) {
+    if let (StatusCode::OK, Some(validators)) = (res.status(), validators) {
+        validators.apply(res.headers_mut());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_files::Files;
+    use actix_web::{dev::Service, test, App};
+    use futures::future::{ok, Either};
+
+    #[actix_web::test]
+    async fn not_modified() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("index.html"), "<p>Hello</p>")?;
+
+        let root = dir.path().to_path_buf();
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(move |req, srv| match check(&root, req) {
+                    Ok(res) => Either::Left(ok(res)),
+                    Err((req, validators)) => {
+                        let fut = srv.call(req);
+                        Either::Right(async move {
+                            let mut res = fut.await?;
+                            apply(&mut res, validators);
+                            Ok::<_, actix_web::Error>(res)
+                        })
+                    }
+                })
+                .service(
+                    Files::new("/", dir.path().to_path_buf())
+                        .use_etag(false)
+                        .use_last_modified(false),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/index.html").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::OK, res.status());
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert!(etag.to_str().unwrap().starts_with("W/\""));
+        assert!(res.headers().get(header::LAST_MODIFIED).is_some());
+
+        let req = test::TestRequest::get()
+            .uri("/index.html")
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::NOT_MODIFIED, res.status());
+        let body = test::read_body(res).await;
+        assert!(body.is_empty());
+
+        // Changed content is sent again
+        fs::write(dir.path().join("index.html"), "<p>Changed</p>")?;
+        let req = test::TestRequest::get()
+            .uri("/index.html")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::OK, res.status());
+
+        Ok(())
+    }
+}
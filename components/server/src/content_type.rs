@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    dev::ServiceResponse,
+    http::header::{self, HeaderValue},
+};
+
+use config::server::mime_type;
+
+/// Set the content type of a successful response when the file
+/// extension of the request path has a configured content type.
+pub(crate) fn apply(
+    mime_types: &HashMap<String, String>,
+    res: &mut ServiceResponse,
+) {
+    if !res.status().is_success() {
+        return;
+    }
+    let value = mime_type(mime_types, res.request().path())
+        .and_then(|mime| HeaderValue::from_str(mime).ok());
+    if let Some(value) = value {
+        res.headers_mut().insert(header::CONTENT_TYPE, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_files::Files;
+    use actix_web::{dev::Service, test, App};
+    use std::fs;
+    use std::sync::Arc;
+
+    async fn content_type(
+        mime_types: HashMap<String, String>,
+        file: &str,
+    ) -> std::io::Result<String> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join(file), "")?;
+
+        let mime_types = Arc::new(mime_types);
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(move |req, srv| {
+                    let mime_types = Arc::clone(&mime_types);
+                    let fut = srv.call(req);
+                    async move {
+                        let mut res = fut.await?;
+                        apply(&mime_types, &mut res);
+                        Ok::<_, actix_web::Error>(res)
+                    }
+                })
+                .service(Files::new("/", dir.path().to_path_buf())),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/{}", file))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+        Ok(res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string())
+    }
+
+    #[actix_web::test]
+    async fn wasm_content_type() -> std::io::Result<()> {
+        assert_eq!(
+            "application/wasm",
+            content_type(HashMap::new(), "main.wasm").await?
+        );
+        assert_eq!(
+            "application/manifest+json",
+            content_type(HashMap::new(), "site.webmanifest").await?
+        );
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn override_content_type() -> std::io::Result<()> {
+        let mut mime_types = HashMap::new();
+        mime_types
+            .insert("data".to_string(), "application/x-custom".to_string());
+        mime_types
+            .insert("wasm".to_string(), "application/octet-stream".to_string());
+        assert_eq!(
+            "application/x-custom",
+            content_type(mime_types.clone(), "model.data").await?
+        );
+        assert_eq!(
+            "application/octet-stream",
+            content_type(mime_types, "main.wasm").await?
+        );
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
+use std::path::PathBuf;
+
 use tokio::sync::oneshot;
 
 use log::info;
 
 use crate::{channels::ServerChannels, router, Error};
-use config::server::{ConnectionInfo, LaunchConfig, ServerConfig};
+use config::server::{ConnectionInfo, HostConfig, LaunchConfig, ServerConfig};
 
 /// Settings required to launch a single web server.
 #[derive(Debug)]
@@ -113,9 +115,61 @@ pub async fn run(configs: Vec<ServerConfig>) -> Result<(), Error> {
     Ok(start(servers).await?)
 }
 
+/// Serve a directory that has already been built.
+///
+/// The directory is served the same way as the build target of a
+/// project but it is never watched or rebuilt; the hosts in the
+/// configuration are replaced by a single host for the directory.
+pub async fn serve_static(
+    directory: PathBuf,
+    config: ServerConfig,
+    launch_config: LaunchConfig,
+) -> Result<(), Error> {
+    launch(static_config(directory, config), launch_config).await
+}
+
+/// Replace the hosts of a configuration with a host for a directory.
+fn static_config(directory: PathBuf, mut config: ServerConfig) -> ServerConfig {
+    let name = config
+        .hosts()
+        .first()
+        .map(|host| host.name().to_string())
+        .unwrap_or_else(|| config::HOST.to_string());
+    config.set_hosts(vec![HostConfig::new(name, directory)]);
+    config
+}
+
 /// Start a headless server with the given channels.
 pub async fn start(
     settings: impl Into<Vec<ServerSettings>>,
 ) -> Result<(), Error> {
+    let settings: Vec<ServerSettings> = settings.into();
+    for server in settings.iter() {
+        server.config.validate()?;
+    }
     Ok(router::serve(settings).await?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_directory_host() {
+        let directory = PathBuf::from("build/release");
+        let config = static_config(directory.clone(), Default::default());
+        assert_eq!(1, config.hosts().len());
+        assert_eq!(config::HOST, config.hosts()[0].name());
+        assert_eq!(&directory, config.hosts()[0].directory());
+
+        let mut config: ServerConfig = Default::default();
+        config.set_hosts(vec![
+            HostConfig::new("example.com".to_string(), "site".into()),
+            HostConfig::new("docs.example.com".to_string(), "docs".into()),
+        ]);
+        let config = static_config(directory.clone(), config);
+        assert_eq!(1, config.hosts().len());
+        assert_eq!("example.com", config.hosts()[0].name());
+        assert_eq!(&directory, config.hosts()[0].directory());
+    }
+}
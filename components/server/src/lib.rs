@@ -1,16 +1,21 @@
 mod channels;
+mod conditional;
+mod content_type;
 mod drop_privileges;
 mod error;
 mod launch;
+mod precompressed;
+mod proxy;
 mod reload_server;
 mod router;
+mod static_files;
 mod watch;
 mod websocket;
 
 pub use channels::*;
 pub use error::Error;
 pub use launch::*;
-pub use watch::watch;
+pub use watch::{serve, watch, BuildSummary};
 
 pub type ErrorCallback = fn(Error);
 type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,179 @@
+use std::path::{Component, Path, PathBuf};
+
+use actix_files::NamedFile;
+use actix_web::{
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, ContentEncoding, HeaderValue},
+        Method,
+    },
+};
+
+/// Encodings of pre-compressed sibling files in order of preference.
+const ENCODINGS: [(&str, &str, ContentEncoding); 2] = [
+    ("br", "br", ContentEncoding::Brotli),
+    ("gzip", "gz", ContentEncoding::Gzip),
+];
+
+/// Determine if an `Accept-Encoding` header value accepts an encoding.
+fn accepts(accept_encoding: &str, name: &str) -> bool {
+    accept_encoding.split(',').any(|item| {
+        let mut parts = item.split(';');
+        let coding = parts.next().unwrap_or("").trim();
+        let disabled = parts.any(|param| {
+            let param = param.trim().replace(' ', "");
+            param == "q=0" || param == "q=0.0" || param == "q=0.00"
+        });
+        (coding.eq_ignore_ascii_case(name) || coding == "*") && !disabled
+    })
+}
+
+/// Resolve a request path to a file within the root directory.
+pub(crate) fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    let mut file = root.to_path_buf();
+    for component in Path::new(path.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => file.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if path.ends_with('/') || file.is_dir() {
+        file.push(config::INDEX_HTML);
+    }
+    Some(file)
+}
+
+/// Find a pre-compressed sibling of the file for a request path
+/// using the first encoding supported by the client.
+pub(crate) fn negotiate(
+    root: &Path,
+    path: &str,
+    accept_encoding: &str,
+) -> Option<(PathBuf, PathBuf, ContentEncoding)> {
+    let file = resolve(root, path)?;
+    if !file.is_file() {
+        return None;
+    }
+    ENCODINGS
+        .iter()
+        .filter(|(name, _, _)| accepts(accept_encoding, name))
+        .find_map(|(_, extension, encoding)| {
+            let mut name = file.file_name()?.to_os_string();
+            name.push(format!(".{}", extension));
+            let compressed = file.with_file_name(name);
+            if compressed.is_file() {
+                Some((file.clone(), compressed, *encoding))
+            } else {
+                None
+            }
+        })
+}
+
+/// Serve a pre-compressed sibling file when one exists and the
+/// client supports the encoding; otherwise the request is returned
+/// so that it may be handled by the next service.
+pub(crate) fn serve(
+    root: &Path,
+    req: ServiceRequest,
+) -> std::result::Result<ServiceResponse, ServiceRequest> {
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return Err(req);
+    }
+
+    let accept_encoding = match req
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(value) => value.to_string(),
+        None => return Err(req),
+    };
+
+    let (file, compressed, encoding) =
+        match negotiate(root, req.path(), &accept_encoding) {
+            Some(result) => result,
+            None => return Err(req),
+        };
+
+    let named = match NamedFile::open(compressed) {
+        Ok(named) => named,
+        Err(_) => return Err(req),
+    };
+
+    let mime_type = mime_guess::from_path(file).first_or_octet_stream();
+    let (http_req, _payload) = req.into_parts();
+    let mut res = named
+        .set_content_type(mime_type)
+        .set_content_encoding(encoding)
+        .prefer_utf8(true)
+        .into_response(&http_req);
+    res.headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    Ok(ServiceResponse::new(http_req, res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_files::Files;
+    use actix_web::{
+        dev::Service,
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+    use futures::future::{ok, Either};
+    use std::fs;
+
+    #[test]
+    fn accept_encoding() {
+        assert!(accepts("gzip, deflate, br", "br"));
+        assert!(accepts("*", "gzip"));
+        assert!(!accepts("gzip;q=0, br", "gzip"));
+        assert!(!accepts("deflate", "br"));
+    }
+
+    #[actix_web::test]
+    async fn serve_brotli() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("main.js"), "console.log('plain');")?;
+        fs::write(dir.path().join("main.js.br"), "brotli")?;
+        fs::write(dir.path().join("main.js.gz"), "gzip")?;
+
+        let root = dir.path().to_path_buf();
+        let app = init_service(
+            App::new()
+                .wrap_fn(move |req, srv| match serve(&root, req) {
+                    Ok(res) => Either::Left(ok(res)),
+                    Err(req) => Either::Right(srv.call(req)),
+                })
+                .service(Files::new("/", dir.path().to_path_buf())),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/main.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip, br"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert!(res.status().is_success());
+        assert_eq!("br", res.headers().get(header::CONTENT_ENCODING).unwrap());
+        assert!(res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("application/javascript"));
+        let body = read_body(res).await;
+        assert_eq!(b"brotli", &body[..]);
+
+        let req = TestRequest::get().uri("/main.js").to_request();
+        let res = call_service(&app, req).await;
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+        let body = read_body(res).await;
+        assert_eq!(b"console.log('plain');", &body[..]);
+
+        Ok(())
+    }
+}
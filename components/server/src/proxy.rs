@@ -0,0 +1,159 @@
+use actix_web::{
+    http::header::{self, HeaderName},
+    web::{self, Data},
+    HttpRequest, HttpResponse, Scope,
+};
+use awc::Client;
+use log::warn;
+
+use config::server::ProxyConfig;
+
+/// Headers that only apply to a single connection so they
+/// are never forwarded.
+const HOP_BY_HOP: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+fn is_hop_by_hop(name: &HeaderName) -> bool {
+    HOP_BY_HOP
+        .iter()
+        .any(|h| name.as_str().eq_ignore_ascii_case(h))
+}
+
+/// Scope that forwards all requests for the proxy prefix
+/// to the backend server.
+///
+/// Requires an HTTP client in the application data.
+pub(crate) fn scope(proxy: &ProxyConfig) -> Scope {
+    web::scope(proxy.prefix())
+        .app_data(Data::new(proxy.clone()))
+        .default_service(web::to(forward))
+}
+
+/// Forward a request to the backend server streaming the
+/// request and response bodies.
+async fn forward(
+    req: HttpRequest,
+    payload: web::Payload,
+    proxy: Data<ProxyConfig>,
+    client: Data<Client>,
+) -> HttpResponse {
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| req.path());
+    let url = proxy.upstream(path_and_query);
+
+    let mut upstream = client.request_from(url.as_str(), req.head());
+    let headers = upstream.headers_mut();
+    headers.remove(header::HOST);
+    for name in HOP_BY_HOP.iter() {
+        headers.remove(*name);
+    }
+    let mut upstream = upstream.no_decompress();
+    if let Some(addr) = req.peer_addr() {
+        upstream = upstream.insert_header((
+            HeaderName::from_static("x-forwarded-for"),
+            addr.ip().to_string(),
+        ));
+    }
+
+    match upstream.send_stream(payload).await {
+        Ok(res) => {
+            let mut response = HttpResponse::build(res.status());
+            for (name, value) in res.headers().iter() {
+                if !is_hop_by_hop(name) {
+                    response.append_header((name.clone(), value.clone()));
+                }
+            }
+            response.streaming(res)
+        }
+        Err(e) => {
+            warn!("Proxy {} failed: {}", url, e);
+            HttpResponse::BadGateway()
+                .content_type("text/plain")
+                .body(format!("Could not connect to {}", proxy.target()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{body, http::StatusCode, test, App, HttpServer};
+    use std::net::TcpListener;
+    use url::Url;
+
+    fn app_proxy(port: u16) -> ProxyConfig {
+        let target: Url = format!("http://127.0.0.1:{}", port).parse().unwrap();
+        ProxyConfig::new("/api".to_string(), target)
+    }
+
+    #[actix_web::test]
+    async fn proxy_prefix() -> std::io::Result<()> {
+        let backend = HttpServer::new(|| {
+            App::new().route(
+                "/api/ping",
+                web::get().to(|req: HttpRequest| async move {
+                    HttpResponse::Ok()
+                        .insert_header(("x-backend", "stub"))
+                        .body(format!("pong {}", req.query_string()))
+                }),
+            )
+        })
+        .workers(1)
+        .disable_signals()
+        .bind("127.0.0.1:0")?;
+        let port = backend.addrs()[0].port();
+        let backend = backend.run();
+        let handle = backend.handle();
+        actix_web::rt::spawn(backend);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(Client::default()))
+                .service(scope(&app_proxy(port))),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/ping?id=1").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+        assert_eq!("stub", res.headers().get("x-backend").unwrap());
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!("pong id=1", body);
+
+        let req = test::TestRequest::get().uri("/api/missing").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+
+        handle.stop(false).await;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn proxy_bad_gateway() -> std::io::Result<()> {
+        // Bind and drop a listener to find a closed port
+        let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(Client::default()))
+                .service(scope(&app_proxy(port))),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/ping").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::BAD_GATEWAY, res.status());
+        Ok(())
+    }
+}
@@ -26,8 +26,19 @@ pub struct Message(pub String);
 #[rtype(usize)]
 pub struct Connect {
     pub addr: Recipient<Message>,
+    pub close: Recipient<Close>,
 }
 
+/// Close a session with a close frame
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Close;
+
+/// Close all sessions as the server is shutting down
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Shutdown;
+
 /// Session is disconnected
 #[derive(Message)]
 #[rtype(result = "()")]
@@ -68,6 +79,7 @@ pub struct Join {
 /// session. implementation is super primitive
 pub struct LiveReloadServer {
     sessions: HashMap<usize, Recipient<Message>>,
+    closers: HashMap<usize, Recipient<Close>>,
     rooms: HashMap<String, HashSet<usize>>,
     rng: ThreadRng,
     visitor_count: Arc<AtomicUsize>,
@@ -81,6 +93,7 @@ impl LiveReloadServer {
 
         LiveReloadServer {
             sessions: HashMap::new(),
+            closers: HashMap::new(),
             rooms,
             rng: rand::thread_rng(),
             visitor_count,
@@ -133,6 +146,7 @@ impl Handler<Connect> for LiveReloadServer {
         // register session with random id
         let id = self.rng.gen::<usize>();
         self.sessions.insert(id, msg.addr);
+        self.closers.insert(id, msg.close);
 
         // auto join session to Main room
         self.rooms
@@ -165,6 +179,7 @@ impl Handler<Disconnect> for LiveReloadServer {
         let mut rooms: Vec<String> = Vec::new();
 
         // remove address
+        self.closers.remove(&msg.id);
         if self.sessions.remove(&msg.id).is_some() {
             // remove session from all rooms
             for (name, sessions) in &mut self.rooms {
@@ -185,6 +200,22 @@ impl Handler<Disconnect> for LiveReloadServer {
     }
 }
 
+/// Handler for Shutdown message.
+impl Handler<Shutdown> for LiveReloadServer {
+    type Result = ();
+
+    fn handle(&mut self, _: Shutdown, _: &mut Context<Self>) {
+        debug!("Closing {} websocket client(s)", self.closers.len());
+        for (_, close) in self.closers.drain() {
+            let _ = close.do_send(Close);
+        }
+        self.sessions.clear();
+        for (_, sessions) in self.rooms.iter_mut() {
+            sessions.clear();
+        }
+    }
+}
+
 /// Handler for Message message.
 impl Handler<ClientMessage> for LiveReloadServer {
     type Result = ();
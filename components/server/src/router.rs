@@ -16,9 +16,8 @@ use futures::future::ok;
 use futures::Future;
 
 use actix::Actor;
-use actix_files::{Files, NamedFile};
 use actix_web::{
-    dev::{Service, ServiceRequest, ServiceResponse},
+    dev::{Service, ServiceResponse},
     error,
     guard::{self, Guard},
     http::{
@@ -26,9 +25,7 @@ use actix_web::{
         header::{self, HeaderValue},
         StatusCode,
     },
-    middleware::{
-        Compat, Condition, DefaultHeaders, Logger, 
-    },
+    middleware::{Compat, Condition, DefaultHeaders, Logger},
     web::{self, Data},
     App, HttpRequest, HttpResponse, HttpServer,
 };
@@ -41,8 +38,11 @@ use log::{error, info, warn};
 
 use crate::{
     channels::{Message, ResponseValue, ServerChannels},
+    conditional::Validators,
     drop_privileges::{drop_privileges, is_root},
+    proxy,
     reload_server::{self, LiveReloadServer},
+    static_files,
     websocket::ws_index,
     Error, Result, ServerSettings,
 };
@@ -52,6 +52,10 @@ use config::{
     server::{ConnectionInfo, PortType, ServerConfig},
 };
 
+/// Seconds to wait for in-flight requests to complete
+/// when the server is stopped gracefully.
+const SHUTDOWN_TIMEOUT: u64 = 5;
+
 /// Wrap the default index page in a specific type
 /// for the route handler.
 pub struct IndexPage(pub String);
@@ -93,13 +97,25 @@ async fn embedded_handler(
         req.path().trim_start_matches("/")
     };
 
-    if let Some(memfs_file) = memfs.get(memfs_path) {
+    if let Some((memfs_file, meta)) = memfs.open(memfs_path) {
+        let validators =
+            meta.etag().map(|etag| Validators::new(etag, meta.modified));
+        if let Some(ref validators) = validators {
+            if validators.is_fresh(&req) {
+                return validators.not_modified();
+            }
+        }
+
         let mime_type = mime_guess::from_path(memfs_path)
             .first()
             .unwrap_or(mime::TEXT_PLAIN);
-        HttpResponse::Ok()
+        let mut response = HttpResponse::Ok()
             .content_type(mime_type)
-            .body(memfs_file.into_owned())
+            .body(memfs_file.into_owned());
+        if let Some(validators) = validators {
+            validators.apply(response.headers_mut());
+        }
+        response
     } else {
         HttpResponse::NotFound()
             .content_type("text/html")
@@ -149,6 +165,8 @@ async fn start(
     let http_addr = opts.get_sock_addr(PortType::Insecure)?;
     let ssl_port = opts.ssl_port();
     let authorities = opts.authorities().clone();
+    let mime_types = Arc::new(opts.mime_types().clone());
+    let proxies = opts.proxy().clone();
 
     let mut virtual_hosts = Vec::new();
 
@@ -178,6 +196,10 @@ async fn start(
             host.load_redirects()?;
         }
 
+        if host.cache().is_none() {
+            host.load_cache()?;
+        }
+
         if host.require_index() {
             let index_page = host.directory().join(config::INDEX_HTML);
             let has_root_redirect =
@@ -186,8 +208,11 @@ async fn start(
                 } else {
                     false
                 };
-            if (!index_page.exists() || !index_page.is_file())
-                && !has_root_redirect
+            let has_index = match host.memory() {
+                Some(ref memfs) => memfs.contains(config::INDEX_HTML),
+                None => index_page.is_file(),
+            };
+            if !has_index && !has_root_redirect
             {
                 return Err(Error::NoIndexFile(
                     host.name().to_string(),
@@ -211,6 +236,10 @@ async fn start(
         virtual_hosts.push(virtual_host);
     }
 
+    for proxy in proxies.iter() {
+        info!("Proxy {} -> {}", proxy.prefix(), proxy.target());
+    }
+
     let registry = parser();
     let data = json!({
         "hosts": virtual_hosts,
@@ -218,6 +247,7 @@ async fn start(
 
     let app_state = Arc::new(AtomicUsize::new(0));
     let reload_server = LiveReloadServer::new(app_state.clone()).start();
+    let shutdown_reload_server = reload_server.clone();
 
     let broadcast_started = Arc::new(Mutex::new(false));
 
@@ -239,6 +269,14 @@ async fn start(
 
         let broadcast_start = Arc::clone(&broadcast_started);
 
+        // Forward proxied path prefixes before any virtual host
+        if !proxies.is_empty() {
+            app = app.app_data(Data::new(awc::Client::default()));
+            for proxy in proxies.iter() {
+                app = app.service(proxy::scope(proxy));
+            }
+        }
+
         //.wrap(Logger::default());
 
         for host in hosts.iter() {
@@ -247,7 +285,13 @@ async fn start(
             let log = host.log();
             let redirects =
                 host.redirects().clone().unwrap_or(Default::default());
-            let error_page = host.directory().join(config::ERROR_HTML);
+            let error_page = host.directory().join(host.error_page());
+            let memory_error_page = host.error_page().to_path_buf();
+            let memory = host.memory().clone();
+            let mime_types = Arc::clone(&mime_types);
+            let cache = Arc::new(host.cache().clone().unwrap_or_default());
+            let redirect_to_slash =
+                host.trailing_slash() != Some(config::TrailingSlash::Never);
 
             let endpoint = host.endpoint().clone();
             let watch = host.endpoint().is_some();
@@ -364,221 +408,205 @@ async fn start(
                     );
                 }
 
-                app = app.service(
-                    web::scope("")
-                        // Handle redirect mappings
-                        .wrap_fn(move |req, srv| {
-                            if let Some(uri) = redirects.items().get(req.path()) {
-                                let location = uri.to_string();
-
-                                let response: Pin<
-                                    Box<
-                                        dyn Future<
-                                            Output = std::result::Result<
-                                                ServiceResponse,
-                                                actix_web::Error,
-                                            >,
+                let scope = web::scope("")
+                    // Handle redirect mappings
+                    .wrap_fn(move |req, srv| {
+                        if let Some(uri) = redirects.items().get(req.path()) {
+                            let location = uri.to_string();
+
+                            let response: Pin<
+                                Box<
+                                    dyn Future<
+                                        Output = std::result::Result<
+                                            ServiceResponse,
+                                            actix_web::Error,
                                         >,
                                     >,
-                                > = Box::pin(async move {
-                                    let redirect = if temporary_redirect {
-                                        HttpResponse::TemporaryRedirect()
-                                            .append_header((
-                                                http::header::LOCATION,
-                                                location,
-                                            ))
-                                            .finish()
-                                    } else {
-                                        HttpResponse::PermanentRedirect()
-                                            .append_header((
-                                                http::header::LOCATION,
-                                                location,
-                                            ))
-                                            .finish()
-                                    };
-
-                                    Ok(req.into_response(redirect))
-                                });
-
-                                return response;
-                            }
-
-                            srv.call(req)
-                        })
-                        // Handle conditional headers
-                        .wrap_fn(move |req, srv| {
-                            let fut = srv.call(req);
-                            async move {
-                                let mut res = fut.await?;
-                                if disable_cache {
-                                    res.headers_mut().insert(
-                                        header::CACHE_CONTROL,
-                                        HeaderValue::from_static(
-                                            "no-cache, no-store, must-revalidate",
-                                        ),
-                                    );
-                                    res.headers_mut().insert(
-                                        header::PRAGMA,
-                                        HeaderValue::from_static("no-cache"),
-                                    );
-                                    res.headers_mut().insert(
-                                        header::EXPIRES,
-                                        HeaderValue::from_static("0"),
-                                    );
-                                }
-
-                                if deny_iframe {
-                                    res.headers_mut().insert(
-                                        header::X_FRAME_OPTIONS,
-                                        HeaderValue::from_static("DENY"),
-                                    );
-                                }
+                                >,
+                            > = Box::pin(async move {
+                                let redirect = if temporary_redirect {
+                                    HttpResponse::TemporaryRedirect()
+                                        .append_header((
+                                            http::header::LOCATION,
+                                            location,
+                                        ))
+                                        .finish()
+                                } else {
+                                    HttpResponse::PermanentRedirect()
+                                        .append_header((
+                                            http::header::LOCATION,
+                                            location,
+                                        ))
+                                        .finish()
+                                };
+
+                                Ok(req.into_response(redirect))
+                            });
+
+                            return response;
+                        }
 
-                                Ok(res)
+                        srv.call(req)
+                    })
+                    // Handle conditional headers
+                    .wrap_fn(move |req, srv| {
+                        let fut = srv.call(req);
+                        async move {
+                            let mut res = fut.await?;
+                            if disable_cache {
+                                res.headers_mut().insert(
+                                    header::CACHE_CONTROL,
+                                    HeaderValue::from_static(
+                                        "no-cache, must-revalidate",
+                                    ),
+                                );
+                                res.headers_mut().insert(
+                                    header::PRAGMA,
+                                    HeaderValue::from_static("no-cache"),
+                                );
+                                res.headers_mut().insert(
+                                    header::EXPIRES,
+                                    HeaderValue::from_static("0"),
+                                );
                             }
-                        })
 
-                        // Handle live rendering
-                        .wrap_fn(move |req, srv| {
+                            if deny_iframe {
+                                res.headers_mut().insert(
+                                    header::X_FRAME_OPTIONS,
+                                    HeaderValue::from_static("DENY"),
+                                );
+                            }
 
-                            let mut href = if req.path().ends_with("/")
-                                || req.path().ends_with(".html")
-                            {
-                                if req.path().ends_with("/") && req.path() != "/" {
-                                    Some(format!(
-                                        "{}{}",
-                                        req.path(),
-                                        config::INDEX_HTML
-                                    ))
-                                } else {
-                                    Some(req.path().to_string())
-                                }
+                            Ok(res)
+                        }
+                    })
+
+                    // Handle live rendering
+                    .wrap_fn(move |req, srv| {
+
+                        let mut href = if req.path().ends_with('/')
+                            || req.path().ends_with(".html")
+                        {
+                            if req.path().ends_with('/') && req.path() != "/" {
+                                Some(format!(
+                                    "{}{}",
+                                    req.path(),
+                                    config::INDEX_HTML
+                                ))
                             } else {
-                                None
-                            };
-
-                            let tx = Arc::clone(&live_render_tx);
-                            let fut = srv.call(req);
-                            async move {
-                                if let (Some(href), Some(ref tx)) =
-                                    (href.take(), &*tx)
-                                {
-                                    let (resp_tx, resp_rx) =
-                                        oneshot::channel::<ResponseValue>();
-
-                                    // TODO: handle RenderSendError
-                                    let _ = tx.send((href, resp_tx)).await;
-
-                                    // WARN: currently this will serve from a stale
-                                    // WARN: cache if the live render channel fails.
-                                    if let Ok(response) = resp_rx.await {
-                                        if let Some(error) = response {
-                                            let registry = parser();
-                                            let data = json!({
-                                                "title": "Render Error",
-                                                "message": error.to_string()});
-                                            let doc = registry
-                                                .render("error", &data)
-                                                .unwrap();
-
-                                            let res = HttpResponse::build(
-                                                StatusCode::INTERNAL_SERVER_ERROR,
-                                            )
-                                            .body(doc);
-                                            return Err(actix_web::Error::from(
-                                                error::InternalError::from_response(
-                                                    error, res,
-                                                ),
-                                            ));
-                                        }
-                                    }
-                                }
-
-                                Ok(fut.await?)
+                                Some(req.path().to_string())
                             }
-                        })
-                        // Always add these headers
-                        .wrap(
+                        } else {
+                            None
+                        };
+
+                        let tx = Arc::clone(&live_render_tx);
+                        let fut = srv.call(req);
+                        async move {
+                            if let (Some(href), Some(ref tx)) =
+                                (href.take(), &*tx)
                             {
-                                let mut headers = DefaultHeaders::new()
-                                    .add((
-                                        header::SERVER,
-                                        config::generator::user_agent(),
-                                    ))
-                                    .add((header::REFERRER_POLICY, "origin"))
-                                    .add((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
-                                    .add((header::X_XSS_PROTECTION, "1; mode=block"))
-
-                                    /*
-                                    .header(
-                                        header::STRICT_TRANSPORT_SECURITY,
-                                        "max-age=31536000; includeSubDomains; preload",
+                                let (resp_tx, resp_rx) =
+                                    oneshot::channel::<ResponseValue>();
+
+                                // TODO: handle RenderSendError
+                                let _ = tx.send((href, resp_tx)).await;
+
+                                // WARN: currently this will serve from a stale
+                                // WARN: cache if the live render channel fails.
+                                if let Ok(Some(error)) = resp_rx.await {
+                                    let registry = parser();
+                                    let data = json!({
+                                        "title": "Render Error",
+                                        "message": error.to_string()});
+                                    let doc = registry
+                                        .render("error", &data)
+                                        .unwrap();
+
+                                    let res = HttpResponse::build(
+                                        StatusCode::INTERNAL_SERVER_ERROR,
                                     )
-                                    */
-                                    // TODO: allow configuring this header
-                                    .add(("permissions-policy", "geolocation=()"));
-
-                                if watch {
-                                    headers = headers.add((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"));
+                                    .body(doc);
+                                    return Err(actix_web::Error::from(
+                                        error::InternalError::from_response(
+                                            error, res,
+                                        ),
+                                    ));
                                 }
+                            }
 
-                                headers
+                            fut.await
+                        }
+                    })
+                    // Always add these headers
+                    .wrap(
+                        {
+                            let mut headers = DefaultHeaders::new()
+                                .add((
+                                    header::SERVER,
+                                    config::generator::user_agent(),
+                                ))
+                                .add((header::REFERRER_POLICY, "origin"))
+                                .add((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+                                .add((header::X_XSS_PROTECTION, "1; mode=block"))
+
+                                /*
+                                .header(
+                                    header::STRICT_TRANSPORT_SECURITY,
+                                    "max-age=31536000; includeSubDomains; preload",
+                                )
+                                */
+                                // TODO: allow configuring this header
+                                .add(("permissions-policy", "geolocation=()"));
+
+                            if watch {
+                                headers = headers.add((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"));
                             }
-                        )
 
-                        // Check virtual hosts
-                        .guard(guard::fn_guard(move |req| {
-                            for g in host_guards.iter() {
-                                if g.check(req) {
-                                    return true;
-                                }
+                            headers
+                        }
+                    )
+
+                    // Check virtual hosts
+                    .guard(guard::fn_guard(move |req| {
+                        for g in host_guards.iter() {
+                            if g.check(req) {
+                                return true;
                             }
-                            false
-                        }))
-                        .wrap(Condition::new(log, Compat::new(Logger::default())))
-                        // Serve static files
-                        .service(
-                            Files::new("/", host.directory().to_path_buf())
-                                .default_handler(move |req: ServiceRequest| {
-                                    let err = error_page.clone();
-                                    let (http_req, _payload) = req.into_parts();
-                                    async {
-                                        let response = if err.exists() {
-                                            match NamedFile::open(err) {
-                                                Ok(file) => {
-                                                    let file = file.set_status_code(StatusCode::NOT_FOUND);
-                                                    file.into_response(&http_req)
-                                                }
-                                                Err(e) => {
-                                                    return Err(
-                                                        actix_web::Error::from(e),
-                                                    )
-                                                }
-                                            }
-                                        } else {
-                                            // TODO: pretty not found when no 404.html for the host?
-                                            HttpResponse::NotFound()
-                                                .content_type("text/html")
-                                                .body("NOT_FOUND")
-                                        };
-
-                                        Ok(ServiceResponse::new(http_req, response))
-                                    }
-                                })
-                                .prefer_utf8(true)
-                                .index_file(config::INDEX_HTML)
-                                .use_etag(!host.disable_cache())
-                                .use_last_modified(!host.disable_cache())
-                                .redirect_to_slash_directory(),
-                        ),
-                );
+                        }
+                        false
+                    }))
+                    .wrap(Condition::new(log, Compat::new(Logger::default())));
+
+                // Serve static files
+                let scope = if let Some(memfs) = memory {
+                    scope.service(static_files::memory(
+                        memfs,
+                        &memory_error_page,
+                        mime_types,
+                        cache,
+                        !host.disable_cache(),
+                        redirect_to_slash,
+                    ))
+                } else {
+                    scope.service(static_files::service(
+                        host.directory(),
+                        error_page,
+                        mime_types,
+                        cache,
+                        !host.disable_cache(),
+                        redirect_to_slash,
+                    ))
+                };
+                app = app.service(scope);
 
             }
         }
 
         app.default_service(web::get().to(default_route))
     })
-    .workers(opts.workers());
+    .workers(opts.workers())
+    .shutdown_timeout(SHUTDOWN_TIMEOUT);
 
     let (mut server, mut redirect_server) = if let Some(ref ssl_config) =
         ssl_config
@@ -647,6 +675,7 @@ async fn start(
             })
             .disable_signals()
             .workers(opts.workers())
+            .shutdown_timeout(SHUTDOWN_TIMEOUT)
             .bind(http_addr)?;
 
             Some(redirect_server)
@@ -705,6 +734,9 @@ async fn start(
             rt.block_on(async move {
                 match shutdown.await {
                     Ok(graceful) => {
+                        // Close websockets so they do not keep
+                        // the server waiting for connections to drain
+                        shutdown_reload_server.do_send(reload_server::Shutdown);
                         shutdown_redirect_server.stop(graceful).await;
                         shutdown_server.stop(graceful).await;
                     }
@@ -726,6 +758,9 @@ async fn start(
             rt.block_on(async move {
                 match shutdown.await {
                     Ok(graceful) => {
+                        // Close websockets so they do not keep
+                        // the server waiting for connections to drain
+                        shutdown_reload_server.do_send(reload_server::Shutdown);
                         handle.stop(graceful).await;
                     }
                     _ => {}
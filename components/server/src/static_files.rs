@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::future::{ok, Either};
+
+use actix_files::{Files, NamedFile};
+use actix_web::{
+    dev::{HttpServiceFactory, Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, HttpRequest, HttpResponse,
+};
+
+use config::{
+    cache::CacheManifest,
+    memfs::{DynamicMemoryFileSystem, MemoryFileSystem},
+    server::mime_type,
+};
+
+use crate::{conditional, content_type, precompressed};
+
+/// Set the `Cache-Control` header of a response when the file for
+/// the request has a directive in the cache manifest.
+fn cache_control(
+    root: &Path,
+    cache: &CacheManifest,
+    res: &mut ServiceResponse,
+) {
+    let status = res.status();
+    if cache.is_empty()
+        || !(status.is_success() || status == StatusCode::NOT_MODIFIED)
+    {
+        return;
+    }
+    let value = precompressed::resolve(root, res.request().path())
+        .and_then(|file| {
+            file.strip_prefix(root)
+                .ok()
+                .map(utils::url::to_href_separator)
+        })
+        .and_then(|path| cache.get(&path).map(|d| d.to_string()))
+        .and_then(|directive| HeaderValue::from_str(&directive).ok());
+    if let Some(value) = value {
+        res.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+}
+
+/// Service for the files in a build directory.
+///
+/// Pre-compressed files are served when accepted, conditional
+/// requests are answered, directories resolve to the index page,
+/// cache directives are applied and missing files respond with
+/// the error page.
+///
+/// Directory URLs without a trailing slash redirect to the URL with
+/// a slash unless `redirect_to_slash` is false, then the index page
+/// is served for both.
+pub(crate) fn service(
+    directory: &Path,
+    error_page: PathBuf,
+    mime_types: Arc<HashMap<String, String>>,
+    cache: Arc<CacheManifest>,
+    use_cache: bool,
+    redirect_to_slash: bool,
+) -> impl HttpServiceFactory + 'static {
+    let compressed_root = directory.to_path_buf();
+    let conditional_root = directory.to_path_buf();
+    let cache_root = directory.to_path_buf();
+
+    let files = Files::new("/", directory.to_path_buf())
+        .default_handler(move |req: ServiceRequest| {
+            let err = error_page.clone();
+            let (http_req, _payload) = req.into_parts();
+            async {
+                let response = if err.exists() {
+                    match NamedFile::open(err) {
+                        Ok(file) => {
+                            let file =
+                                file.set_status_code(StatusCode::NOT_FOUND);
+                            file.into_response(&http_req)
+                        }
+                        Err(e) => return Err(actix_web::Error::from(e)),
+                    }
+                } else {
+                    // TODO: pretty not found when no 404.html for the host?
+                    HttpResponse::NotFound()
+                        .content_type("text/html")
+                        .body("NOT_FOUND")
+                };
+
+                Ok(ServiceResponse::new(http_req, response))
+            }
+        })
+        .prefer_utf8(true)
+        .index_file(config::INDEX_HTML)
+        .use_etag(use_cache)
+        .use_last_modified(use_cache);
+    let files = if redirect_to_slash {
+        files.redirect_to_slash_directory()
+    } else {
+        files
+    };
+
+    web::scope("")
+        // Serve pre-compressed files when accepted
+        .wrap_fn(move |req, srv| {
+            match precompressed::serve(&compressed_root, req) {
+                Ok(res) => Either::Left(ok(res)),
+                Err(req) => Either::Right(srv.call(req)),
+            }
+        })
+        // Respond to conditional requests for files
+        .wrap_fn(move |req, srv| {
+            match conditional::check(&conditional_root, req) {
+                Ok(res) => Either::Left(ok(res)),
+                Err((req, validators)) => {
+                    let fut = srv.call(req);
+                    Either::Right(async move {
+                        let mut res = fut.await?;
+                        conditional::apply(&mut res, validators);
+                        Ok::<_, actix_web::Error>(res)
+                    })
+                }
+            }
+        })
+        // Set content types for configured file extensions
+        .wrap_fn(move |req, srv| {
+            let mime_types = Arc::clone(&mime_types);
+            let fut = srv.call(req);
+            async move {
+                let mut res = fut.await?;
+                content_type::apply(&mime_types, &mut res);
+                Ok::<_, actix_web::Error>(res)
+            }
+        })
+        // Apply cache directives declared for files
+        .wrap_fn(move |req, srv| {
+            let root = cache_root.clone();
+            let cache = Arc::clone(&cache);
+            let fut = srv.call(req);
+            async move {
+                let mut res = fut.await?;
+                cache_control(&root, &cache, &mut res);
+                Ok::<_, actix_web::Error>(res)
+            }
+        })
+        .service(files)
+}
+
+/// Settings for the service of an in-memory build.
+struct MemoryFiles {
+    memfs: DynamicMemoryFileSystem,
+    error_page: String,
+    mime_types: Arc<HashMap<String, String>>,
+    cache: Arc<CacheManifest>,
+    use_cache: bool,
+    redirect_to_slash: bool,
+}
+
+impl MemoryFiles {
+    /// Resolve the file for a request path, directories resolve
+    /// to the index page.
+    fn resolve(&self, path: &str) -> std::result::Result<String, String> {
+        let path = path.trim_start_matches('/');
+        if path.is_empty() || path.ends_with('/') {
+            return Ok(format!("{}{}", path, config::INDEX_HTML));
+        }
+        let index = format!("{}/{}", path, config::INDEX_HTML);
+        if !self.memfs.contains(path) && self.memfs.contains(&index) {
+            if self.redirect_to_slash {
+                return Err(format!("/{}/", path));
+            }
+            return Ok(index);
+        }
+        Ok(path.to_string())
+    }
+
+    fn not_found(&self) -> HttpResponse {
+        match self.memfs.get(&self.error_page) {
+            Some(content) => HttpResponse::NotFound()
+                .content_type("text/html")
+                .body(content.into_owned()),
+            None => HttpResponse::NotFound()
+                .content_type("text/html")
+                .body("NOT_FOUND"),
+        }
+    }
+}
+
+async fn memory_handler(
+    req: HttpRequest,
+    files: web::Data<MemoryFiles>,
+) -> HttpResponse {
+    let file = match files.resolve(req.path()) {
+        Ok(file) => file,
+        Err(location) => {
+            return HttpResponse::Found()
+                .insert_header((header::LOCATION, location))
+                .finish()
+        }
+    };
+
+    let (content, meta) = match files.memfs.open(&file) {
+        Some(result) => result,
+        None => return files.not_found(),
+    };
+
+    let validators = if files.use_cache {
+        meta.etag()
+            .map(|etag| conditional::Validators::new(etag, meta.modified))
+    } else {
+        None
+    };
+    if let Some(ref validators) = validators {
+        if validators.is_fresh(&req) {
+            return validators.not_modified();
+        }
+    }
+
+    let mime = mime_type(&files.mime_types, &file)
+        .map(|mime| mime.to_string())
+        .unwrap_or_else(|| {
+            mime_guess::from_path(&file)
+                .first_or_text_plain()
+                .to_string()
+        });
+    let mut response = HttpResponse::Ok()
+        .content_type(mime)
+        .body(content.into_owned());
+    if let Some(validators) = validators {
+        validators.apply(response.headers_mut());
+    }
+    let directive = files
+        .cache
+        .get(&file)
+        .and_then(|directive| HeaderValue::from_str(directive).ok());
+    if let Some(directive) = directive {
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, directive);
+    }
+    response
+}
+
+/// Service for the files of a build written to memory.
+///
+/// Behaves like the service for a build directory except that
+/// pre-compressed files are not served.
+pub(crate) fn memory(
+    memfs: DynamicMemoryFileSystem,
+    error_page: &Path,
+    mime_types: Arc<HashMap<String, String>>,
+    cache: Arc<CacheManifest>,
+    use_cache: bool,
+    redirect_to_slash: bool,
+) -> impl HttpServiceFactory + 'static {
+    let files = MemoryFiles {
+        memfs,
+        error_page: utils::url::to_href_separator(error_page),
+        mime_types,
+        cache,
+        use_cache,
+        redirect_to_slash,
+    };
+    web::resource("/{tail:.*}")
+        .app_data(web::Data::new(files))
+        .to(memory_handler)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{body, test, App};
+    use std::fs;
+
+    #[actix_web::test]
+    async fn serve_directory() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir(dir.path().join("docs"))?;
+        fs::write(dir.path().join("index.html"), "<p>Home</p>")?;
+        fs::write(dir.path().join("docs").join("index.html"), "<p>Docs</p>")?;
+        fs::write(dir.path().join(config::ERROR_HTML), "<p>Missing</p>")?;
+        fs::write(dir.path().join("main.js"), "console.log('plain');")?;
+        fs::write(dir.path().join("main.js.gz"), "gzip")?;
+        fs::write(
+            dir.path().join(config::cache::CACHE_FILE),
+            r#"{"docs/index.html": "no-store"}"#,
+        )?;
+
+        let app = test::init_service(App::new().service(service(
+            dir.path(),
+            dir.path().join(config::ERROR_HTML),
+            Default::default(),
+            Arc::new(CacheManifest::load(dir.path()).unwrap()),
+            true,
+            true,
+        )))
+        .await;
+
+        let body = |res: ServiceResponse| async move {
+            let bytes = body::to_bytes(res.into_body()).await.unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::OK, res.status());
+        assert!(res.headers().get(header::CACHE_CONTROL).is_none());
+        assert_eq!("<p>Home</p>", body(res).await);
+
+        let req = test::TestRequest::get().uri("/docs/").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(
+            "no-store",
+            res.headers().get(header::CACHE_CONTROL).unwrap()
+        );
+        assert_eq!("<p>Docs</p>", body(res).await);
+
+        let req = test::TestRequest::get().uri("/docs").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_redirection());
+        assert_eq!(
+            Some(&HeaderValue::from_static("/docs/")),
+            res.headers().get(header::LOCATION)
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/main.js")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(
+            "gzip",
+            res.headers().get(header::CONTENT_ENCODING).unwrap()
+        );
+
+        let req = test::TestRequest::get().uri("/missing/").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+        assert_eq!("<p>Missing</p>", body(res).await);
+
+        // Serve the index page for directories without a trailing slash
+        let app = test::init_service(App::new().service(service(
+            dir.path(),
+            dir.path().join(config::ERROR_HTML),
+            Default::default(),
+            Default::default(),
+            true,
+            false,
+        )))
+        .await;
+        let req = test::TestRequest::get().uri("/docs").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!("<p>Docs</p>", body(res).await);
+        Ok(())
+    }
+    #[actix_web::test]
+    async fn serve_memory() -> std::io::Result<()> {
+        let memfs = DynamicMemoryFileSystem::new();
+        memfs.insert("index.html", b"<p>Home</p>".to_vec());
+        memfs.insert("docs/index.html", b"<p>Docs</p>".to_vec());
+        memfs.insert("main.css", b"p{}".to_vec());
+        memfs.insert(config::ERROR_HTML, b"<p>Missing</p>".to_vec());
+        let mut cache: CacheManifest = Default::default();
+        cache.insert("main.css".to_string(), "no-store".to_string());
+
+        let app = test::init_service(App::new().service(memory(
+            memfs.clone(),
+            Path::new(config::ERROR_HTML),
+            Default::default(),
+            Arc::new(cache),
+            true,
+            true,
+        )))
+        .await;
+
+        let body = |res: ServiceResponse| async move {
+            let bytes = body::to_bytes(res.into_body()).await.unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(
+            "text/html",
+            res.headers().get(header::CONTENT_TYPE).unwrap()
+        );
+        assert_eq!("<p>Home</p>", body(res).await);
+
+        let req = test::TestRequest::get().uri("/docs").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_redirection());
+        assert_eq!(
+            Some(&HeaderValue::from_static("/docs/")),
+            res.headers().get(header::LOCATION)
+        );
+
+        let req = test::TestRequest::get().uri("/main.css").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(
+            "no-store",
+            res.headers().get(header::CACHE_CONTROL).unwrap()
+        );
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        let req = test::TestRequest::get()
+            .uri("/main.css")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::NOT_MODIFIED, res.status());
+
+        let req = test::TestRequest::get().uri("/missing").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+        assert_eq!("<p>Missing</p>", body(res).await);
+
+        // Content written after the service is mounted is served
+        memfs.insert("about.html", b"<p>About</p>".to_vec());
+        let req = test::TestRequest::get().uri("/about.html").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!("<p>About</p>", body(res).await);
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 use std::collections::HashSet;
 use std::convert::TryInto;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
 
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 
 use futures_util::FutureExt;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
@@ -20,6 +21,7 @@ use config::server::{
     ConnectionInfo, HostConfig, ServerConfig, SslConfig, 
 };
 
+use config::ProfileSettings;
 use workspace::{CompileResult, HostInfo, HostResult, Invalidator};
 
 use crate::{
@@ -27,6 +29,60 @@ use crate::{
     Error, ErrorCallback, Result, ServerSettings,
 };
 
+/// Summary of a build passed to the callback for an embedded server.
+#[derive(Debug, Clone, Default)]
+pub struct BuildSummary {
+    /// Name of the virtual host that was built.
+    pub host: String,
+    /// Whether this is the initial build.
+    pub initial: bool,
+    /// Hrefs of the pages that changed.
+    pub changed: Vec<String>,
+    /// Error message when the build failed.
+    pub error: Option<String>,
+    /// Durations for the build phases when timings are enabled.
+    pub timings: Vec<(String, Duration)>,
+}
+
+type RebuildCallback = Arc<dyn Fn(&BuildSummary) + Send + Sync>;
+
+/// Build a project, serve it and watch for changes calling
+/// `on_rebuild` after the initial build and after every rebuild.
+///
+/// The server runs headless until a message is sent on the
+/// `shutdown` channel; the value indicates a graceful shutdown.
+pub async fn serve<P, F>(
+    project: P,
+    mut args: ProfileSettings,
+    shutdown: oneshot::Receiver<bool>,
+    on_rebuild: F,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+    F: Fn(&BuildSummary) + Send + Sync + 'static,
+{
+    let port = args.get_port();
+    args.live = Some(true);
+
+    let result =
+        workspace::compile(project, &args, Default::default(), true).await?;
+
+    let callback: RebuildCallback = Arc::new(on_rebuild);
+    listen(
+        args.host.clone(),
+        port,
+        args.tls.clone(),
+        None,
+        true,
+        result,
+        None,
+        |e| error!("{}", e),
+        shutdown,
+        Some(callback),
+    )
+    .await
+}
+
 /// Start watching for file system notifications in the source
 /// directories for the given compiler results.
 pub async fn watch(
@@ -38,10 +94,71 @@ pub async fn watch(
     result: CompileResult,
     authorities: Option<Vec<String>>,
     error_cb: ErrorCallback,
+) -> Result<()> {
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<bool>();
+
+    // Signals are disabled for the web server so we stop
+    // the watchers and the web server gracefully
+    tokio::task::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutting down...");
+        let _ = shutdown_tx.send(true);
+    });
+
+    self::listen(
+        listen,
+        port,
+        tls,
+        launch,
+        headless,
+        result,
+        authorities,
+        error_cb,
+        shutdown_rx,
+        None,
+    )
+    .await
+}
+
+/// Wait for an interrupt or terminate signal.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+async fn listen(
+    listen: Option<String>,
+    port: u16,
+    tls: Option<SslConfig>,
+    launch: Option<String>,
+    headless: bool,
+    result: CompileResult,
+    authorities: Option<Vec<String>>,
+    error_cb: ErrorCallback,
+    shutdown: oneshot::Receiver<bool>,
+    on_rebuild: Option<RebuildCallback>,
 ) -> Result<()> {
     // Create a channel to receive the bind address.
     let (bind_tx, bind_rx) = oneshot::channel::<ConnectionInfo>();
-    let (_shutdown_tx, shutdown_rx) = oneshot::channel::<bool>();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<bool>();
+    let (stop_tx, _) = broadcast::channel::<()>(1);
 
     let host_result: HostResult = result.into();
     let mut host_configs: Vec<(HostInfo, HostConfig)> =
@@ -75,6 +192,17 @@ pub async fn watch(
 
     create_resources(&host_info)?;
 
+    if let Some(ref cb) = on_rebuild {
+        for info in host_info.iter() {
+            cb(&BuildSummary {
+                host: info.name.clone(),
+                initial: true,
+                timings: info.project.timings().phases(),
+                ..Default::default()
+            });
+        }
+    }
+
     let channel_names = hosts
         .iter()
         .map(|h| h.name().to_string())
@@ -89,8 +217,16 @@ pub async fn watch(
         tls,
     );
 
+    // Proxies are only supported by the development server
+    let proxy = host_info
+        .iter()
+        .filter_map(|info| info.project.options.settings.proxy.clone())
+        .flatten()
+        .collect::<Vec<_>>();
+
     opts.set_authorities(authorities);
     opts.set_hosts(hosts);
+    opts.set_proxy(proxy);
     opts.set_disable_signals(true);
 
     // WARN: If we set up more workers currently this will result in websocket
@@ -105,6 +241,9 @@ pub async fn watch(
     let (worker_tx, worker_rx) = oneshot::channel::<ConnectionInfo>();
     let connection_rx = Arc::new(Mutex::new(worker_rx));
 
+    // All the projects share the build events from the arguments
+    let events = host_info[0].project.options.settings.events.clone();
+
     // Set up a worker to pass on connection info to a supervisor process.
     //
     // This should only be required when the UI editor is running which needs
@@ -129,6 +268,25 @@ pub async fn watch(
                         _ => {}
                     }
                 }
+
+                // Forward build events to the supervisor, subscribers
+                // receive on a blocking channel so bridge it from
+                // another thread
+                let (tx, mut build_rx) = mpsc::unbounded_channel();
+                let receiver = events.subscribe();
+                std::thread::spawn(move || {
+                    while let Ok(event) = receiver.recv() {
+                        if tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                });
+                while let Some(event) = build_rx.recv().await {
+                    let params =
+                        serde_json::to_value(&event).map_err(Box::from)?;
+                    let req = notify("build", Some(params));
+                    write(&mut writer, &req).await?;
+                }
                 Ok::<(), psup_impl::Error>(())
             })
             .relaxed(true);
@@ -150,8 +308,18 @@ pub async fn watch(
         Arc::new(RwLock::new(watch_channels)),
         watcher_tx,
         error_cb,
+        &stop_tx,
+        on_rebuild,
     );
 
+    // Stop the watchers and the web server on shutdown
+    tokio::task::spawn(async move {
+        if let Ok(graceful) = shutdown.await {
+            let _ = stop_tx.send(());
+            let _ = shutdown_tx.send(graceful);
+        }
+    });
+
     // Must wait for all the watchers to set up channels before starting the web server
     while let Some(_) = watcher_rx.recv().await {
         watchers_started += 1;
@@ -178,7 +346,12 @@ fn create_resources(hosts: &Vec<HostInfo>) -> Result<()> {
         // Write out the livereload javascript using the correct
         // websocket endpoint which the server will create later
         if let Some(ref endpoint) = host.endpoint {
-            livereload::write(&host.project.config, &host.target, endpoint)?;
+            for (dest, content) in livereload::files(&host.target, endpoint) {
+                host.project
+                    .output()
+                    .write(&dest, content.as_bytes())
+                    .map_err(workspace::Error::from)?;
+            }
         }
         Ok::<(), Error>(())
     })?;
@@ -279,9 +452,13 @@ fn spawn_monitor(
     channels: Arc<RwLock<WatchChannels>>,
     watcher_tx: mpsc::Sender<bool>,
     error_cb: ErrorCallback,
+    stop_tx: &broadcast::Sender<()>,
+    on_rebuild: Option<RebuildCallback>,
 ) {
     for w in watchers {
         let watch_channels = Arc::clone(&channels);
+        let mut stop_rx = stop_tx.subscribe();
+        let on_rebuild = on_rebuild.clone();
 
         let started_tx = watcher_tx.clone();
 
@@ -311,6 +488,15 @@ fn spawn_monitor(
                 watcher.watch(&w.source, RecursiveMode::Recursive)
                     .expect("Failed to start watching");
 
+                // Included config files may be outside the source
+                for include in w.project.config.includes() {
+                    if !include.starts_with(&source) {
+                        if let Err(e) = watcher.watch(include, RecursiveMode::NonRecursive) {
+                            warn!("Failed to watch {} ({})", include.display(), e);
+                        }
+                    }
+                }
+
                 info!("Watch {} in {}", name, source.display());
 
                 let mut invalidator = Invalidator::new(w.project);
@@ -328,6 +514,7 @@ fn spawn_monitor(
 
                 loop {
                     tokio::select! {
+                        _ = stop_rx.recv() => break,
                         val = request.recv() => {
                             if let Some((path, resp_tx)) = val {
                                 let updater = invalidator.updater_mut();
@@ -403,6 +590,10 @@ fn spawn_monitor(
                                                 None
                                             };
 
+                                            let changed = invalidator.find_changed_hrefs(&invalidation);
+
+                                            invalidator.updater_mut().timings().reset();
+
                                             match invalidator
                                                 .updater_mut()
                                                 .invalidate(&invalidation)
@@ -410,8 +601,19 @@ fn spawn_monitor(
                                             {
                                                 // Notify of build completed
                                                 Ok(_) => {
+                                                    if let Some(ref cb) = on_rebuild {
+                                                        cb(&BuildSummary {
+                                                            host: name.clone(),
+                                                            changed: changed.clone(),
+                                                            timings: invalidator
+                                                                .updater_mut()
+                                                                .timings()
+                                                                .phases(),
+                                                            ..Default::default()
+                                                        });
+                                                    }
                                                     let msg =
-                                                        livereload::messages::reload(href);
+                                                        livereload::messages::reload(href, changed);
                                                     let txt = serde_json::to_string(&msg)
                                                         .unwrap();
                                                     let _ = ws_tx.send(Message::Text(txt));
@@ -420,6 +622,13 @@ fn spawn_monitor(
                                                 // Send errors to the websocket
                                                 Err(e) => {
                                                     error!("{}", e);
+                                                    if let Some(ref cb) = on_rebuild {
+                                                        cb(&BuildSummary {
+                                                            host: name.clone(),
+                                                            error: Some(e.to_string()),
+                                                            ..Default::default()
+                                                        });
+                                                    }
 
                                                     let msg = livereload::messages::notify(
                                                         e.to_string(),
@@ -445,3 +654,77 @@ fn spawn_monitor(
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{test_util::TempProject, ProfileName};
+    use std::sync::Mutex as StdMutex;
+
+    fn project() -> Result<TempProject> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project.write("site/layouts/main.hbs", "<html><body></body></html>")?;
+        Ok(project)
+    }
+
+    #[actix_web::test]
+    async fn serve_initial_build() -> Result<()> {
+        // Serving as root drops privileges to the sudo user, which exits
+        if crate::drop_privileges::is_root() {
+            return Ok(());
+        }
+
+        let project = project()?;
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.port = Some(0);
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<bool>();
+        let shutdown_tx = StdMutex::new(Some(shutdown_tx));
+        let builds = Arc::new(StdMutex::new(Vec::new()));
+        let summaries = Arc::clone(&builds);
+
+        serve(project.root(), args, shutdown_rx, move |summary| {
+            summaries.lock().unwrap().push(summary.clone());
+            if let Some(tx) = shutdown_tx.lock().unwrap().take() {
+                let _ = tx.send(false);
+            }
+        })
+        .await?;
+
+        let builds = builds.lock().unwrap();
+        assert_eq!(1, builds.len());
+        assert!(builds[0].initial);
+        assert!(builds[0].error.is_none());
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn graceful_shutdown_releases_port() -> Result<()> {
+        if crate::drop_privileges::is_root() {
+            return Ok(());
+        }
+
+        let port = std::net::TcpListener::bind((config::ADDR, 0))?
+            .local_addr()?
+            .port();
+
+        let project = project()?;
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.port = Some(port);
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<bool>();
+        let shutdown_tx = StdMutex::new(Some(shutdown_tx));
+        let server = serve(project.root(), args, shutdown_rx, move |_| {
+            if let Some(tx) = shutdown_tx.lock().unwrap().take() {
+                let _ = tx.send(true);
+            }
+        });
+        tokio::time::timeout(Duration::from_secs(30), server)
+            .await
+            .expect("graceful shutdown should complete")?;
+
+        // Port is available as soon as the server has stopped
+        std::net::TcpListener::bind((config::ADDR, port))?;
+        Ok(())
+    }
+}
@@ -44,6 +44,15 @@ impl Handler<reload_server::Message> for ClientSocket {
     }
 }
 
+/// Send a close frame to the peer when the server is going away
+impl Handler<reload_server::Close> for ClientSocket {
+    type Result = ();
+    fn handle(&mut self, _: reload_server::Close, ctx: &mut Self::Context) {
+        ctx.close(Some(ws::CloseCode::Away.into()));
+        ctx.stop();
+    }
+}
+
 impl Actor for ClientSocket {
     type Context = ws::WebsocketContext<Self>;
 
@@ -59,7 +68,8 @@ impl Actor for ClientSocket {
         let addr = ctx.address();
         self.addr
             .send(reload_server::Connect {
-                addr: addr.recipient(),
+                addr: addr.clone().recipient(),
+                close: addr.recipient(),
             })
             .into_actor(self)
             .then(|res, act, ctx| {
@@ -0,0 +1,246 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use log::warn;
+use sha3::{Digest, Sha3_256};
+
+use crate::Result;
+
+/// Version of the syntect dependency, keep in step with the
+/// manifest so upgrades do not serve stale HTML.
+const SYNTECT_VERSION: &str = "4.5";
+
+/// Version of the highlighted HTML from the syntect and
+/// application versions.
+fn version() -> String {
+    let app = config::generator::try_get()
+        .map(|app| app.version.as_str())
+        .unwrap_or_default();
+    format!("{}+{}", SYNTECT_VERSION, app)
+}
+
+/// Bounded cache of highlighted HTML keyed by the content
+/// hash of the code and the highlight settings.
+///
+/// When the cache is full the oldest entries are evicted first.
+#[derive(Debug, Default)]
+pub struct HighlightCache {
+    capacity: usize,
+    entries: HashMap<String, String>,
+    order: VecDeque<String>,
+    hits: usize,
+    misses: usize,
+}
+
+impl HighlightCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Key for a code block; the theme is only used for inline
+    /// styles so changing the theme invalidates those entries.
+    ///
+    /// Keys include the version so entries persisted by another
+    /// version are never used.
+    pub fn key(language: &str, theme: Option<&str>, code: &str) -> String {
+        let (mode, theme) = match theme {
+            Some(theme) => ("inline", theme),
+            None => ("class", ""),
+        };
+        let hash = Sha3_256::digest(code.as_bytes());
+        format!(
+            "{}:{}:{}:{}:{}",
+            version(),
+            mode,
+            theme,
+            language,
+            hex::encode(hash)
+        )
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the cached HTML for a key and record the hit or miss.
+    pub fn get(&mut self, key: &str) -> Option<String> {
+        match self.entries.get(key) {
+            Some(html) => {
+                self.hits += 1;
+                Some(html.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: String, html: String) {
+        if self.capacity == 0 || self.entries.contains_key(&key) {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        self.order.push_back(key.clone());
+        self.entries.insert(key, html);
+    }
+
+    /// Load entries from a file written by `save()`, a missing
+    /// file is not an error and a corrupt file is discarded.
+    pub fn load<P: AsRef<Path>>(&mut self, file: P) -> Result<()> {
+        let file = file.as_ref();
+        if !file.exists() {
+            return Ok(());
+        }
+        let reader = BufReader::new(File::open(file)?);
+        let entries: Vec<(String, String)> =
+            match serde_json::from_reader(reader) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("Discard syntax cache {} ({})", file.display(), e);
+                    fs::remove_file(file)?;
+                    return Ok(());
+                }
+            };
+        for (key, html) in entries {
+            self.insert(key, html);
+        }
+        Ok(())
+    }
+
+    /// Save the entries in the order they were inserted.
+    pub fn save<P: AsRef<Path>>(&self, file: P) -> Result<()> {
+        let file = file.as_ref();
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entries = self
+            .order
+            .iter()
+            .filter_map(|key| self.entries.get(key).map(|html| (key, html)))
+            .collect::<Vec<_>>();
+        let writer = BufWriter::new(File::create(file)?);
+        serde_json::to_writer(writer, &entries)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use syntect::parsing::{
+        syntax_definition::Context, Scope, SyntaxDefinition, SyntaxSetBuilder,
+    };
+
+    fn plain_text() -> SyntaxDefinition {
+        let scope = Scope::new("text.plain").unwrap();
+        let mut contexts = HashMap::new();
+        contexts.insert("main".to_string(), Context::new(true));
+        // The parser starts in this context
+        contexts.insert(
+            "__start".to_string(),
+            Context {
+                meta_content_scope: vec![scope],
+                ..Context::new(false)
+            },
+        );
+        SyntaxDefinition {
+            name: "Plain Text".to_string(),
+            file_extensions: vec!["txt".to_string()],
+            scope,
+            first_line_match: None,
+            hidden: false,
+            variables: HashMap::new(),
+            contexts,
+        }
+    }
+
+    #[test]
+    fn highlight_cache_hit() -> Result<()> {
+        let mut builder = SyntaxSetBuilder::new();
+        builder.add(plain_text());
+        let ps = builder.build();
+        let syntax = ps.find_syntax_plain_text();
+
+        let code = "fn main() {}\n";
+        let cache = Mutex::new(HighlightCache::new(2));
+        let first = crate::cached(&cache, &ps, syntax, None, code);
+        assert_eq!(0, cache.lock().unwrap().hits());
+        assert_eq!(1, cache.lock().unwrap().misses());
+
+        let second = crate::cached(&cache, &ps, syntax, None, code);
+        assert_eq!(first, second);
+        assert_eq!(1, cache.lock().unwrap().hits());
+        assert_eq!(1, cache.lock().unwrap().len());
+
+        // Theme and inline styles are part of the key
+        assert_ne!(
+            HighlightCache::key("Plain Text", None, code),
+            HighlightCache::key("Plain Text", Some("base16-ocean.dark"), code)
+        );
+        assert_ne!(
+            HighlightCache::key("Plain Text", Some("base16-ocean.dark"), code),
+            HighlightCache::key("Plain Text", Some("InspiredGitHub"), code)
+        );
+
+        // Oldest entries are evicted when full
+        crate::cached(&cache, &ps, syntax, None, "a\n");
+        crate::cached(&cache, &ps, syntax, None, "b\n");
+        assert_eq!(2, cache.lock().unwrap().len());
+        crate::cached(&cache, &ps, syntax, None, code);
+        assert_eq!(1, cache.lock().unwrap().hits());
+
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("highlight.json");
+        cache.lock().unwrap().save(&file)?;
+        let mut loaded = HighlightCache::new(2);
+        loaded.load(&file)?;
+        let loaded = Mutex::new(loaded);
+        crate::cached(&loaded, &ps, syntax, None, code);
+        assert_eq!(1, loaded.lock().unwrap().hits());
+        Ok(())
+    }
+
+    #[test]
+    fn highlight_cache_version() {
+        let key = HighlightCache::key("Plain Text", None, "a\n");
+        assert!(key.starts_with(&format!("{}+", SYNTECT_VERSION)));
+    }
+
+    #[test]
+    fn highlight_cache_corrupt() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("highlight.json");
+        fs::write(&file, "[[\"key\"")?;
+        let mut cache = HighlightCache::new(2);
+        cache.load(&file)?;
+        assert!(cache.is_empty());
+        assert!(!file.exists());
+        Ok(())
+    }
+}
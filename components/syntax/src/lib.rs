@@ -1,15 +1,15 @@
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
-use std::path::PathBuf;
-use std::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
 
 use once_cell::sync::OnceCell;
 
 use config::syntax::SyntaxConfig;
 
 use syntect::dumps::from_reader;
-use syntect::highlighting::ThemeSet;
+use syntect::highlighting::{Theme, ThemeSet};
 use syntect::html::{ClassStyle, ClassedHTMLGenerator};
 use syntect::parsing::SyntaxReference;
 use syntect::parsing::SyntaxSet;
@@ -35,12 +35,21 @@ pub enum Error {
 
     #[error("Could not parse cached theme set")]
     ThemeSetParse,
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 }
 
 type Result<T> = std::result::Result<T, Error>;
 
+mod cache;
 mod inline;
 
+pub use cache::HighlightCache;
+
 #[derive(Debug)]
 struct HighlightAssets {
     pub syntax_set: SyntaxSet,
@@ -119,20 +128,60 @@ fn lookup() -> &'static HashMap<&'static str, &'static str> {
     })
 }
 
+fn cache() -> &'static Mutex<HighlightCache> {
+    static INSTANCE: OnceCell<Mutex<HighlightCache>> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        Mutex::new(HighlightCache::new(conf(None).cache_size()))
+    })
+}
+
+/// Load previously highlighted code blocks into the cache.
+pub fn load_cache<P: AsRef<Path>>(file: P) -> Result<()> {
+    cache().lock().unwrap().load(file)
+}
+
+/// Save the highlighted code blocks in the cache.
+pub fn save_cache<P: AsRef<Path>>(file: P) -> Result<()> {
+    cache().lock().unwrap().save(file)
+}
+
 pub fn highlight<'a>(value: &str, syntax: &'a SyntaxReference) -> String {
     let config = conf(None);
     let assets = assets(None);
-    let ps = &assets.syntax_set;
+    let theme = config
+        .theme()
+        .as_ref()
+        .map(|name| (name.as_str(), &assets.theme_set.themes[name]));
+    cached(cache(), &assets.syntax_set, syntax, theme, value)
+}
 
-    if let Some(ref theme) = config.theme() {
-        let ts = &assets.theme_set;
+/// Highlight using the cached HTML when the code block has
+/// already been highlighted with the same settings.
+fn cached(
+    cache: &Mutex<HighlightCache>,
+    ps: &SyntaxSet,
+    syntax: &SyntaxReference,
+    theme: Option<(&str, &Theme)>,
+    value: &str,
+) -> String {
+    let key =
+        HighlightCache::key(&syntax.name, theme.map(|(name, _)| name), value);
+    if let Some(html) = cache.lock().unwrap().get(&key) {
+        return html;
+    }
+    let html = render(value, ps, syntax, theme.map(|(_, theme)| theme));
+    cache.lock().unwrap().insert(key, html.clone());
+    html
+}
 
-        return inline::highlighted_html_for_string(
-            value,
-            ps,
-            syntax,
-            &ts.themes[theme],
-        );
+fn render(
+    value: &str,
+    ps: &SyntaxSet,
+    syntax: &SyntaxReference,
+    theme: Option<&Theme>,
+) -> String {
+    if let Some(theme) = theme {
+        return inline::highlighted_html_for_string(value, ps, syntax, theme);
     }
 
     //
@@ -1,27 +1,29 @@
-use regex::Regex;
-
 use crate::text::TextExtraction;
 use crate::Result;
 
 #[derive(Debug)]
 pub struct TransformCache {
-    // Patter for counting words
-    pub words_re: Regex,
-
     // Extracted text.
     pub text: Option<TextExtraction>,
 
+    // Selectors for regions excluded from text extraction.
+    pub ignore: Vec<String>,
+
     // This flag is used internally to trigger syntax highlighting
     // transformations when the syntax configuration is active
     pub syntax_highlight: Option<bool>,
+
+    // Duplicate heading identifiers kept by the warn policy.
+    pub duplicates: Vec<String>,
 }
 
 impl TransformCache {
     pub fn new() -> Result<Self> {
         Ok(Self {
-            words_re: Regex::new(r"\b?\w\b")?,
             text: None,
+            ignore: Vec::new(),
             syntax_highlight: None,
+            duplicates: Vec::new(),
         })
     }
 
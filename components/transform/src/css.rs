@@ -0,0 +1,43 @@
+use regex::{Captures, Regex};
+
+use crate::Result;
+
+/// Rewrite `url()` references in a stylesheet using a resolver function,
+/// references are unchanged when the resolver returns `None`.
+pub fn rewrite_urls<F>(content: &str, resolve: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let url_re = Regex::new(r#"url\(\s*(['"]?)([^'")]+)(['"]?)\s*\)"#)?;
+    let result = url_re.replace_all(content, |caps: &Captures| {
+        let url = caps.get(2).unwrap().as_str();
+        match resolve(url) {
+            Some(value) => format!("url({}{}{})", &caps[1], value, &caps[3]),
+            None => caps[0].to_string(),
+        }
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rewrite_urls;
+    use crate::Result;
+
+    #[test]
+    fn rewrite_css_urls() -> Result<()> {
+        let css = r#"a { background: url("/img/a.png"); } b { background: url(b.png); }"#;
+        let result = rewrite_urls(css, |url| {
+            if url == "/img/a.png" {
+                Some("/img/a.1234.png".to_string())
+            } else {
+                None
+            }
+        })?;
+        assert_eq!(
+            r#"a { background: url("/img/a.1234.png"); } b { background: url(b.png); }"#,
+            result
+        );
+        Ok(())
+    }
+}
@@ -1,24 +1,32 @@
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 
 use lol_html::{
     doc_comments, element, errors::RewritingError, html_content::ContentType,
-    rewrite_str, text, RewriteStrSettings,
+    rewrite_str, text, RewriteStrSettings, Selector,
 };
 
 use htmlentity::entity;
 use regex::{Captures, Regex};
 
-use config::transform::HtmlTransformFlags;
+use config::{
+    style::DeferStyle,
+    transform::{DuplicateIdPolicy, HtmlTransformFlags},
+};
 use toc::TableOfContents;
 
 use crate::cache::TransformCache;
-use crate::text::TextExtraction;
+use crate::text::{self, TextExtraction};
 use crate::{Error, Result};
 
 const HEADINGS: &str = "h1, h2, h3, h4, h5, h6";
 const CODE: &str = "pre > code[class]";
 const TITLE: &str = "title";
 const TEXT: &str = "p, [data-index] *";
+const HREF: &str = "[href]";
+const SRC: &str = "[src]";
+const IMG: &str = "img[src]";
+const SRCSET: &str = "[srcset]";
 
 fn scan(
     doc: &str,
@@ -109,16 +117,15 @@ fn rewrite(
     headings: &mut Vec<String>,
     code_blocks: &mut Vec<String>,
     toc: &mut Option<TableOfContents>,
+    duplicates: &mut Vec<String>,
     cache: &mut TransformCache,
 ) -> std::result::Result<String, RewritingError> {
     let mut seen_headings: HashMap<String, usize> = HashMap::new();
+    let policy = flags.duplicate_ids();
     let lang_re = Regex::new(r"language-([^\s]+)\s?").unwrap();
 
-    let extract_text = cache.text.is_some();
-    let use_words = flags.use_words();
     let highlight = cache.use_syntax_highlight();
 
-    let mut text_buf = String::new();
     let mut element_content_handlers = vec![];
 
     let auto_id_rewrite = element!(HEADINGS, |el| {
@@ -132,9 +139,21 @@ fn rewrite(
                 slug::slugify(&value)
             };
 
-            if seen_headings.contains_key(&id) {
-                let heading_count = seen_headings.get(&id).unwrap();
-                id = format!("{}-{}", &id, heading_count + 1);
+            if let Some(count) = seen_headings.get(&id).cloned() {
+                match policy {
+                    DuplicateIdPolicy::Suffix => {
+                        let mut count = count + 1;
+                        let mut candidate = format!("{}-{}", &id, count);
+                        while seen_headings.contains_key(&candidate) {
+                            count += 1;
+                            candidate = format!("{}-{}", &id, count);
+                        }
+                        seen_headings.insert(id, count);
+                        id = candidate;
+                        el.set_attribute("id", &id)?;
+                    }
+                    _ => duplicates.push(id.clone()),
+                }
             }
 
             if let None = id_attr {
@@ -145,7 +164,7 @@ fn rewrite(
                 toc.add(&el.tag_name(), &id, &value)?;
             }
 
-            seen_headings.entry(id).and_modify(|c| *c += 1).or_insert(0);
+            seen_headings.entry(id).or_insert(0);
         }
         Ok(())
     });
@@ -171,13 +190,69 @@ fn rewrite(
         Ok(())
     });
 
+    if flags.use_auto_id() {
+        element_content_handlers.push(auto_id_rewrite);
+    }
+
+    if highlight {
+        element_content_handlers.push(code_block_rewrite);
+    }
+
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers,
+            ..Default::default()
+        },
+    )
+}
+
+/// Remove the regions of a document that match a selector.
+fn remove_regions(
+    doc: &str,
+    selector: &str,
+) -> std::result::Result<String, RewritingError> {
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![element!(selector, |el| {
+                el.remove();
+                Ok(())
+            })],
+            ..Default::default()
+        },
+    )
+}
+
+/// Extract the text content of a document for search indices.
+///
+/// Regions matching the ignore selectors in the cache are removed
+/// before the text is extracted so that their text is not indexed.
+fn extract(
+    doc: &str,
+    flags: &HtmlTransformFlags,
+    cache: &mut TransformCache,
+) -> Result<()> {
+    let use_words = flags.use_words();
+    let mut text_buf = String::new();
+
+    let selector = cache.ignore.join(", ");
+    let doc = if !selector.is_empty() {
+        selector.parse::<Selector>().map_err(|e| {
+            Error::InvalidSelector(selector.clone(), e.to_string())
+        })?;
+        remove_regions(doc, &selector)
+            .map_err(|e| Error::Rewriting(e.to_string()))?
+    } else {
+        doc.to_string()
+    };
+
     let extract_text_content = text!(TEXT, |t| {
         if let Some(txt) = cache.text.as_mut() {
             text_buf += t.as_str();
             if t.last_in_text_node() {
                 if use_words {
-                    let count = cache.words_re.find_iter(&text_buf).count();
-                    txt.words += count;
+                    txt.words += text::count_words(&text_buf);
                 }
                 txt.chunks.push(text_buf.clone());
                 text_buf.clear();
@@ -186,25 +261,494 @@ fn rewrite(
         Ok(())
     });
 
-    if flags.use_auto_id() {
-        element_content_handlers.push(auto_id_rewrite);
+    rewrite_str(
+        &doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![extract_text_content],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Prefix a root-relative URL with a base path.
+///
+/// Absolute, protocol-relative, anchor-only and document-relative
+/// URLs are returned unchanged as are URLs that already begin
+/// with the base path.
+fn prefix_url(url: &str, base: &str) -> Option<String> {
+    if !url.starts_with('/') || url.starts_with("//") {
+        return None;
+    }
+    if url == base || url.starts_with(&format!("{}/", base)) {
+        return None;
     }
+    Some(format!("{}{}", base, url))
+}
 
-    if highlight {
-        element_content_handlers.push(code_block_rewrite);
+/// Rewrite root-relative `href` and `src` attributes so that they
+/// include a base path, used when a site is served from a subdirectory.
+pub fn prefix_base(doc: &str, base: &str) -> Result<String> {
+    let base = base.trim_end_matches('/');
+    let href_rewrite = element!(HREF, |el| {
+        if let Some(href) = el.get_attribute("href") {
+            if let Some(value) = prefix_url(&href, base) {
+                el.set_attribute("href", &value)?;
+            }
+        }
+        Ok(())
+    });
+
+    let src_rewrite = element!(SRC, |el| {
+        if let Some(src) = el.get_attribute("src") {
+            if let Some(value) = prefix_url(&src, base) {
+                el.set_attribute("src", &value)?;
+            }
+        }
+        Ok(())
+    });
+
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![href_rewrite, src_rewrite],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))
+}
+
+/// Rewrite `href`, `src` and `srcset` attributes using a resolver
+/// function, attributes are unchanged when the resolver returns `None`.
+pub fn rewrite_assets<F>(doc: &str, resolve: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let href_rewrite = element!(HREF, |el| {
+        if let Some(href) = el.get_attribute("href") {
+            if let Some(value) = resolve(&href) {
+                el.set_attribute("href", &value)?;
+            }
+        }
+        Ok(())
+    });
+
+    let src_rewrite = element!(SRC, |el| {
+        if let Some(src) = el.get_attribute("src") {
+            if let Some(value) = resolve(&src) {
+                el.set_attribute("src", &value)?;
+            }
+        }
+        Ok(())
+    });
+
+    let srcset_rewrite = element!(SRCSET, |el| {
+        if let Some(srcset) = el.get_attribute("srcset") {
+            let value = rewrite_srcset(&srcset, &resolve);
+            if value != srcset {
+                el.set_attribute("srcset", &value)?;
+            }
+        }
+        Ok(())
+    });
+
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![
+                href_rewrite,
+                src_rewrite,
+                srcset_rewrite,
+            ],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))
+}
+
+/// Rewrite the URL of each image candidate in a `srcset` value.
+fn rewrite_srcset<F>(srcset: &str, resolve: &F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    srcset
+        .split(',')
+        .map(|candidate| {
+            let candidate = candidate.trim();
+            let (url, descriptor) = match candidate.find(char::is_whitespace) {
+                Some(pos) => candidate.split_at(pos),
+                None => (candidate, ""),
+            };
+            match resolve(url) {
+                Some(value) => format!("{}{}", value, descriptor),
+                None => candidate.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Determine if a URL is relative to the current document.
+///
+/// Absolute, protocol-relative, root-relative and anchor-only
+/// URLs are not document-relative.
+fn is_document_relative(url: &str) -> bool {
+    if url.is_empty() || url.starts_with('#') || url.starts_with('/') {
+        return false;
     }
+    let end = url.find(|c| c == '/' || c == '?' || c == '#');
+    let scheme = match end {
+        Some(pos) => &url[..pos],
+        None => url,
+    };
+    !scheme.contains(':')
+}
 
-    if extract_text {
-        element_content_handlers.push(extract_text_content);
+/// Rewrite document-relative links using a resolver function
+/// that receives the link path without any query or fragment.
+///
+/// The query and fragment are appended to the resolved URL;
+/// links are unchanged when the resolver returns `None`.
+pub fn rewrite_links<F>(doc: &str, resolve: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let link_rewrite = element!("a[href]", |el| {
+        if let Some(href) = el.get_attribute("href") {
+            if is_document_relative(&href) {
+                let pos = href.find(|c| c == '?' || c == '#');
+                let (path, suffix) = href.split_at(pos.unwrap_or(href.len()));
+                if let Some(value) = resolve(path) {
+                    el.set_attribute("href", &format!("{}{}", value, suffix))?;
+                }
+            }
+        }
+        Ok(())
+    });
+
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![link_rewrite],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))
+}
+
+/// Rewrite the URLs of links and images using a resolver function
+/// that receives the attribute value.
+///
+/// Used to make URLs absolute for content published outside of
+/// the page, for example feed items; URLs are unchanged when the
+/// resolver returns `None`.
+pub fn rewrite_urls<F>(doc: &str, resolve: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let href_rewrite = element!("a[href]", |el| {
+        if let Some(href) = el.get_attribute("href") {
+            if let Some(value) = resolve(&href) {
+                el.set_attribute("href", &value)?;
+            }
+        }
+        Ok(())
+    });
+
+    let src_rewrite = element!("img[src]", |el| {
+        if let Some(src) = el.get_attribute("src") {
+            if let Some(value) = resolve(&src) {
+                el.set_attribute("src", &value)?;
+            }
+        }
+        Ok(())
+    });
+
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![href_rewrite, src_rewrite],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))
+}
+
+/// Determine if a URL is an external link.
+///
+/// Only absolute `http` and `https` URLs and protocol-relative
+/// URLs are external.
+pub fn is_external(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    lower.starts_with("http://")
+        || lower.starts_with("https://")
+        || lower.starts_with("//")
+}
+
+/// Compile query parameter name patterns where a `*`
+/// matches any sequence of characters.
+fn param_patterns(params: &[String]) -> Result<Vec<Regex>> {
+    params
+        .iter()
+        .map(|p| {
+            let source = regex::escape(p).replace(r"\*", ".*");
+            Regex::new(&format!("^{}$", source)).map_err(Error::from)
+        })
+        .collect()
+}
+
+/// Remove query parameters matching any of the patterns from a URL,
+/// the remaining query parameters and any fragment are preserved.
+fn strip_url_params(url: &str, patterns: &[Regex]) -> Option<String> {
+    let (rest, fragment) = match url.find('#') {
+        Some(pos) => url.split_at(pos),
+        None => (url, ""),
+    };
+    let (path, query) = match rest.find('?') {
+        Some(pos) => (&rest[..pos], &rest[pos + 1..]),
+        None => return None,
+    };
+
+    // Markup may encode the separator as an entity
+    let separator = if query.contains("&amp;") {
+        "&amp;"
+    } else {
+        "&"
+    };
+
+    let mut stripped = false;
+    let retained = query
+        .split(separator)
+        .filter(|pair| {
+            let name = pair.split('=').next().unwrap_or("");
+            let matched = patterns.iter().any(|re| re.is_match(name));
+            stripped = stripped || matched;
+            !matched
+        })
+        .collect::<Vec<_>>();
+
+    if !stripped {
+        return None;
     }
 
+    if retained.is_empty() {
+        Some(format!("{}{}", path, fragment))
+    } else {
+        Some(format!("{}?{}{}", path, retained.join(separator), fragment))
+    }
+}
+
+/// Remove tracking query parameters such as `utm_source` from
+/// the `href` attribute of external links.
+///
+/// Parameter names are matched against the patterns where a `*`
+/// matches any characters; internal links are unchanged.
+pub fn strip_params(doc: &str, params: &[String]) -> Result<String> {
+    let patterns = param_patterns(params)?;
+    let link_rewrite = element!("a[href]", |el| {
+        if let Some(href) = el.get_attribute("href") {
+            if is_external(&href) {
+                if let Some(value) = strip_url_params(&href, &patterns) {
+                    el.set_attribute("href", &value)?;
+                }
+            }
+        }
+        Ok(())
+    });
+
     rewrite_str(
         doc,
         RewriteStrSettings {
-            element_content_handlers,
+            element_content_handlers: vec![link_rewrite],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))
+}
+
+/// Escape a value for a single quoted JavaScript string.
+fn escape_js(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\'', "\\'")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Inline the critical styles and defer loading the style sheets.
+///
+/// The critical styles are inserted before the first style sheet
+/// or at the end of the `head` when there are no style sheets; a
+/// `noscript` fallback loads the style sheets without scripting.
+pub fn inline_critical_style(
+    doc: &str,
+    css: &str,
+    defer: DeferStyle,
+) -> Result<String> {
+    let style = format!("<style>{}</style>", css);
+    let inserted = Cell::new(false);
+
+    let link_rewrite = element!(r#"head link[rel="stylesheet"]"#, |el| {
+        let href =
+            entity::decode(&el.get_attribute("href").unwrap_or_default());
+        let media = el.get_attribute("media").map(|m| entity::decode(&m));
+
+        if !inserted.get() {
+            el.before(&style, ContentType::Html);
+            inserted.set(true);
+        }
+
+        let media_attr = match media {
+            Some(ref media) => {
+                format!(" media=\"{}\"", utils::entity::escape(media))
+            }
+            None => String::new(),
+        };
+        let fallback = format!(
+            "<noscript><link rel=\"stylesheet\" href=\"{}\"{}></noscript>",
+            utils::entity::escape(&href),
+            media_attr
+        );
+
+        match defer {
+            DeferStyle::Media => {
+                let media = media.as_deref().unwrap_or("all");
+                el.set_attribute("media", "print")?;
+                el.set_attribute(
+                    "onload",
+                    &format!(
+                        "this.onload=null;this.media='{}'",
+                        escape_js(media)
+                    ),
+                )?;
+            }
+            DeferStyle::Preload => {
+                el.set_attribute("rel", "preload")?;
+                el.set_attribute("as", "style")?;
+                el.set_attribute(
+                    "onload",
+                    "this.onload=null;this.rel='stylesheet'",
+                )?;
+            }
+        }
+        el.after(&fallback, ContentType::Html);
+        Ok(())
+    });
+
+    let result = rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![link_rewrite],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))?;
+
+    if inserted.get() {
+        return Ok(result);
+    }
+
+    let head_rewrite = element!("head", |el| {
+        el.append(&style, ContentType::Html);
+        Ok(())
+    });
+
+    rewrite_str(
+        &result,
+        RewriteStrSettings {
+            element_content_handlers: vec![head_rewrite],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))
+}
+
+/// Links and element identifiers in a document.
+#[derive(Debug, Default)]
+pub struct DocumentLinks {
+    /// Values of `href` and `src` attributes.
+    pub links: Vec<String>,
+    /// Values of `id` attributes and the `name` of anchors
+    /// which may be the target of a URL fragment.
+    pub ids: HashSet<String>,
+}
+
+/// Collect the links and fragment targets in a document.
+pub fn links(doc: &str) -> Result<DocumentLinks> {
+    let mut hrefs = Vec::new();
+    let mut srcs = Vec::new();
+    let mut ids = HashSet::new();
+    let mut names = HashSet::new();
+
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![
+                element!(HREF, |el| {
+                    if let Some(href) = el.get_attribute("href") {
+                        hrefs.push(href);
+                    }
+                    Ok(())
+                }),
+                element!(SRC, |el| {
+                    if let Some(src) = el.get_attribute("src") {
+                        srcs.push(src);
+                    }
+                    Ok(())
+                }),
+                element!("[id]", |el| {
+                    if let Some(id) = el.get_attribute("id") {
+                        ids.insert(id);
+                    }
+                    Ok(())
+                }),
+                element!("a[name]", |el| {
+                    if let Some(name) = el.get_attribute("name") {
+                        names.insert(name);
+                    }
+                    Ok(())
+                }),
+            ],
+            ..Default::default()
+        },
+    )
+    .map_err(|e| Error::Rewriting(e.to_string()))?;
+
+    hrefs.append(&mut srcs);
+    ids.extend(names);
+    Ok(DocumentLinks { links: hrefs, ids })
+}
+
+/// Add a `srcset` attribute to images using a resolver function
+/// that receives the `src` attribute value.
+///
+/// Images that already declare a `srcset` are unchanged as are
+/// images when the resolver returns `None`.
+pub fn rewrite_images<F>(doc: &str, resolve: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let img_rewrite = element!(IMG, |el| {
+        if el.get_attribute("srcset").is_none() {
+            if let Some(src) = el.get_attribute("src") {
+                if let Some(value) = resolve(&src) {
+                    el.set_attribute("srcset", &value)?;
+                }
+            }
+        }
+        Ok(())
+    });
+
+    rewrite_str(
+        doc,
+        RewriteStrSettings {
+            element_content_handlers: vec![img_rewrite],
             ..Default::default()
         },
     )
+    .map_err(|e| Error::Rewriting(e.to_string()))
 }
 
 // NOTE: This is necessary because currently the buffer text handlers
@@ -251,7 +795,7 @@ fn word_replace(doc: &str, text: &TextExtraction) -> Result<String> {
             if let Some(avg) = avg_attr {
                 let avg: usize = match avg.as_str().parse() {
                     Ok(res) => res,
-                    Err(_) => 250,
+                    Err(_) => text::WORDS_PER_MINUTE,
                 };
 
                 // It doesn't make sense to show zero minutes for reading
@@ -261,7 +805,7 @@ fn word_replace(doc: &str, text: &TextExtraction) -> Result<String> {
                 //
                 // {{words time=true}} minutes
                 //
-                value = std::cmp::max(value / avg, 2);
+                value = std::cmp::max(text.reading_time(avg), 2);
             }
 
             value.to_string()
@@ -283,22 +827,37 @@ pub fn apply(
     let value = scan(&clean, flags, &mut headings, &mut code_blocks, cache)
         .map_err(|e| Error::Rewriting(e.to_string()))?;
 
+    if cache.text.is_some() {
+        extract(&value, flags, cache)?;
+    }
+
     let mut toc = if flags.use_toc() {
         Some(TableOfContents::new())
     } else {
         None
     };
 
+    let mut duplicates: Vec<String> = Vec::new();
     let mut result = rewrite(
         &value,
         flags,
         &mut headings,
         &mut code_blocks,
         &mut toc,
+        &mut duplicates,
         cache,
     )
     .map_err(|e| Error::Rewriting(e.to_string()))?;
 
+    if !duplicates.is_empty() {
+        match flags.duplicate_ids() {
+            DuplicateIdPolicy::Error => {
+                return Err(Error::DuplicateHeadingId(duplicates.join(", ")))
+            }
+            _ => cache.duplicates.append(&mut duplicates),
+        }
+    }
+
     if flags.use_toc() {
         result = toc_replace(&result, toc.as_ref().unwrap())?;
     }
@@ -309,5 +868,256 @@ pub fn apply(
         }
     }
 
+    if !flags.strip_params().is_empty() {
+        result = strip_params(&result, flags.strip_params())?;
+    }
+
+    if let Some(base) = flags.base_path() {
+        result = prefix_base(&result, base)?;
+    }
+
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply, inline_critical_style, links, prefix_base, rewrite_assets,
+        rewrite_images, rewrite_links, rewrite_urls, strip_params,
+    };
+    use crate::{cache::TransformCache, text::TextExtraction, Error, Result};
+    use config::{
+        style::DeferStyle,
+        transform::{DuplicateIdPolicy, HtmlTransformFlags},
+    };
+
+    fn headings(policy: DuplicateIdPolicy) -> Result<String> {
+        let flags = HtmlTransformFlags {
+            auto_id: Some(true),
+            duplicate_ids: Some(policy),
+            ..Default::default()
+        };
+        let mut cache = TransformCache::new()?;
+        apply(
+            "<h2>Setup</h2><h2>Setup</h2><h2>Setup</h2>",
+            &flags,
+            &mut cache,
+        )
+    }
+
+    #[test]
+    fn duplicate_heading_suffix() -> Result<()> {
+        let result = headings(DuplicateIdPolicy::Suffix)?;
+        assert_eq!(
+            r#"<h2 id="setup">Setup</h2><h2 id="setup-1">Setup</h2><h2 id="setup-2">Setup</h2>"#,
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_heading_warn() -> Result<()> {
+        let flags = HtmlTransformFlags {
+            auto_id: Some(true),
+            duplicate_ids: Some(DuplicateIdPolicy::Warn),
+            ..Default::default()
+        };
+        let mut cache = TransformCache::new()?;
+        let result = apply("<h2>Setup</h2><h2>Setup</h2>", &flags, &mut cache)?;
+        assert_eq!(
+            r#"<h2 id="setup">Setup</h2><h2 id="setup">Setup</h2>"#,
+            result
+        );
+        assert_eq!(vec!["setup".to_string()], cache.duplicates);
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_heading_error() {
+        match headings(DuplicateIdPolicy::Error) {
+            Err(Error::DuplicateHeadingId(id)) => {
+                assert_eq!("setup, setup", id)
+            }
+            _ => panic!("expected duplicate heading id error"),
+        }
+    }
+
+    #[test]
+    fn prefix_root_relative() -> Result<()> {
+        let doc = r#"<a href="/guide/">Guide</a><img src="/logo.png">"#;
+        let result = prefix_base(doc, "/docs/")?;
+        assert_eq!(
+            r#"<a href="/docs/guide/">Guide</a><img src="/docs/logo.png">"#,
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prefix_skips_external() -> Result<()> {
+        let doc = r##"<a href="https://example.com/">A</a><a href="#top">B</a><a href="//cdn.example.com/x.js">C</a><a href="/docs/page/">D</a>"##;
+        let result = prefix_base(doc, "/docs")?;
+        assert_eq!(doc, result);
+        Ok(())
+    }
+
+    #[test]
+    fn image_srcset() -> Result<()> {
+        let doc = r#"<img src="a.png"><img src="b.png" srcset="b.png 1x"><img src="c.gif">"#;
+        let result = rewrite_images(doc, |src| {
+            if src == "c.gif" {
+                None
+            } else {
+                Some(format!("{} 480w", src))
+            }
+        })?;
+        assert_eq!(
+            r#"<img src="a.png" srcset="a.png 480w"><img src="b.png" srcset="b.png 1x"><img src="c.gif">"#,
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn relative_links() -> Result<()> {
+        let doc = r##"<a href="./other.md#intro">A</a><a href="https://example.com/other.md">B</a><a href="#top">C</a><a href="mailto:me@example.com">D</a><a href="missing.md">E</a>"##;
+        let result = rewrite_links(doc, |path| {
+            if path == "./other.md" {
+                Some("/post/other/".to_string())
+            } else {
+                None
+            }
+        })?;
+        assert_eq!(
+            r##"<a href="/post/other/#intro">A</a><a href="https://example.com/other.md">B</a><a href="#top">C</a><a href="mailto:me@example.com">D</a><a href="missing.md">E</a>"##,
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn absolute_urls() -> Result<()> {
+        let doc = r##"<a href="/post/other/#intro">A</a><a href="https://example.com/">B</a><img src="image.png">"##;
+        let result = rewrite_urls(doc, |value| {
+            if value.starts_with("https:") {
+                None
+            } else if value.starts_with('/') {
+                Some(format!("https://example.com{}", value))
+            } else {
+                Some(format!("https://example.com/post/{}", value))
+            }
+        })?;
+        assert_eq!(
+            r##"<a href="https://example.com/post/other/#intro">A</a><a href="https://example.com/">B</a><img src="https://example.com/post/image.png">"##,
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn search_ignore() -> Result<()> {
+        let doc = r#"<nav data-search-ignore><p>Navigation</p></nav><p>Content</p><footer><p>Copyright</p></footer>"#;
+        let flags: HtmlTransformFlags = Default::default();
+        let mut cache = TransformCache::new()?;
+        cache.text = Some(TextExtraction::new());
+        cache.ignore =
+            vec!["[data-search-ignore]".to_string(), "footer".to_string()];
+        let result = apply(doc, &flags, &mut cache)?;
+        assert_eq!(doc, result);
+
+        let text = cache.text.unwrap().to_chunk_string();
+        assert!(text.contains("Content"));
+        assert!(!text.contains("Navigation"));
+        assert!(!text.contains("Copyright"));
+        Ok(())
+    }
+
+    #[test]
+    fn strip_tracking_params() -> Result<()> {
+        let params = vec!["utm_*".to_string(), "fbclid".to_string()];
+        let doc = r##"<a href="https://example.com/post?utm_source=feed&id=42&fbclid=abc&page=2#comments">A</a><a href="/about/?utm_source=nav">B</a><a href="https://example.com/?q=utm_source">C</a><a href="https://example.com/?utm_medium=rss&amp;q=rust">D</a>"##;
+        let result = strip_params(doc, &params)?;
+        assert_eq!(
+            r##"<a href="https://example.com/post?id=42&page=2#comments">A</a><a href="/about/?utm_source=nav">B</a><a href="https://example.com/?q=utm_source">C</a><a href="https://example.com/?q=rust">D</a>"##,
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn strip_only_tracking_params() -> Result<()> {
+        let flags = HtmlTransformFlags {
+            strip_params: Some(vec!["utm_*".to_string(), "fbclid".to_string()]),
+            ..Default::default()
+        };
+        let mut cache = TransformCache::new()?;
+        let doc = r##"<a href="https://example.com/?utm_source=feed&utm_medium=rss&fbclid=abc#top">A</a><a href="//example.com/path?fbclid=abc">B</a>"##;
+        let result = apply(doc, &flags, &mut cache)?;
+        assert_eq!(
+            r##"<a href="https://example.com/#top">A</a><a href="//example.com/path">B</a>"##,
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn collect_links() -> Result<()> {
+        let doc = r##"<h2 id="usage">Usage</h2><a name="top"></a><a href="/guide/#usage">Guide</a><img src="logo.png">"##;
+        let result = links(doc)?;
+        assert_eq!(vec!["/guide/#usage", "logo.png"], result.links);
+        assert!(result.ids.contains("usage"));
+        assert!(result.ids.contains("top"));
+        Ok(())
+    }
+
+    #[test]
+    fn critical_style() -> Result<()> {
+        let doc = r#"<html><head><title>A</title><link rel="stylesheet" href="/style.css"></head><body></body></html>"#;
+        let result =
+            inline_critical_style(doc, "h1{color:red}", DeferStyle::Media)?;
+        assert_eq!(
+            r#"<html><head><title>A</title><style>h1{color:red}</style><link rel="stylesheet" href="/style.css" media="print" onload="this.onload=null;this.media='all'"><noscript><link rel="stylesheet" href="/style.css"></noscript></head><body></body></html>"#,
+            result
+        );
+
+        let result =
+            inline_critical_style(doc, "h1{color:red}", DeferStyle::Preload)?;
+        assert!(result.contains(r#"<style>h1{color:red}</style><link rel="preload" href="/style.css" as="style" onload="this.onload=null;this.rel='stylesheet'">"#));
+
+        let doc = "<html><head><title>A</title></head><body></body></html>";
+        let result =
+            inline_critical_style(doc, "h1{color:red}", DeferStyle::Media)?;
+        assert_eq!(
+            "<html><head><title>A</title><style>h1{color:red}</style></head><body></body></html>",
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn critical_style_escape() -> Result<()> {
+        let doc = r#"<head><link rel="stylesheet" href='/a.css?x=1&amp;y="><script>' media="screen and (x: 'y')"></head>"#;
+        let result =
+            inline_critical_style(doc, "h1{color:red}", DeferStyle::Media)?;
+        assert!(result.contains(
+            r#"onload="this.onload=null;this.media='screen and (x: \'y\')'""#
+        ));
+        assert!(result.contains(r#"<noscript><link rel="stylesheet" href="/a.css?x=1&amp;y=&quot;&gt;&lt;script&gt;" media="screen and (x: &apos;y&apos;)"></noscript>"#));
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_asset_srcset() -> Result<()> {
+        let doc = r#"<img src="a.png" srcset="a-480.png 480w, b.png 960w">"#;
+        let result = rewrite_assets(doc, |url| match url {
+            "a.png" => Some("a.1234.png".to_string()),
+            "a-480.png" => Some("a-480.5678.png".to_string()),
+            _ => None,
+        })?;
+        assert_eq!(
+            r#"<img src="a.1234.png" srcset="a-480.5678.png 480w, b.png 960w">"#,
+            result
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use regex::{Captures, Regex};
+
+use crate::Result;
+
+/// Rewrite single and double quoted string literals in a script
+/// using a resolver function, literals are unchanged when the
+/// resolver returns `None`.
+///
+/// Template literals are not rewritten as they may contain
+/// substitutions.
+pub fn rewrite_strings<F>(content: &str, resolve: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let string_re =
+        Regex::new(r#""((?:\\.|[^"\\\n])*)"|'((?:\\.|[^'\\\n])*)'"#)?;
+    let result = string_re.replace_all(content, |caps: &Captures| {
+        let (quote, value) = match caps.get(1) {
+            Some(value) => ("\"", value.as_str()),
+            None => ("'", caps.get(2).unwrap().as_str()),
+        };
+        match resolve(value) {
+            Some(value) => format!("{}{}{}", quote, value, quote),
+            None => caps[0].to_string(),
+        }
+    });
+    Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rewrite_strings;
+    use crate::Result;
+
+    #[test]
+    fn rewrite_js_strings() -> Result<()> {
+        let js = r#"import a from "./a.js"; const b = '/img/b.png'; // don't
+const c = `/img/b.png`; const d = "it's";"#;
+        let result = rewrite_strings(js, |url| match url {
+            "./a.js" => Some("./a.1234.js".to_string()),
+            "/img/b.png" => Some("/img/b.5678.png".to_string()),
+            _ => None,
+        })?;
+        assert_eq!(
+            r#"import a from "./a.1234.js"; const b = '/img/b.5678.png'; // don't
+const c = `/img/b.png`; const d = "it's";"#,
+            result
+        );
+        Ok(())
+    }
+}
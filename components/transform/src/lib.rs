@@ -7,6 +7,12 @@ pub enum Error {
     #[error("{0}")]
     Rewriting(String),
 
+    #[error("Duplicate heading identifiers {0}")]
+    DuplicateHeadingId(String),
+
+    #[error("Invalid selector {0} ({1})")]
+    InvalidSelector(String, String),
+
     #[error(transparent)]
     ParserInt(#[from] std::num::ParseIntError),
 
@@ -20,5 +26,7 @@ pub enum Error {
 type Result<T> = std::result::Result<T, Error>;
 
 pub mod cache;
+pub mod css;
 pub mod html;
+pub mod js;
 pub mod text;
@@ -1,5 +1,68 @@
 use std::fmt;
 
+use regex::Regex;
+
+/// Default average reading speed in words per minute.
+pub const WORDS_PER_MINUTE: usize = 250;
+
+/// Determine if a character belongs to a script that is written
+/// without spaces between words (Chinese, Japanese) so that each
+/// character is counted as a word.
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3040..=0x30FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xF900..=0xFAFF
+            | 0x20000..=0x2FA1F
+    )
+}
+
+/// Count the words in plain text.
+pub fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else if in_word && (c == '\'' || c == '\u{2019}') {
+            // Contractions such as "don't" are a single word
+        } else {
+            in_word = false;
+        }
+    }
+    count
+}
+
+/// Remove markup from an HTML document leaving the text content;
+/// script and style elements are removed entirely.
+pub fn strip_markup(html: &str) -> String {
+    let blocks =
+        Regex::new(r"(?is)<script[^>]*>.*?</script>|<style[^>]*>.*?</style>")
+            .unwrap();
+    let comments = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    let tags = Regex::new(r"<[^>]*>").unwrap();
+    let text = blocks.replace_all(html, " ");
+    let text = comments.replace_all(&text, " ");
+    let text = tags.replace_all(&text, " ");
+    utils::entity::unescape(&text)
+}
+
+/// Estimate the reading time in minutes for a number of words.
+///
+/// Any text takes at least one minute to read.
+pub fn reading_time(words: usize, words_per_minute: usize) -> usize {
+    let words_per_minute = std::cmp::max(words_per_minute, 1);
+    std::cmp::max((words + words_per_minute - 1) / words_per_minute, 1)
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct TextExtraction {
     pub title: Option<String>,
@@ -14,6 +77,11 @@ impl TextExtraction {
         }
     }
 
+    /// Estimated reading time in minutes for the extracted text.
+    pub fn reading_time(&self, words_per_minute: usize) -> usize {
+        reading_time(self.words, words_per_minute)
+    }
+
     pub fn to_chunk_string(&self) -> String {
         return self
             .chunks
@@ -40,3 +108,27 @@ impl fmt::Display for TextExtraction {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_words() {
+        let html = r#"<p>The quick <em>brown</em> fox doesn't jump over
+the lazy dog; it&apos;s far too tired.</p>
+<script>var ignored = "not counted";</script>"#;
+        let words = count_words(&strip_markup(html));
+        assert_eq!(14, words);
+        assert_eq!(1, reading_time(words, WORDS_PER_MINUTE));
+        assert_eq!(3, reading_time(501, WORDS_PER_MINUTE));
+    }
+
+    #[test]
+    fn cjk_words() {
+        let text =
+            strip_markup("<p>我喜欢读书。</p><p>本を読むのが好きです。</p>");
+        assert_eq!(15, count_words(&text));
+        assert_eq!(3, count_words("Rust 编程"));
+    }
+}
@@ -1,12 +1,25 @@
+use std::io::{self, Read};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
-/// Run a command.
-pub fn run(
+/// Interval used to poll a child process for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Output captured from a command.
+#[derive(Debug)]
+pub struct CommandOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+fn command(
     cmd: &str,
     args: &[&str],
     cwd: Option<PathBuf>,
-) -> std::io::Result<()> {
+) -> io::Result<Command> {
     let cwd = if let Some(cwd) = cwd {
         cwd.to_path_buf()
     } else {
@@ -15,8 +28,122 @@ pub fn run(
 
     let mut command = Command::new(cmd);
     command.current_dir(cwd).args(args);
+    Ok(command)
+}
+
+/// Run a command.
+pub fn run(
+    cmd: &str,
+    args: &[&str],
+    cwd: Option<PathBuf>,
+) -> std::io::Result<()> {
+    let mut command = command(cmd, args, cwd)?;
     command.stdout(Stdio::inherit());
     command.stderr(Stdio::inherit());
     command.output()?;
     Ok(())
 }
+
+/// Wait for a child process to exit.
+///
+/// If the timeout is exceeded the child is killed and reaped
+/// and an error of kind `TimedOut` is returned.
+fn wait_timeout(
+    child: &mut Child,
+    cmd: &str,
+    timeout: Duration,
+) -> io::Result<ExitStatus> {
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if started.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("Command {} timed out after {:?}", cmd, timeout),
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Run a command and kill it if it does not finish within the timeout.
+pub fn run_with_timeout(
+    cmd: &str,
+    args: &[&str],
+    cwd: Option<PathBuf>,
+    timeout: Duration,
+) -> io::Result<ExitStatus> {
+    let mut command = command(cmd, args, cwd)?;
+    command.stdout(Stdio::inherit());
+    command.stderr(Stdio::inherit());
+    let mut child = command.spawn()?;
+    wait_timeout(&mut child, cmd, timeout)
+}
+
+/// Run a command with a timeout capturing stdout and stderr.
+///
+/// Output that is not valid UTF-8 is converted lossily.
+pub fn capture_with_timeout(
+    cmd: &str,
+    args: &[&str],
+    cwd: Option<PathBuf>,
+    timeout: Duration,
+) -> io::Result<CommandOutput> {
+    let mut command = command(cmd, args, cwd)?;
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+    let mut child = command.spawn()?;
+
+    // Read the pipes on threads so a child that fills a pipe
+    // buffer does not block forever
+    let stdout = reader(child.stdout.take());
+    let stderr = reader(child.stderr.take());
+
+    let status = wait_timeout(&mut child, cmd, timeout)?;
+    Ok(CommandOutput {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn reader<R: Read + Send + 'static>(
+    pipe: Option<R>,
+) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_kills_child() {
+        let result =
+            run_with_timeout("sleep", &["5"], None, Duration::from_millis(50));
+        assert_eq!(io::ErrorKind::TimedOut, result.unwrap_err().kind());
+    }
+
+    #[test]
+    fn capture_output() -> io::Result<()> {
+        let output = capture_with_timeout(
+            "echo",
+            &["hello"],
+            None,
+            Duration::from_secs(5),
+        )?;
+        assert!(output.status.success());
+        assert_eq!("hello\n", output.stdout);
+        Ok(())
+    }
+}
@@ -7,9 +7,9 @@ pub fn unescape(txt: &str) -> String {
 }
 
 pub fn escape(txt: &str) -> String {
-    txt.replace(">", "&gt;")
-        .replace("<", "&lt;")
-        .replace("&", "&amp;")
-        .replace("\"", "&quot;")
-        .replace("'", "&apos;")
+    txt.replace('&', "&amp;")
+        .replace('>', "&gt;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
 }
@@ -1,10 +1,14 @@
-use std::fs::File;
+use std::fs::{self, File, Metadata};
 use std::io;
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::io::Write;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use filetime::FileTime;
+
+use crate::{Error, Result};
 
 pub fn read_bytes<P: AsRef<Path>>(input: P) -> io::Result<Vec<u8>> {
     let mut file = File::open(input)?;
@@ -51,3 +55,191 @@ pub fn write_string<P: AsRef<Path>, S: AsRef<str>>(
 ) -> io::Result<()> {
     write_all(output, content.as_ref().as_bytes())
 }
+
+/// Policy for files that already exist in the destination.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overwrite {
+    /// Replace existing files.
+    #[default]
+    Replace,
+    /// Keep existing files.
+    Skip,
+}
+
+/// Called with the destination path and number of
+/// bytes after each file is copied.
+pub type CopyProgress<'a> = &'a dyn Fn(&Path, u64);
+
+/// Options for copying a directory tree.
+#[derive(Default)]
+pub struct CopyOptions<'a> {
+    /// Behavior when a destination file already exists.
+    pub overwrite: Overwrite,
+    /// Copy the targets of symbolic links rather than
+    /// creating symbolic links in the destination.
+    pub follow_links: bool,
+    /// Set the access and modification times of copied
+    /// files to those of the source files.
+    pub preserve_timestamps: bool,
+    /// Reports progress after each file is copied.
+    pub progress: Option<CopyProgress<'a>>,
+}
+
+/// Counts for a directory copy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CopyStats {
+    /// Number of files copied.
+    pub files: usize,
+    /// Number of bytes copied.
+    pub bytes: u64,
+    /// Number of existing files that were skipped.
+    pub skipped: usize,
+}
+
+/// Resolve a path that may not exist yet using the
+/// nearest existing ancestor.
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    if path.exists() {
+        return path.canonicalize();
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if parent != Path::new("") => {
+            Ok(resolve(parent)?.join(name))
+        }
+        _ => Ok(std::env::current_dir()?.join(path)),
+    }
+}
+
+fn set_times(dest: &Path, meta: &Metadata) -> io::Result<()> {
+    filetime::set_file_times(
+        dest,
+        FileTime::from_last_access_time(meta),
+        FileTime::from_last_modification_time(meta),
+    )
+}
+
+fn copy_tree(
+    src: &Path,
+    dest: &Path,
+    options: &CopyOptions,
+    stats: &mut CopyStats,
+) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let from = entry.path();
+        let to = dest.join(entry.file_name());
+
+        if entry.file_type()?.is_symlink() && !options.follow_links {
+            if to.symlink_metadata().is_ok() {
+                if options.overwrite == Overwrite::Skip {
+                    stats.skipped += 1;
+                    continue;
+                }
+                fs::remove_file(&to)?;
+            }
+            crate::symlink::soft(fs::read_link(&from)?, to)?;
+            continue;
+        }
+
+        let meta = fs::metadata(&from)?;
+        if meta.is_dir() {
+            copy_tree(&from, &to, options, stats)?;
+            if options.preserve_timestamps {
+                set_times(&to, &meta)?;
+            }
+        } else {
+            if to.exists() && options.overwrite == Overwrite::Skip {
+                stats.skipped += 1;
+                continue;
+            }
+            let bytes = fs::copy(&from, &to)?;
+            if options.preserve_timestamps {
+                set_times(&to, &meta)?;
+            }
+            stats.files += 1;
+            stats.bytes += bytes;
+            if let Some(progress) = options.progress {
+                progress(&to, bytes);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Recursively copy a directory into a destination directory.
+///
+/// The destination is created if necessary; it is an error for
+/// the destination to be the source or inside the source as the
+/// copy would never terminate.
+pub fn copy_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dest: Q,
+    options: &CopyOptions,
+) -> Result<CopyStats> {
+    let (src, dest) = (src.as_ref(), dest.as_ref());
+    let source = src.canonicalize()?;
+    if resolve(dest)?.starts_with(source) {
+        return Err(Error::CopyIntoSelf(src.to_path_buf(), dest.to_path_buf()));
+    }
+
+    let mut stats: CopyStats = Default::default();
+    copy_tree(src, dest, options, &mut stats)?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn copy_dir_overwrite() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        let dest = dir.path().join("dest");
+        write_string(src.join("a.txt"), "new a")?;
+        write_string(src.join("nested").join("b.txt"), "new b")?;
+        write_string(dest.join("a.txt"), "old a")?;
+
+        let mut options = CopyOptions {
+            overwrite: Overwrite::Skip,
+            ..Default::default()
+        };
+        let stats = copy_dir(&src, &dest, &options)?;
+        assert_eq!(1, stats.files);
+        assert_eq!(5, stats.bytes);
+        assert_eq!(1, stats.skipped);
+        assert_eq!("old a", read_string(dest.join("a.txt"))?);
+        assert_eq!("new b", read_string(dest.join("nested").join("b.txt"))?);
+
+        let copied = Cell::new(0);
+        let progress = |_: &Path, _: u64| copied.set(copied.get() + 1);
+        options.overwrite = Overwrite::Replace;
+        options.progress = Some(&progress);
+        let stats = copy_dir(&src, &dest, &options)?;
+        assert_eq!(2, stats.files);
+        assert_eq!(0, stats.skipped);
+        assert_eq!(2, copied.get());
+        assert_eq!("new a", read_string(dest.join("a.txt"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_dir_into_self() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        write_string(src.join("a.txt"), "a")?;
+
+        let options: CopyOptions = Default::default();
+        for dest in [src.clone(), src.join("backup")] {
+            assert!(matches!(
+                copy_dir(&src, &dest, &options),
+                Err(Error::CopyIntoSelf(..))
+            ));
+        }
+        assert!(!src.join("backup").exists());
+        Ok(())
+    }
+}
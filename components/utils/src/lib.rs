@@ -1,7 +1,11 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    #[error("Cannot copy directory {0} into itself ({1})")]
+    CopyIntoSelf(PathBuf, PathBuf),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
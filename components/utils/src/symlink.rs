@@ -1,6 +1,30 @@
 use std::io;
 use std::path::Path;
 
+use crate::Result;
+
+/// Windows error when the process lacks the privilege
+/// to create symbolic links (`ERROR_PRIVILEGE_NOT_HELD`).
+#[cfg(windows)]
+const ERROR_PRIVILEGE_NOT_HELD: i32 = 1314;
+
+/// Behavior when a symbolic link cannot be created.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+    /// Return the error from creating the symbolic link.
+    #[default]
+    Fail,
+    /// Copy the source file or directory to the target.
+    Copy,
+}
+
+/// The kind of link created by `soft_fallback()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    Symlink,
+    Copy,
+}
+
 #[cfg(windows)]
 pub fn soft<P: AsRef<Path>>(source: P, target: P) -> io::Result<()> {
     let path = source.as_ref();
@@ -16,3 +40,112 @@ pub fn soft<P: AsRef<Path>>(source: P, target: P) -> io::Result<()> {
 pub fn soft<P: AsRef<Path>>(source: P, target: P) -> io::Result<()> {
     std::os::unix::fs::symlink(source, target)
 }
+
+/// Create a symbolic link using the fallback when symbolic
+/// links are not available, for example on Windows without
+/// the privilege to create symbolic links or on file systems
+/// that do not support them.
+pub fn soft_fallback<P: AsRef<Path>>(
+    source: P,
+    target: P,
+    fallback: Fallback,
+) -> Result<LinkKind> {
+    link_with(source.as_ref(), target.as_ref(), fallback, |s, t| {
+        soft(s, t)
+    })
+}
+
+fn link_with<F>(
+    source: &Path,
+    target: &Path,
+    fallback: Fallback,
+    create: F,
+) -> Result<LinkKind>
+where
+    F: FnOnce(&Path, &Path) -> io::Result<()>,
+{
+    match create(source, target) {
+        Ok(_) => Ok(LinkKind::Symlink),
+        Err(e) if !is_unsupported(&e) || fallback == Fallback::Fail => {
+            Err(e.into())
+        }
+        Err(_) => {
+            if source.is_dir() {
+                crate::fs::copy_dir(source, target, &Default::default())?;
+            } else {
+                crate::fs::copy(source, target)?;
+            }
+            Ok(LinkKind::Copy)
+        }
+    }
+}
+
+/// Errors that indicate symbolic links are not available for
+/// the file system or process rather than a problem with the
+/// source or target paths.
+fn is_unsupported(e: &io::Error) -> bool {
+    if e.kind() == io::ErrorKind::Unsupported {
+        return true;
+    }
+    match e.raw_os_error() {
+        // File system does not support symbolic links
+        #[cfg(unix)]
+        Some(code) => code == libc::EPERM || code == libc::EOPNOTSUPP,
+        #[cfg(windows)]
+        Some(code) => code == ERROR_PRIVILEGE_NOT_HELD,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unsupported(_: &Path, _: &Path) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    fn denied(_: &Path, _: &Path) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::PermissionDenied))
+    }
+
+    #[test]
+    fn symlink_fallback() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = dir.path().join("site");
+        crate::fs::write_string(source.join("index.md"), "# Home")?;
+
+        let target = dir.path().join("fail");
+        assert!(
+            link_with(&source, &target, Fallback::Fail, unsupported).is_err()
+        );
+        assert!(!target.exists());
+
+        // Permission errors are not hidden by the fallback
+        assert!(link_with(&source, &target, Fallback::Copy, denied).is_err());
+        assert!(!target.exists());
+
+        let target = dir.path().join("copy");
+        let kind = link_with(&source, &target, Fallback::Copy, unsupported)?;
+        assert_eq!(LinkKind::Copy, kind);
+        assert_eq!("# Home", crate::fs::read_string(target.join("index.md"))?);
+
+        let target = dir.path().join("link");
+        let kind = soft_fallback(&source, &target, Fallback::Copy)?;
+        assert_eq!(LinkKind::Symlink, kind);
+        assert_eq!(source, std::fs::read_link(&target)?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unsupported_errors() {
+        let unsupported =
+            |code| is_unsupported(&io::Error::from_raw_os_error(code));
+        assert!(unsupported(libc::EPERM));
+        assert!(unsupported(libc::EOPNOTSUPP));
+        assert!(!unsupported(libc::EACCES));
+        assert!(!unsupported(libc::EEXIST));
+        assert!(!unsupported(libc::ENOENT));
+    }
+}
@@ -1,14 +1,107 @@
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
 use crossterm::{
     cursor::{MoveToColumn, MoveUp},
     execute,
     terminal::{Clear, ClearType},
 };
 
+use serde::Serialize;
+use serde_json::{Map, Value};
+
 use crate::Result;
 
+/// Environment variable used to select the output mode.
+pub const OUTPUT_ENV_NAME: &str = "UWE_OUTPUT";
+
+const UNSET: u8 = 0;
+const TEXT: u8 = 1;
+const JSON: u8 = 2;
+
+static OUTPUT_MODE: AtomicU8 = AtomicU8::new(UNSET);
+
+/// How user-facing messages are written.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum OutputMode {
+    /// Styled text for people.
+    #[default]
+    Text,
+    /// Newline-delimited JSON records for tools.
+    Json,
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Unknown output mode {}", s)),
+        }
+    }
+}
+
+/// Set the output mode for the process.
+pub fn set_output_mode(mode: OutputMode) {
+    let value = match mode {
+        OutputMode::Text => TEXT,
+        OutputMode::Json => JSON,
+    };
+    OUTPUT_MODE.store(value, Ordering::SeqCst);
+}
+
+/// Get the output mode, when no mode has been set the
+/// environment variable is used.
+pub fn output_mode() -> OutputMode {
+    match OUTPUT_MODE.load(Ordering::SeqCst) {
+        TEXT => OutputMode::Text,
+        JSON => OutputMode::Json,
+        _ => std::env::var(OUTPUT_ENV_NAME)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default(),
+    }
+}
+
+#[inline]
+pub fn is_json() -> bool {
+    output_mode() == OutputMode::Json
+}
+
+/// Message record for the JSON output mode.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    level: &'a str,
+    message: &'a str,
+    #[serde(flatten)]
+    fields: &'a Map<String, Value>,
+}
+
+/// Write a message as a single line of JSON.
+pub fn write_record<W: Write>(
+    writer: &mut W,
+    level: &str,
+    message: &str,
+    fields: &Map<String, Value>,
+) -> std::io::Result<()> {
+    let record = Record {
+        level,
+        message,
+        fields,
+    };
+    serde_json::to_writer(&mut *writer, &record)?;
+    writer.write_all(b"\n")
+}
+
 #[inline]
 pub fn delimiter() -> String {
     use terminal_size::{terminal_size, Width};
+    if is_json() {
+        return String::from("---");
+    }
     if let Some((Width(w), _)) = terminal_size() {
         "─".repeat(w as usize)
     } else {
@@ -18,6 +111,9 @@ pub fn delimiter() -> String {
 
 #[inline]
 pub fn clear_current_line() -> Result<()> {
+    if is_json() {
+        return Ok(());
+    }
     execute!(
         std::io::stdout(),
         MoveToColumn(0),
@@ -28,6 +124,9 @@ pub fn clear_current_line() -> Result<()> {
 
 #[inline]
 pub fn clear_previous_line() -> Result<()> {
+    if is_json() {
+        return Ok(());
+    }
     execute!(
         std::io::stdout(),
         MoveUp(1),
@@ -36,3 +135,34 @@ pub fn clear_previous_line() -> Result<()> {
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_records() -> std::io::Result<()> {
+        let mut buf: Vec<u8> = Vec::new();
+        let mut fields = Map::new();
+        fields.insert("target".to_string(), Value::from("workspace"));
+        write_record(&mut buf, "info", "Build site", &fields)?;
+        write_record(&mut buf, "warn", "Missing \"alt\"", &Map::new())?;
+
+        let output = String::from_utf8(buf).unwrap();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(2, records.len());
+        assert_eq!("info", records[0]["level"]);
+        assert_eq!("Build site", records[0]["message"]);
+        assert_eq!("workspace", records[0]["target"]);
+        assert_eq!("warn", records[1]["level"]);
+        assert_eq!("Missing \"alt\"", records[1]["message"]);
+        assert!(records[1].get("target").is_none());
+
+        assert_eq!(Ok(OutputMode::Json), "json".parse());
+        assert!("yaml".parse::<OutputMode>().is_err());
+        Ok(())
+    }
+}
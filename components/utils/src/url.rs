@@ -1,21 +1,37 @@
 use std::convert::AsRef;
-use std::path::Path;
 use std::path::MAIN_SEPARATOR;
+use std::path::{Component, Path};
 
+/// Convert a file system path to a URL path.
+///
+/// Any Windows drive or UNC prefix is dropped and a root
+/// directory becomes a single leading slash.
 pub fn to_href_separator<P: AsRef<Path>>(p: P) -> String {
-    return p
-        .as_ref()
-        .iter()
-        .map(|c| c.to_string_lossy().into_owned())
-        .collect::<Vec<_>>()
-        .join("/");
+    let mut absolute = false;
+    let mut parts: Vec<String> = Vec::new();
+    for c in p.as_ref().components() {
+        match c {
+            Component::Prefix(_) => {}
+            Component::RootDir => absolute = true,
+            Component::CurDir => parts.push(".".to_string()),
+            Component::ParentDir => parts.push("..".to_string()),
+            Component::Normal(s) => {
+                parts.push(s.to_string_lossy().into_owned())
+            }
+        }
+    }
+    let href = parts.join("/");
+    if absolute {
+        format!("/{}", href)
+    } else {
+        href
+    }
 }
 
 pub fn to_path_separator(url: &str) -> String {
-    return url
-        .split("/")
-        .collect::<Vec<_>>()
-        .join(&MAIN_SEPARATOR.to_string());
+    url.chars()
+        .map(|c| if c == '/' { MAIN_SEPARATOR } else { c })
+        .collect()
 }
 
 pub fn trim_start_slash(url: &str) -> &str {
@@ -33,3 +49,32 @@ pub fn trim_slash(url: &str) -> &str {
 pub fn is_dir(url: &str) -> bool {
     url.ends_with("/") || !url.contains(".")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn href_nested() {
+        let path = Path::new("docs").join("guide").join("index.html");
+        assert_eq!("docs/guide/index.html", to_href_separator(&path));
+        assert_eq!(
+            path.to_string_lossy(),
+            to_path_separator("docs/guide/index.html")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn href_absolute() {
+        assert_eq!("/site/index.md", to_href_separator("/site/index.md"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn href_windows() {
+        assert_eq!("site/post/a.md", to_href_separator(r"site\post\a.md"));
+        assert_eq!("/site/index.md", to_href_separator(r"C:\site\index.md"));
+        assert_eq!(r"site\post\a.md", to_path_separator("site/post/a.md"));
+    }
+}
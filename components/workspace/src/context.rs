@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use collections::computed::ComputedProviders;
+use compiler::{BuildContext, HelperMap, Output};
+use config::ProfileSettings;
+
+use crate::{
+    project::{collate_project, BuildResult, ProjectBuilder, WorkspaceBuilder},
+    Result,
+};
+
+/// Assemble build contexts for a project without rendering.
+///
+/// Loads the configuration for the profile, prepares the runtime
+/// options, locales, data sources and collation and returns a
+/// `BuildContext` for each language; the context for the fallback
+/// language is always first.
+///
+/// There is no process-wide runtime; the runtime options are
+/// owned by each context (`BuildContext::options`) so contexts
+/// for different projects or profiles may be used at the same time.
+/// Code that renders with a context must read the options from the
+/// context rather than assuming a single global configuration.
+pub struct ContextBuilder {
+    project: PathBuf,
+    args: ProfileSettings,
+    output: Output,
+    helpers: HelperMap,
+    computed: ComputedProviders,
+}
+
+impl ContextBuilder {
+    pub fn new<P: AsRef<Path>>(project: P, args: &ProfileSettings) -> Self {
+        Self {
+            project: project.as_ref().to_path_buf(),
+            args: args.clone(),
+            output: Default::default(),
+            helpers: Default::default(),
+            computed: Default::default(),
+        }
+    }
+
+    /// Set the output sink for the contexts.
+    ///
+    /// By default files are written to the build target on disk.
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Set custom template helpers for the contexts.
+    pub fn helpers(mut self, helpers: HelperMap) -> Self {
+        self.helpers = helpers;
+        self
+    }
+
+    /// Set the providers for computed collections.
+    pub fn computed(mut self, computed: ComputedProviders) -> Self {
+        self.computed = computed;
+        self
+    }
+
+    /// Build the contexts for all the projects in the workspace.
+    pub async fn build(self) -> Result<Vec<Arc<BuildContext>>> {
+        let (output, helpers, computed) =
+            (self.output, self.helpers, self.computed);
+        let workspace_builder =
+            WorkspaceBuilder::new(&self.project, &self.args)?;
+        let projects = workspace_builder
+            .build(|builder| {
+                let builder = builder
+                    .output(output.clone())
+                    .helpers(helpers.clone())
+                    .computed(computed.clone());
+                prepare(builder)
+            })
+            .await?;
+
+        Ok(projects
+            .iter()
+            .flat_map(|project| {
+                project
+                    .renderers
+                    .iter()
+                    .map(|renderer| Arc::clone(&renderer.info.context))
+            })
+            .collect())
+    }
+}
+
+async fn prepare(builder: ProjectBuilder) -> BuildResult {
+    Ok(collate_project(builder).await?.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use config::{test_util::TempProject, ProfileName, ProfileSettings};
+
+    use super::ContextBuilder;
+    use crate::Result;
+
+    #[tokio::test]
+    async fn build_context() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en", "fr"])?;
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+
+        let contexts =
+            ContextBuilder::new(project.root(), &args).build().await?;
+        assert_eq!(2, contexts.len());
+
+        let context = &contexts[0];
+        assert_eq!("localhost", context.config.host());
+        assert_eq!(project.source(), context.options.source);
+        assert_eq!("en", context.locales.languages().fallback());
+
+        let collation = context.collation.read().unwrap();
+        assert!(collation.is_fallback());
+        assert_eq!("en", &*collation.get_lang());
+        assert!(collation
+            .resolve(&project.source().join("index.md"))
+            .is_some());
+
+        let collation = contexts[1].collation.read().unwrap();
+        assert_eq!("fr", &*collation.get_lang());
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use config::diagnostics::{self, Diagnostic};
+
+use crate::Error;
+
+fn config_error(e: &config::Error) -> Diagnostic {
+    use config::Error::*;
+    let message = e.to_string();
+    match e {
+        TooManyRedirects(_) | CyclicRedirect { .. } => {
+            Diagnostic::error(diagnostics::REDIRECT, message)
+        }
+        RedirectFileExists(file) => {
+            Diagnostic::error(diagnostics::REDIRECT, message).with_file(file)
+        }
+        _ => Diagnostic::error(diagnostics::CONFIG, message),
+    }
+}
+
+fn collator_error(e: &collator::Error) -> Diagnostic {
+    use collator::Error::*;
+    let message = e.to_string();
+    match e {
+        NoMenuItem(_) => Diagnostic::error(diagnostics::MENU_LINK, message),
+        NoMenuItemPage(file)
+        | NoMenuLink(file, ..)
+        | NoMenuPagePath(file, _)
+        | NoMenuPage(file, ..) => {
+            Diagnostic::error(diagnostics::MENU_LINK, message).with_file(file)
+        }
+        FrontMatterParse(file, err) => {
+            let diagnostic =
+                Diagnostic::error(diagnostics::FRONT_MATTER, message)
+                    .with_file(file);
+            if let Some((line, _)) = err.line_col() {
+                diagnostic.with_line(line + 1)
+            } else {
+                diagnostic
+            }
+        }
+        LinkCollision(_, _, file)
+        | PermalinkCollision(_, _, file)
+        | InvalidOutputPath(_, file)
+        | NoPermalinkDate(file) => {
+            Diagnostic::error(diagnostics::PERMALINK, message).with_file(file)
+        }
+        DuplicatePermalink(_) => {
+            Diagnostic::error(diagnostics::PERMALINK, message)
+        }
+        NoPageFile(file, _) | NotPageFile(file) => {
+            Diagnostic::error(diagnostics::PAGE_FILE, message).with_file(file)
+        }
+        Config(e) => config_error(e),
+        _ => Diagnostic::error(diagnostics::BUILD, message),
+    }
+}
+
+fn compiler_error(e: &compiler::Error, out: &mut Vec<Diagnostic>) {
+    use compiler::Error::*;
+    let message = e.to_string();
+    match e {
+        Multi { errs } => {
+            for e in errs {
+                compiler_error(e, out);
+            }
+        }
+        LayoutNotFound(_) => {
+            out.push(Diagnostic::error(diagnostics::TEMPLATE, message))
+        }
+        LayoutEngineMismatch(_, _, _, file) => out.push(
+            Diagnostic::error(diagnostics::TEMPLATE, message).with_file(file),
+        ),
+        FrontMatter(_) => {
+            out.push(Diagnostic::error(diagnostics::FRONT_MATTER, message))
+        }
+        Collator(e) => out.push(collator_error(e)),
+        Config(e) => out.push(config_error(e)),
+        _ => out.push(Diagnostic::error(diagnostics::BUILD, message)),
+    }
+}
+
+/// Convert a build error to diagnostics.
+///
+/// Aggregated errors yield a diagnostic for each error.
+pub fn from_error(e: &Error) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    match e {
+        Error::Compiler(e) => compiler_error(e, &mut out),
+        Error::Collator(e) => out.push(collator_error(e)),
+        Error::Config(e) => out.push(config_error(e)),
+        Error::RedirectCollision(_) => {
+            out.push(Diagnostic::error(diagnostics::REDIRECT, e.to_string()))
+        }
+        Error::Locale(_) => {
+            out.push(Diagnostic::error(diagnostics::TRANSLATION, e.to_string()))
+        }
+        _ => out.push(Diagnostic::error(diagnostics::BUILD, e.to_string())),
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use collator::CollateInfo;
+    use config::{
+        diagnostics::{Severity, MENU_LINK},
+        test_util::TempProject,
+        ProfileName, ProfileSettings, RuntimeOptions,
+    };
+
+    use super::from_error;
+    use crate::{Error, Result};
+
+    #[test]
+    fn broken_menu_link() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write(
+            config::SITE_TOML,
+            "lang = \"en\"\nhost = \"localhost\"\n\n[menu.main]\npages = [\"/missing/\"]\n",
+        )?;
+        let config = project.config()?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let mut info = CollateInfo::new("en".to_string(), Default::default());
+        let err = collator::menu::compile(&config, &options, &mut info)
+            .err()
+            .map(Error::from)
+            .expect("broken menu link should fail to compile");
+
+        let diagnostics = from_error(&err);
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(MENU_LINK, diagnostics[0].code);
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert!(diagnostics[0].message.contains("/missing/"));
+
+        let multi = Error::Compiler(compiler::Error::Multi {
+            errs: vec![
+                compiler::Error::Collator(collator::Error::NoMenuItem(
+                    "/a/".to_string(),
+                )),
+                compiler::Error::LayoutNotFound("main".to_string()),
+            ],
+        });
+        let codes = from_error(&multi)
+            .into_iter()
+            .map(|d| d.code)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![MENU_LINK, config::diagnostics::TEMPLATE], codes);
+        Ok(())
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+use config::{fingerprint::FingerprintConfig, FileType, RuntimeOptions};
+
+use crate::{
+    output_manifest::{checksum, collect},
+    Result,
+};
+
+/// Rename assets in a build target to include a content hash and
+/// rewrite references in HTML, CSS and JavaScript files to the new
+/// names.
+///
+/// Stylesheets and scripts are renamed after their own references
+/// are rewritten so that the hash reflects the final content; quoted
+/// strings in scripts are resolved relative to the script like
+/// module imports. Files whose name already carries a hash are
+/// not renamed again and linked files are replaced so that source
+/// files are never modified.
+///
+/// Assets are classified using the file types for the runtime
+/// options unless the configuration lists the extensions to rename.
+///
+/// Returns a map of the original hrefs to the renamed hrefs.
+pub fn fingerprint(
+    target: &Path,
+    config: &FingerprintConfig,
+    options: &RuntimeOptions,
+) -> Result<HashMap<String, String>> {
+    let mut files = Vec::new();
+    collect(target, &mut files)?;
+
+    let mut assets: Vec<(&PathBuf, FileType)> = files
+        .iter()
+        .filter(|f| {
+            let href = href(target, f);
+            let selected = match config.extensions() {
+                Some(extensions) => has_extension(f, extensions),
+                None => is_asset(options.get_type(f)),
+            };
+            selected
+                && !config.is_excluded(&href)
+                && !is_fingerprinted(f, config.length())
+        })
+        .map(|f| (f, options.get_type(f)))
+        .collect();
+    // Stylesheets then scripts last so their references are
+    // rewritten before hashing
+    assets.sort_by_key(|(_, file_type)| match file_type {
+        FileType::Script => 2,
+        FileType::Stylesheet => 1,
+        _ => 0,
+    });
+
+    let mut renames: HashMap<String, String> = HashMap::new();
+    for (file, file_type) in assets {
+        if is_link(file) {
+            write_output(file, fs::read(file)?)?;
+        }
+
+        let base = parent_href(&href(target, file));
+        if file_type == FileType::Stylesheet {
+            let content = fs::read_to_string(file)?;
+            let content = transform::css::rewrite_urls(&content, |url| {
+                resolve(url, &base, &renames)
+            })?;
+            write_output(file, content)?;
+        } else if file_type == FileType::Script {
+            let content = fs::read_to_string(file)?;
+            let content = transform::js::rewrite_strings(&content, |url| {
+                resolve(url, &base, &renames)
+            })?;
+            write_output(file, content)?;
+        }
+
+        let hash = checksum(file)?;
+        let hash = &hash[..config.length().min(hash.len())];
+        let stem = file.file_stem().unwrap().to_string_lossy();
+        let ext = file.extension().unwrap().to_string_lossy();
+        let dest = file.with_file_name(format!("{}.{}.{}", stem, hash, ext));
+        debug!("Fingerprint {} -> {}", file.display(), dest.display());
+        fs::rename(file, &dest)?;
+        renames.insert(href(target, file), href(target, &dest));
+    }
+
+    for file in files.iter().filter(|f| has_extension(f, &["html"])) {
+        let content = fs::read_to_string(file)?;
+        let base = parent_href(&href(target, file));
+        let content = transform::html::rewrite_assets(&content, |url| {
+            resolve(url, &base, &renames)
+        })?;
+        write_output(file, content)?;
+    }
+
+    Ok(renames)
+}
+
+/// File types that are renamed by default.
+fn is_asset(file_type: FileType) -> bool {
+    matches!(
+        file_type,
+        FileType::Stylesheet
+            | FileType::Script
+            | FileType::Image
+            | FileType::Font
+    )
+}
+
+/// Determine if the name of a file already carries a hash.
+fn is_fingerprinted(file: &Path, length: usize) -> bool {
+    let stem = file.file_stem().unwrap().to_string_lossy();
+    match stem.rsplit_once('.') {
+        Some((_, hash)) => {
+            hash.len() == length && hash.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+fn is_link(file: &Path) -> bool {
+    fs::symlink_metadata(file)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// Write an output file, a symbolic link to a source file is
+/// replaced rather than written through.
+pub(crate) fn write_output<C: AsRef<[u8]>>(
+    file: &Path,
+    content: C,
+) -> Result<()> {
+    if is_link(file) {
+        fs::remove_file(file)?;
+    }
+    fs::write(file, content)?;
+    Ok(())
+}
+
+pub(crate) fn has_extension<S: AsRef<str>>(
+    file: &Path,
+    extensions: &[S],
+) -> bool {
+    file.extension()
+        .map(|e| extensions.iter().any(|x| e == x.as_ref()))
+        .unwrap_or(false)
+}
+
+pub(crate) fn href(target: &Path, file: &Path) -> String {
+    let rel = file.strip_prefix(target).unwrap();
+    format!("/{}", utils::url::to_href_separator(rel))
+}
+
+pub(crate) fn parent_href(href: &str) -> String {
+    match href.rfind('/') {
+        Some(pos) => href[..pos + 1].to_string(),
+        None => "/".to_string(),
+    }
+}
+
+/// Resolve a local reference relative to a base directory href.
+///
+/// Returns the normalized href for the target file, the path
+/// portion of the reference and any query string or fragment.
+pub(crate) fn locate<'a>(
+    url: &'a str,
+    base: &str,
+) -> Option<(String, &'a str, &'a str)> {
+    if url.contains("://") || url.starts_with("//") || url.starts_with("data:")
+    {
+        return None;
+    }
+
+    let end = url.find(|c| c == '?' || c == '#').unwrap_or(url.len());
+    let (path, suffix) = url.split_at(end);
+    if path.is_empty() {
+        return None;
+    }
+
+    let absolute = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}{}", base, path)
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in absolute.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    Some((format!("/{}", parts.join("/")), path, suffix))
+}
+
+/// Resolve a reference relative to a base directory href and
+/// if it refers to a renamed asset replace the file name.
+fn resolve(
+    url: &str,
+    base: &str,
+    renames: &HashMap<String, String>,
+) -> Option<String> {
+    let (key, path, suffix) = locate(url, base)?;
+    renames.get(&key).map(|renamed| {
+        let name = &renamed[renamed.rfind('/').unwrap() + 1..];
+        let dir = match path.rfind('/') {
+            Some(pos) => &path[..pos + 1],
+            None => "",
+        };
+        format!("{}{}{}", dir, name, suffix)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_assets() -> Result<()> {
+        let target = tempfile::tempdir()?;
+        let root = target.path();
+        fs::create_dir_all(root.join("css"))?;
+        fs::create_dir_all(root.join("img"))?;
+        fs::create_dir_all(root.join("docs"))?;
+        fs::write(root.join("img").join("logo.png"), "png")?;
+        fs::write(
+            root.join("css").join("app.css"),
+            "a { background: url(../img/logo.png); }",
+        )?;
+        fs::write(root.join("robots.txt"), "User-agent: *")?;
+        fs::write(
+            root.join("docs").join("index.html"),
+            r#"<link rel="stylesheet" href="/css/app.css"><img src="../img/logo.png?v=1">"#,
+        )?;
+
+        fs::write(
+            root.join("app.js"),
+            "fetch('/css/app.css'); import('./vendor.0123abcd.js');",
+        )?;
+        fs::write(root.join("vendor.0123abcd.js"), "")?;
+
+        // Linked assets are replaced rather than written through
+        let source = tempfile::tempdir()?;
+        let icon = source.path().join("icon.svg");
+        fs::write(&icon, "<svg/>")?;
+        std::os::unix::fs::symlink(&icon, root.join("img").join("icon.svg"))?;
+
+        let renames =
+            fingerprint(root, &Default::default(), &Default::default())?;
+        assert_eq!(4, renames.len());
+        assert!(icon.exists());
+        assert!(root.join("vendor.0123abcd.js").exists());
+
+        let css = renames.get("/css/app.css").unwrap();
+        let logo = renames.get("/img/logo.png").unwrap();
+        assert!(css.starts_with("/css/app.") && css.ends_with(".css"));
+        assert_ne!("/css/app.css", css);
+        assert!(root.join(css.trim_start_matches('/')).exists());
+        assert!(!root.join("css").join("app.css").exists());
+        assert!(root.join("robots.txt").exists());
+
+        let logo_name = &logo[5..];
+        let html = fs::read_to_string(root.join("docs").join("index.html"))?;
+        assert_eq!(
+            format!(
+                r#"<link rel="stylesheet" href="{}"><img src="../img/{}?v=1">"#,
+                css, logo_name
+            ),
+            html
+        );
+
+        let content =
+            fs::read_to_string(root.join(css.trim_start_matches('/')))?;
+        assert_eq!(
+            format!("a {{ background: url(../img/{}); }}", logo_name),
+            content
+        );
+
+        let js = renames.get("/app.js").unwrap();
+        let content =
+            fs::read_to_string(root.join(js.trim_start_matches('/')))?;
+        assert_eq!(
+            format!("fetch('{}'); import('./vendor.0123abcd.js');", css),
+            content
+        );
+
+        // Hashed names are not renamed again
+        assert!(fingerprint(root, &Default::default(), &Default::default())?
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn fingerprint_file_types() -> Result<()> {
+        let target = tempfile::tempdir()?;
+        let root = target.path();
+        fs::write(root.join("font.woff2"), "font")?;
+        fs::write(root.join("posts.json"), "[]")?;
+
+        let renames =
+            fingerprint(root, &Default::default(), &Default::default())?;
+        assert_eq!(1, renames.len());
+        assert!(renames.contains_key("/font.woff2"));
+
+        let config: FingerprintConfig = serde_json::from_value(
+            serde_json::json!({"extensions": ["json"]}),
+        )?;
+        let renames = fingerprint(root, &config, &Default::default())?;
+        assert_eq!(1, renames.len());
+        assert!(renames.contains_key("/posts.json"));
+        Ok(())
+    }
+}
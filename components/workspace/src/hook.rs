@@ -1,29 +1,33 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::Arc;
 
 use log::{debug, info};
 
 use compiler::BuildContext;
-use config::{HookConfig, ProfileName};
+use config::{Config, HookConfig, HookStage, ProfileName, RuntimeOptions};
 
 use crate::{Error, Result};
 
-pub enum Phase {
-    Before,
-    After,
-}
-
 pub fn exec(
     ctx: &Arc<BuildContext>,
     hook: &HookConfig,
     changed: Option<&PathBuf>,
 ) -> Result<()> {
     let collation = ctx.collation.read().unwrap();
+    let build_target = collation.get_path().canonicalize()?;
+    command(&ctx.config, &ctx.options, &build_target, hook, changed)
+}
 
-    let project_root = ctx.config.project().canonicalize().map_err(|_| {
-        Error::CanonicalProjectRoot(ctx.config.project().to_path_buf())
+fn command(
+    config: &Config,
+    options: &RuntimeOptions,
+    build_target: &Path,
+    hook: &HookConfig,
+    changed: Option<&PathBuf>,
+) -> Result<()> {
+    let project_root = config.project().canonicalize().map_err(|_| {
+        Error::CanonicalProjectRoot(config.project().to_path_buf())
     })?;
 
     let mut cmd = hook.command.clone();
@@ -37,10 +41,9 @@ pub fn exec(
         cmd = project_root.join(&cmd).to_string_lossy().into_owned();
     }
 
-    let build_source = ctx.options.source.canonicalize()?;
-    let build_target = collation.get_path().canonicalize()?;
+    let build_source = options.source.canonicalize()?;
 
-    let node_env = ctx.options.settings.get_node_env(ctx.config.node());
+    let node_env = options.settings.get_node_env(config.node());
 
     info!("{} {}", cmd, args.join(" "));
     debug!("BUILD_PROJECT {}", project_root.display());
@@ -76,20 +79,13 @@ pub fn exec(
 }
 
 pub fn collect<'a>(
-    hooks: &'a HashSet<HookConfig>,
-    phase: Phase,
+    hooks: &'a Vec<HookConfig>,
+    stage: HookStage,
     name: &ProfileName,
 ) -> Vec<&'a HookConfig> {
     hooks
         .into_iter()
-        .filter(|v| {
-            let after = v.after.is_some() && v.after.unwrap();
-            let result = match phase {
-                Phase::Before => !after,
-                Phase::After => after,
-            };
-            result
-        })
+        .filter(|v| v.stage() == stage)
         .filter(|v| {
             if let Some(ref profiles) = v.profiles {
                 profiles.contains(name)
@@ -110,3 +106,27 @@ pub fn run(
     }
     Ok(())
 }
+
+/// Run the hooks for a stage outside of a build context using
+/// the build target for the profile.
+pub fn run_stage(
+    config: &Config,
+    options: &RuntimeOptions,
+    stage: HookStage,
+) -> Result<()> {
+    if let Some(ref hooks) = config.hooks() {
+        let hooks = collect(hooks.exec(), stage, &options.settings.name);
+        if hooks.is_empty() {
+            return Ok(());
+        }
+        // The target may not exist yet for the pre-build stage
+        let target = options.build_target();
+        let build_target = target
+            .canonicalize()
+            .unwrap_or_else(|_| target.to_path_buf());
+        for hook in hooks {
+            command(config, options, &build_target, hook, None)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::{
+    imageops::FilterType, GenericImageView, ImageFormat, ImageOutputFormat,
+};
+use log::debug;
+
+use config::{images::ImagesConfig, FileType, RuntimeOptions};
+
+use crate::{
+    fingerprint::{has_extension, href, locate, parent_href, write_output},
+    output_manifest::{checksum, collect},
+    Result,
+};
+
+/// Generate resized copies of images in a build target and add
+/// a `srcset` attribute to `<img>` elements that reference them.
+///
+/// Resized images are written next to the original using the
+/// width as a suffix, eg: `hero-480.jpg`; only widths smaller
+/// than the original image are generated. JPEG originals are
+/// recompressed using the configured quality when that reduces
+/// the file size.
+///
+/// Encoded images are cached by content hash in the `cache`
+/// directory so that unchanged images are not processed again.
+/// Recompressed images record the hash of their source so they
+/// are not recompressed again when the source has not changed.
+/// Images that cannot be decoded are skipped.
+///
+/// Only files classified as images by the runtime options with
+/// one of the configured extensions are processed.
+///
+/// Returns a map of image hrefs to the generated `srcset` value.
+pub fn optimize(
+    target: &Path,
+    config: &ImagesConfig,
+    options: &RuntimeOptions,
+    cache: &Path,
+) -> Result<HashMap<String, String>> {
+    let widths = config.widths();
+    let mut files = Vec::new();
+    collect(target, &mut files)?;
+
+    let mut sources: HashMap<String, Vec<(String, u32)>> = HashMap::new();
+    for file in files.iter().filter(|f| {
+        options.get_type(f) == FileType::Image
+            && has_extension(f, config.extensions())
+            && !is_generated(f, &widths)
+    }) {
+        if let Some(variants) = resize(file, config, &widths, cache)? {
+            sources.insert(href(target, file), variants);
+        }
+    }
+
+    for file in files.iter().filter(|f| has_extension(f, &["html"])) {
+        let content = fs::read_to_string(file)?;
+        let base = parent_href(&href(target, file));
+        let content = transform::html::rewrite_images(&content, |url| {
+            srcset(url, &base, &sources)
+        })?;
+        write_output(file, content)?;
+    }
+
+    Ok(sources
+        .iter()
+        .map(|(k, v)| {
+            let value = v
+                .iter()
+                .map(|(name, width)| format!("{} {}w", name, width))
+                .collect::<Vec<_>>()
+                .join(", ");
+            (k.clone(), value)
+        })
+        .collect())
+}
+
+/// Determine if a file is a resized copy written by a previous build.
+fn is_generated(file: &Path, widths: &[u32]) -> bool {
+    let stem = file.file_stem().unwrap().to_string_lossy();
+    widths.iter().any(|width| {
+        let suffix = format!("-{}", width);
+        if let Some(name) = stem.strip_suffix(&suffix) {
+            let mut original = file.with_file_name(name);
+            if let Some(ext) = file.extension() {
+                original.set_extension(ext);
+            }
+            original.exists()
+        } else {
+            false
+        }
+    })
+}
+
+/// Write the resized variants for an image file and return the
+/// file names and widths including the original image.
+fn resize(
+    file: &Path,
+    config: &ImagesConfig,
+    widths: &[u32],
+    cache: &Path,
+) -> Result<Option<Vec<(String, u32)>>> {
+    let format = match ImageFormat::from_path(file) {
+        Ok(format) => format,
+        Err(_) => return Ok(None),
+    };
+
+    let output = match format {
+        ImageFormat::Png => ImageOutputFormat::Png,
+        ImageFormat::Jpeg => ImageOutputFormat::Jpeg(config.quality()),
+        _ => {
+            debug!("Image format not supported {}", file.display());
+            return Ok(None);
+        }
+    };
+
+    let bytes = fs::read(file)?;
+    let image = match image::load_from_memory_with_format(&bytes, format) {
+        Ok(image) => image,
+        Err(e) => {
+            debug!("Skip image {} ({})", file.display(), e);
+            return Ok(None);
+        }
+    };
+
+    // An output recompressed by a previous build uses the
+    // hash of the source for the cached variants
+    let hash = checksum(file)?;
+    let (hash, optimized) =
+        match fs::read_to_string(source_record(cache, &hash)) {
+            Ok(source) => (source, true),
+            Err(_) => (hash, false),
+        };
+    let stem = file.file_stem().unwrap().to_string_lossy();
+    let ext = file.extension().unwrap().to_string_lossy();
+    let original = image.width();
+
+    let mut variants = Vec::new();
+    for width in widths.iter().cloned().filter(|w| *w < original) {
+        let cached = cache.join(format!(
+            "{}-{}-{}.{}",
+            hash,
+            width,
+            config.quality(),
+            ext
+        ));
+        if !cached.exists() {
+            let resized = image.resize(width, u32::MAX, FilterType::Lanczos3);
+            let mut buffer = Vec::new();
+            resized.write_to(&mut buffer, output.clone())?;
+            write_cache(&cached, &buffer)?;
+        }
+
+        let name = format!("{}-{}.{}", stem, width, ext);
+        let dest = file.with_file_name(&name);
+        debug!("Image {} -> {}", file.display(), dest.display());
+        write_output(&dest, fs::read(&cached)?)?;
+        variants.push((name, width));
+    }
+
+    if let (ImageFormat::Jpeg, false) = (format, optimized) {
+        let cached =
+            cache.join(format!("{}-{}.{}", hash, config.quality(), ext));
+        if !cached.exists() {
+            let mut buffer = Vec::new();
+            image.write_to(&mut buffer, output)?;
+            write_cache(&cached, &buffer)?;
+        }
+        if cached.metadata()?.len() < bytes.len() as u64 {
+            write_output(file, fs::read(&cached)?)?;
+            write_cache(
+                &source_record(cache, &checksum(file)?),
+                hash.as_bytes(),
+            )?;
+        }
+    }
+
+    if variants.is_empty() {
+        return Ok(None);
+    }
+
+    let name = file.file_name().unwrap().to_string_lossy().into_owned();
+    variants.push((name, original));
+    Ok(Some(variants))
+}
+
+/// Path for the record of the source hash of a recompressed image.
+fn source_record(cache: &Path, hash: &str) -> PathBuf {
+    cache.join(format!("{}.source", hash))
+}
+
+fn write_cache(file: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(file, content)?;
+    Ok(())
+}
+
+/// Build the `srcset` value for a reference using the same
+/// directory prefix as the `src` attribute.
+fn srcset(
+    url: &str,
+    base: &str,
+    sources: &HashMap<String, Vec<(String, u32)>>,
+) -> Option<String> {
+    let (key, path, _) = locate(url, base)?;
+    sources.get(&key).map(|variants| {
+        let dir = match path.rfind('/') {
+            Some(pos) => &path[..pos + 1],
+            None => "",
+        };
+        variants
+            .iter()
+            .map(|(name, width)| format!("{}{} {}w", dir, name, width))
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responsive_images() -> Result<()> {
+        let target = tempfile::tempdir()?;
+        let cache = tempfile::tempdir()?;
+        let root = target.path();
+        fs::create_dir_all(root.join("img"))?;
+        fs::create_dir_all(root.join("docs"))?;
+
+        let image = image::RgbImage::from_fn(2000, 1000, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        });
+        image.save(root.join("img").join("hero.png"))?;
+        fs::write(root.join("img").join("broken.png"), "not an image")?;
+        fs::write(
+            root.join("docs").join("index.html"),
+            r#"<img src="../img/hero.png"><img src="/img/broken.png">"#,
+        )?;
+
+        let sources = optimize(
+            root,
+            &Default::default(),
+            &Default::default(),
+            cache.path(),
+        )?;
+        assert_eq!(1, sources.len());
+
+        for width in &[480, 960, 1440] {
+            let file = root.join("img").join(format!("hero-{}.png", width));
+            let resized = image::open(&file)?;
+            assert_eq!(*width, resized.width());
+            assert_eq!(width / 2, resized.height());
+        }
+
+        let html = fs::read_to_string(root.join("docs").join("index.html"))?;
+        assert_eq!(
+            r#"<img src="../img/hero.png" srcset="../img/hero-480.png 480w, ../img/hero-960.png 960w, ../img/hero-1440.png 1440w, ../img/hero.png 2000w"><img src="/img/broken.png">"#,
+            html
+        );
+
+        // Second run uses the cache and ignores generated files
+        let sources = optimize(
+            root,
+            &Default::default(),
+            &Default::default(),
+            cache.path(),
+        )?;
+        assert_eq!(1, sources.len());
+        assert!(!root.join("img").join("hero-480-480.png").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn recompress_once() -> Result<()> {
+        let target = tempfile::tempdir()?;
+        let source = tempfile::tempdir()?;
+        let cache = tempfile::tempdir()?;
+        let root = target.path();
+
+        let image = image::RgbImage::from_fn(400, 200, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        });
+        let original = source.path().join("photo.jpg");
+        let mut buffer = Vec::new();
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut buffer, ImageOutputFormat::Jpeg(100))?;
+        fs::write(&original, &buffer)?;
+
+        // Linked outputs are replaced and the source is untouched
+        let file = root.join("photo.jpg");
+        std::os::unix::fs::symlink(&original, &file)?;
+        optimize(root, &Default::default(), &Default::default(), cache.path())?;
+        assert!(!fs::symlink_metadata(&file)?.file_type().is_symlink());
+        assert_eq!(buffer, fs::read(&original)?);
+
+        let compressed = fs::read(&file)?;
+        assert!(compressed.len() < buffer.len());
+
+        // Unchanged outputs are not recompressed
+        optimize(root, &Default::default(), &Default::default(), cache.path())?;
+        assert_eq!(compressed, fs::read(&file)?);
+        Ok(())
+    }
+}
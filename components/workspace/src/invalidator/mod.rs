@@ -1,15 +1,20 @@
-use std::collections::HashSet;
-use std::ffi::OsStr;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
-use config::{hook::HookConfig, FileType};
+use log::warn;
 
+use config::{engine::TemplateEngine, hook::HookConfig};
+
+mod plan;
 mod updater;
 mod utils;
 
 use crate::{Error, Project, Result};
 
+pub use self::plan::{ChangeReason, ChangeSet};
+
 use self::{
+    plan::{plan, PageIndex},
     updater::Updater,
     utils::{canonical, filter_ignores, relative_to},
 };
@@ -26,7 +31,7 @@ pub enum Kind {
     File(PathBuf),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Invalidation {
     // Paths that are ignored but we track for debugging
     pub(crate) ignores: HashSet<PathBuf>,
@@ -120,6 +125,96 @@ impl Invalidator {
         None
     }
 
+    /// Get the output URL paths for the files in an invalidation.
+    ///
+    /// Used by the live reload functionality so clients can
+    /// decide how to handle the changes.
+    pub fn find_changed_hrefs(
+        &self,
+        invalidation: &Invalidation,
+    ) -> Vec<String> {
+        let options = self.updater.options();
+        let mut hrefs = Vec::new();
+        for action in invalidation.actions.iter() {
+            let path = match action {
+                Kind::Page(path) | Kind::File(path) => path,
+            };
+            if let Ok(file) =
+                relative_to(path, &options.source, &options.source)
+            {
+                let mut href = None;
+                for renderer in self.updater.renderers().iter() {
+                    let collation =
+                        renderer.info.context.collation.read().unwrap();
+                    href = collation
+                        .get_link_href(&file)
+                        .map(|link| link.as_str().to_string());
+                    if href.is_some() {
+                        break;
+                    }
+                }
+
+                let href = href.unwrap_or_else(|| {
+                    let rel =
+                        file.strip_prefix(&options.source).unwrap_or(&file);
+                    format!("/{}", ::utils::url::to_href_separator(rel))
+                });
+                hrefs.push(href);
+            }
+        }
+        hrefs
+    }
+
+    /// Determine the output files that would change for a list
+    /// of changed source files without rendering.
+    ///
+    /// Deleted files and hooks are not included.
+    pub fn plan_changes(&self, changed: &[PathBuf]) -> Result<ChangeSet> {
+        let options = self.updater.options();
+        let rule = self.get_invalidation(changed.iter().cloned().collect())?;
+
+        let mut direct = Vec::new();
+        for action in rule.actions.iter() {
+            let path = match action {
+                Kind::Page(path) | Kind::File(path) => path,
+            };
+            direct.push(relative_to(path, &options.source, &options.source)?);
+        }
+
+        let mut changes: ChangeSet = Default::default();
+        for renderer in self.updater.renderers().iter() {
+            let collation = renderer.info.context.collation.read().unwrap();
+            let fallback = collation.fallback.read().unwrap();
+            let pages: HashMap<PathBuf, Option<String>> = fallback
+                .pages
+                .iter()
+                .map(|(path, page_lock)| {
+                    let page = page_lock.read().unwrap();
+                    let layout = if page.is_standalone() {
+                        None
+                    } else {
+                        page.layout.clone()
+                    };
+                    (path.to_path_buf(), layout)
+                })
+                .collect();
+            let index = PageIndex {
+                links: fallback.link_map(),
+                queries: &fallback.queries,
+                templates: fallback.synthetic_templates(),
+                pages,
+            };
+            plan(
+                &mut changes,
+                renderer.info.context.options.build_target(),
+                &rule,
+                &direct,
+                &index,
+            );
+        }
+        Ok(changes)
+    }
+
     pub fn get_invalidation(
         &self,
         paths: HashSet<PathBuf>,
@@ -148,8 +243,6 @@ impl Invalidator {
             collections: HashSet::new(),
         };
 
-        let ext = config.engine().extension().to_string();
-
         let config_file = config.file();
         let cfg_file = config_file.canonicalize()?;
 
@@ -170,13 +263,14 @@ impl Invalidator {
         // NOTE: these files are all optional so we cannot error on
         // NOTE: a call to canonicalize() hence the canonical() helper
 
-        let assets = canonical(options.assets_path());
-        let partials = canonical(options.partials_path());
-        let includes = canonical(options.includes_path());
-        let layouts = canonical(options.layouts_path());
+        let source_paths = options.source_paths();
+        let assets = canonical(source_paths.assets);
+        let partials = canonical(source_paths.partials);
+        let includes = canonical(source_paths.includes);
+        let layouts = canonical(source_paths.layouts);
 
         // FIXME: this does not respect when data sources have a `from` directory configured
-        let collections = canonical(options.collections_path());
+        let collections = canonical(source_paths.collections);
 
         let collections_paths: Vec<(String, PathBuf)> = self
             .updater
@@ -203,16 +297,17 @@ impl Invalidator {
                         }
                     }
 
-                    let is_template = if let Some(extension) = path.extension()
-                    {
-                        extension == OsStr::new(&ext)
-                    } else {
-                        false
-                    };
+                    let is_template =
+                        TemplateEngine::from_path(&path).is_some();
 
-                    // This is not used at the moment but we detect it;
-                    // it corresponds to the site.toml file.
-                    if path == cfg_file {
+                    // Config changes are not applied to a running
+                    // build; this is the site.toml file or a file
+                    // it includes
+                    if path == cfg_file || config.includes().contains(&path) {
+                        warn!(
+                            "Config {} changed, restart to apply changes",
+                            path.display()
+                        );
                         rule.ignores.insert(path);
                     } else if path.starts_with(&layouts) {
                         rule.layouts.insert(path);
@@ -240,13 +335,10 @@ impl Invalidator {
                         }
                     } else {
                         let file_type = options.get_type(&path);
-                        match file_type {
-                            FileType::Unknown => {
-                                rule.actions.push(Kind::File(path));
-                            }
-                            _ => {
-                                rule.actions.push(Kind::Page(path));
-                            }
+                        if file_type.is_page() {
+                            rule.actions.push(Kind::Page(path));
+                        } else {
+                            rule.actions.push(Kind::File(path));
                         }
                     }
                 }
@@ -0,0 +1,199 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use config::indexer::QueryList;
+
+use super::{updater::consumers, Invalidation};
+
+/// Reason an output file is affected by a change.
+#[derive(Debug, Clone, Copy, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeReason {
+    /// The source file for the output changed.
+    Direct,
+    /// A partial, include or template used by the output changed.
+    ViaPartial,
+    /// The layout for the output changed.
+    ViaLayout,
+    /// A collection data source queried by the output changed.
+    ViaData,
+}
+
+/// Output files that would change for a set of changed source files.
+#[derive(Debug, Default, Serialize)]
+pub struct ChangeSet {
+    outputs: BTreeMap<PathBuf, ChangeReason>,
+}
+
+impl ChangeSet {
+    /// Map of affected output files to the reason for the change.
+    pub fn outputs(&self) -> &BTreeMap<PathBuf, ChangeReason> {
+        &self.outputs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Add an output, the first reason for an output is kept.
+    fn insert(&mut self, output: PathBuf, reason: ChangeReason) {
+        self.outputs.entry(output).or_insert(reason);
+    }
+}
+
+/// Pages for a locale used to plan changes.
+pub(crate) struct PageIndex<'a> {
+    /// Map of hrefs to source files.
+    pub links: HashMap<String, PathBuf>,
+    /// Queries declared by pages.
+    pub queries: &'a [(QueryList, Arc<PathBuf>)],
+    /// Map of synthetic pages to the query page that generated them.
+    pub templates: HashMap<PathBuf, PathBuf>,
+    /// Source files for pages with the layout name
+    /// when the page is not standalone.
+    pub pages: HashMap<PathBuf, Option<String>>,
+}
+
+fn output(target: &Path, href: &str) -> PathBuf {
+    target.join(utils::url::to_path_separator(href.trim_start_matches('/')))
+}
+
+/// Add the outputs affected by an invalidation for a locale.
+///
+/// The `direct` paths are the changed source files relative
+/// to the project so they match the paths in the page index.
+pub(crate) fn plan(
+    changes: &mut ChangeSet,
+    target: &Path,
+    rule: &Invalidation,
+    direct: &[PathBuf],
+    index: &PageIndex<'_>,
+) {
+    for (href, path) in index.links.iter() {
+        if direct.contains(path) {
+            changes.insert(output(target, href), ChangeReason::Direct);
+        }
+    }
+
+    if !rule.layouts.is_empty() {
+        let names: HashSet<String> = rule
+            .layouts
+            .iter()
+            .filter_map(|p| p.file_stem())
+            .map(|s| s.to_string_lossy().into_owned())
+            .collect();
+        for (href, path) in index.links.iter() {
+            if let Some(Some(layout)) = index.pages.get(path) {
+                if names.contains(layout) {
+                    changes
+                        .insert(output(target, href), ChangeReason::ViaLayout);
+                }
+            }
+        }
+    }
+
+    if !rule.collections.is_empty() {
+        let names: HashSet<String> =
+            rule.collections.iter().map(|(nm, _)| nm.clone()).collect();
+        let pages = consumers(
+            index.links.clone(),
+            index.queries,
+            &index.templates,
+            &names,
+        );
+        for href in pages.keys() {
+            changes.insert(output(target, href), ChangeReason::ViaData);
+        }
+    }
+
+    // No information on which pages use partials, includes
+    // or templates so all pages are affected
+    if !rule.partials.is_empty()
+        || !rule.includes.is_empty()
+        || !rule.templates.is_empty()
+    {
+        for (href, path) in index.links.iter() {
+            if index.pages.contains_key(path) {
+                changes.insert(output(target, href), ChangeReason::ViaPartial);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::indexer::IndexQuery;
+
+    fn index_query(name: &str) -> IndexQuery {
+        IndexQuery {
+            name: name.to_string(),
+            index: "all".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn query(name: &str) -> QueryList {
+        QueryList::One(index_query(name))
+    }
+
+    #[test]
+    fn data_change_outputs() {
+        let mut links = HashMap::new();
+        let mut pages = HashMap::new();
+        for (href, path) in [
+            ("/index.html", "site/index.md"),
+            ("/authors/index.html", "site/authors/index.md"),
+            ("/about/index.html", "site/about.md"),
+        ] {
+            links.insert(href.to_string(), PathBuf::from(path));
+            pages.insert(PathBuf::from(path), None);
+        }
+        let queries = vec![
+            (query("posts"), Arc::new(PathBuf::from("site/index.md"))),
+            (
+                QueryList::Many(vec![
+                    index_query("authors"),
+                    index_query("posts"),
+                ]),
+                Arc::new(PathBuf::from("site/authors/index.md")),
+            ),
+        ];
+        let index = PageIndex {
+            links,
+            queries: &queries,
+            templates: HashMap::new(),
+            pages,
+        };
+
+        let mut rule: Invalidation = Default::default();
+        rule.collections.insert((
+            "authors".to_string(),
+            PathBuf::from("site/collections/authors/alice.toml"),
+        ));
+
+        let target = PathBuf::from("build/debug");
+        let mut changes: ChangeSet = Default::default();
+        plan(
+            &mut changes,
+            &target,
+            &rule,
+            &[PathBuf::from("site/about.md")],
+            &index,
+        );
+
+        let outputs = changes.outputs();
+        assert_eq!(2, outputs.len());
+        assert_eq!(
+            Some(&ChangeReason::ViaData),
+            outputs.get(&target.join("authors").join("index.html"))
+        );
+        assert_eq!(
+            Some(&ChangeReason::Direct),
+            outputs.get(&target.join("about").join("index.html"))
+        );
+    }
+}
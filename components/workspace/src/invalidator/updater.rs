@@ -6,8 +6,12 @@ use std::sync::{Arc, RwLock};
 
 use log::{info, warn};
 
-use collections::{CollectionDataBase, CollectionsMap};
-use config::{hook::HookConfig, Config, RuntimeOptions, SourceProvider};
+use collections::{synthetic, CollectionDataBase, CollectionsMap, QueryCache};
+use compiler::{timing, Timings};
+use config::{
+    hook::HookConfig, indexer::QueryList, Config, RuntimeOptions,
+    SourceProvider,
+};
 
 use crate::{
     project::Project,
@@ -20,6 +24,36 @@ use super::{
     Invalidation, Kind,
 };
 
+/// Filter a link map to the pages that query any of the named
+/// collections.
+///
+/// Synthetic pages are mapped to the query page that generated
+/// them by `templates` so they are consumers when the query page
+/// queries any of the named collections.
+pub(crate) fn consumers(
+    links: HashMap<String, PathBuf>,
+    queries: &[(QueryList, Arc<PathBuf>)],
+    templates: &HashMap<PathBuf, PathBuf>,
+    names: &HashSet<String>,
+) -> HashMap<String, PathBuf> {
+    let pages: HashSet<&PathBuf> = queries
+        .iter()
+        .filter(|(list, _)| {
+            list.to_vec().iter().any(|q| names.contains(&q.name))
+        })
+        .map(|(_, path)| path.as_ref())
+        .collect();
+    links
+        .into_iter()
+        .filter(|(_, page_path)| {
+            pages.contains(page_path)
+                || templates
+                    .get(page_path)
+                    .map_or(false, |template| pages.contains(template))
+        })
+        .collect()
+}
+
 pub struct Updater {
     project: Project,
 
@@ -67,6 +101,10 @@ impl Updater {
         self.project.renderers()
     }
 
+    pub fn timings(&self) -> &Timings {
+        self.project.timings()
+    }
+
     pub fn has_page_path(&self, href: &str) -> bool {
         self.buffer.contains_key(href)
     }
@@ -278,11 +316,14 @@ impl Updater {
 
     /// Update collections.
     ///
-    /// For now this is very basic and just loads and invalidates the
-    /// entire index.
+    /// Changed collections are rebuilt and swapped into the collections
+    /// map. When the change came from data source files only the pages
+    /// that query those collections are invalidated; changes to pages
+    /// that belong to a pages collection invalidate all pages.
     ///
-    /// We don't know which pages should change so we invalidate all
-    /// pages.
+    /// Computed collections are rebuilt after any change and synthetic
+    /// pages generated from queries on the changed collections are
+    /// generated again and rendered.
     ///
     pub(crate) async fn update_collections(
         &mut self,
@@ -383,6 +424,44 @@ impl Updater {
             }
         }
 
+        // Collections that reference a changed collection embed
+        // its documents so they must be rebuilt afterwards
+        let referencing = {
+            let collections = self.project.collections.read().unwrap();
+            collections.referencing(&db_names)
+        };
+        let mut ordered: Vec<String> = db_names
+            .iter()
+            .filter(|name| !referencing.contains(*name))
+            .cloned()
+            .collect();
+        ordered.extend(referencing.iter().cloned());
+        db_names.extend(referencing);
+
+        // Computed collections are derived from the other
+        // collections so they are rebuilt last
+        if !db_names.is_empty() {
+            let computed = {
+                let collections = self.project.collections.read().unwrap();
+                collections.computed_names()
+            };
+            for name in computed {
+                if !db_names.contains(&name) {
+                    ordered.push(name.clone());
+                    db_names.insert(name);
+                }
+            }
+        }
+
+        // Synthetic pages generated from queries on the changed
+        // collections, these cannot be rendered on demand so they
+        // are rendered once the collections have been updated
+        let mut synthetics = Vec::new();
+
+        // When only data source files changed we can restrict
+        // rendering to the pages that query the changed collections
+        let targeted = matchers.is_empty() && !collections.is_empty();
+
         for (_, renderer) in self.project.iter_mut() {
             // Reload the data for invalidated pages
             //
@@ -397,21 +476,128 @@ impl Updater {
             let fallback = collation.fallback.read().unwrap();
 
             // Rebuild databases for collections that changed
-            let mut collections = renderer.info.collections.write().unwrap();
-            for db_name in db_names.iter() {
-                if let Some(db) = collections.map_mut().get_mut(db_name) {
-                    db.build(
+            for db_name in ordered.iter() {
+                let computed = {
+                    let collections = renderer.info.collections.read().unwrap();
+                    match collections.map().get(db_name) {
+                        Some(db) => {
+                            let provider = db.data_provider();
+                            if let SourceProvider::Computed =
+                                provider.source_provider()
+                            {
+                                info!("Compute {}", db_name);
+                                Some(collections.compute(
+                                    db_name,
+                                    provider,
+                                    &renderer.info.context.config,
+                                    &renderer.info.context.options,
+                                    &fallback,
+                                )?)
+                            } else {
+                                None
+                            }
+                        }
+                        None => None,
+                    }
+                };
+
+                if let Some(db) = computed {
+                    let mut collections =
+                        renderer.info.collections.write().unwrap();
+                    collections.map_mut().insert(db_name.to_string(), db);
+                    continue;
+                }
+
+                let db = {
+                    let collections = renderer.info.collections.read().unwrap();
+                    collections.map().get(db_name).map(|db| {
+                        CollectionDataBase::new(
+                            db.source().clone(),
+                            db.data_provider().clone(),
+                        )
+                    })
+                };
+
+                // Build outside of the lock and swap the new database
+                // in so queries never see a partially built collection
+                if let Some(mut db) = db {
+                    info!("Reload {}", db_name);
+                    db.load(
                         db_name,
                         &*renderer.info.context.config,
                         &*renderer.info.context.options,
                         &fallback,
                     )
                     .await?;
+                    {
+                        let collections =
+                            renderer.info.collections.read().unwrap();
+                        db.resolve_references(db_name, collections.map())?;
+                    }
+                    db.load_indices(db_name)?;
+                    let mut collections =
+                        renderer.info.collections.write().unwrap();
+                    collections.map_mut().insert(db_name.to_string(), db);
                 }
             }
 
+            // Generate synthetic pages again for query pages
+            // that query the changed collections
+            drop(fallback);
+            {
+                let mut fallback = collation.fallback.write().unwrap();
+                let templates: Vec<PathBuf> = fallback
+                    .queries
+                    .iter()
+                    .filter(|(list, path)| {
+                        fallback.expanded.contains_key(path)
+                            && list
+                                .to_vec()
+                                .iter()
+                                .any(|q| db_names.contains(&q.name))
+                    })
+                    .map(|(_, path)| path.to_path_buf())
+                    .collect();
+                let collections = renderer.info.collections.read().unwrap();
+                let mut cache = QueryCache::new();
+                for template in templates.iter() {
+                    synthetic::refresh(
+                        &renderer.info.context.config,
+                        &renderer.info.context.options,
+                        &mut fallback,
+                        &collections,
+                        &mut cache,
+                        template,
+                    )?;
+                }
+            }
+            let fallback = collation.fallback.read().unwrap();
+            let templates = fallback.synthetic_templates();
+
+            let lang = fallback.get_lang().to_string();
+            for (_, path) in consumers(
+                fallback.link_map(),
+                &fallback.queries,
+                &templates,
+                &db_names,
+            ) {
+                if templates.contains_key(&path) {
+                    synthetics.push((lang.clone(), path));
+                }
+            }
+
+            // Update the JIT buffer with pages that query the
+            // changed data sources
+            if targeted {
+                let pages = consumers(
+                    fallback.link_map(),
+                    &fallback.queries,
+                    &templates,
+                    &db_names,
+                );
+                self.buffer.extend(pages);
             // Update the JIT buffer with all pages!
-            if matchers.is_empty() {
+            } else if matchers.is_empty() {
                 let all_pages = fallback.link_map();
                 self.buffer.extend(all_pages);
             // Or filter to respect the collections matcher
@@ -440,6 +626,12 @@ impl Updater {
             }
         }
 
+        for (lang, file) in synthetics {
+            let options =
+                RenderOptions::new_file_lang(file, lang, false, false, false);
+            self.project.render(options).await?;
+        }
+
         Ok(())
     }
 
@@ -518,6 +710,8 @@ impl Updater {
         // Must remove any synthetic pages from the list of pages to render server-side.
         self.filter_synthetics();
 
+        let events = self.project.options.settings.events.clone();
+        let phase = events.phase(timing::RENDER);
         for action in &rule.actions {
             match action {
                 Kind::Page(path) | Kind::File(path) => {
@@ -530,6 +724,8 @@ impl Updater {
                 }
             }
         }
+        drop(phase);
+        events.complete();
         Ok(())
     }
 
@@ -631,3 +827,75 @@ impl Updater {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::indexer::IndexQuery;
+
+    fn index_query(name: &str) -> IndexQuery {
+        IndexQuery {
+            name: name.to_string(),
+            index: "all".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn query(name: &str) -> QueryList {
+        QueryList::One(index_query(name))
+    }
+
+    #[test]
+    fn data_source_consumers() {
+        let mut links = HashMap::new();
+        links.insert("/index.html".to_string(), PathBuf::from("site/index.md"));
+        links.insert(
+            "/authors/index.html".to_string(),
+            PathBuf::from("site/authors/index.md"),
+        );
+        links.insert(
+            "/about/index.html".to_string(),
+            PathBuf::from("site/about.md"),
+        );
+        links.insert(
+            "/tags/rust/index.html".to_string(),
+            PathBuf::from("site/tags/rust.md"),
+        );
+
+        let queries = vec![
+            (query("posts"), Arc::new(PathBuf::from("site/index.md"))),
+            (
+                QueryList::Many(vec![
+                    index_query("authors"),
+                    index_query("posts"),
+                ]),
+                Arc::new(PathBuf::from("site/authors/index.md")),
+            ),
+            (query("tags"), Arc::new(PathBuf::from("site/tags/index.md"))),
+        ];
+
+        // Synthetic page generated by the tags query page
+        let mut templates = HashMap::new();
+        templates.insert(
+            PathBuf::from("site/tags/rust.md"),
+            PathBuf::from("site/tags/index.md"),
+        );
+
+        let mut names = HashSet::new();
+        names.insert("authors".to_string());
+        let pages = consumers(links.clone(), &queries, &templates, &names);
+        assert_eq!(1, pages.len());
+        assert!(pages.contains_key("/authors/index.html"));
+
+        names.insert("posts".to_string());
+        let pages = consumers(links.clone(), &queries, &templates, &names);
+        assert_eq!(2, pages.len());
+        assert!(!pages.contains_key("/about/index.html"));
+
+        let mut names = HashSet::new();
+        names.insert("tags".to_string());
+        let pages = consumers(links, &queries, &templates, &names);
+        assert_eq!(1, pages.len());
+        assert!(pages.contains_key("/tags/rust/index.html"));
+    }
+}
@@ -19,6 +19,9 @@ pub enum Error {
     #[error("Global style file {0} does not exist or is not a file ({1})")]
     NoMainStyle(String, PathBuf),
 
+    #[error("Critical style file {0} does not exist or is not a file ({1})")]
+    NoCriticalStyle(PathBuf, PathBuf),
+
     #[error("App manifest {0} does not exist or is not a file ({1})")]
     NoAppManifest(String, PathBuf),
 
@@ -72,6 +75,9 @@ pub enum Error {
     #[error("Duplicate host name {0}: {1} <-> {2}")]
     DuplicateHostName(String, PathBuf, PathBuf),
 
+    #[error("File {0} is not a page that can be previewed")]
+    PreviewNotPage(PathBuf),
+
     #[error(transparent)]
     Box(#[from] Box<dyn std::error::Error + Sync + Send>),
 
@@ -113,18 +119,37 @@ pub enum Error {
 
     #[error(transparent)]
     Release(#[from] release::Error),
+
+    #[error(transparent)]
+    Transform(#[from] transform::Error),
+
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+mod context;
+pub mod diagnostics;
+mod fingerprint;
 mod hook;
+mod images;
 mod invalidator;
+mod link_check;
 pub mod lock;
 mod manifest;
 mod options;
+pub mod output_manifest;
 mod plugins;
+mod preview;
 mod project;
 mod renderer;
 
-pub use invalidator::Invalidator;
+pub use context::ContextBuilder;
+pub use invalidator::{ChangeReason, ChangeSet, Invalidator};
+pub use output_manifest::{build_manifest, ManifestEntry};
+pub use preview::{render_page, Preview};
 pub use project::*;
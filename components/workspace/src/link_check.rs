@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures::future::join_all;
+use log::debug;
+use reqwest::{Client, StatusCode};
+
+use config::link_check::LinkCheckConfig;
+use transform::html::{is_external, DocumentLinks};
+
+use crate::{
+    fingerprint::{has_extension, href, locate, parent_href},
+    output_manifest::collect,
+    Result,
+};
+
+/// A link in the generated output that could not be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLink {
+    /// Output file that contains the link.
+    pub file: PathBuf,
+    /// The link as it appears in the document.
+    pub link: String,
+    /// Why the link is considered dead.
+    pub reason: String,
+}
+
+impl DeadLink {
+    fn new(file: &Path, link: &str, reason: String) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            link: link.to_string(),
+            reason,
+        }
+    }
+}
+
+/// Check the links and assets referenced by the HTML files in
+/// a build target.
+///
+/// Internal references must resolve to a file in the build target
+/// and any fragment must match an identifier in the target document.
+/// When a base path is given it is removed from root-relative links
+/// before they are resolved.
+///
+/// External links are only checked when enabled in the config.
+pub async fn check(
+    target: &Path,
+    base: Option<&str>,
+    config: &LinkCheckConfig,
+) -> Result<Vec<DeadLink>> {
+    let mut files = Vec::new();
+    collect(target, &mut files)?;
+    files.sort();
+
+    let hrefs: HashSet<String> =
+        files.iter().map(|f| href(target, f)).collect();
+    let mut documents: HashMap<String, DocumentLinks> = HashMap::new();
+    for file in files.iter().filter(|f| has_extension(f, &["html"])) {
+        let content = fs::read_to_string(file)?;
+        documents.insert(href(target, file), transform::html::links(&content)?);
+    }
+
+    let mut dead = Vec::new();
+    let mut external: Vec<(PathBuf, String)> = Vec::new();
+    for file in files.iter().filter(|f| has_extension(f, &["html"])) {
+        let key = href(target, file);
+        let doc = documents.get(&key).unwrap();
+        let dir = parent_href(&key);
+
+        for link in doc.links.iter() {
+            if is_external(link) {
+                if config.external() && !config.is_allowed(link) {
+                    external.push((file.to_path_buf(), link.to_string()));
+                }
+                continue;
+            }
+
+            if let Some(fragment) = link.strip_prefix('#') {
+                if !fragment.is_empty() && !doc.ids.contains(fragment) {
+                    dead.push(DeadLink::new(
+                        file,
+                        link,
+                        format!("missing anchor #{}", fragment),
+                    ));
+                }
+                continue;
+            }
+
+            // Other schemes such as `mailto:` or `data:`
+            if has_scheme(link) {
+                continue;
+            }
+
+            let url = strip_base(link, base);
+            if let Some((path, _, suffix)) = locate(url, &dir) {
+                match resolve(&path, &hrefs) {
+                    Some(dest) => {
+                        if let Some(fragment) = fragment(suffix) {
+                            if let Some(doc) = documents.get(&dest) {
+                                if !doc.ids.contains(fragment) {
+                                    dead.push(DeadLink::new(
+                                        file,
+                                        link,
+                                        format!(
+                                            "missing anchor #{} in {}",
+                                            fragment, dest
+                                        ),
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    None => dead.push(DeadLink::new(
+                        file,
+                        link,
+                        format!("{} does not exist", path),
+                    )),
+                }
+            }
+        }
+    }
+
+    if !external.is_empty() {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout()))
+            .build()?;
+        let urls: HashSet<&String> = external.iter().map(|(_, l)| l).collect();
+        let results = join_all(urls.into_iter().map(|url| {
+            let client = &client;
+            async move { (url.clone(), fetch(client, url).await) }
+        }))
+        .await;
+        let failures: HashMap<String, Option<String>> =
+            results.into_iter().collect();
+        for (file, link) in external.iter() {
+            if let Some(Some(reason)) = failures.get(link) {
+                dead.push(DeadLink::new(file, link, reason.clone()));
+            }
+        }
+    }
+
+    Ok(dead)
+}
+
+/// Request an external URL, returns the reason for a failure.
+async fn fetch(client: &Client, url: &str) -> Option<String> {
+    let url = if url.starts_with("//") {
+        format!("https:{}", url)
+    } else {
+        url.to_string()
+    };
+    debug!("Check {}", url);
+
+    // Some servers do not support HEAD requests
+    let response = match client.head(&url).send().await {
+        Ok(res) if res.status() == StatusCode::METHOD_NOT_ALLOWED => {
+            client.get(&url).send().await
+        }
+        res => res,
+    };
+
+    match response {
+        Ok(res)
+            if res.status().is_client_error()
+                || res.status().is_server_error() =>
+        {
+            Some(format!("status {}", res.status()))
+        }
+        Ok(_) => None,
+        Err(e) => Some(e.to_string()),
+    }
+}
+
+/// Determine if a link begins with a URL scheme.
+fn has_scheme(link: &str) -> bool {
+    let end = link.find(|c| c == '/' || c == '?' || c == '#');
+    let scheme = match end {
+        Some(pos) => &link[..pos],
+        None => link,
+    };
+    scheme.contains(':')
+}
+
+/// Remove a base path from a root-relative link.
+fn strip_base<'a>(link: &'a str, base: Option<&str>) -> &'a str {
+    if let Some(base) = base {
+        if let Some(rest) = link.strip_prefix(base) {
+            if rest.is_empty() {
+                return "/";
+            } else if rest.starts_with('/') {
+                return rest;
+            }
+        }
+    }
+    link
+}
+
+/// Fragment from the query and fragment suffix of a link.
+fn fragment(suffix: &str) -> Option<&str> {
+    suffix
+        .find('#')
+        .map(|pos| &suffix[pos + 1..])
+        .filter(|f| !f.is_empty())
+}
+
+/// Resolve a normalized href to a file in the build target,
+/// directories resolve to their index page.
+fn resolve(path: &str, hrefs: &HashSet<String>) -> Option<String> {
+    if hrefs.contains(path) {
+        return Some(path.to_string());
+    }
+    let index = format!("{}/index.html", path.trim_end_matches('/'));
+    if hrefs.contains(&index) {
+        return Some(index);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn dead_internal_link() -> Result<()> {
+        let target = tempfile::tempdir()?;
+        let root = target.path();
+        fs::create_dir_all(root.join("guide"))?;
+        fs::write(root.join("logo.png"), "png")?;
+        fs::write(
+            root.join("guide").join("index.html"),
+            r#"<h2 id="usage">Usage</h2><a href="../">Home</a>"#,
+        )?;
+        fs::write(
+            root.join("index.html"),
+            r##"<img src="logo.png"><a href="/guide/#usage">Guide</a><a href="/missing/">Missing</a><a href="guide/#setup">Setup</a><a href="mailto:me@example.com">Mail</a><a href="https://example.com/">External</a>"##,
+        )?;
+
+        let dead = check(root, None, &Default::default()).await?;
+        assert_eq!(2, dead.len());
+        assert_eq!(root.join("index.html"), dead[0].file);
+        assert_eq!("/missing/", dead[0].link);
+        assert_eq!("guide/#setup", dead[1].link);
+        assert!(dead[1].reason.contains("#setup"));
+        Ok(())
+    }
+}
@@ -39,6 +39,8 @@ fn to_options(
         return Err(Error::TargetAbsolute(args.target.clone()));
     }
 
+    config::validate::paths(project, &args.name.to_string(), args)?;
+
     let source = project.join(&args.source);
 
     let profile_name_path = PathBuf::from(args.name.to_string());
@@ -53,7 +55,11 @@ fn to_options(
     let incremental = args.is_incremental();
     let pristine = args.is_pristine();
 
-    if (pristine || args.is_force()) && base.exists() {
+    // Cleaning stale outputs replaces the pristine behavior
+    // so unrelated files in the build target are preserved,
+    // stale outputs are removed once the build has completed
+    let clean_stale = args.is_clean_stale() && !args.is_force();
+    if !clean_stale && (pristine || args.is_force()) && base.exists() {
         info!("clean {}", base.display());
         fs::remove_dir_all(&base)?;
     }
@@ -94,6 +100,9 @@ fn to_options(
     if let Some(resources) = settings.resources.as_mut() {
         resources.prepare();
     }
+    if let Some(sources) = settings.sources.as_mut() {
+        sources.prepare();
+    }
 
     let opts =
         RuntimeOptions::new(project.to_path_buf(), source, base, settings);
@@ -130,6 +139,12 @@ fn from_cli(settings: &mut ProfileSettings, args: &mut ProfileSettings) {
     if args.release.is_some() {
         settings.release = args.release;
     }
+    if args.minify.is_some() {
+        settings.minify = args.minify;
+    }
+    if args.dump_context.is_some() {
+        settings.dump_context = args.dump_context;
+    }
     if args.host.is_some() {
         settings.host = args.host.clone();
     }
@@ -142,9 +157,26 @@ fn from_cli(settings: &mut ProfileSettings, args: &mut ProfileSettings) {
     if args.exec.is_some() {
         settings.exec = args.exec.clone();
     }
+    if args.include_drafts.is_some() {
+        settings.include_drafts = args.include_drafts;
+    }
     if args.tls.is_some() {
         settings.tls = args.tls.clone();
     }
+    if args.clean_stale.is_some() {
+        settings.clean_stale = args.clean_stale;
+    }
+    if args.keep.is_some() {
+        settings.keep = args.keep.clone();
+    }
+    if args.memory.is_some() {
+        settings.memory = args.memory;
+    }
+
+    // Events are always shared so the caller may subscribe
+    // before or after the options are prepared
+    settings.events = args.events.clone();
+    settings.diagnostics.set_events(args.events.clone());
 }
 
 /// Prepare the live reload style and script.
@@ -313,20 +345,61 @@ fn prepare_manifest(cfg: &mut Config, opts: &RuntimeOptions) -> Result<()> {
     Ok(())
 }
 
+/// Load the critical styles to inline in each page.
+fn prepare_critical_style(
+    cfg: &mut Config,
+    opts: &RuntimeOptions,
+) -> Result<()> {
+    if !opts.settings.should_inline_critical_style() {
+        return Ok(());
+    }
+
+    let critical = cfg.critical_style_mut().get_or_insert(Default::default());
+    let file = opts.source.join(critical.source());
+    if !file.exists() || !file.is_file() {
+        return Err(Error::NoCriticalStyle(
+            critical.source().to_path_buf(),
+            file,
+        ));
+    }
+    critical.set_content(fs::read_to_string(&file)?);
+    Ok(())
+}
+
+/// Prepare the resource hints for the document head.
+fn prepare_hints(cfg: &mut Config) -> Result<()> {
+    let links = match cfg.hints() {
+        Some(hints) => hints.links(cfg)?,
+        None => return Ok(()),
+    };
+    let global_page = cfg.page.get_or_insert(Default::default());
+    for link in links {
+        global_page.links_mut().insert(link);
+    }
+    Ok(())
+}
+
 pub(crate) async fn prepare(
     cfg: &mut Config,
     args: &ProfileSettings,
     members: &Vec<Member>,
 ) -> Result<RuntimeOptions> {
     // Start with the base `build` profile
-    let mut root = cfg.build.as_ref().unwrap().clone();
+    let mut build = cfg.build.as_ref().unwrap().clone();
     let profiles = cfg.profile.as_ref().unwrap();
 
-    let mut overlay: ProfileSettings = match args.name {
+    let mut root: ProfileSettings = match args.name {
+        // Defaults for the standard profiles are beneath
+        // the `build` profile settings
         ProfileName::Debug
         | ProfileName::Release
         | ProfileName::Dist
-        | ProfileName::Test => ProfileSettings::from(&args.name),
+        | ProfileName::Test => {
+            let mut profile = ProfileSettings::from(&args.name);
+            profile.append(&mut build);
+            profile.name = args.name.clone();
+            profile
+        }
         ProfileName::Custom(ref s) => {
             let mut profile = profiles
                 .get(s)
@@ -334,12 +407,11 @@ pub(crate) async fn prepare(
                 .ok_or_else(|| Error::NoProfile(s.to_string()))?;
 
             profile.name = ProfileName::Custom(s.to_string());
-            profile
+            build.append(&mut profile);
+            build
         }
     };
 
-    root.append(&mut overlay);
-
     let mut input = args.clone();
     from_cli(&mut root, &mut input);
 
@@ -368,6 +440,8 @@ pub(crate) async fn prepare(
     prepare_style(cfg, &opts)?;
     prepare_script(cfg, &opts)?;
     prepare_manifest(cfg, &opts)?;
+    prepare_hints(cfg)?;
+    prepare_critical_style(cfg, &opts)?;
 
     // Member URLs for linking between workspaces
     if !members.is_empty() {
@@ -397,3 +471,65 @@ pub(crate) async fn prepare(
 
     Ok(opts)
 }
+
+#[cfg(test)]
+mod tests {
+    use config::{test_util::TempProject, ProfileName, ProfileSettings};
+
+    use crate::Result;
+
+    const LAYOUT: &str = r#"<html>
+  <body>
+    <p>{{title}}</p>
+    {{scripts}}
+  </body>
+</html>
+"#;
+
+    async fn build(project: &TempProject, name: ProfileName) -> Result<String> {
+        let target = project.root().join("build").join(name.to_string());
+        // Arguments as given on the command line
+        let args = ProfileSettings {
+            name: name.clone(),
+            ..Default::default()
+        };
+        crate::compile(project.root(), &args, Default::default(), false)
+            .await?;
+        let file = [target.join("index.html"), target.join("en/index.html")]
+            .iter()
+            .find(|file| file.exists())
+            .cloned()
+            .expect("index page was not written");
+        Ok(std::fs::read_to_string(file)?)
+    }
+
+    #[tokio::test]
+    async fn profile_live_minify_defaults() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project.write("site/layouts/main.hbs", LAYOUT)?;
+        project.write("site/index.md", "+++\ntitle = \"Home\"\n+++\n")?;
+        let live = livereload::javascript();
+
+        let output = build(&project, ProfileName::Release).await?;
+        assert!(output.contains("<p>Home</p>"));
+        assert!(!output.contains("\n  <body>"));
+        assert!(!output.contains(&live));
+
+        // Live reload is enabled by the development server
+        let output = build(&project, ProfileName::Debug).await?;
+        assert!(output.contains("\n  <body>"));
+        assert!(!output.contains(&live));
+
+        // Build settings take precedence over the profile defaults
+        let site =
+            std::fs::read_to_string(project.root().join(config::SITE_TOML))?;
+        project.write(
+            config::SITE_TOML,
+            format!("{}\n[build]\nminify = true\n", site),
+        )?;
+        let output = build(&project, ProfileName::Debug).await?;
+        assert!(output.contains("<p>Home</p>"));
+        assert!(!output.contains("\n  <body>"));
+        Ok(())
+    }
+}
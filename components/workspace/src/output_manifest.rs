@@ -0,0 +1,331 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::Result;
+
+/// Name of the output manifest file written to the build target.
+pub const FILE: &str = config::OUTPUT_MANIFEST;
+
+/// An output file and the checksum of its content.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Path relative to the build target using forward slashes.
+    pub path: String,
+    /// SHA3-256 checksum of the file content as a hex string.
+    pub checksum: String,
+    /// File size in bytes.
+    pub size: u64,
+}
+
+/// Compute the manifest of all files in a build target sorted by path.
+///
+/// The output manifest file itself is not included.
+pub fn build_manifest<P: AsRef<Path>>(target: P) -> Result<Vec<ManifestEntry>> {
+    let target = target.as_ref();
+    let mut files = Vec::new();
+    collect(target, &mut files)?;
+
+    let mut entries = Vec::new();
+    for file in files {
+        let rel = file.strip_prefix(target)?;
+        if rel == Path::new(FILE) {
+            continue;
+        }
+        entries.push(ManifestEntry {
+            path: utils::url::to_href_separator(rel),
+            checksum: checksum(&file)?,
+            size: file.metadata()?.len(),
+        });
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Paths that were added, removed or changed between two manifests.
+pub fn changed(
+    before: &[ManifestEntry],
+    after: &[ManifestEntry],
+) -> Vec<String> {
+    let previous: HashMap<&str, &ManifestEntry> =
+        before.iter().map(|e| (&e.path[..], e)).collect();
+    let current: HashSet<&str> = after.iter().map(|e| &e.path[..]).collect();
+
+    let mut paths: Vec<String> = after
+        .iter()
+        .filter(|a| previous.get(&a.path[..]) != Some(a))
+        .map(|a| a.path.clone())
+        .collect();
+    for b in before {
+        if !current.contains(&b.path[..]) {
+            paths.push(b.path.clone());
+        }
+    }
+    paths.sort();
+    paths
+}
+
+/// Compute the manifest of the outputs of a build sorted by path.
+///
+/// A file is an output when it was recorded by the build or it
+/// was modified after the build started; files matching the keep
+/// list are not outputs.
+pub fn outputs<P: AsRef<Path>>(
+    target: P,
+    recorded: &HashSet<PathBuf>,
+    started: SystemTime,
+    keep: &GlobSet,
+) -> Result<Vec<ManifestEntry>> {
+    let entries = build_manifest(target.as_ref())?
+        .into_iter()
+        .filter(|e| !keep.is_match(&e.path))
+        .filter(|e| {
+            let file =
+                target.as_ref().join(utils::url::to_path_separator(&e.path));
+            recorded.contains(&file)
+                || file
+                    .symlink_metadata()
+                    .and_then(|m| m.modified())
+                    .map(|t| t >= started)
+                    .unwrap_or(false)
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Current time of the file system for a build target.
+///
+/// File systems may record modification times with a coarser
+/// clock than the system clock so outputs are compared with
+/// this time rather than the system time.
+pub fn now<P: AsRef<Path>>(target: P) -> Result<SystemTime> {
+    let probe = target.as_ref().join(".uwe-build");
+    fs::write(&probe, "")?;
+    let modified = probe.metadata()?.modified()?;
+    fs::remove_file(&probe)?;
+    Ok(modified)
+}
+
+/// Write the manifest for a build target to the target directory.
+pub fn write<P: AsRef<Path>>(
+    target: P,
+    entries: &[ManifestEntry],
+) -> Result<PathBuf> {
+    let file = target.as_ref().join(FILE);
+    utils::fs::write_string(&file, serde_json::to_string_pretty(entries)?)?;
+    Ok(file)
+}
+
+/// Paths of the files in a build target relative to the target
+/// using forward slashes; the output manifest file is not included.
+pub fn files<P: AsRef<Path>>(target: P) -> Result<Vec<String>> {
+    let target = target.as_ref();
+    let mut files = Vec::new();
+    collect(target, &mut files)?;
+
+    let mut paths = Vec::new();
+    for file in files {
+        let rel = file.strip_prefix(target)?;
+        if rel != Path::new(FILE) {
+            paths.push(utils::url::to_href_separator(rel));
+        }
+    }
+    Ok(paths)
+}
+
+/// Read the manifest written to a build target by a previous build.
+pub fn read<P: AsRef<Path>>(target: P) -> Result<Option<Vec<ManifestEntry>>> {
+    let file = target.as_ref().join(FILE);
+    if !file.exists() {
+        return Ok(None);
+    }
+    let content = utils::fs::read_string(&file)?;
+    Ok(Some(serde_json::from_str(&content)?))
+}
+
+/// Remove the outputs of a previous build that are not outputs
+/// of the current build and return the removed paths.
+///
+/// Only files listed in the manifest of the previous build are
+/// removed so unrelated files in the target are preserved; files
+/// matching the keep list are never removed.
+pub fn remove_stale<P: AsRef<Path>>(
+    target: P,
+    before: &[ManifestEntry],
+    after: &[ManifestEntry],
+    keep: &GlobSet,
+) -> Result<Vec<String>> {
+    let target = target.as_ref();
+    let current: HashSet<&str> = after.iter().map(|e| &e.path[..]).collect();
+
+    // Never follow symbolic links out of the build target
+    let root = target.canonicalize()?;
+    let mut removed = Vec::new();
+    for entry in before {
+        if current.contains(&entry.path[..]) || keep.is_match(&entry.path) {
+            continue;
+        }
+        let file = target.join(utils::url::to_path_separator(&entry.path));
+        let parent = match file.parent().and_then(|p| p.canonicalize().ok()) {
+            Some(parent) => parent,
+            None => continue,
+        };
+        if !parent.starts_with(&root) || file.symlink_metadata().is_err() {
+            continue;
+        }
+        fs::remove_file(&file)?;
+        removed.push(entry.path.clone());
+        prune(target, file.parent())?;
+    }
+    Ok(removed)
+}
+
+/// Remove empty directories up to the build target.
+fn prune(target: &Path, dir: Option<&Path>) -> Result<()> {
+    let mut dir = dir;
+    while let Some(path) = dir {
+        if path == target || fs::read_dir(path)?.next().is_some() {
+            break;
+        }
+        fs::remove_dir(path)?;
+        dir = path.parent();
+    }
+    Ok(())
+}
+
+pub(crate) fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn checksum(file: &Path) -> Result<String> {
+    let mut reader = File::open(file)?;
+    let mut hasher = Sha3_256::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{test_util::TempProject, ProfileName, ProfileSettings};
+    use std::time::Duration;
+
+    fn build(target: &Path, about: &str) -> Result<()> {
+        fs::create_dir_all(target.join("docs"))?;
+        fs::write(target.join("index.html"), "<p>Home</p>")?;
+        fs::write(target.join("docs").join("about.html"), about)?;
+        Ok(())
+    }
+
+    #[test]
+    fn identical_builds() -> Result<()> {
+        let first = tempfile::tempdir()?;
+        let second = tempfile::tempdir()?;
+        build(first.path(), "<p>About</p>")?;
+        build(second.path(), "<p>About</p>")?;
+
+        let before = build_manifest(first.path())?;
+        assert_eq!(2, before.len());
+        assert_eq!("docs/about.html", before[0].path);
+        assert_eq!(before, build_manifest(second.path())?);
+
+        // Writing the manifest does not change the manifest
+        write(second.path(), &before)?;
+        assert_eq!(before, build_manifest(second.path())?);
+
+        build(second.path(), "<p>Changed</p>")?;
+        let after = build_manifest(second.path())?;
+        assert_eq!(
+            vec!["docs/about.html".to_string()],
+            changed(&before, &after)
+        );
+
+        fs::remove_file(second.path().join("index.html"))?;
+        fs::write(second.path().join("contact.html"), "<p>Contact</p>")?;
+        let after = build_manifest(second.path())?;
+        assert_eq!(
+            vec!["contact.html", "docs/about.html", "index.html"],
+            changed(&before, &after)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn clean_stale_outputs() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        project
+            .write("site/about.md", "+++\nstandalone = true\n+++\n# About")?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        args.clean_stale = Some(true);
+        args.keep = serde_json::from_str(r#"["CNAME"]"#)?;
+
+        // Files in the target before the first build are not outputs
+        let target = project.root().join("build").join("debug");
+        project.write("build/debug/CNAME", "example.com")?;
+        project.write("build/debug/notes.txt", "notes")?;
+        let past = SystemTime::now() - Duration::from_secs(60);
+        filetime::set_file_mtime(
+            target.join("notes.txt"),
+            filetime::FileTime::from_system_time(past),
+        )?;
+
+        crate::compile(project.root(), &args, Default::default(), false)
+            .await?;
+        let before = read(&target)?.unwrap();
+        let about: Vec<&ManifestEntry> =
+            before.iter().filter(|e| e.path.contains("about")).collect();
+        assert_eq!(1, about.len());
+        assert!(before
+            .iter()
+            .all(|e| e.path != "CNAME" && e.path != "notes.txt"));
+
+        // A failed build does not remove any outputs
+        project.write("site/index.md", "+++\ntitle = \n+++\n# Home")?;
+        assert!(crate::compile(
+            project.root(),
+            &args,
+            Default::default(),
+            false
+        )
+        .await
+        .is_err());
+        assert!(before.iter().all(|e| target.join(&e.path).exists()));
+        assert_eq!(before, read(&target)?.unwrap());
+
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        fs::remove_file(project.source().join("about.md"))?;
+        crate::compile(project.root(), &args, Default::default(), false)
+            .await?;
+
+        let after = read(&target)?.unwrap();
+        assert_eq!(before.len() - 1, after.len());
+        assert!(!target.join(&about[0].path).exists());
+        assert!(after.iter().all(|e| !e.path.contains("about")));
+        assert!(target.join("CNAME").exists());
+        assert!(target.join("notes.txt").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use compiler::{run, Output, OutputSink};
+use config::{ProfileName, ProfileSettings};
+
+use crate::{
+    project::{
+        collate_project, BuildResult, Project, ProjectBuilder, WorkspaceBuilder,
+    },
+    Error, Result,
+};
+
+type SinkResult = std::result::Result<(), compiler::Error>;
+
+/// Output sink that captures rendered pages in memory,
+/// resource files are not copied.
+#[derive(Debug, Default)]
+struct PreviewSink {
+    pages: Mutex<HashMap<PathBuf, Vec<u8>>>,
+}
+
+impl OutputSink for PreviewSink {
+    fn write(&self, dest: &Path, content: &[u8]) -> SinkResult {
+        let mut pages = self.pages.lock().unwrap();
+        pages.insert(dest.to_path_buf(), content.to_vec());
+        Ok(())
+    }
+
+    fn copy(&self, _file: &Path, _dest: &Path) -> SinkResult {
+        Ok(())
+    }
+
+    fn link(&self, _file: &Path, _dest: &Path) -> SinkResult {
+        Ok(())
+    }
+}
+
+/// Prepare a project for rendering without rendering any pages.
+async fn compile(builder: ProjectBuilder, output: Output) -> BuildResult {
+    Ok(collate_project(builder.output(output)).await?.build()?)
+}
+
+/// Render single pages using the full project context.
+///
+/// The project configuration, collections and templates are loaded
+/// once so the preview may be kept and used to render pages as they
+/// are edited.
+pub struct Preview {
+    projects: Vec<Project>,
+    sink: Arc<PreviewSink>,
+}
+
+impl Preview {
+    /// Load a project and prepare it for rendering pages.
+    pub async fn new<P: AsRef<Path>>(
+        project: P,
+        args: &ProfileSettings,
+    ) -> Result<Self> {
+        let sink = Arc::new(PreviewSink::default());
+        let output = Output::new(Arc::clone(&sink) as Arc<dyn OutputSink>);
+        let workspace_builder = WorkspaceBuilder::new(project.as_ref(), args)?;
+        let projects = workspace_builder
+            .build(|builder| compile(builder, output.clone()))
+            .await?;
+        Ok(Self { projects, sink })
+    }
+
+    /// Render a page in the site source to an HTML string.
+    ///
+    /// The page data is reloaded before rendering so changes to the
+    /// front matter are used; the page is rendered for the default
+    /// language of the project that contains the file.
+    pub async fn render_page<P: AsRef<Path>>(
+        &mut self,
+        file: P,
+    ) -> Result<String> {
+        let file = file.as_ref().to_path_buf();
+        let project = self
+            .projects
+            .iter()
+            .find(|p| file.starts_with(&p.options.source))
+            .ok_or_else(|| Error::OutsideSourceTree(file.clone()))?;
+
+        let (parser, renderer) = project
+            .parsers
+            .iter()
+            .zip(project.renderers.iter())
+            .find(|(_, r)| {
+                let collation = r.info.context.collation.read().unwrap();
+                collation.is_fallback()
+            })
+            .ok_or_else(|| Error::PreviewNotPage(file.clone()))?;
+
+        let types = project.options.settings.types.as_ref().unwrap();
+        if !project.options.has_parse_file_match(&file, types) {
+            return Err(Error::PreviewNotPage(file));
+        }
+
+        renderer.reload(&file)?;
+
+        self.sink.pages.lock().unwrap().clear();
+        run::one(&renderer.info.context, parser, &file).await?;
+
+        // Skip the page data sidecar file when it was written
+        let mut pages = self.sink.pages.lock().unwrap();
+        let dest = pages
+            .keys()
+            .find(|dest| !pages.contains_key(&dest.with_extension("")))
+            .cloned()
+            .ok_or_else(|| Error::PreviewNotPage(file))?;
+        let content = pages.remove(&dest).unwrap();
+        Ok(String::from_utf8_lossy(&content).into_owned())
+    }
+}
+
+/// Render a single page of a project to an HTML string.
+///
+/// Loads the project for each call, use a `Preview` to render
+/// pages repeatedly.
+pub async fn render_page<P: AsRef<Path>, F: AsRef<Path>>(
+    project: P,
+    file: F,
+) -> Result<String> {
+    let mut args = ProfileSettings::from(&ProfileName::Debug);
+    args.live = Some(false);
+    let mut preview = Preview::new(project, &args).await?;
+    preview.render_page(file).await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use compiler::run::context_file;
+    use config::{test_util::TempProject, ProfileName, ProfileSettings};
+
+    use super::{render_page, Preview};
+    use crate::Result;
+
+    const PAGE: &str =
+        "<!--\ntitle = \"Preview\"\nstandalone = true\n-->\n<h1>{{title}}</h1>";
+
+    const SITE: &str = r#"lang = "en"
+host = "localhost"
+
+[build]
+extend = ["html"]
+"#;
+
+    #[tokio::test]
+    async fn preview_page() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project.write(config::SITE_TOML, SITE)?;
+        let file = project.write("site/preview.html", PAGE)?;
+
+        let result = render_page(project.root(), &file).await?;
+        assert_eq!("<h1>Preview</h1>", result.trim());
+
+        // Edits are used without loading the project again
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        let mut preview = Preview::new(project.root(), &args).await?;
+        project
+            .write("site/preview.html", PAGE.replace("Preview", "Edited"))?;
+        let result = preview.render_page(&file).await?;
+        assert_eq!("<h1>Edited</h1>", result.trim());
+
+        assert!(preview
+            .render_page(project.root().join("other.html"))
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dump_page_context() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project.write(
+            config::SITE_TOML,
+            r#"lang = "en"
+host = "localhost"
+
+[build]
+extend = ["html"]
+
+[db.load.posts]
+provider = "files"
+type = "json"
+from = "posts"
+
+[db.load.posts.index.all]
+key = "*"
+"#,
+        )?;
+        project.write("site/posts/hello.json", r#"{"title": "Hello"}"#)?;
+        let file = project.write(
+            "site/posts.html",
+            r#"<!--
+title = "Posts"
+standalone = true
+
+[query]
+name = "posts"
+index = "all"
+include-docs = true
+-->
+<h1>{{title}}</h1>"#,
+        )?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        args.dump_context = Some(true);
+        let mut preview = Preview::new(project.root(), &args).await?;
+        let result = preview.render_page(&file).await?;
+        assert_eq!("<h1>Posts</h1>", result.trim());
+
+        // The sidecar file is left in the sink after the page
+        let pages = preview.sink.pages.lock().unwrap();
+        let content = pages
+            .iter()
+            .find(|(dest, _)| context_file(dest.with_extension("")) == **dest)
+            .map(|(_, content)| content)
+            .expect("page context file");
+        let context: Value = serde_json::from_slice(content)?;
+        assert_eq!(json!("Posts"), context["title"]);
+        assert_eq!(json!("Hello"), context["result"][0]["value"]["title"]);
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
 
 use log::{debug, info, warn};
 
@@ -12,10 +13,18 @@ use url::Url;
 use collator::{
     self, menu, CollateInfo, CollateRequest, CollateResult, Collation,
 };
-use compiler::{parser, parser::Parser, BuildContext};
+use compiler::{
+    parser,
+    parser::Parser,
+    process::{CommandProcessor, ProcessorMap},
+    timing, BuildContext, HelperMap, MemorySink, Output, Timings,
+};
 
 use config::{
-    hook::HookConfig,
+    cache::CacheManifest,
+    generator::BuildInfo,
+    hook::{HookConfig, HookStage},
+    memfs::{DynamicMemoryFileSystem, MemoryFileSystem},
     plugin_cache::PluginCache,
     profile::Profiles,
     redirect::{RedirectConfig, Redirects},
@@ -24,12 +33,14 @@ use config::{
     Config, ProfileSettings, RuntimeOptions,
 };
 
-use collections::{synthetic, CollectionsMap, QueryCache};
+use collections::{
+    computed::ComputedProviders, synthetic, CollectionsMap, QueryCache,
+};
 
 use locale::Locales;
 
 use crate::{
-    lock,
+    hook, lock,
     manifest::Manifest,
     plugins,
     renderer::{CompilerInput, RenderFilter, RenderOptions, Renderer, Sources},
@@ -41,6 +52,13 @@ pub type BuildResult =
 
 const PLUGIN_SYNTAX: &str = "std::syntax";
 
+/// Name of the file for the persisted syntax highlighting cache.
+const SYNTAX_CACHE: &str = "highlight.json";
+
+fn syntax_cache_file() -> Result<PathBuf> {
+    Ok(dirs::syntax_cache_dir()?.join(SYNTAX_CACHE))
+}
+
 fn get_manifest_file(options: &RuntimeOptions) -> PathBuf {
     let mut manifest_file = options.build_target().clone();
     manifest_file.set_extension(config::JSON);
@@ -121,10 +139,21 @@ pub async fn new_project_builder(
 ) -> Result<ProjectBuilder> {
     let options = crate::options::prepare(&mut config, args, members).await?;
     let redirects = config.redirects().clone();
+    let timings = Timings::new(options.settings.should_record_timings());
+    let output = if options.settings.is_memory() {
+        Output::new(Arc::new(MemorySink::new(
+            options.build_target().to_path_buf(),
+            DynamicMemoryFileSystem::new(),
+        )))
+    } else {
+        Default::default()
+    };
     let builder = ProjectBuilder {
         config: config,
         options,
         redirects,
+        timings,
+        output,
         ..Default::default()
     };
 
@@ -192,10 +221,48 @@ pub struct ProjectBuilder {
     redirects: RedirectConfig,
     collations: CollationBuilder,
     collections: CollectionsMap,
+    computed: ComputedProviders,
     cache: QueryCache,
+    output: Output,
+    helpers: HelperMap,
+    processors: ProcessorMap,
+    timings: Timings,
 }
 
 impl ProjectBuilder {
+    /// Set the output sink for generated files.
+    ///
+    /// By default files are written to the build target on disk.
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Set custom template helpers registered with the
+    /// handlebars parser for each locale.
+    pub fn helpers(mut self, helpers: HelperMap) -> Self {
+        self.helpers = helpers;
+        self
+    }
+
+    /// Set the processors for resource files keyed by extension.
+    ///
+    /// Commands declared in the `resources.process` profile
+    /// settings replace processors for the same extension.
+    pub fn processors(mut self, processors: ProcessorMap) -> Self {
+        self.processors = processors;
+        self
+    }
+
+    /// Set the providers for computed collections.
+    ///
+    /// Collections that use the `computed` source provider are
+    /// loaded from the provider registered with the collection name.
+    pub fn computed(mut self, computed: ComputedProviders) -> Self {
+        self.computed = computed;
+        self
+    }
+
     /// Determine and verify input source files to compile.
     pub async fn sources(mut self) -> Result<Self> {
         debug!("Preparing sources...");
@@ -215,7 +282,13 @@ impl ProjectBuilder {
 
         if let Some(ref dependencies) = self.config.dependencies() {
             if !dependencies.is_empty() {
-                let mut plugins = plugin::install(&self.config).await?;
+                // Assign the plugin features for the build profile
+                let mut config = self.config.clone();
+                config.set_dependencies(
+                    dependencies.for_profile(&self.options.settings)?,
+                );
+
+                let mut plugins = plugin::install(&config).await?;
 
                 // Prepare the dependencies
                 for (dep, plugin) in plugins.iter_mut() {
@@ -245,6 +318,17 @@ impl ProjectBuilder {
     pub async fn locales(mut self) -> Result<Self> {
         debug!("Loading locales...");
 
+        // Resources are merged in the resolved dependency order
+        // so earlier plugins take precedence for duplicate messages
+        if let Some(ref plugin_cache) = self.plugins {
+            for (_, plugin) in plugin_cache.plugins().iter() {
+                let dir = plugin.base().join(config::LOCALES);
+                if dir.is_dir() {
+                    self.locales.add_resources(dir);
+                }
+            }
+        }
+
         self.locales
             .load(&self.config, self.options.locales_path())?;
         Ok(self)
@@ -297,14 +381,38 @@ impl ProjectBuilder {
             self.locales.languages(),
         );
 
+        let diagnostics = &self.options.settings.diagnostics;
         let mut errors = collator::walk(req, &mut res).await?;
         if !errors.is_empty() {
-            // TODO: print all errors?
-            let e = errors.swap_remove(0);
+            // The first error fails the build, the others
+            // are recorded so the report includes every error
+            let e = errors.remove(0);
+            for e in errors {
+                for diagnostic in
+                    crate::diagnostics::from_error(&Error::Collator(e))
+                {
+                    diagnostics.push(diagnostic);
+                }
+            }
             return Err(Error::Collator(e));
         }
 
         let locales: Vec<CollateInfo> = res.try_into()?;
+
+        if self.options.settings.should_report_orphans() {
+            for info in locales.iter() {
+                for orphan in info.orphans(&self.options) {
+                    let message = format!("Orphan {}", orphan.display());
+                    warn!("{}", message);
+                    diagnostics.warn(
+                        config::diagnostics::ORPHAN_RESOURCE,
+                        message,
+                        &orphan,
+                    );
+                }
+            }
+        }
+
         self.collations = CollationBuilder { locales };
         Ok(self)
     }
@@ -331,8 +439,17 @@ impl ProjectBuilder {
             }
         }
 
+        // Redirect the non-canonical form of directory URLs
+        if let Some(policy) = self.options.settings.trailing_slash {
+            for collation in self.collations.iter_mut() {
+                let links = collation.link_map();
+                self.redirects
+                    .trailing_slash(policy, links.keys().map(|k| k.as_str()));
+            }
+        }
+
         // Validate the redirects
-        self.redirects.validate()?;
+        self.redirects.validate(self.config.limits().redirects())?;
 
         Ok(self)
     }
@@ -366,6 +483,7 @@ impl ProjectBuilder {
 
         // Load data sources and create indices
         let mut collections: CollectionsMap = Default::default();
+        collections.set_computed(self.computed.clone());
         collections
             .load(&self.config, &self.options, collation)
             .await?;
@@ -394,6 +512,36 @@ impl ProjectBuilder {
         Ok(self)
     }
 
+    /// Link translated pages to each other.
+    pub async fn translations(self) -> Result<Self> {
+        debug!("Collate page translations...");
+
+        collator::translations(
+            &self.locales,
+            &self.config,
+            &self.options,
+            &self.collations.locales,
+        )?;
+        Ok(self)
+    }
+
+    /// Create error pages.
+    pub async fn error_pages(mut self) -> Result<Self> {
+        debug!("Collate error pages...");
+
+        if let Some(error_pages) = self.config.error_pages() {
+            for collation in self.collations.iter_mut() {
+                collator::error_pages(
+                    error_pages,
+                    &self.config,
+                    &self.options,
+                    collation,
+                )?;
+            }
+        }
+        Ok(self)
+    }
+
     /// Perform pagination.
     pub async fn pages(mut self) -> Result<Self> {
         debug!("Collate paginated pages...");
@@ -479,6 +627,9 @@ impl ProjectBuilder {
             let syntax_plugin = plugin_cache.find(PLUGIN_SYNTAX).unwrap();
             info!("Syntax highlighting on");
             syntax::setup(syntax_plugin.base(), syntax_config)?;
+            if syntax_config.persist_cache() {
+                syntax::load_cache(syntax_cache_file()?)?;
+            }
         }
         Ok(self)
     }
@@ -494,12 +645,50 @@ impl ProjectBuilder {
             None
         };
 
+        let mut processors = self.processors;
+        if let Some(ref resources) = self.options.settings.resources {
+            if !resources.process.is_empty()
+                && !self.options.settings.can_exec()
+            {
+                return Err(Error::NoExecCapability(
+                    self.config.host().to_string(),
+                ));
+            }
+            for (ext, command) in resources.process.iter() {
+                processors.insert(
+                    ext,
+                    CommandProcessor::new(
+                        command.clone(),
+                        self.options.project.clone(),
+                    ),
+                );
+            }
+        }
+
         let sources = Arc::new(self.sources);
         let config = Arc::new(self.config);
         let options = Arc::new(self.options);
 
+        // Processed resources are always copied and linked
+        // using the extension of the processed output
+        let mut collations = self.collations;
+        if !processors.is_empty() {
+            for info in collations.iter_mut() {
+                let files: Vec<PathBuf> = info
+                    .resources
+                    .iter()
+                    .filter(|file| processors.find(file).is_some())
+                    .map(|file| file.to_path_buf())
+                    .collect();
+                for file in files {
+                    let processor = processors.find(&file).unwrap();
+                    info.set_processed(&file, processor.extension())?;
+                }
+            }
+        }
+
         // Get a map of collations keyed by locale wrapper
-        let collations = self.collations.build()?;
+        let collations = collations.build()?;
 
         let locales = Arc::new(self.locales);
 
@@ -514,6 +703,10 @@ impl ProjectBuilder {
         let mut renderers: Vec<Renderer> = Vec::new();
         let mut parsers: Vec<Box<dyn Parser + Send + Sync>> = Vec::new();
 
+        let output = self.output;
+        let helpers = self.helpers;
+        let timings = self.timings;
+
         collations.into_iter().try_for_each(|collation| {
             let context = Arc::new(BuildContext {
                 config: Arc::clone(&config),
@@ -521,6 +714,10 @@ impl ProjectBuilder {
                 locales: Arc::clone(&locales),
                 collation: Arc::new(RwLock::new(collation)),
                 plugins: plugins.clone(),
+                output: output.clone(),
+                helpers: helpers.clone(),
+                processors: processors.clone(),
+                timings: timings.clone(),
             });
 
             let parser: Box<dyn Parser + Send + Sync> = parser::build(
@@ -555,6 +752,9 @@ impl ProjectBuilder {
             manifest,
             redirects: self.redirects,
             collections,
+            timings,
+            output,
+            redirect_files: Default::default(),
             //cache: self.cache,
         })
     }
@@ -584,9 +784,12 @@ pub struct Project {
     pub locales: Arc<Locales>,
     pub collections: Arc<RwLock<CollectionsMap>>,
 
-    parsers: Vec<Box<dyn Parser + Send + Sync>>,
+    pub(crate) parsers: Vec<Box<dyn Parser + Send + Sync>>,
     pub(crate) renderers: Vec<Renderer>,
     manifest: Option<Arc<RwLock<Manifest>>>,
+    timings: Timings,
+    output: Output,
+    redirect_files: HashSet<PathBuf>,
 }
 
 impl Project {
@@ -602,6 +805,16 @@ impl Project {
         &self.collections
     }
 
+    /// Durations recorded for the phases of the build.
+    pub fn timings(&self) -> &Timings {
+        &self.timings
+    }
+
+    /// Output sink for the generated files.
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
     pub fn parsers_mut(&mut self) -> &mut Vec<Box<dyn Parser + Send + Sync>> {
         &mut self.parsers
     }
@@ -688,15 +901,32 @@ impl Project {
             if let Some(url) = res.sitemap.take() {
                 result.sitemaps.push(url);
             }
-
-            // TODO: ensure redirects work in multi-lingual config
-            // TODO: respect the render_type !!!!
-            self.redirects.write(&renderer.info.context.options)?;
         }
 
+        // TODO: ensure redirects work in multi-lingual config
+        // TODO: respect the render_type !!!!
+        self.write_redirects()?;
+
         Ok(result)
     }
 
+    /// Write the redirect files, a redirect may not replace
+    /// a file generated by the build.
+    fn write_redirects(&mut self) -> Result<()> {
+        let target = self.options.build_target();
+        let written = self.output.files();
+        for (path, content) in self.redirects.files(&self.options)? {
+            let file = target.join(path);
+            if written.contains(&file) && !self.redirect_files.contains(&file) {
+                return Err(config::Error::RedirectFileExists(file).into());
+            }
+            self.output.write(&file, &content)?;
+            self.redirect_files.insert(file);
+        }
+        info!("Redirects {}", self.redirects.map().len());
+        Ok(())
+    }
+
     pub(crate) async fn run_hook(
         &self,
         hook: &HookConfig,
@@ -708,6 +938,12 @@ impl Project {
         Ok(())
     }
 
+    /// Run the hooks for a lifecycle stage that is not part
+    /// of rendering, for example `pre-publish`.
+    pub fn run_hooks(&self, stage: HookStage) -> Result<()> {
+        hook::run_stage(&self.config, &self.options, stage)
+    }
+
     pub fn write_manifest(&self) -> Result<()> {
         // Write the manifest for incremental builds
         if let Some(ref manifest) = self.manifest {
@@ -717,6 +953,18 @@ impl Project {
         Ok(())
     }
 
+    /// Persist the syntax highlighting cache when configured.
+    pub fn write_syntax_cache(&self) -> Result<()> {
+        if self.config.is_syntax_enabled(&self.options.settings.name) {
+            if let Some(ref syntax_config) = self.config.syntax() {
+                if syntax_config.persist_cache() {
+                    syntax::save_cache(syntax_cache_file()?)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn write_robots(&self, sitemaps: Vec<Url>) -> Result<()> {
         let output_robots = self
             .config
@@ -733,12 +981,199 @@ impl Project {
             //// NOTE: than the `target`
             let robots_file =
                 self.options.build_target().join(config::robots::FILE);
-            utils::fs::write_string(&robots_file, robots.to_string())?;
+            self.output
+                .write(&robots_file, robots.to_string().as_bytes())?;
             info!("Robots {}", robots_file.display());
         }
 
         Ok(())
     }
+
+    pub fn write_build_info(&self) -> Result<()> {
+        if let Some(ref build_info) = self.config.build_info() {
+            if build_info.file()
+                && build_info.profiles().is_match(self.options.profile())
+            {
+                let app = config::generator::get(None);
+                let info = BuildInfo::new(
+                    app,
+                    build_info,
+                    self.config.commit().clone(),
+                );
+                // NOTE: like robots this is always at the root
+                let info_file =
+                    self.options.build_target().join(config::generator::FILE);
+                self.output
+                    .write(&info_file, &serde_json::to_vec_pretty(&info)?)?;
+                info!("Build info {}", info_file.display());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn fingerprint_assets(&self) -> Result<()> {
+        if let Some(ref fingerprint) = self.config.fingerprint() {
+            if fingerprint.profiles().is_match(self.options.profile()) {
+                if self.output.memory().is_some() {
+                    warn!("Fingerprint assets requires a build on disk");
+                    return Ok(());
+                }
+                let renames = crate::fingerprint::fingerprint(
+                    self.options.build_target(),
+                    fingerprint,
+                    &self.options,
+                )?;
+                info!("Fingerprint {} asset(s)", renames.len());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn optimize_images(&self) -> Result<()> {
+        if let Some(ref images) = self.config.images() {
+            if images.profiles().is_match(self.options.profile()) {
+                if self.output.memory().is_some() {
+                    warn!("Optimize images requires a build on disk");
+                    return Ok(());
+                }
+                let sources = crate::images::optimize(
+                    self.options.build_target(),
+                    images,
+                    &self.options,
+                    &dirs::image_cache_dir()?,
+                )?;
+                info!("Images {} resized", sources.len());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn check_links(&self) -> Result<()> {
+        if let Some(ref link_check) = self.config.link_check() {
+            if link_check.profiles().is_match(self.options.profile()) {
+                if self.output.memory().is_some() {
+                    warn!("Check links requires a build on disk");
+                    return Ok(());
+                }
+                let base = self
+                    .config
+                    .transform
+                    .as_ref()
+                    .and_then(|t| t.html.as_ref())
+                    .and_then(|h| h.base_path());
+                let dead = crate::link_check::check(
+                    self.options.build_target(),
+                    base,
+                    link_check,
+                )
+                .await?;
+                for link in dead.iter() {
+                    let message =
+                        format!("Dead link {} ({})", link.link, link.reason);
+                    warn!("{} in {}", message, link.file.display());
+                    self.options.settings.diagnostics.warn(
+                        config::diagnostics::DEAD_LINK,
+                        message,
+                        &link.file,
+                    );
+                }
+                info!("Links {} dead link(s)", dead.len());
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the cache directives declared by pages and the site
+    /// cache rules so they are applied by the server and publisher.
+    pub fn write_cache_manifest(&self) -> Result<()> {
+        let target = self.options.build_target();
+        let mut manifest: CacheManifest = Default::default();
+
+        if let Some(ref cache) = self.config.cache() {
+            let files = match self.output.memory() {
+                Some(memfs) => memfs.iter().map(|f| f.to_string()).collect(),
+                None => crate::output_manifest::files(target)?,
+            };
+            for path in files {
+                if path == config::cache::CACHE_FILE {
+                    continue;
+                }
+                if let Some(directive) = cache.find(&path) {
+                    manifest.insert(path, directive.to_string());
+                }
+            }
+        }
+
+        // Directives for pages take precedence over the rules
+        for renderer in self.renderers.iter() {
+            let collation = renderer.info.context.collation.read().unwrap();
+            let locale = collation.locale.read().unwrap();
+            for (_, page) in locale.pages() {
+                let page = page.read().unwrap();
+                if let Some(directive) = page.cache() {
+                    config::cache::validate(directive)?;
+                    let file = locale.get_path().join(page.destination());
+                    if let Ok(rel) = file.strip_prefix(target) {
+                        manifest.insert(
+                            utils::url::to_href_separator(rel),
+                            directive.to_string(),
+                        );
+                    }
+                }
+            }
+        }
+
+        let file = target.join(config::cache::CACHE_FILE);
+        if !manifest.is_empty() {
+            self.output
+                .write(&file, &serde_json::to_vec_pretty(&manifest)?)?;
+            info!(
+                "Cache {} directive(s) {}",
+                manifest.map().len(),
+                file.display()
+            );
+        } else if let Some(memfs) = self.output.memory() {
+            memfs.remove(config::cache::CACHE_FILE);
+        } else if file.exists() {
+            // Remove a manifest left by a previous build
+            std::fs::remove_file(file)?;
+        }
+        Ok(())
+    }
+
+    /// Write the manifest of the outputs of a build that started
+    /// at the given time; when cleaning stale outputs the outputs
+    /// of the previous build that were not generated are removed.
+    pub fn write_output_manifest(&self, started: SystemTime) -> Result<()> {
+        let settings = &self.options.settings;
+        if settings.should_write_output_manifest() {
+            let target = self.options.build_target();
+            let keep = settings.keep_matcher()?;
+            let recorded = self
+                .renderers
+                .first()
+                .map(|r| r.info.context.output.files())
+                .unwrap_or_default();
+            let current = crate::output_manifest::outputs(
+                target, &recorded, started, &keep,
+            )?;
+
+            if settings.is_clean_stale() {
+                if let Some(before) = crate::output_manifest::read(target)? {
+                    let removed = crate::output_manifest::remove_stale(
+                        target, &before, &current, &keep,
+                    )?;
+                    for path in removed {
+                        info!("Stale {}", path);
+                    }
+                }
+            }
+
+            let file = crate::output_manifest::write(target, &current)?;
+            info!("Manifest {}", file.display());
+        }
+        Ok(())
+    }
 }
 
 fn scm_digest(project: &PathBuf) -> Option<String> {
@@ -917,7 +1352,16 @@ impl TryInto<Vec<(HostInfo, HostConfig)>> for HostResult {
             );
 
             let mut host = HostConfig::new(hostname, target);
+            if let Some(error_pages) = info.project.config.error_pages() {
+                if let Some(not_found) = error_pages.not_found() {
+                    host.set_error_page(PathBuf::from(not_found));
+                }
+            }
             host.set_redirects(Some(redirect_uris));
+            host.set_trailing_slash(
+                info.project.options.settings.trailing_slash,
+            );
+            host.set_memory(info.project.output().memory().cloned());
             host.set_endpoint(endpoint);
             out.push((info, host));
 
@@ -1055,47 +1499,441 @@ pub async fn compile<P: AsRef<Path>>(
 }
 
 pub async fn default_compiler(builder: ProjectBuilder) -> BuildResult {
-    // Resolve sources, locales and collate the page data
-    let builder = builder
-        .sources()
-        .and_then(|s| s.plugins())
-        .and_then(|s| s.locales())
-        .and_then(|s| s.runtime())
-        .and_then(|s| s.collate())
-        .and_then(|s| s.inherit())
-        .and_then(|s| s.collate_plugins())
-        .await?;
-
-    // Load collections, resolve synthetic assets
-    let builder = builder.load_collections().and_then(|s| s.menus()).await?;
-
-    // Redirects come after synthetic assets in case
-    // they need to create any redirects.
-    let builder = builder.redirects().await?;
-
-    // Pagination, collections, syntax highlighting
-    let builder = builder
-        .pages()
-        .and_then(|s| s.each())
-        .and_then(|s| s.assign())
-        .and_then(|s| s.syntax())
-        // NOTE: feed comes after synthetic collections
-        // NOTE: so that <link rel="alternate"> patterns
-        // NOTE: can be injected correctly
-        .and_then(|s| s.feed())
-        .await?;
-
-    let mut state = builder.build()?;
+    let started = if builder.options.settings.should_write_output_manifest() {
+        crate::output_manifest::now(builder.options.build_target())?
+    } else {
+        SystemTime::now()
+    };
+    let _span = timing::span("build")
+        .field("host", builder.config.host())
+        .field("profile", builder.options.settings.name.to_string());
+    let timings = builder.timings.clone();
+    let events = builder.options.settings.events.clone();
+
+    hook::run_stage(&builder.config, &builder.options, HookStage::PreBuild)?;
+
+    let mut state = collate_project(builder).await?.build()?;
 
     // Render all the languages
-    let result = state.render(Default::default()).await?;
+    let result = {
+        let _phase = events.phase(timing::RENDER);
+        state.render(Default::default()).await?
+    };
+
+    {
+        let _phase = events.phase(timing::WRITE);
+        let _timer = timings.scope(timing::WRITE);
+
+        // Write the robots file containing any
+        // generated sitemaps
+        state.write_robots(result.sitemaps)?;
+
+        // Write build metadata when configured
+        state.write_build_info()?;
+
+        // Generate responsive image sizes when configured
+        state.optimize_images()?;
+
+        // Rename assets with content hashes when configured,
+        // must run after all output has been written
+        state.fingerprint_assets()?;
 
-    // Write the robots file containing any
-    // generated sitemaps
-    state.write_robots(result.sitemaps)?;
+        // Check links once all output has been written
+        state.check_links().await?;
 
-    // Write out manifest for incremental builds
-    state.write_manifest()?;
+        // Write cache directives once asset names are final
+        state.write_cache_manifest()?;
+
+        // Write the output manifest when configured
+        state.write_output_manifest(started)?;
+
+        // Write out manifest for incremental builds
+        state.write_manifest()?;
+
+        // Keep highlighted code blocks for the next build
+        state.write_syntax_cache()?;
+    }
+
+    state.run_hooks(HookStage::PostBuild)?;
+
+    events.complete();
 
     Ok(state)
 }
+
+/// Run the project builder phases that prepare the project
+/// data for rendering.
+pub(crate) async fn collate_project(
+    builder: ProjectBuilder,
+) -> Result<ProjectBuilder> {
+    let timings = builder.timings.clone();
+    let events = builder.options.settings.events.clone();
+
+    // Resolve sources, locales and collate the page data
+    let builder = {
+        let _phase = events.phase(timing::COLLATION);
+        let _timer = timings.scope(timing::COLLATION);
+        builder
+            .sources()
+            .and_then(|s| s.plugins())
+            .and_then(|s| s.locales())
+            .and_then(|s| s.runtime())
+            .and_then(|s| s.collate())
+            .and_then(|s| s.inherit())
+            .and_then(|s| s.collate_plugins())
+            .await?
+    };
+
+    let builder = {
+        let _phase = events.phase(timing::DATA_SOURCES);
+        let _timer = timings.scope(timing::DATA_SOURCES);
+
+        // Load collections, resolve synthetic assets
+        let builder =
+            builder.load_collections().and_then(|s| s.menus()).await?;
+
+        // Redirects come after synthetic assets in case
+        // they need to create any redirects.
+        let builder = builder.redirects().await?;
+
+        // Pagination, collections, syntax highlighting
+        builder
+            .pages()
+            .and_then(|s| s.each())
+            .and_then(|s| s.assign())
+            .and_then(|s| s.syntax())
+            // NOTE: feed comes after synthetic collections
+            // NOTE: so that <link rel="alternate"> patterns
+            // NOTE: can be injected correctly
+            .and_then(|s| s.feed())
+            .and_then(|s| s.translations())
+            .and_then(|s| s.error_pages())
+            .await?
+    };
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Value;
+
+    use config::{
+        cache::CacheManifest, events::BuildEvent, memfs::MemoryFileSystem,
+        test_util::TempProject, ProfileName, ProfileSettings,
+    };
+
+    use super::timing;
+    use crate::Result;
+
+    #[tokio::test]
+    async fn build_events() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        project
+            .write("site/about.md", "+++\nstandalone = true\n+++\n# About")?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        let rx = args.events.subscribe();
+
+        super::compile(project.root(), &args, Default::default(), false)
+            .await?;
+        let events: Vec<BuildEvent> = rx.try_iter().collect();
+
+        let phases: Vec<String> = events
+            .iter()
+            .filter_map(|e| match e {
+                BuildEvent::PhaseStarted { phase } => {
+                    Some(format!("+{}", phase))
+                }
+                BuildEvent::PhaseFinished { phase, .. } => {
+                    Some(format!("-{}", phase))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "+collation",
+                "-collation",
+                "+data sources",
+                "-data sources",
+                "+rendering",
+                "-rendering",
+                "+writing",
+                "-writing",
+            ],
+            phases
+        );
+
+        // Pages are rendered during the render phase
+        let position = |started: bool| {
+            events.iter().position(|e| match e {
+                BuildEvent::PhaseStarted { phase } => {
+                    started && phase == timing::RENDER
+                }
+                BuildEvent::PhaseFinished { phase, .. } => {
+                    !started && phase == timing::RENDER
+                }
+                _ => false,
+            })
+        };
+        let (start, finish) =
+            (position(true).unwrap(), position(false).unwrap());
+        let rendered: Vec<usize> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| matches!(e, BuildEvent::PageRendered { .. }))
+            .map(|(i, _)| i)
+            .collect();
+        assert!(rendered.len() >= 2);
+        assert!(rendered.iter().all(|i| *i > start && *i < finish));
+
+        match events.last() {
+            Some(BuildEvent::Complete {
+                pages, warnings, ..
+            }) => {
+                assert_eq!(rendered.len(), *pages);
+                assert_eq!(0, *warnings);
+            }
+            _ => panic!("expected the build to complete"),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn build_span() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        project
+            .write("site/about.md", "+++\nstandalone = true\n+++\n# About")?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let capture = Capture::new(Arc::clone(&records));
+        super::compile(project.root(), &args, Default::default(), false)
+            .await?;
+        drop(capture);
+
+        let output = records.lock().unwrap().clone();
+        let spans: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // Spans from builds on other threads may also be captured
+        let build = spans
+            .iter()
+            .find(|s| s["span"] == "build" && s["profile"] == "debug")
+            .unwrap();
+        assert_eq!("info", build["level"]);
+        assert!(build["duration_ms"].is_f64());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hook_stages() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        project
+            .write("site/about.md", "+++\nstandalone = true\n+++\n# About")?;
+        let site =
+            std::fs::read_to_string(project.root().join(config::SITE_TOML))?;
+        let hook = |stage: &str| {
+            format!(
+                "\n[[hook]]\ncommand = \"sh\"\nargs = [\"-c\", \"echo {} >> hooks.txt\"]\nstage = \"{}\"\nstdout = false\n",
+                stage, stage
+            )
+        };
+        project.write(
+            config::SITE_TOML,
+            format!("{}{}{}", site, hook("post-build"), hook("pre-build")),
+        )?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        args.exec = Some(true);
+        super::compile(project.root(), &args, Default::default(), false)
+            .await?;
+
+        let output = std::fs::read_to_string(project.root().join("hooks.txt"))?;
+        assert_eq!(
+            vec!["pre-build", "post-build"],
+            output.lines().collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_page() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        project
+            .write("site/about.md", "+++\nstandalone = true\n+++\n# About")?;
+        project.write(
+            "site/layouts/error.hbs",
+            "<h1>{{title}}</h1><p>{{status}}: {{message}}</p>",
+        )?;
+        let site =
+            std::fs::read_to_string(project.root().join(config::SITE_TOML))?;
+        project.write(
+            config::SITE_TOML,
+            format!(
+                "{}\n[error_pages.pages.404]\ntitle = \"Not Found\"\nmessage = \"Nothing here\"\n",
+                site
+            ),
+        )?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        super::compile(project.root(), &args, Default::default(), false)
+            .await?;
+
+        // Locale resources are loaded once for the process so pages
+        // have a locale prefix when other tests use several locales
+        let target = project.root().join("build").join("debug");
+        let file = [
+            target.join(config::ERROR_HTML),
+            target.join("en").join(config::ERROR_HTML),
+        ]
+        .iter()
+        .find(|file| file.exists())
+        .cloned()
+        .expect("error page was not written");
+        assert_eq!(
+            "<h1>Not Found</h1><p>404: Nothing here</p>",
+            std::fs::read_to_string(file)?.trim()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cache_manifest() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        project.write(
+            "site/about.md",
+            "+++\nstandalone = true\ncache = \"no-store\"\n+++\n# About",
+        )?;
+        let site =
+            std::fs::read_to_string(project.root().join(config::SITE_TOML))?;
+        project.write(
+            config::SITE_TOML,
+            format!(
+                "{}\n[[cache.rules]]\npattern = \"**/*.html\"\ndirective = \"no-cache\"\n",
+                site
+            ),
+        )?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        super::compile(project.root(), &args, Default::default(), false)
+            .await?;
+
+        let target = project.root().join("build").join("debug");
+        let manifest = CacheManifest::load(&target)?;
+        // Locale resources are loaded once for the process so pages
+        // have a locale prefix when other tests use several locales
+        let get = |name: &str| {
+            manifest
+                .map()
+                .iter()
+                .find(|(path, _)| path.trim_start_matches("en/") == name)
+                .map(|(_, directive)| directive.as_str())
+        };
+        assert_eq!(Some("no-store"), get("about/index.html"));
+        assert_eq!(Some("no-cache"), get("index.html"));
+
+        // Manifest from the previous build is removed
+        project.write(config::SITE_TOML, site)?;
+        project
+            .write("site/about.md", "+++\nstandalone = true\n+++\n# About")?;
+        super::compile(project.root(), &args, Default::default(), false)
+            .await?;
+        assert!(!target.join(config::cache::CACHE_FILE).exists());
+
+        project.write(
+            "site/about.md",
+            "+++\nstandalone = true\ncache = \"no-store=1\"\n+++\n# About",
+        )?;
+        assert!(super::compile(
+            project.root(),
+            &args,
+            Default::default(),
+            false
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn memory_output() -> Result<()> {
+        let project = TempProject::multi_lingual(&["en"])?;
+        project
+            .write("site/index.md", "+++\nstandalone = true\n+++\n# Home")?;
+        project
+            .write("site/about.md", "+++\nstandalone = true\n+++\n# About")?;
+
+        let mut args = ProfileSettings::from(&ProfileName::Debug);
+        args.live = Some(false);
+        args.memory = Some(true);
+        let result =
+            super::compile(project.root(), &args, Default::default(), false)
+                .await?;
+
+        let memfs = result.projects[0].output().memory().unwrap().clone();
+        let files: Vec<String> = memfs
+            .iter()
+            .map(|f| f.trim_start_matches("en/").to_string())
+            .collect();
+        assert!(files.contains(&"index.html".to_string()));
+        assert!(files.contains(&"about/index.html".to_string()));
+        assert!(files.contains(&config::REDIRECTS_FILE.to_string()));
+
+        let target = project.root().join("build").join("debug");
+        assert!(!target.join(config::INDEX_HTML).exists());
+        assert!(!target.join(config::REDIRECTS_FILE).exists());
+        Ok(())
+    }
+
+    /// Capture span records until dropped.
+    struct Capture(Option<Box<dyn Write + Send>>);
+
+    impl Capture {
+        fn new(records: Arc<Mutex<Vec<u8>>>) -> Self {
+            Self(timing::set_span_writer(Some(Box::new(Records(records)))))
+        }
+    }
+
+    impl Drop for Capture {
+        fn drop(&mut self) {
+            timing::set_span_writer(self.0.take());
+        }
+    }
+
+    struct Records(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Records {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
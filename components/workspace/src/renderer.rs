@@ -1,9 +1,9 @@
 use std::ffi::OsStr;
-use std::fs::{self, File};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use url::Url;
 
 use human_bytes::human_bytes;
@@ -11,13 +11,16 @@ use human_bytes::human_bytes;
 use collator::{builder::PageBuilder, resource::Resource};
 use collections::{synthetic, CollectionsMap, QueryCache};
 use compiler::{
-    compile, parser::Parser, run, BuildContext, CompilerOutput, ParseData,
+    compile, parser::Parser, run, timing, BuildContext, CompilerOutput,
+    ParseData,
 };
 use config::{
-    hook::HookConfig,
+    diagnostics::Diagnostic,
+    hook::{HookConfig, HookStage},
     plugin::dependency::DependencyTarget,
-    profile::Profiles,
+    profile::{ProfileSettings, Profiles},
     sitemap::{SiteMapEntry, SiteMapFile, SiteMapIndex},
+    Page,
 };
 use locale::{LocaleName, Locales};
 use search::{
@@ -225,7 +228,12 @@ impl Renderer {
         render_options: &RenderOptions,
     ) -> Result<Option<Url>> {
         if render_options.search_index {
-            self.create_search_indices(&output.data)?;
+            let _timer =
+                self.info.context.timings.scope(timing::SEARCH_INDEX);
+            self.create_search_indices(
+                &output.data,
+                render_options.file().is_none(),
+            )?;
         }
 
         if render_options.sitemap {
@@ -235,12 +243,16 @@ impl Renderer {
         }
     }
 
-    fn create_search_indices(&self, parse_list: &Vec<ParseData>) -> Result<()> {
+    fn create_search_indices(
+        &self,
+        parse_list: &Vec<ParseData>,
+        all_pages: bool,
+    ) -> Result<()> {
         let ctx = &self.info.context;
         let collation = ctx.collation.read().unwrap();
         let include_index = ctx.options.settings.should_include_index();
         if let Some(ref search) = ctx.config.search {
-            for (_id, search) in search.items.iter() {
+            for (id, search) in search.items.iter() {
                 let mut intermediates: Vec<IntermediateEntry> = Vec::new();
                 info!("Prepare search index ({})", parse_list.len());
                 for parse_data in parse_list {
@@ -260,7 +272,12 @@ impl Renderer {
                             url = url.trim_end_matches(config::INDEX_HTML);
                         }
 
-                        if !search.matcher.filter(url) {
+                        if !search.includes(
+                            &ctx.config,
+                            &ctx.options.source,
+                            &parse_data.file,
+                            url,
+                        ) {
                             continue;
                         }
 
@@ -273,21 +290,32 @@ impl Renderer {
                     }
                 }
 
+                // Only warn for a full build as rendering a
+                // single file may not include any scoped pages
+                if all_pages && intermediates.is_empty() {
+                    let message =
+                        format!("Search index {} does not match any pages", id);
+                    warn!("{}", message);
+                    ctx.options.settings.diagnostics.push(Diagnostic::warning(
+                        config::diagnostics::SEARCH_INDEX,
+                        message,
+                    ));
+                }
+
                 info!("Compile search index ({})", intermediates.len());
                 let idx: Index = compile_index(intermediates);
                 let index_file =
                     search.get_output_path(collation.get_path().as_ref());
 
-                // If there are path filters for compiling specific files
-                // we are not guaranteed that the parent directory will exist!
-                if let Some(parent) = index_file.parent() {
-                    if !parent.exists() {
-                        fs::create_dir_all(parent)?;
-                    }
-                }
-
+                // The index writer only writes to a file so the index
+                // is staged and passed to the output sink
+                let staged = tempfile::NamedTempFile::new()?;
+                let bytes_written = search::writer::write(&idx, staged.path())?;
                 info!("Write search index to {}", index_file.display());
-                let bytes_written = search::writer::write(&idx, index_file)?;
+                ctx.output.write(
+                    &index_file,
+                    &utils::fs::read_bytes(staged.path())?,
+                )?;
                 info!("Search index {}", human_bytes(bytes_written as f64));
             }
         }
@@ -308,16 +336,18 @@ impl Renderer {
             // How many entries per chunk window?
             let entries = sitemap.entries.as_ref().unwrap();
 
-            let with_lang = if ctx.locales.is_multi_lingual() {
-                Some(collation.get_lang().to_string())
-            } else {
-                None
-            };
+            let with_lang = ctx
+                .locales
+                .languages()
+                .url_prefix(&collation.get_lang())
+                .map(|s| s.to_string());
 
             // Base canonical URL
             let base = ctx
                 .options
                 .get_canonical_url(&ctx.config, with_lang.as_ref())?;
+            let root =
+                ctx.options.get_canonical_url::<&str>(&ctx.config, None)?;
 
             // Create the top-level index of all sitemaps
             let folder = sitemap.name.as_ref().unwrap().to_string();
@@ -325,10 +355,6 @@ impl Renderer {
 
             let base_folder = collation.get_path().join(&folder);
 
-            if !base_folder.exists() {
-                fs::create_dir_all(&base_folder)?;
-            }
-
             let err_name = OsStr::new("404");
 
             for (count, window) in parse_list.chunks(*entries).enumerate() {
@@ -347,21 +373,24 @@ impl Renderer {
                         }
                         true
                     })
-                    .map(|d| {
+                    .filter_map(|d| {
                         // Get the href to use to build the location
                         let href = collation.get_link_href(&d.file).unwrap();
-                        // Get the last modification data from the page
                         let page = collation.resolve(&d.file).unwrap();
                         let page = &*page.read().unwrap();
-                        // Generate the absolute location
-                        let location = base.join(href.as_ref()).unwrap();
-                        let lastmod = page.lastmod();
-                        SiteMapEntry { location, lastmod }
+                        site_map_entry(
+                            &root,
+                            with_lang.as_deref(),
+                            &href,
+                            page,
+                            &ctx.options.settings,
+                        )
                     })
                     .collect();
 
-                let map_file = File::create(&sitemap_path)?;
-                sitemap.to_writer(map_file)?;
+                let mut map_file: Vec<u8> = Vec::new();
+                sitemap.to_writer(&mut map_file)?;
+                ctx.output.write(&sitemap_path, &map_file)?;
 
                 // Add the file to the index
                 idx.maps.push(sitemap);
@@ -369,8 +398,9 @@ impl Renderer {
 
             // Write out the master index file
             let idx_path = base_folder.join(config::sitemap::FILE);
-            let idx_file = File::create(&idx_path)?;
-            idx.to_writer(idx_file)?;
+            let mut idx_file: Vec<u8> = Vec::new();
+            idx.to_writer(&mut idx_file)?;
+            ctx.output.write(&idx_path, &idx_file)?;
 
             let sitemap_url = idx.to_location();
             info!("Sitemap {} ({})", sitemap_url.to_string(), idx.maps.len());
@@ -395,7 +425,7 @@ impl Renderer {
                 &self.info.context,
                 hook::collect(
                     hooks.exec(),
-                    hook::Phase::Before,
+                    HookStage::PostCollate,
                     &self.info.context.options.settings.name,
                 ),
                 None,
@@ -410,7 +440,7 @@ impl Renderer {
                 &self.info.context,
                 hook::collect(
                     hooks.exec(),
-                    hook::Phase::After,
+                    HookStage::PostRender,
                     &self.info.context.options.settings.name,
                 ),
                 None,
@@ -428,7 +458,7 @@ impl Renderer {
         // When working with multi-lingual sites the target may not exist yet
         let collation = self.info.context.collation.read().unwrap();
         let path = collation.get_path();
-        if !path.exists() {
+        if self.info.context.output.memory().is_none() && !path.exists() {
             fs::create_dir_all(path.as_ref())?;
         }
 
@@ -437,22 +467,25 @@ impl Renderer {
             info!("Incremental build enabled");
         }
 
+        let destination = |p: &PathBuf| -> Option<PathBuf> {
+            collation
+                .get_resource(p)
+                .map(|target| match target.as_ref() {
+                    Resource::Page { ref target }
+                    | Resource::File { ref target } => {
+                        target.get_output(collation.get_path().as_ref())
+                    }
+                })
+        };
+
         let manifest_filter = |p: &&Arc<PathBuf>| -> bool {
             if let Some(ref manifest) = self.info.manifest {
                 let manifest = manifest.read().unwrap();
-                if let Some(target) = collation.get_resource(*p) {
-                    match target.as_ref() {
-                        Resource::Page { ref target }
-                        | Resource::File { ref target } => {
-                            let dest = target
-                                .get_output(collation.get_path().as_ref());
-                            if manifest.exists(p)
-                                && !manifest.is_dirty(p, &dest, false)
-                            {
-                                debug!("[NOOP] {}", p.display());
-                                return false;
-                            }
-                        }
+                if let Some(dest) = destination(p) {
+                    if manifest.exists(p) && !manifest.is_dirty(p, &dest, false)
+                    {
+                        debug!("[NOOP] {}", p.display());
+                        return false;
                     }
                 }
             }
@@ -530,9 +563,15 @@ impl Renderer {
         };
 
         let filter = |p: &&Arc<PathBuf>| -> bool {
-            let filtered = path_filter(p);
+            let mut filtered = path_filter(p);
             if filtered && is_incremental {
-                return manifest_filter(p);
+                filtered = manifest_filter(p);
+            }
+            // Outputs of skipped files are still outputs of this build
+            if !filtered {
+                if let Some(dest) = destination(p) {
+                    self.info.context.output.touch(&dest);
+                }
             }
             filtered
         };
@@ -566,3 +605,127 @@ impl Renderer {
         Ok(())
     }
 }
+
+/// Sitemap entry for a page with an absolute location.
+///
+/// The location includes the locale prefix for multi-lingual sites;
+/// pages that are drafts or should not be indexed are excluded.
+fn site_map_entry(
+    root: &Url,
+    prefix: Option<&str>,
+    href: &str,
+    page: &Page,
+    settings: &ProfileSettings,
+) -> Option<SiteMapEntry> {
+    if page.draft() || page.noindex() {
+        return None;
+    }
+    let mut href = href.trim_start_matches('/');
+    if !settings.should_include_index() {
+        href = href.trim_end_matches(config::INDEX_HTML);
+    }
+    let href = settings.canonical_href(href);
+    let path = match prefix {
+        Some(prefix) => format!("{}/{}", prefix, href),
+        None => href,
+    };
+    let location = root.join(&path).ok()?;
+    Some(SiteMapEntry::new(location, page))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{Arc, RwLock};
+
+    use collator::{get_locale_target, CollateInfo};
+    use config::{
+        sitemap::SiteMapFile, test_util::TempProject, Page, ProfileName,
+        ProfileSettings, RuntimeOptions,
+    };
+    use locale::Locales;
+
+    use super::site_map_entry;
+
+    #[test]
+    fn site_map_alternates(
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let project = TempProject::multi_lingual(&["en", "fr"])?;
+        let config = project.config()?;
+        let mut locales: Locales = Default::default();
+        locales.load(&config, project.locales())?;
+
+        let mut options: RuntimeOptions = Default::default();
+        options.source = project.source();
+        options.settings = ProfileSettings::from(&ProfileName::Debug);
+
+        let about = project.source().join("about.md");
+        let draft = project.write("site/draft.md", "# Draft\n")?;
+        let target = PathBuf::from("build");
+        let mut collations = Vec::new();
+        for lang in &["en", "fr"] {
+            let path = get_locale_target(lang, &target, locales.languages());
+            let mut info = CollateInfo::new(lang.to_string(), path);
+            for (key, href) in
+                [(&about, "/about/index.html"), (&draft, "/draft.html")]
+            {
+                let dest = PathBuf::from(href.trim_start_matches('/'));
+                let mut page: Page = Default::default();
+                page.seal(&config, &options, key, &dest, None)?;
+                page.draft = Some(key == &draft);
+                let key = Arc::new(key.clone());
+                info.link(Arc::clone(&key), Arc::new(href.to_string()))?;
+                info.add_page(&key, dest, Arc::new(RwLock::new(page)));
+            }
+            collations.push(info);
+        }
+        collator::translations(&locales, &config, &options, &collations)?;
+
+        let root = options.get_canonical_url::<&str>(&config, None)?;
+        for info in collations.iter() {
+            let prefix = locales.languages().url_prefix(info.get_lang());
+            let page = info.resolve(&draft).unwrap().read().unwrap();
+            assert!(site_map_entry(
+                &root,
+                prefix,
+                "/draft.html",
+                &page,
+                &options.settings
+            )
+            .is_none());
+
+            let page = info.resolve(&about).unwrap().read().unwrap();
+            let entry = site_map_entry(
+                &root,
+                prefix,
+                "/about/index.html",
+                &page,
+                &options.settings,
+            )
+            .unwrap();
+            assert_eq!(
+                root.join(&format!("{}/about/", info.get_lang()))?,
+                entry.location
+            );
+
+            let sitemap = SiteMapFile {
+                href: "1.xml".to_string(),
+                entries: vec![entry],
+            };
+            let mut buf: Vec<u8> = Vec::new();
+            sitemap.to_writer(&mut buf)?;
+            let xml = String::from_utf8(buf)?;
+            assert!(
+                xml.contains(r#"xmlns:xhtml="http://www.w3.org/1999/xhtml""#)
+            );
+            for lang in &["en", "fr"] {
+                let href = root.join(&format!("{}/about/", lang))?;
+                assert!(xml.contains(&format!(
+                    r#"<xhtml:link rel="alternate" hreflang="{}" href="{}"/>"#,
+                    lang, href
+                )));
+            }
+        }
+        Ok(())
+    }
+}
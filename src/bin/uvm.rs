@@ -37,6 +37,10 @@ enum Command {
         #[structopt(short = "s", long = "self")]
         update_self: bool,
 
+        /// Report when a new version is available without installing
+        #[structopt(short, long)]
+        check: bool,
+
         /// Semver range filter
         #[structopt(env = "UVM_INSTALL_VERSION_RANGE", hide_env_values = true)]
         version_range: Option<String>,
@@ -47,7 +51,11 @@ enum Command {
     Remove { version: String },
 
     /// Remove old release versions
-    Prune {},
+    Prune {
+        /// Keep the most recent versions
+        #[structopt(short, long)]
+        keep: Option<usize>,
+    },
 
     /// Uninstall the platform tools
     Uninstall {},
@@ -69,9 +77,16 @@ async fn run(cmd: Command) -> release::Result<()> {
         }
         Command::Update {
             update_self,
+            check,
             version_range,
         } => {
-            if update_self {
+            if check {
+                if let Some(latest) = release::check().await? {
+                    info!("A new version {}@{} is available", name, latest);
+                } else {
+                    info!("No new version available ✓");
+                }
+            } else if update_self {
                 release::update_self(version).await?;
             } else {
                 let range = if let Some(range) = version_range {
@@ -85,8 +100,12 @@ async fn run(cmd: Command) -> release::Result<()> {
         Command::Remove { version } => {
             release::remove(version).await?;
         }
-        Command::Prune {} => {
-            release::prune().await?;
+        Command::Prune { keep } => {
+            if let Some(keep) = keep {
+                release::prune_keep(keep).await?;
+            } else {
+                release::prune().await?;
+            }
         }
         Command::Uninstall {} => {
             release::uninstall().await?;
@@ -36,6 +36,8 @@ async fn run(cmd: Command) -> Result<()> {
                 bare: args.bare,
                 remote_name: args.remote_name,
                 remote_url: args.remote_url,
+                gitignore: args.gitignore,
+                vcs: args.vcs,
             };
             uwe::new::project(opts).await?;
         }
@@ -54,7 +56,13 @@ async fn run(cmd: Command) -> Result<()> {
 
         Command::Clean { args } => {
             let project = opts::project_path(&args.project)?;
-            uwe::clean::clean(project).await?;
+            let options = uwe::clean::CleanOptions {
+                short_codes: args.short_codes,
+                dry_run: args.dry_run,
+                yes: args.yes,
+                keep: args.keep,
+            };
+            uwe::clean::clean(project, options).await?;
         }
 
         Command::Docs { args } => {
@@ -100,6 +108,8 @@ async fn run(cmd: Command) -> Result<()> {
                 project,
                 exec: args.exec,
                 sync_redirects: args.sync_redirects,
+                allow_mass_delete: args.allow_mass_delete,
+                force: args.force,
             };
             uwe::publish::publish(opts).await?;
         }
@@ -117,14 +127,18 @@ async fn run(cmd: Command) -> Result<()> {
                 paths,
                 release: Some(true),
                 name: args.profile,
+                include_drafts: args.compile.drafts(),
                 exec: Some(args.compile.exec),
                 member: args.compile.member,
-                include_drafts: Some(args.compile.include_drafts),
+                timings: if args.timings { Some(true) } else { None },
+                clean_stale: if args.clean_stale { Some(true) } else { None },
                 ..Default::default()
             };
 
             let now = SystemTime::now();
-            match uwe::build::compile(&project, build_args).await {
+            match uwe::build::compile(&project, build_args, args.diagnostics)
+                .await
+            {
                 Ok(_) => {
                     if let Ok(t) = now.elapsed() {
                         info!("{:?}", t);
@@ -154,9 +168,15 @@ async fn run(cmd: Command) -> Result<()> {
                 launch: args.launch,
                 host: Some(args.server.addr),
                 port: args.server.port,
+                include_drafts: args.compile.drafts(),
                 exec: Some(args.compile.exec),
                 member: args.compile.member,
-                include_drafts: Some(args.compile.include_drafts),
+                dump_context: if args.dump_context {
+                    Some(true)
+                } else {
+                    None
+                },
+                memory: if args.memory { Some(true) } else { None },
                 tls,
                 ..Default::default()
             };
@@ -181,8 +201,15 @@ fn main() -> Result<()> {
     let args = Uwe::from_args();
 
     uwe::panic_hook();
+    if let Some(mode) = args.output {
+        utils::terminal::set_output_mode(mode);
+    }
     uwe::log_level(&*args.log_level).or_else(fatal)?;
 
+    if args.strict_config {
+        std::env::set_var(config::ENV_STRICT_CONFIG, "1");
+    }
+
     // Configure the generator meta data ahead of time
 
     // Must configure the version here otherwise option_env!() will
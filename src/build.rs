@@ -1,12 +1,39 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use log::info;
 
 use crate::Error;
-use config::ProfileSettings;
+use config::{diagnostics::Diagnostics, ProfileSettings};
 
+/// Compile a project.
+///
+/// When a diagnostics path is given warnings and errors for the
+/// build are written to the path as JSON.
 pub async fn compile<P: AsRef<Path>>(
     project: P,
-    args: ProfileSettings,
+    mut args: ProfileSettings,
+    diagnostics: Option<PathBuf>,
 ) -> Result<(), Error> {
-    workspace::compile(project, &args, Default::default(), false).await?;
+    args.diagnostics = Diagnostics::new(diagnostics.is_some());
+    let result =
+        workspace::compile(project, &args, Default::default(), false).await;
+
+    if let Some(ref path) = diagnostics {
+        if let Err(ref e) = result {
+            for diagnostic in workspace::diagnostics::from_error(e) {
+                args.diagnostics.push(diagnostic);
+            }
+        }
+        args.diagnostics.write(path)?;
+    }
+
+    let result = result?;
+    for project in result.projects.iter() {
+        let timings = project.timings();
+        if timings.is_enabled() {
+            info!("Timings for {}", project.config.host());
+            timings.print();
+        }
+    }
     Ok(())
 }
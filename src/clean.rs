@@ -1,19 +1,196 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-use log::info;
+use log::{info, warn};
 
-use crate::Result;
+use config::{profile::ProfileName, Config};
+
+use crate::{Error, Result};
+
+/// Options for the clean command.
+#[derive(Debug, Default)]
+pub struct CleanOptions {
+    /// Also clear the short code cache.
+    pub short_codes: bool,
+    /// List the paths that would be removed without deleting them.
+    pub dry_run: bool,
+    /// Do not prompt when the target contains unexpected entries.
+    pub yes: bool,
+    /// Paths relative to the build target to preserve.
+    pub keep: Vec<PathBuf>,
+}
+
+pub async fn clean(project: PathBuf, options: CleanOptions) -> Result<()> {
+    for path in options.keep.iter() {
+        if path.is_absolute() {
+            return Err(Error::NotRelative(path.to_path_buf()));
+        }
+    }
 
-pub async fn clean(project: PathBuf) -> Result<()> {
     let workspace = workspace::open(&project, true, &vec![])?;
     for config in workspace.into_iter() {
         let profile = config.build.as_ref().unwrap();
         let target = &profile.target;
-        if target.exists() && target.is_dir() {
-            info!("Remove {}", target.display());
-            fs::remove_dir_all(&target)?;
+        if !target.exists() || !target.is_dir() {
+            continue;
+        }
+
+        let paths = removals(target, &options.keep)?;
+        let unknown = unexpected(target, &known_entries(&config))?;
+
+        if options.dry_run {
+            for path in unknown.iter() {
+                warn!("Unexpected entry {}", path.display());
+            }
+            for path in paths.iter() {
+                info!("Would remove {}", path.display());
+            }
+            continue;
+        }
+
+        if !unknown.is_empty() && !options.yes {
+            for path in unknown.iter() {
+                warn!("Unexpected entry {}", path.display());
+            }
+            if !confirm(target)? {
+                info!("Skip {}", target.display());
+                continue;
+            }
+        }
+
+        for path in paths {
+            info!("Remove {}", path.display());
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    if options.short_codes {
+        if options.dry_run {
+            info!("Would clear short code cache");
+        } else {
+            info!("Clear short code cache");
+            compiler::short_code::clear()?;
+        }
+    }
+    Ok(())
+}
+
+/// Names of the top-level entries a build may write into the target;
+/// a directory for each profile and the manifest for incremental builds.
+fn known_entries(config: &Config) -> HashSet<String> {
+    let mut names: Vec<String> = vec![
+        ProfileName::Debug,
+        ProfileName::Release,
+        ProfileName::Dist,
+        ProfileName::Test,
+    ]
+    .iter()
+    .map(|n| n.to_string())
+    .collect();
+
+    if let Some(ref profiles) = config.profile {
+        names.extend(profiles.keys().cloned());
+    }
+
+    let mut known = HashSet::new();
+    for name in names {
+        known.insert(format!("{}.{}", name, config::JSON));
+        known.insert(name);
+    }
+    known
+}
+
+/// Top-level entries in the target that a build would not have created.
+fn unexpected(target: &Path, known: &HashSet<String>) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(target)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        if !known.contains(name.as_ref()) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Determine the paths to remove from the target.
+///
+/// Without any kept paths the target itself is removed, otherwise
+/// only the entries that do not contain a kept path are removed.
+pub fn removals(target: &Path, keep: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if keep.is_empty() {
+        return Ok(vec![target.to_path_buf()]);
+    }
+    let keep: Vec<PathBuf> = keep.iter().map(|p| target.join(p)).collect();
+    let mut paths = Vec::new();
+    collect_removals(target, &keep, &mut paths)?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn collect_removals(
+    dir: &Path,
+    keep: &[PathBuf],
+    paths: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if keep.iter().any(|k| k == &path) {
+            continue;
+        }
+        if path.is_dir() && keep.iter().any(|k| k.starts_with(&path)) {
+            collect_removals(&path, keep, paths)?;
+        } else {
+            paths.push(path);
         }
     }
     Ok(())
 }
+
+fn confirm(target: &Path) -> Result<bool> {
+    print!("Remove files in {}? [y/N] ", target.display());
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::test_util::TempProject;
+
+    #[test]
+    fn dry_run_removals() -> Result<()> {
+        let project = TempProject::new()?;
+        project.write("build/debug/index.html", "")?;
+        project.write("build/debug/keep.txt", "")?;
+        project.write("build/debug.json", "{}")?;
+
+        let target = project.root().join("build");
+        let keep = vec![PathBuf::from("debug/keep.txt")];
+        let paths = removals(&target, &keep)?;
+        assert_eq!(
+            vec![
+                target.join("debug").join("index.html"),
+                target.join("debug.json"),
+            ],
+            paths
+        );
+
+        // Listing the removals does not delete anything
+        assert!(target.join("debug").join("index.html").exists());
+        assert!(target.join("debug.json").exists());
+
+        assert_eq!(vec![target.clone()], removals(&target, &[])?);
+        Ok(())
+    }
+}
@@ -1,8 +1,62 @@
 use crate::{Error, Result};
 use std::env;
+use std::io::{self, Write};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Value};
+
+use utils::terminal;
 
 const LOG_ENV_NAME: &str = "UWE_LOG";
 
+/// Logger that writes newline-delimited JSON records to stderr.
+struct JsonLogger {
+    level: LevelFilter,
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        let mut fields = Map::new();
+        fields.insert("target".to_string(), Value::from(record.target()));
+        let message = record.args().to_string();
+        let stderr = io::stderr();
+        let mut writer = stderr.lock();
+        let _ = terminal::write_record(&mut writer, level, &message, &fields);
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+fn init() {
+    if terminal::is_json() {
+        let level = env::var(LOG_ENV_NAME)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(LevelFilter::Info);
+        if log::set_boxed_logger(Box::new(JsonLogger { level })).is_ok() {
+            log::set_max_level(level);
+        }
+    } else {
+        pretty_env_logger::init_custom_env(LOG_ENV_NAME);
+    }
+}
+
 pub fn log_level(level: &str) -> Result<()> {
     match level {
         "trace" => env::set_var(LOG_ENV_NAME, level),
@@ -13,12 +67,12 @@ pub fn log_level(level: &str) -> Result<()> {
         _ => {
             // Jump a few hoops to pretty print this message
             env::set_var(LOG_ENV_NAME, "error");
-            pretty_env_logger::init_custom_env(LOG_ENV_NAME);
+            init();
             return Err(Error::UnknownLogLevel(level.to_string()));
         }
     }
 
-    pretty_env_logger::init_custom_env(LOG_ENV_NAME);
+    init();
 
     Ok(())
 }
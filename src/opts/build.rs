@@ -19,6 +19,18 @@ pub struct Compile {
     pub member: Vec<String>,
 }
 
+impl Compile {
+    /// Drafts are only overridden when the flag is given so that
+    /// profile settings are respected.
+    pub fn drafts(&self) -> Option<bool> {
+        if self.include_drafts {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(StructOpt, Debug)]
 pub struct Build {
     #[structopt(flatten)]
@@ -32,6 +44,18 @@ pub struct Build {
     #[structopt(parse(from_os_str), default_value = ".")]
     pub project: PathBuf,
 
+    /// Print durations for the build phases
+    #[structopt(long)]
+    pub timings: bool,
+
+    /// Remove only stale outputs of the previous build
+    #[structopt(long)]
+    pub clean_stale: bool,
+
+    /// Write build diagnostics as JSON to a file (use - for stdout)
+    #[structopt(long, parse(from_os_str))]
+    pub diagnostics: Option<PathBuf>,
+
     /// Compile only these paths
     #[structopt(parse(from_os_str))]
     pub paths: Vec<PathBuf>,
@@ -6,4 +6,20 @@ pub struct Clean {
     /// Project path
     #[structopt(parse(from_os_str), default_value = ".")]
     pub project: PathBuf,
+
+    /// Also clear the short code cache
+    #[structopt(long)]
+    pub short_codes: bool,
+
+    /// List the paths that would be removed
+    #[structopt(long)]
+    pub dry_run: bool,
+
+    /// Do not ask for confirmation
+    #[structopt(short, long)]
+    pub yes: bool,
+
+    /// Keep a path relative to the build target
+    #[structopt(long, parse(from_os_str))]
+    pub keep: Vec<PathBuf>,
 }
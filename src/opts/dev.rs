@@ -22,6 +22,14 @@ pub struct Dev {
     #[structopt(long)]
     pub headless: bool,
 
+    /// Write the template data for each page to a JSON file
+    #[structopt(long)]
+    pub dump_context: bool,
+
+    /// Serve the build from memory without writing files
+    #[structopt(long)]
+    pub memory: bool,
+
     #[structopt(flatten)]
     pub server: WebServerOpts,
 
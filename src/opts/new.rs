@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use config::href::UrlPath;
+use project::VcsInit;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -25,6 +26,14 @@ pub struct New {
     #[structopt(long)]
     pub bare: bool,
 
+    /// Initial version control state (detach, keep or none)
+    #[structopt(long, default_value = "detach")]
+    pub vcs: VcsInit,
+
+    /// Template for the .gitignore file (minimal, default or full)
+    #[structopt(long)]
+    pub gitignore: Option<String>,
+
     /// Remote name for the new project
     #[structopt(long, default_value = "origin")]
     pub remote_name: String,
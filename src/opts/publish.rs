@@ -16,6 +16,14 @@ pub struct Publish {
     #[structopt(short, long)]
     pub sync_redirects: bool,
 
+    /// Allow deletions that exceed the delete limit
+    #[structopt(long)]
+    pub allow_mass_delete: bool,
+
+    /// Publish even when nothing has changed
+    #[structopt(short, long)]
+    pub force: bool,
+
     /// Project path
     #[structopt(parse(from_os_str), default_value = ".")]
     pub project: PathBuf,
@@ -11,6 +11,10 @@ pub struct Sync {
     #[structopt(short, long)]
     pub branch: Option<String>,
 
+    /// Mirror remotes to push the branch to
+    #[structopt(long)]
+    pub mirror: Vec<String>,
+
     /// Commit message
     #[structopt(short, long)]
     pub message: Option<String>,
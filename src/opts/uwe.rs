@@ -1,8 +1,9 @@
 use structopt::StructOpt;
 
+use utils::terminal::OutputMode;
+
 use super::{
-    Build, Clean, Dev, Docs, Lang, New, Publish, Server, Sync, Task,
-    Test,
+    Build, Clean, Dev, Docs, Lang, New, Publish, Server, Sync, Task, Test,
 };
 
 #[derive(Debug, StructOpt)]
@@ -28,6 +29,14 @@ pub struct Uwe {
     #[structopt(long, default_value = "info")]
     pub log_level: String,
 
+    /// Output format for messages (text or json)
+    #[structopt(long, env = "UWE_OUTPUT")]
+    pub output: Option<OutputMode>,
+
+    /// Warn about unknown keys in site configuration
+    #[structopt(long)]
+    pub strict_config: bool,
+
     #[structopt(subcommand)]
     pub cmd: Command,
 }
@@ -3,7 +3,7 @@ use std::fs;
 use std::path::PathBuf;
 
 use human_bytes::human_bytes;
-use log::{debug, info};
+use log::{debug, info, warn};
 use semver::VersionReq;
 
 use config::{
@@ -279,7 +279,10 @@ pub async fn update_registry() -> Result<()> {
 
 /// Lint a plugin.
 pub async fn lint(path: PathBuf, inspect: bool) -> Result<()> {
-    let plugin = plugin::lint(path).await?;
+    let (plugin, report) = plugin::lint(path).await?;
+    for warning in report.warnings() {
+        warn!("{}", warning);
+    }
     if inspect {
         println!("{}", toml::to_string(&plugin)?);
     }
@@ -1,7 +1,11 @@
 use std::path::PathBuf;
 
-use config::{ProfileName, ProfileSettings};
-use publisher::{self, aws_publish, AwsPublishRequest, PublishProvider};
+use log::info;
+
+use config::{HookStage, ProfileName, ProfileSettings};
+use publisher::{
+    self, aws_publish, AwsPublishRequest, DeleteLimit, PublishProvider,
+};
 
 use workspace::{compile, Project};
 
@@ -14,6 +18,8 @@ pub struct PublishOptions {
     pub provider: PublishProvider,
     pub exec: bool,
     pub sync_redirects: bool,
+    pub allow_mass_delete: bool,
+    pub force: bool,
 }
 
 pub async fn publish(options: PublishOptions) -> Result<()> {
@@ -50,6 +56,17 @@ async fn do_publish(options: &PublishOptions, project: &Project) -> Result<()> {
                     let region =
                         publisher::parse_region(&publish_config.region)?;
 
+                    let mut delete_limit = DeleteLimit {
+                        max: env.max_delete,
+                        ..Default::default()
+                    };
+                    if let Some(ratio) = env.max_delete_ratio {
+                        delete_limit.ratio = ratio;
+                    }
+                    if let Some(min) = env.min_delete {
+                        delete_limit.min = min;
+                    }
+
                     let request = AwsPublishRequest {
                         region,
                         profile_name: publish_config.credentials.clone(),
@@ -59,9 +76,24 @@ async fn do_publish(options: &PublishOptions, project: &Project) -> Result<()> {
                         build_target: project.options.build_target().clone(),
                         sync_redirects: options.sync_redirects,
                         redirects_manifest: None,
+                        delete_limit,
+                        allow_mass_delete: options.allow_mass_delete,
                     };
 
-                    aws_publish(request).await?
+                    let target = project.options.build_target();
+                    let checksum = project::content_checksum(target)?;
+                    let state = project::PublishState::new(
+                        &project.options.project,
+                        &options.env,
+                    )?;
+                    if !options.force && !state.is_changed(&checksum)? {
+                        info!("No changes to publish");
+                        return Ok(());
+                    }
+
+                    project.run_hooks(HookStage::PrePublish)?;
+                    aws_publish(request).await?;
+                    state.save(&checksum)?;
                 } else {
                     return Err(Error::UnknownPublishEnvironment(
                         options.env.to_string(),
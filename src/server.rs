@@ -19,9 +19,9 @@ async fn serve_project(
     args: Compile,
 ) -> Result<()> {
     let mut settings = ProfileSettings::from(&ProfileName::Release);
+    settings.include_drafts = args.drafts();
     settings.exec = Some(args.exec);
     settings.member = args.member;
-    settings.include_drafts = Some(args.include_drafts);
 
     let result =
         compile(&project, &settings, Default::default(), false).await?;
@@ -71,12 +71,8 @@ pub async fn serve(
         serve_project(project, opts, launch, args).await?;
     // Handle directory
     } else if let Some(directory) = targets.1 {
-        let mut opts = server_config(&server, config::PORT, config::PORT_SSL);
-
-        let host = HostConfig::new_directory(directory);
-        opts.add_host(host);
-
-        server::launch(opts, launch).await?;
+        let opts = server_config(&server, config::PORT, config::PORT_SSL);
+        server::serve_static(directory, opts, launch).await?;
     // Handle configuration file
     } else if let Some(configs) = targets.2 {
         let mut servers = Vec::new();
@@ -1,4 +1,6 @@
-use log::info;
+use log::{info, warn};
+
+use config::sync::ConflictPolicy;
 
 use crate::{
     opts::{self, Sync},
@@ -32,12 +34,46 @@ pub async fn run(opts: Sync) -> Result<()> {
         branch
     );
 
-    scm::sync(
+    let mirrors = if !opts.mirror.is_empty() {
+        opts.mirror
+    } else {
+        config.sync().mirrors().clone()
+    };
+
+    let report = scm::sync(
         &project,
         remote.to_string(),
         branch.to_string(),
+        mirrors,
         opts.add,
         opts.message,
-    )
-    .map_err(Error::from)
+        conflict_policy(config.sync().conflict()),
+        Some(scm::progress::bar()),
+    )?;
+
+    for remote in report.pushed.iter() {
+        info!("Pushed {} ✓", remote);
+    }
+    for (remote, e) in report.failed.iter() {
+        warn!("Push to {} failed ✗ ({})", remote, e);
+    }
+
+    if !report.failed.is_empty() {
+        let remotes = report
+            .failed
+            .iter()
+            .map(|(remote, _)| remote.to_string())
+            .collect::<Vec<_>>();
+        return Err(Error::from(scm::Error::PushFailed(remotes.join(", "))));
+    }
+
+    Ok(())
+}
+
+fn conflict_policy(policy: ConflictPolicy) -> scm::ConflictPolicy {
+    match policy {
+        ConflictPolicy::Abort => scm::ConflictPolicy::Abort,
+        ConflictPolicy::PreferLocal => scm::ConflictPolicy::PreferLocal,
+        ConflictPolicy::PreferRemote => scm::ConflictPolicy::PreferRemote,
+    }
 }